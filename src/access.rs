@@ -0,0 +1,208 @@
+use crate::{Act, Role, Stop, UserEvent};
+use accesskit::{Action, ActionRequest, NodeBuilder, NodeId, Tree, TreeUpdate};
+use winit::{event::WindowEvent, event_loop, window};
+
+/// The `access` module provides the [`Access`] struct, which describes a window to screen
+/// readers and other assistive technology through [`accesskit`].
+///
+/// # Reaching the app without a mouse with `Access`
+///
+/// The [`crate::UserEvent::AccessKit`] variant has ridden along in the event loop for a long
+/// time, and nothing ever sent it, so a screen reader found each window empty.  The map is a
+/// picture, and a picture tells a screen reader nothing, but the things we can do with it have
+/// names, and so do the data files we have open.  The `Access` of each window now hands the
+/// platform a tree describing the window:
+///
+/// * The window itself, named for its title, holds the rest.
+/// * The content of the window, a canvas for a map, a table, an image for a chart or a log,
+///   named for the [`Role`] of the window.  The map canvas answers the increment and decrement
+///   actions by zooming, and the scroll actions by panning, so that a screen reader can move the
+///   map with the same gestures it uses on a slider or a scrolling pane.  Once the user asks for
+///   [`Act::DescribeView`], the canvas carries the [`crate::Synopsis`] of the view as its
+///   description, in a polite live region, so the screen reader reads each new one aloud.
+/// * A toolbar of commands, with a button for each [`Act`] the deployment has not disabled,
+///   named with [`Act::title`].  Pressing one does what its key binding does.
+/// * A list of layers, with an item for each data file open in the app.
+///
+/// Building the tree costs nothing until assistive technology asks for it.  The
+/// [`accesskit_winit`] adapter sends a [`crate::UserEvent::AccessKit`] event when a screen reader
+/// first looks at the window, and the [`crate::App`] answers with [`Access::update`].  After
+/// that, the app sends the tree again whenever what it describes changes, such as a data file
+/// loading or a reload of the config disabling a group, and the adapter drops the update if
+/// nobody is listening.  An action on a node comes back the same way, and [`Access::act`] turns
+/// it into an [`Act`] for [`crate::App::act`].  A move of focus lands on a [`crate::Stop`] of
+/// the [`crate::Focus`] of the window, which each update reports back, so the keyboard and the
+/// screen reader agree on where focus rests.
+///
+/// The adapter needs to see the window before the platform does, so [`crate::App::create_window`]
+/// opens each window hidden, attaches the `Access`, then shows the window.  It also needs every
+/// [`WindowEvent`] the window receives, which [`Access::process`] passes on.
+///
+/// Each node needs an id that stays the same from one update to the next.  The window, its
+/// content, the toolbar and the list take fixed ids, a button takes [`ACT_NODES`] plus the place
+/// of its act among the variants of [`Act`], and a layer takes [`LAYER_NODES`] plus its place in
+/// the list.
+///
+/// * The `adapter` field holds the [`accesskit_winit::Adapter`] for the window.
+pub struct Access {
+    adapter: accesskit_winit::Adapter,
+}
+
+impl Access {
+    /// The `new` method attaches an adapter to `window`, which must not be visible yet, sending
+    /// its events to the event loop through `proxy`.
+    pub fn new(window: &window::Window, proxy: event_loop::EventLoopProxy<UserEvent>) -> Self {
+        Self {
+            adapter: accesskit_winit::Adapter::with_event_loop_proxy(window, proxy),
+        }
+    }
+
+    /// The `process` method passes `event` from `window` to the adapter, which follows focus and
+    /// the size of the window from them.
+    pub fn process(&mut self, window: &window::Window, event: &WindowEvent) {
+        self.adapter.process_event(window, event);
+    }
+
+    /// The `update` method sends the tree of a window titled `title` with `role` to the platform,
+    /// if assistive technology is listening, with a button for each of `acts` and an item for
+    /// each of `layers`, keyboard focus on the node `focus`, and the `synopsis` of the view, if
+    /// any, on the content.
+    pub fn update(
+        &mut self,
+        title: &str,
+        role: Role,
+        acts: &[Act],
+        layers: &[String],
+        focus: NodeId,
+        synopsis: Option<&str>,
+    ) {
+        self.adapter
+            .update_if_active(|| Self::tree(title, role, acts, layers, focus, synopsis));
+    }
+
+    /// The `tree` method builds the tree described in the [`Access`] docs.  A `focus` on a node
+    /// no longer in the tree falls back on the window.
+    pub fn tree(
+        title: &str,
+        role: Role,
+        acts: &[Act],
+        layers: &[String],
+        focus: NodeId,
+        synopsis: Option<&str>,
+    ) -> TreeUpdate {
+        let mut nodes = Vec::new();
+
+        let mut content = match role {
+            Role::Map => {
+                let mut node = NodeBuilder::new(accesskit::Role::Canvas);
+                for action in [
+                    Action::Increment,
+                    Action::Decrement,
+                    Action::ScrollUp,
+                    Action::ScrollDown,
+                    Action::ScrollLeft,
+                    Action::ScrollRight,
+                ] {
+                    node.add_action(action);
+                }
+                node
+            }
+            Role::Table => NodeBuilder::new(accesskit::Role::Table),
+            Role::Chart => NodeBuilder::new(accesskit::Role::Image),
+            Role::Log => NodeBuilder::new(accesskit::Role::Log),
+        };
+        content.set_name(role.to_string());
+        if let Some(synopsis) = synopsis {
+            content.set_description(synopsis);
+            content.set_live(accesskit::Live::Polite);
+        }
+        content.add_action(Action::Focus);
+        nodes.push((CONTENT_NODE, content.build()));
+
+        let mut buttons = Vec::new();
+        for act in acts {
+            let id = Stop::Command(act.clone()).node();
+            let mut button = NodeBuilder::new(accesskit::Role::Button);
+            button.set_name(act.title());
+            button.add_action(Action::Default);
+            button.add_action(Action::Focus);
+            nodes.push((id, button.build()));
+            buttons.push(id);
+        }
+        let mut toolbar = NodeBuilder::new(accesskit::Role::Toolbar);
+        toolbar.set_name("Commands");
+        toolbar.set_children(buttons);
+        nodes.push((COMMANDS_NODE, toolbar.build()));
+
+        let mut items = Vec::new();
+        for (index, layer) in layers.iter().enumerate() {
+            let id = Stop::Layer(index).node();
+            let mut item = NodeBuilder::new(accesskit::Role::ListItem);
+            item.set_name(layer.as_str());
+            item.add_action(Action::Focus);
+            nodes.push((id, item.build()));
+            items.push(id);
+        }
+        let mut list = NodeBuilder::new(accesskit::Role::List);
+        list.set_name("Layers");
+        list.set_children(items);
+        nodes.push((LAYERS_NODE, list.build()));
+
+        let mut root = NodeBuilder::new(accesskit::Role::Window);
+        root.set_name(title);
+        root.set_children(vec![CONTENT_NODE, COMMANDS_NODE, LAYERS_NODE]);
+        nodes.push((ROOT_NODE, root.build()));
+
+        let focus = match nodes.iter().any(|(id, _)| *id == focus) {
+            true => focus,
+            false => ROOT_NODE,
+        };
+        TreeUpdate {
+            nodes,
+            tree: Some(Tree::new(ROOT_NODE)),
+            focus,
+        }
+    }
+
+    /// The `act` method returns the [`Act`] asked for by `request`, or [`None`] if the request
+    /// asks for no act, as with a move of focus, which lands with [`crate::Focus::land`].
+    pub fn act(request: &ActionRequest) -> Option<Act> {
+        match (Stop::from_node(request.target)?, request.action) {
+            (Stop::Content, Action::Increment) => Some(Act::ZoomIn),
+            (Stop::Content, Action::Decrement) => Some(Act::ZoomOut),
+            (Stop::Content, Action::ScrollUp) => Some(Act::PanUp),
+            (Stop::Content, Action::ScrollDown) => Some(Act::PanDown),
+            (Stop::Content, Action::ScrollLeft) => Some(Act::PanLeft),
+            (Stop::Content, Action::ScrollRight) => Some(Act::PanRight),
+            (Stop::Command(act), Action::Default) => Some(act),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Access").finish_non_exhaustive()
+    }
+}
+
+/// The `ROOT_NODE` constant holds the id of the node for the window itself.
+pub const ROOT_NODE: NodeId = NodeId(0);
+
+/// The `CONTENT_NODE` constant holds the id of the node for the content of the window, such as
+/// the map.
+pub const CONTENT_NODE: NodeId = NodeId(1);
+
+/// The `COMMANDS_NODE` constant holds the id of the toolbar of commands.
+pub const COMMANDS_NODE: NodeId = NodeId(2);
+
+/// The `LAYERS_NODE` constant holds the id of the list of layers.
+pub const LAYERS_NODE: NodeId = NodeId(3);
+
+/// The `ACT_NODES` constant holds the id of the button for the first variant of [`Act`], with
+/// the rest following in order.
+pub const ACT_NODES: u64 = 100;
+
+/// The `LAYER_NODES` constant holds the id of the item for the first layer in the list, with the
+/// rest following in order.
+pub const LAYER_NODES: u64 = 10_000;
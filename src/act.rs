@@ -15,16 +15,117 @@ use convert_case::Casing;
 /// variant name in snake case, and `value` is the desired key mapping.
 ///
 /// Upon initial load, the application will attempt to read valid key mappings from `Tardy.toml`
-/// into a [`config::Config`] struct contained in the `config` field of [`crate::App`],
+/// into the [`crate::Keybindings`] of the [`crate::Settings`] contained in the `settings` field of [`crate::App`],
 /// and will warn the user if no mappings return and substitute a default configuration instead.
 ///
-/// Modifers are not currently supported, so only use single characters as `value` arguments.
+/// Values may include modifier keys, as in `new_window = "Ctrl+N"`.  See [`crate::Stroke`] for
+/// the syntax.
 ///
 /// ## Update 0.1.1
 ///
 /// The `Act` enum now includes a `CloseWindow` variant, indicating the user intent to close the
 /// window.  Once we successfully created background processes to spawn new windows, the need to
 /// subsequently close windows became clear.
+///
+/// ## Update 0.1.2
+///
+/// The `ReopenClosedWindow` variant restores the most recently closed window, for when the user
+/// closes a window by accident.  The `NewTableWindow`, `NewChartWindow` and `NewLogWindow`
+/// variants create windows with the corresponding [`crate::Role`], while `NewWindow` continues to
+/// create a map.
+///
+/// The `RepeatLast` variant repeats the most recent action, like `.` in vim, so that performing
+/// the same operation several times in a row takes one key.
+///
+/// The `ZoomIn`, `ZoomOut` and `Pan*` variants move the map in the focused window, and the
+/// `NextWindow` variant moves focus to the next window.  These give the keyboard and game
+/// controllers (see [`crate::Pad`]) a way to get around without a mouse.
+///
+/// The `ToggleInk` and `UndoInk` variants control pen annotation (see [`crate::Ink`]), and the
+/// `PasteGeometry` variant adds WKT or GeoJSON from the clipboard to the annotations (see
+/// [`crate::Sketch`]).
+///
+/// The `BindFromClipboard` variant rebinds an act from a line of the config on the clipboard,
+/// as in `zoom_in = "Ctrl+Up"`, and writes the bindings back to the config file (see
+/// [`crate::Cmd::bind`]).  The `EditBindings` variant opens the keybinding editor over the map,
+/// which does the same one key press at a time (see [`crate::Rebind`]).  The `PasteConfig`
+/// variant replaces the config file with the TOML on the clipboard, if it parses, and reloads it
+/// (see [`crate::Editor`]).
+///
+/// The `DescribeView` variant sums up the view of the map in a line of text, for screen readers
+/// and the clipboard (see [`crate::Synopsis`]).
+///
+/// The `CycleVision` variant simulates color blindness over the map, to check the colors of a
+/// choropleth (see [`crate::Vision`]).
+///
+/// The `NextTool` variant chooses the next tool a plugin adds for presses on the map, and back to
+/// identifying after the last (see [`crate::Plugins`]).
+///
+/// The `ToggleLargeUi` variant turns the large-UI mode on or off, making the lines over the map,
+/// touch targets and symbols bigger for showing the map across a counter (see
+/// [`crate::Palette`]).
+///
+/// The `ToggleVisible` variant hides all windows, or shows them again, and is meant for use as a
+/// global hotkey (see [`crate::Hotkeys`]).
+///
+/// The `ToggleDebugOverlay` variant shows or hides frame statistics over the window (see
+/// [`crate::Overlay`]).
+///
+/// The `ShowUsage` variant reports the memory and disk used by the app, and the
+/// `ClearTileCache` and `ClearResponseCache` variants empty the matching cache (see
+/// [`crate::Usage`]).
+///
+/// The `ShowTasks` variant lists the jobs running in the background, and the `CancelTasks`
+/// variant stops them (see [`crate::Tasks`]).  The `ShowAgents` variant reports how each
+/// long-running background agent is doing (see [`crate::Agents`]), and the `LoadOffered` variant
+/// loads the data files the watcher has found since the last time.
+///
+/// The `SaveSnapshot` variant saves a picture of the map in the focused window (see
+/// [`crate::Offscreen`]), and the `ToggleRecording` variant starts or stops recording it as a
+/// clip (see [`crate::Recorder`]).  The `ExportSelection` variant writes the features in view of
+/// the focused map to GeoJSON, and the `ExportLayers` variant writes every feature of every open
+/// table (see [`crate::Dataset::save_geojson`]).  The `ExportSelectionShapefile` and
+/// `ExportLayersShapefile` variants write the same features as a shapefile (see
+/// [`crate::Shapefile`]), and the `ExportSelectionParquet` and `ExportLayersParquet` variants as
+/// GeoParquet (see [`crate::GeoParquet`]).  The `ExportSelectionCsv`, `ExportLayersCsv`,
+/// `ExportSelectionXlsx` and `ExportLayersXlsx` variants write the rows of the same tables as CSV
+/// or as an Excel workbook, with a record of their source (see [`crate::Workbook`]).
+///
+/// The `SaveBookmark` variant saves the view of the focused map as a bookmark, and the
+/// `NextBookmark` variant goes round the saved bookmarks in turn.  The `ShowRecentFiles` variant
+/// lists the data files opened most recently (see [`crate::Store`]).  The `CopyPermalink` variant
+/// copies a link to the view of the focused map, with its layers and the last parcel lookup, and
+/// the `OpenPermalink` variant opens such a link from the clipboard (see [`crate::Permalink`]).
+/// The `SaveWorkspace` variant saves the open data files and the view of the focused map, to
+/// restore on startup (see [`crate::Workspace`]).
+///
+/// The `ShowCatalog` variant lists the layers of the regional data catalog, the
+/// `NextCatalogEntry` variant moves on to the next of them, and the `AddFromCatalog` variant
+/// opens the current one for the ground in view of the focused map (see [`crate::Catalog`]).
+/// The `LookUpParcel` variant looks up the parcel for the address or map and tax lot number on
+/// the clipboard, and shows it on the focused map (see [`crate::Parcels`]).  The `NextLookup`
+/// variant asks again the saved lookups in turn (see [`crate::Store`]).
+///
+/// The `GeocodeAddresses` variant finds the place of each address in the open CSV tables
+/// without geometry, and opens the results as a point layer (see [`crate::Geocoder`]).  The
+/// `SearchImagery` variant searches a STAC catalog for the imagery over the focused map, and
+/// opens the scenes it finds as a layer (see [`crate::Stac`]).  The `ShowScene` variant draws
+/// the picture of the next of those scenes under the middle of the focused map on every map,
+/// and after the last, none (see [`crate::Cog`]).  The `DownloadFromClipboard`
+/// variant fetches the file at the address on the clipboard, like a TIGER boundary zip or the
+/// `href` of a scene, into the data directory (see [`crate::Download`]).
+///
+/// The `ShowNetwork` variant lists the recent HTTP requests of the app (see
+/// [`crate::Network`]), and the `ShowGpu` variant reports the graphics card in use and the others
+/// available (see [`crate::Gpu`]).  The `ShowSymbols` variant lists the icons packed for point
+/// symbols, the values that pick them, and how many points of each open file get each icon (see
+/// [`crate::Atlas`]).
+///
+/// The `TraceLogs` variant turns the logs from the app up to trace until the `ResetLogs`
+/// variant turns them back down (see [`crate::Verbosity`]).
+///
+/// Each variant belongs to a [`Group`], returned by [`Act::group`], so that a deployment can
+/// switch off whole families of actions at once.
 #[derive(
     Debug,
     Default,
@@ -44,8 +145,182 @@ pub enum Act {
     CloseWindow,
     /// The `Exit` variant indicates the user would like to close the current window.
     Exit,
-    /// The `NewWindow` variant indicates the user would like to create a new window.
+    /// The `NewWindow` variant indicates the user would like to create a new map window.
     NewWindow,
+    /// The `NewTableWindow` variant indicates the user would like to create a new table window.
+    NewTableWindow,
+    /// The `NewChartWindow` variant indicates the user would like to create a new chart window.
+    NewChartWindow,
+    /// The `NewLogWindow` variant indicates the user would like to create a new log window.
+    NewLogWindow,
+    /// The `ReopenClosedWindow` variant indicates the user would like to restore the most
+    /// recently closed window.
+    ReopenClosedWindow,
+    /// The `ZoomIn` variant indicates the user would like to zoom in on the map.
+    ZoomIn,
+    /// The `ZoomOut` variant indicates the user would like to zoom out on the map.
+    ZoomOut,
+    /// The `PanUp` variant indicates the user would like to move the view of the map up.
+    PanUp,
+    /// The `PanDown` variant indicates the user would like to move the view of the map down.
+    PanDown,
+    /// The `PanLeft` variant indicates the user would like to move the view of the map left.
+    PanLeft,
+    /// The `PanRight` variant indicates the user would like to move the view of the map right.
+    PanRight,
+    /// The `NextWindow` variant indicates the user would like to focus the next window.
+    NextWindow,
+    /// The `SaveBookmark` variant indicates the user would like to remember the view of the map.
+    SaveBookmark,
+    /// The `NextBookmark` variant indicates the user would like to move the map to the next
+    /// bookmark.
+    NextBookmark,
+    /// The `CopyPermalink` variant indicates the user would like to share the view of the map
+    /// as a link.
+    CopyPermalink,
+    /// The `OpenPermalink` variant indicates the user would like to open a shared view of the
+    /// map.
+    OpenPermalink,
+    /// The `SaveWorkspace` variant indicates the user would like to save the open files and the
+    /// view of the map, to pick up from on a later start.
+    SaveWorkspace,
+    /// The `ToggleVisible` variant indicates the user would like to hide or show the app.
+    ToggleVisible,
+    /// The `ToggleInk` variant indicates the user would like to turn stylus annotation on or off.
+    ToggleInk,
+    /// The `UndoInk` variant indicates the user would like to remove the latest annotation.
+    UndoInk,
+    /// The `PasteGeometry` variant indicates the user would like to put the geometry on the
+    /// clipboard on the map.
+    PasteGeometry,
+    /// The `DescribeView` variant indicates the user would like the view of the map described in
+    /// words.
+    DescribeView,
+    /// The `CycleVision` variant indicates the user would like to see the map as a user with the
+    /// next kind of color blindness would.
+    CycleVision,
+    /// The `NextTool` variant indicates the user would like a press on the map to go to the next
+    /// tool.
+    NextTool,
+    /// The `ToggleLargeUi` variant indicates the user would like everything bigger, or back to
+    /// the usual size.
+    ToggleLargeUi,
+    /// The `ToggleDebugOverlay` variant indicates the user would like to show or hide the debug
+    /// overlay.
+    ToggleDebugOverlay,
+    /// The `TraceLogs` variant indicates the user would like every log message from the app,
+    /// until [`Act::ResetLogs`].
+    TraceLogs,
+    /// The `ResetLogs` variant indicates the user would like the log level from the config back.
+    ResetLogs,
+    /// The `ShowTasks` variant indicates the user would like to see the jobs running in the
+    /// background.
+    ShowTasks,
+    /// The `CancelTasks` variant indicates the user would like to stop every job running in the
+    /// background.
+    CancelTasks,
+    /// The `ShowAgents` variant indicates the user would like to see how the background agents
+    /// are doing.
+    ShowAgents,
+    /// The `LoadOffered` variant indicates the user would like to load the new and changed data
+    /// files found in the data folders.
+    LoadOffered,
+    /// The `ShowRecentFiles` variant indicates the user would like to see the data files opened
+    /// most recently.
+    ShowRecentFiles,
+    /// The `ShowCatalog` variant indicates the user would like to see the layers of the data
+    /// catalog.
+    ShowCatalog,
+    /// The `NextCatalogEntry` variant indicates the user would like to move on to the next layer
+    /// of the data catalog.
+    NextCatalogEntry,
+    /// The `AddFromCatalog` variant indicates the user would like to add the current layer of
+    /// the data catalog to the map.
+    AddFromCatalog,
+    /// The `LookUpParcel` variant indicates the user would like to look up the parcel for the
+    /// address or tax lot number on the clipboard.
+    LookUpParcel,
+    /// The `NextLookup` variant indicates the user would like to look up the next saved parcel
+    /// lookup again.
+    NextLookup,
+    /// The `GeocodeAddresses` variant indicates the user would like the addresses in the open
+    /// tables put on the map.
+    GeocodeAddresses,
+    /// The `SearchImagery` variant indicates the user would like to find the imagery over the
+    /// map.
+    SearchImagery,
+    /// The `ShowScene` variant indicates the user would like to see the picture of the next scene
+    /// found under the map.
+    ShowScene,
+    /// The `DownloadFromClipboard` variant indicates the user would like to download the file at
+    /// the address on the clipboard.
+    DownloadFromClipboard,
+    /// The `SaveSnapshot` variant indicates the user would like to save a picture of the map.
+    SaveSnapshot,
+    /// The `ExportSelection` variant indicates the user would like to save the features in view
+    /// as GeoJSON.
+    ExportSelection,
+    /// The `ExportLayers` variant indicates the user would like to save every open table as
+    /// GeoJSON.
+    ExportLayers,
+    /// The `ExportSelectionShapefile` variant indicates the user would like to save the features
+    /// in view as a shapefile.
+    ExportSelectionShapefile,
+    /// The `ExportLayersShapefile` variant indicates the user would like to save every open table
+    /// as a shapefile.
+    ExportLayersShapefile,
+    /// The `ExportSelectionParquet` variant indicates the user would like to save the features in
+    /// view as GeoParquet.
+    ExportSelectionParquet,
+    /// The `ExportLayersParquet` variant indicates the user would like to save every open table
+    /// as GeoParquet.
+    ExportLayersParquet,
+    /// The `ExportSelectionCsv` variant indicates the user would like to save the rows in view
+    /// as CSV.
+    ExportSelectionCsv,
+    /// The `ExportLayersCsv` variant indicates the user would like to save every open table as
+    /// CSV.
+    ExportLayersCsv,
+    /// The `ExportSelectionXlsx` variant indicates the user would like to save the rows in view
+    /// as an Excel workbook.
+    ExportSelectionXlsx,
+    /// The `ExportLayersXlsx` variant indicates the user would like to save every open table as
+    /// an Excel workbook.
+    ExportLayersXlsx,
+    /// The `ToggleRecording` variant indicates the user would like to start or stop recording
+    /// the map.
+    ToggleRecording,
+    /// The `ShowNetwork` variant indicates the user would like to see the recent HTTP requests.
+    ShowNetwork,
+    /// The `ShowGpu` variant indicates the user would like to see which graphics card draws the
+    /// map.
+    ShowGpu,
+    /// The `ShowSymbols` variant indicates the user would like to see the icons loaded for point
+    /// symbols, and which points get them.
+    ShowSymbols,
+    /// The `ShowUsage` variant indicates the user would like to see how much memory and disk the
+    /// app is using.
+    ShowUsage,
+    /// The `ClearTileCache` variant indicates the user would like to delete the downloaded map
+    /// tiles.
+    ClearTileCache,
+    /// The `ClearResponseCache` variant indicates the user would like to delete the saved
+    /// responses from the BEA API.
+    ClearResponseCache,
+    /// The `BindFromClipboard` variant indicates the user would like to bind an act to the keys
+    /// named on the clipboard.
+    BindFromClipboard,
+    /// The `EditBindings` variant indicates the user would like to change key bindings in the
+    /// keybinding editor.
+    EditBindings,
+    /// The `PasteConfig` variant indicates the user would like the config on the clipboard saved
+    /// and applied.
+    PasteConfig,
+    /// The `ReloadConfig` variant indicates the user would like to read the config again, after
+    /// editing it.
+    ReloadConfig,
+    /// The `RepeatLast` variant indicates the user would like to repeat the most recent action.
+    RepeatLast,
     /// The `Be` variant does nothing.
     #[default]
     Be,
@@ -68,6 +343,21 @@ impl Act {
         self.to_string().to_case(convert_case::Case::Title)
     }
 
+    /// The `repeatable` method returns `true` if repeating the act with [`Act::RepeatLast`] makes
+    /// sense.  Doing nothing, repeating, reloading, clearing a cache and exiting the program are
+    /// not worth repeating.
+    pub fn repeatable(&self) -> bool {
+        !matches!(
+            self,
+            Self::Be
+                | Self::Exit
+                | Self::ReloadConfig
+                | Self::RepeatLast
+                | Self::ClearTileCache
+                | Self::ClearResponseCache
+        )
+    }
+
     /// The `snake` method converts the variant name to `Snake` case using
     /// [`convert_case::Case::Snake`].
     /// Used to read variant names as keys in `Tardy.toml`.  We check against each variant of
@@ -75,4 +365,112 @@ impl Act {
     pub fn snake(&self) -> String {
         self.to_string().to_case(convert_case::Case::Snake)
     }
+
+    /// The `default_binding` method returns the key binding the act gets when the config does
+    /// not say otherwise, in the syntax of [`crate::Chord`], or [`None`] if the act starts out
+    /// unbound.  Used for the fallback config and the default config file.
+    pub fn default_binding(&self) -> Option<&'static str> {
+        match self {
+            Self::CloseWindow => Some("Ctrl+W"),
+            Self::EditBindings => Some("Ctrl+K Ctrl+S"),
+            Self::Exit => Some("DoubleEscape"),
+            Self::NewWindow => Some("Ctrl+N"),
+            Self::ReloadConfig => Some("Ctrl+R"),
+            Self::ReopenClosedWindow => Some("Ctrl+Shift+T"),
+            Self::RepeatLast => Some("."),
+            Self::ToggleDebugOverlay => Some("F12"),
+            _ => None,
+        }
+    }
+
+    /// The `default_global` method returns the global hotkey the act gets when the `[global]`
+    /// table of the config does not say otherwise, in the syntax of the [`crate::Hotkeys`], or
+    /// [`None`] if the act starts out without one.  Used for the fallback config and the default
+    /// config file.
+    pub fn default_global(&self) -> Option<&'static str> {
+        match self {
+            Self::SaveSnapshot => Some("Ctrl+Alt+KeyS"),
+            Self::ToggleVisible => Some("Ctrl+Alt+KeyB"),
+            _ => None,
+        }
+    }
+
+    /// The `group` method returns the [`Group`] the act belongs to.  Acts that fit no particular
+    /// group are [`Group::General`].
+    pub fn group(&self) -> Group {
+        match self {
+            Self::ToggleInk
+            | Self::UndoInk
+            | Self::PasteGeometry
+            | Self::BindFromClipboard
+            | Self::EditBindings
+            | Self::PasteConfig => Group::Editing,
+            Self::NewLogWindow
+            | Self::ToggleDebugOverlay
+            | Self::ShowUsage
+            | Self::ShowNetwork
+            | Self::ShowGpu
+            | Self::ShowSymbols
+            | Self::TraceLogs
+            | Self::ResetLogs => Group::Debug,
+            Self::CloseWindow
+            | Self::Exit
+            | Self::ClearTileCache
+            | Self::ClearResponseCache
+            | Self::CancelTasks => Group::Destructive,
+            _ => Group::General,
+        }
+    }
+}
+
+/// The `Group` enum sorts the variants of [`Act`] into families that a deployment can disable as
+/// a whole.
+///
+/// # Locking out actions with `Group`
+///
+/// The same build runs on staff laptops and on the public kiosk, and the kiosk should not let
+/// visitors scribble on the map or close the only window.  Rather than maintain a separate set of
+/// key bindings for each deployment, the config lists the groups to switch off:
+///
+/// ```toml
+/// [groups]
+/// disabled = ["editing", "destructive"]
+/// ```
+///
+/// The [`crate::App`] checks the group of every act in [`crate::App::act`], so a disabled act does
+/// nothing no matter whether it came from the keyboard, the mouse, a controller or a global
+/// hotkey.
+///
+/// * The `General` variant holds everything else, and cannot be disabled in a useful way.
+/// * The `Editing` variant holds actions that change the data the user is looking at, such as
+///   [`Act::ToggleInk`] and [`Act::UndoInk`], or the config, such as [`Act::BindFromClipboard`].
+/// * The `Debug` variant holds actions meant for developers, such as [`Act::NewLogWindow`].
+/// * The `Destructive` variant holds actions that throw away state, such as [`Act::CloseWindow`]
+///   and [`Act::Exit`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Group {
+    /// The `General` variant holds acts that belong to no other group.
+    #[default]
+    General,
+    /// The `Editing` variant holds acts that change user data.
+    Editing,
+    /// The `Debug` variant holds acts meant for developers.
+    Debug,
+    /// The `Destructive` variant holds acts that throw away state.
+    Destructive,
 }
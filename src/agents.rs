@@ -0,0 +1,565 @@
+use crate::{
+    prune, Arrive, Basemap, Cache, Class, Context, Courier, Dataset, Fetch, Fetcher, Format, Home,
+    Job, Network, Schedule, Settings, Store, Tasks, Timeouts, UserEvent, MAX_ZOOM,
+    PARTIAL_EXTENSION, STORE_FILE,
+};
+use std::collections::BTreeMap;
+use std::path;
+use std::sync::{Arc, Mutex};
+use std::time;
+use strum::IntoEnumIterator;
+use tokio_util::sync::CancellationToken;
+
+/// The `agents` module provides the [`Agents`] struct, which supervises the long-running
+/// background work of the app, and the [`Agent`] enum, which names each kind of work.
+///
+/// # Keeping house with `Agents`
+///
+/// Some work never finishes.  The tile cache keeps growing while the app is open, not just
+/// between runs, so trimming it once in [`crate::Map::tiles`] is not enough.  A user who opens a
+/// CSV and then edits it in a spreadsheet expects the table to follow.  A laptop headed out to
+/// the field wants the map around home in the cache before it loses the network.  Early versions
+/// sketched this out as a gang of `Imp` types under an `ImpKing`, sending `Hijinks` back to the
+/// event loop, but the sketch never ran, and it ended its days commented out in the
+/// `user_event` handler of [`crate::App`].
+///
+/// Each [`Agent`] now does one job, in rounds:
+///
+/// * The [`Agent::Janitor`] trims the tile cache to the `cache_size` of the [`Basemap`] with
+///   [`prune`], and clears away partial tiles left by the prefetcher.
+/// * The [`Agent::Refresher`] checks the files behind the open datasets, and reads a file again
+///   when it has changed on disk.  The new table arrives as a [`UserEvent::Loaded`], and takes
+///   the place of the old one.
+/// * The [`Agent::Prefetcher`] fetches the tiles around the opening view of the map, at the
+///   opening zoom level and the one below, skipping tiles already in the cache.  The tiles go
+///   where the tile provider of [`galileo`] looks for them, at the address of the tile with the
+///   scheme taken off, under [`crate::TILE_DIR`], so the map finds them without a download.
+///   The prefetcher starts out off, since most tile servers forbid bulk downloads (see
+///   [`Schedule`]).
+/// * The [`Agent::Watcher`] keeps an eye on the data folders listed in the `[data]` table of the
+///   config (see [`crate::Folders`]), where other tools drop their exports.  When a CSV,
+///   GeoJSON, Parquet or GeoPackage file turns up, or changes, it sends a
+///   [`UserEvent::Offer`], and the [`crate::App`] offers to load it, until the user takes it up
+///   with [`crate::Act::LoadOffered`].  Reading every export the moment it lands would fill the app
+///   with half-written files and tables nobody asked for, so the user decides.
+///
+/// `Agents` runs a loop for each agent, waiting out the interval from the `[agents]` table of
+/// the config (see [`Schedule`]) between rounds.  The janitor and the refresher wait before
+/// their first round, since [`crate::Map::tiles`] has just trimmed the cache and the datasets are
+/// fresh, but the prefetcher and the watcher start at once.  Each round runs as a bulk job of
+/// the [`Tasks`] registry, with [`Tasks::run`], so it shows up in [`crate::Act::ShowTasks`],
+/// gives way to the work the user is waiting on, and only holds a permit while it works, not
+/// while it sleeps.  Cancelling the round with [`crate::Act::CancelTasks`] skips it, and the
+/// agent carries on at the next interval.
+///
+/// ## When an agent fails
+///
+/// A round that fails goes by the [`Restart`] policy of the agent, from [`Agent::restart`].
+/// Under [`Restart::OnFailure`], the agent logs the error and tries again, waiting
+/// [`RESTART_DELAY`] after the first failure and twice as long after each failure in a row, up to
+/// the interval.  After [`RESTART_LIMIT`] failures in a row, the agent stops, and the error goes
+/// to the event loop through the [`Courier`], so the user hears about it once, rather than every
+/// few minutes.  Under [`Restart::Never`], the agent stops at the first failure.  Reloading the
+/// config with [`crate::Act::ReloadConfig`] starts every agent afresh.  [`crate::Act::ShowAgents`]
+/// prints how each agent is doing.
+///
+/// * The `tasks` field holds the [`Tasks`] registry that runs each round.
+/// * The `courier` field holds the [`Courier`] that delivers new datasets and errors.
+/// * The `home` field holds the [`Home`] that locates the cache directory.
+/// * The `watched` field holds the path of each open dataset, with the time it was last modified
+///   when we read it.
+/// * The `seen` field holds the data files the watcher found in its last round, with the time
+///   each was last modified, or [`None`] before the first round.
+/// * The `status` field holds the [`Status`] of each agent.
+/// * The `token` field holds the [`CancellationToken`] that stops the agents started last.
+#[derive(Debug, Clone)]
+pub struct Agents {
+    tasks: Tasks,
+    courier: Courier,
+    home: Home,
+    watched: Arc<Mutex<BTreeMap<path::PathBuf, Option<time::SystemTime>>>>,
+    seen: Arc<Mutex<Option<BTreeMap<path::PathBuf, Option<time::SystemTime>>>>>,
+    status: Arc<Mutex<BTreeMap<Agent, Status>>>,
+    token: CancellationToken,
+}
+
+impl Agents {
+    /// The `new` method creates a supervisor for agents running their rounds on `tasks`, and
+    /// reporting through `courier`.  No agent runs until [`Agents::start`].
+    pub fn new(tasks: Tasks, courier: Courier, home: Home) -> Self {
+        Self {
+            tasks,
+            courier,
+            home,
+            watched: Arc::new(Mutex::new(BTreeMap::new())),
+            seen: Arc::new(Mutex::new(None)),
+            status: Arc::new(Mutex::new(BTreeMap::new())),
+            token: CancellationToken::new(),
+        }
+    }
+
+    /// The `start` method stops any agents already running, and starts each agent with a
+    /// nonzero interval in the `[agents]` table of `settings`.  The janitor and the prefetcher
+    /// work from the `[map]` table, and the watcher from the `[data]` table.
+    #[tracing::instrument(skip_all)]
+    pub fn start(&mut self, settings: &Settings) {
+        self.stop();
+        self.token = CancellationToken::new();
+        if let Ok(mut seen) = self.seen.lock() {
+            *seen = None;
+        }
+        for agent in Agent::iter() {
+            let Some(interval) = agent.interval(settings.agents()) else {
+                self.set_status(agent, Status::Off);
+                continue;
+            };
+            self.set_status(agent, Status::Waiting);
+            let agents = self.clone();
+            let settings = settings.clone();
+            let token = self.token.clone();
+            crate::spawn(async move {
+                tokio::select! {
+                    _ = token.cancelled() => tracing::trace!("Stopped the {agent}."),
+                    _ = agents.supervise(agent, interval, &settings) => {}
+                }
+            });
+            tracing::trace!("Started the {agent}, every {interval:?}.");
+        }
+    }
+
+    /// The `stop` method stops every agent.  A round already running stops at its next `await`.
+    pub fn stop(&self) {
+        self.token.cancel();
+    }
+
+    /// The `watch` method adds the dataset read from `path` to the files the
+    /// [`Agent::Refresher`] checks for changes, noting when the file was last modified.
+    pub fn watch(&self, path: &path::Path) {
+        let modified = Self::modified(path);
+        if let Ok(mut watched) = self.watched.lock() {
+            watched.insert(path.to_path_buf(), modified);
+        }
+    }
+
+    /// The `status` method returns the [`Status`] of `agent`, or [`None`] if the agents have
+    /// not started.
+    pub fn status(&self, agent: Agent) -> Option<Status> {
+        self.status
+            .lock()
+            .ok()
+            .and_then(|status| status.get(&agent).cloned())
+    }
+
+    /// The `set_status` method records the [`Status`] of `agent`.
+    fn set_status(&self, agent: Agent, status: Status) {
+        if let Ok(mut statuses) = self.status.lock() {
+            statuses.insert(agent, status);
+        }
+    }
+
+    /// The `supervise` method runs the rounds of `agent`, every `interval`, restarting it after
+    /// a failure as described in the [`Agents`] docs.  Returns once the agent stops for good.
+    async fn supervise(&self, agent: Agent, interval: time::Duration, settings: &Settings) {
+        let mut failures = 0;
+        let mut wait = match agent {
+            Agent::Prefetcher | Agent::Watcher => time::Duration::ZERO,
+            _ => interval,
+        };
+        loop {
+            crate::sleep(wait).await;
+            wait = interval;
+            self.set_status(agent, Status::Working);
+            let result = self
+                .tasks
+                .run(agent.description(), Class::Bulk, |job| {
+                    self.round(agent, settings, job)
+                })
+                .await;
+            let blame = match result {
+                Some(Err(blame)) => blame,
+                Some(Ok(())) | None => {
+                    failures = 0;
+                    self.set_status(agent, Status::Waiting);
+                    continue;
+                }
+            };
+            failures += 1;
+            if agent.restart() == Restart::Never || failures >= RESTART_LIMIT {
+                tracing::warn!("The {agent} has stopped after {failures} failures.");
+                self.set_status(agent, Status::Stopped(blame.to_string()));
+                self.courier
+                    .send(blame.with_context(format!("running the {agent}")));
+                return;
+            }
+            wait = (RESTART_DELAY * 2u32.pow(failures - 1)).min(interval);
+            tracing::warn!("The {agent} failed, restarting in {wait:?}: {blame}");
+            self.set_status(agent, Status::Restarting(failures));
+        }
+    }
+
+    /// The `round` method does one round of work for `agent`, reporting to `job`.
+    async fn round(&self, agent: Agent, settings: &Settings, job: Job) -> Arrive<()> {
+        match agent {
+            Agent::Janitor => self.sweep(settings.map()).await,
+            Agent::Refresher => self.refresh(job).await,
+            Agent::Prefetcher => self.prefetch(settings.map(), &job).await,
+            Agent::Watcher => self.scan(settings.data().dirs()),
+        }
+    }
+
+    /// The `sweep` method does a round of the [`Agent::Janitor`], trimming the tile cache to the
+    /// `cache_size` of `basemap` on a worker thread, by the cache index in the [`Store`] if it
+    /// opens.
+    ///
+    /// Will [`crate::Blame::Io`] if a file cannot be inspected or deleted,
+    /// [`crate::Blame::Sqlite`] if the cache index cannot be read or written, and
+    /// [`crate::Blame::Join`] if the worker thread panics.
+    async fn sweep(&self, basemap: &Basemap) -> Arrive<()> {
+        let tiles = Cache::Tiles.dir(&self.home);
+        let store = self.home.data().join(STORE_FILE);
+        let limit = basemap.cache_size() * 1024 * 1024;
+        crate::unblock(move || {
+            Self::sweep_partials(&tiles)?;
+            if limit == 0 {
+                return Ok(());
+            }
+            let mut store = Store::open(&store)
+                .inspect_err(|e| tracing::debug!("Trimming the tiles by age on disk: {e}"))
+                .ok();
+            prune(&tiles, limit, store.as_mut())
+        })
+        .await
+        .context("trimming the tile cache")?
+    }
+
+    /// The `sweep_partials` method removes the partial tiles the [`Agent::Prefetcher`] left
+    /// under `dir` when it was stopped part way through writing them.
+    fn sweep_partials(dir: &path::Path) -> Arrive<()> {
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|ext| ext == PARTIAL_EXTENSION) {
+                    std::fs::remove_file(&path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The `refresh` method does a round of the [`Agent::Refresher`], reading each watched file
+    /// that has changed since we last read it, reporting to `job`.  A file that fails to read is
+    /// one file's problem rather than the agent's, so the error goes to the event loop and the
+    /// round carries on.  A file that has gone missing stays on the list, in case it comes back.
+    async fn refresh(&self, job: Job) -> Arrive<()> {
+        let watched = self
+            .watched
+            .lock()
+            .map(|watched| watched.clone())
+            .unwrap_or_default();
+        for (path, modified) in watched {
+            let now = Self::modified(&path);
+            if now.is_none() || now == modified {
+                continue;
+            }
+            tracing::info!("Reloading {}, which changed on disk.", path.display());
+            // Note the change before reading, so a broken file is not read every round.
+            self.watch(&path);
+            match Dataset::load(path.clone(), job.clone()).await {
+                Ok(dataset) => self.courier.notify(UserEvent::Loaded(dataset)),
+                Err(e) => self
+                    .courier
+                    .send(e.with_context(format!("reloading {}", path.display()))),
+            }
+        }
+        Ok(())
+    }
+
+    /// The `scan` method does a round of the [`Agent::Watcher`], listing the data files in each
+    /// of `dirs` and offering each file that is new or changed since the last round with a
+    /// [`UserEvent::Offer`].  The first round only takes note of what is there, since files that
+    /// were there before the app started are not news.  Files already open are left to the
+    /// [`Agent::Refresher`].  A folder that does not exist, perhaps a network share that is not
+    /// mounted yet, counts as empty.
+    fn scan(&self, dirs: &[path::PathBuf]) -> Arrive<()> {
+        let mut found = BTreeMap::new();
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                tracing::trace!("Cannot read data folder {}.", dir.display());
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                let readable = matches!(
+                    Format::from_path(&path),
+                    Some(Format::Csv | Format::GeoJson | Format::Parquet | Format::GeoPackage)
+                );
+                if readable && path.is_file() {
+                    let modified = Self::modified(&path);
+                    found.insert(path, modified);
+                }
+            }
+        }
+        let Ok(mut seen) = self.seen.lock() else {
+            return Ok(());
+        };
+        if let Some(seen) = seen.as_ref() {
+            let open = self
+                .watched
+                .lock()
+                .map(|watched| watched.clone())
+                .unwrap_or_default();
+            found
+                .iter()
+                .filter(|(path, modified)| seen.get(*path) != Some(*modified))
+                .filter(|(path, _)| !open.contains_key(*path))
+                .for_each(|(path, _)| {
+                    tracing::trace!("Offering {}.", path.display());
+                    self.courier.notify(UserEvent::Offer(path.clone()));
+                });
+        }
+        *seen = Some(found);
+        Ok(())
+    }
+
+    /// The `prefetch` method does a round of the [`Agent::Prefetcher`], fetching each tile
+    /// around the opening view of `basemap` that is not in the cache yet, reporting to `job`.
+    ///
+    /// Will [`crate::Blame::Http`] or [`crate::Blame::Timeout`] if the tile server does not
+    /// answer, and [`crate::Blame::Io`] if a tile cannot be written.
+    async fn prefetch(&self, basemap: &Basemap, job: &Job) -> Arrive<()> {
+        let tiles = Cache::Tiles.dir(&self.home);
+        let wanted = Self::around(basemap)
+            .into_iter()
+            .map(|(z, x, y)| {
+                let url = basemap.tile(z, x, y);
+                let path = Fetcher::tile_path(&tiles, &url);
+                (url, path)
+            })
+            .filter(|(_, path)| !path.exists())
+            .collect::<Vec<(String, path::PathBuf)>>();
+        if wanted.is_empty() {
+            tracing::trace!("The tiles around the opening view are all cached.");
+            return Ok(());
+        }
+        let total = wanted.len();
+        for (count, (url, path)) in wanted.into_iter().enumerate() {
+            Self::fetch_tile(&url, &path).await?;
+            job.set_progress((count + 1) as f32 / total as f32);
+        }
+        tracing::info!("Prefetched {total} tiles.");
+        Ok(())
+    }
+
+    /// The `fetch_tile` method downloads the tile at `url` to `path`, writing it beside the
+    /// target first, so a tile at `path` is always a whole one.  The span is named for
+    /// [`crate::TILE_SPAN`], so [`crate::Latency`] times it with the tiles of the map.
+    #[tracing::instrument(name = "tile", skip_all)]
+    async fn fetch_tile(url: &str, path: &path::Path) -> Arrive<()> {
+        let network = Network::global();
+        let bytes = network
+            .within(Fetch::Tiles, url, async {
+                let request = network.client().get(url);
+                let response = network.send(request, Fetch::Tiles).await?;
+                let bytes = response
+                    .error_for_status()?
+                    .bytes()
+                    .await
+                    .map_err(|e| network.blame(e, url))?;
+                Ok(bytes)
+            })
+            .await?;
+        let path = path.to_path_buf();
+        crate::unblock(move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("creating {}", parent.display()))?;
+            }
+            let mut partial = path.as_os_str().to_owned();
+            partial.push(".");
+            partial.push(PARTIAL_EXTENSION);
+            std::fs::write(&partial, &bytes)
+                .with_context(|| format!("writing {}", path.display()))?;
+            std::fs::rename(&partial, &path)?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// The `around` method returns the zoom level and coordinates of each tile within
+    /// [`PREFETCH_RADIUS`] tiles of the opening view of `basemap`, at the opening zoom level and
+    /// the one below, in the usual web map numbering.
+    fn around(basemap: &Basemap) -> Vec<(u32, i32, i32)> {
+        let [latitude, longitude] = *basemap.center();
+        let zoom = (*basemap.zoom()).min(MAX_ZOOM);
+        let mut tiles = Vec::new();
+        for (z, radius) in [(zoom, PREFETCH_RADIUS), (zoom + 1, PREFETCH_RADIUS * 2)] {
+            if z > MAX_ZOOM {
+                continue;
+            }
+            let count = 2i32.pow(z);
+            let x = ((longitude + 180.0) / 360.0 * count as f64).floor() as i32;
+            let latitude = latitude.to_radians();
+            let y = ((1.0 - latitude.tan().asinh() / std::f64::consts::PI) / 2.0 * count as f64)
+                .floor() as i32;
+            for dx in -radius..=radius {
+                for dy in -radius..=radius {
+                    let (x, y) = (x + dx, y + dy);
+                    if (0..count).contains(&x) && (0..count).contains(&y) {
+                        tiles.push((z, x, y));
+                    }
+                }
+            }
+        }
+        tiles
+    }
+
+    /// The `modified` method returns when the file at `path` was last modified, or [`None`] if
+    /// we cannot tell, perhaps because the file is gone.
+    fn modified(path: &path::Path) -> Option<time::SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+}
+
+/// Prints the [`Status`] of each agent, one per line.
+impl std::fmt::Display for Agents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = Agent::iter()
+            .map(|agent| match self.status(agent) {
+                Some(status) => format!("{agent}: {status}"),
+                None => format!("{agent}: not started"),
+            })
+            .collect::<Vec<String>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// The `Agent` enum names the kinds of long-running work supervised by [`Agents`].
+///
+/// * The `Janitor` variant trims the tile cache.
+/// * The `Refresher` variant reloads data files that changed on disk.
+/// * The `Prefetcher` variant fetches the tiles around the opening view.
+/// * The `Watcher` variant looks for new and changed files in the data folders.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+)]
+pub enum Agent {
+    /// The `Janitor` variant indicates the agent that trims the tile cache.
+    #[display("cache janitor")]
+    Janitor,
+    /// The `Refresher` variant indicates the agent that reloads changed data files.
+    #[display("data refresher")]
+    Refresher,
+    /// The `Prefetcher` variant indicates the agent that fetches tiles ahead of time.
+    #[display("tile prefetcher")]
+    Prefetcher,
+    /// The `Watcher` variant indicates the agent that watches the data folders.
+    #[display("folder watcher")]
+    Watcher,
+}
+
+impl Agent {
+    /// The `interval` method returns the time between rounds of the agent from `schedule`, or
+    /// [`None`] if the agent is turned off.
+    pub fn interval(&self, schedule: &Schedule) -> Option<time::Duration> {
+        let seconds = match self {
+            Self::Janitor => schedule.janitor(),
+            Self::Refresher => schedule.refresher(),
+            Self::Prefetcher => schedule.prefetcher(),
+            Self::Watcher => schedule.watcher(),
+        };
+        Timeouts::limit(*seconds)
+    }
+
+    /// The `restart` method returns the [`Restart`] policy of the agent.  The janitor, the
+    /// refresher and the watcher only touch local files, so trying again costs little.  The
+    /// prefetcher is a guest of somebody else's tile server, and a server that turns it away
+    /// should not hear from it again until the user says so.
+    pub fn restart(&self) -> Restart {
+        match self {
+            Self::Janitor | Self::Refresher | Self::Watcher => Restart::OnFailure,
+            Self::Prefetcher => Restart::Never,
+        }
+    }
+
+    /// The `description` method returns the description of a round of the agent, for the
+    /// [`Tasks`] registry.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Janitor => "Trimming the tile cache",
+            Self::Refresher => "Checking open files for changes",
+            Self::Prefetcher => "Prefetching tiles",
+            Self::Watcher => "Scanning data folders",
+        }
+    }
+}
+
+/// The `Restart` enum says what [`Agents`] does when a round of an [`Agent`] fails.
+///
+/// * The `Never` variant stops the agent at the first failure.
+/// * The `OnFailure` variant tries again after a delay, up to [`RESTART_LIMIT`] failures in a
+///   row.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum Restart {
+    /// The `Never` variant indicates the agent stops after a failure.
+    #[display("never")]
+    Never,
+    /// The `OnFailure` variant indicates the agent restarts after a failure.
+    #[display("on failure")]
+    OnFailure,
+}
+
+/// The `Status` enum describes what an [`Agent`] is doing.
+///
+/// * The `Off` variant means the [`Schedule`] turns the agent off.
+/// * The `Waiting` variant means the agent is waiting for its next round.
+/// * The `Working` variant means a round is running, or waiting for a permit.
+/// * The `Restarting` variant holds the number of rounds in a row that have failed.
+/// * The `Stopped` variant holds the error that stopped the agent.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum Status {
+    /// The `Off` variant indicates the agent is turned off in the config.
+    #[display("off")]
+    Off,
+    /// The `Waiting` variant indicates the agent is between rounds.
+    #[display("waiting")]
+    Waiting,
+    /// The `Working` variant indicates the agent is doing a round.
+    #[display("working")]
+    Working,
+    /// The `Restarting` variant indicates the agent failed and will try again.
+    #[display("restarting after {_0} failures")]
+    Restarting(u32),
+    /// The `Stopped` variant indicates the agent failed and gave up.
+    #[display("stopped: {_0}")]
+    Stopped(String),
+}
+
+/// The `PREFETCH_RADIUS` constant holds how many tiles out from the opening view the
+/// [`Agent::Prefetcher`] fetches at the opening zoom level.  The level below covers the same
+/// ground, so it takes twice as many.
+pub const PREFETCH_RADIUS: i32 = 2;
+
+/// The `RESTART_DELAY` constant holds how long an [`Agent`] waits after its first failure before
+/// trying again.
+pub const RESTART_DELAY: time::Duration = time::Duration::from_secs(5);
+
+/// The `RESTART_LIMIT` constant holds how many rounds of an [`Agent`] may fail in a row before
+/// it stops.
+pub const RESTART_LIMIT: u32 = 5;
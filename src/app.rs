@@ -1,7 +1,20 @@
-use crate::{Act, Arrive, Cmd, Lens};
+use crate::{
+    Access, Act, Agents, Arrive, Atlas, Blame, Cache, Catalog, Clash, Class, Cli, Clipboard, Cmd,
+    Cog, Context, Courier, Crash, Dataset, Diagnostic, Download, Edit, Editor, Figure, Format,
+    GeoParquet, Geocoder, Glide, Gpu, Group, Home, Hotkeys, Job, Layers, Lens, Listing, Map, Nav,
+    Network, Pad, Palette, Panel, Parcel, Parcels, Permalink, Place, Plugins, Prescription,
+    Problem, Query, Rebind, Recorder, Reporter, Role, Scenery, Scroll, Secrets, Settings, Severity,
+    Shapefile, Sketch, Snippet, Stac, Stamp, Stop, Store, Synopsis, Tasks, Tiles, Usage, UserEvent,
+    Verbosity, Vision, Workbook, Workspace, CATALOG_DIR, DEFAULT_SCHEME, DOWNLOAD_DIR, FEATURE_DIR,
+    FIGURE_DOT, GEOCODE_DIR, IMAGERY_DIR, LOG_LEVEL, PAD_INTERVAL, PAN_STEP, RECORDING_DIR,
+    SNAPSHOT_DIR, SNAPSHOT_SCALE, SOFTWARE_TITLE, STORE_FILE, WORKSPACE_DIR, WORKSPACE_EXTENSION,
+    ZOOM_STEP,
+};
 use rand::Rng;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
+use strum::IntoEnumIterator;
+use web_time as time;
 use winit::application::ApplicationHandler;
 use winit::{
     dpi,
@@ -43,20 +56,145 @@ use winit::{
 ///
 /// The `App` struct now includes a `proxy` field holding the event loop proxy used to send events
 /// from the async process back to the sync event loop as a user event of type `Hijinks`.
+///
+/// ## Update 0.1.2
+///
+/// The `App` struct now includes a `closed` field holding a [`Prescription`] for each recently
+/// closed window, so the user can reopen them.
+///
+/// Actions used to apply to whichever window delivered the key event.  The `focused` field now
+/// tracks the window with keyboard focus, and actions that target a single window apply to the
+/// focused window.
+///
+/// The `disabled` field holds the [`Group`] types switched off for this deployment.  Acts in a
+/// disabled group stop at the door of [`App::act`].
+///
+/// ## Update 0.1.3
+///
+/// Errors used to meet one of three ends: an `expect` that took the whole app down, a trace
+/// message nobody reads, or an `unwrap` waiting its turn.  Now every error handled by the `App`
+/// goes through [`App::report_error`], which decides by [`Severity`] whether the user gets a
+/// warning while the app carries on, or [`App::shutdown`] closes the app cleanly with an
+/// explanation.
+///
+/// ## Update 0.1.4
+///
+/// The `recorder` field holds the [`Recorder`] while the user records a map window with
+/// [`Act::ToggleRecording`].  Each frame the window draws goes to [`Recorder::capture`], which
+/// streams it to the encoder, and [`App::stop_recording`] finishes the clip on a background job.
+///
+/// The `tiles` field holds the tile layer of the base map, made for the first map window and
+/// shared by the rest, as described in the [`Map`] docs.  Closing the last map window keeps the
+/// layer, and the tiles it holds, for the next.
 #[derive(Debug)]
 pub struct App {
+    agents: Agents,
+    atlas: Option<Atlas>,
+    catalog: Catalog,
+    clashes: Vec<Clash>,
+    cli: Cli,
+    clipboard: Option<Clipboard>,
+    closed: Vec<Prescription>,
     cmd: Cmd,
-    config: config::Config,
-    proxy: event_loop::EventLoopProxy<accesskit_winit::Event>,
+    datasets: Vec<Dataset>,
+    diagnostics: Vec<Diagnostic>,
+    disabled: HashSet<Group>,
+    figures: Vec<Figure>,
+    focused: Option<window::WindowId>,
+    gpu: Gpu,
+    hidden: bool,
+    home: Home,
+    hotkeys: Option<Hotkeys>,
+    last: Option<Act>,
+    modifiers: event::Modifiers,
+    notice: Option<Panel>,
+    offered: Vec<std::path::PathBuf>,
+    pad: Option<Pad>,
+    palette: Palette,
+    plugins: Plugins,
+    proxy: event_loop::EventLoopProxy<UserEvent>,
+    query: Option<Query>,
+    rebind: Option<Rebind>,
+    recorder: Option<Recorder>,
+    reporter: Reporter,
+    scene: Option<(String, Scenery)>,
+    scroll: Scroll,
+    settings: Settings,
+    stamp: Option<Arc<Stamp>>,
+    started: bool,
+    store: Option<Store>,
+    tasks: Tasks,
+    tiles: Option<Tiles>,
+    vision: Option<Vision>,
     windows: HashMap<window::WindowId, Lens>,
 }
 
 /// ### Fields
 ///
+/// * The `agents` field holds the [`Agents`] doing long-running work in the background.
+/// * The `atlas` field holds the [`Atlas`] of point symbols from the `[symbols]` table, if any.
+/// * The `catalog` field holds the [`Catalog`] of regional layers from the `[catalog]` table,
+///   and the built-in ones.
+/// * The `clashes` field holds the [`Clash`] types found while reading key bindings, for display
+///   in a warning on startup.
+/// * The `cli` field holds the [`Cli`] arguments, which override values from the config.
+/// * The `clipboard` field holds the system [`Clipboard`], once something has been copied.
+/// * The `closed` field holds a stack of [`Prescription`] types describing recently closed
+///   windows, with the most recent on top.  Holds at most [`CLOSED_WINDOWS`] entries.
 /// * The `cmd` field holds the [`Cmd`] struct, which maps keyboard inputs to program responses.
-/// * The `config` field holds the [`config::Config`] loaded from `Tardy.toml`.
+/// * The `datasets` field holds each [`Dataset`] read from a file the user dropped on a window,
+///   in the order they finished loading.
+/// * The `diagnostics` field holds the [`Diagnostic`] types found while reading the config, for
+///   display in a report on startup.
+/// * The `disabled` field holds the [`Group`] types listed under `disabled` in the `[groups]`
+///   table of the config.
+/// * The `figures` field holds the [`Figure`] of each open [`Dataset`] styled so far, which every
+///   map window draws over the map.
+/// * The `focused` field holds the [`window::WindowId`] of the window with keyboard focus, if any.
+/// * The `gpu` field holds the [`Gpu`] that every map window draws with.
+/// * The `hidden` field is `true` while [`Act::ToggleVisible`] has hidden the windows.
+/// * The `home` field holds the [`Home`] that locates the config file and cache directory.
+/// * The `hotkeys` field holds the system-wide [`Hotkeys`] from the `[global]` table of the
+///   config, if any.
+/// * The `last` field holds the most recent repeatable [`Act`], for use by [`Act::RepeatLast`].
+/// * The `modifiers` field holds the [`event::Modifiers`] currently held down, updated on each
+///   [`WindowEvent::ModifiersChanged`].
+/// * The `notice` field holds the [`Panel`] of the last report or warning, shown over every map
+///   until the next key press, as described in [`App::notify`].
+/// * The `offered` field holds the paths of data files the [`Agents`] found in the data folders,
+///   waiting for the user to load them with [`Act::LoadOffered`].
+/// * The `pad` field holds the [`Pad`] reading game controller input, if the platform supports
+///   controllers.
+/// * The `palette` field holds the [`Palette`] from the `[theme]` table, resolved against the
+///   contrast setting of the system, which every window draws with.
+/// * The `plugins` field holds the data providers, layer factories and tools other crates add
+///   to the app, as described in [`Plugins`].
 /// * The `proxy` fields holds the [`event_loop::EventLoopProxy`] that async processes use to send
-///   [`Hijinks`] to the main event loop.
+///   a [`UserEvent`] to the main event loop, by way of the [`Courier`] from [`App::courier`].
+/// * The `query` field holds the last parcel lookup that found a parcel, for a [`Permalink`] to
+///   share.
+/// * The `rebind` field holds the keybinding editor while it is open, which takes every key
+///   press, as described in [`Rebind`].
+/// * The `reporter` field holds the [`Reporter`] that counts errors for telemetry, if the
+///   `[telemetry]` table of the config turns it on.
+/// * The `scene` field holds the address and layer of the scene drawn on every map, if any,
+///   from [`App::show_scene`].
+/// * The `scroll` field holds the [`Scroll`] preferences for the mouse wheel, read from the
+///   `[scroll]` table in the config.
+/// * The `settings` field holds the [`Settings`] read from the merged config by
+///   [`App::load_config`].
+/// * The `stamp` field holds the [`Stamp`] drawing the icons of the [`Atlas`] over every map,
+///   put on the card with the first map window, as described in [`App::adorn`].
+/// * The `started` field indicates whether we have created the initial window.  The event loop
+///   calls [`ApplicationHandler::resumed`] again after every suspension, and we only want one
+///   initial window.
+/// * The `store` field holds the [`Store`] of bookmarks, recent files and the like, or [`None`]
+///   if it could not be opened, in which case the app carries on without remembering them.
+/// * The `tasks` field holds the [`Tasks`] registry of jobs running in the background.
+/// * The `tiles` field holds the [`Tiles`] layer of the base map, shared by every map window,
+///   once the first opens.
+/// * The `vision` field holds the [`Vision`] simulated over the map by [`Act::CycleVision`], or
+///   [`None`] for normal vision.
 /// * The `windows` field holds a [`HashMap`] with keys of type [`window::WindowId`] and values of type [`Lens`].
 impl App {
     /// Creates an instance of `App`.  Reads user key mappings from `Tardy.toml` using
@@ -69,24 +207,97 @@ impl App {
     /// sync loop.  Since we tie up the main event loop when we run the application, we create a
     /// proxy on startup and store it in the `proxy` field of `App` for later use.  
     ///
-    /// This later use occurs when summoning the [`ImpKing`], at which point we clone the proxy
-    /// and pass it to the async process, making no further use of it within `App`.  As the top
+    /// This later use occurs when starting background work, at which point we clone the proxy
+    /// into a [`Courier`] for the [`Tasks`] registry and the [`Agents`], which carry it to the
+    /// async processes.  As the top
     /// level data structure, we are using `App` to carry water from `main.rs` to a place where
     /// the async workers can drink it.
-    pub fn new(proxy: event_loop::EventLoopProxy<accesskit_winit::Event>) -> Self {
+    ///
+    /// The `cli` parameter holds the command line arguments.  A `--config` path replaces the
+    /// search in [`Home`], and the other arguments override the matching config values in
+    /// [`App::load_config`].
+    pub fn new(proxy: event_loop::EventLoopProxy<UserEvent>, cli: Cli) -> Self {
+        let home = Home::new().with_config(cli.config().clone());
         let cmd = Cmd::default();
-        let config = config::Config::default();
         let windows = HashMap::new();
+        let tasks = Tasks::new(Courier::new(proxy.clone()));
+        let agents = Agents::new(tasks.clone(), Courier::new(proxy.clone()), home.clone());
         let mut app = Self {
+            agents,
+            atlas: None,
+            catalog: Catalog::default(),
+            clashes: Vec::new(),
+            cli,
+            clipboard: None,
+            closed: Vec::new(),
             cmd,
-            config,
+            datasets: Vec::new(),
+            diagnostics: Vec::new(),
+            disabled: HashSet::new(),
+            figures: Vec::new(),
+            focused: None,
+            gpu: Gpu::new(),
+            hidden: false,
+            home,
+            hotkeys: None,
+            last: None,
+            modifiers: event::Modifiers::default(),
+            notice: None,
+            offered: Vec::new(),
+            pad: None,
+            palette: Palette::default(),
+            plugins: Plugins::default(),
             proxy,
+            query: None,
+            rebind: None,
+            recorder: None,
+            reporter: Reporter::default(),
+            scene: None,
+            scroll: Scroll::default(),
+            settings: Settings::default(),
+            stamp: None,
+            started: false,
+            store: None,
+            tasks,
+            tiles: None,
+            vision: None,
             windows,
         };
+        let crashes = Crash::report(&Crash::dir(&app.home));
         app.load_config();
+        if !crashes.is_empty() {
+            let lines = crashes
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            app.warn(Panel::new(
+                "The app crashed last time, reports saved to",
+                lines,
+            ));
+        }
+        app.load_reporter(&crashes);
         app.load_cmds();
+        app.load_scroll();
+        app.load_pad();
+        app.load_hotkeys();
+        app.load_groups();
+        app.load_symbols();
+        app.load_theme();
+        app.load_store();
+        app.load_catalog();
+        app.tasks.configure(app.settings.tasks());
+        app.agents.start(&app.settings);
         app
     }
+
+    /// The `with_plugins` method hands the app the `plugins` of a crate building on it, as
+    /// described in [`Plugins`].  Call it before running the event loop, so the first map window
+    /// draws the layers of the plugins.
+    pub fn with_plugins(mut self, plugins: Plugins) -> Self {
+        tracing::debug!("{plugins:?}");
+        self.plugins = plugins;
+        self
+    }
     /// Instead of using a `WindowBuilder`, we now create a default instance of
     /// [`window::WindowAttributes`], and modify it to be transparent and carry the title `Tardy`.
     /// Besides looking cool, `winit` recommends setting the window to transparent if you are not
@@ -98,75 +309,1943 @@ impl App {
     /// to need an [`Arc`] here to render an `egui` menu on top of a GIS map.
     ///
     /// Finally, we create an instance of [`Lens`] from the window, and insert it as a value into
-    /// the [`HashMap`] in the `windows` field, using the window id as a key.
+    /// the [`HashMap`] in the `windows` field, using the window id as a key.  A map window gets
+    /// its map from [`App::attach_map`].  A map that fails to open goes to
+    /// [`App::report_error`], and leaves the window open but empty, rather than failing the
+    /// window.
+    ///
+    /// The `role` parameter sets the [`Role`] of the new [`Lens`].  When `attributes` is [`None`],
+    /// we use the defaults from [`Role::attributes`], which add the role name to the title.
     ///
     /// Will [`crate::Blame::EventLoop`] when [`event_loop::ActiveEventLoop::create_window`] fails.
     #[tracing::instrument(skip_all)]
     pub fn create_window(
         &mut self,
         event_loop: &event_loop::ActiveEventLoop,
+        role: Role,
         attributes: Option<window::WindowAttributes>,
     ) -> Arrive<()> {
         let attr = if let Some(attributes) = attributes {
             attributes
         } else {
-            window::Window::default_attributes()
-                .with_title("Tardy")
-                .with_transparent(true)
+            role.attributes()
         };
-        let window = event_loop.create_window(attr)?;
+        // In the browser, the window draws on a canvas we add to the page.
+        #[cfg(target_arch = "wasm32")]
+        let attr = winit::platform::web::WindowAttributesExtWebSys::with_append(attr, true);
+        // The accessibility adapter has to see the window before the platform does.
+        let window = event_loop.create_window(attr.with_visible(false))?;
         let window = Arc::new(window);
         // Did I create a window?
         tracing::trace!("Window created: {:?}", window.id());
-        self.windows.insert(window.id(), Lens::new(window.clone()));
+        let mut lens = Lens::new(window.clone(), role);
+        lens.with_access(Access::new(&window, self.proxy.clone()))
+            .with_palette(self.palette);
+        lens.adopt(self.settings.graphics());
+        self.windows.insert(window.id(), lens);
+        if let Err(e) = self.attach_map(&window.id()) {
+            self.report_error(e, event_loop);
+        }
+        self.describe(&window.id());
+        window.set_visible(!self.hidden);
         // How many am I up to?
         tracing::trace!("Total windows: {}", self.windows.len());
         Ok(())
     }
 
-    /// The user specifies key mappings in `Tardy.toml`, as described in the docs for [`Act`].
-    /// I chose to use the [`config`] crate for parsing `toml`, as I'm likely to botch it if I
-    /// tried to do it myself.  Here we call [`config::Config::builder`] and attempt to read in the
-    /// source at `Tardy.toml`.
+    /// The `attach_map` method gives the map window with id `id` a [`crate::Canvas`] from the
+    /// [`Gpu`], and a [`Map`] drawing on it, opening on the view from the `[map]` table of the
+    /// config.  Does nothing for other roles, or a window that already has a map.  A map drawn
+    /// in software says so in the title of the window, with [`SOFTWARE_TITLE`].  In the
+    /// browser, the first map waits for [`Gpu::prepare`] to open the card, and attaches on the
+    /// [`UserEvent::Prepared`] that brings it.
+    ///
+    /// Will [`crate::Blame::CreateSurface`], [`crate::Excuse::NoAdapter`] or
+    /// [`crate::Blame::RequestDevice`] if the window cannot be drawn on, as described in
+    /// [`Gpu::canvas`], and [`crate::Excuse::NoResolution`] if the map cannot open at the
+    /// configured zoom level.
+    ///
+    /// The new map shows the notice the other maps show, which for the first map means the
+    /// warnings found as the app started.
+    #[tracing::instrument(skip(self))]
+    pub fn attach_map(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(lens) = self.windows.get_mut(id) else {
+            return Ok(());
+        };
+        if *lens.role() != Role::Map || lens.map().is_some() {
+            return Ok(());
+        }
+        let window = lens.window().clone();
+        #[cfg(target_arch = "wasm32")]
+        if !self.gpu.ready() {
+            let courier = Courier::new(self.proxy.clone());
+            self.gpu.prepare(window, self.settings.graphics(), courier);
+            return Ok(());
+        }
+        let canvas = self
+            .gpu
+            .canvas(window.clone(), self.settings.graphics())
+            .context("preparing the window for the map")?;
+        if self.gpu.software() && !window.title().ends_with(SOFTWARE_TITLE) {
+            window.set_title(&format!("{}{SOFTWARE_TITLE}", window.title()));
+        }
+        let tiles = self
+            .tiles
+            .get_or_insert_with(|| Map::tiles(self.settings.map(), &self.home));
+        let mut map = Map::new(
+            self.settings.map(),
+            tiles,
+            window,
+            canvas.device().clone(),
+            canvas.surface().clone(),
+            canvas.queue().clone(),
+            canvas.config().clone(),
+            *canvas.sample_count(),
+        )
+        .context("opening the map")?;
+        map.add_layers(self.plugins.layers(self.settings.map()));
+        map.reveal(self.scene.as_ref().map(|(_, scene)| scene));
+        map.show(&self.figures);
+        lens.with_canvas(canvas).with_map(map);
+        let sources = self.sources();
+        let animate = *self.settings.effects().animate();
+        if let Some(lens) = self.windows.get_mut(id) {
+            lens.shade(&sources, animate);
+            lens.post(Place::Notice, self.notice.clone());
+        }
+        self.adorn();
+        tracing::trace!("Map attached.");
+        Ok(())
+    }
+
+    /// The `save_snapshot` method saves a picture of the map in the window with id `id`, at
+    /// [`SNAPSHOT_SCALE`] times the size of the window, as a PNG under the [`SNAPSHOT_DIR`]
+    /// directory of [`Home::data`].  A window without a map has nothing to save.
+    ///
+    /// Will [`crate::Blame::Image`] if the file cannot be written, and anything
+    /// [`Gpu::offscreen`] or [`crate::Offscreen::render`] will.
+    #[tracing::instrument(skip_all)]
+    pub fn save_snapshot(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(lens) = self.windows.get(id) else {
+            return Ok(());
+        };
+        let Some(map) = lens.map() else {
+            tracing::info!("The {} window has no map to save.", lens.role());
+            return Ok(());
+        };
+        let size = lens.window().inner_size();
+        let snapshot = self
+            .gpu
+            .offscreen(
+                size.width * SNAPSHOT_SCALE,
+                size.height * SNAPSHOT_SCALE,
+                self.settings.graphics(),
+            )?
+            .render(map)
+            .context("drawing the picture of the map")?;
+        let dir = self.home.data().join(SNAPSHOT_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let stamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        snapshot.save(&dir.join(format!("map-{stamp}.png")))
+    }
+
+    /// The `export_features` method writes each open table in `format`, to GeoJSON with
+    /// [`Dataset::save_geojson`], to a shapefile with [`Shapefile::save`], to GeoParquet with
+    /// [`GeoParquet::save`], or to CSV or an Excel workbook with [`Workbook`], under the
+    /// [`FEATURE_DIR`] directory of the data directory.  If
+    /// `selection` is `true`, only the features in view of the map in the window with id `id`
+    /// go, and a table with none in view writes no file.  Otherwise every feature goes.  The rows
+    /// go in the order of the `[sort]` table of the config (see [`crate::Sort`]), if it names a
+    /// column of the table, and in the order of the file otherwise.
+    ///
+    /// Will [`crate::Blame::Io`] if a file cannot be written, after writing the tables before it.
+    #[tracing::instrument(skip(self))]
+    pub fn export_features(
+        &mut self,
+        id: &window::WindowId,
+        selection: bool,
+        format: Format,
+    ) -> Arrive<()> {
+        let extent = match selection {
+            true => {
+                let extent = self
+                    .windows
+                    .get(id)
+                    .and_then(|lens| lens.map().as_ref().and_then(Map::extent));
+                if extent.is_none() {
+                    tracing::info!("No map in this window to select from.");
+                    return Ok(());
+                }
+                extent
+            }
+            false => None,
+        };
+        if self.datasets.is_empty() {
+            tracing::info!("No data files open to export.");
+            return Ok(());
+        }
+        let dir = self.home.data().join(FEATURE_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let stamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        for dataset in &self.datasets {
+            let stem = dataset
+                .path()
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "features".to_string());
+            let extension = match format {
+                Format::Shapefile => "shp",
+                Format::Parquet => "parquet",
+                Format::Csv => "csv",
+                Format::Xlsx => "xlsx",
+                _ => "geojson",
+            };
+            let path = match selection {
+                true => dir.join(format!("{stem}-selection-{stamp}.{extension}")),
+                false => dir.join(format!("{stem}-{stamp}.{extension}")),
+            };
+            let chosen = dataset
+                .selection(extent.as_ref())
+                .with_context(|| format!("selecting from {stem}"))?;
+            // Skip tables with nothing in view, rather than leave empty files behind.
+            if chosen.is_empty() {
+                tracing::info!("Nothing in view from {stem}.");
+                continue;
+            }
+            let settings = self.settings.sort();
+            let sort = settings
+                .column()
+                .as_deref()
+                .filter(|name| chosen.column(name).is_some())
+                .map(|name| (name, *settings.descending()));
+            let chosen = match sort.and_then(|(name, _)| chosen.column(name)) {
+                Some(column) => chosen
+                    .sort(column, *settings.descending())
+                    .with_context(|| format!("sorting {stem}"))?,
+                None => chosen,
+            };
+            match format {
+                Format::Shapefile => Shapefile::from_dataset(&chosen).save(&path),
+                Format::Parquet => GeoParquet::save(&chosen, &path),
+                Format::Csv => {
+                    let record = Workbook::record(dataset, &chosen, extent.as_ref(), sort);
+                    Workbook::save_csv(&chosen, &record, &path)
+                }
+                Format::Xlsx => Workbook::new(dataset, &chosen, extent.as_ref(), sort).save(&path),
+                _ => chosen.save_geojson(&path),
+            }
+            .with_context(|| format!("exporting {stem}"))?;
+        }
+        Ok(())
+    }
+
+    /// The `toggle_recording` method starts recording the map in the window with id `id`, or
+    /// stops the recording under way, wherever it is.  The frames come out at the size of the
+    /// window when the recording starts, and go to a clip under the [`RECORDING_DIR`] directory
+    /// of [`Home::data`].  A window without a map has nothing to record.
+    ///
+    /// Will fail as [`Gpu::offscreen`] and [`crate::Reel::start`] do.
+    #[tracing::instrument(skip_all)]
+    pub fn toggle_recording(&mut self, id: &window::WindowId) -> Arrive<()> {
+        if self.recorder.is_some() {
+            self.stop_recording();
+            return Ok(());
+        }
+        let Some(lens) = self.windows.get(id) else {
+            return Ok(());
+        };
+        if lens.map().is_none() {
+            tracing::info!("The {} window has no map to record.", lens.role());
+            return Ok(());
+        }
+        let size = lens.window().inner_size();
+        let offscreen = self
+            .gpu
+            .offscreen(size.width, size.height, self.settings.graphics())?;
+        let recording = self.settings.recording();
+        let dir = self.home.data().join(RECORDING_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let stamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        let path = dir.join(format!("map-{stamp}.{}", recording.format().extension()));
+        self.recorder = Some(Recorder::new(*id, offscreen, recording, &path)?);
+        tracing::info!(
+            "Recording the map.  Use {} again to stop.",
+            self.cmd.label(&Act::ToggleRecording)
+        );
+        Ok(())
+    }
+
+    /// The `stop_recording` method stops the recording under way, if any, and waits for the
+    /// encoder to finish the clip on a bulk job of the [`Tasks`] registry.  Errors arrive
+    /// through the [`Courier`].
+    pub fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let now = time::Instant::now();
+        let reel = recorder.finish();
+        tracing::info!("Recorded {} frames, finishing.", reel.frames());
+        self.tasks
+            .spawn("Saving the recording", Class::Bulk, move |_| async move {
+                crate::unblock(move || reel.finish(now)).await?
+            });
+    }
+
+    /// The `close_window` method removes the window with id `id` from the `windows` field, writing
+    /// a [`Prescription`] for the window onto the `closed` stack before the window drops.  If the
+    /// stack grows beyond [`CLOSED_WINDOWS`] entries, we forget the oldest prescription.
+    ///
+    /// Does nothing if `id` does not refer to an open window.  Closing the focused window clears
+    /// the `focused` field until the window manager focuses another window.
+    #[tracing::instrument(skip_all)]
+    pub fn close_window(&mut self, id: &window::WindowId) {
+        if self.focused.as_ref() == Some(id) {
+            self.focused = None;
+        }
+        if self.recorder.as_ref().map(Recorder::window).as_ref() == Some(id) {
+            self.stop_recording();
+        }
+        if let Some(lens) = self.windows.remove(id) {
+            self.closed.push(lens.prescription());
+            if self.closed.len() > CLOSED_WINDOWS {
+                self.closed.remove(0);
+            }
+            tracing::trace!("Closed windows remembered: {}", self.closed.len());
+        }
+    }
+
+    /// The `reopen_window` method pops the most recent [`Prescription`] from the `closed` stack
+    /// and passes the resulting [`window::WindowAttributes`] to [`App::create_window`].
+    ///
+    /// Logs a warning and returns `Ok` if there are no closed windows to reopen.
+    /// Will [`crate::Blame::OsError`] if [`App::create_window`] fails.
+    #[tracing::instrument(skip_all)]
+    pub fn reopen_window(&mut self, event_loop: &event_loop::ActiveEventLoop) -> Arrive<()> {
+        if let Some(prescription) = self.closed.pop() {
+            tracing::info!("Reopening window.");
+            self.create_window(
+                event_loop,
+                *prescription.role(),
+                Some(prescription.attributes()),
+            )
+        } else {
+            tracing::warn!("No closed windows to reopen.");
+            Ok(())
+        }
+    }
+
+    /// The user specifies key mappings in `Tardy.toml`, as described in the docs for [`Act`].
+    /// I chose to use the [`config`] crate for parsing `toml`, as I'm likely to botch it if I
+    /// tried to do it myself.  The config now comes in layers, from built-in defaults up through
+    /// the system, user and project files to the environment and command line, and [`Layers`]
+    /// merges them in the order described in its docs.
+    ///
+    /// On first run, when there is no config file at all, we write one with
+    /// [`crate::write_default_config`] so the user has something to edit, and read that.
+    ///
+    /// Once the config is built, [`Diagnostic::check`] looks for unknown keys and values of the
+    /// wrong type, and [`App::report_diagnostics`] tells the user about them, along with any
+    /// trouble reading the files themselves.  Nothing here unwraps anymore, so a broken config
+    /// costs the user some settings rather than the whole app.
+    ///
+    /// Credentials come from the [`Secrets`] file, read separately from the config layers so
+    /// that nothing we merge or write back can carry them into a shared file, unless the
+    /// environment or the command line sets them, as described in [`Settings::with_secrets`].
+    #[tracing::instrument(skip_all)]
+    pub fn load_config(&mut self) {
+        if !self.home.layers().iter().any(|path| path.is_file()) {
+            let path = self.home.config();
+            tracing::info!("No config found, writing defaults.");
+            if let Err(e) = crate::write_default_config(&path) {
+                tracing::warn!("Could not write default config: {e}");
+            }
+        }
+        let layers = Layers::new(self.cli.clone(), self.home.clone());
+        let (config, diagnostics) = layers.load();
+        self.diagnostics.extend(diagnostics);
+        self.diagnostics.extend(Diagnostic::check(&config));
+        let secrets = self.home.secrets();
+        let secrets = match Secrets::load(&secrets) {
+            Ok(secrets) => secrets,
+            Err(e) => {
+                tracing::warn!("Could not read secrets from {}.", secrets.display());
+                self.diagnostics.push(Diagnostic::new(
+                    String::new(),
+                    Snippet::from_file(&secrets)
+                        .map(Problem::Syntax)
+                        .unwrap_or_else(|| Problem::Unreadable(e.to_string())),
+                    Some(secrets.display().to_string()),
+                ));
+                Secrets::default()
+            }
+        };
+        self.report_diagnostics();
+        self.settings = Settings::load(&config).with_secrets(&secrets, &layers.pinned());
+        if let Some(key) = self.settings.api().key() {
+            Network::global().hide(key);
+        }
+        if let Err(e) = Network::global().configure(self.settings.network()) {
+            tracing::warn!("Keeping the old network limits: {e}");
+        }
+
+        // Read the settings to make sure they're correct.
+        tracing::trace!("{:#?}", self.settings);
+    }
+
+    /// The `report_usage` method shows the memory and disk used by the app, as measured by
+    /// [`Usage::measure`], in a notice from [`App::notify`].
+    pub fn report_usage(&mut self) {
+        let usage = Usage::measure(&self.home);
+        self.notify(Panel::from_text("Usage", &usage.to_string()));
+    }
+
+    /// The `report_recent` method lists the recent files from the [`Store`] in a notice from
+    /// [`App::notify`], the most recently opened first.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the store cannot be read.
+    pub fn report_recent(&mut self) -> Arrive<()> {
+        let Some(store) = &self.store else {
+            tracing::info!("No store to remember recent files in.");
+            return Ok(());
+        };
+        let recent = store.recent().context("reading the recent files")?;
+        let lines = match recent.is_empty() {
+            true => vec!["No recent files.".to_string()],
+            false => recent
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+        };
+        self.notify(Panel::new("Recent files", lines));
+        Ok(())
+    }
+
+    /// The `clear_cache` method empties `cache`, along with its index in the [`Store`].
+    ///
+    /// Will [`crate::Blame::Io`] if the cache cannot be removed, and [`crate::Blame::Sqlite`] if
+    /// the index cannot be dropped.
+    pub fn clear_cache(&self, cache: Cache) -> Arrive<()> {
+        cache.clear(&self.home)?;
+        if let Some(store) = &self.store {
+            store
+                .forget(cache)
+                .with_context(|| format!("forgetting the {cache} cache"))?;
+        }
+        Ok(())
+    }
+
+    /// The `report_diagnostics` method warns the user about each [`Diagnostic`] in the
+    /// `diagnostics` field, in a notice from [`App::warn`], like [`App::report_clashes`].
+    #[tracing::instrument(skip_all)]
+    pub fn report_diagnostics(&mut self) {
+        if !self.diagnostics.is_empty() {
+            let title = format!("Found {} problems with the config:", self.diagnostics.len());
+            let text = self
+                .diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("\n");
+            self.warn(Panel::from_text(title, &text));
+        }
+    }
+
+    /// The `courier` method returns a [`Courier`] holding a clone of the `proxy` field, for a
+    /// background task to send its errors and other news back to the event loop.
+    pub fn courier(&self) -> Courier {
+        Courier::new(self.proxy.clone())
+    }
+
+    /// The `open_file` method reads the data file at `path` as an interactive job of the
+    /// [`Tasks`] registry, parsing it off the event loop with [`Dataset::load`], or with
+    /// [`Plugins::load`] if a [`crate::DataProvider`] accepts the file.  The table arrives later
+    /// as a [`UserEvent::Loaded`], and errors arrive through the [`Courier`].
+    pub fn open_file(&self, path: std::path::PathBuf) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.remember(&path) {
+                tracing::debug!("Could not add {} to the recent files: {e}", path.display());
+            }
+        }
+        let provider = self.plugins.provider(&path);
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        let courier = self.courier();
+        self.tasks.spawn(
+            format!("Opening {name}"),
+            Class::Interactive,
+            move |job| async move {
+                let dataset = match provider {
+                    Some(provider) => Plugins::load(provider, path, job).await?,
+                    None => Dataset::load(path, job).await?,
+                };
+                courier.notify(UserEvent::Loaded(dataset));
+                Ok(())
+            },
+        );
+    }
+
+    /// The `geocode_addresses` method starts a bulk job of the [`Tasks`] registry for each open
+    /// CSV table without geometry, finding the place of each address with a [`Geocoder`], as
+    /// described in its docs.  The results arrive as a [`UserEvent::Loaded`], to open as a
+    /// point layer, and errors arrive through the [`Courier`].
+    pub fn geocode_addresses(&self) {
+        let tables = self
+            .datasets
+            .iter()
+            .filter(|dataset| *dataset.format() == Format::Csv && dataset.geometry().is_none())
+            .cloned()
+            .collect::<Vec<Dataset>>();
+        if tables.is_empty() {
+            tracing::info!("No CSV tables without geometry open to geocode.");
+            return;
+        }
+        let dir = self.home.data().join(GEOCODE_DIR);
+        for dataset in tables {
+            let name = dataset
+                .path()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| dataset.path().display().to_string());
+            let geocoder = Geocoder::new(self.settings.geocoding().clone());
+            let courier = self.courier();
+            let dir = dir.clone();
+            self.tasks.spawn(
+                format!("Geocoding {name}"),
+                Class::Bulk,
+                move |job| async move {
+                    let table = geocoder.run(dataset, dir, job).await?;
+                    courier.notify(UserEvent::Loaded(table));
+                    Ok(())
+                },
+            );
+        }
+    }
+
+    /// The `add_from_catalog` method adds the current layer of the [`Catalog`] to the map in the
+    /// window with id `id`, with [`App::add_listing`].
+    pub fn add_from_catalog(&self, id: &window::WindowId) {
+        match self.catalog.current().cloned() {
+            Some(listing) => self.add_listing(id, listing),
+            None => tracing::info!("The data catalog is empty."),
+        }
+    }
+
+    /// The `add_listing` method starts an interactive job of the [`Tasks`] registry that
+    /// fetches the layer of `listing` for the ground in view of the map in the window with id
+    /// `id`, with [`Listing::add`].  The table arrives as a [`UserEvent::Loaded`], and errors
+    /// arrive through the [`Courier`].
+    pub fn add_listing(&self, id: &window::WindowId, listing: Listing) {
+        let Some(extent) = self
+            .windows
+            .get(id)
+            .and_then(|lens| lens.map().as_ref().and_then(Map::extent))
+        else {
+            tracing::info!("No map in this window to add {} to.", listing.name());
+            return;
+        };
+        let dir = self.home.data().join(CATALOG_DIR);
+        let courier = self.courier();
+        self.tasks.spawn(
+            format!("Adding {}", listing.name()),
+            Class::Interactive,
+            move |job| async move {
+                let dataset = listing.add(extent, dir, job).await?;
+                courier.notify(UserEvent::Loaded(dataset));
+                Ok(())
+            },
+        );
+    }
+
+    /// The `search_imagery` method starts an interactive job of the [`Tasks`] registry that
+    /// searches the STAC catalog of the [`Settings`] for the scenes over the map in the window
+    /// with id `id`, with [`Stac::search`].  The scenes arrive as a [`UserEvent::Loaded`], to open
+    /// as a layer, and errors arrive through the [`Courier`].
+    pub fn search_imagery(&self, id: &window::WindowId) {
+        let Some(extent) = self
+            .windows
+            .get(id)
+            .and_then(|lens| lens.map().as_ref().and_then(Map::extent))
+        else {
+            tracing::info!("No map in this window to search for imagery.");
+            return;
+        };
+        let stac = Stac::new(self.settings.imagery().clone());
+        let dir = self.home.data().join(IMAGERY_DIR);
+        let courier = self.courier();
+        self.tasks.spawn(
+            format!("Searching for {}", self.settings.imagery().collection()),
+            Class::Interactive,
+            move |job| async move {
+                let scenes = stac.search(extent, dir, job).await?;
+                courier.notify(UserEvent::Loaded(scenes));
+                Ok(())
+            },
+        );
+    }
+
+    /// The `show_scene` method draws the picture of the next scene under the middle of the map in
+    /// the window with id `id` on every map, as described in [`Cog`].  The scenes are the rows
+    /// with an `href` of the open tables from [`App::search_imagery`], newest first, and after
+    /// the last, or with none there, the maps show no scene.  The picture opens as an
+    /// interactive job of the [`Tasks`] registry, and arrives as a [`UserEvent::Scene`], with
+    /// errors through the [`Courier`].
+    pub fn show_scene(&mut self, id: &window::WindowId) {
+        let Some(extent) = self
+            .windows
+            .get(id)
+            .and_then(|lens| lens.map().as_ref().and_then(Map::extent))
+        else {
+            tracing::info!("No map in this window to show a scene on.");
+            return;
+        };
+        let (x_min, y_min, x_max, y_max) = extent.to_mercator();
+        let (x, y) = ((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+        let hrefs = self
+            .datasets
+            .iter()
+            .filter_map(|dataset| Some((dataset, dataset.column("href")?)))
+            .flat_map(|(dataset, column)| {
+                let hrefs = dataset.texts(column);
+                dataset
+                    .outlines()
+                    .iter()
+                    .zip(hrefs.iter())
+                    .filter(|(shape, _)| shape.as_ref().is_some_and(|shape| shape.holds(x, y)))
+                    .filter_map(|(_, href)| href.filter(|href| !href.is_empty()))
+                    .map(str::to_string)
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<String>>();
+        let shown = self.scene.as_ref().map(|(href, _)| href);
+        let next = match shown.and_then(|shown| hrefs.iter().position(|href| href == shown)) {
+            Some(at) => hrefs.get(at + 1),
+            None => hrefs.first(),
+        };
+        let Some(href) = next.cloned() else {
+            tracing::info!("No scene shown, of {} under the map.", hrefs.len());
+            self.scene = None;
+            self.windows.values_mut().for_each(|lens| lens.reveal(None));
+            return;
+        };
+        let courier = self.courier();
+        self.tasks.spawn(
+            "Opening a scene".to_string(),
+            Class::Interactive,
+            move |_job| async move {
+                let scene = Cog::open(&href).await?;
+                courier.notify(UserEvent::Scene(scene));
+                Ok(())
+            },
+        );
+    }
+
+    /// The `download` method fetches `download` as a bulk job of the [`Tasks`] registry, with
+    /// [`Download::fetch`], and returns the [`Job`], so the caller can cancel it.  Errors arrive
+    /// through the [`Courier`], once the download has run out of retries.
+    pub fn download(&self, download: Download) -> Job {
+        let name = download
+            .target()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| download.url().clone());
+        self.tasks.spawn(
+            format!("Downloading {name}"),
+            Class::Bulk,
+            move |job| async move {
+                download.fetch(&job).await?;
+                Ok(())
+            },
+        )
+    }
+
+    /// The `download_clipboard` method downloads the file at the address on the clipboard with
+    /// [`App::download`], into [`DOWNLOAD_DIR`] under the data directory, named for the last part
+    /// of the address.  A SHA-256 digest after the address, separated by a space, is checked
+    /// against the file, as in [`Download::with_checksum`].  A file of the same name already in
+    /// the folder stays as it is, and the download fails with [`crate::Excuse::DownloadExists`]
+    /// rather than write over it.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the clipboard cannot be read.
+    pub fn download_clipboard(&mut self) -> Arrive<()> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return Ok(());
+        };
+        let text = clipboard.paste().context("pasting from the clipboard")?;
+        let mut words = text.split_whitespace();
+        let Some(url) = words
+            .next()
+            .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+        else {
+            tracing::info!("No address on the clipboard to download.");
+            return Ok(());
+        };
+        let name = url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit(['/', '\\']).next())
+            .filter(|name| !name.trim_matches('.').is_empty())
+            .unwrap_or("download");
+        let target = self.home.data().join(DOWNLOAD_DIR).join(name);
+        let mut download = Download::new(url, target);
+        if let Some(checksum) = words.next() {
+            download = download.with_checksum(checksum);
+        }
+        tracing::info!("Downloading {}.", Network::global().redact(url));
+        self.download(download);
+        Ok(())
+    }
+
+    /// The `redraw_all` method invalidates every window, after a background task changes
+    /// something any of them might show, like the progress of a job.  The redraws wait for
+    /// [`App::flush_redraws`], so a burst of news costs one frame.
+    pub fn redraw_all(&mut self) {
+        self.windows.values_mut().for_each(Lens::invalidate);
+    }
+
+    /// The `flush_redraws` method turns the invalidations collected since the last pass into at
+    /// most one redraw request per window, with [`Lens::flush`].  A window that draws continuously
+    /// by its [`crate::Pacing`] keeps invalidating itself, whether focused or not.  Returns the
+    /// earliest time a window that had to wait may ask again, for [`App::pace`].
+    ///
+    /// Called from [`ApplicationHandler::about_to_wait`], once the events of this pass are in.
+    #[tracing::instrument(skip_all)]
+    pub fn flush_redraws(&mut self) -> Option<time::Instant> {
+        self.windows
+            .values_mut()
+            .filter(|lens| lens.needs_frames())
+            .for_each(Lens::invalidate);
+        let now = time::Instant::now();
+        self.windows
+            .values_mut()
+            .filter_map(|lens| lens.flush(now))
+            .min()
+    }
+
+    /// The `tasks` method returns the [`Tasks`] registry, for starting a job in the background.
+    pub fn tasks(&self) -> &Tasks {
+        &self.tasks
+    }
+
+    /// The `report_error` method is the one place the app decides what a failure means for the
+    /// user, whether it comes from a background task as a [`UserEvent::Error`] or from an act
+    /// handled on the event loop.  It goes by the [`Severity`] of the [`Blame`]:
+    ///
+    /// * A transient error goes to the log, since whoever sent it has already given up retrying.
+    /// * Any other error is recoverable.  The user gets a warning over the map, in a notice from
+    ///   [`App::warn`], and the app carries on without whatever failed, such as one layer or one
+    ///   extra window.
+    /// * A fatal error goes to [`App::shutdown`].
+    ///
+    /// The user reads [`Blame::report`], while the details go to the log at the debug level.
+    /// The [`Reporter`] counts each error once, here, or in [`App::shutdown`] for a fatal one,
+    /// since some errors go straight there.
+    #[tracing::instrument(skip_all)]
+    pub fn report_error(&mut self, blame: Blame, event_loop: &event_loop::ActiveEventLoop) {
+        tracing::debug!("Failed: {blame}");
+        match blame.severity() {
+            Severity::Transient => tracing::info!("{}", blame.report()),
+            Severity::Error => self.warn(Panel::new("Something went wrong", vec![blame.report()])),
+            Severity::Fatal => return self.shutdown(blame, event_loop),
+        }
+        self.reporter.count(&blame);
+    }
+
+    /// The `notify` method shows `panel` over every map until the next key press, as described
+    /// in [`crate::Panel`], for the reports the user asks for.  Every line goes to the log as
+    /// well, so the record outlives the notice.
+    pub fn notify(&mut self, panel: Panel) {
+        tracing::info!("{}", panel.title());
+        panel
+            .lines()
+            .iter()
+            .for_each(|line| tracing::info!("{line}"));
+        self.post_notice(Some(panel));
+    }
+
+    /// The `warn` method shows `panel` like [`App::notify`], for a problem the user should know
+    /// about, with the lines in the log as warnings.  A notice still showing keeps its lines
+    /// above the warning, so the problems found as the config loads all wait for the first map.
+    pub fn warn(&mut self, panel: Panel) {
+        tracing::warn!("{}", panel.title());
+        panel
+            .lines()
+            .iter()
+            .for_each(|line| tracing::warn!("  {line}"));
+        let panel = match self.notice.take() {
+            Some(notice) => {
+                let mut lines = notice.lines().clone();
+                lines.push(panel.title().clone());
+                lines.extend(panel.lines().iter().cloned());
+                Panel::new(notice.title().clone(), lines)
+            }
+            None => panel,
+        };
+        self.post_notice(Some(panel));
+    }
+
+    /// The `post_notice` method shows `notice` over every map, in place of the notice before, or
+    /// takes the notice away with [`None`].  A map window that opens later shows the notice
+    /// too, so a warning found as the app starts waits for the first map.
+    pub fn post_notice(&mut self, notice: Option<Panel>) {
+        self.windows
+            .values_mut()
+            .for_each(|lens| lens.post(Place::Notice, notice.clone()));
+        self.notice = notice;
+    }
+
+    /// The `show_status` method keeps the status of the keyboard in step with the [`Cmd`], as
+    /// described in [`crate::Panel`].  While a count or a key sequence is pending, the focused
+    /// window, or the window with id `id` if none has focus, shows a panel headed by the count
+    /// and the keys pressed so far, listing each binding that finishes the sequence and the
+    /// [`Act`] it performs, and otherwise no window shows one.
+    pub fn show_status(&mut self, id: &window::WindowId) {
+        let pending = self.cmd.pending().to_string();
+        let title = match (self.cmd.count(), pending.is_empty()) {
+            (Some(count), true) => Some(format!("Count: {count}")),
+            (Some(count), false) => Some(format!("{count} {pending}")),
+            (None, false) => Some(pending),
+            (None, true) => None,
+        };
+        let panel = title.map(|title| {
+            let hints = self
+                .cmd
+                .hints()
+                .into_iter()
+                .map(|(chord, act)| format!("{chord}  {}", act.title()))
+                .collect();
+            Panel::new(title, hints)
+        });
+        let target = self.target(id);
+        for (window, lens) in self.windows.iter_mut() {
+            let shown = panel.clone().filter(|_| *window == target);
+            lens.post(Place::Status, shown);
+        }
+    }
+
+    /// The `load_reporter` method sets up the [`Reporter`] from the `[telemetry]` table of the
+    /// config, and has it upload the error counts from earlier runs along with `crashes`, the
+    /// crash reports from [`Crash::report`].  With telemetry off, nothing is counted or sent.
+    #[tracing::instrument(skip_all)]
+    pub fn load_reporter(&mut self, crashes: &[std::path::PathBuf]) {
+        self.reporter = Reporter::new(self.settings.telemetry().clone(), &self.home);
+        if self.reporter.is_enabled() {
+            tracing::info!("Telemetry is on.");
+            self.reporter.upload(crashes);
+        }
+    }
+
+    /// The `load_catalog` method builds the [`Catalog`] from the `[catalog]` table of the
+    /// [`Settings`], keeping the current layer if it is still listed.
+    pub fn load_catalog(&mut self) {
+        let current = self.catalog.current().map(|listing| listing.name().clone());
+        self.catalog = Catalog::new(self.settings.catalog());
+        if let Some(name) = current {
+            self.catalog = self.catalog.clone().with_current(&name);
+        }
+    }
+
+    /// The `load_store` method opens the [`Store`] in the data directory, and moves into it any
+    /// bookmarks and recent files kept there as JSON, with [`Store::import`].  The app runs fine
+    /// without it, so failure only goes to the log.
+    pub fn load_store(&mut self) {
+        match Store::open(&self.home.data().join(STORE_FILE)) {
+            Ok(mut store) => {
+                if let Err(e) = store.import(&self.home.data()) {
+                    tracing::warn!("Could not move bookmarks and recent files into the store: {e}");
+                }
+                self.store = Some(store);
+            }
+            Err(e) => tracing::warn!("Could not open the store: {e}"),
+        }
+    }
+
+    /// The `save_reporter` method saves the error counts of the [`Reporter`] for the next
+    /// upload, as the app exits or reloads the config.
+    pub fn save_reporter(&mut self) {
+        if let Err(e) = self.reporter.save() {
+            tracing::debug!("Could not save error counts: {e}");
+        }
+    }
+
+    /// The `shutdown` method closes the app cleanly after `blame`, a failure we cannot carry on
+    /// from, such as having no window to draw in.  We tell the user why, close the windows so
+    /// they drop in order, and ask the event loop to exit, rather than panicking out from under
+    /// everything.  The message goes to the log at the error level, where it outlives the
+    /// windows, and the [`Reporter`] counts the error.
+    #[tracing::instrument(skip_all)]
+    pub fn shutdown(&mut self, blame: Blame, event_loop: &event_loop::ActiveEventLoop) {
+        tracing::error!("{blame}");
+        tracing::error!("{}", blame.report());
+        self.reporter.count(&blame);
+        let ids = self
+            .windows
+            .keys()
+            .cloned()
+            .collect::<Vec<window::WindowId>>();
+        ids.iter().for_each(|id| self.close_window(id));
+        event_loop.exit();
+    }
+
+    /// Keys and values play reversed roles in the [`Cmd`] and [`crate::Keybindings`] structs.  Here we
+    /// convert one to the other using the [`Cmd::load`] method.
+    /// Failure to read any commands from the config will produce an empty [`Cmd`], which will
+    /// restrict the user to mouse interactions.
+    ///
+    /// Any problems with the bindings go into the `clashes` field, and we warn the user about
+    /// each of them with [`App::report_clashes`].
+    #[tracing::instrument(skip_all)]
+    pub fn load_cmds(&mut self) {
+        let (cmd, clashes) = Cmd::load(self.settings.keybindings());
+        self.cmd = cmd;
+        self.clashes = clashes;
+        self.report_clashes();
+        tracing::trace!("Commands read from config.");
+        // Do you see the commands you expected?
+        tracing::trace!("{:?}", self.cmd);
+    }
+
+    /// The `report_clashes` method warns the user about each [`Clash`] in the `clashes` field,
+    /// listing the offending lines of the config, in a notice from [`App::warn`].
+    #[tracing::instrument(skip_all)]
+    pub fn report_clashes(&mut self) {
+        if !self.clashes.is_empty() {
+            let title = format!(
+                "Found {} problems with key bindings in {CONFIG_FILE}:",
+                self.clashes.len()
+            );
+            let text = self
+                .clashes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("\n");
+            self.warn(Panel::from_text(title, &text));
+        }
+    }
+
+    /// The `load_scroll` method takes the [`Scroll`] preferences from the `[scroll]` table in the
+    /// [`Settings`].  If the table is missing or malformed, [`Settings::load`] has left the
+    /// defaults from [`Scroll::default`].
+    #[tracing::instrument(skip_all)]
+    pub fn load_scroll(&mut self) {
+        self.scroll = *self.settings.ui().scroll();
+        tracing::trace!("{:?}", self.scroll);
+    }
+
+    /// The `load_pad` method connects to game controllers using [`Pad::new`].  Controllers are a
+    /// nice extra, so if the platform does not support them, we log the reason and carry on.
+    #[tracing::instrument(skip_all)]
+    pub fn load_pad(&mut self) {
+        match Pad::new() {
+            Ok(pad) => self.pad = Some(pad),
+            Err(e) => tracing::info!("Gamepad support unavailable: {e}"),
+        }
+    }
+
+    /// The `load_hotkeys` method registers the global hotkeys from the config using
+    /// [`Hotkeys::load`], with presses arriving as a [`UserEvent::Hotkey`].  Global hotkeys are
+    /// optional, so failures only produce a warning.  Any old hotkeys go first, since dropping
+    /// them takes the handler of the new ones with them.
+    #[tracing::instrument(skip_all)]
+    pub fn load_hotkeys(&mut self) {
+        self.hotkeys = None;
+        let courier = Courier::new(self.proxy.clone());
+        match Hotkeys::load(self.settings.keybindings().global(), courier) {
+            Ok(hotkeys) => self.hotkeys = hotkeys,
+            Err(e) => tracing::warn!("Global hotkeys unavailable: {e}"),
+        }
+    }
+
+    /// The `load_groups` method reads the list of disabled [`Group`] types from the `[groups]`
+    /// table of the [`Settings`].  Without the table, every group is enabled.  A malformed list
+    /// enables every group too, since the alternative is guessing what the user meant.
+    #[tracing::instrument(skip_all)]
+    pub fn load_groups(&mut self) {
+        self.disabled = self
+            .settings
+            .ui()
+            .groups()
+            .disabled()
+            .iter()
+            .copied()
+            .collect();
+        if self.disabled.is_empty() {
+            tracing::trace!("No action groups disabled.");
+        } else {
+            tracing::info!("Disabled action groups: {:?}", self.disabled);
+        }
+    }
+
+    /// The `load_symbols` method packs the icons in the folder of the `[symbols]` table of the
+    /// [`Settings`] into an [`Atlas`].  Without a folder, or with one we cannot read, there is no
+    /// atlas, and points go without icons.  The maps let go of the [`Stamp`] of the old atlas and
+    /// take one of the new, as in [`App::adorn`].
+    #[tracing::instrument(skip_all)]
+    pub fn load_symbols(&mut self) {
+        let symbols = self.settings.symbols();
+        self.atlas = match symbols.folder(&self.home.config()) {
+            Some(dir) => match Atlas::build(&dir, *symbols.size()) {
+                Ok(atlas) => Some(atlas),
+                Err(e) => {
+                    tracing::warn!("Could not read the symbols in {}: {e}", dir.display());
+                    None
+                }
+            },
+            None => None,
+        };
+        self.stamp = None;
+        self.adorn();
+    }
+
+    /// The `adorn` method hands every map the [`Stamp`] drawing the icons of the [`Atlas`], as
+    /// described in [`crate::Stamp`].  The stamp goes on the card of the first window with a map,
+    /// which every window shares, so before the first map opens there is nothing to build it on,
+    /// and without an atlas the maps draw no icons.
+    pub fn adorn(&mut self) {
+        if self.stamp.is_none() {
+            let canvas = self
+                .windows
+                .values()
+                .find_map(|lens| lens.canvas().as_ref());
+            if let (Some(atlas), Some(canvas)) = (&self.atlas, canvas) {
+                let stamp = Stamp::new(canvas.device().clone(), canvas.queue().clone(), atlas);
+                self.stamp = Some(Arc::new(stamp));
+            }
+        }
+        self.windows
+            .values_mut()
+            .for_each(|lens| lens.adorn(self.stamp.clone()));
+    }
+
+    /// The `style` method styles `dataset` by the `[choropleth]` and `[symbols]` tables of the
+    /// [`Settings`] in the background, with [`Figure::new`], as described in the [`Figure`] docs.
+    /// Lines and dots take their widths from the [`Palette`], at the scale of the large-UI mode,
+    /// and points with an icon in the [`Atlas`] leave the dot to the icon.  The figure arrives
+    /// as a [`UserEvent::Styled`], and errors arrive through the [`Courier`].
+    pub fn style(&self, dataset: Dataset) {
+        let choropleth = self.settings.choropleth().clone();
+        let symbols = self.settings.symbols().clone();
+        let scale = self.palette.scale();
+        let (width, dot) = (self.palette.line() * scale, FIGURE_DOT * scale);
+        let icons = match &self.atlas {
+            Some(atlas) => atlas.names().into_iter().map(str::to_owned).collect(),
+            None => Vec::new(),
+        };
+        let courier = self.courier();
+        self.courier().spawn(async move {
+            let figure = crate::unblock(move || {
+                Figure::new(&dataset, &choropleth, &symbols, &icons, width, dot)
+            })
+            .await?;
+            courier.notify(UserEvent::Styled(figure));
+            Ok(())
+        });
+    }
+
+    /// The `load_theme` method resolves the `[theme]` table of the [`Settings`] into the
+    /// [`Palette`] of every window, asking the system for its contrast setting if the table
+    /// leaves it to the system, as described in [`crate::Contrast`].
+    #[tracing::instrument(skip_all)]
+    pub fn load_theme(&mut self) {
+        let theme = self.settings.theme();
+        self.palette = theme.contrast().palette().sized(*theme.large());
+        tracing::trace!("High contrast: {}.", self.palette.high());
+        let scheme = self.settings.choropleth().ramp();
+        if !scheme.safe() {
+            tracing::warn!(
+                "The {} scheme is hard to read with color blindness.  Try {DEFAULT_SCHEME}.",
+                scheme.name()
+            );
+        }
+    }
+
+    /// The `sources` method returns the name and text of each shader to run over the map, in
+    /// order: those of the [`Palette`], those of the `[effects]` table, then the simulation of
+    /// the [`Vision`], if any, which sees everything the others drew.
+    pub fn sources(&self) -> Vec<(String, String)> {
+        let mut sources = self.palette.shaders();
+        sources.extend(self.settings.effects().sources(&self.home.config()));
+        sources.extend(
+            self.vision
+                .map(|vision| (vision.to_string(), vision.shader())),
+        );
+        sources
+    }
+
+    /// The `next_tool` method chooses the next [`crate::Tool`] for presses on the map, with
+    /// [`Plugins::next_tool`], or goes back to identifying after the last.
+    pub fn next_tool(&mut self) {
+        match self.plugins.next_tool() {
+            Some(name) => tracing::info!("Tool: {name}."),
+            None => tracing::info!("Tool: identify."),
+        }
+    }
+
+    /// The `press` method answers a press on the map of the window with id `id`, at `longitude`
+    /// and `latitude`, with the active [`crate::Tool`], over the datasets open in the app, and
+    /// shows what it has to say in a notice.  With no tool chosen, we identify what lies there,
+    /// which means logging the place and, when the [`Catalog`] has a parcel layer, looking up
+    /// the parcel under it with [`App::look_up`].
+    ///
+    /// Will fail as the tool does.
+    pub fn press(&mut self, id: &window::WindowId, longitude: f64, latitude: f64) -> Arrive<()> {
+        let Some(tool) = self.plugins.tool() else {
+            tracing::info!("Identify at {longitude:.6}, {latitude:.6}");
+            if let Err(Blame::Excuse {
+                source: crate::Excuse::NoService,
+                ..
+            }) = Parcels::new(self.settings.lookup(), &self.catalog)
+            {
+                tracing::debug!("No parcel layer in the catalog, so no parcel to identify.");
+                return Ok(());
+            }
+            return self.look_up(id, Query::Point(longitude, latitude));
+        };
+        let name = tool.name().to_string();
+        let said = tool
+            .press(longitude, latitude, &self.datasets)
+            .with_context(|| format!("running the {name} tool"))?;
+        if let Some(said) = said {
+            self.notify(Panel::from_text(name, &said));
+        }
+        Ok(())
+    }
+
+    /// The `cycle_vision` method moves on to the next [`Vision`] with [`Vision::next`], runs it
+    /// over the map in every window, and logs the colors of the choropleth scheme as the vision
+    /// sees them, as described in [`crate::Scheme`].
+    pub fn cycle_vision(&mut self) {
+        self.vision = Vision::next(self.vision);
+        let sources = self.sources();
+        let animate = *self.settings.effects().animate();
+        for lens in self.windows.values_mut() {
+            lens.shade(&sources, animate);
+        }
+        let choropleth = self.settings.choropleth();
+        let scheme = choropleth.ramp();
+        let classes = *choropleth.classes();
+        tracing::info!(
+            "The {} scheme: {}",
+            scheme.name(),
+            scheme.swatch(classes, None)
+        );
+        match self.vision {
+            Some(vision) => tracing::info!(
+                "Seeing the map with {vision}: {}",
+                scheme.swatch(classes, Some(vision))
+            ),
+            None => tracing::info!("Seeing the map with normal vision."),
+        }
+    }
+
+    /// The `toggle_large` method turns the large-UI mode of the [`Palette`] on or off in every
+    /// window, as described in [`crate::Palette`], and styles the open data files again at the
+    /// new scale.  The `large` key of the `[theme]` table takes over again when the config
+    /// reloads.
+    pub fn toggle_large(&mut self) {
+        self.palette = self.palette.sized(!self.palette.large());
+        for lens in self.windows.values_mut() {
+            lens.with_palette(self.palette);
+            lens.invalidate_chrome();
+        }
+        self.datasets
+            .iter()
+            .for_each(|dataset| self.style(dataset.clone()));
+        match self.palette.large() {
+            true => tracing::info!("Large UI on, at {}x.", self.palette.scale()),
+            false => tracing::info!("Large UI off."),
+        }
+    }
+
+    /// The `outline` method returns what the [`Access`] of each window describes beyond the
+    /// window itself: the acts of the groups not disabled, and a name for each open data file.
+    fn outline(&self) -> (Vec<Act>, Vec<String>) {
+        let acts = Act::iter()
+            .filter(|act| !self.disabled.contains(&act.group()))
+            .collect();
+        let layers = self
+            .datasets
+            .iter()
+            .map(|dataset| {
+                let name = dataset
+                    .path()
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| dataset.path().display().to_string());
+                format!("{name}, {} table", dataset.format())
+            })
+            .collect();
+        (acts, layers)
+    }
+
+    /// The `describe` method sends the tree of the window with id `id` to assistive
+    /// technology, if any is listening, with [`Lens::describe`].
+    pub fn describe(&mut self, id: &window::WindowId) {
+        let (acts, layers) = self.outline();
+        if let Some(lens) = self.windows.get_mut(id) {
+            lens.describe(&acts, &layers);
+        }
+    }
+
+    /// The `describe_view` method sums up the view of the map in the window with id `id` as a
+    /// [`Synopsis`], over the datasets open in the app, then hands it to the [`Access`] of the
+    /// window, shows it in a notice with [`App::notify`] and copies it to the [`Clipboard`].  A
+    /// window without a map has no view to describe.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the text cannot be copied, after the notice and the
+    /// screen reader have it.
+    #[tracing::instrument(skip(self))]
+    pub fn describe_view(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(lens) = self.windows.get_mut(id) else {
+            return Ok(());
+        };
+        let Some(extent) = lens.map().as_ref().and_then(Map::extent) else {
+            tracing::info!("No map in this window to describe.");
+            return Ok(());
+        };
+        let synopsis = Synopsis::survey(&extent, &self.datasets, self.settings.summary());
+        let text = synopsis.to_string();
+        lens.with_synopsis(text.clone());
+        self.describe(id);
+        self.notify(Panel::from_text("In view", &text));
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        match &mut self.clipboard {
+            Some(clipboard) => clipboard
+                .copy(&text)
+                .context("copying the description of the view"),
+            None => Ok(()),
+        }
+    }
+
+    /// The `save_bookmark` method saves the view of the map in the window with id `id` to the
+    /// [`Store`], named after the place in view as described in [`Synopsis`].  Saving the same
+    /// place again adds another bookmark of the same name, which takes its own turn.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the bookmark cannot be written.
+    pub fn save_bookmark(&self, id: &window::WindowId) -> Arrive<()> {
+        let Some(store) = &self.store else {
+            tracing::info!("No store to keep bookmarks in.");
+            return Ok(());
+        };
+        let Some(extent) = self
+            .windows
+            .get(id)
+            .and_then(|lens| lens.map().as_ref().and_then(Map::extent))
+        else {
+            tracing::info!("No map in this window to bookmark.");
+            return Ok(());
+        };
+        let name = Synopsis::survey(&extent, &[], self.settings.summary())
+            .place()
+            .clone();
+        store
+            .save_bookmark(&name, &extent)
+            .with_context(|| format!("saving the bookmark {name}"))?;
+        tracing::info!("Bookmarked {name}.");
+        Ok(())
+    }
+
+    /// The `next_bookmark` method moves the map in the window with id `id` to the bookmark
+    /// visited longest ago, as described in [`Store::visit`], so that pressing it again and again
+    /// goes round every bookmark.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the bookmarks cannot be read.
+    pub fn next_bookmark(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(store) = &self.store else {
+            tracing::info!("No store to keep bookmarks in.");
+            return Ok(());
+        };
+        let Some(lens) = self.windows.get_mut(id) else {
+            return Ok(());
+        };
+        let Some(map) = lens.map().as_ref() else {
+            tracing::info!("No map in this window to move.");
+            return Ok(());
+        };
+        let Some((name, extent)) = store.visit().context("visiting a bookmark")? else {
+            tracing::info!("No bookmarks yet.");
+            return Ok(());
+        };
+        let size = lens.window().inner_size();
+        map.frame(&extent, size.width, size.height);
+        lens.invalidate();
+        tracing::info!("Moved to {name}.");
+        Ok(())
+    }
+
+    /// The `copy_permalink` method copies a link to the view of the map in the window with id
+    /// `id` to the clipboard, and logs it, as described in [`Permalink`].  A table read from a
+    /// file the [`Catalog`] wrote goes in the link by the name of its layer, and any other by
+    /// its path.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the link cannot be copied.
+    pub fn copy_permalink(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(lens) = self.windows.get(id) else {
+            return Ok(());
+        };
+        let Some(extent) = lens.map().as_ref().and_then(Map::extent) else {
+            tracing::info!("No map in this window to share.");
+            return Ok(());
+        };
+        let width = lens.window().inner_size().width;
+        let dir = self.home.data().join(CATALOG_DIR);
+        let mut layers = Vec::new();
+        let mut catalog = Vec::new();
+        for dataset in &self.datasets {
+            let listing = self
+                .catalog
+                .listings()
+                .iter()
+                .find(|listing| *dataset.path() == dir.join(listing.file()));
+            match listing {
+                Some(listing) => catalog.push(listing.name().clone()),
+                None => layers.push(dataset.path().clone()),
+            }
+        }
+        let link = Permalink::from_view(&extent, width, layers, catalog, self.query.clone());
+        let text = link.to_string();
+        tracing::info!("{text}");
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        match &mut self.clipboard {
+            Some(clipboard) => clipboard
+                .copy(&text)
+                .context("copying the link to the view"),
+            None => Ok(()),
+        }
+    }
+
+    /// The `save_workspace` method writes the data files open in the app and the view of the map
+    /// in the window with id `id` to a new [`Workspace`] file under the [`WORKSPACE_DIR`]
+    /// directory of the data directory, and tells the user where, to pass to `--workspace` or
+    /// the `workspace` value of the config.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be written, and fails as [`Workspace::save`]
+    /// does.
+    pub fn save_workspace(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(lens) = self.windows.get(id) else {
+            return Ok(());
+        };
+        let Some(extent) = lens.map().as_ref().and_then(Map::extent) else {
+            tracing::info!("No map in this window to save.");
+            return Ok(());
+        };
+        let width = lens.window().inner_size().width;
+        let open = self
+            .datasets
+            .iter()
+            .map(|dataset| dataset.path().clone())
+            .collect();
+        let workspace = Workspace::from_view(&extent, width, open);
+        let dir = self.home.data().join(WORKSPACE_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let stamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        let path = dir.join(format!("workspace-{stamp}.{WORKSPACE_EXTENSION}"));
+        workspace.save(&path)?;
+        self.notify(Panel::new(
+            "Saved the workspace",
+            vec![path.display().to_string()],
+        ));
+        Ok(())
+    }
+
+    /// The `open_paths` method opens the files at `paths`, handed over by the desktop once the
+    /// app is running, as described in [`crate::Finder`].  A workspace, told apart by
+    /// [`Workspace::is_workspace`], opens in the focused window with [`App::open_workspace`], and
+    /// any other file opens as a data file with [`App::open_file`].
+    pub fn open_paths(&mut self, paths: Vec<std::path::PathBuf>) {
+        let id = self.windows.keys().next().map(|first| self.target(first));
+        for path in paths {
+            if !Workspace::is_workspace(&path) {
+                tracing::info!("Opening {}.", path.display());
+                self.open_file(path);
+                continue;
+            }
+            if let Err(e) = self.open_workspace(id, &path) {
+                self.warn(Panel::new(
+                    "Cannot restore the workspace",
+                    vec![path.display().to_string(), e.report()],
+                ));
+            }
+        }
+    }
+
+    /// The `open_workspace` method opens the data files of the [`Workspace`] in the file at
+    /// `path`, and moves the map in the window with id `id`, if any, to its view, with
+    /// [`App::open_permalink`].  Without a window or a view, the files open on their own.
+    ///
+    /// Will fail as [`Workspace::read`] does if the file is not a workspace.
+    pub fn open_workspace(
+        &mut self,
+        id: Option<window::WindowId>,
+        path: &std::path::Path,
+    ) -> Arrive<()> {
+        let workspace = Workspace::read(path)?;
+        if let (Some(id), Some(permalink)) = (id, workspace.permalink()) {
+            return self.open_permalink(&id, &permalink);
+        }
+        for file in workspace.open() {
+            match file.exists() {
+                true => self.open_file(file.clone()),
+                false => tracing::warn!("Cannot open {}: not on this machine.", file.display()),
+            }
+        }
+        Ok(())
+    }
+
+    /// The `open_permalink_clipboard` method opens the link to a view on the clipboard in the
+    /// window with id `id`, with [`App::open_permalink`].
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the clipboard holds no text, and fails as
+    /// [`Permalink::parse`] does if the text is not a link to a view.
+    pub fn open_permalink_clipboard(&mut self, id: &window::WindowId) -> Arrive<()> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return Ok(());
+        };
+        let text = clipboard.paste().context("pasting from the clipboard")?;
+        let permalink = Permalink::parse(&text)?;
+        self.open_permalink(id, &permalink)
+    }
+
+    /// The `open_permalink` method moves the map in the window with id `id` to the view of
+    /// `permalink`, opens the tables it names that are not open yet, and looks up its parcel
+    /// again, as described in [`Permalink`].
+    ///
+    /// Will fail as [`App::look_up`] does.
+    pub fn open_permalink(&mut self, id: &window::WindowId, permalink: &Permalink) -> Arrive<()> {
+        let Some(lens) = self.windows.get_mut(id) else {
+            return Ok(());
+        };
+        let Some(map) = lens.map().as_ref() else {
+            tracing::info!("No map in this window to open the link in.");
+            return Ok(());
+        };
+        let size = lens.window().inner_size();
+        map.frame(
+            &permalink.extent(size.width, size.height),
+            size.width,
+            size.height,
+        );
+        lens.invalidate();
+        tracing::info!(
+            "Moved to {:.6}, {:.6} at zoom {:.2}.",
+            permalink.longitude(),
+            permalink.latitude(),
+            permalink.zoom()
+        );
+        let open = self
+            .datasets
+            .iter()
+            .map(|dataset| dataset.path().clone())
+            .collect::<HashSet<std::path::PathBuf>>();
+        for path in permalink.layers() {
+            if open.contains(path) {
+                continue;
+            }
+            match path.exists() {
+                true => self.open_file(path.clone()),
+                false => tracing::warn!("Cannot open {}: not on this machine.", path.display()),
+            }
+        }
+        let dir = self.home.data().join(CATALOG_DIR);
+        for name in permalink.catalog() {
+            let listing = self
+                .catalog
+                .listings()
+                .iter()
+                .find(|listing| listing.name() == name)
+                .cloned();
+            match listing {
+                Some(listing) if open.contains(&dir.join(listing.file())) => {}
+                Some(listing) => self.add_listing(id, listing),
+                None => tracing::warn!("The data catalog has no layer named {name}."),
+            }
+        }
+        match permalink.query() {
+            Some(query) => self.look_up(id, query.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// The `look_up_clipboard` method looks up the parcel for the address or map and tax lot
+    /// number on the clipboard, with [`App::look_up`], for the window with id `id`.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the clipboard holds no text, and fails as
+    /// [`App::look_up`] does.
+    pub fn look_up_clipboard(&mut self, id: &window::WindowId) -> Arrive<()> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return Ok(());
+        };
+        let text = clipboard.paste().context("pasting from the clipboard")?;
+        match Query::parse(&text) {
+            Some(query) => self.look_up(id, query),
+            None => {
+                tracing::info!("Nothing on the clipboard to look up.");
+                Ok(())
+            }
+        }
+    }
+
+    /// The `look_up` method starts an interactive job of the [`Tasks`] registry that looks up
+    /// the parcel for `query` with [`Parcels::find`], as described in its docs.  The parcel
+    /// arrives as a [`UserEvent::Found`] for the window with id `id`, where the `query` becomes
+    /// the last lookup for a [`Permalink`], and errors arrive through the [`Courier`].
+    ///
+    /// Will [`crate::Excuse::NoService`] if the [`Catalog`] has no parcel layer to ask.
+    pub fn look_up(&mut self, id: &window::WindowId, query: Query) -> Arrive<()> {
+        let parcels = Parcels::new(self.settings.lookup(), &self.catalog)?;
+        let courier = self.courier();
+        let id = *id;
+        self.tasks.spawn(
+            "Looking up a parcel",
+            Class::Interactive,
+            move |_| async move {
+                let parcel = parcels.find(query.clone()).await?;
+                courier.notify(UserEvent::Found(id, query, parcel));
+                Ok(())
+            },
+        );
+        Ok(())
+    }
+
+    /// The `next_lookup` method looks up again the saved query asked longest ago, as described
+    /// in [`Store::next_query`], for the window with id `id`, so that pressing it again and again
+    /// goes round every saved lookup.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the queries cannot be read, and fails as
+    /// [`App::look_up`] does.
+    pub fn next_lookup(&mut self, id: &window::WindowId) -> Arrive<()> {
+        let Some(store) = &self.store else {
+            tracing::info!("No store to keep lookups in.");
+            return Ok(());
+        };
+        let Some(query) = store.next_query().context("reading the saved lookups")? else {
+            tracing::info!("No saved lookups yet.");
+            return Ok(());
+        };
+        self.look_up(id, query)
+    }
+
+    /// The `show_parcel` method shows the lines of `parcel` in a notice, as described in
+    /// [`Parcels`], and highlights it on the map of the window with id `id`, moving the map to
+    /// show it.
+    ///
+    /// Will fail as [`Sketch::new`] does if the outline of the parcel is not in longitude and
+    /// latitude.
+    pub fn show_parcel(&mut self, id: &window::WindowId, parcel: Parcel) -> Arrive<()> {
+        self.notify(Panel::new("Parcel", parcel.lines(self.settings.lookup())));
+        let sketch = Sketch::new(parcel.geometry().clone()).context("highlighting the parcel")?;
+        if let Some(lens) = self.windows.get_mut(id) {
+            lens.select(vec![sketch]);
+        }
+        Ok(())
+    }
+
+    /// The `paste_geometry` method reads WKT or GeoJSON from the clipboard onto the annotations
+    /// of the window with id `id`, and moves the map to show it, as described in [`Sketch`].
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the clipboard holds no text, and fails as
+    /// [`Sketch::parse`] does if the text holds no geometry.
+    pub fn paste_geometry(&mut self, id: &window::WindowId) -> Arrive<()> {
+        if !self.windows.contains_key(id) {
+            return Ok(());
+        }
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return Ok(());
+        };
+        let text = clipboard.paste().context("pasting from the clipboard")?;
+        let sketches = Sketch::parse(&text).context("reading the pasted geometry")?;
+        if let Some(lens) = self.windows.get_mut(id) {
+            lens.sketch(sketches);
+        }
+        Ok(())
+    }
+
+    /// The `describe_all` method sends the tree of every window to assistive technology, after
+    /// a change to the acts or layers it describes.
+    pub fn describe_all(&mut self) {
+        let (acts, layers) = self.outline();
+        for lens in self.windows.values_mut() {
+            lens.describe(&acts, &layers);
+        }
+    }
+
+    /// The `accessibility_event` method answers `event` from the [`accesskit_winit`] adapter of
+    /// a window, as described in the [`Access`] docs.  A request for the tree gets the tree, a
+    /// move of focus moves the focus, and any other action runs the [`Act`] it stands for in
+    /// the window, through [`App::act`], so disabled groups stay disabled.
+    #[tracing::instrument(skip_all)]
+    pub fn accessibility_event(
+        &mut self,
+        event: accesskit_winit::Event,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) {
+        let id = event.window_id;
+        match event.window_event {
+            accesskit_winit::WindowEvent::InitialTreeRequested => {
+                tracing::trace!("Tree requested for {id:?}.");
+                self.describe(&id);
+            }
+            accesskit_winit::WindowEvent::ActionRequested(request) => {
+                tracing::trace!("Accessibility action {:?} on {:?}.", request.action, id);
+                if request.action == accesskit::Action::Focus {
+                    let (acts, layers) = self.outline();
+                    if let (Some(lens), Some(stop)) =
+                        (self.windows.get_mut(&id), Stop::from_node(request.target))
+                    {
+                        lens.land(stop, &acts, &layers);
+                    }
+                    return;
+                }
+                if let Some(act) = Access::act(&request) {
+                    if let Err(e) = self.act(&act, &id, event_loop) {
+                        self.report_error(e, event_loop);
+                    }
+                }
+            }
+            accesskit_winit::WindowEvent::AccessibilityDeactivated => {
+                tracing::trace!("Accessibility off for {id:?}.");
+            }
+        }
+    }
+
+    /// The `load_log_level` method swaps in the `log_level` from the [`Settings`] as the log
+    /// filter, behind the per-module filters of the `[logging]` table, using [`Verbosity::set`].
+    /// Logging starts with the level from the config, so we only need this when a reload changes
+    /// it.  Without a level, we keep the rest of the filter in use.
+    #[tracing::instrument(skip_all)]
+    pub fn load_log_level(&mut self) {
+        let verbosity = Verbosity::global();
+        let base = match self.settings.startup().log_level() {
+            Some(level) => level.clone(),
+            None => std::env::var(tracing_subscriber::EnvFilter::DEFAULT_ENV)
+                .unwrap_or_else(|_| LOG_LEVEL.to_string()),
+        };
+        if let Err(e) = verbosity.set(&self.settings.logging().merge(&base)) {
+            tracing::warn!("Keeping the old log level: {e}");
+        }
+    }
+
+    /// The `reload_config` method reads the config again and applies it to the running app, so
+    /// that changes take effect without a restart.  We redo each of the `load_` steps from
+    /// [`App::new`] except for controllers, which do not depend on the config.  The old hotkeys
+    /// go first, so their registrations are out of the way of the new ones.  A new `log_level`
+    /// or `[logging]` table takes effect through [`App::load_log_level`].  A new `[map]` table
+    /// lets go of the shared [`Tiles`] layer, so the next map window builds one from the new
+    /// table, while open windows keep the layer they have.  A new `[choropleth]`, `[symbols]` or
+    /// `[theme]` table styles the open data files again, with [`App::style`].
+    #[tracing::instrument(skip_all)]
+    pub fn reload_config(&mut self) {
+        self.hotkeys = None;
+        self.diagnostics.clear();
+        self.save_reporter();
+        let level = self.settings.startup().log_level().clone();
+        let logging = self.settings.logging().clone();
+        let basemap = self.settings.map().clone();
+        let choropleth = self.settings.choropleth().clone();
+        let symbols = self.settings.symbols().clone();
+        let theme = self.settings.theme().clone();
+        self.load_config();
+        if *self.settings.map() != basemap {
+            self.tiles = None;
+        }
+        let restyle = *self.settings.choropleth() != choropleth
+            || *self.settings.symbols() != symbols
+            || *self.settings.theme() != theme;
+        if *self.settings.startup().log_level() != level || *self.settings.logging() != logging {
+            self.load_log_level();
+        }
+        self.reporter = Reporter::new(self.settings.telemetry().clone(), &self.home);
+        self.load_cmds();
+        self.load_scroll();
+        self.load_hotkeys();
+        self.load_groups();
+        self.load_symbols();
+        self.load_theme();
+        if restyle {
+            self.datasets
+                .iter()
+                .for_each(|dataset| self.style(dataset.clone()));
+        }
+        self.load_catalog();
+        self.describe_all();
+        self.tasks.configure(self.settings.tasks());
+        self.agents.start(&self.settings);
+        let graphics = self.settings.graphics().clone();
+        let sources = self.sources();
+        let animate = *self.settings.effects().animate();
+        for lens in self.windows.values_mut() {
+            lens.with_palette(self.palette);
+            lens.adopt(&graphics);
+            lens.shade(&sources, animate);
+        }
+        tracing::info!("Config reloaded.");
+    }
+
+    /// The `save_editor` method saves the config text in `editor` with [`Editor::save`], warns
+    /// about any problems the save let through, and reloads the config with
+    /// [`App::reload_config`].
+    ///
+    /// Will [`crate::Excuse::ConfigInvalid`] if the text does not parse, in which case neither
+    /// the file nor the running app changes.
+    #[tracing::instrument(skip_all)]
+    pub fn save_editor(&mut self, editor: &mut Editor) -> Arrive<()> {
+        let diagnostics = editor.save()?;
+        if !diagnostics.is_empty() {
+            let text = diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("\n");
+            self.warn(Panel::from_text("Problems in the saved config", &text));
+        }
+        self.reload_config();
+        Ok(())
+    }
+
+    /// The `paste_config` method opens the config file from [`Home::config`] in an [`Editor`],
+    /// replaces its text with the TOML on the clipboard, and saves it with
+    /// [`App::save_editor`].  The user edits the text in whatever editor they like, copies it,
+    /// and pastes it here, without having to find the file.
     ///
-    /// If the build fails, we fall back on a default that happens to be exactly the same as my
-    /// current `Tardy.toml`.  The current method has some drawbacks.  The default fallback would
-    /// get onerous if I had more than two actions to worry about.  Also, I resort to unwrapping
-    /// the default build, which will crash my program if it panics for some reason.
+    /// Will [`crate::Blame::Clipboard`] if the clipboard holds no text, and fails as
+    /// [`Editor::open`] and [`App::save_editor`] do.
+    pub fn paste_config(&mut self) -> Arrive<()> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return Ok(());
+        };
+        let text = clipboard.paste().context("pasting from the clipboard")?;
+        let mut editor = Editor::open(&self.home.config())?;
+        editor.edit(text);
+        if !editor.is_dirty() {
+            tracing::info!("The config on the clipboard matches the file.");
+            return Ok(());
+        }
+        self.save_editor(&mut editor)
+    }
+
+    /// The `open_startup` method handles the `open` and `workspace` values from the [`Settings`],
+    /// which the arguments of [`Cli`] override, including the files the desktop passes when the
+    /// user opens one with the app, as described in the [`Cli`] docs.  Each data file opens with
+    /// [`App::open_file`], once the first window is up, so its table and any error land in that
+    /// window, and a link to a view from [`Cli::permalink`] opens in that window with
+    /// [`App::open_permalink`].  The workspace opens last, with [`App::open_workspace`], so its
+    /// view wins over the view of a link.
     #[tracing::instrument(skip_all)]
-    pub fn load_config(&mut self) {
-        if let Ok(config) = config::Config::builder()
-            .add_source(config::File::with_name("config"))
-            .build()
-        {
-            self.config = config;
-            // Sanity check that the file read correctly.
-            tracing::trace!("Config set from file.");
-        } else {
-            // Warn me the user config couldn't be read.
-            tracing::warn!("Could not read config from file.");
-            let config = config::Config::builder();
-            let config = config.set_default("exit", "Escape").unwrap();
-            let config = config.set_default("new_window", "n").unwrap();
-            let config = config.build().unwrap();
-            self.config = config;
+    pub fn open_startup(&mut self) {
+        for path in self.settings.startup().open() {
+            tracing::info!("Opening {} on startup.", path.display());
+            self.open_file(path.clone());
+        }
+        if let Some(permalink) = self.cli.permalink() {
+            let first = self.windows.keys().next().copied();
+            let opened = permalink.and_then(|permalink| match first {
+                Some(id) => self.open_permalink(&id, &permalink),
+                None => Ok(()),
+            });
+            if let Err(e) = opened {
+                tracing::warn!("Cannot open the link on startup: {e}");
+            }
+        }
+        if let Some(workspace) = self.settings.startup().workspace().clone() {
+            let first = self.windows.keys().next().copied();
+            if let Err(e) = self.open_workspace(first, &workspace) {
+                self.warn(Panel::new(
+                    "Cannot restore the workspace",
+                    vec![workspace.display().to_string(), e.report()],
+                ));
+            }
         }
+    }
 
-        // Read the config to make sure its correct.
-        tracing::trace!("{:#?}", self.config);
+    /// The `headless` method returns `true` if the config or the `--headless` argument asks us to
+    /// run without windows.
+    pub fn headless(&self) -> bool {
+        *self.settings.startup().headless()
     }
 
-    /// Keys and values play reversed roles in the [`Cmd`] and [`config::Config`] structs.  Here we
-    /// convert one to the other using the [`Cmd::from`] implementation.
-    /// Failure to read any commands from the config will produce an empty [`Cmd`], which will
-    /// restrict the user to mouse interactions.
+    /// The `hotkey` method dispatches the [`Act`] of a global hotkey from the [`Hotkeys`] to
+    /// [`App::act`], targeting the focused window or any window if none has focus.  Since the app
+    /// may be in the background when a hotkey arrives, there is often no focused window.
     #[tracing::instrument(skip_all)]
-    pub fn load_cmds(&mut self) {
-        let cmd = Cmd::from(&self.config);
-        self.cmd = cmd;
-        tracing::trace!("Commands read from config.");
-        // Do you see the commands you expected?
-        tracing::trace!("{:?}", self.cmd);
+    pub fn hotkey(&mut self, act: &Act, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(fallback) = self.windows.keys().next().copied() else {
+            return;
+        };
+        let target = self.target(&fallback);
+        if let Err(e) = self.act(act, &target, event_loop) {
+            self.report_error(e, event_loop);
+        }
+    }
+
+    /// The `toggle_visible` method hides every window, or shows them again if they are hidden.
+    /// When showing the windows, we also ask the window `id` for focus, so the app comes to the
+    /// front.
+    #[tracing::instrument(skip_all)]
+    pub fn toggle_visible(&mut self, id: &window::WindowId) {
+        self.hidden = !self.hidden;
+        tracing::info!("Windows {}.", if self.hidden { "hidden" } else { "shown" });
+        for lens in self.windows.values() {
+            lens.window().set_visible(!self.hidden);
+        }
+        if !self.hidden {
+            if let Some(lens) = self.windows.get(id) {
+                lens.window().focus_window();
+            }
+        }
+    }
+
+    /// The `poll_pad` method dispatches any [`Act`] variants waiting in the [`Pad`] to
+    /// [`App::act`].  Controllers do not belong to a window, so the acts apply to the focused
+    /// window, or to any window if none has focus.
+    #[tracing::instrument(skip_all)]
+    pub fn poll_pad(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let acts = match self.pad.as_mut() {
+            Some(pad) => pad.poll(),
+            None => return,
+        };
+        for act in acts {
+            let fallback = match self.windows.keys().next() {
+                Some(id) => *id,
+                None => return,
+            };
+            let target = self.target(&fallback);
+            if let Err(e) = self.act(&act, &target, event_loop) {
+                self.report_error(e, event_loop);
+            }
+        }
+    }
+
+    /// The `next_window` method moves focus from the window `id` to the next window, in the
+    /// order of [`window::WindowId`], wrapping around after the last window.
+    #[tracing::instrument(skip_all)]
+    pub fn next_window(&self, id: &window::WindowId) {
+        let mut ids = self
+            .windows
+            .keys()
+            .copied()
+            .collect::<Vec<window::WindowId>>();
+        ids.sort();
+        let next = ids
+            .iter()
+            .position(|other| other == id)
+            .map(|idx| ids[(idx + 1) % ids.len()]);
+        if let Some(lens) = next.and_then(|next| self.windows.get(&next)) {
+            lens.window().focus_window();
+        }
+    }
+
+    /// The `bind` method changes the key binding for `act` to `chord` at runtime, then writes
+    /// the updated bindings back to the user config file from [`Home::config`] using
+    /// [`Cmd::save`], so the user no longer has to edit the file and restart to change a
+    /// binding.  The keybinding editor from [`App::rebind`] and [`App::bind_clipboard`] both
+    /// come through here.
+    ///
+    /// Will [`crate::Blame::Io`] or one of the toml variants of [`crate::Blame`] if
+    /// [`Cmd::save`] fails.  The new binding takes effect either way.
+    #[tracing::instrument(skip(self))]
+    pub fn bind(&mut self, act: &Act, chord: crate::Chord) -> Arrive<()> {
+        if let Some(previous) = self.cmd.bind(act, chord) {
+            if &previous != act {
+                tracing::warn!("Unbound {}", previous.title());
+            }
+        }
+        self.cmd.save(&self.home.config())
+    }
+
+    /// The `edit_bindings` method opens the keybinding editor, as described in [`Rebind`], over
+    /// every map.
+    pub fn edit_bindings(&mut self) {
+        let rebind = Rebind::new();
+        self.post_notice(Some(rebind.panel(&self.cmd)));
+        self.rebind = Some(rebind);
+    }
+
+    /// The `rebind` method hands the key press in `event` to the open keybinding editor, binds
+    /// the keys the user chose with [`App::bind`], and draws the editor again, or puts it away
+    /// when the user closes it.
+    ///
+    /// Fails as [`App::bind`] does, with the editor still open.
+    pub fn rebind(&mut self, event: &event::KeyEvent) -> Arrive<()> {
+        let Some(rebind) = &mut self.rebind else {
+            return Ok(());
+        };
+        let bound = match rebind.key(event, self.modifiers.state()) {
+            Edit::Stay => Ok(()),
+            Edit::Bind(act, chord) => self.bind(&act, chord),
+            Edit::Close => {
+                self.rebind = None;
+                self.post_notice(None);
+                return Ok(());
+            }
+        };
+        if let Some(rebind) = &self.rebind {
+            self.post_notice(Some(rebind.panel(&self.cmd)));
+        }
+        bound
+    }
+
+    /// The `bind_clipboard` method reads a line of the config from the clipboard, as in
+    /// `zoom_in = "Ctrl+Up"`, and binds the act it names to the keys it gives with [`App::bind`].
+    /// Text that names no act goes to the log, and changes nothing.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the clipboard holds no text, fails as
+    /// [`crate::Chord`] does if the keys do not parse, and as [`App::bind`] does after that.
+    pub fn bind_clipboard(&mut self) -> Arrive<()> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("opening the clipboard")?);
+        }
+        let Some(clipboard) = &mut self.clipboard else {
+            return Ok(());
+        };
+        let text = clipboard.paste().context("pasting from the clipboard")?;
+        let Some((name, keys)) = text.trim().split_once('=') else {
+            tracing::info!("No binding on the clipboard, like zoom_in = \"Ctrl+Up\".");
+            return Ok(());
+        };
+        let name = name.trim();
+        let Some(act) = Act::iter().find(|act| act.snake() == name) else {
+            tracing::info!("No act named {name}.");
+            return Ok(());
+        };
+        let chord = keys
+            .trim()
+            .trim_matches('"')
+            .parse::<crate::Chord>()
+            .with_context(|| format!("reading the keys for {name}"))?;
+        self.bind(&act, chord)
     }
 
     /// The act method dispatches program responses based upon the variant of [`Act`] passed in the
@@ -177,6 +2256,14 @@ impl App {
     ///
     /// We match on `act` and dispatch to the appropriate handler, before returning `Ok`.
     /// Will [`crate::Blame::EventLoop`] if [`App::create_window`] fails.
+    ///
+    /// After dispatching an act where [`Act::repeatable`] is `true`, we store it in the `last`
+    /// field.  An [`Act::RepeatLast`] dispatches the stored act again, on the window given by
+    /// `id` rather than the window where it first happened.
+    ///
+    /// Acts whose [`Group`] appears in the `disabled` field do nothing, and do not replace the
+    /// stored act in `last`.  Since every source of input ends up here, this is the one place we
+    /// need to check.
     #[tracing::instrument(skip_all)]
     pub fn act(
         &mut self,
@@ -184,10 +2271,14 @@ impl App {
         id: &window::WindowId,
         event_loop: &event_loop::ActiveEventLoop,
     ) -> Arrive<()> {
-        match act {
+        if self.disabled.contains(&act.group()) {
+            tracing::info!("{} is disabled in this deployment.", act.title());
+            return Ok(());
+        }
+        let result = match act {
             Act::CloseWindow => {
                 tracing::info!("Closing window.");
-                let _ = self.windows.remove(id);
+                self.close_window(id);
                 Ok(())
             }
             Act::Exit => {
@@ -195,20 +2286,258 @@ impl App {
                 self.windows.clear();
                 Ok(())
             }
-            Act::NewWindow => self.create_window(event_loop, None),
+            Act::NewWindow => self.create_window(event_loop, Role::Map, None),
+            Act::NewTableWindow => self.create_window(event_loop, Role::Table, None),
+            Act::NewChartWindow => self.create_window(event_loop, Role::Chart, None),
+            Act::NewLogWindow => self.create_window(event_loop, Role::Log, None),
+            Act::ReopenClosedWindow => self.reopen_window(event_loop),
+            Act::ZoomIn => self.nudge(id, Glide::Zoom(1.0 / ZOOM_STEP)),
+            Act::ZoomOut => self.nudge(id, Glide::Zoom(ZOOM_STEP)),
+            Act::PanUp => self.nudge(id, Glide::Pan(0.0, PAN_STEP)),
+            Act::PanDown => self.nudge(id, Glide::Pan(0.0, -PAN_STEP)),
+            Act::PanLeft => self.nudge(id, Glide::Pan(PAN_STEP, 0.0)),
+            Act::PanRight => self.nudge(id, Glide::Pan(-PAN_STEP, 0.0)),
+            Act::ToggleVisible => {
+                self.toggle_visible(id);
+                Ok(())
+            }
+            Act::ToggleInk => {
+                if let Some(lens) = self.windows.get_mut(id) {
+                    lens.toggle_ink();
+                }
+                Ok(())
+            }
+            Act::DescribeView => self.describe_view(id),
+            Act::CycleVision => {
+                self.cycle_vision();
+                Ok(())
+            }
+            Act::NextTool => {
+                self.next_tool();
+                Ok(())
+            }
+            Act::ToggleLargeUi => {
+                self.toggle_large();
+                Ok(())
+            }
+            Act::ToggleDebugOverlay => {
+                if let Some(lens) = self.windows.get_mut(id) {
+                    lens.toggle_overlay();
+                }
+                Ok(())
+            }
+            Act::UndoInk => {
+                if let Some(lens) = self.windows.get_mut(id) {
+                    lens.undo_ink();
+                }
+                Ok(())
+            }
+            Act::PasteGeometry => self.paste_geometry(id),
+            Act::BindFromClipboard => self.bind_clipboard(),
+            Act::EditBindings => {
+                self.edit_bindings();
+                Ok(())
+            }
+            Act::PasteConfig => self.paste_config(),
+            Act::NextWindow => {
+                self.next_window(id);
+                Ok(())
+            }
+            Act::ReloadConfig => {
+                self.reload_config();
+                Ok(())
+            }
+            Act::TraceLogs => Verbosity::global().boost(env!("CARGO_PKG_NAME")),
+            Act::ResetLogs => Verbosity::global().reset(),
+            Act::ShowGpu => {
+                self.notify(Panel::from_text("Graphics", &self.gpu.to_string()));
+                Ok(())
+            }
+            Act::ShowSymbols => {
+                let scale = self.windows.get(id).map_or(1.0, Lens::symbol_scale);
+                let mut lines = Vec::new();
+                match &self.atlas {
+                    Some(atlas) => {
+                        lines.push(format!(
+                            "The symbol atlas is {}x{}.",
+                            atlas.image().width(),
+                            atlas.image().height()
+                        ));
+                        for name in atlas.names() {
+                            match atlas.sprite(name, scale) {
+                                Some((size, _)) => lines.push(format!("Icon: {name}, at {size}x")),
+                                None => lines.push(format!("Icon: {name}")),
+                            }
+                        }
+                    }
+                    None => lines.push("No symbol atlas loaded.".to_string()),
+                }
+                let symbols = self.settings.symbols();
+                for (value, icon) in symbols.icons() {
+                    lines.push(format!("{value} -> {icon}"));
+                }
+                for dataset in &self.datasets {
+                    let mut counts = BTreeMap::<Option<String>, usize>::new();
+                    for icon in symbols.classify(dataset) {
+                        *counts.entry(icon).or_default() += 1;
+                    }
+                    let name = dataset.path().display();
+                    for (icon, count) in counts {
+                        match icon {
+                            Some(icon) => lines.push(format!("{name}: {count} points as {icon}")),
+                            None => lines.push(format!("{name}: {count} points without an icon")),
+                        }
+                    }
+                }
+                self.notify(Panel::new("Symbols", lines));
+                Ok(())
+            }
+            Act::ShowNetwork => {
+                self.notify(Panel::from_text("Network", &Network::global().to_string()));
+                Ok(())
+            }
+            Act::ShowTasks => {
+                self.notify(Panel::from_text("Tasks", &self.tasks.to_string()));
+                Ok(())
+            }
+            Act::CancelTasks => {
+                self.tasks.cancel_all();
+                Ok(())
+            }
+            Act::LoadOffered => {
+                let offered = std::mem::take(&mut self.offered);
+                if offered.is_empty() {
+                    tracing::info!("No new data files to load.");
+                }
+                offered.into_iter().for_each(|path| self.open_file(path));
+                Ok(())
+            }
+            Act::SaveSnapshot => self.save_snapshot(id),
+            Act::ExportSelection => self.export_features(id, true, Format::GeoJson),
+            Act::ExportLayers => self.export_features(id, false, Format::GeoJson),
+            Act::ExportSelectionShapefile => self.export_features(id, true, Format::Shapefile),
+            Act::ExportLayersShapefile => self.export_features(id, false, Format::Shapefile),
+            Act::ExportSelectionParquet => self.export_features(id, true, Format::Parquet),
+            Act::ExportLayersParquet => self.export_features(id, false, Format::Parquet),
+            Act::ExportSelectionCsv => self.export_features(id, true, Format::Csv),
+            Act::ExportLayersCsv => self.export_features(id, false, Format::Csv),
+            Act::ExportSelectionXlsx => self.export_features(id, true, Format::Xlsx),
+            Act::ExportLayersXlsx => self.export_features(id, false, Format::Xlsx),
+            Act::ToggleRecording => self.toggle_recording(id),
+            Act::ShowAgents => {
+                self.notify(Panel::from_text("Agents", &self.agents.to_string()));
+                Ok(())
+            }
+            Act::ShowUsage => {
+                self.report_usage();
+                Ok(())
+            }
+            Act::ClearTileCache => self.clear_cache(Cache::Tiles),
+            Act::ClearResponseCache => self.clear_cache(Cache::Responses),
+            Act::SaveBookmark => self.save_bookmark(id),
+            Act::NextBookmark => self.next_bookmark(id),
+            Act::CopyPermalink => self.copy_permalink(id),
+            Act::OpenPermalink => self.open_permalink_clipboard(id),
+            Act::SaveWorkspace => self.save_workspace(id),
+            Act::ShowRecentFiles => self.report_recent(),
+            Act::ShowCatalog => {
+                self.notify(Panel::from_text("Data catalog", &self.catalog.to_string()));
+                Ok(())
+            }
+            Act::NextCatalogEntry => {
+                let text = match self.catalog.advance() {
+                    Some(listing) => listing.to_string(),
+                    None => "The data catalog is empty.".to_string(),
+                };
+                self.notify(Panel::from_text("Data catalog", &text));
+                Ok(())
+            }
+            Act::AddFromCatalog => {
+                self.add_from_catalog(id);
+                Ok(())
+            }
+            Act::LookUpParcel => self.look_up_clipboard(id),
+            Act::NextLookup => self.next_lookup(id),
+            Act::GeocodeAddresses => {
+                self.geocode_addresses();
+                Ok(())
+            }
+            Act::SearchImagery => {
+                self.search_imagery(id);
+                Ok(())
+            }
+            Act::ShowScene => {
+                self.show_scene(id);
+                Ok(())
+            }
+            Act::DownloadFromClipboard => self.download_clipboard(),
+            Act::RepeatLast => {
+                return match self.last.clone() {
+                    Some(last) => {
+                        tracing::trace!("Repeating {last}.");
+                        self.act(&last, id, event_loop)
+                    }
+                    None => {
+                        tracing::trace!("Nothing to repeat.");
+                        Ok(())
+                    }
+                };
+            }
             Act::Be => {
                 tracing::trace!("Taking it easy.");
                 Ok(())
             }
+        };
+        if act.repeatable() {
+            self.last = Some(act.clone());
+        }
+        result
+    }
+
+    /// The `nudge` method passes `glide` to [`Lens::nudge`] on the window `id`, for actions that
+    /// move the map from the keyboard or a controller.  Always returns `Ok`, so that it fits in
+    /// the match in [`App::act`].
+    fn nudge(&mut self, id: &window::WindowId, glide: Glide) -> Arrive<()> {
+        if let Some(lens) = self.windows.get_mut(id) {
+            lens.nudge(&glide);
+        }
+        Ok(())
+    }
+
+    /// The `act_counted` method dispatches `act` to [`App::act`] as many times as the count
+    /// prefix typed before the binding, taken from [`Cmd::take_count`].  Without a count, the act
+    /// happens once.  The target window comes from [`App::target`], using `id` as the fallback.
+    ///
+    /// Stops at the first error and returns it.
+    #[tracing::instrument(skip_all)]
+    pub fn act_counted(
+        &mut self,
+        act: &Act,
+        id: &window::WindowId,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) -> Arrive<()> {
+        let count = self.cmd.take_count();
+        tracing::trace!("Performing {act} {count} times.");
+        for _ in 0..count {
+            let target = self.target(id);
+            self.act(act, &target, event_loop)?;
         }
+        Ok(())
     }
 
     /// The `keyboard_input` method takes incoming keyboard presses and translates them to an [`Act`] variant using the [`Cmd::act`] method.
     /// If the key event passed in the `event` argument translates to a valid [`Act`], we pass it
     /// to the [`App::act`] method for handling.
     ///
+    /// While the keybinding editor is open, every press goes to [`App::rebind`] instead.
+    /// Releasing a key goes to [`Cmd::release`], which may finish a hold binding.  A press puts
+    /// away the notice showing over the map, and the status panel follows the count and the keys
+    /// pending in the [`Cmd`], with [`App::show_status`].
+    ///
     /// Takes a mutable reference to `self` to pass to [`App::act`].
-    /// The `id` parameter indicates the active window and gets passed to [`App::act`].
+    /// The `id` parameter indicates the window that received the key event.  We pass the focused
+    /// window from [`App::target`] to [`App::act`] instead, falling back to `id` when no window
+    /// reports focus.  Any count prefix applies through [`App::act_counted`].
     /// The `event_loop` parameter indicates the active event loop, and also gets passed to
     /// [`App::act`].
     /// Commits a `FauxPas` if [`App::act`] fails.
@@ -219,22 +2548,198 @@ impl App {
         event: &event::KeyEvent,
         event_loop: &event_loop::ActiveEventLoop,
     ) -> Arrive<()> {
+        // The keybinding editor takes every key while it is open.
+        if self.rebind.is_some() {
+            return match event.state.is_pressed() {
+                true => self.rebind(event),
+                false => Ok(()),
+            };
+        }
         // Dispatch actions only on press.
         if event.state.is_pressed() {
             // Tell me I at least pressed the right key.
             tracing::trace!("Press detected: {:#?}", event);
-            if let Some(act) = self.cmd.act(event) {
+            // A key press puts away the notice, before the act can raise another.
+            if self.notice.is_some() && !event.repeat {
+                self.post_notice(None);
+            }
+            let acted = self.cmd.act(event, &self.modifiers);
+            // The status panel follows the count and the keys pressed so far.
+            self.show_status(id);
+            if let Some(act) = acted {
                 // Helpful to know it triggered if the handler doesn't respond right.
                 tracing::trace!("Act detected: {act}");
-                self.act(&act, id, event_loop)?;
+                self.act_counted(&act, id, event_loop)?;
+            } else if self.cmd.count().is_some() || !self.cmd.pending().is_empty() {
+                tracing::trace!("Sequence pending: {}", self.cmd.pending());
+            } else if let Some(nav) = Nav::from_key(event, self.modifiers.state()) {
+                self.navigate(id, nav, event_loop)?;
             } else {
                 // No crime here.
                 tracing::trace!("Invalid key.");
             }
+        } else if let Some(act) = self.cmd.release(event) {
+            tracing::trace!("Act detected on release: {act}");
+            self.act_counted(&act, id, event_loop)?;
+        }
+        Ok(())
+    }
+
+    /// The `navigate` method moves the focus of the focused window by `nav`, from a key no
+    /// binding claims, and performs the [`Act`] the key asks for, if any, as described in the
+    /// [`crate::Focus`] docs.  Like [`App::keyboard_input`], we target the focused window from
+    /// [`App::target`], falling back to `id`.
+    pub fn navigate(
+        &mut self,
+        id: &window::WindowId,
+        nav: Nav,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) -> Arrive<()> {
+        let target = self.target(id);
+        let (acts, layers) = self.outline();
+        let Some(lens) = self.windows.get_mut(&target) else {
+            return Ok(());
+        };
+        match lens.navigate(nav, &acts, &layers) {
+            Some(act) => {
+                tracing::trace!("Act from navigation: {act}");
+                self.act(&act, &target, event_loop)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The `poll_hold` method dispatches the hold bindings from [`Cmd::poll_hold`] to
+    /// [`App::act`] once their keys have been held long enough, targeting the focused window or any
+    /// window if none has focus.  Called each time the event loop wakes, because nothing arrives
+    /// from [`winit`] at the moment the hold delay runs out.
+    #[tracing::instrument(skip_all)]
+    pub fn poll_hold(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        for act in self.cmd.poll_hold() {
+            let fallback = match self.windows.keys().next() {
+                Some(id) => *id,
+                None => return,
+            };
+            let target = self.target(&fallback);
+            if let Err(e) = self.act(&act, &target, event_loop) {
+                self.report_error(e, event_loop);
+            }
+        }
+    }
+
+    /// The `poll_lapse` method puts away the status panel once the pending sequence and count
+    /// lapse in the [`Cmd`], with [`Cmd::lapse`].  Called each time the event loop wakes, like
+    /// [`App::poll_hold`], and [`App::pace`] wakes the loop when the sequence is due to lapse.
+    pub fn poll_lapse(&mut self) {
+        if !self.cmd.lapse() {
+            return;
+        }
+        if let Some(id) = self.windows.keys().next().copied() {
+            self.show_status(&id);
+        }
+    }
+
+    /// The `mouse_input` method takes incoming mouse button presses and translates them to an
+    /// [`Act`] variant using the [`Cmd::click`] method, then passes the [`Act`] to [`App::act_counted`],
+    /// following the pattern of [`App::keyboard_input`].
+    ///
+    /// Commits a `FauxPas` if [`App::act`] fails.
+    #[tracing::instrument(skip_all)]
+    pub fn mouse_input(
+        &mut self,
+        id: &window::WindowId,
+        state: event::ElementState,
+        button: &event::MouseButton,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) -> Arrive<()> {
+        if state.is_pressed() {
+            // A click hides the focus the keyboard was showing.
+            let (acts, layers) = self.outline();
+            if let Some(lens) = self.windows.get_mut(id) {
+                lens.point(&acts, &layers);
+            }
+            if let Some(act) = self.cmd.click(button, &self.modifiers) {
+                tracing::trace!("Act detected: {act}");
+                self.act_counted(&act, id, event_loop)?;
+            }
         }
         Ok(())
     }
 
+    /// The `mouse_wheel` method converts a scroll event into a [`crate::Glide`] using the
+    /// [`Scroll`] preferences and the modifier keys currently held, and passes it to
+    /// [`Lens::scroll`] on the window under the mouse.  Unlike key presses, scrolling applies to the
+    /// window under the pointer rather than the focused window, which is what users expect from a
+    /// map.
+    #[tracing::instrument(skip_all)]
+    pub fn mouse_wheel(&mut self, id: &window::WindowId, delta: &event::MouseScrollDelta) {
+        let glide = self.scroll.glide(delta, self.modifiers.state());
+        tracing::trace!("Glide: {glide:?}");
+        if let Some(lens) = self.windows.get_mut(id) {
+            lens.scroll(&glide);
+        }
+    }
+
+    /// The `target` method returns the id of the window that actions should apply to.  This is
+    /// the focused window when we know which window has focus, and otherwise the `fallback` id,
+    /// typically the window that delivered the triggering event.
+    ///
+    /// Some platforms deliver key events to a window that has just lost focus, so an action like
+    /// [`Act::CloseWindow`] could close the wrong window if we trusted the id of the event.
+    pub fn target(&self, fallback: &window::WindowId) -> window::WindowId {
+        match self.focused {
+            Some(id) if self.windows.contains_key(&id) => id,
+            _ => *fallback,
+        }
+    }
+
+    /// The `pace` method sets the [`event_loop::ControlFlow`] according to whether any window
+    /// needs new frames.  The purpose of this method is to keep CPU and GPU usage near zero when
+    /// the app is at rest.
+    ///
+    /// Each window draws by its own [`crate::Pacing`], from [`Lens::policy`].  When any visible
+    /// window draws continuously (see [`Lens::needs_frames`]), we ask the event loop to wake up
+    /// again after the shortest frame interval among them, and [`App::flush_redraws`] requests
+    /// the frames.  Otherwise we set the control flow to [`event_loop::ControlFlow::Wait`], and
+    /// the app sleeps until the next event arrives.  Reactive windows, like tables and logs,
+    /// redraw only when an event invalidates them.
+    ///
+    /// A window invalidated too soon after its last frame waits for the interval to run out, and
+    /// `redraw` holds the time it may ask again, from [`App::flush_redraws`].  We wake up then, or
+    /// when the hold delay of a key with a hold binding runs out (see [`Cmd::hold_deadline`]),
+    /// whichever comes first.  While a game controller is connected, we also wake up every
+    /// [`PAD_INTERVAL`] to check it for input, because controller events do not arrive through
+    /// the event loop.  Global hotkeys need no such care, since each press wakes the loop as a
+    /// [`UserEvent::Hotkey`].
+    ///
+    /// Called from [`ApplicationHandler::about_to_wait`].
+    #[tracing::instrument(skip_all)]
+    pub fn pace(&self, redraw: Option<time::Instant>, event_loop: &event_loop::ActiveEventLoop) {
+        let interval = self
+            .windows
+            .values()
+            .filter(|lens| lens.needs_frames())
+            .map(|lens| *lens.interval())
+            .min();
+        let deadline = redraw
+            .into_iter()
+            .chain(self.cmd.hold_deadline())
+            .chain(self.cmd.deadline())
+            .min();
+        if let Some(interval) = interval {
+            let next = time::Instant::now() + interval;
+            let next = deadline.map_or(next, |deadline| deadline.min(next));
+            event_loop.set_control_flow(event_loop::ControlFlow::WaitUntil(next));
+        } else if let Some(deadline) = deadline {
+            event_loop.set_control_flow(event_loop::ControlFlow::WaitUntil(deadline));
+        } else if self.pad.as_ref().is_some_and(Pad::connected) {
+            let next = time::Instant::now() + PAD_INTERVAL;
+            event_loop.set_control_flow(event_loop::ControlFlow::WaitUntil(next));
+        } else {
+            event_loop.set_control_flow(event_loop::ControlFlow::Wait);
+        }
+    }
+
     /// The `screen_sizes` method returns a vector of physical sizes for each monitor available to
     /// the app.  The purpose of this function is to ascertain valid areas for drawing new windows.
     /// We avoid asking for windows with areas outside the size of the target screen.
@@ -435,15 +2940,29 @@ impl App {
 }
 
 /// The impl for `ApplicationHandler` is boiled down to as little as possible.
-/// * The `resumed` method gets called once at startup when the program is ready
-///   to make the initial window.  Calls [`App::create_window`] and unwraps it with an `expect`.
-/// * The `window_event` method removes the current window on a [`WindowEvent::CloseRequested`].
+/// * The `resumed` method gets called at startup when the program is ready
+///   to make the initial window.  Calls [`App::create_window`] and unwraps it with an `expect`,
+///   unless [`App::headless`] is `true`, in which case there are no windows and the app exits
+///   once the startup work in [`App::open_startup`] is done.
+///   The method gets called again when the app wakes from suspension (after sleep, a GPU reset
+///   or a compositor restart), in which case we call [`Lens::resume`] on each window instead.
+/// * The `suspended` method calls [`Lens::suspend`] on each window, so that we stop drawing to
+///   surfaces that may no longer exist.
+/// * The `window_event` method removes the current window on a [`WindowEvent::CloseRequested`],
+///   through [`App::act`] as an [`Act::CloseWindow`], unless the deployment disables it.
+///   It records the focused window on a [`WindowEvent::Focused`].
 ///   It dispatches keyboard input from a [`WindowEvent::KeyboardInput`] to the [`App::keyboard_input`]
-///   method, converting errors to trace level logs (hopefully they weren't important).
-/// * The [`WindowEvent::RedrawRequested`] variant will trigger a [`window::Window::request_redraw`]
-///   call if the `refresh` field on [`Lens`] is set to `true`, which it never is.
+///   method, converting errors to trace level logs (hopefully they weren't important).  Mouse
+///   button presses from a [`WindowEvent::MouseInput`] go to [`App::mouse_input`] in the same way.
+/// * The [`WindowEvent::RedrawRequested`] variant draws the map with [`Lens::render`], marks the
+///   [`Lens`] as drawn with [`Lens::drawn`], and updates the debug overlay with [`Lens::frame`].
+///   A frame that fails to draw goes to [`App::report_error`].
+/// * Any other event passes to [`Lens::window_event`], which dispatches on the [`Role`] of the
+///   window.
 /// * We delegate program exit to the `about_to_wait` method, where we check to see if there are open
-///   windows remaining.  If all windows are closed, we exit gracefully.
+///   windows remaining.  If all windows are closed, we exit gracefully.  Otherwise, we call
+///   [`App::flush_redraws`] to request the frames invalidated since the last pass, and
+///   [`App::pace`] to decide whether to sleep or keep drawing.
 ///
 ///   ## Version 0.1.1 Update
 ///
@@ -494,62 +3013,163 @@ impl App {
 ///     * No further variants of [`Act`] participate in [`Hijinks`].
 ///   * [`Hijinks::Vandalize`] - Respond by logging the contained message as an INFO level trace.
 ///   * [`Hijinks::Filch`] - Respond by sending a vector of [`Frame`] instances to the filcher.
-impl ApplicationHandler<accesskit_winit::Event> for App {
+///
+///   ## Version 0.1.4 Update
+///
+///   The `Hijinks` never made it past a comment, and have gone, along with the `Imp` types that
+///   were to cause them.  The background work they stood in for now belongs to the [`Agents`],
+///   and reaches the event loop as a [`UserEvent`], one variant for each kind of news.  A
+///   [`UserEvent::Loaded`] from the refresher replaces the open [`Dataset`] read from the same
+///   file, rather than adding a second copy.
+impl ApplicationHandler<UserEvent> for App {
     #[tracing::instrument(skip_all)]
     fn resumed(&mut self, event_loop: &event_loop::ActiveEventLoop) {
-        self.create_window(event_loop, None)
-            .expect("Could not create window.");
+        if self.started {
+            tracing::info!("Resuming.");
+            self.windows.values_mut().for_each(Lens::resume);
+            let ids = self
+                .windows
+                .keys()
+                .copied()
+                .collect::<Vec<window::WindowId>>();
+            for id in ids {
+                if let Err(e) = self.attach_map(&id) {
+                    self.report_error(e, event_loop);
+                }
+            }
+        } else {
+            if self.headless() {
+                tracing::info!("Running headless.");
+            } else {
+                // Without a first window there is nothing to show, so this one is fatal, whatever
+                // the severity of the error.
+                if let Err(e) = self
+                    .create_window(event_loop, Role::Map, None)
+                    .context("opening the first window")
+                {
+                    self.shutdown(e, event_loop);
+                    return;
+                }
+            }
+            self.open_startup();
+            self.started = true;
+        }
     }
 
     #[tracing::instrument(skip_all)]
-    fn user_event(
-        &mut self,
-        event_loop: &event_loop::ActiveEventLoop,
-        event: accesskit_winit::Event,
-    ) {
-        tracing::info!("User event detected.");
-        // match event {
-        //     Hijinks::Meddle(meddle) => match meddle.act() {
-        //         Act::CloseWindow => {
-        //             tracing::info!("Close window received.");
-        //             let keys = self
-        //                 .windows
-        //                 .keys()
-        //                 .cloned()
-        //                 .collect::<Vec<window::WindowId>>();
-        //             if keys.len() > 1 {
-        //                 let mut rng = rand::thread_rng();
-        //                 let idx = rng.gen_range(0..keys.len());
-        //                 self.windows.remove(&keys[idx]);
-        //             } else {
-        //                 tracing::info!("App refuses to close the last window.");
-        //             }
-        //         }
-        //         Act::NewWindow => {
-        //             if let Some(frame) = meddle.frame() {
-        //                 tracing::info!("Creating window from imp.");
-        //                 let position = frame.position();
-        //                 let size = frame.size();
-        //                 let attr = window::Window::default_attributes()
-        //                     .with_title(meddle.title())
-        //                     .with_transparent(true)
-        //                     .with_position(*position)
-        //                     .with_inner_size(*size);
-        //                 self.create_window(event_loop, Some(attr)).unwrap();
-        //             } else {
-        //                 tracing::warn!("New window invocations should always include a frame.");
-        //             }
-        //         }
-        //         _ => tracing::warn!("Imps can't send this type of act."),
-        //     },
-        //     Hijinks::Vandalize(msg) => tracing::info!(msg),
-        //     Hijinks::Filch(filch) => {
-        //         if let Some(frames) = self.frames(FRAMES) {
-        //             let tx = filch.dissolve();
-        //             tx.send(frames).unwrap();
-        //         }
-        //     }
-        // }
+    fn suspended(&mut self, _event_loop: &event_loop::ActiveEventLoop) {
+        tracing::info!("Suspending.");
+        self.windows.values_mut().for_each(Lens::suspend);
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn user_event(&mut self, event_loop: &event_loop::ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::AccessKit(event) => self.accessibility_event(event, event_loop),
+            UserEvent::Error(blame) => self.report_error(blame, event_loop),
+            UserEvent::Hotkey(act) => self.hotkey(&act, event_loop),
+            UserEvent::Progress(job) => {
+                tracing::trace!("{job}");
+                self.redraw_all();
+            }
+            UserEvent::Finished(job) => {
+                tracing::info!("Finished {}.", job.description());
+                self.redraw_all();
+            }
+            UserEvent::Loaded(dataset) => {
+                tracing::info!(
+                    "Loaded {} as a {} table.",
+                    dataset.path().display(),
+                    dataset.format()
+                );
+                self.agents.watch(dataset.path());
+                match self
+                    .datasets
+                    .iter_mut()
+                    .find(|open| open.path() == dataset.path())
+                {
+                    Some(open) => *open = dataset.clone(),
+                    None => self.datasets.push(dataset.clone()),
+                }
+                self.style(dataset);
+                self.describe_all();
+                self.redraw_all();
+            }
+            UserEvent::Styled(figure) => {
+                if self
+                    .datasets
+                    .iter()
+                    .any(|open| open.path() == figure.path())
+                {
+                    match self
+                        .figures
+                        .iter_mut()
+                        .find(|open| open.path() == figure.path())
+                    {
+                        Some(open) => *open = figure,
+                        None => self.figures.push(figure),
+                    }
+                    let figures = &self.figures;
+                    self.windows
+                        .values_mut()
+                        .for_each(|lens| lens.show(figures));
+                }
+            }
+            UserEvent::Found(id, query, parcel) => {
+                // Only a lookup that found something is worth sharing in a permalink, or saving.
+                if let Some(store) = &self.store {
+                    if let Err(e) = store.save_query(&query) {
+                        tracing::warn!("Could not save the lookup: {e}");
+                    }
+                }
+                self.query = Some(query);
+                if let Err(e) = self.show_parcel(&id, parcel) {
+                    self.report_error(e, event_loop);
+                }
+            }
+            UserEvent::Mail(mailbox) => {
+                for event in mailbox.drain() {
+                    self.user_event(event_loop, event);
+                }
+            }
+            UserEvent::Offer(path) => {
+                if !self.offered.contains(&path) {
+                    tracing::info!(
+                        "New data in {}.  Use {} to load it.",
+                        path.display(),
+                        self.cmd.label(&Act::LoadOffered)
+                    );
+                    self.offered.push(path);
+                }
+            }
+            UserEvent::Open(paths) => self.open_paths(paths),
+            UserEvent::Scene(scene) => {
+                tracing::info!("Showing the scene at {}.", scene.url());
+                let href = scene.url().to_string();
+                let layer = scene.layer();
+                self.windows
+                    .values_mut()
+                    .for_each(|lens| lens.reveal(Some(&layer)));
+                self.scene = Some((href, layer));
+            }
+            UserEvent::Prepared(hardware) => {
+                self.gpu.adopt(hardware);
+                let ids = self
+                    .windows
+                    .keys()
+                    .copied()
+                    .collect::<Vec<window::WindowId>>();
+                for id in ids {
+                    if let Err(e) = self.attach_map(&id) {
+                        self.report_error(e, event_loop);
+                    }
+                }
+            }
+            UserEvent::Redraw(id) => match self.windows.get_mut(&id) {
+                Some(lens) => lens.invalidate(),
+                None => tracing::trace!("No window {id:?} to redraw."),
+            },
+        }
     }
 
     #[tracing::instrument(skip_all)]
@@ -563,13 +3183,31 @@ impl ApplicationHandler<accesskit_winit::Event> for App {
             Some(window) => window,
             None => return,
         };
+        window.relay(&event);
 
         match event {
             WindowEvent::CloseRequested => {
-                tracing::trace!("Closing Window={id:?}");
-                self.windows.remove(&id);
+                // The close button goes through the same door as the keys, so a deployment
+                // that disables the destructive group keeps its windows open.
+                tracing::trace!("Close requested for Window={id:?}");
+                if let Err(e) = self.act(&Act::CloseWindow, &id, event_loop) {
+                    self.report_error(e, event_loop);
+                }
                 tracing::trace!("Windows remaining: {}", self.windows.len());
             }
+            WindowEvent::Focused(focused) => {
+                if focused {
+                    tracing::trace!("Focused Window={id:?}");
+                    self.focused = Some(id);
+                } else if self.focused == Some(id) {
+                    tracing::trace!("Unfocused Window={id:?}");
+                    self.focused = None;
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                tracing::trace!("Modifiers: {:?}", modifiers.state());
+                self.modifiers = modifiers;
+            }
             WindowEvent::KeyboardInput {
                 event,
                 is_synthetic: false,
@@ -577,33 +3215,74 @@ impl ApplicationHandler<accesskit_winit::Event> for App {
             } => {
                 match self.keyboard_input(&id, &event, event_loop) {
                     Ok(_) => tracing::trace!("Event handled."),
-                    Err(e) => tracing::trace!("Unexpected: {}", e.to_string()),
+                    Err(e) => self.report_error(e, event_loop),
                 };
             }
+            WindowEvent::MouseInput { state, button, .. } => {
+                match self.mouse_input(&id, state, &button, event_loop) {
+                    Ok(_) => tracing::trace!("Event handled."),
+                    Err(e) => self.report_error(e, event_loop),
+                };
+            }
+            WindowEvent::MouseWheel { delta, .. } => self.mouse_wheel(&id, &delta),
+            WindowEvent::DroppedFile(path) => self.open_file(path),
             WindowEvent::RedrawRequested => {
-                // I left these comments in from the example to remind me to put some cool stuff
-                // here later.
-                //
-                // Redraw the application.
-                //
                 // It's preferable for applications that do not render continuously to render in
                 // this event rather than in AboutToWait, since rendering in here allows
                 // the program to gracefully handle redraws requested by the OS.
-
-                // Draw.
-
-                // Queue a RedrawRequested event.
-                //
-                // You only need to call this if you've determined that you need to redraw in
-                // applications which do not always need to. Applications that redraw continuously
-                // can render here instead.
-                if *window.refresh() {
-                    window.window().request_redraw();
-                    window.with_refresh(false);
+                // Marking the frame drawn first lets a frame that cannot draw ask for another.
+                let now = time::Instant::now();
+                window.drawn(now);
+                let rendered = window.render();
+                window.frame();
+                let captured = match (&mut self.recorder, window.map()) {
+                    (Some(recorder), Some(map)) if recorder.window() == id => {
+                        recorder.capture(map, now)
+                    }
+                    _ => Ok(false),
+                };
+                if let Err(e) = rendered {
+                    self.report_error(e, event_loop);
+                }
+                for fault in self.gpu.faults() {
+                    self.report_error(fault.into(), event_loop);
+                }
+                match captured {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        tracing::info!("The recording ran its length, or the encoder stopped.");
+                        self.stop_recording();
+                    }
+                    Err(e) => {
+                        self.stop_recording();
+                        self.report_error(e, event_loop);
+                    }
                 }
             }
-            _ => (),
+            // Everything else depends on what the window is showing.
+            event => {
+                window.window_event(&event);
+                if let Some((longitude, latitude)) = window.take_press() {
+                    if let Err(e) = self.press(&id, longitude, latitude) {
+                        self.report_error(e, event_loop);
+                    }
+                }
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn exiting(&mut self, _event_loop: &event_loop::ActiveEventLoop) {
+        // The frames are on disk already, so we wait for the encoder to close the clip.
+        if let Some(recorder) = self.recorder.take() {
+            tracing::info!("Exiting with a recording under way, finishing the clip.");
+            if let Err(e) = recorder.finish().finish(time::Instant::now()) {
+                tracing::warn!("{e}");
+            }
         }
+        self.agents.stop();
+        self.tasks.cancel_all();
+        self.save_reporter();
     }
 
     #[tracing::instrument(skip_all)]
@@ -611,6 +3290,12 @@ impl ApplicationHandler<accesskit_winit::Event> for App {
         if self.windows.is_empty() {
             tracing::trace!("No windows left, exiting...");
             event_loop.exit();
+        } else {
+            self.poll_pad(event_loop);
+            self.poll_hold(event_loop);
+            self.poll_lapse();
+            let redraw = self.flush_redraws();
+            self.pace(redraw, event_loop);
         }
     }
 }
@@ -680,6 +3365,24 @@ pub const FRAME_POOL: usize = 100;
 /// The `FRAMES` constant determines the number of frames given to each [`crate::Imp`].
 pub const FRAMES: usize = 10;
 
+/// The `CLOSED_WINDOWS` constant determines how many recently closed windows the [`App`]
+/// remembers for reopening.
+pub const CLOSED_WINDOWS: usize = 10;
+
+/// The `CONFIG_FILE` constant holds the name of the config file.  [`Home::config`] decides which
+/// directory it lives in.  We read the config from this file on startup, and [`App::bind`] writes
+/// key bindings back to it.  A config in another of the [`CONFIG_FORMATS`] takes the same name
+/// with a different extension.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The `CONFIG_FORMATS` constant lists the file extensions we accept for config files, in the
+/// order [`Home`] looks for them in each directory.  See [`crate::Layers::format`].
+pub const CONFIG_FORMATS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// The `FRAME_INTERVAL` constant sets the time between frames of a window before it adopts the
+/// `fps` of the `[graphics]` table, roughly sixty frames per second.  See [`Lens::policy`].
+pub const FRAME_INTERVAL: time::Duration = time::Duration::from_millis(16);
+
 /// The `MIN_SPAN` constant serves as both the minimum size constraint for the height and width of
 /// new windows, as well as the minimum padding between window and screen sizes.
 /// Used to implement [`From<monitor::MonitorHandle>`] for [`Frame`].
@@ -1,4 +1,5 @@
 use derive_more::Error;
+use web_time as time;
 
 /// The `arrive` module holds error handling types for the `tardy` crate.
 ///
@@ -19,56 +20,841 @@ use derive_more::Error;
 /// conversion of errors from other libraries into a common type using the question mark operator.
 /// Library-specific errors fall under the `Excuse` variant, which contains an [`Excuse`] enum with
 /// variants for different internal error conditions.
-#[derive(
-    Debug,
-    Copy,
-    Clone,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    Hash,
-    derive_more::Display,
-    Error,
-    derive_more::From,
-)]
+///
+/// ## Update 0.1.2
+///
+/// The experiment above did not work.  The variants held nothing, so `self.source()` had nothing
+/// to show, and every error from a library printed as its name and `None`.  Each variant now
+/// holds the error it came from in a `source` field, which [`Error::source`] hands on to whoever
+/// asks, and which we print after the name of the variant.
+///
+/// The error alone often does not say enough.  An [`std::io::Error`] that reads "No such file or
+/// directory" leaves us guessing which file.  Each variant also holds an optional `context`,
+/// describing what we were doing when the error happened, which prints before the rest.  The
+/// [`Context`] trait adds it to any result on the way up:
+///
+/// ```ignore
+/// std::fs::read_to_string(path).context("reading bookmarks")?;
+/// ```
+///
+/// Adding context to an error that already has some puts the new context in front, so the message
+/// reads from the outside in, as in `loading the config: reading secrets.toml: Io: ...`.
+///
+/// Since library errors generally cannot be copied or compared, neither can `Blame` anymore.
+///
+/// ## Update 0.1.3
+///
+/// The display above is written for us, and reads like it.  A user looking at
+/// `TomlDe: TOML parse error at line 3, column 7` in a dialog learns only that something broke,
+/// and the bug report that follows quotes whatever fraction of it they copied.  So each error
+/// condition now has two more faces:
+///
+/// * [`Blame::code`] returns a stable code, like `BEA-E042`, for a dialog to show and a bug report
+///   to quote.  The display prints it first, so the logs carry it too, and we can search the logs
+///   for the code the user sends.
+/// * [`Blame::message`] returns a short sentence for the user, saying what went wrong in their
+///   terms rather than ours.  [`Blame::report`] puts the two together for a dialog.
+///
+/// Library errors take codes from `BEA-E001`, and each [`Excuse`] takes its own from `BEA-E040`,
+/// since an excuse says more than the `Excuse` variant wrapping it.  A code, once given out,
+/// belongs to its condition for good.  A new variant takes the next free number, and the number
+/// of a removed variant is never used again, so a code in an old bug report still means what it
+/// meant.
+///
+/// ## Update 0.1.4
+///
+/// Some failures go away if we wait a moment: a read interrupted by a signal, a connection reset
+/// by a busy server, a window with no frame ready yet.  Others never will, and retrying them only
+/// keeps the user waiting for bad news.  [`Blame::severity`] sorts each error into a
+/// [`Severity`], and [`Blame::is_retryable`] and [`Blame::retry_after`] turn that into one retry
+/// policy, so that everything fetching over the network or running in the background gives up at
+/// the same point and waits the same way.
+#[derive(Debug, Error)]
 pub enum Blame {
     /// Triggered when the [`csv`] crate is unable to read the `.csv` file containing inspirational
     /// quotes.  Easy to find by feeding it a bogus path.
-    #[from(csv::Error)]
-    #[display("Csv: {:?}", self.source())]
-    Csv,
+    Csv {
+        /// The error from the [`csv`] crate.
+        source: csv::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Arrow` variant indicates the [`arrow`] crate could not build, filter or sort a table,
+    /// in [`crate::Dataset`].
+    Arrow {
+        /// The error from the [`arrow`] crate.
+        source: arrow::error::ArrowError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Clipboard` variant indicates the [`arboard`] crate could not reach the system
+    /// clipboard, or the system would not take our text, in [`crate::Clipboard`].
+    #[cfg(not(target_arch = "wasm32"))]
+    Clipboard {
+        /// The error from the [`arboard`] crate.
+        source: arboard::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Config` variant indicates the [`config`] crate could not build or read the config.
+    Config {
+        /// The error from the [`config`] crate.
+        source: config::ConfigError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `CreateSurface` variant indicates [`wgpu`] could not make a surface to draw on from a
+    /// window, in [`crate::Gpu::canvas`].
+    CreateSurface {
+        /// The error from the [`wgpu`] crate.
+        source: wgpu::CreateSurfaceError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
     /// The `EventLoop` variant triggers on failure to create a new [`winit`] event loop.
-    #[from(winit::error::EventLoopError)]
-    #[display("EventLoop: {:?}", self.source())]
-    EventLoop,
+    EventLoop {
+        /// The error from the [`winit`] crate.
+        source: winit::error::EventLoopError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
     /// The `EventLoopClosed` variant occurs when an async event tries to send a message to event
     /// loop after it has been closed.
-    #[from(winit::event_loop::EventLoopClosed<accesskit_winit::Event>)]
-    #[display("EventLoopClosed: {:?}", self.source())]
-    EventLoopClosed,
-    /// The `Excuse` variant indicates an internal library error.  
+    EventLoopClosed {
+        /// The error from the [`winit`] crate, holding the event that could not be sent.  The
+        /// event may itself hold a `Blame`, so we box it to keep the size of `Blame` finite.
+        source: Box<winit::event_loop::EventLoopClosed<crate::UserEvent>>,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Excuse` variant indicates an internal library error.
     /// The variant contains an [`Excuse`] enum that describes the error condition.
-    Excuse(Excuse),
+    Excuse {
+        /// The internal error condition.
+        source: Excuse,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Filter` variant indicates the directives for the log filter, as given to
+    /// [`crate::Verbosity`], do not parse.
+    Filter {
+        /// The error from the [`tracing_subscriber`] crate.
+        source: tracing_subscriber::filter::ParseError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Gilrs` variant indicates the [`gilrs`] crate could not connect to the platform
+    /// controller API.
+    Gilrs {
+        /// The error from the [`gilrs`] crate.
+        source: gilrs::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `GlobalHotkey` variant indicates the [`global_hotkey`] crate could not start the
+    /// platform hotkey manager.
+    #[cfg(not(target_arch = "wasm32"))]
+    GlobalHotkey {
+        /// The error from the [`global_hotkey`] crate.
+        source: global_hotkey::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Gpu` variant indicates the graphics card rejected a command, as caught by
+    /// [`crate::Fault::guard`] or the handler on the device, described in [`crate::Gpu`].
+    Gpu {
+        /// The fault reported by [`wgpu`].
+        source: crate::Fault,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Http` variant indicates the [`reqwest`] client could not complete a request, such as
+    /// a call to the BEA API or the download of a map tile.  The error names the address of the
+    /// request, which goes into the log and bug reports, so the conversion redacts it first, as
+    /// in [`crate::Network::redact`].
+    Http {
+        /// The error from the [`reqwest`] crate.
+        source: reqwest::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Image` variant indicates the [`image`] crate could not decode or encode an image,
+    /// such as a map tile or an exported screenshot.
+    Image {
+        /// The error from the [`image`] crate.
+        source: image::ImageError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
     /// The `Io` variant indicates an error opening the file location where the csv quotes should
     /// be.
-    #[from(std::io::Error)]
-    #[display("Io: {:?}", self.source())]
-    Io,
+    Io {
+        /// The error from the standard library.
+        source: std::io::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Join` variant indicates a task handed to a [`tokio`] worker thread panicked, or was
+    /// dropped before it finished, as when [`crate::Dataset::load`] reads a file off the event
+    /// loop.
+    Join {
+        /// The error from the [`tokio`] crate.
+        source: tokio::task::JoinError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Json` variant indicates the [`serde_json`] crate could not read a JSON file, like a
+    /// GeoJSON file read by [`crate::Dataset::read`].
+    Json {
+        /// The error from the [`serde_json`] crate.
+        source: serde_json::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Metrics` variant indicates the [`opentelemetry`] crates could not start exporting
+    /// metrics to the collector named by [`crate::Otlp`].
+    Metrics {
+        /// The error from the [`opentelemetry`] crate.
+        source: opentelemetry::metrics::MetricsError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
     /// The `Oneshot` variant indicates an error in the [`tokio`] oneshot channel used to call for
     /// [`crate::Frame`] instances from the [`crate::App`] and receive new frames.
-    #[from(tokio::sync::oneshot::error::RecvError)]
-    #[display("Oneshot: {:?}", self.source())]
-    Oneshot,
+    Oneshot {
+        /// The error from the [`tokio`] crate.
+        source: tokio::sync::oneshot::error::RecvError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
     /// The `OsError` variant indicates an error from the [`winit`] crate.
-    #[from(winit::error::OsError)]
-    #[display("OsError: {:?}", self.source())]
-    OsError,
+    OsError {
+        /// The error from the [`winit`] crate.
+        source: winit::error::OsError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Parquet` variant indicates the [`parquet`] crate could not read or write a Parquet
+    /// file, in [`crate::GeoParquet`].
+    Parquet {
+        /// The error from the [`parquet`] crate.
+        source: parquet::errors::ParquetError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Reload` variant indicates [`crate::Verbosity`] could not swap the log filter, because
+    /// the subscriber holding it has gone away.
+    Reload {
+        /// The error from the [`tracing_subscriber`] crate.
+        source: tracing_subscriber::reload::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `RequestDevice` variant indicates [`wgpu`] could not get a device from the graphics
+    /// adapter, so we have nothing to draw with.
+    RequestDevice {
+        /// The error from the [`wgpu`] crate.
+        source: wgpu::RequestDeviceError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Sqlite` variant indicates the [`rusqlite`] crate could not open, migrate, read or
+    /// write the [`crate::Store`], or read a [`crate::GeoPackage`].
+    #[cfg(not(target_arch = "wasm32"))]
+    Sqlite {
+        /// The error from the [`rusqlite`] crate.
+        source: rusqlite::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Surface` variant indicates [`wgpu`] could not get the next texture to draw a frame
+    /// on from the surface of a window.
+    Surface {
+        /// The error from the [`wgpu`] crate.
+        source: wgpu::SurfaceError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Svg` variant indicates the [`resvg`] crate could not parse an SVG icon for the
+    /// [`crate::Atlas`].
+    Svg {
+        /// The error from the [`resvg`] crate.
+        source: resvg::usvg::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Tiff` variant indicates the [`tiff`] crate could not read the header or a tile of the
+    /// COG of a scene for a [`crate::Cog`].
+    Tiff {
+        /// The error from the [`tiff`] crate.
+        source: tiff::TiffError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Timeout` variant indicates a server took longer to answer than the `[network]` table
+    /// of the config allows, described in [`crate::Timeouts`].
+    Timeout {
+        /// The call that ran out of time.
+        source: crate::Overdue,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `TomlDe` variant indicates the [`toml`] crate could not parse a file, such as the
+    /// config file we are about to write back to.
+    TomlDe {
+        /// The error from the [`toml`] crate.
+        source: toml::de::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `TomlSer` variant indicates the [`toml`] crate could not serialize a value.
+    TomlSer {
+        /// The error from the [`toml`] crate.
+        source: toml::ser::Error,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Trace` variant indicates the [`opentelemetry`] crates could not start exporting
+    /// spans to the collector named by [`crate::Otlp`].
+    Trace {
+        /// The error from the [`opentelemetry`] crate.
+        source: opentelemetry::trace::TraceError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+    /// The `Zip` variant indicates the [`zip`] crate could not read or write a
+    /// [`crate::Bundle`].
+    Zip {
+        /// The error from the [`zip`] crate.
+        source: zip::result::ZipError,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
     /// The `Tokio` variant indicates an error with the mpsc channel used to send [`Hijinks`] from
     /// [`crate::Imp`] types to the [`crate::ImpKing`].
-    #[from(tokio::sync::mpsc::error::SendError<accesskit_winit::Event>)]
-    #[display("Tokio: {:?}", self.source())]
-    Tokio,
+    Tokio {
+        /// The error from the [`tokio`] crate, holding the event that could not be sent.
+        source: tokio::sync::mpsc::error::SendError<accesskit_winit::Event>,
+        /// What we were doing at the time.
+        context: Option<String>,
+    },
+}
+
+impl Blame {
+    /// The `context` method returns what we were doing when the error happened, if anyone said.
+    pub fn context(&self) -> Option<&str> {
+        self.context_slot().as_deref()
+    }
+
+    /// The `with_context` method adds `context` to the error, in front of any context it already
+    /// has.
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        let context = context.into();
+        let slot = self.context_mut();
+        *slot = Some(match slot.take() {
+            Some(inner) => format!("{context}: {inner}"),
+            None => context,
+        });
+        self
+    }
+
+    /// The `code` method returns the stable error code of the condition, as described in the
+    /// docs for [`Blame`].  An [`Excuse`] reports its own code from [`Excuse::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Arrow { .. } => "BEA-E030",
+            Self::Csv { .. } => "BEA-E001",
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Clipboard { .. } => "BEA-E028",
+            Self::Config { .. } => "BEA-E002",
+            Self::CreateSurface { .. } => "BEA-E025",
+            Self::EventLoop { .. } => "BEA-E003",
+            Self::EventLoopClosed { .. } => "BEA-E004",
+            Self::Excuse { source, .. } => source.code(),
+            Self::Filter { .. } => "BEA-E020",
+            Self::Gilrs { .. } => "BEA-E005",
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::GlobalHotkey { .. } => "BEA-E006",
+            Self::Gpu { .. } => "BEA-E027",
+            Self::Http { .. } => "BEA-E014",
+            Self::Image { .. } => "BEA-E015",
+            Self::Io { .. } => "BEA-E007",
+            Self::Join { .. } => "BEA-E023",
+            Self::Json { .. } => "BEA-E022",
+            Self::Metrics { .. } => "BEA-E019",
+            Self::Oneshot { .. } => "BEA-E008",
+            Self::OsError { .. } => "BEA-E009",
+            Self::Parquet { .. } => "BEA-E029",
+            Self::Reload { .. } => "BEA-E021",
+            Self::RequestDevice { .. } => "BEA-E016",
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Sqlite { .. } => "BEA-E031",
+            Self::Surface { .. } => "BEA-E017",
+            Self::Svg { .. } => "BEA-E026",
+            Self::Tiff { .. } => "BEA-E032",
+            Self::Timeout { .. } => "BEA-E024",
+            Self::TomlDe { .. } => "BEA-E010",
+            Self::TomlSer { .. } => "BEA-E011",
+            Self::Trace { .. } => "BEA-E018",
+            Self::Zip { .. } => "BEA-E012",
+            Self::Tokio { .. } => "BEA-E013",
+        }
+    }
+
+    /// The `message` method returns a short description of the condition for the user, without
+    /// the details of the source error.  An [`Excuse`] reports its own message from
+    /// [`Excuse::message`].
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::Arrow { .. } => "A table of data could not be put together.",
+            Self::Csv { .. } => "A data file could not be read.",
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Clipboard { .. } => "The text could not be copied to the clipboard.",
+            Self::Config { .. } => "The settings could not be read.",
+            Self::CreateSurface { .. } => "A window could not be set up for drawing.",
+            Self::EventLoop { .. } => "The app could not start its windowing system.",
+            Self::EventLoopClosed { .. } => "The app was closing and could not finish a task.",
+            Self::Excuse { source, .. } => source.message(),
+            Self::Filter { .. } => "The log level is not written correctly.",
+            Self::Gilrs { .. } => "Game controllers are not available on this system.",
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::GlobalHotkey { .. } => "System-wide shortcuts are not available on this system.",
+            Self::Gpu { .. } => "The graphics card refused to draw part of the map.",
+            Self::Http { .. } => "A download failed.  Check the network connection.",
+            Self::Image { .. } => "An image could not be read or saved.",
+            Self::Io { .. } => "A file could not be read or written.",
+            Self::Join { .. } => "A background task stopped unexpectedly.",
+            Self::Json { .. } => "A data file contains a mistake and could not be read.",
+            Self::Metrics { .. } => "Monitoring could not be started.",
+            Self::Oneshot { .. } => "A window stopped responding while drawing.",
+            Self::OsError { .. } => "The operating system refused to open a window.",
+            Self::Parquet { .. } => "A Parquet file could not be read or written.",
+            Self::Reload { .. } => "The log level could not be changed.",
+            Self::RequestDevice { .. } => "The graphics card could not be used for drawing.",
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Sqlite { .. } => "A GeoPackage or the bookmarks could not be read or saved.",
+            Self::Surface { .. } => "A window could not be drawn.",
+            Self::Svg { .. } => "An icon could not be read.",
+            Self::Tiff { .. } => "A picture from the imagery server could not be read.",
+            Self::Timeout { .. } => {
+                "A server took too long to answer.  Check the network connection."
+            }
+            Self::TomlDe { .. } => "A settings file contains a mistake and could not be read.",
+            Self::TomlSer { .. } => "The settings could not be saved.",
+            Self::Trace { .. } => "Monitoring could not be started.",
+            Self::Zip { .. } => "The bundle file is damaged or could not be written.",
+            Self::Tokio { .. } => "The app was closing and could not finish a task.",
+        }
+    }
+
+    /// The `report` method combines [`Blame::message`] and [`Blame::code`] into the text of an
+    /// error dialog, as in `A file could not be read or written. (BEA-E007)`.  The developer
+    /// details belong in the log, where the display of `Blame` puts them.
+    pub fn report(&self) -> String {
+        format!("{} ({})", self.message(), self.code())
+    }
+
+    /// The `severity` method sorts the error into a [`Severity`].  Errors from reading and
+    /// writing go by the [`std::io::ErrorKind`] underneath, since an interrupted read and a
+    /// missing file call for different handling.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Arrow { source, .. } => match source {
+                arrow::error::ArrowError::IoError(_, e) => Severity::of_io(e),
+                _ => Severity::Error,
+            },
+            Self::Csv { source, .. } => match source.kind() {
+                csv::ErrorKind::Io(e) => Severity::of_io(e),
+                _ => Severity::Error,
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Clipboard { .. } => Severity::Error,
+            Self::Config { .. } => Severity::Error,
+            Self::CreateSurface { .. } => Severity::Error,
+            Self::EventLoop { .. } => Severity::Fatal,
+            Self::EventLoopClosed { .. } => Severity::Fatal,
+            Self::Excuse { source, .. } => source.severity(),
+            Self::Filter { .. } => Severity::Error,
+            Self::Gilrs { .. } => Severity::Error,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::GlobalHotkey { .. } => Severity::Error,
+            Self::Gpu { source, .. } => match source.filter() {
+                wgpu::ErrorFilter::OutOfMemory => Severity::Fatal,
+                _ => Severity::Error,
+            },
+            Self::Http { source, .. } => Severity::of_http(source),
+            Self::Image { source, .. } => match source {
+                image::ImageError::IoError(e) => Severity::of_io(e),
+                _ => Severity::Error,
+            },
+            Self::Io { source, .. } => Severity::of_io(source),
+            Self::Join { .. } => Severity::Error,
+            Self::Json { .. } => Severity::Error,
+            Self::Metrics { .. } => Severity::Error,
+            Self::Oneshot { .. } => Severity::Fatal,
+            Self::OsError { .. } => Severity::Error,
+            Self::Parquet { source, .. } => match source {
+                parquet::errors::ParquetError::External(e) => e
+                    .downcast_ref::<std::io::Error>()
+                    .map_or(Severity::Error, Severity::of_io),
+                _ => Severity::Error,
+            },
+            Self::Reload { .. } => Severity::Error,
+            Self::RequestDevice { .. } => Severity::Fatal,
+            // Another copy of the app holding the store lets go soon enough to try again.
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Sqlite { source, .. } => match source.sqlite_error_code() {
+                Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+                    Severity::Transient
+                }
+                _ => Severity::Error,
+            },
+            Self::Surface { source, .. } => match source {
+                wgpu::SurfaceError::OutOfMemory => Severity::Fatal,
+                _ => Severity::Transient,
+            },
+            Self::Svg { .. } => Severity::Error,
+            Self::Tiff { .. } => Severity::Error,
+            Self::Timeout { .. } => Severity::Transient,
+            Self::TomlDe { .. } => Severity::Error,
+            Self::TomlSer { .. } => Severity::Error,
+            Self::Trace { .. } => Severity::Error,
+            Self::Zip { source, .. } => match source {
+                zip::result::ZipError::Io(e) => Severity::of_io(e),
+                _ => Severity::Error,
+            },
+            Self::Tokio { .. } => Severity::Fatal,
+        }
+    }
+
+    /// The `is_retryable` method returns `true` if trying again might succeed, meaning the
+    /// [`Severity`] is [`Severity::Transient`].
+    pub fn is_retryable(&self) -> bool {
+        self.severity() == Severity::Transient
+    }
+
+    /// The `retry_after` method returns how long to wait before trying again, after `attempt`
+    /// tries have failed with this error, counting from one.  The wait starts at [`RETRY_DELAY`]
+    /// and doubles with each attempt.  Returns [`None`] if the error is not retryable, or once
+    /// `attempt` reaches [`RETRY_LIMIT`], at which point the caller should tell the user.
+    pub fn retry_after(&self, attempt: u32) -> Option<time::Duration> {
+        if !self.is_retryable() || attempt >= RETRY_LIMIT {
+            return None;
+        }
+        Some(RETRY_DELAY * 2u32.pow(attempt.saturating_sub(1)))
+    }
+
+    /// The `context_slot` method returns the `context` field of whichever variant this is.
+    fn context_slot(&self) -> &Option<String> {
+        match self {
+            // The desktop has a clipboard, global hotkeys and a store, and the browser does not.
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Clipboard { context, .. }
+            | Self::GlobalHotkey { context, .. }
+            | Self::Sqlite { context, .. } => context,
+            Self::Arrow { context, .. }
+            | Self::Csv { context, .. }
+            | Self::Config { context, .. }
+            | Self::CreateSurface { context, .. }
+            | Self::EventLoop { context, .. }
+            | Self::EventLoopClosed { context, .. }
+            | Self::Excuse { context, .. }
+            | Self::Filter { context, .. }
+            | Self::Gilrs { context, .. }
+            | Self::Gpu { context, .. }
+            | Self::Http { context, .. }
+            | Self::Image { context, .. }
+            | Self::Io { context, .. }
+            | Self::Join { context, .. }
+            | Self::Json { context, .. }
+            | Self::Metrics { context, .. }
+            | Self::Oneshot { context, .. }
+            | Self::OsError { context, .. }
+            | Self::Parquet { context, .. }
+            | Self::Reload { context, .. }
+            | Self::RequestDevice { context, .. }
+            | Self::Surface { context, .. }
+            | Self::Svg { context, .. }
+            | Self::Tiff { context, .. }
+            | Self::Timeout { context, .. }
+            | Self::TomlDe { context, .. }
+            | Self::TomlSer { context, .. }
+            | Self::Trace { context, .. }
+            | Self::Zip { context, .. }
+            | Self::Tokio { context, .. } => context,
+        }
+    }
+
+    /// The `context_mut` method returns the `context` field of whichever variant this is, for
+    /// [`Blame::with_context`] to fill in.
+    fn context_mut(&mut self) -> &mut Option<String> {
+        match self {
+            // The desktop has a clipboard, global hotkeys and a store, and the browser does not.
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Clipboard { context, .. }
+            | Self::GlobalHotkey { context, .. }
+            | Self::Sqlite { context, .. } => context,
+            Self::Arrow { context, .. }
+            | Self::Csv { context, .. }
+            | Self::Config { context, .. }
+            | Self::CreateSurface { context, .. }
+            | Self::EventLoop { context, .. }
+            | Self::EventLoopClosed { context, .. }
+            | Self::Excuse { context, .. }
+            | Self::Filter { context, .. }
+            | Self::Gilrs { context, .. }
+            | Self::Gpu { context, .. }
+            | Self::Http { context, .. }
+            | Self::Image { context, .. }
+            | Self::Io { context, .. }
+            | Self::Join { context, .. }
+            | Self::Json { context, .. }
+            | Self::Metrics { context, .. }
+            | Self::Oneshot { context, .. }
+            | Self::OsError { context, .. }
+            | Self::Parquet { context, .. }
+            | Self::Reload { context, .. }
+            | Self::RequestDevice { context, .. }
+            | Self::Surface { context, .. }
+            | Self::Svg { context, .. }
+            | Self::Tiff { context, .. }
+            | Self::Timeout { context, .. }
+            | Self::TomlDe { context, .. }
+            | Self::TomlSer { context, .. }
+            | Self::Trace { context, .. }
+            | Self::Zip { context, .. }
+            | Self::Tokio { context, .. } => context,
+        }
+    }
+}
+
+/// Prints the error code, the context, if any, then the name of the variant and the source
+/// error, as in `[BEA-E007] reading secrets.toml: Io: permission denied (os error 13)`.
+impl std::fmt::Display for Blame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] ", self.code())?;
+        if let Some(context) = self.context() {
+            write!(f, "{context}: ")?;
+        }
+        match self {
+            Self::Arrow { source, .. } => write!(f, "Arrow: {source}"),
+            Self::Csv { source, .. } => write!(f, "Csv: {source}"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Clipboard { source, .. } => write!(f, "Clipboard: {source}"),
+            Self::Config { source, .. } => write!(f, "Config: {source}"),
+            Self::CreateSurface { source, .. } => write!(f, "CreateSurface: {source}"),
+            Self::EventLoop { source, .. } => write!(f, "EventLoop: {source}"),
+            Self::EventLoopClosed { source, .. } => write!(f, "EventLoopClosed: {source}"),
+            Self::Excuse { source, .. } => write!(f, "{source}"),
+            Self::Filter { source, .. } => write!(f, "Filter: {source}"),
+            Self::Gilrs { source, .. } => write!(f, "Gilrs: {source}"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::GlobalHotkey { source, .. } => write!(f, "GlobalHotkey: {source}"),
+            Self::Gpu { source, .. } => write!(f, "Gpu: {source}"),
+            Self::Http { source, .. } => write!(f, "Http: {source}"),
+            Self::Image { source, .. } => write!(f, "Image: {source}"),
+            Self::Io { source, .. } => write!(f, "Io: {source}"),
+            Self::Join { source, .. } => write!(f, "Join: {source}"),
+            Self::Json { source, .. } => write!(f, "Json: {source}"),
+            Self::Metrics { source, .. } => write!(f, "Metrics: {source}"),
+            Self::Oneshot { source, .. } => write!(f, "Oneshot: {source}"),
+            Self::OsError { source, .. } => write!(f, "OsError: {source}"),
+            Self::Parquet { source, .. } => write!(f, "Parquet: {source}"),
+            Self::Reload { source, .. } => write!(f, "Reload: {source}"),
+            Self::RequestDevice { source, .. } => write!(f, "RequestDevice: {source}"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Sqlite { source, .. } => write!(f, "Sqlite: {source}"),
+            Self::Surface { source, .. } => write!(f, "Surface: {source}"),
+            Self::Svg { source, .. } => write!(f, "Svg: {source}"),
+            Self::Tiff { source, .. } => write!(f, "Tiff: {source}"),
+            Self::Timeout { source, .. } => write!(f, "Timeout: {source}"),
+            Self::TomlDe { source, .. } => write!(f, "TomlDe: {source}"),
+            Self::TomlSer { source, .. } => write!(f, "TomlSer: {source}"),
+            Self::Trace { source, .. } => write!(f, "Trace: {source}"),
+            Self::Zip { source, .. } => write!(f, "Zip: {source}"),
+            Self::Tokio { source, .. } => write!(f, "Tokio: {source}"),
+        }
+    }
+}
+
+/// The `Severity` enum describes how to handle an error, as returned by [`Blame::severity`].
+/// The variants are ordered from least to most severe.
+///
+/// * The `Transient` variant marks an error that may go away on its own, which we retry quietly
+///   before troubling the user.
+/// * The `Error` variant marks an error that retrying will not fix, which we tell the user about
+///   straight away.  The app carries on without whatever failed.
+/// * The `Fatal` variant marks an error the app cannot carry on from, such as losing the event
+///   loop.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+)]
+pub enum Severity {
+    /// The `Transient` variant marks an error worth retrying.
+    Transient,
+    /// The `Error` variant marks an error to tell the user about.
+    Error,
+    /// The `Fatal` variant marks an error the app cannot recover from.
+    Fatal,
+}
+
+impl Severity {
+    /// The `of_io` method sorts an [`std::io::Error`] by its kind.  Interruptions, timeouts and
+    /// dropped connections are [`Severity::Transient`], and anything else, like a missing file
+    /// or a denied permission, is a [`Severity::Error`].
+    pub fn of_io(error: &std::io::Error) -> Self {
+        use std::io::ErrorKind;
+        match error.kind() {
+            ErrorKind::Interrupted
+            | ErrorKind::TimedOut
+            | ErrorKind::WouldBlock
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+            | ErrorKind::UnexpectedEof => Self::Transient,
+            _ => Self::Error,
+        }
+    }
+
+    /// The `of_http` method sorts a [`reqwest::Error`].  Timeouts, failed connections, and
+    /// responses asking us to come back later, meaning a server error or `429 Too Many
+    /// Requests`, are [`Severity::Transient`].  Anything else, like a bad API key or a missing
+    /// page, is a [`Severity::Error`].
+    pub fn of_http(error: &reqwest::Error) -> Self {
+        if error.is_timeout() || error.is_connect() {
+            return Self::Transient;
+        }
+        match error.status() {
+            Some(status)
+                if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS =>
+            {
+                Self::Transient
+            }
+            _ => Self::Error,
+        }
+    }
+}
+
+/// The `RETRY_DELAY` constant sets how long to wait before the first retry of a
+/// [`Severity::Transient`] error.  The wait doubles with each retry after that.
+pub const RETRY_DELAY: time::Duration = time::Duration::from_millis(250);
+
+/// The `RETRY_LIMIT` constant caps the number of attempts at an operation that keeps failing
+/// with a [`Severity::Transient`] error, before we give up and tell the user.
+pub const RETRY_LIMIT: u32 = 4;
+
+/// The `blame_from` macro implements [`From`] for [`Blame`] from each source error type, so the
+/// question mark operator can convert them, starting with no context.
+macro_rules! blame_from {
+    ($($source:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$source> for Blame {
+                fn from(source: $source) -> Self {
+                    Self::$variant {
+                        source,
+                        context: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+blame_from!(
+    arrow::error::ArrowError => Arrow,
+    csv::Error => Csv,
+    config::ConfigError => Config,
+    wgpu::CreateSurfaceError => CreateSurface,
+    winit::error::EventLoopError => EventLoop,
+    Excuse => Excuse,
+    tracing_subscriber::filter::ParseError => Filter,
+    gilrs::Error => Gilrs,
+    crate::Fault => Gpu,
+    image::ImageError => Image,
+    std::io::Error => Io,
+    tokio::task::JoinError => Join,
+    serde_json::Error => Json,
+    opentelemetry::metrics::MetricsError => Metrics,
+    tokio::sync::oneshot::error::RecvError => Oneshot,
+    winit::error::OsError => OsError,
+    parquet::errors::ParquetError => Parquet,
+    tracing_subscriber::reload::Error => Reload,
+    wgpu::RequestDeviceError => RequestDevice,
+    wgpu::SurfaceError => Surface,
+    resvg::usvg::Error => Svg,
+    tiff::TiffError => Tiff,
+    crate::Overdue => Timeout,
+    toml::de::Error => TomlDe,
+    toml::ser::Error => TomlSer,
+    opentelemetry::trace::TraceError => Trace,
+    zip::result::ZipError => Zip,
+    tokio::sync::mpsc::error::SendError<accesskit_winit::Event> => Tokio,
+);
+
+// The desktop has a clipboard, global hotkeys and a store, and the browser does not.
+#[cfg(not(target_arch = "wasm32"))]
+blame_from!(
+    arboard::Error => Clipboard,
+    global_hotkey::Error => GlobalHotkey,
+    rusqlite::Error => Sqlite,
+);
+
+impl From<reqwest::Error> for Blame {
+    fn from(mut source: reqwest::Error) -> Self {
+        if let Some(url) = source.url_mut() {
+            let redacted = crate::Network::global().redact(url.as_str());
+            if let Ok(redacted) = reqwest::Url::parse(&redacted) {
+                *url = redacted;
+            }
+        }
+        Self::Http {
+            source,
+            context: None,
+        }
+    }
+}
+
+impl From<winit::event_loop::EventLoopClosed<crate::UserEvent>> for Blame {
+    fn from(source: winit::event_loop::EventLoopClosed<crate::UserEvent>) -> Self {
+        Self::EventLoopClosed {
+            source: Box::new(source),
+            context: None,
+        }
+    }
+}
+
+/// The `Context` trait adds a description of what we were doing to the error in a result, as
+/// described in the docs for [`Blame`].  It works on any result whose error converts into
+/// [`Blame`], so the context can go on before the question mark does the converting.
+pub trait Context<T> {
+    /// The `context` method adds `context` to the error, if there is one.
+    fn context(self, context: impl Into<String>) -> Arrive<T>;
+
+    /// The `with_context` method adds the context returned by `context` to the error, if there
+    /// is one.  Use it when building the context costs something, like a call to `format!`, so
+    /// we only pay on failure.
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Arrive<T>;
+}
+
+impl<T, E: Into<Blame>> Context<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Arrive<T> {
+        self.map_err(|e| e.into().with_context(context))
+    }
+
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Arrive<T> {
+        self.map_err(|e| e.into().with_context(context()))
+    }
 }
 
 /// The `Arrive` type is an alias of the [`Result`] type, using the common error type [`Blame`].
@@ -77,8 +863,6 @@ pub enum Blame {
 pub type Arrive<T> = Result<T, Blame>;
 
 /// The `Excuse` enum describes internal library error states.
-/// Currently the only variant is `NoFrames`, indicating the struct does not have a frame to pop
-/// from the `frames` field.
 #[derive(
     Debug,
     Copy,
@@ -92,7 +876,174 @@ pub type Arrive<T> = Result<T, Blame>;
     derive_more::Display,
 )]
 pub enum Excuse {
+    /// The `BundleEmpty` variant indicates a [`crate::Bundle`] had no files to export or import.
+    BundleEmpty,
+    /// The `ChecksumMismatch` variant indicates a [`crate::Download`] finished with a file that
+    /// does not match its checksum.
+    ChecksumMismatch,
+    /// The `ConfigExists` variant indicates we refused to write the default config over an
+    /// existing file.
+    ConfigExists,
+    /// The `ConfigFormat` variant indicates we refused to write TOML to a config file in another
+    /// format, like JSON.
+    ConfigFormat,
+    /// The `ConfigInvalid` variant indicates we refused to save config text that does not parse.
+    ConfigInvalid,
+    /// The `DownloadExists` variant indicates a [`crate::Download`] refused to write over a file
+    /// already at its target.
+    DownloadExists,
+    /// The `EmptyBinding` variant indicates a key binding in the config has no key.
+    EmptyBinding,
+    /// The `Encoder` variant indicates [`crate::FFMPEG`] is not installed, or failed to encode a
+    /// recording, in [`crate::Reel::start`] or [`crate::Reel::finish`].
+    Encoder,
+    /// The `ExportSize` variant indicates a picture of the map was asked for at a size the
+    /// graphics card cannot draw, in [`crate::Offscreen::new`].
+    ExportSize,
+    /// The `ExportFormat` variant indicates a headless [`crate::Export`] was asked to write a
+    /// file of a kind other than PNG or PDF.
+    ExportFormat,
+    /// The `FieldName` variant indicates the `[lookup]` settings name a field that is not a
+    /// plain field name, in [`crate::Parcels::new`].
+    FieldName,
+    /// The `NoAdapter` variant indicates no graphics card on the machine can draw on the window
+    /// given to [`crate::Gpu::canvas`].
+    NoAdapter,
+    /// The `NoAddress` variant indicates a [`crate::Geocoder`] was given a table with none of
+    /// the columns it reads addresses from.
+    NoAddress,
     /// The `NoFrames` variant indicates the struct does not have a frame to pop from the
     /// `frames` field.
     NoFrames,
+    /// The `NoMatch` variant indicates the service of a [`crate::Geocoder`] found no place for
+    /// an address.
+    NoMatch,
+    /// The `NoParcel` variant indicates [`crate::Parcels::find`] found no parcel for what it was
+    /// asked.
+    NoParcel,
+    /// The `NoRanges` variant indicates the server of a [`crate::Cog`] sent the whole file when
+    /// asked for part of it.
+    NoRanges,
+    /// The `NoService` variant indicates the [`crate::Catalog`] has no layer of the name the
+    /// `[lookup]` table gives for [`crate::Parcels`].
+    NoService,
+    /// The `NotGeometry` variant indicates text given to [`crate::Sketch::parse`] was neither
+    /// WKT nor GeoJSON.
+    NotGeometry,
+    /// The `NotPermalink` variant indicates text given to [`crate::Permalink::parse`] was not a
+    /// link to a view of the app.
+    NotPermalink,
+    /// The `NoResolution` variant indicates the tile schema of a [`crate::Map`] has no resolution
+    /// for the zoom level asked for.
+    NoResolution,
+    /// The `Projected` variant indicates a [`crate::Sketch`] has positions off the globe, most
+    /// likely because they are not in longitude and latitude.
+    Projected,
+    /// The `Readback` variant indicates the graphics card did not hand back the pixels of a
+    /// picture, in [`crate::Offscreen::render`].
+    Readback,
+    /// The `SceneLayout` variant indicates a [`crate::Cog`] holds a picture that the map cannot
+    /// draw, in a projection or with pixels of a kind it does not read.
+    SceneLayout,
+    /// The `Shader` variant indicates a shader from the `[effects]` table did not compile, in
+    /// [`crate::Effect::new`].
+    Shader,
+    /// The `Unavailable` variant indicates the app runs in a web browser, which lacks what was
+    /// asked for, like a file system or the system clipboard, as described in [`crate::run_web`].
+    Unavailable,
+    /// The `UnknownModifier` variant indicates a key binding in the config names a modifier key
+    /// that we do not recognize.
+    UnknownModifier,
+    /// The `UnsupportedFormat` variant indicates a [`crate::Dataset`] cannot read files of the
+    /// kind it was given.
+    UnsupportedFormat,
+    /// The `WeakMatch` variant indicates the service of a [`crate::Geocoder`] found a place for
+    /// an address, but scored the match too low to trust.
+    WeakMatch,
+}
+
+impl Excuse {
+    /// The `code` method returns the stable error code of the excuse, as described in the docs
+    /// for [`Blame`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BundleEmpty => "BEA-E040",
+            Self::ChecksumMismatch => "BEA-E049",
+            Self::ConfigExists => "BEA-E041",
+            Self::ConfigFormat => "BEA-E042",
+            Self::ConfigInvalid => "BEA-E043",
+            Self::DownloadExists => "BEA-E068",
+            Self::EmptyBinding => "BEA-E044",
+            Self::Encoder => "BEA-E053",
+            Self::ExportSize => "BEA-E051",
+            Self::ExportFormat => "BEA-E055",
+            Self::FieldName => "BEA-E071",
+            Self::NoAdapter => "BEA-E050",
+            Self::NoAddress => "BEA-E060",
+            Self::NoFrames => "BEA-E045",
+            Self::NoMatch => "BEA-E061",
+            Self::NoParcel => "BEA-E065",
+            Self::NoRanges => "BEA-E069",
+            Self::NoService => "BEA-E066",
+            Self::NoResolution => "BEA-E047",
+            Self::NotGeometry => "BEA-E063",
+            Self::NotPermalink => "BEA-E067",
+            Self::Projected => "BEA-E064",
+            Self::Readback => "BEA-E052",
+            Self::SceneLayout => "BEA-E070",
+            Self::Shader => "BEA-E054",
+            Self::Unavailable => "BEA-E056",
+            Self::UnknownModifier => "BEA-E046",
+            Self::UnsupportedFormat => "BEA-E048",
+            Self::WeakMatch => "BEA-E062",
+        }
+    }
+
+    /// The `severity` method sorts the excuse into a [`Severity`].  A window with no frame ready
+    /// will have one shortly, and everything else needs the user to change something.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::NoFrames => Severity::Transient,
+            _ => Severity::Error,
+        }
+    }
+
+    /// The `message` method returns a short description of the excuse for the user.
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::BundleEmpty => "There are no settings files to put in or take from the bundle.",
+            Self::ChecksumMismatch => "A download arrived damaged, and was thrown away.",
+            Self::ConfigExists => "A settings file already exists, and was left alone.",
+            Self::ConfigFormat => "Only TOML settings files can be edited or saved by the app.",
+            Self::ConfigInvalid => "The settings contain a mistake, so they were not saved.",
+            Self::DownloadExists => {
+                "A file of that name was already downloaded, and was left alone."
+            }
+            Self::EmptyBinding => "A shortcut in the settings has no key.",
+            Self::Encoder => "The recording could not be encoded.  Is ffmpeg installed?",
+            Self::ExportSize => "The picture is too large, or too small, to draw.",
+            Self::ExportFormat => "The map can only be exported as a PNG or PDF file.",
+            Self::FieldName => "A field name in the lookup settings is not a plain name.",
+            Self::NoAdapter => "No graphics card on this machine can draw the map.",
+            Self::NoAddress => "The table has no address column to geocode.",
+            Self::NoFrames => "A window had nothing ready to draw.",
+            Self::NoMatch => "The address could not be found.",
+            Self::NoParcel => "No parcel was found there, or by that address or tax lot number.",
+            Self::NoRanges => "The imagery server sends only whole files, too large to draw.",
+            Self::NoService => "The data catalog has no parcel or address service to ask.",
+            Self::NoResolution => "The map could not open at the configured zoom level.",
+            Self::NotGeometry => "The clipboard holds no WKT or GeoJSON geometry.",
+            Self::NotPermalink => "That is not a link to a view of the map.",
+            Self::Projected => "The geometry is not in longitude and latitude.",
+            Self::Readback => "The picture of the map could not be read back.",
+            Self::SceneLayout => "The scene is in a projection or format the map cannot draw.",
+            Self::Shader => "A custom shader has a mistake in it, so the map draws without it.",
+            Self::Unavailable => "This is not available in the web browser, only on the desktop.",
+            Self::UnknownModifier => {
+                "A shortcut in the settings uses a modifier key we do not know."
+            }
+            Self::UnsupportedFormat => "The app cannot read files of this kind yet.",
+            Self::WeakMatch => "The address was found, but the match needs a second look.",
+        }
+    }
 }
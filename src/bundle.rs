@@ -0,0 +1,301 @@
+use crate::{
+    Arrive, Context, Excuse, Home, Store, CONFIG_FILE, CONFIG_FORMATS, CONFIG_VERSION, PROFILES,
+    STORE_FILE,
+};
+use std::io::{Read, Write};
+use std::path;
+
+/// The `bundle` module provides the [`Bundle`] struct, which packs the setup of a user into a
+/// single zip file and unpacks it on another machine.
+///
+/// # Moving a setup between machines with `Bundle`
+///
+/// A user who has tuned their key bindings and saved their bookmarks on one machine wants the
+/// same setup on the next, and the analyst down the hall wants a copy too.  Copying files out of a
+/// platform config directory is a chore nobody does twice.  The `Bundle` gathers the files that
+/// make up a setup into a zip file with [`Bundle::export`], and [`Bundle::import`] puts them in
+/// place on the other end.  The `--export-bundle` and `--import-bundle` arguments of
+/// [`crate::Cli`] do the same from the command line.
+///
+/// A bundle holds:
+///
+/// * The config file from [`Home::config`], which carries the key bindings along with every
+///   other setting, under its own name, such as `config.toml`.
+/// * Each file in [`BUNDLE_FILES`] that exists in the data directory from [`Home::data`], which
+///   is the [`Store`] of bookmarks, recent files and saved queries.  We pack a copy the
+///   [`Store`] writes with [`Store::copy`], so a bundle made while the app runs is whole.
+/// * A [`MANIFEST`] recording the version of the app and of the config layout.
+///
+/// The [`crate::Secrets`] file never goes in a bundle, since bundles are made to be shared.  For
+/// the same reason, a config holding an API key, in the `[api]` table or the table of a
+/// [`crate::Profile`], goes in the bundle without it, as `config.toml` whatever its format, since
+/// we write the config again to leave the key out.
+///
+/// Importing writes the config to the user config directory from [`Home::user`], and the other
+/// files to the data directory.  Each file it replaces is first copied aside with a `.bak`
+/// extension.  We only unpack the names listed above, each up to [`BUNDLE_ENTRY_LIMIT`] bytes,
+/// so a bundle cannot write anywhere else on the machine, nor fill the disk.  An imported config
+/// from an older version of the app is upgraded by [`crate::migrate`] when the app next reads it.
+///
+/// * The `home` field holds the [`Home`] that locates the files.
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Bundle {
+    home: Home,
+}
+
+impl Bundle {
+    /// The `export` method writes the setup to a zip file at `path`, and returns the names of
+    /// the files it packed.
+    ///
+    /// Will [`Excuse::BundleEmpty`] if there is nothing to export, [`crate::Blame::Io`] if a file
+    /// cannot be read or written, and [`crate::Blame::Zip`] if the archive cannot be written.
+    #[tracing::instrument(skip(self))]
+    pub fn export(&self, path: &path::Path) -> Arrive<Vec<String>> {
+        let mut files = Vec::new();
+        let config = self.home.config();
+        if config.is_file() {
+            files.push(Self::config(&config)?);
+        }
+        let data = self.home.data();
+        for name in BUNDLE_FILES {
+            let file = data.join(name);
+            if !file.is_file() {
+                continue;
+            }
+            match Self::store(&file) {
+                Ok(bytes) => files.push((name.to_string(), bytes)),
+                Err(e) => tracing::warn!("Leaving {} out of the bundle: {e}", file.display()),
+            }
+        }
+        if files.is_empty() {
+            tracing::warn!("No setup to export.");
+            return Err(Excuse::BundleEmpty.into());
+        }
+
+        let archive =
+            std::fs::File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        let mut zip = zip::ZipWriter::new(archive);
+        let options = zip::write::SimpleFileOptions::default();
+        let mut names = Vec::new();
+        for (name, bytes) in files {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(&bytes)?;
+            tracing::trace!("Packed {name}.");
+            names.push(name);
+        }
+        zip.start_file(MANIFEST, options)?;
+        zip.write_all(Self::manifest(&names).as_bytes())?;
+        zip.finish()?;
+        tracing::info!("Exported {} files to {}.", names.len(), path.display());
+        Ok(names)
+    }
+
+    /// The `import` method unpacks the zip file at `path` into the user config and data
+    /// directories, and returns the paths of the files it wrote.  Entries with names we do not
+    /// expect, or larger than [`BUNDLE_ENTRY_LIMIT`], are skipped with a warning.
+    ///
+    /// Will [`Excuse::BundleEmpty`] if the archive holds no files we expect, [`crate::Blame::Io`]
+    /// if a file cannot be read or written, and [`crate::Blame::Zip`] if the archive cannot be
+    /// read.
+    #[tracing::instrument(skip(self))]
+    pub fn import(&self, path: &path::Path) -> Arrive<Vec<path::PathBuf>> {
+        let archive =
+            std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        let mut archive =
+            zip::ZipArchive::new(archive).with_context(|| format!("reading {}", path.display()))?;
+        let mut entries = Vec::new();
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            let name = entry.name().to_string();
+            if name == MANIFEST {
+                continue;
+            }
+            if !Self::expected(&name) {
+                tracing::warn!("Skipping {name} in the bundle.");
+                continue;
+            }
+            // The size in the archive is only what the archive claims, so we count as we read.
+            let mut bytes = Vec::new();
+            (&mut entry)
+                .take(BUNDLE_ENTRY_LIMIT + 1)
+                .read_to_end(&mut bytes)?;
+            if bytes.len() as u64 > BUNDLE_ENTRY_LIMIT {
+                tracing::warn!(
+                    "Skipping {name} in the bundle: larger than {BUNDLE_ENTRY_LIMIT} bytes."
+                );
+                continue;
+            }
+            entries.push((name, bytes));
+        }
+        if entries.is_empty() {
+            tracing::warn!("Nothing to import from {}.", path.display());
+            return Err(Excuse::BundleEmpty.into());
+        }
+
+        let user = self.home.user();
+        std::fs::create_dir_all(&user)?;
+        let data = self.home.data();
+        let mut written = Vec::new();
+        for (name, bytes) in entries {
+            let target = match Self::is_config(&name) {
+                true => user.join(&name),
+                false => data.join(&name),
+            };
+            if Self::is_config(&name) {
+                // Only one config per directory counts, so set aside any config in another format
+                // that would shadow the new one.
+                for format in CONFIG_FORMATS {
+                    let other = user.join(CONFIG_FILE).with_extension(format);
+                    if other.is_file() && other != target {
+                        Self::backup(&other)?;
+                        std::fs::remove_file(&other)?;
+                    }
+                }
+            }
+            if target.is_file() {
+                Self::backup(&target)?;
+            }
+            std::fs::write(&target, bytes)
+                .with_context(|| format!("unpacking {}", target.display()))?;
+            tracing::trace!("Unpacked {}.", target.display());
+            written.push(target);
+        }
+        tracing::info!("Imported {} files from {}.", written.len(), path.display());
+        Ok(written)
+    }
+
+    /// The `expected` method returns `true` if `name` is a file a bundle may hold.  Names with
+    /// a directory in them never are.
+    fn expected(name: &str) -> bool {
+        Self::is_config(name) || BUNDLE_FILES.contains(&name)
+    }
+
+    /// The `is_config` method returns `true` if `name` is the config file, in any of the
+    /// [`CONFIG_FORMATS`].
+    fn is_config(name: &str) -> bool {
+        CONFIG_FORMATS
+            .iter()
+            .any(|format| name == format!("config.{format}"))
+    }
+
+    /// The `config` method returns the name and contents of the config file at `path` for the
+    /// bundle.  A config holding an API key comes back as `config.toml` without the key, as
+    /// described in the [`Bundle`] docs, and any other as it is.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be read, [`crate::Blame::Config`] if it
+    /// cannot be parsed, and [`crate::Blame::TomlSer`] if it cannot be written as TOML.
+    fn config(path: &path::Path) -> Arrive<(String, Vec<u8>)> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| CONFIG_FILE.to_string());
+        let bytes = std::fs::read(path).with_context(|| format!("packing {}", path.display()))?;
+        let mut table = config::Config::builder()
+            .add_source(config::File::from(path))
+            .build()
+            .and_then(|config| config.try_deserialize::<toml::Table>())
+            .with_context(|| format!("reading {}", path.display()))?;
+        if !Self::strip(&mut table) {
+            return Ok((name, bytes));
+        }
+        tracing::info!("Leaving the API key out of the bundled config.");
+        let text = toml::to_string_pretty(&table)?;
+        Ok((CONFIG_FILE.to_string(), text.into_bytes()))
+    }
+
+    /// The `strip` method removes the API key from the `[api]` table of `table`, and from that
+    /// of each profile, and returns `true` if there was one to remove.
+    fn strip(table: &mut toml::Table) -> bool {
+        let mut stripped = false;
+        let mut drop_key = |table: &mut toml::Table| {
+            if let Some(toml::Value::Table(api)) = table.get_mut("api") {
+                stripped |= api.remove("key").is_some();
+            }
+        };
+        drop_key(table);
+        if let Some(toml::Value::Table(profiles)) = table.get_mut(PROFILES) {
+            for profile in profiles.values_mut() {
+                if let toml::Value::Table(profile) = profile {
+                    drop_key(profile);
+                }
+            }
+        }
+        stripped
+    }
+
+    /// The `store` method returns a whole copy of the [`Store`] at `path`, written through
+    /// [`Store::copy`] to a file in the temporary directory, which it then removes.
+    ///
+    /// Will fail as [`Store::open`] and [`Store::copy`] do, and [`crate::Blame::Io`] if the copy
+    /// cannot be read.
+    fn store(path: &path::Path) -> Arrive<Vec<u8>> {
+        let copy = std::env::temp_dir().join(format!("{STORE_FILE}-{}", std::process::id()));
+        // `VACUUM INTO` will not write over a file, as one left by an earlier run that failed.
+        std::fs::remove_file(&copy).ok();
+        Store::open(path)?.copy(&copy)?;
+        let bytes = std::fs::read(&copy).with_context(|| format!("packing {}", copy.display()));
+        if let Err(e) = std::fs::remove_file(&copy) {
+            tracing::warn!("Could not remove {}: {e}", copy.display());
+        }
+        bytes
+    }
+
+    /// The `backup` method copies the file at `path` aside, adding `.bak` to its name.
+    fn backup(path: &path::Path) -> Arrive<()> {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        std::fs::copy(path, &backup)?;
+        tracing::info!("Backed up {} before importing.", path.display());
+        Ok(())
+    }
+
+    /// The `manifest` method describes the bundle holding `names`, in TOML.
+    fn manifest(names: &[String]) -> String {
+        let files = names
+            .iter()
+            .map(|name| format!("\"{name}\""))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!(
+            "app_version = \"{}\"\nconfig_version = {CONFIG_VERSION}\nfiles = [{files}]\n",
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
+
+/// The `BUNDLE_FILES` constant lists the files besides the config that a [`Bundle`] carries from
+/// the data directory, when they exist.
+pub const BUNDLE_FILES: [&str; 1] = [STORE_FILE];
+
+/// The `BUNDLE_ENTRY_LIMIT` constant holds the most bytes [`Bundle::import`] unpacks from a
+/// single entry of a bundle, 64 megabytes, far past any config or store of bookmarks.
+pub const BUNDLE_ENTRY_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// The `MANIFEST` constant holds the name of the file describing a [`Bundle`].
+pub const MANIFEST: &str = "bundle.toml";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_stay_out_of_the_bundle() {
+        let mut table =
+            "exit = \"DoubleEscape\"\n[api]\nkey = \"a\"\n[profile.kiosk.api]\nkey = \"b\""
+                .parse::<toml::Table>()
+                .expect("config");
+        assert!(Bundle::strip(&mut table));
+        let text = toml::to_string(&table).expect("toml");
+        assert!(!text.contains("key ="));
+        assert!(text.contains("DoubleEscape"));
+        assert!(!Bundle::strip(&mut table));
+    }
+
+    #[test]
+    fn only_known_names_unpack() {
+        assert!(Bundle::expected("config.toml"));
+        assert!(Bundle::expected(STORE_FILE));
+        assert!(!Bundle::expected("../config.toml"));
+        assert!(!Bundle::expected("bookmarks.toml"));
+    }
+}
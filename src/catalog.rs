@@ -0,0 +1,400 @@
+use crate::{Arrive, Context, Dataset, Extent, Fetch, Job, Listings, Network};
+use std::io::Write;
+use std::path;
+
+/// The `catalog` module provides the [`Catalog`] struct, which lists the regional data the app
+/// knows where to find, the [`Listing`] struct, which holds one entry of it, and the [`Feed`]
+/// enum, which names the kinds of service an entry reads from.
+///
+/// # Adding regional data with `Catalog`
+///
+/// Most of the people at the city who want a map are not GIS staff.  They know they want the
+/// parcels and the floodplain under their permits, but not that the parcels live at the end of a
+/// long ArcGIS REST address, or how to ask it for GeoJSON.  Until now they asked someone who
+/// did, and waited.  The `Catalog` keeps the addresses of the layers asked for most, so that
+/// adding one takes a few keys, with the listing in a [`crate::Panel`] over the map:
+///
+/// * [`crate::Act::ShowCatalog`] shows the layers, by the group that publishes them, with a
+///   marker on the current one.
+/// * [`crate::Act::NextCatalogEntry`] moves the marker to the next layer and shows its name.  A
+///   count prefix skips ahead, so `3` before the key moves three layers on.
+/// * [`crate::Act::AddFromCatalog`] fetches the current layer for the ground in view of the
+///   focused map, as an interactive job of the [`crate::Tasks`] registry, and opens it as a
+///   table, like a file dropped on a window, drawn on the map as a [`crate::Figure`].
+///
+/// The one built-in layer is the flood hazard zones of the National Flood Hazard Layer, which
+/// FEMA publishes as a public map service.  The layers of the city and the
+/// county, like the parcels, zoning and site addresses, come from the `[catalog]` table of the
+/// config (see [`Listings`]), which a deployment fills in with the addresses its GIS staff
+/// stand behind.  A layer of the same name as the built-in one replaces it, so a deployment can
+/// follow a service that moves without waiting for a release, and setting `builtin = false`
+/// drops it altogether.
+///
+/// Each [`Listing`] reads from a [`Feed`].  A layer of an ArcGIS map or feature service asks the
+/// `query` operation of the layer for the features crossing the ground in view, in geographic
+/// coordinates, as GeoJSON.  A service hands back only so many features at a time, and says so
+/// with `exceededTransferLimit`, so we ask again from where the last page left off until it
+/// stops saying so, or until we have read [`CATALOG_PAGES`] pages, in case a server ignores the
+/// offset and sends the first page forever.  A plain GeoJSON file is fetched whole.  Either
+/// way, the features go to a file under [`CATALOG_DIR`] in the data directory, named after the
+/// layer, and open from there with [`Dataset::load`].  Adding the same layer again fetches it
+/// afresh and replaces the open table, as when the refresher reloads a changed file.
+///
+/// * The `listings` field holds the layers of the catalog, in order by group and name.
+/// * The `cursor` field holds the index of the current layer.
+#[derive(Debug, Clone, Default, PartialEq, derive_getters::Getters)]
+pub struct Catalog {
+    listings: Vec<Listing>,
+    cursor: usize,
+}
+
+impl Catalog {
+    /// The `new` method creates the catalog from the built-in layers and those of `settings`,
+    /// as described in the [`Catalog`] docs, with the first layer current.
+    pub fn new(settings: &Listings) -> Self {
+        let mut listings = Vec::new();
+        if *settings.builtin() {
+            listings.extend(BUILT_IN.iter().map(|(name, group, feed, url, about)| {
+                Listing::new(
+                    name.to_string(),
+                    group.to_string(),
+                    *feed,
+                    url.to_string(),
+                    about.to_string(),
+                )
+            }));
+        }
+        for (name, listing) in settings.layers() {
+            listings.retain(|built: &Listing| built.name() != name);
+            listings.push(listing.clone().with_name(name));
+        }
+        listings.sort_by(|a, b| (a.group(), a.name()).cmp(&(b.group(), b.name())));
+        Self {
+            listings,
+            cursor: 0,
+        }
+    }
+
+    /// The `with_current` method makes the layer called `name` current, if there is one, so
+    /// that reloading the config leaves the marker where it was.
+    pub fn with_current(mut self, name: &str) -> Self {
+        if let Some(cursor) = self
+            .listings
+            .iter()
+            .position(|listing| listing.name() == name)
+        {
+            self.cursor = cursor;
+        }
+        self
+    }
+
+    /// The `current` method returns the current layer, or [`None`] if the catalog is empty.
+    pub fn current(&self) -> Option<&Listing> {
+        self.listings.get(self.cursor)
+    }
+
+    /// The `advance` method makes the next layer current, going round to the first after the
+    /// last, and returns it, or [`None`] if the catalog is empty.
+    pub fn advance(&mut self) -> Option<&Listing> {
+        if self.listings.is_empty() {
+            return None;
+        }
+        self.cursor = (self.cursor + 1) % self.listings.len();
+        self.current()
+    }
+}
+
+/// Prints the layers of the catalog, one per line, with a marker on the current one, or says
+/// the catalog is empty.
+impl std::fmt::Display for Catalog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.listings.is_empty() {
+            return write!(f, "The data catalog is empty.");
+        }
+        let lines = self
+            .listings
+            .iter()
+            .enumerate()
+            .map(|(index, listing)| {
+                let marker = if index == self.cursor { ">" } else { " " };
+                format!("{marker} {}. {listing}", index + 1)
+            })
+            .collect::<Vec<String>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// The `Listing` struct holds one layer of the [`Catalog`], as it appears in the `[catalog]`
+/// table of the config.
+///
+/// ```toml
+/// [catalog.layers."Storm drains"]
+/// group = "City of Grants Pass"
+/// feed = "Arcgis"
+/// url = "https://gis.example.gov/arcgis/rest/services/Storm/MapServer/2"
+/// about = "Storm drain inlets and catch basins."
+/// ```
+///
+/// * The `name` field holds the name of the layer, taken from its key in the config.
+/// * The `group` field holds the name of the group that publishes the layer.
+/// * The `feed` field holds the [`Feed`] the layer reads from.
+/// * The `url` field holds the address of the layer, or of the GeoJSON file.
+/// * The `about` field holds a sentence describing the layer.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    derive_getters::Getters,
+    derive_new::new,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Listing {
+    #[serde(skip)]
+    name: String,
+    group: String,
+    feed: Feed,
+    url: String,
+    about: String,
+}
+
+impl Listing {
+    /// The `with_name` method sets the name of the layer.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// The `file` method returns the name of the file the layer goes to, made from its name in
+    /// lower case, with a dash for anything but a letter or a digit.
+    pub fn file(&self) -> String {
+        let stem = self
+            .name
+            .chars()
+            .map(|c| match c.is_alphanumeric() {
+                true => c.to_ascii_lowercase(),
+                false => '-',
+            })
+            .collect::<String>();
+        format!("{}.geojson", stem.trim_matches('-'))
+    }
+
+    /// The `add` method fetches the features of the layer crossing `extent`, reporting to `job`,
+    /// writes them to a file in `dir`, and reads the file back as a table, as described in the
+    /// [`Catalog`] docs.
+    ///
+    /// Will [`crate::Blame::Http`] if the service cannot be reached or answers with an error,
+    /// [`crate::Blame::Timeout`] if it stops answering, once retries are spent,
+    /// [`crate::Blame::Io`] if the file cannot be written, and otherwise fails as
+    /// [`Dataset::load`] does.
+    #[tracing::instrument(skip_all, fields(name = %self.name))]
+    pub async fn add(&self, extent: Extent, dir: path::PathBuf, job: Job) -> Arrive<Dataset> {
+        let features = match self.feed {
+            Feed::Arcgis => {
+                let mut features = Vec::new();
+                for pages in 1.. {
+                    let query = self.feed.query(&extent, features.len());
+                    let body = self.fetch(&query).await?;
+                    let page = body
+                        .get("features")
+                        .and_then(serde_json::Value::as_array)
+                        .cloned()
+                        .unwrap_or_default();
+                    // The flag sits on top in some versions of the server, and under the
+                    // properties in others.
+                    let more = [
+                        "/exceededTransferLimit",
+                        "/properties/exceededTransferLimit",
+                    ]
+                    .iter()
+                    .any(|pointer| body.pointer(pointer) == Some(&serde_json::Value::Bool(true)));
+                    let done = page.is_empty() || !more;
+                    features.extend(page);
+                    if done {
+                        break;
+                    }
+                    if pages == CATALOG_PAGES {
+                        tracing::warn!(
+                            "Stopped after {pages} pages of {}, with more to read.",
+                            self.name
+                        );
+                        break;
+                    }
+                }
+                features
+            }
+            Feed::Geojson => self
+                .fetch(&[])
+                .await?
+                .get("features")
+                .and_then(serde_json::Value::as_array)
+                .cloned()
+                .unwrap_or_default(),
+        };
+        tracing::info!("Fetched {} features of {}.", features.len(), self.name);
+
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        let path = dir.join(self.file());
+        let collection = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features,
+        });
+        let file =
+            std::fs::File::create(&path).with_context(|| format!("writing {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &collection)?;
+        writer
+            .flush()
+            .with_context(|| format!("writing {}", path.display()))?;
+        Dataset::load(path, job).await
+    }
+
+    /// The `fetch` method asks the service for the layer with the `query` parameters, retrying
+    /// while the failure is worth retrying, and returns the body of the answer.  For an ArcGIS
+    /// layer, the parameters go to its `query` operation, which is how [`crate::Parcels`] asks
+    /// for one feature at a time.
+    ///
+    /// Will [`crate::Blame::Http`] if the service cannot be reached or answers with an error,
+    /// and [`crate::Blame::Timeout`] if it stops answering, once retries are spent.
+    pub async fn fetch(&self, query: &[(&str, String)]) -> Arrive<serde_json::Value> {
+        let mut attempt = 0;
+        loop {
+            let blame = match self.ask(query).await {
+                Ok(body) => return Ok(body),
+                Err(blame) => blame,
+            };
+            attempt += 1;
+            match blame.retry_after(attempt) {
+                Some(delay) => {
+                    tracing::info!(
+                        "Fetching {} interrupted, retrying in {delay:?}: {blame}",
+                        self.name
+                    );
+                    crate::sleep(delay).await;
+                }
+                None => return Err(blame),
+            }
+        }
+    }
+
+    /// The `ask` method sends one request for the layer to the service.  The span is named for
+    /// [`crate::REQUEST_SPAN`], so [`crate::Latency`] times it, and the request goes through
+    /// [`Network::send`], so it shows up in the network log.
+    ///
+    /// Will [`crate::Blame::Http`] if the request fails or the service answers with an error.
+    #[tracing::instrument(name = "request", skip_all)]
+    async fn ask(&self, query: &[(&str, String)]) -> Arrive<serde_json::Value> {
+        let url = match self.feed {
+            Feed::Arcgis => format!("{}/query", self.url.trim_end_matches('/')),
+            Feed::Geojson => self.url.clone(),
+        };
+        let request = Network::global().client().get(&url).query(query);
+        let body = Network::global()
+            .send(request, Fetch::Requests)
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?;
+        Ok(body)
+    }
+}
+
+/// Prints the group, name and description of the layer on one line.
+impl std::fmt::Display for Listing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.group, self.name)?;
+        if !self.about.is_empty() {
+            write!(f, ", {}", self.about)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Feed` enum names the kinds of service a [`Listing`] reads from, chosen by its `feed`
+/// key.
+///
+/// * The `Arcgis` variant reads a layer of an ArcGIS map or feature service, through its `query`
+///   operation, and is the default.  The `url` of the listing ends in the number of the layer,
+///   as in `.../MapServer/0`.
+/// * The `Geojson` variant reads a GeoJSON file, whole.
+///
+/// The config takes the names in lowercase too, as in `feed = "geojson"`.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Feed {
+    /// The `Arcgis` variant indicates an ArcGIS service layer.
+    #[default]
+    #[serde(alias = "arcgis")]
+    Arcgis,
+    /// The `Geojson` variant indicates a GeoJSON file.
+    #[serde(alias = "geojson")]
+    Geojson,
+}
+
+impl Feed {
+    /// The `query` method returns the query parameters asking the service for the features
+    /// crossing `extent`, in geographic coordinates, as GeoJSON, starting after the first
+    /// `offset` features.  A GeoJSON file takes no parameters.
+    pub fn query(&self, extent: &Extent, offset: usize) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Arcgis => vec![
+                ("where", "1=1".to_string()),
+                ("outFields", "*".to_string()),
+                (
+                    "geometry",
+                    format!(
+                        "{},{},{},{}",
+                        extent.west(),
+                        extent.south(),
+                        extent.east(),
+                        extent.north()
+                    ),
+                ),
+                ("geometryType", "esriGeometryEnvelope".to_string()),
+                ("inSR", "4326".to_string()),
+                ("spatialRel", "esriSpatialRelIntersects".to_string()),
+                ("outSR", "4326".to_string()),
+                ("resultOffset", offset.to_string()),
+                ("f", "geojson".to_string()),
+            ],
+            Self::Geojson => Vec::new(),
+        }
+    }
+}
+
+/// The `CATALOG_DIR` constant holds the name of the directory under [`crate::Home::data`] where
+/// layers added from the [`Catalog`] go.
+pub const CATALOG_DIR: &str = "catalog";
+
+/// The `CATALOG_PAGES` constant holds the most pages of features [`Listing::add`] reads from an
+/// ArcGIS layer before it stops, with however many features it has.
+pub const CATALOG_PAGES: usize = 100;
+
+/// The `BUILT_IN` constant holds the layers the [`Catalog`] ships with, as name, group, feed,
+/// address and description.
+///
+/// The flood hazard zones are layer 28, `Flood Hazard Zones`, of the `NFHL` map service that
+/// FEMA lists among the ways to reach the National Flood Hazard Layer, at
+/// <https://www.fema.gov/flood-maps/national-flood-hazard-layer>.  The service directory at
+/// <https://hazards.fema.gov/arcgis/rest/services/public/NFHL/MapServer> names each layer by
+/// number.  A layer we cannot point to a publisher for does not belong here.
+const BUILT_IN: [(&str, &str, Feed, &str, &str); 1] = [(
+    "Floodplain",
+    "FEMA",
+    Feed::Arcgis,
+    "https://hazards.fema.gov/arcgis/rest/services/public/NFHL/MapServer/28",
+    "flood hazard zones of the National Flood Hazard Layer",
+)];
@@ -0,0 +1,151 @@
+use std::path;
+
+/// The `cli` module provides the [`Cli`] struct, which holds the arguments passed to the app on
+/// the command line.
+///
+/// # Launching the app with `Cli`
+///
+/// We use the derive API of the [`clap`] crate, which turns the fields of the struct into
+/// arguments and the doc comments on the fields into the `--help` text.  The arguments are:
+///
+/// * `--config <path>` reads the config from `path` instead of searching for it (see
+///   [`crate::Home`]).  The file may be TOML, YAML or JSON, going by the extension.  Key
+///   bindings changed at runtime are saved back to the same file, if it is TOML.
+/// * `--open <file>` opens a data file on startup.  Repeat the argument to open several files.
+/// * `--workspace <file>` restores the data files and view saved in a [`crate::Workspace`] on
+///   startup.
+/// * `--log-level <level>` sets the log filter, in the syntax of the `RUST_LOG` environment
+///   variable, which it replaces.
+/// * `--log-format <format>` writes the log as `text` or `json` (see [`crate::LogFormat`]).
+/// * `--headless` runs without opening any windows, keeping the background agents at work, or
+///   with `--export`, draws one picture of the map and exits.
+/// * `--profile <name>` applies the named profile from the config (see [`crate::Profile`]).
+/// * `--write-default-config [path]` writes a commented config file listing every setting (see
+///   [`crate::default_config`]) to `path`, or to the usual config location, and exits.
+/// * `--export <path>` runs headless, draws the map and the data files to open, writes the picture
+///   to `path` as a PNG or PDF file, then exits, for scheduled map making on a server (see
+///   [`crate::Export`]).  `--extent <west,south,east,north>` sets the ground it covers and
+///   `--size <widthxheight>` the size of the picture.
+/// * `--export-bundle <path>` and `--import-bundle <path>` pack the setup of the user into a zip
+///   file, or unpack one, and exit (see [`crate::Bundle`]).
+///
+/// Any other argument names a data file to open, as with `--open`, as in
+/// `bea_egui parcels.gpkg`, in whatever format [`crate::Dataset`] or a [`crate::DataProvider`]
+/// reads.  This is how the desktop hands over a file when the user picks the app from "Open
+/// with" on Windows or Linux.  The app does not register itself for any file type, so a
+/// double-click opens it only where the user or an installer has made it the default.  A file it
+/// cannot read fails with [`crate::Excuse::UnsupportedFormat`] in the first window, rather than
+/// stopping the app.  A link to a view, starting with [`crate::PERMALINK_SCHEME`], opens in the
+/// first window instead, as described in [`crate::Permalink`], and of several, the last wins.
+/// A workspace file, told apart by [`crate::Workspace::is_workspace`], is restored as with
+/// `--workspace`, unless that is given too.
+///
+/// The Finder on macOS sends an open-file event rather than an argument, which the
+/// [`crate::Finder`] hands on to the app once it runs.
+///
+/// Some of these have a matching key in the config file, so that a deployment can set them once.
+/// Arguments given on the command line win, because [`Cli::overrides`] writes them into the
+/// config as overrides, which [`config`] ranks above every source.
+#[derive(Debug, Default, Clone, clap::Parser, derive_getters::Getters)]
+#[command(version, about)]
+pub struct Cli {
+    /// Read the config from this file instead of searching for one.
+    #[arg(long, value_name = "PATH")]
+    config: Option<path::PathBuf>,
+    /// Open a data file on startup.  May be repeated.
+    #[arg(long, value_name = "FILE")]
+    open: Vec<path::PathBuf>,
+    /// Restore a saved workspace on startup.
+    #[arg(long, value_name = "FILE")]
+    workspace: Option<path::PathBuf>,
+    /// Set the log filter, as in RUST_LOG (e.g. "bea_egui=debug").
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<String>,
+    /// Write the log as plain text, or as JSON lines for log shippers.
+    #[arg(long, value_name = "FORMAT")]
+    log_format: Option<crate::LogFormat>,
+    /// Run without opening any windows.  With --export, draw the map once and exit.
+    #[arg(long)]
+    headless: bool,
+    /// Apply the named profile from the config, as in [profile.NAME].
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Write a commented default config to PATH, or the usual location, and exit.
+    #[arg(long, value_name = "PATH", num_args = 0..=1)]
+    write_default_config: Option<Option<path::PathBuf>>,
+    /// Run headless, draw the map and data files, write them to PATH as .png or .pdf, and exit.
+    #[arg(long, value_name = "PATH")]
+    export: Option<path::PathBuf>,
+    /// The ground to export, in degrees, as WEST,SOUTH,EAST,NORTH.
+    #[arg(long, value_name = "EXTENT", requires = "export", allow_hyphen_values = true,
+          value_parser = crate::Extent::parse)]
+    extent: Option<crate::Extent>,
+    /// The size of the exported picture in pixels, as WIDTHxHEIGHT.
+    #[arg(long, value_name = "SIZE", requires = "export", value_parser = crate::Export::size)]
+    size: Option<(u32, u32)>,
+    /// Pack the config, bookmarks, queries and styles into a zip file at PATH, and exit.
+    #[arg(long, value_name = "PATH")]
+    export_bundle: Option<path::PathBuf>,
+    /// Unpack a zip file made with --export-bundle into the user config directory, and exit.
+    #[arg(long, value_name = "PATH", conflicts_with = "export_bundle")]
+    import_bundle: Option<path::PathBuf>,
+    /// Data files or a link to a view to open on startup, as passed by the desktop.
+    #[arg(value_name = "FILE")]
+    files: Vec<path::PathBuf>,
+}
+
+impl Cli {
+    /// The `overrides` method writes the arguments that have a matching config key into
+    /// `builder` as overrides, so that they take precedence over the config file.  Arguments the
+    /// user did not pass leave the config alone.  The keys are `open`, `workspace`, `log_level`,
+    /// `log_format` and `headless`, which `--export` also sets.  The files passed without a flag
+    /// join `open`, or set `workspace` if they are workspaces, as described in the [`Cli`] docs,
+    /// and links to a view are left for [`Cli::permalink`].
+    ///
+    /// Will [`crate::Blame::Config`] if [`config::ConfigBuilder::set_override`] rejects a value.
+    pub fn overrides(
+        &self,
+        mut builder: config::ConfigBuilder<config::builder::DefaultState>,
+    ) -> crate::Arrive<config::ConfigBuilder<config::builder::DefaultState>> {
+        let (workspaces, files): (Vec<_>, Vec<_>) = self
+            .files
+            .iter()
+            .filter(|path| !crate::Permalink::is_permalink(&path.to_string_lossy()))
+            .partition(|path| crate::Workspace::is_workspace(path));
+        let open = self
+            .open
+            .iter()
+            .chain(files)
+            .map(|path| path.display().to_string())
+            .collect::<Vec<String>>();
+        if !open.is_empty() {
+            builder = builder.set_override("open", open)?;
+        }
+        if let Some(workspace) = self.workspace.as_ref().or(workspaces.last().copied()) {
+            builder = builder.set_override("workspace", workspace.display().to_string())?;
+        }
+        if let Some(level) = &self.log_level {
+            builder = builder.set_override("log_level", level.as_str())?;
+        }
+        if let Some(format) = &self.log_format {
+            builder = builder.set_override("log_format", format.to_string())?;
+        }
+        if self.headless || self.export.is_some() {
+            builder = builder.set_override("headless", true)?;
+        }
+        Ok(builder)
+    }
+
+    /// The `permalink` method returns the last link to a view passed without a flag, as
+    /// described in the [`Cli`] docs, or [`None`] if there is none.
+    ///
+    /// Will [`crate::Excuse::NotPermalink`] if the link does not describe a view.
+    pub fn permalink(&self) -> Option<crate::Arrive<crate::Permalink>> {
+        self.files
+            .iter()
+            .rev()
+            .map(|path| path.to_string_lossy())
+            .find(|text| crate::Permalink::is_permalink(text))
+            .map(|text| crate::Permalink::parse(&text))
+    }
+}
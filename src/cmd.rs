@@ -1,6 +1,10 @@
-use crate::Act;
-use std::collections::HashMap;
+use crate::{
+    Act, Arrive, Chord, Context, Keybindings, Stroke, CONFIG_VERSION, DOUBLE, HOLD, VERSION,
+};
+use std::collections::{HashMap, HashSet};
+use std::path;
 use strum::IntoEnumIterator;
+use web_time as time;
 use winit::{event, keyboard};
 
 /// The `cmd` module maps keyboard input from the user to variants of the [`Act`] enum as the
@@ -13,74 +17,800 @@ use winit::{event, keyboard};
 /// the [`derive_more`] crate to implement [`derive_more::Deref`], and [`derive_more::DerefMut`],
 /// so that we can easily access the methods of the underlying [`HashMap`].
 ///
-/// We lean on the [`derive_new`] crate for a boilerplate implementation of the [`Cmd::new`]
-/// method.
-///
 /// Maybe this should be named `Command`, but I do not feel like doing the extra typing today.
+///
+/// ## Update 0.1.2
+///
+/// The keys of the [`HashMap`] are now of type [`Stroke`] instead of [`String`], so that bindings
+/// can include modifier keys like `Ctrl+Shift+N`.
+///
+/// ## Chord sequences
+///
+/// The keys are now of type [`Chord`], a sequence of one or more strokes, so that bindings like
+/// `g b` work.  This turns `Cmd` into a small state machine.  The `pending` field holds the strokes
+/// entered so far in an unfinished sequence, and the `since` field records when the most recent
+/// stroke arrived.  If the user waits longer than the `timeout` between strokes, we forget the
+/// pending sequence, at the next stroke or at [`Cmd::lapse`], whichever comes first.  While a
+/// sequence is pending, [`Cmd::hints`] lists the ways to finish it, which the [`crate::App`]
+/// shows in a "which key" style [`crate::Panel`] over the map.
+///
+/// ## Mouse bindings
+///
+/// Mouse buttons bind through the same config syntax as keys, using names like `MouseMiddle` or
+/// `Ctrl+DoubleMouseLeft` (see [`Stroke::from_mouse`]).  The `last_click` field remembers the
+/// most recent button press, so that [`Cmd::click`] can recognize a double-click.
+///
+/// ## Count prefixes
+///
+/// Typing digits before a binding sets a count, so that `3` followed by the binding for
+/// [`Act::NewWindow`] opens three windows.  The `count` field holds the count typed so far, which
+/// the [`crate::App`] shows in the same panel, and [`Cmd::take_count`] hands it to the app when
+/// the binding arrives.  Digits only count when they are not themselves the start of a binding.
+///
+/// ## Double-press and hold
+///
+/// Bindings with the [`DOUBLE`] prefix fire on the second press of a key within `double_press`
+/// of the first, tracked in the `last_key` field.  Like a double-click, the first press still
+/// fires any plain binding for the key.
+///
+/// Bindings with the [`HOLD`] prefix fire once the key has been held down for `hold`.  While a
+/// key with a hold binding is down, the `holding` field tracks it, by its physical key, so that
+/// two keys held at once each get their release, and the [`crate::App`] asks [`Cmd::poll_hold`]
+/// whether the time is up.  When the key comes back up, [`Cmd::release`]
+/// fires the hold binding a second time, so that a toggle like [`Act::ToggleInk`] lasts only as
+/// long as the key is down.  A key released before the time is up counts as an ordinary press.
+/// Hold bindings apply to single strokes, not to the end of a sequence.
+///
+/// The timing comes from the optional `[timing]` table in the config, in milliseconds:
+///
+/// ```toml
+/// [timing]
+/// double_press = 300
+/// hold = 400
+/// chord = 1500
+/// ```
 #[derive(
-    Debug, Default, Clone, PartialEq, Eq, derive_new::new, derive_more::Deref, derive_more::DerefMut,
+    Debug, Clone, PartialEq, Eq, derive_more::Deref, derive_more::DerefMut, derive_getters::Getters,
 )]
-pub struct Cmd(HashMap<String, Act>);
+pub struct Cmd {
+    #[deref]
+    #[deref_mut]
+    bindings: HashMap<Chord, Act>,
+    count: Option<usize>,
+    double_press: time::Duration,
+    hold: time::Duration,
+    #[getter(skip)]
+    holding: HashMap<keyboard::PhysicalKey, Held>,
+    last_click: Option<(event::MouseButton, time::Instant)>,
+    last_key: Option<(Stroke, time::Instant)>,
+    pending: Chord,
+    since: Option<time::Instant>,
+    timeout: time::Duration,
+}
 
 impl Cmd {
-    /// Given an incoming [`event::KeyEvent`] from the [`winit`] crate, the `act` method checks the
-    /// [`HashMap`] in `Self` to determine if the key event maps to an [`Act`] variant.
-    pub fn act(&self, event: &event::KeyEvent) -> Option<Act> {
-        match event.logical_key.as_ref() {
-            keyboard::Key::Named(k) => {
-                let key = format!("{k:?}");
-                if let Some(act) = self.get(&key) {
-                    tracing::trace!("Act detected: {act}");
-                    Some(act.clone())
-                } else {
-                    None
-                }
-                // tracing::trace!("Named key not implemented: {k:?}");
-                // None
+    /// The `new` method creates a `Cmd` from a [`HashMap`] of bindings, with no pending strokes,
+    /// a timeout of [`CHORD_TIMEOUT`], a double-press window of [`DOUBLE_PRESS`] and a hold delay
+    /// of [`HOLD_DELAY`].
+    pub fn new(bindings: HashMap<Chord, Act>) -> Self {
+        Self {
+            bindings,
+            count: None,
+            double_press: DOUBLE_PRESS,
+            hold: HOLD_DELAY,
+            holding: HashMap::new(),
+            last_click: None,
+            last_key: None,
+            pending: Chord::default(),
+            since: None,
+            timeout: CHORD_TIMEOUT,
+        }
+    }
+
+    /// Given an incoming [`event::KeyEvent`] from the [`winit`] crate, and the
+    /// [`event::Modifiers`] held down at the time, the `act` method adds the key event to the
+    /// pending sequence and checks the [`HashMap`] in `Self` to determine if the sequence maps to
+    /// an [`Act`] variant.
+    ///
+    /// * If the sequence matches a binding, we clear the pending sequence and return the [`Act`].
+    /// * If the sequence is the beginning of a longer binding, we keep it pending and return
+    ///   [`None`] while we wait for the next stroke.
+    /// * If the sequence matches nothing, we clear the pending sequence.  When the failed
+    ///   sequence was longer than one stroke, we try the latest stroke again on its own, so that
+    ///   a wrong turn in a sequence does not swallow a perfectly good binding.
+    ///
+    /// If a character key does not match, we try again without `Shift`, because the user
+    /// probably wrote `?` instead of `Shift+?`.  See [`Stroke::without_shift`].  Failing that, we
+    /// try the physical position of the key, for bindings with the `physical:` prefix.  See
+    /// [`Stroke::from_physical`].
+    ///
+    /// A second press within `double_press` tries the double-press binding first, and a press of
+    /// a key with a hold binding waits to see how long the key stays down, as described in the
+    /// [`Cmd`] docs.  Key repeats from a held key never count as a double-press.
+    pub fn act(&mut self, event: &event::KeyEvent, modifiers: &event::Modifiers) -> Option<Act> {
+        let stroke = Stroke::from_key(&event.logical_key, modifiers.state())?;
+        tracing::trace!("Stroke event: {stroke}");
+        if event.repeat && self.holding.contains_key(&event.physical_key) {
+            // The key is still down, and poll_hold decides what happens next.
+            return None;
+        }
+        self.expire();
+        if self.count_digit(&stroke) {
+            return None;
+        }
+        let mut candidates = vec![stroke.clone()];
+        if let keyboard::Key::Character(_) = event.logical_key {
+            candidates.push(stroke.without_shift());
+        }
+        if let Some(physical) = Stroke::from_physical(&event.physical_key, modifiers.state()) {
+            candidates.push(physical);
+        }
+        if !event.repeat && self.double(&stroke) {
+            candidates.insert(0, stroke.prefixed(DOUBLE));
+        }
+        if !event.repeat && self.pending.is_empty() {
+            let hold = Chord::new(vec![stroke.prefixed(HOLD)]);
+            if let Some(act) = self.get(&hold).cloned() {
+                tracing::trace!("Hold pending: {hold}");
+                let held = Held {
+                    act,
+                    candidates,
+                    fired: false,
+                    since: time::Instant::now(),
+                };
+                self.holding.insert(event.physical_key, held);
+                return None;
             }
-            keyboard::Key::Character(k) => {
-                tracing::trace!("Character event: {k}");
-                if let Some(value) = self.get(k) {
-                    Some(value.clone())
-                } else {
-                    tracing::trace!("Command key not present {k}");
-                    None
-                }
+        }
+        self.dispatch(candidates)
+    }
+
+    /// The `double` method returns `true` if `stroke` repeats the previous key press within
+    /// `double_press`.  A third press starts over rather than counting as a second double-press.
+    fn double(&mut self, stroke: &Stroke) -> bool {
+        let now = time::Instant::now();
+        let double = match &self.last_key {
+            Some((last, at)) => last == stroke && now.duration_since(*at) < self.double_press,
+            None => false,
+        };
+        self.last_key = if double {
+            None
+        } else {
+            Some((stroke.clone(), now))
+        };
+        double
+    }
+
+    /// The `poll_hold` method returns the hold bindings of the keys being held, for each key that
+    /// has been down for at least `hold`.  Each hold fires here at most once.
+    pub fn poll_hold(&mut self) -> Vec<Act> {
+        let mut acts = Vec::new();
+        for held in self.holding.values_mut() {
+            if held.fired || held.since.elapsed() < self.hold {
+                continue;
+            }
+            held.fired = true;
+            tracing::trace!("Hold detected: {}", held.act);
+            acts.push(held.act.clone());
+        }
+        acts
+    }
+
+    /// The `hold_deadline` method returns the earliest time at which a key being held will count
+    /// as held, so the [`crate::App`] knows when to wake up and call [`Cmd::poll_hold`].
+    pub fn hold_deadline(&self) -> Option<time::Instant> {
+        self.holding
+            .values()
+            .filter(|held| !held.fired)
+            .map(|held| held.since + self.hold)
+            .min()
+    }
+
+    /// The `release` method handles a key coming back up.  If it is a key being held, we either
+    /// fire the hold binding again to end the hold, or, if the key came up too soon, treat it as
+    /// an ordinary press.  Releasing any other key does nothing.
+    pub fn release(&mut self, event: &event::KeyEvent) -> Option<Act> {
+        let held = self.holding.remove(&event.physical_key)?;
+        if held.fired {
+            tracing::trace!("Hold released: {}", held.act);
+            Some(held.act)
+        } else {
+            tracing::trace!("Released before hold, treating as a press.");
+            self.dispatch(held.candidates)
+        }
+    }
+
+    /// Given a [`event::MouseButton`] pressed by the user, and the [`event::Modifiers`] held down
+    /// at the time, the `click` method checks for a binding to the button, in the same manner as
+    /// [`Cmd::act`] does for keys.
+    ///
+    /// If the same button was pressed less than [`DOUBLE_CLICK`] ago, the press counts as a
+    /// double-click, and we look for a double-click binding before falling back to the single
+    /// click binding.  See [`Stroke::from_mouse`] for the names of mouse buttons in the config.
+    pub fn click(
+        &mut self,
+        button: &event::MouseButton,
+        modifiers: &event::Modifiers,
+    ) -> Option<Act> {
+        let now = time::Instant::now();
+        let double = match self.last_click {
+            Some((last, at)) => last == *button && now.duration_since(at) < DOUBLE_CLICK,
+            None => false,
+        };
+        // A third click starts over rather than counting as a second double-click.
+        self.last_click = if double { None } else { Some((*button, now)) };
+        let stroke = Stroke::from_mouse(button, modifiers.state(), false);
+        tracing::trace!("Click event: {stroke}");
+        let mut candidates = Vec::new();
+        if double {
+            candidates.push(Stroke::from_mouse(button, modifiers.state(), true));
+        }
+        candidates.push(stroke);
+        self.dispatch(candidates)
+    }
+
+    /// The `dispatch` method adds a stroke to the pending sequence and looks for a matching
+    /// binding, as described in [`Cmd::act`].  The `candidates` are alternative readings of the
+    /// same stroke in order of preference, and the first one to match a binding or the beginning
+    /// of a binding wins.
+    fn dispatch(&mut self, candidates: Vec<Stroke>) -> Option<Act> {
+        self.expire();
+        for candidate in candidates.iter().cloned() {
+            let mut chord = self.pending.clone();
+            chord.push(candidate);
+            if let Some(act) = self.get(&chord) {
+                tracing::trace!("Act detected: {act}");
+                let act = act.clone();
+                self.reset();
+                return Some(act);
+            }
+            if self.is_prefix(&chord) {
+                tracing::trace!("Chord pending: {chord}");
+                self.pending = chord;
+                self.since = Some(time::Instant::now());
+                return None;
+            }
+        }
+        if self.pending.is_empty() {
+            tracing::trace!("Command key not present {:?}", candidates);
+            None
+        } else {
+            tracing::trace!("Chord abandoned: {}", self.pending);
+            self.reset();
+            self.dispatch(candidates)
+        }
+    }
+
+    /// The `key_for` method looks up the binding for `act`, the reverse of the usual direction,
+    /// and returns it in the config syntax, like `Ctrl+n`.  Menus, tooltips and the help overlay
+    /// use this to show the current shortcut next to each action, so they stay correct when the
+    /// user changes a binding.
+    ///
+    /// If several chords trigger `act`, we pick the shortest, breaking ties alphabetically, so the
+    /// answer does not change from one call to the next.  Returns [`None`] if `act` is unbound.
+    pub fn key_for(&self, act: &Act) -> Option<String> {
+        self.keys_for(act).into_iter().next()
+    }
+
+    /// The `keys_for` method returns every binding for `act` in the config syntax, shortest
+    /// first, in the order used by [`Cmd::key_for`].
+    pub fn keys_for(&self, act: &Act) -> Vec<String> {
+        let mut chords = self
+            .iter()
+            .filter(|(_, bound)| *bound == act)
+            .map(|(chord, _)| (chord.len(), chord.to_string()))
+            .collect::<Vec<(usize, String)>>();
+        chords.sort();
+        chords.into_iter().map(|(_, chord)| chord).collect()
+    }
+
+    /// The `label` method returns the title of `act` followed by its binding in parentheses, as
+    /// in `New Window (Ctrl+n)`, or just the title if `act` is unbound.
+    pub fn label(&self, act: &Act) -> String {
+        match self.key_for(act) {
+            Some(key) => format!("{} ({key})", act.title()),
+            None => act.title(),
+        }
+    }
+
+    /// The `bind` method binds `chord` to `act` at runtime.  Any chords previously bound to `act`
+    /// are removed, as is any other act previously bound to `chord`, because the config format
+    /// holds one binding per act.  Returns the act previously bound to `chord`, if any.
+    pub fn bind(&mut self, act: &Act, chord: Chord) -> Option<Act> {
+        self.retain(|_, bound| bound != act);
+        let previous = self.insert(chord.clone(), act.clone());
+        tracing::info!("Bound {chord} to {}", act.title());
+        previous
+    }
+
+    /// The `save` method writes the bindings back to the TOML config file at `path`, so that
+    /// changes made with [`Cmd::bind`] survive a restart.
+    ///
+    /// We read the existing file first and only replace the keys named after [`Act`] variants,
+    /// so that other tables in the file (like `[scroll]`) come through untouched.  If the file does
+    /// not exist yet, we create it, along with any missing parent directories.  Comments and key
+    /// order in the file do not survive the trip.
+    ///
+    /// Will [`crate::Excuse::ConfigFormat`] if the file is YAML or JSON rather than TOML,
+    /// [`crate::Blame::TomlDe`] if the existing file is not valid TOML,
+    /// [`crate::Blame::TomlSer`] if the table fails to serialize, and [`crate::Blame::Io`] if
+    /// writing the file fails.
+    #[tracing::instrument(skip(self))]
+    pub fn save(&self, path: &path::Path) -> Arrive<()> {
+        if crate::Layers::format(path).is_some_and(|format| format != config::FileFormat::Toml) {
+            tracing::warn!("Not writing bindings to {}.", path.display());
+            return Err(crate::Excuse::ConfigFormat.into());
+        }
+        let mut table = match std::fs::read_to_string(path) {
+            Ok(contents) => contents
+                .parse::<toml::Table>()
+                .with_context(|| format!("reading bindings from {}", path.display()))?,
+            Err(_) => toml::Table::new(),
+        };
+        Act::iter().for_each(|act| {
+            table.remove(&act.snake());
+        });
+        for (chord, act) in self.iter() {
+            table.insert(act.snake(), toml::Value::String(chord.to_string()));
+        }
+        // A new file has the current layout, so mark it as such rather than leave it for
+        // migration.
+        table
+            .entry(VERSION)
+            .or_insert(toml::Value::Integer(CONFIG_VERSION));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(&table)?)?;
+        tracing::info!("Bindings saved.");
+        Ok(())
+    }
+
+    /// The `take_count` method returns the pending count, or one if no count is pending, and
+    /// clears the count.  The count never exceeds [`MAX_COUNT`].
+    pub fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1)
+    }
+
+    /// The `count_digit` method adds `stroke` to the pending count if it is an unmodified digit
+    /// and no binding or pending sequence claims it.  A leading zero is not a count, so that `0`
+    /// stays available as an ordinary key.  Returns `true` if the stroke went into the count.
+    fn count_digit(&mut self, stroke: &Stroke) -> bool {
+        if !self.pending.is_empty() || !stroke.modifiers().is_empty() {
+            return false;
+        }
+        let digit = match stroke.key().parse::<usize>() {
+            Ok(digit) if stroke.key().len() == 1 => digit,
+            _ => return false,
+        };
+        if digit == 0 && self.count.is_none() {
+            return false;
+        }
+        let chord = Chord::new(vec![stroke.clone()]);
+        if self.contains_key(&chord) || self.is_prefix(&chord) {
+            return false;
+        }
+        let count = self.count.unwrap_or(0) * 10 + digit;
+        self.count = Some(count.min(MAX_COUNT));
+        self.since = Some(time::Instant::now());
+        tracing::trace!("Count: {}", count.min(MAX_COUNT));
+        true
+    }
+
+    /// The `hints` method lists the bindings that begin with the pending sequence, as pairs of
+    /// the strokes remaining to finish the binding and the resulting [`Act`], sorted by the
+    /// remaining strokes.  Returns an empty vector when no sequence is pending.
+    pub fn hints(&self) -> Vec<(Chord, Act)> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let depth = self.pending.len();
+        let mut hints = self
+            .iter()
+            .filter(|(chord, _)| chord.len() > depth && chord.starts_with(&self.pending))
+            .map(|(chord, act)| (Chord::new(chord[depth..].to_vec()), act.clone()))
+            .collect::<Vec<(Chord, Act)>>();
+        hints.sort_by_key(|(chord, _)| chord.to_string());
+        hints
+    }
+
+    /// The `deadline` method returns the time at which the pending sequence and count lapse, so
+    /// the [`crate::App`] knows when to wake up and call [`Cmd::lapse`].
+    pub fn deadline(&self) -> Option<time::Instant> {
+        let waiting = !self.pending.is_empty() || self.count.is_some();
+        self.since
+            .filter(|_| waiting)
+            .map(|since| since + self.timeout)
+    }
+
+    /// The `lapse` method forgets the pending sequence and count once `timeout` has passed since
+    /// the last stroke, as the next stroke would, and returns `true` if there was something to
+    /// forget, so the status of the keyboard can go.
+    pub fn lapse(&mut self) -> bool {
+        match self.deadline() {
+            Some(deadline) if time::Instant::now() >= deadline => {
+                tracing::trace!("Chord lapsed: {}", self.pending);
+                self.reset();
+                self.count = None;
+                true
             }
-            other => {
-                tracing::trace!("Unrecognized key event: {other:?}");
-                None
+            _ => false,
+        }
+    }
+
+    /// The `reset` method clears the pending sequence.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.since = None;
+    }
+
+    /// The `expire` method clears the pending sequence and count if more than `timeout` has
+    /// passed since the last stroke.
+    fn expire(&mut self) {
+        if let Some(since) = self.since {
+            if since.elapsed() > self.timeout {
+                tracing::trace!("Chord timed out: {}", self.pending);
+                self.reset();
+                self.count = None;
             }
         }
     }
+
+    /// The `is_prefix` method returns `true` if `chord` is the beginning of a longer binding.
+    fn is_prefix(&self, chord: &Chord) -> bool {
+        self.keys()
+            .any(|key| key.len() > chord.len() && key.starts_with(chord))
+    }
+}
+
+impl Default for Cmd {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
 }
 
 /// Here we rely on the [`strum`] and [`strum_macros`] crates to generate an iterator method over the
-/// variants of [`Act`].  For each variant, we check to see if the [`config::Config`] passed in by the `config` argument  contains the snake
-/// case version of the variant name as a key.  When a key is present, the method inserts a new
-/// entry into [`HashMap`] in `Self` using the `config` value as a key, and the corresponding [`Act`] variant as the value.
+/// variants of [`Act`].  For each variant, we check to see if the [`Keybindings`] passed in by the `keybindings` argument  contains
+/// a binding for the variant.  When a binding is present, the method inserts a new
+/// entry into [`HashMap`] in `Self` using the binding as a key, and the corresponding [`Act`] variant as the value.
 ///
-/// The table in the [`config::Config`] has [`String`] representations of [`Act`] variants as keys
+/// The `acts` field of the [`Keybindings`] has [`Act`] variants as keys
 /// and keyboard characters as values.  We need the reverse, where the keyboard character enetered by the
 /// user is the key, and the triggered [`Act`] is the value, so we create a new [`HashMap`] with
 /// this inverse relationship, stored in the `Cmd` struct.
-impl From<&config::Config> for Cmd {
-    fn from(config: &config::Config) -> Self {
-        let mut cmds = HashMap::new();
-        let table = config.cache.clone().into_table().unwrap();
-        Act::iter()
-            .map(|a| {
-                let key = a.snake();
-                if let Some(entry) = table.get(&key) {
-                    tracing::trace!("Command detected: {a}");
-                    let value = entry.clone().into_string().unwrap();
-                    cmds.insert(value, a.clone());
+///
+/// Each value is parsed into a [`Chord`].  Bindings that fail to parse are skipped with a
+/// warning, so one typo does not cost the user every other binding.
+///
+/// The work happens in [`Cmd::load`], and this implementation logs any [`Clash`] found along the
+/// way and throws the list away.  Call [`Cmd::load`] directly to show the clashes to the user.
+impl From<&Keybindings> for Cmd {
+    fn from(keybindings: &Keybindings) -> Self {
+        let (cmd, clashes) = Self::load(keybindings);
+        clashes
+            .iter()
+            .for_each(|clash| tracing::warn!("Key binding problem: {clash}"));
+        cmd
+    }
+}
+
+impl Cmd {
+    /// The `load` method reads bindings from `keybindings` as described in the [`From`]
+    /// implementation, and also returns a list of [`Clash`] types describing problems with the
+    /// bindings.
+    ///
+    /// When two acts claim the same chord, the act listed first in [`Act`] keeps the binding,
+    /// rather than whichever the [`HashMap`] happened to insert last, and we report the
+    /// duplicate.  The exception is a built-in default binding, from [`Act::default_binding`], which
+    /// quietly gives way to a binding the user wrote, since the user cannot be expected to unbind
+    /// every default they walk over.  An empty binding, as in `exit = ""`, leaves the act
+    /// unbound.  We also report bindings that fail to parse, keys with names we do not recognize,
+    /// and bindings that can never fire because a shorter binding matches first.
+    pub fn load(keybindings: &Keybindings) -> (Self, Vec<Clash>) {
+        let mut cmds: HashMap<Chord, Act> = HashMap::new();
+        let mut builtin: HashSet<Chord> = HashSet::new();
+        let mut clashes = Vec::new();
+        for a in Act::iter() {
+            let key = a.snake();
+            if let Some(value) = keybindings.acts().get(&a) {
+                tracing::trace!("Command detected: {a}");
+                let value = value.clone();
+                if value.trim().is_empty() {
+                    tracing::trace!("Unbound: {a}");
+                    continue;
+                }
+                let chord = match value.parse::<Chord>() {
+                    Ok(chord) => chord,
+                    Err(e) => {
+                        tracing::trace!("Could not parse {key} = {value}: {e}");
+                        clashes.push(Clash::Invalid { act: a, value });
+                        continue;
+                    }
+                };
+                if let Some(stroke) = chord.iter().find(|stroke| !stroke.is_known()) {
+                    clashes.push(Clash::Unknown {
+                        act: a.clone(),
+                        key: stroke.key().clone(),
+                    });
                 }
-            })
-            .for_each(drop);
+                let is_default = a.default_binding() == Some(value.as_str());
+                if let Some(first) = cmds.get(&chord) {
+                    if builtin.contains(&chord) && !is_default {
+                        tracing::trace!("{a} takes {chord} from the default for {first}");
+                        builtin.remove(&chord);
+                        cmds.insert(chord, a);
+                    } else if is_default && !builtin.contains(&chord) {
+                        tracing::trace!("Default {chord} for {a} already taken by {first}");
+                    } else {
+                        clashes.push(Clash::Duplicate {
+                            chord,
+                            kept: first.clone(),
+                            dropped: a,
+                        });
+                    }
+                } else {
+                    if is_default {
+                        builtin.insert(chord.clone());
+                    }
+                    cmds.insert(chord, a);
+                }
+            }
+        }
+        for (chord, act) in cmds.iter() {
+            for (prefix, by) in cmds.iter() {
+                if prefix.len() < chord.len() && chord.starts_with(prefix) {
+                    clashes.push(Clash::Shadowed {
+                        chord: chord.clone(),
+                        act: act.clone(),
+                        prefix: prefix.clone(),
+                        by: by.clone(),
+                    });
+                }
+            }
+        }
         if cmds.is_empty() {
             tracing::trace!("No valid commands detected!");
         }
-        Self::new(cmds)
+        let mut cmd = Self::new(cmds);
+        let timing = keybindings.timing();
+        cmd.double_press = time::Duration::from_millis(*timing.double_press());
+        cmd.hold = time::Duration::from_millis(*timing.hold());
+        cmd.timeout = time::Duration::from_millis(*timing.chord());
+        (cmd, clashes)
+    }
+}
+
+/// The `Held` struct tracks a key with a hold binding while it is down, under its physical key
+/// in the `holding` field of the [`Cmd`], which matches the release to the press.
+///
+/// * The `act` field holds the act bound to holding the key.
+/// * The `candidates` field holds the readings of the press, for dispatch as an ordinary press if
+///   the key comes up too soon.
+/// * The `fired` field is `true` once the hold binding has fired.
+/// * The `since` field holds the time of the press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Held {
+    act: Act,
+    candidates: Vec<Stroke>,
+    fired: bool,
+    since: time::Instant,
+}
+
+/// The `Clash` enum describes a problem with the key bindings read from the config.
+///
+/// # Reporting binding problems with `Clash`
+///
+/// Before `Clash`, a binding with a typo quietly disappeared, and two acts bound to the same key
+/// fought it out inside the [`HashMap`], with the winner decided by iteration order.  The user
+/// had no way to know why a key did nothing.  Now [`Cmd::load`] collects a `Clash` for each
+/// problem, and the [`crate::App`] shows the list on startup.  The [`std::fmt::Display`]
+/// implementation writes each problem in terms of the lines in the config file, so the user knows
+/// which lines to fix.
+///
+/// * The `Duplicate` variant indicates two acts are bound to the same chord.
+/// * The `Invalid` variant indicates a binding could not be parsed.
+/// * The `Unknown` variant indicates a binding names a key we do not recognize.  The binding is
+///   kept, in case the key is real and our list is short, but it will probably never fire.
+/// * The `Shadowed` variant indicates a binding can never fire, because a shorter binding matches
+///   the beginning of the sequence first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clash {
+    /// The `Duplicate` variant holds the chord, the act that kept it, and the act dropped.
+    Duplicate {
+        /// The chord claimed by both acts.
+        chord: Chord,
+        /// The act that kept the binding.
+        kept: Act,
+        /// The act that lost the binding.
+        dropped: Act,
+    },
+    /// The `Invalid` variant holds the act and the value that failed to parse.
+    Invalid {
+        /// The act named on the left side of the binding.
+        act: Act,
+        /// The value on the right side of the binding.
+        value: String,
+    },
+    /// The `Unknown` variant holds the act and the unrecognized key name.
+    Unknown {
+        /// The act named on the left side of the binding.
+        act: Act,
+        /// The key name we do not recognize.
+        key: String,
+    },
+    /// The `Shadowed` variant holds the unreachable binding and the shorter binding in its way.
+    Shadowed {
+        /// The chord that can never fire.
+        chord: Chord,
+        /// The act bound to the unreachable chord.
+        act: Act,
+        /// The shorter chord that matches first.
+        prefix: Chord,
+        /// The act bound to the shorter chord.
+        by: Act,
+    },
+}
+
+impl std::fmt::Display for Clash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Duplicate {
+                chord,
+                kept,
+                dropped,
+            } => write!(
+                f,
+                "{} = \"{chord}\" conflicts with {} = \"{chord}\"; keeping {}",
+                dropped.snake(),
+                kept.snake(),
+                kept.snake()
+            ),
+            Self::Invalid { act, value } => {
+                write!(f, "{} = \"{value}\" is not a valid binding", act.snake())
+            }
+            Self::Unknown { act, key } => {
+                write!(f, "{} uses unrecognized key name \"{key}\"", act.snake())
+            }
+            Self::Shadowed {
+                chord,
+                act,
+                prefix,
+                by,
+            } => write!(
+                f,
+                "{} = \"{chord}\" can never fire because {} = \"{prefix}\" matches first",
+                act.snake(),
+                by.snake()
+            ),
+        }
+    }
+}
+
+/// The `DOUBLE_CLICK` constant sets the longest interval between two presses of the same mouse
+/// button that [`Cmd::click`] will count as a double-click.
+pub const DOUBLE_CLICK: time::Duration = time::Duration::from_millis(400);
+
+/// The `DOUBLE_PRESS` constant sets the default longest interval between two presses of the same
+/// key that [`Cmd::act`] will count as a double-press.
+pub const DOUBLE_PRESS: time::Duration = time::Duration::from_millis(300);
+
+/// The `HOLD_DELAY` constant sets the default time a key must stay down to count as held.
+pub const HOLD_DELAY: time::Duration = time::Duration::from_millis(400);
+
+/// The `MAX_COUNT` constant caps the count prefix, so that a slip of the finger does not open a
+/// thousand windows.
+pub const MAX_COUNT: usize = 99;
+
+/// The `CHORD_TIMEOUT` constant sets how long [`Cmd`] waits for the next stroke in a [`Chord`]
+/// before giving up on the sequence.
+pub const CHORD_TIMEOUT: time::Duration = time::Duration::from_millis(1500);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> Chord {
+        text.parse::<Chord>().expect("chord")
+    }
+
+    fn stroke(text: &str) -> Stroke {
+        text.parse::<Stroke>().expect("stroke")
+    }
+
+    fn cmd() -> Cmd {
+        let bindings = [
+            (chord("Ctrl+n"), Act::NewWindow),
+            (chord("g b"), Act::NextBookmark),
+            (chord("g s"), Act::SaveBookmark),
+            (chord("Escape"), Act::Exit),
+        ];
+        Cmd::new(bindings.into_iter().collect())
+    }
+
+    #[test]
+    fn sequences_finish_with_hints() {
+        let mut cmd = cmd();
+        assert!(cmd.hints().is_empty());
+        assert_eq!(cmd.dispatch(vec![stroke("g")]), None);
+        let hints = cmd.hints();
+        assert_eq!(
+            hints,
+            vec![
+                (chord("b"), Act::NextBookmark),
+                (chord("s"), Act::SaveBookmark)
+            ]
+        );
+        assert!(cmd.deadline().is_some());
+        assert_eq!(cmd.dispatch(vec![stroke("s")]), Some(Act::SaveBookmark));
+        assert!(cmd.pending().is_empty());
+        assert!(cmd.deadline().is_none());
+    }
+
+    #[test]
+    fn wrong_turns_try_the_stroke_alone() {
+        let mut cmd = cmd();
+        assert_eq!(cmd.dispatch(vec![stroke("g")]), None);
+        assert_eq!(cmd.dispatch(vec![stroke("Ctrl+n")]), Some(Act::NewWindow));
+        assert!(cmd.pending().is_empty());
+    }
+
+    #[test]
+    fn sequences_lapse() {
+        let mut cmd = cmd();
+        cmd.timeout = time::Duration::ZERO;
+        assert!(!cmd.lapse());
+        cmd.dispatch(vec![stroke("g")]);
+        assert!(cmd.lapse());
+        assert!(cmd.pending().is_empty());
+        assert!(cmd.deadline().is_none());
+        cmd.dispatch(vec![stroke("g")]);
+        cmd.reset();
+        assert!(cmd.hints().is_empty());
+    }
+
+    #[test]
+    fn digits_count() {
+        let mut cmd = cmd();
+        assert!(!cmd.count_digit(&stroke("0")));
+        assert!(cmd.count_digit(&stroke("1")));
+        assert!(cmd.count_digit(&stroke("0")));
+        assert!(!cmd.count_digit(&stroke("Ctrl+2")));
+        assert_eq!(*cmd.count(), Some(10));
+        assert!(cmd.deadline().is_some());
+        assert_eq!(cmd.take_count(), 10);
+        assert_eq!(cmd.take_count(), 1);
+        for _ in 0..4 {
+            cmd.count_digit(&stroke("9"));
+        }
+        assert_eq!(cmd.take_count(), MAX_COUNT);
+        cmd.bind(&Act::ZoomIn, chord("3"));
+        assert!(!cmd.count_digit(&stroke("3")));
+    }
+
+    #[test]
+    fn binding_replaces_both_ways() {
+        let mut cmd = cmd();
+        assert_eq!(cmd.bind(&Act::NewWindow, chord("Ctrl+w")), None);
+        assert_eq!(cmd.key_for(&Act::NewWindow).as_deref(), Some("Ctrl+w"));
+        assert!(!cmd.contains_key(&chord("Ctrl+n")));
+        assert_eq!(
+            cmd.bind(&Act::ZoomIn, chord("Ctrl+w")),
+            Some(Act::NewWindow)
+        );
+        assert_eq!(cmd.key_for(&Act::NewWindow), None);
+        assert_eq!(cmd.label(&Act::NewWindow), Act::NewWindow.title());
+        assert_eq!(
+            cmd.label(&Act::ZoomIn),
+            format!("{} (Ctrl+w)", Act::ZoomIn.title())
+        );
+    }
+
+    #[test]
+    fn shortest_keys_come_first() {
+        let mut cmd = cmd();
+        cmd.insert(chord("z z"), Act::Exit);
+        cmd.insert(chord("q"), Act::Exit);
+        assert_eq!(cmd.keys_for(&Act::Exit), vec!["Escape", "q", "z z"]);
+        assert_eq!(cmd.key_for(&Act::Exit).as_deref(), Some("Escape"));
     }
 }
@@ -0,0 +1,714 @@
+use crate::{Arrive, Excuse, Extent, Fetch, Network, EARTH_RADIUS, MAX_ZOOM};
+use galileo::decoded_image::DecodedImage;
+use galileo::error::GalileoError;
+use galileo::galileo_types::cartesian::Size;
+use galileo::layer::data_provider::DataProvider;
+use galileo::tile_scheme::TileIndex;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io::{self, Read, Seek};
+use std::sync::{Arc, Mutex, RwLock};
+use tiff::tags::Tag;
+
+/// The `cog` module provides the [`Cog`] struct, which draws the picture of a scene found by a
+/// [`crate::Stac`] search on the map, reading only the parts of its COG in view.
+///
+/// # Drawing a scene with `Cog`
+///
+/// A scene of Sentinel-2 runs to a few hundred megabytes, and one of NAIP to more, and we want
+/// to look at a field of it.  A cloud-optimized GeoTIFF puts its header, with the place of every
+/// tile of the picture in the file, at the front, and keeps smaller copies of the picture,
+/// the overviews, for looking at it from further out.  [`Cog::open`] asks the server for the
+/// first [`COG_HEADER`] bytes of the file with an HTTP range request, asking for more, up to
+/// [`COG_HEADER_LIMIT`], if the header runs longer, and reads the size, tiles and overviews of
+/// the picture from it, with the ground it covers and the projection it is in from the
+/// GeoTIFF tags.  A file the server will not send in parts fails with
+/// [`crate::Excuse::NoRanges`], rather than downloading the lot.
+///
+/// The `Cog` is a data provider for the raster tile layer of [`galileo`], like the
+/// [`crate::Fetcher`] of the base map, so the map asks it for the tiles in view as the user
+/// moves around, and [`Cog::layer`] wraps it in a [`Scenery`] layer for every map to share.  For
+/// each tile of the map, we find the place in the picture under each pixel, pick the overview
+/// with about one pixel of the picture to one of the tile, fetch the tiles of the overview
+/// under the tile with range requests, and copy the pixels over.  A tile of the map off the
+/// scene costs no request at all.  The tiles of the picture are kept for reuse, the last
+/// [`COG_CHUNKS`] of them, since the tiles of the map around one share them.  Each request goes
+/// in the log of [`Network`] and is timed with the tiles of the base map.
+///
+/// The picture may be in longitude and latitude, in web mercator, or in a zone of UTM, on
+/// WGS84 or NAD83, which covers the scenes of Sentinel-2 and NAIP.  We read the first three
+/// bands, 8 bits each, as red, green and blue, like the `visual` asset of Sentinel-2 or the
+/// `image` asset of NAIP, or one band as grey, and leave out the pixels holding the nodata
+/// value of the file.  A picture of some other kind fails with
+/// [`crate::Excuse::SceneLayout`].
+///
+/// * The `url` field holds the address of the COG.
+/// * The `layout` field holds what we read from the header of the COG.
+/// * The `chunks` field holds the tiles of the picture fetched lately.
+pub struct Cog {
+    url: String,
+    layout: Layout,
+    chunks: Mutex<Chunks>,
+}
+
+impl Cog {
+    /// The `open` method reads the header of the COG at `url`, as described in the [`Cog`]
+    /// docs.
+    ///
+    /// Will [`crate::Blame::Http`] if the server cannot be reached or answers with an error,
+    /// [`crate::Excuse::NoRanges`] if it will not send part of the file,
+    /// [`crate::Blame::Tiff`] if the header cannot be read, and [`crate::Excuse::SceneLayout`]
+    /// if the picture is not one we can draw.
+    #[tracing::instrument]
+    pub async fn open(url: &str) -> Arrive<Self> {
+        let mut length = COG_HEADER;
+        let layout = loop {
+            let header = Self::range(url, 0, length).await?;
+            let whole = (header.len() as u64) < length;
+            match Layout::read(header.to_vec()) {
+                Ok(layout) => break layout,
+                Err(e) if !whole && length < COG_HEADER_LIMIT => {
+                    tracing::debug!("The header runs past {length} bytes, asking for more: {e}");
+                    length *= 4;
+                }
+                Err(e) => return Err(e.with_context(format!("reading the header of {url}"))),
+            }
+        };
+        tracing::info!(
+            "Opened a scene of {} by {} pixels with {} overviews.",
+            layout.levels[0].width,
+            layout.levels[0].height,
+            layout.levels.len() - 1
+        );
+        Ok(Self {
+            url: url.to_string(),
+            layout,
+            chunks: Mutex::new(Chunks::default()),
+        })
+    }
+
+    /// The `url` method returns the address of the COG.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The `layer` method wraps the scene in a [`Scenery`] layer, for [`crate::Map::reveal`].
+    pub fn layer(self) -> Scenery {
+        let layer =
+            galileo::layer::RasterTileLayer::new(galileo::TileSchema::web(MAX_ZOOM), self, None);
+        Arc::new(RwLock::new(layer))
+    }
+
+    /// The `draw` method returns the pixels of the tile at `index`, as described in the
+    /// [`Cog`] docs, [`COG_TILE`] pixels across in rows of red, green, blue and alpha.
+    ///
+    /// Fails as [`Cog::chunk`] does.
+    async fn draw(&self, index: &TileIndex) -> Arrive<Vec<u8>> {
+        let span = 2.0 * std::f64::consts::PI * EARTH_RADIUS;
+        let size = span / f64::from(index.z).exp2();
+        let step = size / f64::from(COG_TILE);
+        let west = -span / 2.0 + f64::from(index.x) * size;
+        let north = span / 2.0 - f64::from(index.y) * size;
+        let places = (0..COG_TILE * COG_TILE)
+            .map(|cell| {
+                let x = west + (f64::from(cell % COG_TILE) + 0.5) * step;
+                let y = north - (f64::from(cell / COG_TILE) + 0.5) * step;
+                let (longitude, latitude) = Extent::unproject(x, y);
+                self.layout.place(longitude, latitude)
+            })
+            .collect::<Vec<(f64, f64)>>();
+        // Pixels of the full picture between two pixels across the middle of the tile.
+        let middle = (COG_TILE / 2 * COG_TILE + COG_TILE / 2) as usize;
+        let (x, y) = places[middle];
+        let (east, south) = places[middle + 1];
+        let level = self.layout.level((east - x).hypot(south - y));
+        let full = &self.layout.levels[0];
+        let ratio = (
+            f64::from(level.width) / f64::from(full.width),
+            f64::from(level.height) / f64::from(full.height),
+        );
+        let wanted = places
+            .iter()
+            .map(|(x, y)| level.chunk_at(x * ratio.0, y * ratio.1))
+            .collect::<Vec<Option<(u32, u32, u32)>>>();
+        let needed = wanted
+            .iter()
+            .flatten()
+            .map(|(chunk, _, _)| *chunk)
+            .collect::<BTreeSet<u32>>();
+        let mut chunks = HashMap::new();
+        for chunk in needed {
+            chunks.insert(chunk, self.chunk(level, chunk).await?);
+        }
+        let mut pixels = vec![0; (COG_TILE * COG_TILE * 4) as usize];
+        for (cell, (chunk, x, y)) in wanted
+            .iter()
+            .enumerate()
+            .filter_map(|(cell, wanted)| Some((cell, (*wanted)?)))
+        {
+            if let Some(pixel) = chunks
+                .get(&chunk)
+                .and_then(|chunk| chunk.pixel(x, y, self.layout.nodata))
+            {
+                pixels[cell * 4..cell * 4 + 4].copy_from_slice(&pixel);
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// The `chunk` method returns the tile at `index` of the picture at `level`, from those kept
+    /// if we have it, and otherwise from the server.  An empty tile, as a sparse COG leaves
+    /// where the scene has no pixels, costs no request.
+    ///
+    /// Will [`crate::Blame::Http`] if the tile cannot be fetched, and [`crate::Blame::Tiff`] if
+    /// it cannot be decoded.
+    async fn chunk(&self, level: &Level, index: u32) -> Arrive<Arc<Chunk>> {
+        let key = (level.ifd, index);
+        if let Some(chunk) = self.chunks.lock().ok().and_then(|chunks| chunks.get(&key)) {
+            return Ok(chunk);
+        }
+        let place = level
+            .offsets
+            .get(index as usize)
+            .zip(level.counts.get(index as usize));
+        let chunk = match place {
+            Some((&offset, &count)) if count > 0 => {
+                let bytes = Self::range(&self.url, offset, count).await?;
+                self.layout.decode(level, index, offset, &bytes)?
+            }
+            _ => Chunk::default(),
+        };
+        let chunk = Arc::new(chunk);
+        if let Ok(mut chunks) = self.chunks.lock() {
+            chunks.keep(key, chunk.clone());
+        }
+        Ok(chunk)
+    }
+
+    /// The `range` method fetches `length` bytes of the file at `url` from `start`, retrying
+    /// while the failure is worth retrying.  The span is named for [`crate::TILE_SPAN`], so
+    /// [`crate::Latency`] times it with the tiles of the base map.
+    ///
+    /// Will [`crate::Blame::Http`] if the server cannot be reached or answers with an error,
+    /// once retries are spent, and [`crate::Excuse::NoRanges`] if it sends the whole file.
+    #[tracing::instrument(name = "tile", skip(url))]
+    async fn range(url: &str, start: u64, length: u64) -> Arrive<bytes::Bytes> {
+        let mut attempt = 0;
+        loop {
+            let blame = match Self::ask(url, start, length).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(blame) => blame,
+            };
+            attempt += 1;
+            match blame.retry_after(attempt) {
+                Some(delay) => {
+                    tracing::info!("Scene tile interrupted, retrying in {delay:?}: {blame}");
+                    crate::sleep(delay).await;
+                }
+                None => return Err(blame),
+            }
+        }
+    }
+
+    /// The `ask` method sends one range request for `length` bytes of the file at `url` from
+    /// `start`, through [`Network::send`], so it shows up in the network log.
+    ///
+    /// Will [`crate::Blame::Http`] if the request fails or the server answers with an error, and
+    /// [`crate::Excuse::NoRanges`] if it sends the whole file.
+    async fn ask(url: &str, start: u64, length: u64) -> Arrive<bytes::Bytes> {
+        let last = start + length.max(1) - 1;
+        let request = Network::global()
+            .client()
+            .get(url)
+            .header(reqwest::header::RANGE, format!("bytes={start}-{last}"));
+        let response = Network::global()
+            .send(request, Fetch::Tiles)
+            .await?
+            .error_for_status()?;
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(Excuse::NoRanges.into());
+        }
+        Ok(response.bytes().await?)
+    }
+}
+
+impl DataProvider<TileIndex, DecodedImage, ()> for Cog {
+    async fn load_raw(&self, key: &TileIndex) -> Result<bytes::Bytes, GalileoError> {
+        self.draw(key).await.map(bytes::Bytes::from).map_err(|e| {
+            tracing::debug!(
+                "Could not draw the scene tile {}/{}/{}: {e}",
+                key.z,
+                key.x,
+                key.y
+            );
+            GalileoError::Generic(e.to_string())
+        })
+    }
+
+    fn decode(&self, bytes: bytes::Bytes, _context: ()) -> Result<DecodedImage, GalileoError> {
+        DecodedImage::from_raw(bytes.to_vec(), Size::new(COG_TILE, COG_TILE))
+    }
+}
+
+impl std::fmt::Debug for Cog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cog")
+            .field("url", &self.url)
+            .field("layout", &self.layout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `Scenery` type is the raster tile layer of a scene, shared by every map window behind a
+/// lock, as for the [`crate::Tiles`] of the base map.
+pub type Scenery = Arc<RwLock<galileo::layer::RasterTileLayer<Cog>>>;
+
+/// The `Layout` struct holds what we read from the header of a COG, as described in the
+/// [`Cog`] docs.
+///
+/// * The `header` field holds the bytes of the header, to decode the tiles against.
+/// * The `levels` field holds the full picture and its overviews, largest first.
+/// * The `projection` field holds the [`Projection`] of the picture.
+/// * The `origin` field holds the corner of the first pixel, in the units of the projection.
+/// * The `pixel` field holds the width and height of a pixel of the full picture, in the units
+///   of the projection.
+/// * The `nodata` field holds the value of the pixels without data, if the file says.
+struct Layout {
+    header: Vec<u8>,
+    levels: Vec<Level>,
+    projection: Projection,
+    origin: (f64, f64),
+    pixel: (f64, f64),
+    nodata: Option<u8>,
+}
+
+impl Layout {
+    /// The `read` method reads the layout from the `header` of a COG.
+    ///
+    /// Will [`crate::Blame::Tiff`] if the header is cut short or is not a TIFF, and
+    /// [`crate::Excuse::SceneLayout`] if the picture is not one we can draw.
+    fn read(header: Vec<u8>) -> Arrive<Self> {
+        let mut decoder = tiff::decoder::Decoder::new(Sparse::new(vec![(0, header.as_slice())]))?;
+        let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag)?;
+        let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag)?;
+        let keys = decoder.get_tag_u16_vec(Tag::GeoKeyDirectoryTag)?;
+        let projection = Projection::from_keys(&keys).ok_or(Excuse::SceneLayout)?;
+        if scale.len() < 2 || tiepoint.len() < 6 || scale[0] <= 0.0 || scale[1] <= 0.0 {
+            return Err(Excuse::SceneLayout.into());
+        }
+        let nodata = decoder
+            .get_tag_ascii_string(Tag::GdalNodata)
+            .ok()
+            .and_then(|text| text.trim_matches(['\0', ' ']).parse::<f64>().ok())
+            .filter(|value| (0.0..=255.0).contains(value))
+            .map(|value| value as u8);
+        let mut levels = Vec::new();
+        let mut ifd = 0;
+        loop {
+            // A mask goes with each picture in some files, marked in the subfile type.
+            let kind = match decoder.find_tag(Tag::NewSubfileType)? {
+                Some(value) => value.into_u32()?,
+                None => 0,
+            };
+            let tiled = matches!(decoder.get_chunk_type(), tiff::decoder::ChunkType::Tile);
+            if kind & 4 == 0 && tiled {
+                let (width, height) = decoder.dimensions()?;
+                let (tile_width, tile_height) = decoder.chunk_dimensions();
+                levels.push(Level {
+                    ifd,
+                    width,
+                    height,
+                    tile_width,
+                    tile_height,
+                    offsets: decoder.get_tag_u64_vec(Tag::TileOffsets)?,
+                    counts: decoder.get_tag_u64_vec(Tag::TileByteCounts)?,
+                });
+            }
+            if !decoder.more_images() {
+                break;
+            }
+            decoder.next_image()?;
+            ifd += 1;
+        }
+        drop(decoder);
+        if levels.first().map(|level| level.ifd) != Some(0) {
+            return Err(Excuse::SceneLayout.into());
+        }
+        levels.sort_by_key(|level| std::cmp::Reverse(level.width));
+        Ok(Self {
+            header,
+            levels,
+            projection,
+            origin: (
+                tiepoint[3] - tiepoint[0] * scale[0],
+                tiepoint[4] + tiepoint[1] * scale[1],
+            ),
+            pixel: (scale[0], scale[1]),
+            nodata,
+        })
+    }
+
+    /// The `place` method returns the place of `longitude` and `latitude` in the full picture,
+    /// in pixels from the corner of the first.
+    fn place(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        let (x, y) = self.projection.forward(longitude, latitude);
+        (
+            (x - self.origin.0) / self.pixel.0,
+            (self.origin.1 - y) / self.pixel.1,
+        )
+    }
+
+    /// The `level` method returns the smallest picture with at most `across` pixels of the full
+    /// picture to each of its own, for a tile of the map showing `across` pixels of the full
+    /// picture to each of its own.
+    fn level(&self, across: f64) -> &Level {
+        let full = f64::from(self.levels[0].width);
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| full / f64::from(level.width) <= across.max(1.0))
+            .unwrap_or(&self.levels[0])
+    }
+
+    /// The `decode` method decodes the tile at `index` of the picture at `level`, fetched as
+    /// `bytes` from `offset` in the file.
+    ///
+    /// Will [`crate::Blame::Tiff`] if the tile cannot be decoded, and
+    /// [`crate::Excuse::SceneLayout`] if its pixels are not 8 bits a band.
+    fn decode(&self, level: &Level, index: u32, offset: u64, bytes: &[u8]) -> Arrive<Chunk> {
+        let parts = vec![(0, self.header.as_slice()), (offset, bytes)];
+        let mut decoder = tiff::decoder::Decoder::new(Sparse::new(parts))?;
+        decoder.seek_to_image(level.ifd)?;
+        let (width, height) = decoder.chunk_data_dimensions(index);
+        let samples = match decoder.read_chunk(index)? {
+            tiff::decoder::DecodingResult::U8(samples) => samples,
+            _ => return Err(Excuse::SceneLayout.into()),
+        };
+        let bands = samples.len() / (width as usize * height as usize).max(1);
+        Ok(Chunk {
+            width,
+            height,
+            bands,
+            samples,
+        })
+    }
+}
+
+impl std::fmt::Debug for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Layout")
+            .field("levels", &self.levels.len())
+            .field("projection", &self.projection)
+            .field("origin", &self.origin)
+            .field("pixel", &self.pixel)
+            .field("nodata", &self.nodata)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `Level` struct holds the layout of the full picture of a COG, or of one of its
+/// overviews.
+///
+/// * The `ifd` field holds the number of the picture in the file.
+/// * The `width` and `height` fields hold the size of the picture, in pixels.
+/// * The `tile_width` and `tile_height` fields hold the size of its tiles, in pixels.
+/// * The `offsets` and `counts` fields hold the place and length of each tile in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Level {
+    ifd: usize,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    offsets: Vec<u64>,
+    counts: Vec<u64>,
+}
+
+impl Level {
+    /// The `chunk_at` method returns the number of the tile holding the pixel at `x` and `y`,
+    /// and the place of the pixel in the tile, or [`None`] off the picture.
+    fn chunk_at(&self, x: f64, y: f64) -> Option<(u32, u32, u32)> {
+        let inside =
+            (0.0..f64::from(self.width)).contains(&x) && (0.0..f64::from(self.height)).contains(&y);
+        if !inside {
+            return None;
+        }
+        let (x, y) = (x as u32, y as u32);
+        let across = self.width.div_ceil(self.tile_width.max(1));
+        let chunk = y / self.tile_height * across + x / self.tile_width;
+        Some((chunk, x % self.tile_width, y % self.tile_height))
+    }
+}
+
+/// The `Chunk` struct holds the pixels of one tile of a COG.  A tile at the right or bottom
+/// edge of the picture may hold fewer pixels than the others.
+///
+/// * The `width` and `height` fields hold the size of the tile, in pixels.
+/// * The `bands` field holds the number of samples in each pixel.
+/// * The `samples` field holds the samples, a pixel at a time, a row at a time.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Chunk {
+    width: u32,
+    height: u32,
+    bands: usize,
+    samples: Vec<u8>,
+}
+
+impl Chunk {
+    /// The `pixel` method returns the color of the pixel at `x` and `y`, as described in the
+    /// [`Cog`] docs, or [`None`] if the tile holds no such pixel or it holds `nodata`.
+    fn pixel(&self, x: u32, y: u32, nodata: Option<u8>) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let start = (y as usize * self.width as usize + x as usize) * self.bands;
+        let pixel = self.samples.get(start..start + self.bands)?;
+        let color = match pixel {
+            [red, green, blue, ..] => [*red, *green, *blue],
+            [grey, ..] => [*grey; 3],
+            [] => return None,
+        };
+        match nodata {
+            Some(nodata) if color == [nodata; 3] => None,
+            _ => Some([color[0], color[1], color[2], 255]),
+        }
+    }
+}
+
+/// The `Chunks` struct holds the tiles of the picture fetched lately, the last [`COG_CHUNKS`]
+/// of them, keyed by the number of the picture in the file and of the tile in it.
+#[derive(Debug, Default)]
+struct Chunks {
+    chunks: HashMap<(usize, u32), Arc<Chunk>>,
+    order: VecDeque<(usize, u32)>,
+}
+
+impl Chunks {
+    /// The `get` method returns the tile at `key`, if kept.
+    fn get(&self, key: &(usize, u32)) -> Option<Arc<Chunk>> {
+        self.chunks.get(key).cloned()
+    }
+
+    /// The `keep` method keeps `chunk` at `key`, letting go of the tile kept longest ago when
+    /// there are more than [`COG_CHUNKS`].
+    fn keep(&mut self, key: (usize, u32), chunk: Arc<Chunk>) {
+        if self.chunks.insert(key, chunk).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > COG_CHUNKS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.chunks.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// The `Projection` enum holds the projections of the pictures a [`Cog`] can draw, as named by
+/// the GeoTIFF keys of the file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Projection {
+    /// The `Geographic` variant indicates longitude and latitude, in degrees.
+    Geographic,
+    /// The `Mercator` variant indicates web mercator, in meters, like the base map.
+    Mercator,
+    /// The `Utm` variant indicates a zone of Universal Transverse Mercator, in meters, and
+    /// whether it is the zone south of the equator.
+    Utm(u16, bool),
+}
+
+impl Projection {
+    /// The `from_keys` method reads the projection from the GeoTIFF `keys` of a file, or returns
+    /// [`None`] for one we cannot draw.  The keys start with four numbers about the keys, and go
+    /// on four numbers to a key, with the code of the key first and its value last.
+    fn from_keys(keys: &[u16]) -> Option<Self> {
+        let key = |code: u16| {
+            keys.get(4..)?
+                .chunks_exact(4)
+                .find(|entry| entry[0] == code && entry[1] == 0)
+                .map(|entry| entry[3])
+        };
+        match key(PROJECTED_KEY).or_else(|| key(GEOGRAPHIC_KEY))? {
+            4326 | 4269 => Some(Self::Geographic),
+            3857 => Some(Self::Mercator),
+            code @ 32601..=32660 => Some(Self::Utm(code - 32600, false)),
+            code @ 32701..=32760 => Some(Self::Utm(code - 32700, true)),
+            // NAD83 differs from WGS84 by about a meter, less than a pixel of any scene.
+            code @ 26901..=26923 => Some(Self::Utm(code - 26900, false)),
+            code => {
+                tracing::info!("The scene is in EPSG:{code}, which the map cannot draw.");
+                None
+            }
+        }
+    }
+
+    /// The `forward` method returns the place of `longitude` and `latitude` in the projection.
+    /// We project to UTM with the series of Snyder, good to well under a meter across a zone.
+    fn forward(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        let (zone, south) = match self {
+            Self::Geographic => return (longitude, latitude),
+            Self::Mercator => return Extent::project(longitude, latitude),
+            Self::Utm(zone, south) => (f64::from(*zone), *south),
+        };
+        let (a, k0) = (6_378_137.0, 0.9996);
+        let flattening = 1.0 / 298.257_223_563;
+        let e2: f64 = flattening * (2.0 - flattening);
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        let ep2 = e2 / (1.0 - e2);
+        let phi = latitude.to_radians();
+        let lambda = (longitude - (zone * 6.0 - 183.0)).to_radians();
+        let (sin, cos, tan) = (phi.sin(), phi.cos(), phi.tan());
+        let n = a / (1.0 - e2 * sin * sin).sqrt();
+        let t = tan * tan;
+        let c = ep2 * cos * cos;
+        let big_a = cos * lambda;
+        let m = a
+            * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+                - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+                + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+                - (35.0 * e6 / 3072.0) * (6.0 * phi).sin());
+        let x = k0
+            * n
+            * (big_a
+                + (1.0 - t + c) * big_a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * big_a.powi(5) / 120.0)
+            + 500_000.0;
+        let y = k0
+            * (m + n
+                * tan
+                * (big_a.powi(2) / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * big_a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * big_a.powi(6) / 720.0));
+        match south {
+            true => (x, y + 10_000_000.0),
+            false => (x, y),
+        }
+    }
+}
+
+/// The `Sparse` struct reads a file of which we hold only some parts, like the header of a COG
+/// and one of its tiles, for the [`tiff`] decoder, which reads from a whole file.  A read
+/// outside the parts fails as the end of the file.
+///
+/// * The `parts` field holds each part with its place in the file.
+/// * The `position` field holds the place in the file of the next read.
+struct Sparse<'a> {
+    parts: Vec<(u64, &'a [u8])>,
+    position: u64,
+}
+
+impl<'a> Sparse<'a> {
+    /// The `new` method creates a reader over `parts`, at the start of the file.
+    fn new(parts: Vec<(u64, &'a [u8])>) -> Self {
+        Self { parts, position: 0 }
+    }
+}
+
+impl Read for Sparse<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let position = self.position;
+        let Some((start, bytes)) = self
+            .parts
+            .iter()
+            .find(|(start, bytes)| (*start..*start + bytes.len() as u64).contains(&position))
+        else {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        };
+        let from = (position - start) as usize;
+        let count = buf.len().min(bytes.len() - from);
+        buf[..count].copy_from_slice(&bytes[from..from + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for Sparse<'_> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let end = self
+            .parts
+            .iter()
+            .map(|(start, bytes)| start + bytes.len() as u64)
+            .max()
+            .unwrap_or_default();
+        let position = match pos {
+            io::SeekFrom::Start(position) => Some(position),
+            io::SeekFrom::Current(step) => self.position.checked_add_signed(step),
+            io::SeekFrom::End(step) => end.checked_add_signed(step),
+        };
+        self.position = position.ok_or(io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(self.position)
+    }
+}
+
+/// The `COG_TILE` constant holds the size of a tile of the map a [`Cog`] draws, in pixels, the
+/// size of the tiles of the base map.
+pub const COG_TILE: u32 = 256;
+
+/// The `COG_HEADER` constant holds how many bytes from the start of a COG a [`Cog`] asks for
+/// first, which holds the whole header of most.
+pub const COG_HEADER: u64 = 64 * 1024;
+
+/// The `COG_HEADER_LIMIT` constant holds the most bytes a [`Cog`] asks for in search of the end
+/// of the header, past which the file is not laid out for reading in parts.
+pub const COG_HEADER_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// The `COG_CHUNKS` constant holds how many tiles of the picture a [`Cog`] keeps for reuse.
+pub const COG_CHUNKS: usize = 64;
+
+/// The `PROJECTED_KEY` constant holds the code of the GeoTIFF key naming a projection.
+const PROJECTED_KEY: u16 = 3072;
+
+/// The `GEOGRAPHIC_KEY` constant holds the code of the GeoTIFF key naming a datum in longitude
+/// and latitude.
+const GEOGRAPHIC_KEY: u16 = 2048;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utm_zones_center_on_their_meridian() {
+        let zone = Projection::from_keys(&[1, 1, 0, 1, 3072, 0, 1, 32610]).expect("zone");
+        assert_eq!(zone, Projection::Utm(10, false));
+        let (x, y) = zone.forward(-123.0, 42.0);
+        assert!((x - 500_000.0).abs() < 0.01);
+        assert!((y - 4_649_776.22).abs() < 0.1);
+        let (west, _) = zone.forward(-124.0, 42.0);
+        let (east, _) = zone.forward(-122.0, 42.0);
+        assert!((west + east - 1_000_000.0).abs() < 0.01);
+        assert!(Projection::from_keys(&[1, 1, 0, 1, 3072, 0, 1, 2270]).is_none());
+    }
+
+    #[test]
+    fn sparse_files_read_their_parts() {
+        let header = [1, 2, 3, 4];
+        let tile = [9, 8];
+        let mut sparse = Sparse::new(vec![(0, &header[..]), (10, &tile[..])]);
+        let mut buf = [0; 4];
+        sparse.read_exact(&mut buf).expect("header");
+        assert_eq!(buf, header);
+        assert!(sparse.read(&mut buf).is_err());
+        sparse.seek(io::SeekFrom::Start(10)).expect("seek");
+        let mut buf = [0; 2];
+        sparse.read_exact(&mut buf).expect("tile");
+        assert_eq!(buf, tile);
+    }
+
+    #[test]
+    fn tiles_of_the_picture_are_found() {
+        let level = Level {
+            ifd: 0,
+            width: 1000,
+            height: 600,
+            tile_width: 512,
+            tile_height: 512,
+            offsets: vec![0; 4],
+            counts: vec![0; 4],
+        };
+        assert_eq!(level.chunk_at(10.0, 10.0), Some((0, 10, 10)));
+        assert_eq!(level.chunk_at(600.0, 520.0), Some((3, 88, 8)));
+        assert_eq!(level.chunk_at(1000.0, 10.0), None);
+        assert_eq!(level.chunk_at(-0.5, 10.0), None);
+    }
+}
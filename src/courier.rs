@@ -0,0 +1,215 @@
+use crate::{
+    Act, Arrive, Blame, Cog, Dataset, Figure, Hardware, Job, Overflow, Parcel, Pipe, Pushed, Query,
+    MAILBOX_CAPACITY, SHELF_CAPACITY,
+};
+use winit::{event_loop, window};
+
+/// The `courier` module provides the [`UserEvent`] enum, which carries news from async tasks to
+/// the event loop, and the [`Courier`] struct, which delivers it.
+///
+/// # Getting errors out of async tasks with `Courier`
+///
+/// A task spawned on the [`tokio`] runtime has nobody to return its error to.  A download that
+/// fails inside [`tokio::spawn`] drops its [`Blame`] on the floor, and the user waits for a result
+/// that never comes.  The event loop is where the user hears about things, so errors need to get
+/// there.  The [`winit`] event loop accepts events of our own through an
+/// [`event_loop::EventLoopProxy`], as described in the docs for [`crate::App`], and the
+/// [`UserEvent`] enum is the type it accepts.
+///
+/// The `Courier` wraps a clone of the proxy for a background task to carry:
+///
+/// * [`Courier::spawn`] runs a task that returns an [`Arrive`], and sends the error on if there is
+///   one.  Background work should go through it rather than calling [`crate::spawn`] directly.
+/// * [`Courier::send`] sends a [`Blame`] from a task that wants to carry on after reporting it.
+///
+/// The [`crate::App`] receives each [`UserEvent::Error`] in its `user_event` method, and handles it
+/// by [`crate::Severity`]: transient errors go to the log, since the task should have retried
+/// them already, other errors go to the user, and fatal errors close the app.
+///
+/// Errors are not the only news.  A job run by [`crate::Tasks`] reports its progress as a
+/// [`UserEvent::Progress`] and its success as a [`UserEvent::Finished`], a file read in the
+/// background arrives as a [`UserEvent::Loaded`], and a task that has
+/// changed what a window shows asks for a [`UserEvent::Redraw`].  [`Courier::notify`] sends any of
+/// them.  Each kind of news gets a variant of its own, so the compiler tells us when the app
+/// forgets to handle one.
+///
+/// Some news goes stale the moment more arrives, and a fast job could otherwise bury the event
+/// loop in it.  [`Courier::notify`] puts each [`UserEvent::Progress`] and [`UserEvent::Redraw`]
+/// in a [`Pipe`] rather than sending it straight on, where a newer report for the same job or
+/// window replaces the one waiting.  The event loop hears a single [`UserEvent::Mail`] when the
+/// pipe fills from empty, and drains it then.
+///
+/// Tables are news of another kind.  The refresher reads a file again each time it changes, and
+/// the watcher offers every file that lands in a data folder, so a tool writing a file over and
+/// over could queue a copy of the table for every write.  Each [`UserEvent::Loaded`] and
+/// [`UserEvent::Offer`] goes on a shelf of its own, another [`Pipe`], keyed by the path of the
+/// file, so a newer read of a file replaces the one waiting, as the [`crate::App`] would replace
+/// it anyway.  The [`UserEvent::Styled`] that follows each table goes on the shelf the same
+/// way.  The shelf holds [`SHELF_CAPACITY`] files, and a full shelf turns away the new
+/// table, with a warning, rather than losing one the user is already waiting on.
+///
+/// * The `proxy` field holds the [`event_loop::EventLoopProxy`] to the event loop.
+/// * The `mailbox` field holds the [`Pipe`] of news waiting to be merged, shared among clones.
+/// * The `shelf` field holds the [`Pipe`] of tables and offers waiting, shared among clones.
+#[derive(Debug, Clone)]
+pub struct Courier {
+    proxy: event_loop::EventLoopProxy<UserEvent>,
+    mailbox: Pipe<Slot, UserEvent>,
+    shelf: Pipe<Slot, UserEvent>,
+}
+
+impl Courier {
+    /// The `new` method creates a courier delivering to the event loop through `proxy`, with an
+    /// empty mailbox of [`MAILBOX_CAPACITY`] places that drops the oldest news when full, and an
+    /// empty shelf of [`SHELF_CAPACITY`] places that turns away new tables when full.
+    pub fn new(proxy: event_loop::EventLoopProxy<UserEvent>) -> Self {
+        Self {
+            proxy,
+            mailbox: Pipe::new(MAILBOX_CAPACITY, Overflow::DropOldest),
+            shelf: Pipe::new(SHELF_CAPACITY, Overflow::DropNewest),
+        }
+    }
+
+    /// The `send` method delivers `blame` to the event loop as a [`UserEvent::Error`].  If the
+    /// event loop has closed, nobody is left to tell, so we log the error and move on.
+    pub fn send(&self, blame: Blame) {
+        if let Err(closed) = self.proxy.send_event(UserEvent::Error(blame)) {
+            if let UserEvent::Error(blame) = closed.0 {
+                tracing::warn!("Event loop closed before receiving: {blame}");
+            }
+        }
+    }
+
+    /// The `notify` method delivers `event` to the event loop.  If the event loop has closed, the
+    /// app is on its way out and nobody needs the news, so we note it at trace level and move on.
+    /// Errors should go through [`Courier::send`] instead, which logs them louder.
+    ///
+    /// Progress and redraws go through the mailbox, and tables and offers through the shelf, as
+    /// described in the [`Courier`] docs.
+    pub fn notify(&self, event: UserEvent) {
+        let slot = match &event {
+            UserEvent::Progress(job) => Some((Slot::Progress(*job.id()), &self.mailbox)),
+            UserEvent::Redraw(id) => Some((Slot::Redraw(*id), &self.mailbox)),
+            UserEvent::Loaded(dataset) => Some((Slot::Loaded(dataset.path().clone()), &self.shelf)),
+            UserEvent::Styled(figure) => Some((Slot::Styled(figure.path().clone()), &self.shelf)),
+            UserEvent::Offer(path) => Some((Slot::Offer(path.clone()), &self.shelf)),
+            _ => None,
+        };
+        let event = match slot {
+            Some((slot, pipe)) => match pipe.push(slot.clone(), event) {
+                Pushed::First => UserEvent::Mail(pipe.clone()),
+                Pushed::Dropped => {
+                    match slot {
+                        Slot::Loaded(path) | Slot::Styled(path) | Slot::Offer(path) => {
+                            tracing::warn!(
+                                "Too many tables waiting, turned away {}.",
+                                path.display()
+                            )
+                        }
+                        _ => tracing::trace!("Mailbox full, dropped the oldest news."),
+                    }
+                    return;
+                }
+                Pushed::Queued | Pushed::Merged => return,
+            },
+            None => event,
+        };
+        if let Err(closed) = self.proxy.send_event(event) {
+            tracing::trace!("Event loop closed before receiving: {:?}", closed.0);
+        }
+    }
+
+    /// The `spawn` method runs `task` in the background with [`crate::spawn`], and delivers its
+    /// error, if any, with [`Courier::send`].
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: std::future::Future<Output = Arrive<()>> + Send + 'static,
+    {
+        let courier = self.clone();
+        crate::spawn(async move {
+            if let Err(blame) = task.await {
+                courier.send(blame);
+            }
+        })
+    }
+}
+
+/// The `UserEvent` enum holds the events we send to the [`winit`] event loop through an
+/// [`event_loop::EventLoopProxy`].
+///
+/// * The `AccessKit` variant holds an event from the [`accesskit_winit`] adapter.
+/// * The `Error` variant holds a [`Blame`] from a background task, sent by a [`Courier`].
+/// * The `Progress` variant holds a [`Job`] that has reported progress.
+/// * The `Finished` variant holds a [`Job`] that finished without error, so its data is ready.
+/// * The `Loaded` variant holds a [`Dataset`] read from a file in the background.
+/// * The `Styled` variant holds the [`Figure`] of a [`Dataset`], styled in the background.
+/// * The `Found` variant holds a [`Parcel`] looked up in the background, the [`Query`] that
+///   found it, and the [`window::WindowId`] of the window it was looked up in.
+/// * The `Offer` variant holds the path of a data file that turned up in a watched folder.
+/// * The `Open` variant holds the paths of the files the desktop opened with the app, as
+///   described in [`crate::Finder`].
+/// * The `Scene` variant holds the [`Cog`] of a scene opened in the background, to show on
+///   the maps.
+/// * The `Prepared` variant holds the [`Hardware`] opened in the background by
+///   [`crate::Gpu::prepare`], in the browser.
+/// * The `Mail` variant holds the mailbox of a [`Courier`] with news waiting in it.
+/// * The `Redraw` variant holds the [`window::WindowId`] of a window whose contents have changed.
+#[derive(Debug, derive_more::From)]
+pub enum UserEvent {
+    /// The `AccessKit` variant holds an accessibility event.
+    AccessKit(accesskit_winit::Event),
+    /// The `Error` variant holds an error from a background task.
+    Error(Blame),
+    /// The `Progress` variant holds a job that is further along.
+    #[from(skip)]
+    Progress(Job),
+    /// The `Finished` variant holds a job whose results are ready.
+    #[from(skip)]
+    Finished(Job),
+    /// The `Loaded` variant holds a table read from a data file.
+    Loaded(Dataset),
+    /// The `Styled` variant holds the features of a table ready to draw.
+    Styled(Figure),
+    /// The `Found` variant holds a parcel looked up for a window.
+    #[from(skip)]
+    Found(window::WindowId, Query, Parcel),
+    /// The `Offer` variant holds the path of a new or changed data file the user may load.
+    Offer(std::path::PathBuf),
+    /// The `Open` variant holds the paths of files the user opened with the app.
+    #[from(skip)]
+    Open(Vec<std::path::PathBuf>),
+    /// The `Scene` variant holds the picture of a scene, ready to draw.
+    Scene(Cog),
+    /// The `Prepared` variant holds the graphics card, opened for the maps.
+    Prepared(Hardware),
+    /// The `Mail` variant holds a mailbox to drain.
+    #[from(skip)]
+    Mail(Pipe<Slot, UserEvent>),
+    /// The `Redraw` variant holds the id of a window to redraw.
+    Redraw(window::WindowId),
+    /// The `Hotkey` variant holds the act of a global hotkey the user pressed.
+    #[from(skip)]
+    Hotkey(Act),
+}
+
+/// The `Slot` enum holds the keys of the mailbox and the shelf of a [`Courier`], under which
+/// news of the same thing merges.
+///
+/// * The `Progress` variant holds the id of a [`Job`].
+/// * The `Redraw` variant holds the [`window::WindowId`] of a window.
+/// * The `Loaded` variant holds the path of the file a [`Dataset`] was read from.
+/// * The `Styled` variant holds the path of the file a [`Figure`] was drawn from.
+/// * The `Offer` variant holds the path of a file offered from a watched folder.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Slot {
+    /// The `Progress` variant indicates the progress of a job.
+    Progress(u64),
+    /// The `Redraw` variant indicates a redraw of a window.
+    Redraw(window::WindowId),
+    /// The `Loaded` variant indicates a table read from a file.
+    Loaded(std::path::PathBuf),
+    /// The `Styled` variant indicates the features of a table styled for drawing.
+    Styled(std::path::PathBuf),
+    /// The `Offer` variant indicates a file offered for loading.
+    Offer(std::path::PathBuf),
+}
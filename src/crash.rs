@@ -0,0 +1,248 @@
+use crate::{Arrive, Context, Home};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path;
+use std::sync::{Arc, Mutex};
+
+/// The `crash` module provides the [`Crash`] struct, which writes a crash report when the app
+/// panics, and the [`Recent`] struct, which remembers the last lines of the log for the report.
+///
+/// # Reporting panics with `Crash`
+///
+/// When the app panics, the message goes to the terminal, which a user launching from a desktop
+/// shortcut does not have.  The window vanishes, and the bug report we get reads "it closed".
+/// Instead, [`Crash::install`] sets a panic hook that writes a crash report to a file under the
+/// [`CRASH_DIR`] directory of [`Home::data`], before handing the panic on to the default hook.
+/// The report holds:
+///
+/// * The version of the app.
+/// * The panic message, and where in the code it happened.
+/// * A backtrace, captured whether or not `RUST_BACKTRACE` is set.
+/// * The last [`CRASH_LINES`] lines of the log, from [`Recent`], which [`crate::trace_init`]
+///   feeds alongside the usual output.
+///
+/// The app is in no state to show a dialog while it panics, so the next start does it instead.
+/// The [`crate::App`] calls [`Crash::report`], which moves each new report into the
+/// [`REPORTED`] directory, so we only mention it once, and shows the user where the reports went
+/// in a notice over the map, with [`crate::App::warn`].
+///
+/// * The `dir` field holds the directory to write crash reports to.
+/// * The `recent` field holds the [`Recent`] log lines to include.
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Crash {
+    dir: path::PathBuf,
+    recent: Recent,
+}
+
+impl Crash {
+    /// The `install` method sets the panic hook described in the [`Crash`] docs.  The previous
+    /// hook, normally the default one that prints to the terminal, still runs after ours.
+    pub fn install(self) {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            match self.write(info) {
+                Ok(path) => eprintln!("Crash report saved to {}.", path.display()),
+                Err(e) => eprintln!("Could not save a crash report: {e}"),
+            }
+            previous(info);
+        }));
+        tracing::trace!("Panic hook installed.");
+    }
+
+    /// The `write` method writes a crash report for the panic described by `info`, and returns
+    /// the path of the file.  Reports are named after the time of the crash in seconds since
+    /// the Unix epoch and the id of the process, as in `crash-1729100000-4242.log`.  We never
+    /// write over a report, so a second panic in the same second, on another thread, adds a
+    /// count to the name, as in `crash-1729100000-4242-1.log`.
+    ///
+    /// Will [`crate::Blame::Io`] if the directory cannot be created or the file cannot be
+    /// written.
+    pub fn write(&self, info: &std::panic::PanicHookInfo) -> Arrive<path::PathBuf> {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "unknown panic".to_string(),
+            },
+        };
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| "unknown location".to_string());
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating {}", self.dir.display()))?;
+        let pid = std::process::id();
+        let mut count = 0;
+        let (path, mut file) = loop {
+            let name = match count {
+                0 => format!("crash-{seconds}-{pid}.log"),
+                count => format!("crash-{seconds}-{pid}-{count}.log"),
+            };
+            let path = self.dir.join(name);
+            match std::fs::File::create_new(&path) {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => count += 1,
+                Err(e) => return Err(e).with_context(|| format!("creating {}", path.display())),
+            }
+        };
+        writeln!(
+            file,
+            "{} {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(file, "Panicked on thread '{thread}' at {location}:")?;
+        writeln!(file, "{message}")?;
+        writeln!(file, "\nBacktrace:\n{backtrace}")?;
+        writeln!(file, "\nRecent log:")?;
+        for line in self.recent.lines() {
+            writeln!(file, "{line}")?;
+        }
+        Ok(path)
+    }
+
+    /// The `report` method moves each crash report in `dir` from an earlier run into the
+    /// [`REPORTED`] directory, and returns the new paths of the reports, for the app to tell the
+    /// user about.
+    #[tracing::instrument]
+    pub fn report(dir: &path::Path) -> Vec<path::PathBuf> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut crashes = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file() && path.extension().is_some_and(|extension| extension == "log")
+            })
+            .collect::<Vec<path::PathBuf>>();
+        crashes.sort();
+        let reported = dir.join(REPORTED);
+        let mut moved = Vec::new();
+        for crash in crashes {
+            let Some(name) = crash.file_name() else {
+                continue;
+            };
+            let target = reported.join(name);
+            let path = match std::fs::create_dir_all(&reported)
+                .and_then(|_| std::fs::rename(&crash, &target))
+            {
+                Ok(()) => target,
+                Err(e) => {
+                    tracing::trace!("Could not move {}: {e}", crash.display());
+                    crash
+                }
+            };
+            tracing::trace!("Found the crash report {}.", path.display());
+            moved.push(path);
+        }
+        moved
+    }
+
+    /// The `dir` method returns the directory for crash reports under the data directory of
+    /// `home`.
+    pub fn dir(home: &Home) -> path::PathBuf {
+        home.data().join(CRASH_DIR)
+    }
+}
+
+/// The `Recent` struct holds the last [`CRASH_LINES`] lines of the log, for [`Crash`] to include
+/// in a report.  It serves as a writer for a [`tracing_subscriber::fmt::Layer`], which hands it
+/// each formatted event in turn.  Clones share the same lines.
+///
+/// * The `lines` field holds the lines, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct Recent {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl Recent {
+    /// The `lines` method returns a copy of the lines held.  Since it runs inside the panic
+    /// hook, it will not wait on a lock held by the thread that panicked, and returns nothing
+    /// instead.
+    pub fn lines(&self) -> Vec<String> {
+        match self.lines.try_lock() {
+            Ok(lines) => lines.iter().cloned().collect(),
+            Err(std::sync::TryLockError::Poisoned(lines)) => {
+                lines.into_inner().iter().cloned().collect()
+            }
+            Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+        }
+    }
+
+    /// The `push` method adds `line` to the end, dropping the oldest line once there are more
+    /// than [`CRASH_LINES`].
+    fn push(&self, line: String) {
+        let mut lines = match self.lines.lock() {
+            Ok(lines) => lines,
+            Err(e) => e.into_inner(),
+        };
+        lines.push_back(line);
+        while lines.len() > CRASH_LINES {
+            lines.pop_front();
+        }
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for Recent {
+    type Writer = RecentWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RecentWriter {
+            recent: self.clone(),
+            buffer: String::new(),
+        }
+    }
+}
+
+/// The `RecentWriter` struct collects the text of one log event for [`Recent`], which gets the
+/// complete lines when the writer drops.
+///
+/// * The `recent` field holds the [`Recent`] to add lines to.
+/// * The `buffer` field holds the text written so far.
+#[derive(Debug)]
+pub struct RecentWriter {
+    recent: Recent,
+    buffer: String,
+}
+
+impl std::io::Write for RecentWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.push_str(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RecentWriter {
+    fn drop(&mut self) {
+        for line in self.buffer.lines().filter(|line| !line.is_empty()) {
+            self.recent.push(line.to_string());
+        }
+    }
+}
+
+/// The `CRASH_DIR` constant holds the name of the directory for crash reports, under the data
+/// directory from [`Home::data`].
+pub const CRASH_DIR: &str = "crashes";
+
+/// The `CRASH_LINES` constant sets how many lines of the log a [`Recent`] keeps for a crash
+/// report.
+pub const CRASH_LINES: usize = 200;
+
+/// The `REPORTED` constant holds the name of the directory under [`CRASH_DIR`] for crash reports
+/// the user has already been told about.
+pub const REPORTED: &str = "reported";
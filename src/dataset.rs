@@ -0,0 +1,741 @@
+use crate::{
+    Arrive, Context, Excuse, Extent, GeoPackage, GeoParquet, Job, Shape, Shapefile, Synopsis,
+};
+use arrow::array::{Array, ArrayRef, AsArray, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Float64Type, Schema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path;
+use std::sync::{Arc, OnceLock};
+
+/// The `dataset` module provides the [`Dataset`] struct, which holds a table read from a data
+/// file, and the [`Format`] enum, which names the kinds of file it reads.
+///
+/// # Reading data files with `Dataset`
+///
+/// Users bring their own data: a CSV of building permits, a GeoJSON file of parcels from the
+/// county.  The county files run to hundreds of megabytes, and parsing one on the event loop
+/// freezes every window until it is done.  The user sees a frozen app, and closes it.
+///
+/// Instead, [`Dataset::load`] hands the parsing to a worker thread with [`crate::unblock`],
+/// where it can take as long as it likes.  The [`crate::App`]
+/// runs the load as an interactive job of the [`crate::Tasks`] registry when the user drops a file
+/// on a window, so the user can see it in [`crate::Act::ShowTasks`] and cancel it with
+/// [`crate::Act::CancelTasks`]:
+///
+/// * The file is read through a `Tally`, which counts the bytes the parser has taken, and reports
+///   them as progress with [`Job::set_progress`] each time another [`PROGRESS_STEP`] of the file
+///   has gone by.  Each report reaches the event loop as a [`crate::UserEvent::Progress`].
+/// * The `Tally` checks [`Job::is_cancelled`] before each read, and fails the read once the job
+///   is cancelled, so the worker thread stops parsing rather than finishing a file nobody wants.
+/// * The finished `Dataset` reaches the event loop as a [`crate::UserEvent::Loaded`].
+///
+/// Every format comes out as the same kind of table, one row per record and one column per
+/// field, ready for a layer or a chart to pick from:
+///
+/// * A CSV file keeps its header row as the column names.
+/// * A GeoJSON file takes a row for each feature, and a column for each property name found in
+///   any feature, in the order we first see them.  The geometry of the feature, as GeoJSON text,
+///   goes in a last column named [`GEOMETRY`].
+/// * A Parquet file takes a row for each row of the file, and a column for each column, with the
+///   primary geometry of a GeoParquet file in the [`GEOMETRY`] column, as GeoJSON text (see
+///   [`GeoParquet`]).  Large tables read far faster as Parquet than as CSV.
+/// * A GeoPackage takes a row for each feature of its first table of features, and a column for
+///   each column, with the geometry in the [`GEOMETRY`] column, as GeoJSON text (see
+///   [`GeoPackage`]).
+/// * A shapefile takes a row for each record of its `.dbf` table, and a column for each field,
+///   with the shape of the record in the [`GEOMETRY`] column, as GeoJSON text (see
+///   [`Shapefile`]).
+/// * Excel workbooks are recognized, but not read yet, and fail with
+///   [`Excuse::UnsupportedFormat`], as does any file we do not recognize.
+/// * A file read by a [`crate::DataProvider`] comes out however the provider reads it, as
+///   [`Format::Plugin`].
+///
+/// ## Update 0.1.4
+///
+/// The table was a vector of rows of text, which made every sort, filter and sum walk every row
+/// and parse every number again, and copied the whole table on the way to and from Parquet.  The
+/// table is now an Apache [`arrow`] [`RecordBatch`], which holds each column as one typed array:
+///
+/// * A column from a Parquet file keeps its type, so numbers stay numbers, and reading one is a
+///   matter of handing over the arrays the [`parquet`] crate decoded, without a copy.  Writing
+///   one back with [`GeoParquet::save`] hands the arrays the other way.
+/// * A CSV or GeoJSON file reads as text, with an empty value as a missing one, and each column
+///   takes the narrowest type that holds every value in it as written: flags if every value is
+///   `true` or `false`, whole numbers if every value is one, decimal numbers if every value is a
+///   number, and text otherwise.  A value that would print differently as a number, like
+///   `01234` or `1.50`, keeps its column as text, so a ZIP code keeps its leading zero.  The
+///   [`GEOMETRY`] column stays text.
+/// * The kernels of [`arrow::compute`] do the work over whole columns.  [`Dataset::selection`]
+///   filters the rows in view, and [`Dataset::sort`] orders the rows by the column of the
+///   `[sort]` table of the config (see [`crate::Sort`]) on the way out of the app.  Each returns
+///   a new `Dataset` sharing the arrays it keeps.  Cloning a `Dataset` shares its arrays too, so
+///   handing one to a worker or a [`crate::Tool`] is cheap.
+/// * [`Dataset::texts`] and [`Dataset::numbers`] read a whole column as text or numbers, whatever
+///   its type, for code that wants one or the other, like a [`crate::Synopsis`] or a
+///   [`crate::Choropleth`].  A column that already has the type asked for comes back without a
+///   copy, and any other is converted once.  [`Dataset::shapes`] reads the [`GEOMETRY`] column
+///   without a copy.
+/// * [`Dataset::outlines`] reads the [`GEOMETRY`] column into a [`Shape`] for each row the first
+///   time it is asked, and keeps the shapes, shared between clones, so picking the rows in view
+///   each time the map moves does not parse the text of every feature again.
+///
+/// Results from the BEA API, when the app fetches them, are meant to land in a `Dataset` the same
+/// way, as do tables from any other tool that speaks Arrow.
+///
+/// # Writing features back out with `Dataset::save_geojson`
+///
+/// An analysis is only useful if it leaves the app.  The planner who opens the county parcels
+/// and a table of permits wants the parcels in view back in the desktop GIS, with the columns
+/// the app has on them, and [`Dataset::save_geojson`] writes a table to a GeoJSON feature
+/// collection that any GIS tool opens.  [`crate::Act::ExportSelection`] writes the features in
+/// view of the focused map, so the user selects features by framing them, and
+/// [`crate::Act::ExportLayers`] writes every feature of every table.  The files go under the
+/// [`FEATURE_DIR`] directory of the data directory, one per table.  For partners who take only
+/// shapefiles, [`crate::Shapefile`] writes the same features in that format, and for
+/// large tables [`GeoParquet::save`] writes them as GeoParquet.  For the people who work in
+/// spreadsheets, [`crate::Workbook`] writes the rows as an Excel workbook or as CSV, with a
+/// record of where they came from.
+///
+/// Each row becomes a feature.  The [`GEOMETRY`] column goes back to being the geometry, and
+/// every other column becomes a property, including any a [`crate::DataProvider`] joined onto
+/// the table, so the attributes travel with the shapes.  A table with no geometry, such as a
+/// CSV, writes each feature with a null geometry, as GeoJSON allows, and has nothing in view.
+/// A text column read from a CSV or GeoJSON file may still mix numbers in with its text, so to
+/// round-trip a number or a flag from the source file we write back as JSON any value in it that
+/// reads as a JSON number or `true` or `false` and prints the same again, and write a missing
+/// value as null.  A value like `01234`, which would lose its leading zero as a number, stays
+/// text.  A number or flag column writes its values as such, and a text column from Parquet or a
+/// provider writes its text as text.
+///
+/// * The `path` field holds the path of the file the table came from.
+/// * The `format` field holds the [`Format`] of the file.
+/// * The `table` field holds the columns of the table.
+/// * The `outlines` field holds the shape of each row, once [`Dataset::outlines`] has read them.
+#[derive(Debug, Clone, derive_getters::Getters)]
+pub struct Dataset {
+    path: path::PathBuf,
+    format: Format,
+    table: RecordBatch,
+    #[getter(skip)]
+    outlines: Arc<OnceLock<Vec<Option<Shape>>>>,
+}
+
+impl Dataset {
+    /// The `new` method creates a table read from the file at `path` in `format`, holding the
+    /// columns of `table`, for a [`crate::DataProvider`] to hand back.
+    pub fn new(path: path::PathBuf, format: Format, table: RecordBatch) -> Self {
+        Self {
+            path,
+            format,
+            table,
+            outlines: Arc::default(),
+        }
+    }
+
+    /// The `from_rows` method creates a table read from the file at `path` in `format`, with
+    /// columns named by `columns`, holding the `rows` in their order, for a reader or a
+    /// [`crate::DataProvider`] that reads text.  An empty value becomes a missing one, and each
+    /// column takes a type as described in the [`Dataset`] docs.
+    ///
+    /// Will [`crate::Blame::Arrow`] if the table cannot be built.
+    pub fn from_rows(
+        path: path::PathBuf,
+        format: Format,
+        columns: Vec<String>,
+        rows: Vec<Vec<String>>,
+    ) -> Arrive<Self> {
+        let mut fields = Vec::with_capacity(columns.len());
+        let mut arrays = Vec::with_capacity(columns.len());
+        for (index, name) in columns.iter().enumerate() {
+            let values = rows
+                .iter()
+                .map(|row| {
+                    row.get(index)
+                        .map(String::as_str)
+                        .filter(|text| !text.is_empty())
+                })
+                .collect::<Vec<Option<&str>>>();
+            let kind = match name == GEOMETRY {
+                true => DataType::Utf8,
+                false => Self::infer(&values),
+            };
+            arrays.push(Self::array(&values, &kind));
+            fields.push(Field::new(name, kind, true));
+        }
+        // The row count covers a table without columns, which has no array to count.
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        let table =
+            RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), arrays, &options)?;
+        Ok(Self::new(path, format, table))
+    }
+
+    /// The `infer` method returns the type of a column read from text, holding `values`, as
+    /// described in the [`Dataset`] docs.  A column without a value is text.
+    fn infer(values: &[Option<&str>]) -> DataType {
+        let filled = values.iter().flatten().copied().collect::<Vec<&str>>();
+        if filled.is_empty() {
+            DataType::Utf8
+        } else if filled.iter().all(|text| matches!(*text, "true" | "false")) {
+            DataType::Boolean
+        } else if filled.iter().all(|text| {
+            text.parse::<i64>()
+                .is_ok_and(|number| number.to_string() == *text)
+        }) {
+            DataType::Int64
+        } else if filled.iter().all(|text| {
+            text.parse::<f64>()
+                .is_ok_and(|number| number.is_finite() && number.to_string() == *text)
+        }) {
+            DataType::Float64
+        } else {
+            DataType::Utf8
+        }
+    }
+
+    /// The `array` method builds a column of type `kind` from `values`, as picked by
+    /// [`Dataset::infer`].
+    fn array(values: &[Option<&str>], kind: &DataType) -> ArrayRef {
+        match kind {
+            DataType::Boolean => Arc::new(
+                values
+                    .iter()
+                    .map(|text| text.map(|text| text == "true"))
+                    .collect::<BooleanArray>(),
+            ),
+            DataType::Int64 => Arc::new(
+                values
+                    .iter()
+                    .map(|text| text.and_then(|text| text.parse::<i64>().ok()))
+                    .collect::<Int64Array>(),
+            ),
+            DataType::Float64 => Arc::new(
+                values
+                    .iter()
+                    .map(|text| text.and_then(|text| text.parse::<f64>().ok()))
+                    .collect::<Float64Array>(),
+            ),
+            _ => Arc::new(values.iter().copied().collect::<StringArray>()),
+        }
+    }
+
+    /// The `load` method reads the file at `path` on a worker thread, reporting to `job`, as
+    /// described in the [`Dataset`] docs.
+    ///
+    /// Will [`crate::Blame::Join`] if the worker thread panics, and otherwise fails as
+    /// [`Dataset::read`] does.
+    pub async fn load(path: path::PathBuf, job: Job) -> Arrive<Self> {
+        let name = path.display().to_string();
+        crate::unblock(move || Self::read(&path, &job))
+            .await
+            .with_context(|| format!("reading {name}"))?
+    }
+
+    /// The `read` method reads the file at `path`, reporting to `job`.  It blocks until the whole
+    /// file is parsed, so call it from [`Dataset::load`] rather than the event loop.
+    ///
+    /// Will [`Excuse::UnsupportedFormat`] if we cannot read this kind of file,
+    /// [`crate::Blame::Io`] if the file cannot be opened or the job is cancelled,
+    /// [`crate::Blame::Csv`] if a CSV file is malformed, [`crate::Blame::Json`] if a GeoJSON
+    /// file is, [`crate::Blame::Parquet`] if a Parquet file is, and [`crate::Blame::Sqlite`] if a
+    /// GeoPackage is.  A shapefile fails as [`Shapefile::read`] does.
+    #[tracing::instrument(skip(job))]
+    pub fn read(path: &path::Path, job: &Job) -> Arrive<Self> {
+        let format = Format::from_path(path).ok_or(Excuse::UnsupportedFormat)?;
+        if format == Format::Xlsx {
+            tracing::warn!("Excel workbooks are not supported yet.");
+            return Err(Excuse::UnsupportedFormat.into());
+        }
+        let dataset = match format {
+            // Parquet reads by row groups, from anywhere in the file, so it opens the file itself
+            // and reports by rows rather than bytes.
+            Format::Parquet => Self::new(path.to_path_buf(), format, GeoParquet::read(path, job)?),
+            // A GeoPackage is a database, which SQLite opens itself, so it reports by rows too.
+            Format::GeoPackage => {
+                let (columns, rows) = GeoPackage::read(path, job)?;
+                Self::from_rows(path.to_path_buf(), format, columns, rows)?
+            }
+            // A shapefile is several files read by record, so it reports by records as well.
+            Format::Shapefile => {
+                let (columns, rows) = Shapefile::read(path, job)?;
+                Self::from_rows(path.to_path_buf(), format, columns, rows)?
+            }
+            _ => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| format!("opening {}", path.display()))?;
+                let total = file.metadata()?.len();
+                let reader = io::BufReader::new(Tally::new(file, total, job));
+                let (columns, rows) = match format {
+                    Format::Csv => Self::read_csv(reader)?,
+                    _ => Self::read_geojson(reader)?,
+                };
+                Self::from_rows(path.to_path_buf(), format, columns, rows)?
+            }
+        };
+        tracing::info!(
+            "Read {} rows of {} columns from {}.",
+            dataset.len(),
+            dataset.table.num_columns(),
+            path.display()
+        );
+        Ok(dataset)
+    }
+
+    /// The `len` method returns the number of rows in the table.
+    pub fn len(&self) -> usize {
+        self.table.num_rows()
+    }
+
+    /// The `is_empty` method returns `true` if the table has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `columns` method returns the names of the columns, in order.
+    pub fn columns(&self) -> Vec<String> {
+        self.table
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect()
+    }
+
+    /// The `column` method returns the index of the column named `name`, or [`None`] if the table
+    /// has no such column.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.table.schema().index_of(name).ok()
+    }
+
+    /// The `geometry` method returns the index of the [`GEOMETRY`] column, or [`None`] if the
+    /// table has no geometry.
+    pub fn geometry(&self) -> Option<usize> {
+        self.column(GEOMETRY)
+    }
+
+    /// The `shapes` method returns the [`GEOMETRY`] column, as GeoJSON text, without a copy, or
+    /// [`None`] if the table has no geometry.
+    pub fn shapes(&self) -> Option<&StringArray> {
+        self.table.column(self.geometry()?).as_string_opt::<i32>()
+    }
+
+    /// The `outlines` method returns the shape of the geometry of each row, or [`None`] for a
+    /// row without geometry or with geometry we cannot read, as described in the [`Dataset`]
+    /// docs.  The first call reads the [`GEOMETRY`] column, and every later call, on this table
+    /// or a clone of it, returns the same shapes.
+    pub fn outlines(&self) -> &[Option<Shape>] {
+        self.outlines.get_or_init(|| match self.shapes() {
+            Some(shapes) => shapes
+                .iter()
+                .map(|text| {
+                    let geometry = serde_json::from_str::<serde_json::Value>(text?).ok()?;
+                    Shape::from_geojson(&geometry)
+                })
+                .collect(),
+            None => vec![None; self.len()],
+        })
+    }
+
+    /// The `texts` method returns the column at `column` as text, with a missing value null.  A
+    /// text column comes back as it is, without a copy, and any other prints its values into a
+    /// new one.
+    pub fn texts(&self, column: usize) -> Cow<'_, StringArray> {
+        use arrow::util::display::{ArrayFormatter, FormatOptions};
+        let array = self.table.column(column);
+        if let Some(strings) = array.as_string_opt::<i32>() {
+            return Cow::Borrowed(strings);
+        }
+        let printed = match ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default()) {
+            Ok(formatter) => (0..array.len())
+                .map(|row| {
+                    array
+                        .is_valid(row)
+                        .then(|| formatter.value(row).to_string())
+                })
+                .collect::<StringArray>(),
+            Err(e) => {
+                tracing::warn!("Cannot print column {column}: {e}");
+                StringArray::new_null(array.len())
+            }
+        };
+        Cow::Owned(printed)
+    }
+
+    /// The `numbers` method returns the column at `column` as numbers, with a missing value
+    /// null.  A column of floats comes back as it is, without a copy, any other number column
+    /// casts to floats, and a text column reads each value with [`Synopsis::number`].
+    pub fn numbers(&self, column: usize) -> Cow<'_, Float64Array> {
+        let array = self.table.column(column);
+        if let Some(floats) = array.as_primitive_opt::<Float64Type>() {
+            return Cow::Borrowed(floats);
+        }
+        if array.data_type().is_numeric() {
+            if let Ok(floats) = arrow::compute::cast(array, &DataType::Float64) {
+                return Cow::Owned(floats.as_primitive::<Float64Type>().clone());
+            }
+        }
+        let numbers = self
+            .texts(column)
+            .iter()
+            .map(|text| text.and_then(Synopsis::number))
+            .collect::<Float64Array>();
+        Cow::Owned(numbers)
+    }
+
+    /// The `selection` method returns the rows with a position of their geometry in `extent`,
+    /// or every row if there is no `extent`, as a table sharing the arrays of this one.  A table
+    /// without geometry has nothing in any extent.  The rows are picked from
+    /// [`Dataset::outlines`], and the shapes of the rows kept go with the new table.
+    ///
+    /// Will [`crate::Blame::Arrow`] if the rows cannot be filtered.
+    pub fn selection(&self, extent: Option<&Extent>) -> Arrive<Self> {
+        let Some(extent) = extent else {
+            return Ok(self.clone());
+        };
+        let bounds = extent.to_mercator();
+        let outlines = self.outlines();
+        let mask = outlines
+            .iter()
+            .map(|shape| Some(shape.as_ref().is_some_and(|shape| shape.touches(bounds))))
+            .collect::<BooleanArray>();
+        let table = arrow::compute::filter_record_batch(&self.table, &mask)?;
+        let kept = outlines
+            .iter()
+            .zip(mask.values())
+            .filter(|(_, keep)| *keep)
+            .map(|(shape, _)| shape.clone())
+            .collect();
+        Ok(self.derive(table, Some(kept)))
+    }
+
+    /// The `sort` method returns the rows ordered by the column at `column`, largest first if
+    /// `descending`, with missing values last, as a table sharing the arrays of this one.
+    ///
+    /// Will [`crate::Blame::Arrow`] if the column cannot be sorted.
+    pub fn sort(&self, column: usize, descending: bool) -> Arrive<Self> {
+        let options = arrow::compute::SortOptions {
+            descending,
+            nulls_first: false,
+        };
+        let order =
+            arrow::compute::sort_to_indices(self.table.column(column), Some(options), None)?;
+        let table = arrow::compute::take_record_batch(&self.table, &order)?;
+        let outlines = self.outlines.get().map(|outlines| {
+            order
+                .values()
+                .iter()
+                .map(|row| outlines[*row as usize].clone())
+                .collect()
+        });
+        Ok(self.derive(table, outlines))
+    }
+
+    /// The `derive` method returns a table from the same file as this one, holding `table`, with
+    /// the shapes of its rows in `outlines` if we already have them.
+    fn derive(&self, table: RecordBatch, outlines: Option<Vec<Option<Shape>>>) -> Self {
+        let mut dataset = Self::new(self.path.clone(), self.format, table);
+        if let Some(outlines) = outlines {
+            dataset.outlines = Arc::new(OnceLock::from(outlines));
+        }
+        dataset
+    }
+
+    /// The `save_geojson` method writes the rows of the table to `path` as a GeoJSON feature
+    /// collection, as described in the [`Dataset`] docs.  Pick the rows first with
+    /// [`Dataset::selection`].  Returns the number of features written.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be written, and [`crate::Blame::Json`] if
+    /// the features cannot be encoded.
+    #[tracing::instrument(skip(self))]
+    pub fn save_geojson(&self, path: &path::Path) -> Arrive<usize> {
+        let geometry = self.geometry();
+        let columns = self
+            .columns()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != geometry)
+            .map(|(index, name)| (name, self.properties(index)))
+            .collect::<Vec<(String, Vec<serde_json::Value>)>>();
+        let features = (0..self.len())
+            .map(|row| {
+                let properties = columns
+                    .iter()
+                    .map(|(name, values)| (name.clone(), values[row].clone()))
+                    .collect::<serde_json::Map<String, serde_json::Value>>();
+                let shape = self
+                    .shapes()
+                    .filter(|shapes| shapes.is_valid(row))
+                    .and_then(|shapes| serde_json::from_str(shapes.value(row)).ok())
+                    .unwrap_or(serde_json::Value::Null);
+                serde_json::json!({
+                    "type": "Feature",
+                    "geometry": shape,
+                    "properties": properties,
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+        let count = features.len();
+        let collection = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features,
+        });
+        let file =
+            std::fs::File::create(path).with_context(|| format!("writing {}", path.display()))?;
+        let mut writer = io::BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &collection)?;
+        writer.flush()?;
+        tracing::info!("Wrote {count} features to {}.", path.display());
+        Ok(count)
+    }
+
+    /// The `save_csv` method writes the rows of the table to `path` as CSV, with a header row of
+    /// the column names, and every value as text, with a missing value empty.  The
+    /// [`GEOMETRY`] column goes as GeoJSON text, so the file reads back with its geometry.
+    /// Returns the number of rows written.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be written, and [`crate::Blame::Csv`] if a
+    /// row cannot be encoded.
+    #[tracing::instrument(skip(self))]
+    pub fn save_csv(&self, path: &path::Path) -> Arrive<usize> {
+        let columns = (0..self.table.num_columns())
+            .map(|column| self.texts(column))
+            .collect::<Vec<Cow<'_, StringArray>>>();
+        let file =
+            std::fs::File::create(path).with_context(|| format!("writing {}", path.display()))?;
+        let mut writer = csv::Writer::from_writer(io::BufWriter::new(file));
+        writer.write_record(self.columns())?;
+        for row in 0..self.len() {
+            writer.write_record(columns.iter().map(|column| match column.is_valid(row) {
+                true => column.value(row),
+                false => "",
+            }))?;
+        }
+        writer.flush()?;
+        tracing::info!("Wrote {} rows to {}.", self.len(), path.display());
+        Ok(self.len())
+    }
+
+    /// The `properties` method returns the value of each row in the column at `column` as a
+    /// JSON value for [`Dataset::save_geojson`], as described in the [`Dataset`] docs.
+    fn properties(&self, column: usize) -> Vec<serde_json::Value> {
+        let array = self.table.column(column);
+        let typed = matches!(self.format, Format::Parquet | Format::Plugin)
+            && matches!(array.data_type(), DataType::Utf8 | DataType::LargeUtf8);
+        self.texts(column)
+            .iter()
+            .map(|text| match (text, typed) {
+                (None, _) => serde_json::Value::Null,
+                (Some(text), true) => serde_json::Value::String(text.to_string()),
+                (Some(text), false) => Self::value(text),
+            })
+            .collect()
+    }
+
+    /// The `value` method turns the text of a cell back into a JSON value for
+    /// [`Dataset::save_geojson`], as described in the [`Dataset`] docs.
+    fn value(text: &str) -> serde_json::Value {
+        if text.is_empty() {
+            return serde_json::Value::Null;
+        }
+        match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(value @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)))
+                if value.to_string() == text =>
+            {
+                value
+            }
+            _ => serde_json::Value::String(text.to_string()),
+        }
+    }
+
+    /// The `read_csv` method reads a CSV table with a header row from `reader`.
+    fn read_csv<R: Read>(reader: R) -> Arrive<(Vec<String>, Vec<Vec<String>>)> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let columns = reader
+            .headers()?
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            rows.push(record?.iter().map(|value| value.to_string()).collect());
+        }
+        Ok((columns, rows))
+    }
+
+    /// The `read_geojson` method reads the features of a GeoJSON feature collection from
+    /// `reader` into a table, as described in the [`Dataset`] docs.
+    fn read_geojson<R: Read>(reader: R) -> Arrive<(Vec<String>, Vec<Vec<String>>)> {
+        let collection: Collection = serde_json::from_reader(reader)?;
+        let mut columns = Vec::new();
+        let mut index = HashMap::new();
+        for feature in &collection.features {
+            for name in feature
+                .properties
+                .iter()
+                .flat_map(|properties| properties.keys())
+            {
+                index.entry(name.clone()).or_insert_with(|| {
+                    columns.push(name.clone());
+                    columns.len() - 1
+                });
+            }
+        }
+        let rows = collection
+            .features
+            .into_iter()
+            .map(|feature| {
+                let mut row = vec![String::new(); columns.len() + 1];
+                for (name, value) in feature.properties.into_iter().flatten() {
+                    row[index[&name]] = match value {
+                        serde_json::Value::String(text) => text,
+                        serde_json::Value::Null => String::new(),
+                        value => value.to_string(),
+                    };
+                }
+                if let Some(geometry) = feature.geometry {
+                    row[columns.len()] = geometry.to_string();
+                }
+                row
+            })
+            .collect();
+        columns.push(GEOMETRY.to_string());
+        Ok((columns, rows))
+    }
+}
+
+/// The `Collection` struct holds the parts of a GeoJSON feature collection we read.
+#[derive(serde::Deserialize)]
+struct Collection {
+    features: Vec<Feature>,
+}
+
+/// The `Feature` struct holds the parts of a GeoJSON feature we read.
+#[derive(serde::Deserialize)]
+struct Feature {
+    geometry: Option<serde_json::Value>,
+    properties: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// The `Tally` struct counts the bytes read from a file, reporting progress and stopping on
+/// cancellation, as described in the [`Dataset`] docs.
+///
+/// * The `inner` field holds the reader of the file.
+/// * The `read` field holds the number of bytes read so far.
+/// * The `total` field holds the size of the file.
+/// * The `reported` field holds the last fraction reported to the `job`.
+/// * The `job` field holds the [`Job`] reading the file.
+struct Tally<'a, R> {
+    inner: R,
+    read: u64,
+    total: u64,
+    reported: f32,
+    job: &'a Job,
+}
+
+impl<'a, R> Tally<'a, R> {
+    /// The `new` method wraps `inner`, a file of `total` bytes read by `job`.
+    fn new(inner: R, total: u64, job: &'a Job) -> Self {
+        Self {
+            inner,
+            read: 0,
+            total,
+            reported: 0.0,
+            job,
+        }
+    }
+}
+
+impl<R: Read> Read for Tally<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.job.is_cancelled() {
+            return Err(io::Error::other("the job was cancelled"));
+        }
+        let count = self.inner.read(buf)?;
+        self.read += count as u64;
+        if self.total > 0 {
+            let fraction = self.read as f32 / self.total as f32;
+            if fraction - self.reported >= PROGRESS_STEP {
+                self.reported = fraction;
+                self.job.set_progress(fraction);
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// The `Format` enum names the kinds of data file a [`Dataset`] recognizes.
+///
+/// * The `Csv` variant covers comma-separated text, with a `.csv` extension.
+/// * The `GeoJson` variant covers GeoJSON feature collections, with a `.geojson` or `.json`
+///   extension.
+/// * The `Parquet` variant covers Parquet and GeoParquet tables, with a `.parquet` or
+///   `.geoparquet` extension.
+/// * The `GeoPackage` variant covers GeoPackage databases, with a `.gpkg` extension.
+/// * The `Shapefile` variant covers shapefiles, with a `.shp` extension.
+/// * The `Xlsx` variant covers Excel workbooks, with a `.xlsx` extension.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+)]
+pub enum Format {
+    /// The `Csv` variant indicates a CSV file.
+    #[display("CSV")]
+    Csv,
+    /// The `GeoJson` variant indicates a GeoJSON file.
+    #[display("GeoJSON")]
+    GeoJson,
+    /// The `Parquet` variant indicates a Parquet file.
+    #[display("Parquet")]
+    Parquet,
+    /// The `GeoPackage` variant indicates a GeoPackage.
+    #[display("GeoPackage")]
+    GeoPackage,
+    /// The `Shapefile` variant indicates a shapefile.
+    #[display("shapefile")]
+    Shapefile,
+    /// The `Xlsx` variant indicates an Excel workbook.
+    #[display("Excel")]
+    Xlsx,
+    /// The `Plugin` variant indicates a file read by a [`crate::DataProvider`].
+    #[display("plugin")]
+    Plugin,
+}
+
+impl Format {
+    /// The `from_path` method returns the format of the file at `path`, going by its extension,
+    /// or [`None`] if we do not recognize it.
+    pub fn from_path(path: &path::Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "geojson" | "json" => Some(Self::GeoJson),
+            "parquet" | "geoparquet" => Some(Self::Parquet),
+            "gpkg" => Some(Self::GeoPackage),
+            "shp" => Some(Self::Shapefile),
+            "xlsx" => Some(Self::Xlsx),
+            _ => None,
+        }
+    }
+}
+
+/// The `GEOMETRY` constant holds the name of the column holding the geometry of each feature in
+/// a [`Dataset`] read from GeoJSON.
+pub const GEOMETRY: &str = "geometry";
+
+/// The `FEATURE_DIR` constant holds the name of the directory for features written with
+/// [`Dataset::save_geojson`], under the data directory.
+pub const FEATURE_DIR: &str = "features";
+
+/// The `PROGRESS_STEP` constant holds the fraction of a file a [`Dataset`] reads between progress
+/// reports, so a large file sends a hundred reports rather than one for every block.
+pub const PROGRESS_STEP: f32 = 0.01;
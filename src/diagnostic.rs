@@ -0,0 +1,419 @@
+use crate::{
+    fold_choice, Act, Clip, Contrast, Feed, Gamut, Group, LogFormat, Pacing, Power, PresentMode,
+    ScrollMode, Secrets, Service, Snippet, PROFILES, SCHEMES, SECRETS_FILE,
+};
+use config::ValueKind;
+use strum::IntoEnumIterator;
+
+/// The `diagnostic` module provides the [`Diagnostic`] struct, which describes a problem with a
+/// value in the config, and the [`Problem`] and [`Kind`] enums it is built from.
+///
+/// # Checking the config with `Diagnostic`
+///
+/// The [`config`] crate will happily read any TOML we give it, and only complains when we ask for
+/// a value of the wrong type, at which point we used to `unwrap()`.  A typo in the config either
+/// crashed the app or vanished without a trace.  Instead, [`Diagnostic::check`] walks the whole
+/// config against the keys we recognize before anything else reads it, and returns a
+/// `Diagnostic` for each problem, naming the key, what we expected, what we found, and the file
+/// the value came from.  The [`crate::App`] collects them into a report on startup.
+///
+/// The recognized keys are:
+///
+/// * The snake case name of each [`Act`], holding a key binding.
+/// * `open`, a list of files, and `workspace`, a file, as set by [`crate::Cli`].
+/// * `version`, the whole number described in [`crate::Migration`].
+/// * `log_level`, holding text, and `log_format`, one of the [`LogFormat`] names.
+/// * `headless`, a boolean.
+/// * The `[agents]` table, holding `janitor`, `refresher` and `prefetcher` intervals in
+///   seconds, described in [`crate::Schedule`].
+/// * The `[data]` table, holding a `dirs` list of folders, described in [`crate::Folders`].
+/// * The `[api]` table, holding a `key`, described in [`crate::Settings`].
+/// * The `[logging]` table, holding a `filters` table of module paths and levels, described in
+///   [`crate::Logging`].
+/// * The `[map]` table, described in [`crate::Basemap`].
+/// * The `[scroll]` table, described in [`crate::Scroll`].
+/// * The `[global]` table, holding a binding for each act, described in [`crate::Hotkeys`].
+/// * The `[groups]` table, holding a `disabled` list of [`Group`] names.
+/// * The `[network]` table, holding `connect`, `read`, `request` and `download` limits in
+///   seconds, described in [`crate::Timeouts`].
+/// * The `[otlp]` table, holding an `endpoint` and a `service_name`, described in
+///   [`crate::Otlp`].
+/// * The `[tasks]` table, holding `interactive` and `bulk` limits, described in
+///   [`crate::Concurrency`].
+/// * The `[telemetry]` table, holding `enabled` and an `endpoint`, described in
+///   [`crate::Telemetry`].
+/// * The `[timing]` table, holding `double_press`, `hold` and `chord` in milliseconds, described
+///   in [`crate::Cmd`].
+/// * The `[profile]` table, holding a table for each [`crate::Profile`], which may contain any of
+///   the keys above.
+///
+/// Fields:
+///
+/// * The `key` field holds the path to the value, like `scroll.mode`.
+/// * The `problem` field holds the [`Problem`] with the value.
+/// * The `origin` field holds the file the value came from, if the [`config`] crate knows.
+///   Values from environment variables and the command line have no file.
+#[derive(Debug, Clone, PartialEq, derive_new::new, derive_getters::Getters)]
+pub struct Diagnostic {
+    key: String,
+    problem: Problem,
+    origin: Option<String>,
+}
+
+impl Diagnostic {
+    /// The `check` method compares every value in `config` against the recognized keys, and
+    /// returns a `Diagnostic` for each value that is unknown, of the wrong type, or not one of
+    /// the allowed choices.  An empty vector means the config is clean.
+    #[tracing::instrument(skip_all)]
+    pub fn check(config: &config::Config) -> Vec<Self> {
+        let mut diagnostics = Vec::new();
+        if let ValueKind::Table(table) = &config.cache.kind {
+            for (key, value) in table {
+                Self::check_value(key, value, &mut diagnostics);
+            }
+        }
+        diagnostics.sort_by(|a, b| a.key.cmp(&b.key));
+        diagnostics
+    }
+
+    /// The `check_value` method checks a single value at `key` against [`Kind::expected`], and
+    /// descends into tables.
+    fn check_value(key: &str, value: &config::Value, diagnostics: &mut Vec<Self>) {
+        let origin = value.origin().map(|origin| origin.to_string());
+        let Some(kind) = Kind::expected(key) else {
+            diagnostics.push(Self::new(key.to_string(), Problem::Unknown, origin));
+            return;
+        };
+        if !kind.matches(&value.kind) {
+            let problem = Problem::Mismatch {
+                expected: kind,
+                found: Kind::describe(&value.kind),
+            };
+            diagnostics.push(Self::new(key.to_string(), problem, origin));
+            return;
+        }
+        if Secrets::is_secret(key) && origin.is_some() {
+            diagnostics.push(Self::new(key.to_string(), Problem::Secret, origin.clone()));
+        }
+        match &value.kind {
+            ValueKind::Table(table) => {
+                for (child, value) in table {
+                    Self::check_value(&format!("{key}.{child}"), value, diagnostics);
+                }
+            }
+            ValueKind::Array(values) => {
+                for value in values {
+                    Self::check_choice(key, value, diagnostics);
+                }
+            }
+            _ => Self::check_choice(key, value, diagnostics),
+        }
+    }
+
+    /// The `check_choice` method checks that `value` is one of [`Kind::choices`] for `key`, if
+    /// the key has a fixed set of choices.  The config takes the choices whatever their case and
+    /// with or without underscores, as in `json` or `low_power`, so the check compares them with
+    /// [`fold_choice`], as [`crate::choice`] does.
+    fn check_choice(key: &str, value: &config::Value, diagnostics: &mut Vec<Self>) {
+        let choices = Kind::choices(key);
+        if choices.is_empty() {
+            return;
+        }
+        let text = value.to_string();
+        if !choices
+            .iter()
+            .any(|choice| fold_choice(choice) == fold_choice(&text))
+        {
+            let origin = value.origin().map(|origin| origin.to_string());
+            let problem = Problem::Choice {
+                value: text,
+                choices,
+            };
+            diagnostics.push(Self::new(key.to_string(), problem, origin));
+        }
+    }
+}
+
+/// Writes the diagnostic as a single line for the startup report, as in
+/// `config.toml: scroll.sensitivity should be a number, found text "fast"`.
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(origin) = &self.origin {
+            write!(f, "{origin}: ")?;
+        }
+        match &self.problem {
+            Problem::Unreadable(message) => write!(f, "could not read the file: {message}"),
+            Problem::Syntax(snippet) => write!(f, "could not read the file:\n{snippet}"),
+            Problem::Unknown => write!(f, "{} is not a recognized key", self.key),
+            Problem::Secret => write!(
+                f,
+                "{} is a secret, move it to {SECRETS_FILE} so it stays out of shared configs",
+                self.key
+            ),
+            Problem::Mismatch { expected, found } => {
+                write!(f, "{} should be {expected}, found {found}", self.key)
+            }
+            Problem::Choice { value, choices } => write!(
+                f,
+                "{} cannot be \"{value}\", choose one of {}",
+                self.key,
+                choices.join(", ")
+            ),
+        }
+    }
+}
+
+/// The `Problem` enum describes what is wrong with a value in the config.
+///
+/// * The `Unreadable` variant holds the error from a config file that would not parse at all.
+/// * The `Syntax` variant holds a [`Snippet`] pointing at the mistake in a file that would not
+///   parse, when the parser tells us where it is.  We prefer it to `Unreadable` where we can.
+/// * The `Unknown` variant indicates a key we do not recognize, usually a typo.
+/// * The `Secret` variant indicates a secret in a config file, which belongs in the
+///   [`crate::Secrets`] file instead.
+/// * The `Mismatch` variant holds the [`Kind`] we expected and a description of what we found.
+/// * The `Choice` variant holds a value that is not one of the allowed choices, and the choices.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// The `Unreadable` variant holds the parse error for the file.
+    Unreadable(String),
+    /// The `Syntax` variant holds the place in the file that would not parse.
+    Syntax(Snippet),
+    /// The `Unknown` variant indicates an unrecognized key.
+    Unknown,
+    /// The `Secret` variant indicates a secret written in a config file.
+    Secret,
+    /// The `Mismatch` variant indicates a value of the wrong type.
+    Mismatch {
+        /// The kind of value the key holds.
+        expected: Kind,
+        /// A description of the value found.
+        found: String,
+    },
+    /// The `Choice` variant indicates a value outside the allowed choices.
+    Choice {
+        /// The value found.
+        value: String,
+        /// The allowed values.
+        choices: Vec<String>,
+    },
+}
+
+/// The `Kind` enum describes the type of value a config key holds.
+///
+/// * The `Text` variant holds text.  Numbers and booleans pass too, because `repeat_last = 1`
+///   is a perfectly good binding, and values from environment variables that look like numbers
+///   arrive as numbers.
+/// * The `Flag` variant holds a boolean.
+/// * The `Whole` variant holds a whole number.
+/// * The `Number` variant holds any number.
+/// * The `List` variant holds a list.
+/// * The `Table` variant holds a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// The `Text` variant expects text.
+    Text,
+    /// The `Flag` variant expects `true` or `false`.
+    Flag,
+    /// The `Whole` variant expects a whole number.
+    Whole,
+    /// The `Number` variant expects any number.
+    Number,
+    /// The `List` variant expects a list.
+    List,
+    /// The `Table` variant expects a table.
+    Table,
+}
+
+impl Kind {
+    /// The `expected` method returns the kind of value held by `key`, a dotted path like
+    /// `scroll.mode`, or [`None`] if we do not recognize the key.
+    pub fn expected(key: &str) -> Option<Self> {
+        if key == PROFILES {
+            return Some(Self::Table);
+        }
+        if let Some(profile) = key.strip_prefix(&format!("{PROFILES}.")) {
+            return match profile.split_once('.') {
+                None => Some(Self::Table),
+                Some((_, key)) => Self::expected(key),
+            };
+        }
+        let is_act = |name: &str| Act::iter().any(|act| act.snake() == name);
+        match key.split_once('.') {
+            None => match key {
+                "agents" | "api" | "catalog" | "choropleth" | "data" | "effects" | "geocoding"
+                | "graphics" | "imagery" | "logging" | "lookup" | "map" | "network" | "otlp"
+                | "recording" | "scroll" | "global" | "groups" | "symbols" | "sort" | "summary"
+                | "tasks" | "telemetry" | "theme" | "timing" => Some(Self::Table),
+                "open" => Some(Self::List),
+                "workspace" | "log_level" | "log_format" => Some(Self::Text),
+                "version" => Some(Self::Whole),
+                "headless" => Some(Self::Flag),
+                key if is_act(key) => Some(Self::Text),
+                _ => None,
+            },
+            Some(("scroll", field)) => match field {
+                "mode" | "ctrl" | "shift" | "alt" => Some(Self::Text),
+                "invert" => Some(Self::Flag),
+                "sensitivity" => Some(Self::Number),
+                _ => None,
+            },
+            Some(("agents", "janitor" | "refresher" | "prefetcher" | "watcher")) => {
+                Some(Self::Whole)
+            }
+            Some(("catalog", "builtin")) => Some(Self::Flag),
+            Some(("catalog", "layers")) => Some(Self::Table),
+            Some(("catalog", field)) if field.starts_with("layers.") => {
+                // Layer names may hold dots, so the field is whatever follows the last one.
+                match field.rsplit_once('.').map(|(_, field)| field) {
+                    Some("group" | "feed" | "url" | "about") if field.matches('.').count() > 1 => {
+                        Some(Self::Text)
+                    }
+                    _ => Some(Self::Table),
+                }
+            }
+            Some(("choropleth", "column" | "scheme")) => Some(Self::Text),
+            Some(("choropleth", "classes")) => Some(Self::Whole),
+            Some(("data", "dirs")) => Some(Self::List),
+            Some(("effects", "files")) => Some(Self::List),
+            Some(("effects", "animate")) => Some(Self::Flag),
+            Some(("geocoding", "service" | "url")) => Some(Self::Text),
+            Some(("geocoding", "columns")) => Some(Self::List),
+            Some(("geocoding", "delay")) => Some(Self::Whole),
+            Some(("geocoding", "score")) => Some(Self::Number),
+            Some(("graphics", "present_mode")) => Some(Self::Text),
+            Some(("graphics", "samples" | "fps")) => Some(Self::Whole),
+            Some(("graphics", "fxaa" | "software")) => Some(Self::Flag),
+            Some(("graphics", "power" | "adapter" | "pacing" | "gamut")) => Some(Self::Text),
+            Some(("api", "key")) => Some(Self::Text),
+            Some(("imagery", "catalog" | "collection")) => Some(Self::Text),
+            Some(("lookup", "parcels" | "addresses" | "taxlot" | "address")) => Some(Self::Text),
+            Some(("lookup", "fields")) => Some(Self::List),
+            Some(("lookup", "links")) => Some(Self::Table),
+            Some(("lookup", field)) if field.starts_with("links.") => Some(Self::Text),
+            Some(("imagery", "days" | "limit")) => Some(Self::Whole),
+            Some(("imagery", "cloud_cover")) => Some(Self::Number),
+            Some(("imagery", "assets")) => Some(Self::List),
+            Some(("map", field)) => match field {
+                "center" => Some(Self::List),
+                "zoom" | "cache_size" => Some(Self::Whole),
+                "tile_url" | "attribution" => Some(Self::Text),
+                _ => None,
+            },
+            Some(("logging", "filters")) => Some(Self::Table),
+            Some(("logging", field)) if field.starts_with("filters.") => Some(Self::Text),
+            Some(("network", "connect" | "read" | "request" | "download")) => Some(Self::Whole),
+            Some(("otlp", "endpoint" | "service_name")) => Some(Self::Text),
+            Some(("recording", "format")) => Some(Self::Text),
+            Some(("recording", "fps" | "seconds")) => Some(Self::Whole),
+            Some(("symbols", "dir" | "column" | "fallback")) => Some(Self::Text),
+            Some(("symbols", "size")) => Some(Self::Whole),
+            Some(("symbols", "icons")) => Some(Self::Table),
+            Some(("symbols", field)) if field.starts_with("icons.") => Some(Self::Text),
+            Some(("sort", "column")) => Some(Self::Text),
+            Some(("sort", "descending")) => Some(Self::Flag),
+            Some(("summary", "place" | "column")) => Some(Self::Text),
+            Some(("tasks", "interactive" | "bulk")) => Some(Self::Whole),
+            Some(("telemetry", "enabled")) => Some(Self::Flag),
+            Some(("telemetry", "endpoint")) => Some(Self::Text),
+            Some(("theme", "contrast")) => Some(Self::Text),
+            Some(("theme", "large")) => Some(Self::Flag),
+            Some(("global", act)) if is_act(act) => Some(Self::Text),
+            Some(("groups", "disabled")) => Some(Self::List),
+            Some(("timing", "double_press" | "hold" | "chord")) => Some(Self::Whole),
+            _ => None,
+        }
+    }
+
+    /// The `choices` method returns the allowed values for `key`, or an empty vector if any
+    /// value of the right kind will do.  For lists, the choices apply to each member.
+    pub fn choices(key: &str) -> Vec<String> {
+        if let Some(profile) = key.strip_prefix(&format!("{PROFILES}.")) {
+            return match profile.split_once('.') {
+                None => Vec::new(),
+                Some((_, key)) => Self::choices(key),
+            };
+        }
+        match key {
+            "scroll.mode" | "scroll.ctrl" | "scroll.shift" | "scroll.alt" => {
+                ScrollMode::iter().map(|mode| mode.to_string()).collect()
+            }
+            "log_format" => LogFormat::iter().map(|format| format.to_string()).collect(),
+            "recording.format" => Clip::iter().map(|clip| clip.to_string()).collect(),
+            "geocoding.service" => Service::iter().map(|service| service.to_string()).collect(),
+            key if key.starts_with("catalog.layers.") && key.ends_with(".feed") => {
+                Feed::iter().map(|feed| feed.to_string()).collect()
+            }
+            "graphics.present_mode" => PresentMode::iter().map(|mode| mode.to_string()).collect(),
+            "graphics.power" => Power::iter().map(|power| power.to_string()).collect(),
+            "graphics.pacing" => Pacing::iter().map(|pacing| pacing.to_string()).collect(),
+            "graphics.gamut" => Gamut::iter().map(|gamut| gamut.to_string()).collect(),
+            "choropleth.scheme" => SCHEMES
+                .iter()
+                .map(|scheme| scheme.name().to_string())
+                .collect(),
+            "theme.contrast" => Contrast::iter()
+                .map(|contrast| contrast.to_string())
+                .collect(),
+            "groups.disabled" => Group::iter()
+                .map(|group| group.to_string().to_lowercase())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The `matches` method returns `true` if a value of kind `value` is acceptable as `self`.
+    pub fn matches(&self, value: &ValueKind) -> bool {
+        match self {
+            Self::Text => !matches!(
+                value,
+                ValueKind::Nil | ValueKind::Table(_) | ValueKind::Array(_)
+            ),
+            Self::Flag => matches!(value, ValueKind::Boolean(_)),
+            Self::Whole => matches!(
+                value,
+                ValueKind::I64(_) | ValueKind::I128(_) | ValueKind::U64(_) | ValueKind::U128(_)
+            ),
+            Self::Number => matches!(
+                value,
+                ValueKind::I64(_)
+                    | ValueKind::I128(_)
+                    | ValueKind::U64(_)
+                    | ValueKind::U128(_)
+                    | ValueKind::Float(_)
+            ),
+            Self::List => matches!(value, ValueKind::Array(_)),
+            Self::Table => matches!(value, ValueKind::Table(_)),
+        }
+    }
+
+    /// The `describe` method describes a value for the user, as in `text "fast"` or `a table`.
+    pub fn describe(value: &ValueKind) -> String {
+        match value {
+            ValueKind::Nil => "nothing".to_string(),
+            ValueKind::Boolean(b) => format!("{b}"),
+            ValueKind::I64(_) | ValueKind::I128(_) | ValueKind::U64(_) | ValueKind::U128(_) => {
+                format!("the number {value}")
+            }
+            ValueKind::Float(_) => format!("the number {value}"),
+            ValueKind::String(s) => format!("text \"{s}\""),
+            ValueKind::Table(_) => "a table".to_string(),
+            ValueKind::Array(_) => "a list".to_string(),
+        }
+    }
+}
+
+/// Describes the kind the way we would say it in a sentence, as in "should be a number".
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Text => "text",
+            Self::Flag => "true or false",
+            Self::Whole => "a whole number",
+            Self::Number => "a number",
+            Self::List => "a list",
+            Self::Table => "a table",
+        };
+        write!(f, "{text}")
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{Arrive, Context, Excuse, Fetch, Job, Network, PROGRESS_STEP};
+use sha2::Digest;
+use std::path;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::AsyncWriteExt;
+
+/// The `download` module provides the [`Download`] struct, which fetches a large file to disk in
+/// chunks, picking up where it left off after a failure.
+///
+/// # Fetching big files with `Download`
+///
+/// Census TIGER boundary files come as zip archives of tens or hundreds of megabytes, and aerial
+/// imagery runs larger.  Reading the whole response into memory before writing it out, the way
+/// the API requests do, holds all of it in memory at once, tells the user nothing until it is
+/// done, and starts over from zero when the connection drops at 95%.  A `Download` instead:
+///
+/// * Writes each chunk of the response to disk as it arrives, to a partial file next to the
+///   target, and reports the bytes received as progress with [`Job::set_progress`] each time
+///   another [`PROGRESS_STEP`] of the file has gone by.  The partial file takes its name from a
+///   digest of the address, with [`PARTIAL_EXTENSION`] on the end, so two addresses that end in
+///   the same file name do not write into each other.
+/// * Resumes after a failure.  When the connection drops, the partial file stays, and the next
+///   attempt asks the server for the rest with a `Range` header.  The server may have changed
+///   the file since, and the rest of a new file on the end of the start of the old one is a
+///   damaged file, so we keep the `ETag` of the response, or failing that its `Last-Modified`
+///   date, beside the partial file with [`VALIDATOR_EXTENSION`] on the end, and send it back in
+///   an `If-Range` header.  A server holding the same file sends the rest, and one holding a new
+///   file sends the whole of it, which gets a fresh start, as does a partial file with no
+///   validator to check it by.  We wait between attempts as
+///   [`crate::Blame::retry_after`] says, and give up as it says, but an attempt that made
+///   progress earns a fresh count, so a long download over a poor connection can keep going.
+///   The partial file also survives a cancelled job or a closed app, and the next download of
+///   the same address into the same folder picks it up.
+/// * Checks the finished file against a SHA-256 checksum, if [`Download::with_checksum`] gave
+///   one, and throws the file away if it does not match, since resuming a corrupt file only adds
+///   to the damage.
+/// * Moves the finished file into place, so a file at the target is always a whole one.  A
+///   download never writes over a file already at the target, and fails with
+///   [`Excuse::DownloadExists`] before it starts instead.
+///
+/// Each attempt goes through [`Network::send`] as a [`Fetch::Downloads`], so it shows up in the
+/// network log, and [`Download::fetch`] opens a span named for [`crate::DOWNLOAD_SPAN`], so
+/// [`crate::Latency`] times the whole download.  Each attempt must also finish within the
+/// `download` limit of [`crate::Timeouts`].  A server that goes quiet fails the attempt as a
+/// [`crate::Blame::Timeout`], which counts as transient, so the next attempt resumes.  The
+/// [`crate::App`] runs downloads as bulk jobs of the [`crate::Tasks`] registry with
+/// [`crate::App::download`], so they give way to the work the user is waiting on, and
+/// [`crate::Act::DownloadFromClipboard`] starts one from an address on the clipboard, like the
+/// `href` of a scene from [`crate::Stac`].
+///
+/// A web page has no disk to write to, so in the browser [`Download::fetch`] fails with
+/// [`Excuse::Unavailable`] (see [`crate::run_web`]).
+///
+/// * The `url` field holds the address of the file.
+/// * The `target` field holds the path to write the finished file to.
+/// * The `checksum` field holds the expected SHA-256 digest of the file, in lowercase hex, if
+///   known.
+#[derive(Debug, Clone, derive_getters::Getters)]
+pub struct Download {
+    url: String,
+    target: path::PathBuf,
+    checksum: Option<String>,
+}
+
+impl Download {
+    /// The `new` method creates a download of the file at `url` to `target`, with no checksum.
+    pub fn new(url: impl Into<String>, target: impl Into<path::PathBuf>) -> Self {
+        Self {
+            url: url.into(),
+            target: target.into(),
+            checksum: None,
+        }
+    }
+
+    /// The `with_checksum` method sets the expected SHA-256 digest of the file, as hex.
+    pub fn with_checksum(mut self, sha256: impl Into<String>) -> Self {
+        self.checksum = Some(sha256.into().to_lowercase());
+        self
+    }
+
+    /// The `partial` method returns the path of the file that holds the download until it is
+    /// finished, beside the target, named for the SHA-256 digest of the address with
+    /// [`PARTIAL_EXTENSION`] on the end.
+    pub fn partial(&self) -> path::PathBuf {
+        let digest = sha2::Sha256::digest(self.url.as_bytes());
+        let name = format!("{}.{PARTIAL_EXTENSION}", hex::encode(&digest[..16]));
+        self.target.with_file_name(name)
+    }
+
+    /// The `validator` method returns the path of the file that holds the `ETag` or
+    /// `Last-Modified` date of the partial file, the partial file with [`VALIDATOR_EXTENSION`]
+    /// on the end.
+    pub fn validator(&self) -> path::PathBuf {
+        self.partial().with_extension(VALIDATOR_EXTENSION)
+    }
+
+    /// The `fetch` method downloads the file, reporting to `job`, as described in the
+    /// [`Download`] docs, and returns the path of the finished file.
+    ///
+    /// Will [`crate::Blame::Http`] if the server cannot be reached or answers with an error, and
+    /// [`crate::Blame::Timeout`] if it stops answering, once retries are spent.  Will
+    /// [`crate::Blame::Io`] if the file cannot be written, [`Excuse::ChecksumMismatch`] if the
+    /// file arrives damaged, and [`Excuse::DownloadExists`] if a file is already at the target.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tracing::instrument(name = "download", skip_all)]
+    pub async fn fetch(&self, job: &Job) -> Arrive<path::PathBuf> {
+        if tokio::fs::try_exists(&self.target).await.unwrap_or(true) {
+            tracing::warn!(
+                "{} already exists, and was left alone.",
+                self.target.display()
+            );
+            return Err(Excuse::DownloadExists.into());
+        }
+        let partial = self.partial();
+        if let Some(parent) = self.target.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let mut attempt = 0;
+        loop {
+            let before = Self::received(&partial).await;
+            let result = Network::global()
+                .within(Fetch::Downloads, &self.url, self.resume(&partial, job))
+                .await;
+            let Err(blame) = result else {
+                break;
+            };
+            if Self::received(&partial).await > before {
+                attempt = 0;
+            }
+            attempt += 1;
+            match blame.retry_after(attempt) {
+                Some(delay) => {
+                    tracing::info!("Download interrupted, resuming in {delay:?}: {blame}");
+                    crate::sleep(delay).await;
+                }
+                None => return Err(blame),
+            }
+        }
+        self.verify(&partial).await?;
+        tokio::fs::rename(&partial, &self.target)
+            .await
+            .with_context(|| format!("moving {} into place", self.target.display()))?;
+        // The partial file is gone, and its validator with it.
+        tokio::fs::remove_file(self.validator()).await.ok();
+        tracing::info!("Downloaded {}.", self.target.display());
+        Ok(self.target.clone())
+    }
+
+    /// The `fetch` method fails with [`Excuse::Unavailable`], since the browser has no disk to
+    /// download the file to.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn fetch(&self, _job: &Job) -> Arrive<path::PathBuf> {
+        tracing::warn!(
+            "Cannot download {} in the web browser.",
+            Network::global().redact(&self.url)
+        );
+        Err(Excuse::Unavailable.into())
+    }
+
+    /// The `resume` method makes one attempt at the rest of the file, appending to `partial`,
+    /// if the validator beside it says the server still holds the same file, as described in
+    /// the [`Download`] docs.  A server that cannot satisfy the range because `partial` already
+    /// holds the whole file counts as success, and [`Download::verify`] has the last word.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn resume(&self, partial: &path::Path, job: &Job) -> Arrive<()> {
+        let validator = self.validator();
+        let mut offset = Self::received(partial).await;
+        let mut request = Network::global().client().get(&self.url);
+        if offset > 0 {
+            match tokio::fs::read_to_string(&validator).await {
+                Ok(tag) if !tag.trim().is_empty() => {
+                    request = request
+                        .header(reqwest::header::RANGE, format!("bytes={offset}-"))
+                        .header(reqwest::header::IF_RANGE, tag.trim());
+                }
+                _ => {
+                    tracing::info!("Starting over, with nothing to check the partial file by.");
+                    offset = 0;
+                }
+            }
+        }
+        let network = Network::global();
+        let response = network.send(request, Fetch::Downloads).await?;
+        let status = response.status();
+        if offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            tracing::trace!("Nothing left to download after {offset} bytes.");
+            return Ok(());
+        }
+        let mut response = response.error_for_status()?;
+        let (mut file, mut received) = if status == reqwest::StatusCode::PARTIAL_CONTENT {
+            tracing::info!("Resuming download after {offset} bytes.");
+            let file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(partial)
+                .await?;
+            (file, offset)
+        } else {
+            if offset > 0 {
+                tracing::info!("The file changed on the server, so the download starts over.");
+            }
+            let file = tokio::fs::File::create(partial).await?;
+            match Self::tag(response.headers()) {
+                Some(tag) => tokio::fs::write(&validator, tag)
+                    .await
+                    .with_context(|| format!("writing {}", validator.display()))?,
+                None => {
+                    tokio::fs::remove_file(&validator).await.ok();
+                }
+            }
+            (file, 0)
+        };
+        let total = response
+            .content_length()
+            .map(|length| received + length)
+            .filter(|total| *total > 0);
+        let mut reported = 0.0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| network.blame(e, &self.url))?
+        {
+            file.write_all(&chunk)
+                .await
+                .with_context(|| format!("writing {}", partial.display()))?;
+            received += chunk.len() as u64;
+            if let Some(total) = total {
+                let fraction = received as f32 / total as f32;
+                if fraction - reported >= PROGRESS_STEP {
+                    reported = fraction;
+                    job.set_progress(fraction);
+                }
+            }
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// The `tag` method returns the value of `headers` to send back in `If-Range`: the `ETag`,
+    /// unless it is weak, which `If-Range` does not take, or else the `Last-Modified` date, or
+    /// [`None`] if the server sent neither.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn tag(headers: &reqwest::header::HeaderMap) -> Option<String> {
+        let header = |name: reqwest::header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        header(reqwest::header::ETAG)
+            .filter(|tag| !tag.starts_with("W/"))
+            .or_else(|| header(reqwest::header::LAST_MODIFIED))
+    }
+
+    /// The `verify` method checks `partial` against the checksum, if there is one, removing the
+    /// file if it does not match.
+    ///
+    /// Will [`Excuse::ChecksumMismatch`] if the digests differ, and [`crate::Blame::Io`] if the
+    /// file cannot be read.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn verify(&self, partial: &path::Path) -> Arrive<()> {
+        let Some(expected) = &self.checksum else {
+            return Ok(());
+        };
+        let path = partial.to_path_buf();
+        let actual = crate::unblock(move || Self::sha256(&path)).await??;
+        if actual != *expected {
+            tracing::warn!("Expected SHA-256 {expected}, received {actual}.");
+            tokio::fs::remove_file(partial).await?;
+            return Err(Excuse::ChecksumMismatch.into());
+        }
+        tracing::trace!("Checksum verified.");
+        Ok(())
+    }
+
+    /// The `sha256` method returns the SHA-256 digest of the file at `path`, in lowercase hex.
+    /// It blocks until the whole file is read.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be read.
+    pub fn sha256(path: &path::Path) -> Arrive<String> {
+        let mut file =
+            std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        let mut hasher = sha2::Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// The `received` method returns the size of the partial file at `partial`, or zero if there
+    /// is none.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn received(partial: &path::Path) -> u64 {
+        tokio::fs::metadata(partial)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or_default()
+    }
+}
+
+/// The `PARTIAL_EXTENSION` constant holds the extension added to the name of a file while a
+/// [`Download`] is still writing it.
+pub const PARTIAL_EXTENSION: &str = "part";
+
+/// The `VALIDATOR_EXTENSION` constant holds the extension of the file beside the partial file
+/// of a [`Download`] that holds the `ETag` or `Last-Modified` date it was fetched with.
+pub const VALIDATOR_EXTENSION: &str = "validator";
+
+/// The `DOWNLOAD_DIR` constant holds the name of the directory under [`crate::Home::data`] where
+/// [`crate::Act::DownloadFromClipboard`] puts the files it fetches.
+pub const DOWNLOAD_DIR: &str = "downloads";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partials_follow_the_address() {
+        let first = Download::new(
+            "https://a.example/tiger/roads.zip",
+            "/srv/downloads/roads.zip",
+        );
+        let second = Download::new(
+            "https://b.example/osm/roads.zip",
+            "/srv/downloads/roads.zip",
+        );
+        assert_ne!(first.partial(), second.partial());
+        assert_eq!(first.partial().parent(), first.target().parent());
+        assert_eq!(
+            first.validator().extension().and_then(|ext| ext.to_str()),
+            Some(VALIDATOR_EXTENSION)
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn weak_tags_give_way_to_dates() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let date = "Wed, 21 Oct 2026 07:28:00 GMT";
+        headers.insert(reqwest::header::LAST_MODIFIED, date.parse().expect("date"));
+        headers.insert(
+            reqwest::header::ETAG,
+            "W/\"abc\"".parse().expect("weak tag"),
+        );
+        assert_eq!(Download::tag(&headers).as_deref(), Some(date));
+        headers.insert(reqwest::header::ETAG, "\"abc\"".parse().expect("tag"));
+        assert_eq!(Download::tag(&headers).as_deref(), Some("\"abc\""));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{default_config, Arrive, Context, Diagnostic, Excuse, Layers, Problem, Snippet};
+use std::ops::Range;
+use std::path;
+
+/// The `editor` module provides the [`Editor`] struct, which edits the text of the config file,
+/// and the [`Span`] and [`Syntax`] types used to highlight it.
+///
+/// # Editing the raw config with `Editor`
+///
+/// Some settings will never get a proper control in a settings dialog, and some users would
+/// rather type than click anyway.  The `Editor` holds the text of the config file as the user
+/// edits it, and gives a panel everything it needs to show it:
+///
+/// * [`Editor::highlight`] splits the text into [`Span`] types, each marked with the [`Syntax`]
+///   of a TOML token, for coloring.
+/// * [`Editor::validate`] checks the text the same way we check the config on startup, with
+///   [`Diagnostic::check`], so the user can see problems before saving.
+/// * [`Editor::save`] refuses text that does not parse, and writes the rest to the file.  The
+///   [`crate::App`] then reloads the config with [`crate::App::reload_config`], so the change
+///   takes effect without a restart.
+///
+/// The user edits the text in whatever editor they like and copies it, and
+/// [`crate::Act::PasteConfig`] fills the editor with the text on the clipboard and saves it,
+/// through [`crate::App::save_editor`], with any problems shown in a notice over the map.
+///
+/// The editor only handles TOML, since that is what it highlights.  A YAML or JSON config
+/// belongs to the deployment tooling that wrote it.
+///
+/// * The `path` field holds the path to the config file.
+/// * The `text` field holds the text as edited.
+/// * The `saved` field holds the text as last read or written, to tell if there are changes.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Editor {
+    path: path::PathBuf,
+    text: String,
+    saved: String,
+}
+
+impl Editor {
+    /// The `open` method reads the config file at `path` for editing.  If there is no file yet,
+    /// the editor starts from [`default_config`].
+    ///
+    /// Will [`Excuse::ConfigFormat`] if `path` is not a TOML file, and [`crate::Blame::Io`] if
+    /// the file exists but cannot be read.
+    #[tracing::instrument]
+    pub fn open(path: &path::Path) -> Arrive<Self> {
+        if Layers::format(path).is_some_and(|format| format != config::FileFormat::Toml) {
+            tracing::warn!("Cannot edit {}, only TOML configs.", path.display());
+            return Err(Excuse::ConfigFormat.into());
+        }
+        let text = if path.exists() {
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?
+        } else {
+            default_config()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            saved: text.clone(),
+            text,
+        })
+    }
+
+    /// The `edit` method replaces the text being edited with `text`.
+    pub fn edit(&mut self, text: String) {
+        self.text = text;
+    }
+
+    /// The `revert` method throws away the changes since the last save.
+    pub fn revert(&mut self) {
+        self.text = self.saved.clone();
+    }
+
+    /// The `is_dirty` method returns `true` if the text has changed since the last save.
+    pub fn is_dirty(&self) -> bool {
+        self.text != self.saved
+    }
+
+    /// The `highlight` method splits the text into [`Span`] types for coloring, using
+    /// [`highlight`].
+    pub fn highlight(&self) -> Vec<Span> {
+        highlight(&self.text)
+    }
+
+    /// The `validate` method returns a [`Diagnostic`] for each problem with the text.  Text that
+    /// does not parse produces a single [`Problem::Syntax`] pointing at the mistake, or a
+    /// [`Problem::Unreadable`] if the parser cannot say where it is.  Otherwise we check the values
+    /// with [`Diagnostic::check`].  An empty vector means the text is clean.
+    #[tracing::instrument(skip_all)]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let file = config::File::from_str(&self.text, config::FileFormat::Toml);
+        match config::Config::builder().add_source(file).build() {
+            Ok(config) => Diagnostic::check(&config),
+            Err(e) => {
+                let origin = self.path.display().to_string();
+                let problem = match self.text.parse::<toml::Table>() {
+                    Err(error) => match Snippet::from_toml(&self.text, &error) {
+                        Some(snippet) => Problem::Syntax(snippet.with_origin(origin.clone())),
+                        None => Problem::Unreadable(e.to_string()),
+                    },
+                    Ok(_) => Problem::Unreadable(e.to_string()),
+                };
+                vec![Diagnostic::new(String::new(), problem, Some(origin))]
+            }
+        }
+    }
+
+    /// The `save` method writes the text to the file, creating any missing parent directories,
+    /// and returns the problems found by [`Editor::validate`].  Problems like an unknown key do
+    /// not stop the save, since the user may know better, but text that does not parse does.
+    ///
+    /// Will [`Excuse::ConfigInvalid`] if the text does not parse, and [`crate::Blame::Io`] if
+    /// writing fails.
+    #[tracing::instrument(skip_all)]
+    pub fn save(&mut self) -> Arrive<Vec<Diagnostic>> {
+        let diagnostics = self.validate();
+        if diagnostics.iter().any(|diagnostic| {
+            matches!(
+                diagnostic.problem(),
+                Problem::Unreadable(_) | Problem::Syntax(_)
+            )
+        }) {
+            tracing::warn!(
+                "Not saving {}, the config does not parse.",
+                self.path.display()
+            );
+            return Err(Excuse::ConfigInvalid.into());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, &self.text)
+            .with_context(|| format!("saving {}", self.path.display()))?;
+        self.saved = self.text.clone();
+        tracing::info!("Config saved to {}.", self.path.display());
+        Ok(diagnostics)
+    }
+}
+
+/// The `Span` struct marks a range of the config text with its [`Syntax`].
+///
+/// * The `syntax` field holds the kind of token.
+/// * The `range` field holds the byte range of the token in the text.
+#[derive(Debug, Clone, PartialEq, Eq, derive_new::new, derive_getters::Getters)]
+pub struct Span {
+    syntax: Syntax,
+    range: Range<usize>,
+}
+
+/// The `Syntax` enum describes the kinds of TOML token that [`highlight`] tells apart.
+///
+/// * The `Comment` variant marks a comment, from `#` to the end of the line.
+/// * The `Table` variant marks a table header, like `[scroll]`.
+/// * The `Key` variant marks a key, like `mode`.
+/// * The `Text` variant marks a quoted string.
+/// * The `Number` variant marks a number, or a date or time.
+/// * The `Flag` variant marks `true` or `false`.
+/// * The `Punctuation` variant marks `=`, `,` and the brackets of arrays and inline tables.
+/// * The `Plain` variant marks anything else, usually a mistake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum_macros::EnumIter, derive_more::Display)]
+pub enum Syntax {
+    /// The `Comment` variant marks a comment.
+    Comment,
+    /// The `Table` variant marks a table header.
+    Table,
+    /// The `Key` variant marks a key.
+    Key,
+    /// The `Text` variant marks a string.
+    Text,
+    /// The `Number` variant marks a number, date or time.
+    Number,
+    /// The `Flag` variant marks a boolean.
+    Flag,
+    /// The `Punctuation` variant marks punctuation.
+    Punctuation,
+    /// The `Plain` variant marks anything else.
+    Plain,
+}
+
+/// The `highlight` function splits TOML `text` into [`Span`] types, in order.  Whitespace
+/// belongs to no span.
+///
+/// This is a highlighter rather than a parser, so it is forgiving: it never fails, and text that
+/// would not parse still gets colored as well as we can guess, which is when the user needs it
+/// most.  We track whether the next word is a key or a value, which depends on whether we have
+/// passed an `=` on this line, or are inside an inline table.
+pub fn highlight(text: &str) -> Vec<Span> {
+    let chars = text.char_indices().collect::<Vec<(usize, char)>>();
+    let offset = |i: usize| chars.get(i).map(|(at, _)| *at).unwrap_or(text.len());
+    let mut spans = Vec::new();
+    let mut expect_key = true;
+    let mut nesting = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        match c {
+            '\n' => {
+                if nesting.is_empty() {
+                    expect_key = true;
+                }
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            '#' => {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+                spans.push(Span::new(Syntax::Comment, start..offset(i)));
+            }
+            '[' if expect_key && nesting.is_empty() => {
+                while i < chars.len() && !matches!(chars[i].1, ']' | '\n') {
+                    i += 1;
+                }
+                // Consume both brackets of an array of tables, as in [[layer]].
+                while i < chars.len() && chars[i].1 == ']' {
+                    i += 1;
+                }
+                spans.push(Span::new(Syntax::Table, start..offset(i)));
+            }
+            '"' | '\'' => {
+                let triple = chars.get(i + 1).map(|(_, c)| *c) == Some(c)
+                    && chars.get(i + 2).map(|(_, c)| *c) == Some(c);
+                i += if triple { 3 } else { 1 };
+                while i < chars.len() {
+                    let next = chars[i].1;
+                    if next == '\\' && c == '"' {
+                        i += 2;
+                        continue;
+                    }
+                    if next == '\n' && !triple {
+                        break;
+                    }
+                    if next == c {
+                        if !triple {
+                            i += 1;
+                            break;
+                        }
+                        if chars.get(i + 1).map(|(_, c)| *c) == Some(c)
+                            && chars.get(i + 2).map(|(_, c)| *c) == Some(c)
+                        {
+                            i += 3;
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                let syntax = if expect_key {
+                    Syntax::Key
+                } else {
+                    Syntax::Text
+                };
+                spans.push(Span::new(syntax, start..offset(i)));
+            }
+            '=' => {
+                expect_key = false;
+                i += 1;
+                spans.push(Span::new(Syntax::Punctuation, start..offset(i)));
+            }
+            '[' | '{' => {
+                nesting.push(c);
+                expect_key = c == '{';
+                i += 1;
+                spans.push(Span::new(Syntax::Punctuation, start..offset(i)));
+            }
+            ']' | '}' | ',' => {
+                if c == ',' {
+                    expect_key = nesting.last() == Some(&'{');
+                } else {
+                    nesting.pop();
+                }
+                i += 1;
+                spans.push(Span::new(Syntax::Punctuation, start..offset(i)));
+            }
+            _ => {
+                while i < chars.len()
+                    && !chars[i].1.is_whitespace()
+                    && !matches!(chars[i].1, '=' | ',' | '[' | ']' | '{' | '}' | '#')
+                {
+                    i += 1;
+                }
+                let word = &text[start..offset(i)];
+                let syntax = if expect_key {
+                    Syntax::Key
+                } else if word == "true" || word == "false" {
+                    Syntax::Flag
+                } else if word.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-')
+                    || matches!(word, "inf" | "nan")
+                {
+                    Syntax::Number
+                } else {
+                    Syntax::Plain
+                };
+                spans.push(Span::new(syntax, start..offset(i)));
+            }
+        }
+    }
+    spans
+}
@@ -0,0 +1,322 @@
+use crate::{Arrive, Excuse};
+use std::sync::Arc;
+use web_time as time;
+
+/// The `effect` module provides the [`Effect`] struct, a pass that runs a shader of the user's
+/// own over the finished picture of the map.
+///
+/// # Custom shaders with `Effect`
+///
+/// Some things we want to show do not fit the symbology of a layer: water that flows along the
+/// streams, hillshading that follows the light, contours drawn from the colors of an elevation
+/// tile.  They all come down to the same trick, which is to run a little program over every
+/// pixel of the map after it is drawn.  An `Effect` holds one such program, written in WGSL, the
+/// shading language of [`wgpu`], and runs it as a full-screen pass the same way the
+/// [`crate::Fxaa`] pass does: the map draws into a texture held by the effect, and
+/// [`Effect::apply`] draws the texture onto the next target through the shader.
+///
+/// The user writes only the fragment shader, a function named `fs_main`, and we put
+/// [`EFFECT_PRELUDE`] in front of it.  The prelude declares the vertex shader, the `frame`
+/// texture holding the map with its `frame_sampler`, and a `globals` uniform with the seconds
+/// since the canvas opened in `time` and the size of the frame in pixels in `size`.  A shader
+/// that inverts the colors of the map reads:
+///
+/// ```wgsl
+/// @fragment
+/// fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+///     let color = textureSample(frame, frame_sampler, in.uv);
+///     return vec4<f32>(1.0 - color.rgb, color.a);
+/// }
+/// ```
+///
+/// The `[effects]` table of the config lists the shader files (see [`crate::Effects`]), and the
+/// [`crate::App`] reads them for each map window, where [`crate::Canvas::shade`] builds an
+/// `Effect` for each and chains them in order, ahead of the FXAA pass if it is on.  A shader
+/// that moves with `time` needs a steady stream of frames, which the `animate` key of the table
+/// asks for, as described in [`crate::Lens`].  A shader with a mistake in it fails to build with
+/// [`Excuse::Shader`], and the map draws without it.
+///
+/// * The `name` field holds the name of the effect, from its file, for the log.
+/// * The `pipeline` field holds the render pipeline of the pass.
+/// * The `layout` field holds the layout of the bind group for the frame texture.
+/// * The `sampler` field holds the sampler reading the frame texture.
+/// * The `globals` field holds the uniform buffer of the `globals` in the shader.
+/// * The `texture` field holds the texture the map draws into.
+/// * The `view` field holds the view of `texture` the map draws through.
+/// * The `bind_group` field binds `view`, `sampler` and `globals` for the pass.
+/// * The `device` field holds the device shared by every window.
+/// * The `queue` field holds the queue shared by every window.
+#[derive(Debug)]
+pub struct Effect {
+    name: String,
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    globals: wgpu::Buffer,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+impl Effect {
+    /// The `new` method builds the pass named `name` from the fragment shader in `source`, for a
+    /// surface configured by `config`, with a frame texture of the same size and format.
+    ///
+    /// Will [`Excuse::Shader`] if the shader does not compile, or does not fit the prelude.
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: &wgpu::SurfaceConfiguration,
+        name: &str,
+        source: &str,
+    ) -> Arrive<Self> {
+        // The shader comes from the user, so we catch its mistakes instead of letting wgpu panic.
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(name),
+            source: wgpu::ShaderSource::Wgsl(format!("{EFFECT_PRELUDE}\n{source}").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("effect"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(name),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(name),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        if let Some(e) = crate::settle(device.pop_error_scope()).flatten() {
+            tracing::warn!("The {name} shader did not build: {e}");
+            return Err(Excuse::Shader.into());
+        }
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("effect"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let globals = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("effect globals"),
+            size: GLOBALS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (texture, view, bind_group) = Self::frame(&device, &layout, &sampler, &globals, config);
+        Ok(Self {
+            name: name.to_owned(),
+            pipeline,
+            layout,
+            sampler,
+            globals,
+            texture,
+            view,
+            bind_group,
+            device,
+            queue,
+        })
+    }
+
+    /// The `frame` method creates the frame texture for a surface configured by `config`, with
+    /// its view and the bind group reading it.
+    fn frame(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        globals: &wgpu::Buffer,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> (wgpu::Texture, wgpu::TextureView, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("effect frame"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("effect frame"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: globals.as_entire_binding(),
+                },
+            ],
+        });
+        (texture, view, bind_group)
+    }
+
+    /// The `name` method returns the name of the effect.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The `view` method returns the view of the frame texture, for the map, or the effect
+    /// before this one, to draw into.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// The `resize` method replaces the frame texture with one fit for a surface configured by
+    /// `config`, if the size has changed.
+    pub fn resize(&mut self, config: &wgpu::SurfaceConfiguration) {
+        let size = self.texture.size();
+        if (size.width, size.height) == (config.width, config.height) {
+            return;
+        }
+        let (texture, view, bind_group) = Self::frame(
+            &self.device,
+            &self.layout,
+            &self.sampler,
+            &self.globals,
+            config,
+        );
+        self.texture = texture;
+        self.view = view;
+        self.bind_group = bind_group;
+    }
+
+    /// The `apply` method draws the frame texture onto `target` through the shader, with
+    /// `elapsed` as the `time` of the `globals`.
+    pub fn apply(&self, target: &wgpu::TextureView, elapsed: time::Duration) {
+        let size = self.texture.size();
+        let globals = [
+            elapsed.as_secs_f32(),
+            0.0,
+            size.width as f32,
+            size.height as f32,
+        ];
+        let bytes: Vec<u8> = globals
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        self.queue.write_buffer(&self.globals, 0, &bytes);
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&self.name),
+            });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&self.name),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            // One triangle large enough to cover the screen, see the prelude.
+            pass.draw(0..3, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
+
+/// The `GLOBALS_SIZE` constant holds the size in bytes of the `Globals` struct of
+/// [`EFFECT_PRELUDE`], four floats.
+const GLOBALS_SIZE: u64 = 16;
+
+/// The `EFFECT_PRELUDE` constant holds the WGSL put in front of the shader of every [`Effect`],
+/// as described in the [`Effect`] docs.  The vertex shader is the one from
+/// [`crate::FXAA_SHADER`], a single triangle covering the screen.
+pub const EFFECT_PRELUDE: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct Globals {
+    time: f32,
+    size: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0) var frame: texture_2d<f32>;
+@group(0) @binding(1) var frame_sampler: sampler;
+@group(0) @binding(2) var<uniform> globals: Globals;
+"#;
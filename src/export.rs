@@ -0,0 +1,326 @@
+use crate::{
+    Arrive, Atlas, Cli, Context, Dataset, Excuse, Extent, Figure, Gpu, Home, Job, Map, Plugins,
+    Settings, Snapshot, Stamp, Workspace, FIGURE_DOT,
+};
+use std::io::Write;
+use std::path;
+use std::sync::Arc;
+
+/// The `export` module provides the [`Export`] struct, which draws a picture of the map in
+/// headless mode, without opening a window, and writes it to a PNG or PDF file.
+///
+/// # Making maps on a server with `Export`
+///
+/// The planning office prints the same few maps every week, for the council packet and the
+/// front counter, and someone has been opening the app each Monday to save them by hand with
+/// [`crate::Act::SaveSnapshot`].  A scheduled job on a server can do it instead, where there is
+/// no desktop to open a window on.  That is what headless mode is for.  With `--headless` alone,
+/// the app opens no windows and keeps its background agents running.  With `--export <PATH>` as
+/// well, the headless app draws the map offscreen, writes it to `PATH` and exits, without
+/// starting the event loop, so it runs the same from cron or a task scheduler as from a
+/// terminal.  The `--export` argument turns on headless mode by itself, so `--headless` may be
+/// left off.
+///
+/// * `--extent <WEST,SOUTH,EAST,NORTH>` sets the ground the picture covers, in degrees of
+///   longitude and latitude (see [`Extent::parse`]).  Without it, the picture opens on the view
+///   of the workspace, if there is one with a view, or else on the view from the `[map]` table
+///   of the config, as a new window would.  The extent fills the picture
+///   in one direction, and a picture of a different shape shows extra ground in the other,
+///   rather than stretching the map (see [`Map::frame`]).
+/// * `--size <WIDTHxHEIGHT>` sets the size of the picture in pixels, [`EXPORT_SIZE`] if not
+///   given.
+///
+/// The extension of `PATH` picks the format.  A `.png` file holds the pixels as drawn, through
+/// [`Snapshot::save`].  A `.pdf` file holds a single page, sized to print the picture at
+/// [`EXPORT_DPI`], with the picture embedded as a JPEG, which keeps a large map to a size that
+/// fits in an email.  Any other extension is an [`Excuse::ExportFormat`].
+///
+/// The [`crate::Gpu`] opens the graphics card without a surface, as described in the
+/// [`crate::Offscreen`] docs, and the [`Map`] draws into a texture of the size asked for.  A
+/// map fresh from the config has no tiles on hand, so we draw it, wait [`EXPORT_SETTLE`] for the
+/// tiles it asked for to arrive, and draw again, until a wait passes with no new tiles or we have
+/// drawn [`EXPORT_ROUNDS`] times.  Tiles already in the cache from an earlier run arrive at once,
+/// so a weekly job finishes quickly after the first week.
+///
+/// The picture shows the base map, the layers of any [`Plugins`], and the data files named by
+/// the `open` value of the config, the `--open` argument and the [`Workspace`] of the
+/// `workspace` value or the `--workspace` argument.  Each file reads as in
+/// [`crate::App::open_file`], through a [`crate::DataProvider`] if one claims it, and draws over
+/// the map as a [`Figure`], styled by the `[choropleth]` and `[symbols]` tables and sized by the
+/// `[theme]` table, with the icons of its points drawn through a [`Stamp`], as in a window.  A
+/// file that cannot be read is left out, with a warning, as is a workspace that cannot be read.
+/// The effects of the `[effects]` table run in the window of a [`crate::Lens`], so the picture
+/// comes out without them.
+///
+/// * The `path` field holds the path of the file to write.
+/// * The `extent` field holds the ground the picture covers, if given.
+/// * The `width` field holds the width of the picture in pixels.
+/// * The `height` field holds the height of the picture in pixels.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Export {
+    path: path::PathBuf,
+    extent: Option<Extent>,
+    width: u32,
+    height: u32,
+}
+
+impl Export {
+    /// The `from_cli` method returns the export asked for by the `--export`, `--extent` and
+    /// `--size` arguments of `cli`, or [`None`] if there is no `--export` argument.
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        let path = cli.export().clone()?;
+        let (width, height) = cli.size().unwrap_or(EXPORT_SIZE);
+        Some(Self {
+            path,
+            extent: *cli.extent(),
+            width,
+            height,
+        })
+    }
+
+    /// The `size` method reads a picture size from `text` in the form `WIDTHxHEIGHT`, as the
+    /// `--size` argument of [`Cli`] takes it.  The message of the error goes to [`clap`], which
+    /// shows it to the user.
+    pub fn size(text: &str) -> Result<(u32, u32), String> {
+        let (width, height) = text
+            .split_once(['x', 'X'])
+            .ok_or(format!("expected WIDTHxHEIGHT, found {text}"))?;
+        let side = |side: &str| {
+            side.trim()
+                .parse::<u32>()
+                .map_err(|e| format!("{e} in {text}"))
+        };
+        Ok((side(width)?, side(height)?))
+    }
+
+    /// The `run` method draws the picture with the `[map]` and `[graphics]` tables of
+    /// `settings`, and writes it to the file, as described in the [`Export`] docs.  The tiles
+    /// come from the cache under `home`, shared with the app, the layers of `plugins` draw over
+    /// them, and the data files over those.
+    ///
+    /// Will [`Excuse::ExportFormat`] if the file is not a PNG or PDF, [`Excuse::NoAdapter`] if no
+    /// graphics card can draw the map, [`Excuse::ExportSize`] if the card cannot draw a picture
+    /// of the size, and [`crate::Blame::Image`] or [`crate::Blame::Io`] if the file cannot be
+    /// written.
+    #[tracing::instrument(skip_all)]
+    pub async fn run(&self, settings: &Settings, home: &Home, plugins: &Plugins) -> Arrive<()> {
+        let extension = self
+            .path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        let pdf = match extension.as_deref() {
+            Some("png") => false,
+            Some("pdf") => true,
+            _ => {
+                tracing::warn!("Cannot export {}.", self.path.display());
+                return Err(Excuse::ExportFormat.into());
+            }
+        };
+        let theme = settings.theme();
+        let palette = theme.contrast().palette().sized(*theme.large());
+        let scale = palette.scale();
+        let (width, dot) = (palette.line() * scale, FIGURE_DOT * scale);
+        let symbols = settings.symbols();
+        let atlas = match symbols.folder(&home.config()) {
+            Some(dir) => match Atlas::build(&dir, *symbols.size()) {
+                Ok(atlas) => Some(atlas),
+                Err(e) => {
+                    tracing::warn!("Could not read the symbols in {}: {e}", dir.display());
+                    None
+                }
+            },
+            None => None,
+        };
+        let icons = match &atlas {
+            Some(atlas) => atlas.names().into_iter().map(str::to_owned).collect(),
+            None => Vec::new(),
+        };
+        let workspace = match settings.startup().workspace() {
+            Some(path) => match Workspace::read(path) {
+                Ok(workspace) => workspace,
+                Err(e) => {
+                    tracing::warn!("Cannot restore {}: {e}", path.display());
+                    Workspace::default()
+                }
+            },
+            None => Workspace::default(),
+        };
+        let extent = self.extent.or_else(|| {
+            workspace
+                .permalink()
+                .map(|permalink| permalink.extent(self.width, self.height))
+        });
+        let mut figures = Vec::new();
+        for path in settings.startup().open().iter().chain(workspace.open()) {
+            let job = Job::detached(format!("Reading {}", path.display()));
+            let read = match plugins.provider(path) {
+                Some(provider) => Plugins::load(provider, path.clone(), job).await,
+                None => Dataset::load(path.clone(), job).await,
+            };
+            match read {
+                Ok(dataset) => {
+                    let (choropleth, symbols) = (settings.choropleth(), settings.symbols());
+                    let figure = Figure::new(&dataset, choropleth, symbols, &icons, width, dot);
+                    figures.push(figure);
+                }
+                Err(e) => tracing::warn!("Cannot draw {}: {e}", path.display()),
+            }
+        }
+
+        let mut gpu = Gpu::new();
+        let offscreen = gpu.offscreen(self.width, self.height, settings.graphics())?;
+        let tiles = Map::tiles(settings.map(), home);
+        let mut map = Map::offscreen(
+            settings.map(),
+            &tiles,
+            extent.as_ref(),
+            self.width,
+            self.height,
+            offscreen.device().clone(),
+            offscreen.queue().clone(),
+        )
+        .context("opening the map")?;
+        map.add_layers(plugins.layers(settings.map()));
+        map.show(&figures);
+        if let Some(atlas) = &atlas {
+            let stamp = Stamp::new(offscreen.device().clone(), offscreen.queue().clone(), atlas);
+            map.adorn(Some(Arc::new(stamp)));
+            map.rescale(scale);
+        }
+
+        // A new map starts with the flag raised, so lower it before the first drawing, and any
+        // tile arriving from then on raises it again.
+        map.damaged();
+        let mut snapshot = offscreen.render(&map)?;
+        for round in 1..EXPORT_ROUNDS {
+            crate::sleep(EXPORT_SETTLE).await;
+            if !map.damaged() {
+                tracing::debug!("No new tiles after {round} rounds.");
+                break;
+            }
+            snapshot = offscreen.render(&map)?;
+        }
+        if let Some(extent) = map.extent() {
+            tracing::info!("Exporting {extent:?}.");
+        }
+
+        match pdf {
+            true => Self::pdf(&snapshot, &self.path),
+            false => snapshot.save(&self.path),
+        }
+    }
+
+    /// The `pdf` method writes `snapshot` to `path` as a single page PDF, as described in the
+    /// [`Export`] docs.  The format is simple enough at one picture to a page that we write it by
+    /// hand: a catalog, a page tree, a page, the picture and the line that draws it, followed by
+    /// the table of where each of them starts in the file.
+    ///
+    /// Will [`crate::Blame::Image`] if the picture cannot be encoded, and [`crate::Blame::Io`] if
+    /// the file cannot be written.
+    fn pdf(snapshot: &Snapshot, path: &path::Path) -> Arrive<()> {
+        let (width, height) = (*snapshot.width(), *snapshot.height());
+        // JPEG has no alpha, and the map is opaque, so we drop it.
+        let rgb = snapshot
+            .pixels()
+            .chunks_exact(4)
+            .flat_map(|pixel| &pixel[..3])
+            .copied()
+            .collect::<Vec<u8>>();
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, EXPORT_QUALITY).encode(
+            &rgb,
+            width,
+            height,
+            image::ExtendedColorType::Rgb8,
+        )?;
+        let points = |pixels: u32| f64::from(pixels) * 72.0 / f64::from(EXPORT_DPI);
+        let (across, down) = (points(width), points(height));
+        let draw = format!("q {across:.2} 0 0 {down:.2} 0 0 cm /Map Do Q");
+
+        let mut file = Vec::new();
+        let mut starts = Vec::new();
+        file.extend_from_slice(b"%PDF-1.4\n");
+        let mut object = |file: &mut Vec<u8>, head: String, stream: Option<&[u8]>| {
+            starts.push(file.len());
+            let number = starts.len();
+            file.extend_from_slice(format!("{number} 0 obj\n{head}\n").as_bytes());
+            if let Some(stream) = stream {
+                file.extend_from_slice(b"stream\n");
+                file.extend_from_slice(stream);
+                file.extend_from_slice(b"\nendstream\n");
+            }
+            file.extend_from_slice(b"endobj\n");
+        };
+        object(
+            &mut file,
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            None,
+        );
+        object(
+            &mut file,
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            None,
+        );
+        object(
+            &mut file,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {across:.2} {down:.2}] \
+                 /Resources << /XObject << /Map 4 0 R >> >> /Contents 5 0 R >>"
+            ),
+            None,
+        );
+        object(
+            &mut file,
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>",
+                jpeg.len()
+            ),
+            Some(&jpeg),
+        );
+        object(
+            &mut file,
+            format!("<< /Length {} >>", draw.len()),
+            Some(draw.as_bytes()),
+        );
+        let table = file.len();
+        let mut tail = format!("xref\n0 {}\n0000000000 65535 f \n", starts.len() + 1);
+        for start in &starts {
+            tail.push_str(&format!("{start:010} 00000 n \n"));
+        }
+        tail.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{table}\n%%EOF\n",
+            starts.len() + 1
+        ));
+        file.extend_from_slice(tail.as_bytes());
+
+        std::fs::File::create(path)
+            .and_then(|mut out| out.write_all(&file))
+            .with_context(|| format!("writing {}", path.display()))?;
+        tracing::info!(
+            "Saved a {width}x{height} picture of the map to {}.",
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// The `EXPORT_SIZE` constant holds the width and height in pixels of a picture from
+/// [`Export`] when the `--size` argument is not given, a letter page in landscape at
+/// [`EXPORT_DPI`], less the margins.
+pub const EXPORT_SIZE: (u32, u32) = (1500, 1125);
+
+/// The `EXPORT_DPI` constant holds the pixels to the inch of the page of a PDF from [`Export`].
+pub const EXPORT_DPI: u32 = 150;
+
+/// The `EXPORT_QUALITY` constant holds the quality of the JPEG in a PDF from [`Export`], out of
+/// a hundred.  Map labels blur below about ninety.
+pub const EXPORT_QUALITY: u8 = 92;
+
+/// The `EXPORT_ROUNDS` constant holds the most times [`Export`] draws the map while waiting for
+/// tiles.
+pub const EXPORT_ROUNDS: u32 = 20;
+
+/// The `EXPORT_SETTLE` constant holds how long [`Export`] waits for tiles between drawings.
+pub const EXPORT_SETTLE: std::time::Duration = std::time::Duration::from_millis(1500);
@@ -0,0 +1,174 @@
+use crate::{Basemap, Cache, Exchange, Fetch, Network, Store, TILE_SPAN};
+use galileo::decoded_image::DecodedImage;
+use galileo::error::GalileoError;
+use galileo::layer::data_provider::{DataProvider, FileCacheController, UrlImageProvider};
+use galileo::tile_scheme::TileIndex;
+use std::path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::Instrument;
+use web_time as time;
+
+/// The `fetcher` module provides the [`Fetcher`] struct, which loads the tiles of the base map
+/// for the tile layer of [`galileo`], and keeps count of the tiles on the way.
+///
+/// # Watching the tiles arrive with `Fetcher`
+///
+/// The tile layer of [`galileo`] fetches its tiles through a data provider, and the
+/// [`UrlImageProvider`] it comes with fetches each tile from the tile server, keeping a copy on
+/// disk, but tells nobody what it is doing.  When the map fills in slowly, we want to know how
+/// many tiles are still on the way, which the debug overlay shows (see [`crate::Overlay`]).  The
+/// `Fetcher` wraps the provider from [`galileo`] and hands each tile over to it, counting the
+/// tiles it waits for in a count shared by every map, which [`Fetcher::queued`] reads.  A tile
+/// the provider finds in the cache on disk, under the path from [`Fetcher::tile_path`], comes
+/// back without a trip to the server, so it does not count.
+///
+/// Each tile fetched from the server goes in the log of [`Network`] as an [`Exchange`], so
+/// [`crate::Act::ShowNetwork`] lists the tiles of the map next to the calls to the BEA API, and
+/// loads inside a span named [`TILE_SPAN`], so [`crate::Latency`] times it.  The provider from
+/// [`galileo`] hands back the body of a tile without the response, so a tile that arrives goes
+/// in the log with a status of 200, and one that does not goes in as failed.
+///
+/// Each tile loaded, from the server or from the disk, goes in the cache index of the [`Store`]
+/// as used just now, under the key from [`Fetcher::tile_key`], so that [`crate::prune`] trims
+/// the tiles used longest ago rather than those written longest ago.
+///
+/// * The `basemap` field holds the [`Basemap`] settings, for the address of each tile.
+/// * The `dir` field holds the directory of the tile cache.
+/// * The `provider` field holds the provider from [`galileo`] that fetches and caches the
+///   tiles.
+/// * The `store` field holds the [`Store`] with the cache index, if it opened.
+pub struct Fetcher {
+    basemap: Basemap,
+    dir: path::PathBuf,
+    provider: UrlImageProvider<TileIndex, FileCacheController>,
+    store: Option<Mutex<Store>>,
+}
+
+impl Fetcher {
+    /// The `new` method creates a fetcher for the tiles of `basemap`, caching them under `dir`
+    /// and indexing them in `store`.
+    pub fn new(basemap: &Basemap, dir: &path::Path, store: Option<Store>) -> Self {
+        let source = basemap.clone();
+        let tile_source = move |index: &TileIndex| source.tile(index.z, index.x, index.y);
+        Self {
+            basemap: basemap.clone(),
+            dir: dir.to_path_buf(),
+            provider: UrlImageProvider::new_cached(tile_source, dir),
+            store: store.map(Mutex::new),
+        }
+    }
+
+    /// The `queued` method returns the number of tiles the maps are waiting on from the tile
+    /// server.
+    pub fn queued() -> usize {
+        QUEUED.load(Ordering::Relaxed)
+    }
+
+    /// The `tile_path` method returns where the tile provider of [`galileo`] keeps the tile at
+    /// `url` under `tiles`: the address with the scheme taken off.
+    pub fn tile_path(tiles: &path::Path, url: &str) -> path::PathBuf {
+        let stripped = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .unwrap_or(url);
+        tiles.join(stripped)
+    }
+
+    /// The `tile_key` method returns the key of the tile kept at `path` under `tiles` in the
+    /// cache index: the path below `tiles`, with `/` between the parts on every platform.
+    pub fn tile_key(tiles: &path::Path, path: &path::Path) -> String {
+        path.strip_prefix(tiles)
+            .unwrap_or(path)
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The `touch` method records the tile at `url`, holding `bytes` bytes, as used just now in
+    /// the cache index.  A tile missing from the index is trimmed by its age on disk, so a
+    /// failure only goes to the log.
+    fn touch(&self, url: &str, bytes: usize) {
+        let Some(Ok(store)) = self.store.as_ref().map(Mutex::lock) else {
+            return;
+        };
+        let key = Self::tile_key(&self.dir, &Self::tile_path(&self.dir, url));
+        if let Err(e) = store.index(Cache::Tiles, &key, bytes as u64) {
+            tracing::debug!("Could not index the tile {key}: {e}");
+        }
+    }
+}
+
+impl DataProvider<TileIndex, DecodedImage, ()> for Fetcher {
+    async fn load_raw(&self, key: &TileIndex) -> Result<bytes::Bytes, GalileoError> {
+        let url = self.basemap.tile(key.z, key.x, key.y);
+        if Self::tile_path(&self.dir, &url).is_file() {
+            let result = self.provider.load_raw(key).await;
+            if let Ok(bytes) = &result {
+                self.touch(&url, bytes.len());
+            }
+            return result;
+        }
+        let _waiting = Waiting::new();
+        let started = time::Instant::now();
+        let result = self
+            .provider
+            .load_raw(key)
+            .instrument(tracing::info_span!(TILE_SPAN))
+            .await;
+        if let Ok(bytes) = &result {
+            self.touch(&url, bytes.len());
+        }
+        let network = Network::global();
+        let url = network.redact(&url);
+        let exchange = match &result {
+            Ok(bytes) => Exchange::new(
+                Fetch::Tiles,
+                url,
+                Some(200),
+                started.elapsed(),
+                bytes.len() as u64,
+                false,
+            ),
+            Err(_) => Exchange::new(Fetch::Tiles, url, None, started.elapsed(), 0, false),
+        };
+        network.record(exchange);
+        result
+    }
+
+    fn decode(&self, bytes: bytes::Bytes, context: ()) -> Result<DecodedImage, GalileoError> {
+        self.provider.decode(bytes, context)
+    }
+}
+
+impl std::fmt::Debug for Fetcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Fetcher")
+            .field("basemap", &self.basemap)
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `Waiting` struct counts a tile in [`Fetcher::queued`] for as long as it lives, so a tile
+/// the map stops waiting for, when the view moves on before it arrives, stops counting too.
+struct Waiting;
+
+impl Waiting {
+    /// The `new` method counts one more tile on the way.
+    fn new() -> Self {
+        QUEUED.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        QUEUED.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The `QUEUED` static holds the count of tiles on the way from the tile server, shared by every
+/// [`Fetcher`].
+static QUEUED: AtomicUsize = AtomicUsize::new(0);
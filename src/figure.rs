@@ -0,0 +1,397 @@
+use crate::{Choropleth, Dataset, Extent, Symbols, Synopsis};
+use galileo::galileo_types::cartesian::{CartesianPoint3d, Point2d};
+use galileo::galileo_types::geo::Crs;
+use galileo::galileo_types::geometry::Geom;
+use galileo::galileo_types::geometry_type::CartesianSpace2d;
+use galileo::galileo_types::impls::{ClosedContour, Contour, Polygon};
+use galileo::render::render_bundle::RenderPrimitive;
+use galileo::symbol::{CirclePointSymbol, SimpleContourSymbol, SimplePolygonSymbol, Symbol};
+use num_traits::AsPrimitive;
+use std::path;
+use std::sync::{Arc, RwLock};
+
+/// The `figure` module provides the [`Figure`] struct, which holds the features of an open data
+/// file ready to draw, styled by the `[choropleth]` and `[symbols]` tables of the config.
+///
+/// # Styling data files with `Figure`
+///
+/// A [`Dataset`] keeps its geometry as GeoJSON text, one row at a time, which is the right form
+/// for saving, and the wrong one for drawing every time the map moves.  A `Figure` takes the
+/// shapes of [`Dataset::outlines`], which read the text once, with every position projected into
+/// web mercator meters, the projection of the base map, and hands them to a [`galileo`] feature
+/// layer, which tessellates them once and draws them on the card with the rest of the map.
+///
+/// The style of each feature comes from its row:
+///
+/// * [`Choropleth::classify`] picks the color of its class, by the value of the `column` of the
+///   `[choropleth]` table.  A row outside every class, or every row when the table has no
+///   `column`, takes [`FIGURE_COLOR`].
+/// * [`Symbols::classify`] picks the icon of a point, by the value of the `column` of the
+///   `[symbols]` table.  A point whose icon is in the [`crate::Atlas`] draws as the icon, through
+///   the [`crate::Stamp`] of the map, and the rest draw as dots.
+///
+/// The [`Brush`] of the layer draws polygons filled with the color at [`FIGURE_FILL`] alpha, so
+/// the map shows through, and outlined in a darker shade of it, lines in the color, and points as
+/// a dot [`FIGURE_DOT`] pixels wide.  The line widths and dots grow with the scale of the
+/// `[theme]` table, as the ink does.
+///
+/// Reading the text of a large file takes a while, so the [`crate::App`] styles each table in
+/// the background as it loads, and again when the config changes, and the figure arrives as a
+/// [`crate::UserEvent::Styled`].  Every map window draws the same layer, from
+/// [`Figure::layer`], over the base map and the layers of any plugins, as described in
+/// [`crate::Map::show`].
+///
+/// * The `path` field holds the path of the data file the figure was drawn from.
+/// * The `features` field holds the shapes of the rows with geometry, with their styles, shared
+///   by every map for the icons of their points.
+/// * The `strokes` field holds the layer drawing the features, shared by every map.
+#[derive(Clone, derive_getters::Getters)]
+pub struct Figure {
+    path: path::PathBuf,
+    features: Arc<Vec<Feature>>,
+    #[getter(skip)]
+    strokes: Strokes,
+}
+
+impl Figure {
+    /// The `new` method styles the rows of `dataset` by `choropleth` and `symbols`, as described
+    /// in the [`Figure`] docs, drawing lines `width` pixels wide and dots `dot` pixels wide.  A
+    /// point with an icon among `icons`, the names in the atlas, draws no dot.  A row without
+    /// geometry, or with geometry we cannot read, is left out.
+    #[tracing::instrument(skip_all)]
+    pub fn new(
+        dataset: &Dataset,
+        choropleth: &Choropleth,
+        symbols: &Symbols,
+        icons: &[String],
+        width: f64,
+        dot: f64,
+    ) -> Self {
+        let path = dataset.path().clone();
+        let features = match dataset.geometry() {
+            Some(_) => {
+                let colors = choropleth.classify(dataset);
+                let icons = symbols.classify(dataset);
+                dataset
+                    .outlines()
+                    .iter()
+                    .zip(colors)
+                    .zip(icons)
+                    .filter_map(|((shape, color), icon)| {
+                        Some(Feature {
+                            shape: shape.clone()?,
+                            color: color.unwrap_or(FIGURE_COLOR),
+                            icon,
+                        })
+                    })
+                    .collect::<Vec<Feature>>()
+            }
+            None => Vec::new(),
+        };
+        let marks = features
+            .iter()
+            .flat_map(Mark::from_feature)
+            .filter(|mark| {
+                let stamped = mark.icon.as_ref().is_some_and(|icon| icons.contains(icon));
+                !(stamped && matches!(mark.geometry, Geom::Point(_)))
+            })
+            .collect::<Vec<Mark>>();
+        tracing::trace!(
+            "Styled {} features of {} as {} marks.",
+            features.len(),
+            path.display(),
+            marks.len()
+        );
+        let layer = galileo::layer::FeatureLayer::new(marks, Brush { width, dot }, Crs::EPSG3857);
+        Self {
+            path,
+            features: Arc::new(features),
+            strokes: Arc::new(RwLock::new(layer)),
+        }
+    }
+
+    /// The `layer` method returns the layer drawing the figure, for a map to draw over the base
+    /// map.  The layer is shared, so each map gets a handle to the same one.
+    pub fn layer(&self) -> Box<dyn galileo::layer::Layer> {
+        Box::new(self.strokes.clone())
+    }
+}
+
+/// The galileo layer inside `Figure` does not implement [`std::fmt::Debug`], so we print the
+/// path and the number of features, and leave it at that.
+impl std::fmt::Debug for Figure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Figure")
+            .field("path", &self.path)
+            .field("features", &self.features.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `Feature` struct holds the shape and style of one row of a [`Figure`].
+///
+/// * The `shape` field holds the shape of the geometry of the row.
+/// * The `color` field holds the color of the row, in sRGB.
+/// * The `icon` field holds the name of the icon for the points of the row, if any.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Feature {
+    shape: Shape,
+    color: [u8; 3],
+    icon: Option<String>,
+}
+
+/// The `Mark` struct holds one part of the geometry of a [`Feature`], a point, a line or a
+/// polygon, in the form a [`galileo`] feature layer draws, with the style of the feature.  A row
+/// with many parts, like a multi-polygon or a geometry collection, makes a mark of each.
+///
+/// * The `geometry` field holds the part, in web mercator meters.
+/// * The `color` field holds the color of the feature, in sRGB.
+/// * The `icon` field holds the name of the icon for a point, if any.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Mark {
+    geometry: Geom<Point2d>,
+    color: [u8; 3],
+    icon: Option<String>,
+}
+
+impl Mark {
+    /// The `from_feature` method returns a mark for each part of the shape of `feature`.  A ring
+    /// of a polygon ends where it starts in GeoJSON, and the contours of [`galileo`] close
+    /// themselves, so we leave off the repeated position.
+    pub fn from_feature(feature: &Feature) -> Vec<Self> {
+        let point = |(x, y): &(f64, f64)| Point2d::new(*x, *y);
+        let ring = |ring: &Vec<(f64, f64)>| {
+            let mut points = ring.iter().map(point).collect::<Vec<Point2d>>();
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            ClosedContour::new(points)
+        };
+        let shape = &feature.shape;
+        let points = shape.points.iter().map(|at| Geom::Point(point(at)));
+        let lines = shape
+            .lines
+            .iter()
+            .map(|line| Geom::Contour(Contour::open(line.iter().map(point).collect())));
+        let areas = shape.areas.iter().filter_map(|rings| {
+            let (outer, inner) = rings.split_first()?;
+            Some(Geom::Polygon(Polygon::new(
+                ring(outer),
+                inner.iter().map(ring).collect(),
+            )))
+        });
+        areas
+            .chain(lines)
+            .chain(points)
+            .map(|geometry| Self {
+                geometry,
+                color: feature.color,
+                icon: feature.icon.clone(),
+            })
+            .collect()
+    }
+}
+
+impl galileo::layer::feature_layer::Feature for Mark {
+    type Geom = Geom<Point2d>;
+
+    fn geometry(&self) -> &Self::Geom {
+        &self.geometry
+    }
+}
+
+/// The `Brush` struct is the [`Symbol`] of the layer of a [`Figure`], drawing each [`Mark`] with
+/// the symbols [`galileo`] provides, in the color of the mark, as described in the [`Figure`]
+/// docs.
+///
+/// * The `width` field holds the width of lines and outlines, in pixels.
+/// * The `dot` field holds the width of a point, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, derive_getters::Getters)]
+pub struct Brush {
+    width: f64,
+    dot: f64,
+}
+
+impl Brush {
+    /// The `darken` method returns the shade of `color` that outlines a polygon filled with it.
+    fn darken(color: [u8; 3]) -> galileo::Color {
+        let [red, green, blue] = color.map(|channel| (f32::from(channel) * 0.6).round() as u8);
+        galileo::Color::rgba(red, green, blue, 255)
+    }
+}
+
+impl Symbol<Mark> for Brush {
+    fn render<'a, N, P>(
+        &self,
+        feature: &Mark,
+        geometry: &'a Geom<P>,
+        min_resolution: f64,
+    ) -> Vec<RenderPrimitive<'a, N, P, Contour<P>, Polygon<P>>>
+    where
+        N: AsPrimitive<f32>,
+        P: CartesianPoint3d<Num = N> + Clone,
+    {
+        let [red, green, blue] = feature.color;
+        let solid = galileo::Color::rgba(red, green, blue, 255);
+        match geometry {
+            Geom::Polygon(_) | Geom::MultiPolygon(_) => {
+                SimplePolygonSymbol::new(galileo::Color::rgba(red, green, blue, FIGURE_FILL))
+                    .with_stroke_color(Self::darken(feature.color))
+                    .with_stroke_width(self.width)
+                    .render(feature, geometry, min_resolution)
+            }
+            Geom::Contour(_) | Geom::MultiContour(_) => SimpleContourSymbol::new(solid, self.width)
+                .render(feature, geometry, min_resolution),
+            _ => CirclePointSymbol::new(solid, self.dot).render(feature, geometry, min_resolution),
+        }
+    }
+}
+
+/// The `Strokes` type is the feature layer of a [`Figure`], shared by every map window behind a
+/// lock, as the [`crate::Tiles`] of the base map are.
+pub type Strokes =
+    Arc<RwLock<galileo::layer::FeatureLayer<Point2d, Mark, Brush, CartesianSpace2d>>>;
+
+/// The `Shape` struct holds the geometry of one row of a [`Figure`], in web mercator meters,
+/// sorted by how it draws.  The members of a multi-part geometry or a geometry collection go
+/// with the parts of their kind.
+///
+/// * The `points` field holds the position of each point.
+/// * The `lines` field holds the positions of each line.
+/// * The `areas` field holds the rings of each polygon, the outer ring first.
+/// * The `bounds` field holds the box around every position, as `(x_min, y_min, x_max, y_max)`.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Shape {
+    points: Vec<(f64, f64)>,
+    lines: Vec<Vec<(f64, f64)>>,
+    areas: Vec<Vec<Vec<(f64, f64)>>>,
+    bounds: (f64, f64, f64, f64),
+}
+
+impl Shape {
+    /// The `from_geojson` method reads the shape of the GeoJSON `geometry`, or returns [`None`]
+    /// if it holds no positions.
+    pub fn from_geojson(geometry: &serde_json::Value) -> Option<Self> {
+        let mut shape = Self {
+            points: Vec::new(),
+            lines: Vec::new(),
+            areas: Vec::new(),
+            bounds: (
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+        };
+        shape.read(geometry);
+        let positions = shape
+            .points
+            .iter()
+            .chain(shape.lines.iter().flatten())
+            .chain(shape.areas.iter().flatten().flatten());
+        for (x, y) in positions {
+            let (x_min, y_min, x_max, y_max) = shape.bounds;
+            shape.bounds = (x_min.min(*x), y_min.min(*y), x_max.max(*x), y_max.max(*y));
+        }
+        (shape.bounds.0 <= shape.bounds.2).then_some(shape)
+    }
+
+    /// The `touches` method returns `true` if any position of the shape lies in `bounds`, the box
+    /// `(x_min, y_min, x_max, y_max)` in web mercator meters, as from [`Extent::to_mercator`].
+    /// A shape whose box misses `bounds` is turned away without looking at its positions.
+    pub fn touches(&self, bounds: (f64, f64, f64, f64)) -> bool {
+        let (x_min, y_min, x_max, y_max) = bounds;
+        let (left, bottom, right, top) = self.bounds;
+        if right < x_min || left > x_max || top < y_min || bottom > y_max {
+            return false;
+        }
+        self.points
+            .iter()
+            .chain(self.lines.iter().flatten())
+            .chain(self.areas.iter().flatten().flatten())
+            .any(|(x, y)| (x_min..=x_max).contains(x) && (y_min..=y_max).contains(y))
+    }
+
+    /// The `holds` method returns `true` if the point at `x` and `y`, in web mercator meters,
+    /// lies inside a polygon of the shape, and not in one of its holes.  We count the edges of
+    /// each polygon crossed by a line from the point to the right, which is odd inside.
+    pub fn holds(&self, x: f64, y: f64) -> bool {
+        let (left, bottom, right, top) = self.bounds;
+        if !(left..=right).contains(&x) || !(bottom..=top).contains(&y) {
+            return false;
+        }
+        self.areas.iter().any(|rings| {
+            let crossed = rings
+                .iter()
+                .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+                .filter(|((x_a, y_a), (x_b, y_b))| {
+                    (*y_a > y) != (*y_b > y) && x < x_a + (y - y_a) / (y_b - y_a) * (x_b - x_a)
+                })
+                .count();
+            crossed % 2 == 1
+        })
+    }
+
+    /// The `read` method sorts the parts of the GeoJSON `geometry` into the shape.
+    fn read(&mut self, geometry: &serde_json::Value) {
+        let empty = serde_json::Value::Null;
+        let coordinates = geometry.get("coordinates").unwrap_or(&empty);
+        match geometry.get("type").and_then(serde_json::Value::as_str) {
+            Some("Point" | "MultiPoint") => self.points.extend(Self::path(coordinates)),
+            Some("LineString") => self.lines.push(Self::path(coordinates)),
+            Some("MultiLineString") => self
+                .lines
+                .extend(Self::members(coordinates).iter().map(Self::path)),
+            Some("Polygon") => self.areas.push(Self::rings(coordinates)),
+            Some("MultiPolygon") => self
+                .areas
+                .extend(Self::members(coordinates).iter().map(Self::rings)),
+            Some("GeometryCollection") => {
+                Self::members(geometry.get("geometries").unwrap_or(&empty))
+                    .iter()
+                    .for_each(|member| self.read(member));
+            }
+            _ => {}
+        }
+        self.lines.retain(|line| !line.is_empty());
+        self.areas.retain(|rings| !rings.is_empty());
+    }
+
+    /// The `members` method returns the members of the GeoJSON array `value`, or none if it is
+    /// not an array.
+    fn members(value: &serde_json::Value) -> &[serde_json::Value] {
+        value.as_array().map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The `rings` method returns the rings of the GeoJSON `coordinates` of a polygon.
+    fn rings(coordinates: &serde_json::Value) -> Vec<Vec<(f64, f64)>> {
+        let mut rings = Self::members(coordinates)
+            .iter()
+            .map(Self::path)
+            .collect::<Vec<Vec<(f64, f64)>>>();
+        rings.retain(|ring| !ring.is_empty());
+        rings
+    }
+
+    /// The `path` method returns the positions of the GeoJSON `coordinates` of a point or a
+    /// line, in order, in web mercator meters.
+    fn path(coordinates: &serde_json::Value) -> Vec<(f64, f64)> {
+        let mut points = Vec::new();
+        Synopsis::points(coordinates, &mut points);
+        points
+            .into_iter()
+            .map(|(longitude, latitude)| Extent::project(longitude, latitude))
+            .collect()
+    }
+}
+
+/// The `FIGURE_COLOR` constant holds the color of a feature outside every class of the
+/// [`Choropleth`], or of every feature without one, in sRGB.
+pub const FIGURE_COLOR: [u8; 3] = [51, 102, 204];
+
+/// The `FIGURE_FILL` constant holds the alpha of the fill of a polygon, about half, so the map
+/// shows through.
+pub const FIGURE_FILL: u8 = 128;
+
+/// The `FIGURE_DOT` constant holds the width of a point, in pixels at a scale of one.
+pub const FIGURE_DOT: f64 = 6.0;
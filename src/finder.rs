@@ -0,0 +1,102 @@
+use crate::Courier;
+#[cfg(target_os = "macos")]
+use crate::UserEvent;
+#[cfg(target_os = "macos")]
+use objc2::runtime::{AnyClass, AnyObject, Bool, Sel};
+#[cfg(target_os = "macos")]
+use std::sync::OnceLock;
+
+/// The `finder` module provides the [`Finder`] struct, which hands the files the desktop opens
+/// with the app on macOS to the event loop.
+///
+/// # Opening files from the Finder with `Finder`
+///
+/// On Windows and Linux, the desktop opens a file with the app by launching it with the path as
+/// an argument, which the [`crate::Cli`] reads.  The Finder on macOS launches the app with no
+/// arguments at all, and sends the files in an event instead, asking the delegate of the
+/// application for `application:openURLs:`, both at launch and whenever the user opens another
+/// file while the app runs.  [`winit`] has no event for it, and answers for the delegate with a
+/// class of its own, so [`Finder::listen`] teaches that class the method, after the event loop
+/// sets up the delegate and before it starts running.  The method sends the paths of the files
+/// to the event loop as a [`UserEvent::Open`], through a [`Courier`], and the [`crate::App`]
+/// opens each in the focused window with [`crate::App::open_paths`].
+///
+/// The Finder only offers the app for the kinds of file named in the `CFBundleDocumentTypes`
+/// of its bundle, so the event arrives from a packaged app, not from `cargo run`.  On other
+/// platforms, [`Finder::listen`] does nothing.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Finder;
+
+impl Finder {
+    /// The `listen` method has the files the Finder opens with the app delivered to the event
+    /// loop by `courier`, as described in the [`Finder`] docs.  Call it once, on the main
+    /// thread, after building the event loop and before running it.  Later calls keep the
+    /// first courier.
+    #[cfg(target_os = "macos")]
+    pub fn listen(courier: Courier) {
+        use objc2_app_kit::NSApplication;
+        use objc2_foundation::MainThreadMarker;
+
+        if COURIER.set(courier).is_err() {
+            return;
+        }
+        let Some(main) = MainThreadMarker::new() else {
+            tracing::warn!("Files from the Finder will not open: not on the main thread.");
+            return;
+        };
+        let app = NSApplication::sharedApplication(main);
+        // The delegate belongs to the event loop, which set it up when it was built.
+        let Some(delegate) = (unsafe { app.delegate() }) else {
+            tracing::warn!("Files from the Finder will not open: the app has no delegate.");
+            return;
+        };
+        let object: &AnyObject = delegate.as_ref();
+        let class: &AnyClass = object.class();
+        let open: extern "C" fn(&AnyObject, Sel, &AnyObject, &AnyObject) = Self::open;
+        // Safety: the method takes the receiver, the selector and two objects, as the type
+        // encoding says, and the class gains it before the event loop can call it.
+        let added = unsafe {
+            objc2::ffi::class_addMethod(
+                class as *const AnyClass as *mut objc2::ffi::objc_class,
+                objc2::sel!(application:openURLs:).as_ptr(),
+                Some(std::mem::transmute::<_, unsafe extern "C" fn()>(open)),
+                c"v@:@@".as_ptr(),
+            )
+        };
+        match Bool::from_raw(added).as_bool() {
+            true => tracing::trace!("Listening for files from the Finder."),
+            false => tracing::warn!("Files from the Finder will not open: the method is taken."),
+        }
+    }
+
+    /// The `listen` method does nothing, since only macOS sends files in an event, as described
+    /// in the [`Finder`] docs.
+    #[cfg(not(target_os = "macos"))]
+    pub fn listen(_courier: Courier) {}
+
+    /// The `open` method answers `application:openURLs:` for the delegate of the application,
+    /// sending the paths of the files among `urls` to the event loop.  Addresses that are not
+    /// files are left out.
+    #[cfg(target_os = "macos")]
+    extern "C" fn open(_delegate: &AnyObject, _selector: Sel, _app: &AnyObject, urls: &AnyObject) {
+        use objc2_foundation::{NSArray, NSURL};
+
+        // Safety: AppKit passes an array of URLs, as the method is declared.
+        let urls = unsafe { &*(urls as *const AnyObject as *const NSArray<NSURL>) };
+        let paths = urls
+            .iter()
+            .filter(|url| unsafe { url.isFileURL() })
+            .filter_map(|url| unsafe { url.path() })
+            .map(|path| std::path::PathBuf::from(path.to_string()))
+            .collect::<Vec<std::path::PathBuf>>();
+        tracing::info!("The Finder opened {} files with the app.", paths.len());
+        if let Some(courier) = COURIER.get().filter(|_| !paths.is_empty()) {
+            courier.notify(UserEvent::Open(paths));
+        }
+    }
+}
+
+/// The `COURIER` static holds the [`Courier`] that [`Finder::open`] delivers files through, as
+/// the method reaches us from AppKit with nothing of ours in hand.
+#[cfg(target_os = "macos")]
+static COURIER: OnceLock<Courier> = OnceLock::new();
@@ -0,0 +1,284 @@
+use crate::{Act, ACT_NODES, CONTENT_NODE, LAYER_NODES};
+use accesskit::NodeId;
+use strum::IntoEnumIterator;
+use winit::{event, keyboard};
+
+/// The `focus` module provides the [`Focus`] struct, which tracks the part of a window that
+/// takes keyboard input, and the [`Nav`] enum, which names the keys that move it.
+///
+/// # Getting around without a mouse with `Focus`
+///
+/// Every act has a key binding, if the config gives it one, but a binding is something to look
+/// up and remember, and the default config leaves most acts unbound.  A user who cannot use a
+/// mouse, or would rather not, expects the keys every other app uses: Tab to move between the
+/// parts of a window, the arrow keys to move within one, Enter or Space to press a button, and
+/// the arrows and `+` and `-` to move the map.  Those keys now work in every window, whatever the
+/// bindings say.  A binding still wins, so a config that binds an arrow key to something else
+/// keeps it, and we fall back on [`Nav`] for the keys no binding claims.
+///
+/// The parts of a window are the same ones the [`crate::Access`] tree describes to a screen
+/// reader, and each place focus can rest is a [`Stop`]:
+///
+/// * The content of the window, such as the map.  The arrow keys pan the map.
+/// * The commands, one button for each [`Act`] the deployment has not disabled.  The arrow keys
+///   move between them, and Enter or Space presses the one with focus.
+/// * The layers, one item for each data file open in the app.  The up and down arrows move
+///   between them.
+///
+/// Tab moves focus forward from the content to the commands to the layers and around again, and
+/// Shift+Tab moves it back, skipping a part with nothing in it.  Home and End jump to the ends of
+/// the commands or layers.  The `+` and `-` keys zoom the map wherever focus rests.  Focus moved
+/// by a screen reader lands on the stop of the node it moved to, with [`Stop::from_node`], and
+/// each [`crate::Access`] update reports the stop with focus, so the two agree.
+///
+/// Focus the user cannot see is no help, so the `visible` field turns on as soon as the keyboard
+/// moves focus, and off again at a click, like the focus rings of a web page.  While it is on, a
+/// map with focus draws a ring around its edge with [`crate::Canvas::ring`], in the colors of
+/// the [`crate::Palette`] of the window, and a command or layer with focus shows its name in the
+/// title of the window, after [`FOCUS_SEPARATOR`].  The commands and layers are not drawn over
+/// the map, so there is nothing to ring, and the title names the one with focus for a sighted
+/// user, as the tree names it for a screen reader.
+///
+/// * The `stop` field holds the [`Stop`] with focus.
+/// * The `visible` field indicates the keyboard moved focus last, so we show where it rests.
+#[derive(Debug, Default, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Focus {
+    stop: Stop,
+    visible: bool,
+}
+
+impl Focus {
+    /// The `navigate` method applies `nav` to the focus of a window with a button for each of
+    /// `acts` and `layers` items, as described in the [`Focus`] docs.  Returns the [`Act`] the
+    /// key asks for, if any, such as panning the map or pressing a button.
+    pub fn navigate(&mut self, nav: Nav, acts: &[Act], layers: usize) -> Option<Act> {
+        self.settle(acts, layers);
+        self.visible = true;
+        let stop = self.stop.clone();
+        match (nav, stop) {
+            (Nav::ZoomIn, _) => return Some(Act::ZoomIn),
+            (Nav::ZoomOut, _) => return Some(Act::ZoomOut),
+            (Nav::Next, stop) => self.stop = Self::part(&stop, true, acts, layers),
+            (Nav::Previous, stop) => self.stop = Self::part(&stop, false, acts, layers),
+            (Nav::Up, Stop::Content) => return Some(Act::PanUp),
+            (Nav::Down, Stop::Content) => return Some(Act::PanDown),
+            (Nav::Left, Stop::Content) => return Some(Act::PanLeft),
+            (Nav::Right, Stop::Content) => return Some(Act::PanRight),
+            (Nav::Activate, Stop::Command(act)) => return Some(act),
+            (Nav::Activate, _) | (Nav::First, Stop::Content) | (Nav::Last, Stop::Content) => {}
+            (nav, Stop::Command(act)) => {
+                let place = acts.iter().position(|other| *other == act).unwrap_or(0);
+                let place = Self::step(place, acts.len(), nav, true);
+                self.stop = Stop::Command(acts[place].clone());
+            }
+            (nav, Stop::Layer(place)) => {
+                self.stop = Stop::Layer(Self::step(place, layers, nav, false));
+            }
+        }
+        None
+    }
+
+    /// The `point` method hides the focus after a click, which also puts focus back on the
+    /// content of the window.  Returns `true` if anything changed.
+    pub fn point(&mut self) -> bool {
+        let changed = self.visible || self.stop != Stop::Content;
+        self.visible = false;
+        self.stop = Stop::Content;
+        changed
+    }
+
+    /// The `land` method moves focus to `stop`, as when a screen reader moves it.
+    pub fn land(&mut self, stop: Stop) {
+        self.stop = stop;
+    }
+
+    /// The `settle` method moves focus back to the content if its stop is gone, as when a reload
+    /// of the config disables the group of the act with focus.
+    pub fn settle(&mut self, acts: &[Act], layers: usize) {
+        let gone = match &self.stop {
+            Stop::Content => false,
+            Stop::Command(act) => !acts.contains(act),
+            Stop::Layer(place) => *place >= layers,
+        };
+        if gone {
+            self.stop = Stop::Content;
+        }
+    }
+
+    /// The `ring` method returns `true` if the window should draw a focus ring around its
+    /// content.
+    pub fn ring(&self) -> bool {
+        self.visible && self.stop == Stop::Content
+    }
+
+    /// The `part` method returns the first stop of the part after the part of `stop`, or before
+    /// it if not `forward`, skipping parts with nothing in them.
+    fn part(stop: &Stop, forward: bool, acts: &[Act], layers: usize) -> Stop {
+        let parts = [
+            Some(Stop::Content),
+            acts.first().cloned().map(Stop::Command),
+            (layers > 0).then_some(Stop::Layer(0)),
+        ];
+        let current = match stop {
+            Stop::Content => 0,
+            Stop::Command(_) => 1,
+            Stop::Layer(_) => 2,
+        };
+        (1..parts.len())
+            .map(|offset| match forward {
+                true => (current + offset) % parts.len(),
+                false => (current + parts.len() - offset) % parts.len(),
+            })
+            .find_map(|index| parts[index].clone())
+            .unwrap_or_default()
+    }
+
+    /// The `step` method moves `place` within a part of `len` stops by `nav`.  A row of buttons
+    /// wraps around, and a list stops at the ends.
+    fn step(place: usize, len: usize, nav: Nav, wrap: bool) -> usize {
+        let last = len.saturating_sub(1);
+        match nav {
+            Nav::First => 0,
+            Nav::Last => last,
+            Nav::Up | Nav::Left if place == 0 && wrap => last,
+            Nav::Up | Nav::Left => place.saturating_sub(1),
+            Nav::Down | Nav::Right if place >= last && wrap => 0,
+            Nav::Down | Nav::Right => (place + 1).min(last),
+            _ => place,
+        }
+    }
+}
+
+/// The `Stop` enum describes a place in a window that keyboard focus can rest, as described in
+/// the [`Focus`] docs.
+///
+/// * The `Content` variant is the content of the window, such as the map.  This is the default.
+/// * The `Command` variant is the button for an [`Act`].
+/// * The `Layer` variant is the item for a data file, by its place in the list.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum Stop {
+    /// The `Content` variant indicates the content of the window.
+    #[default]
+    Content,
+    /// The `Command` variant indicates the button for an act.
+    Command(Act),
+    /// The `Layer` variant indicates the item for a data file.
+    Layer(usize),
+}
+
+impl Stop {
+    /// The `node` method returns the id of the node for the stop in the [`crate::Access`] tree.
+    pub fn node(&self) -> NodeId {
+        match self {
+            Self::Content => CONTENT_NODE,
+            Self::Command(act) => {
+                let place = Act::iter().position(|other| other == *act).unwrap_or(0);
+                NodeId(ACT_NODES + place as u64)
+            }
+            Self::Layer(place) => NodeId(LAYER_NODES + *place as u64),
+        }
+    }
+
+    /// The `from_node` method returns the stop for the node `id` of the [`crate::Access`] tree,
+    /// or [`None`] if focus cannot rest there.
+    pub fn from_node(id: NodeId) -> Option<Self> {
+        if id == CONTENT_NODE {
+            return Some(Self::Content);
+        }
+        if id.0 >= LAYER_NODES {
+            return Some(Self::Layer((id.0 - LAYER_NODES) as usize));
+        }
+        let place = id.0.checked_sub(ACT_NODES)?;
+        Act::iter().nth(place as usize).map(Self::Command)
+    }
+
+    /// The `label` method names the stop for the title of the window, with the names of the
+    /// open `layers`.
+    pub fn label(&self, layers: &[String]) -> String {
+        match self {
+            Self::Content => String::new(),
+            Self::Command(act) => format!("{} button", act.title()),
+            Self::Layer(place) => match layers.get(*place) {
+                Some(layer) => format!("{layer} layer"),
+                None => String::new(),
+            },
+        }
+    }
+}
+
+/// The `Nav` enum names the keys that move focus and the map, whatever the key bindings say, as
+/// described in the [`Focus`] docs.
+///
+/// * The `Next` and `Previous` variants are Tab and Shift+Tab.
+/// * The `Up`, `Down`, `Left` and `Right` variants are the arrow keys.
+/// * The `First` and `Last` variants are Home and End.
+/// * The `Activate` variant is Enter or Space.
+/// * The `ZoomIn` and `ZoomOut` variants are `+` (or `=`, on the same key) and `-`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Nav {
+    /// The `Next` variant moves focus to the next part of the window.
+    Next,
+    /// The `Previous` variant moves focus to the part of the window before.
+    Previous,
+    /// The `Up` variant moves up.
+    Up,
+    /// The `Down` variant moves down.
+    Down,
+    /// The `Left` variant moves left.
+    Left,
+    /// The `Right` variant moves right.
+    Right,
+    /// The `First` variant moves to the first stop of a part.
+    First,
+    /// The `Last` variant moves to the last stop of a part.
+    Last,
+    /// The `Activate` variant presses the button with focus.
+    Activate,
+    /// The `ZoomIn` variant zooms the map in.
+    ZoomIn,
+    /// The `ZoomOut` variant zooms the map out.
+    ZoomOut,
+}
+
+impl Nav {
+    /// The `from_key` method returns the navigation asked for by `event` with `modifiers` held,
+    /// or [`None`] if the key does not navigate.  Keys held with Ctrl, Alt or the logo key
+    /// belong to the bindings, and Shift counts only with Tab.
+    pub fn from_key(event: &event::KeyEvent, modifiers: keyboard::ModifiersState) -> Option<Self> {
+        if modifiers.control_key() || modifiers.alt_key() || modifiers.super_key() {
+            return None;
+        }
+        match &event.logical_key {
+            keyboard::Key::Named(named) => match named {
+                keyboard::NamedKey::Tab if modifiers.shift_key() => Some(Self::Previous),
+                keyboard::NamedKey::Tab => Some(Self::Next),
+                keyboard::NamedKey::ArrowUp => Some(Self::Up),
+                keyboard::NamedKey::ArrowDown => Some(Self::Down),
+                keyboard::NamedKey::ArrowLeft => Some(Self::Left),
+                keyboard::NamedKey::ArrowRight => Some(Self::Right),
+                keyboard::NamedKey::Home => Some(Self::First),
+                keyboard::NamedKey::End => Some(Self::Last),
+                keyboard::NamedKey::Enter | keyboard::NamedKey::Space => Some(Self::Activate),
+                _ => None,
+            },
+            keyboard::Key::Character(text) => match text.as_str() {
+                "+" | "=" => Some(Self::ZoomIn),
+                "-" => Some(Self::ZoomOut),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The `FOCUS_COLOR` constant holds the color of the focus ring around the map, in sRGB with
+/// alpha.  Amber stands out against the greens, greys and blues of a base map.
+pub const FOCUS_COLOR: [u8; 4] = [255, 191, 0, 255];
+
+/// The `FOCUS_RING` constant holds the width of the focus ring around the map, in logical
+/// pixels.
+pub const FOCUS_RING: f64 = 3.0;
+
+/// The `FOCUS_SEPARATOR` constant holds the text between the title of a window and the name of
+/// the command or layer with focus.
+pub const FOCUS_SEPARATOR: &str = " | Focus: ";
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+/// The `fxaa` module provides the [`Fxaa`] struct, a pass that smooths the jagged edges of a
+/// finished frame.
+///
+/// # Smoothing edges with `Fxaa`
+///
+/// At a scale factor of one, a road drawn one pixel wide steps along the pixel grid like a
+/// staircase, and the edges of labels shimmer as the map pans.  Multisampling fixes this at the
+/// source, by testing several points in each pixel as the lines are drawn, but it has to happen
+/// inside the pipelines that draw the lines, which belong to the [`galileo`] renderer, and the
+/// renderer chooses its own multisampling without taking a sample count from us.
+///
+/// Fast approximate anti-aliasing, or FXAA, works on the finished frame instead, so it works on
+/// anything.  The map draws into a texture held by the `Fxaa` instead of the window, and
+/// [`Fxaa::apply`] copies the texture onto the window with one more pass.  For each pixel, the
+/// pass compares the brightness of its neighbors, and where they differ sharply, as across the
+/// edge of a line, it blends a few samples along the edge.  The result is a little soft compared
+/// to multisampling, and costs one full-screen pass per frame, which any card draws in well
+/// under a millisecond.
+///
+/// The [`crate::Canvas`] turns the pass on when the `fxaa` key of the `[graphics]` table asks
+/// for it, or as a fallback when the card cannot multisample at the `samples` count asked for,
+/// as described in [`crate::Graphics`].
+///
+/// * The `pipeline` field holds the render pipeline of the pass.
+/// * The `layout` field holds the layout of the bind group for the frame texture.
+/// * The `sampler` field holds the sampler reading the frame texture.
+/// * The `texture` field holds the texture the map draws into.
+/// * The `view` field holds the view of `texture` the map draws through.
+/// * The `bind_group` field binds `view` and `sampler` for the pass.
+/// * The `device` field holds the device shared by every window.
+/// * The `queue` field holds the queue shared by every window.
+#[derive(Debug)]
+pub struct Fxaa {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+impl Fxaa {
+    /// The `new` method builds the pass for a surface configured by `config`, with a frame
+    /// texture of the same size and format.
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fxaa"),
+            source: wgpu::ShaderSource::Wgsl(FXAA_SHADER.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("fxaa"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("fxaa"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("fxaa"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("fxaa"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let (texture, view, bind_group) = Self::frame(&device, &layout, &sampler, config);
+        Self {
+            pipeline,
+            layout,
+            sampler,
+            texture,
+            view,
+            bind_group,
+            device,
+            queue,
+        }
+    }
+
+    /// The `frame` method creates the frame texture for a surface configured by `config`, with
+    /// its view and the bind group reading it.
+    fn frame(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> (wgpu::Texture, wgpu::TextureView, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("fxaa frame"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fxaa frame"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        (texture, view, bind_group)
+    }
+
+    /// The `view` method returns the view of the frame texture, for the map to draw into.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// The `resize` method replaces the frame texture with one fit for a surface configured by
+    /// `config`, if the size has changed.
+    pub fn resize(&mut self, config: &wgpu::SurfaceConfiguration) {
+        let size = self.texture.size();
+        if (size.width, size.height) == (config.width, config.height) {
+            return;
+        }
+        let (texture, view, bind_group) =
+            Self::frame(&self.device, &self.layout, &self.sampler, config);
+        self.texture = texture;
+        self.view = view;
+        self.bind_group = bind_group;
+    }
+
+    /// The `apply` method draws the frame texture onto `target`, the view of the window texture,
+    /// smoothing the edges on the way.
+    pub fn apply(&self, target: &wgpu::TextureView) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("fxaa"),
+            });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("fxaa"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            // One triangle large enough to cover the screen, see the vertex shader.
+            pass.draw(0..3, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
+
+/// The `FXAA_SHADER` constant holds the WGSL source of the [`Fxaa`] pass.  The vertex shader
+/// draws a single triangle covering the screen, and the fragment shader is the compact FXAA of
+/// Timothy Lottes, which looks at the four diagonal neighbors of each pixel, and blends along the
+/// edge they describe unless the blend overshoots the range of the neighborhood.
+pub const FXAA_SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0) var frame: texture_2d<f32>;
+@group(0) @binding(1) var frame_sampler: sampler;
+
+const EDGE_MIN: f32 = 0.0312;
+const EDGE_MAX: f32 = 0.125;
+const REDUCE_MIN: f32 = 0.0078125;
+const REDUCE_MUL: f32 = 0.125;
+const SPAN_MAX: f32 = 8.0;
+
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn fetch(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(frame, frame_sampler, uv, 0.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(frame));
+    let center = fetch(in.uv);
+    let m = luma(center.rgb);
+    let nw = luma(fetch(in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let ne = luma(fetch(in.uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let sw = luma(fetch(in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let se = luma(fetch(in.uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let lo = min(m, min(min(nw, ne), min(sw, se)));
+    let hi = max(m, max(max(nw, ne), max(sw, se)));
+    if hi - lo < max(EDGE_MIN, hi * EDGE_MAX) {
+        return center;
+    }
+    var dir = vec2<f32>(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+    let reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+    let third = fetch(in.uv + dir * (1.0 / 3.0 - 0.5));
+    let two_thirds = fetch(in.uv + dir * (2.0 / 3.0 - 0.5));
+    let near = 0.5 * (third + two_thirds);
+    let far = near * 0.5 + 0.25 * (fetch(in.uv - dir * 0.5) + fetch(in.uv + dir * 0.5));
+    let far_luma = luma(far.rgb);
+    if far_luma < lo || far_luma > hi {
+        return vec4<f32>(near.rgb, center.a);
+    }
+    return vec4<f32>(far.rgb, center.a);
+}
+"#;
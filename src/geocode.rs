@@ -0,0 +1,495 @@
+use crate::{
+    Arrive, Context, Dataset, Excuse, Fetch, Format, Geocoding, Job, Network, PartialFailure,
+    GEOMETRY,
+};
+use arrow::array::{Array, StringArray};
+use std::borrow::Cow;
+use std::path;
+use web_time as time;
+
+/// The `geocode` module provides the [`Geocoder`] struct, which finds the place of each address
+/// in a table, the [`Service`] enum, which names the geocoding services it can ask, and the
+/// [`Candidate`] struct, which holds the best answer for one address.
+///
+/// # Putting addresses on the map with `Geocoder`
+///
+/// The permit office keeps its permits as a spreadsheet of street addresses, with no
+/// coordinates, and asks for them on the map every month.  Until now someone pasted the
+/// addresses into a web form in batches, and the rows that failed fell through the cracks.  The
+/// [`Geocoder`] does the whole table in one go:
+///
+/// 1. The user opens the CSV file as usual, and it comes in as a table without a
+///    [`GEOMETRY`] column.
+/// 2. [`crate::Act::GeocodeAddresses`] starts a bulk job of the [`crate::Tasks`] registry for
+///    each open CSV table without geometry.  The job asks the [`Service`] named in the
+///    `[geocoding]` table of the config (see [`Geocoding`]) for each address in turn, reporting
+///    progress after each row, and can be cancelled like any other job with
+///    [`crate::Act::CancelTasks`].  A request that fails in a way worth retrying waits and tries
+///    again, as for a [`crate::Download`], and the rest count against the row.
+/// 3. The answers go to a new CSV file under [`GEOCODE_DIR`] in the data directory, with the
+///    columns of the source followed by `latitude`, `longitude`, `match_address`,
+///    `match_score` and `match_status`, and a [`GEOMETRY`] column holding a point for each good
+///    match.  The same table opens as a point layer, and the file reopens as one later.
+/// 4. The rows that did not match go to the log as a [`PartialFailure`], one line each, so the
+///    office can fix the addresses and run the file again.
+///
+/// The address of a row comes from the columns named by the `columns` key, joined with commas,
+/// as in `["street", "city", "state", "zip"]`, or failing that from a column called `address`,
+/// in any case.  A table with neither fails with [`Excuse::NoAddress`].
+///
+/// Each service scores its answers differently, if at all.  An ArcGIS locator scores each
+/// candidate from 0 to 100, and we take its score as is.  The Census Bureau and Nominatim do not,
+/// so we score the match ourselves with [`Geocoder::score`], as the share of the words of the
+/// address found in the address matched, after shortening street types and directions the way
+/// the Postal Service does.  A match scoring at least the `score` key, [`MATCH_SCORE`] by
+/// default, goes on the map as `matched`.  A weaker one keeps its coordinates in the file, but
+/// stays off the map as `review`, and fails with [`Excuse::WeakMatch`].  An address with no
+/// answer is `unmatched`, and fails with [`Excuse::NoMatch`], and a row whose request failed is
+/// `failed`.
+///
+/// The rows go one at a time, with the delay between requests the service asks for, since a
+/// public service will block a client that sends hundreds at once.  A county locator on the
+/// local network can take them as fast as they come, with `delay = 0`.
+///
+/// * The `settings` field holds the [`Geocoding`] settings from the config.
+#[derive(Debug, Clone, derive_getters::Getters, derive_new::new)]
+pub struct Geocoder {
+    settings: Geocoding,
+}
+
+impl Geocoder {
+    /// The `run` method geocodes each row of `dataset`, reporting to `job`, writes the results
+    /// to a CSV file in `dir` and logs the rows that failed, as described in the [`Geocoder`]
+    /// docs.  Returns the results as a table, to open as a point layer.
+    ///
+    /// Will [`Excuse::NoAddress`] if the table has no address, [`crate::Blame::Io`] or
+    /// [`crate::Blame::Csv`] if the file cannot be written, and the error of the first row if
+    /// every row failed.
+    #[tracing::instrument(skip_all)]
+    pub async fn run(&self, dataset: Dataset, dir: path::PathBuf, job: Job) -> Arrive<Dataset> {
+        let addresses = self.addresses(&dataset)?;
+        let total = addresses.len();
+        let mut results = Vec::with_capacity(total);
+        for (row, address) in addresses.iter().enumerate() {
+            if row > 0 {
+                crate::sleep(self.delay()).await;
+            }
+            results.push(self.locate(address).await);
+            job.set_progress((row + 1) as f32 / total.max(1) as f32);
+        }
+
+        let stem = dataset
+            .path()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "addresses".to_string());
+        let stamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        let path = dir.join(format!("{stem}-geocoded-{stamp}.csv"));
+        let table = self.table(&dataset, path.clone(), &results)?;
+        table.save_csv(&path)?;
+
+        let batch = addresses
+            .into_iter()
+            .zip(results)
+            .enumerate()
+            .map(|(row, (address, result))| {
+                let result = result.and_then(|candidate| match self.is_good(&candidate) {
+                    true => Ok(candidate),
+                    false => Err(Excuse::WeakMatch.into()),
+                });
+                (format!("row {}, {address}", row + 1), result)
+            })
+            .collect::<PartialFailure<Candidate>>();
+        tracing::info!("Geocoded {stem} to {}.", path.display());
+        batch.log();
+        batch.into_arrive()?;
+        Ok(table)
+    }
+
+    /// The `addresses` method returns the address of each row of `dataset`, as described in the
+    /// [`Geocoder`] docs.
+    ///
+    /// Will [`Excuse::NoAddress`] if the table has none of the address columns.
+    pub fn addresses(&self, dataset: &Dataset) -> Arrive<Vec<String>> {
+        let columns = match self.settings.columns().is_empty() {
+            true => dataset
+                .columns()
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(ADDRESS_COLUMN))
+                .into_iter()
+                .collect::<Vec<usize>>(),
+            false => self
+                .settings
+                .columns()
+                .iter()
+                .filter_map(|name| dataset.column(name))
+                .collect::<Vec<usize>>(),
+        };
+        if columns.is_empty() {
+            return Err(Excuse::NoAddress.into());
+        }
+        let parts = columns
+            .iter()
+            .map(|column| dataset.texts(*column))
+            .collect::<Vec<Cow<'_, StringArray>>>();
+        let addresses = (0..dataset.len())
+            .map(|row| {
+                parts
+                    .iter()
+                    .filter(|part| part.is_valid(row))
+                    .map(|part| part.value(row).trim())
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            })
+            .collect();
+        Ok(addresses)
+    }
+
+    /// The `locate` method asks the service for the best match for `address`, retrying while
+    /// the failure is worth retrying.
+    ///
+    /// Will [`Excuse::NoMatch`] if the address is empty or the service has no answer,
+    /// [`crate::Blame::Http`] if the service cannot be reached or answers with an error, and
+    /// [`crate::Blame::Timeout`] if it stops answering, once retries are spent.
+    pub async fn locate(&self, address: &str) -> Arrive<Candidate> {
+        if address.is_empty() {
+            return Err(Excuse::NoMatch.into());
+        }
+        let mut attempt = 0;
+        loop {
+            let blame = match self.ask(address).await {
+                Ok(candidate) => return candidate.ok_or_else(|| Excuse::NoMatch.into()),
+                Err(blame) => blame,
+            };
+            attempt += 1;
+            match blame.retry_after(attempt) {
+                Some(delay) => {
+                    tracing::info!("Geocoding interrupted, retrying in {delay:?}: {blame}");
+                    crate::sleep(delay).await;
+                }
+                None => return Err(blame),
+            }
+        }
+    }
+
+    /// The `ask` method sends one request for `address` to the service, and returns its best
+    /// match, if any.  The span is named for [`crate::REQUEST_SPAN`], so [`crate::Latency`]
+    /// times it, and the request goes through [`Network::send`], so it shows up in the network
+    /// log.
+    ///
+    /// Will [`crate::Blame::Http`] if the request fails or the service answers with an error.
+    #[tracing::instrument(name = "request", skip_all)]
+    async fn ask(&self, address: &str) -> Arrive<Option<Candidate>> {
+        let service = self.settings.service();
+        let url = self.settings.url().clone();
+        let url = url.unwrap_or_else(|| service.url().to_string());
+        let request = Network::global()
+            .client()
+            .get(&url)
+            .query(&service.query(address));
+        let body = Network::global()
+            .send(request, Fetch::Requests)
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?;
+        Ok(service.candidate(&body).map(|candidate| {
+            let score = candidate
+                .score
+                .unwrap_or_else(|| Self::score(address, &candidate.address));
+            candidate.with_score(score)
+        }))
+    }
+
+    /// The `table` method puts the results for each row of `dataset` alongside its columns, in a
+    /// table read from `path`, as described in the [`Geocoder`] docs.
+    ///
+    /// Will [`crate::Blame::Arrow`] if the table cannot be built.
+    fn table(
+        &self,
+        dataset: &Dataset,
+        path: path::PathBuf,
+        results: &[Arrive<Candidate>],
+    ) -> Arrive<Dataset> {
+        let mut columns = dataset.columns();
+        columns.extend(
+            [
+                "latitude",
+                "longitude",
+                "match_address",
+                "match_score",
+                "match_status",
+                GEOMETRY,
+            ]
+            .map(String::from),
+        );
+        let texts = (0..dataset.columns().len())
+            .map(|column| dataset.texts(column))
+            .collect::<Vec<Cow<'_, StringArray>>>();
+        let rows = results
+            .iter()
+            .enumerate()
+            .map(|(row, result)| {
+                let mut values = texts
+                    .iter()
+                    .map(|column| match column.is_valid(row) {
+                        true => column.value(row).to_string(),
+                        false => String::new(),
+                    })
+                    .collect::<Vec<String>>();
+                match result {
+                    Ok(candidate) => {
+                        let good = self.is_good(candidate);
+                        values.push(candidate.latitude.to_string());
+                        values.push(candidate.longitude.to_string());
+                        values.push(candidate.address.clone());
+                        values.push(format!("{:.0}", candidate.score.unwrap_or_default()));
+                        values.push(if good { "matched" } else { "review" }.to_string());
+                        values.push(match good {
+                            true => serde_json::json!({
+                                "type": "Point",
+                                "coordinates": [candidate.longitude, candidate.latitude],
+                            })
+                            .to_string(),
+                            false => String::new(),
+                        });
+                    }
+                    Err(blame) => {
+                        values.extend(vec![String::new(); 4]);
+                        let status = match blame {
+                            crate::Blame::Excuse {
+                                source: Excuse::NoMatch,
+                                ..
+                            } => "unmatched",
+                            _ => "failed",
+                        };
+                        values.push(status.to_string());
+                        values.push(String::new());
+                    }
+                }
+                values
+            })
+            .collect::<Vec<Vec<String>>>();
+        Dataset::from_rows(path, Format::Csv, columns, rows)
+    }
+
+    /// The `is_good` method returns `true` if `candidate` scores at least the `score` key of the
+    /// settings.
+    fn is_good(&self, candidate: &Candidate) -> bool {
+        candidate.score.unwrap_or_default() >= *self.settings.score()
+    }
+
+    /// The `delay` method returns the wait between requests, from the `delay` key of the
+    /// settings in milliseconds, or failing that from [`Service::delay`].
+    fn delay(&self) -> time::Duration {
+        match self.settings.delay() {
+            Some(delay) => time::Duration::from_millis(*delay),
+            None => self.settings.service().delay(),
+        }
+    }
+
+    /// The `score` method scores `matched` as an answer for `address` from 0 to 100, as the
+    /// share of the words of `address` found in `matched`, as described in the [`Geocoder`]
+    /// docs.  An address without words scores 0.
+    pub fn score(address: &str, matched: &str) -> f64 {
+        let words = |text: &str| {
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    let word = word.to_lowercase();
+                    ABBREVIATIONS
+                        .iter()
+                        .find(|(long, _)| *long == word)
+                        .map_or(word, |(_, short)| short.to_string())
+                })
+                .collect::<Vec<String>>()
+        };
+        let asked = words(address);
+        if asked.is_empty() {
+            return 0.0;
+        }
+        let found = words(matched);
+        let hits = asked.iter().filter(|word| found.contains(word)).count();
+        100.0 * hits as f64 / asked.len() as f64
+    }
+}
+
+/// The `Service` enum names the geocoding services a [`Geocoder`] can ask, chosen by the
+/// `service` key of the `[geocoding]` table.
+///
+/// * The `Census` variant asks the one-line address locator of the Census Bureau, which covers
+///   the United States, needs no key, and is the default.
+/// * The `Nominatim` variant asks the Nominatim search of OpenStreetMap, which covers the world.
+///   The public server asks for no more than one request a second.
+/// * The `Arcgis` variant asks an ArcGIS geocode service, like the locator a county publishes,
+///   through its `findAddressCandidates` operation.  Point the `url` key at the operation of the
+///   locator, since the World locator of Esri wants a token for batches.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Service {
+    /// The `Census` variant indicates the Census Bureau geocoder.
+    #[default]
+    Census,
+    /// The `Nominatim` variant indicates the OpenStreetMap geocoder.
+    Nominatim,
+    /// The `Arcgis` variant indicates an ArcGIS geocode service.
+    Arcgis,
+}
+
+impl Service {
+    /// The `url` method returns the address of the public service, used when the `url` key of
+    /// the settings is missing.
+    pub fn url(&self) -> &'static str {
+        match self {
+            Self::Census => "https://geocoding.geo.census.gov/geocoder/locations/onelineaddress",
+            Self::Nominatim => "https://nominatim.openstreetmap.org/search",
+            Self::Arcgis => concat!(
+                "https://geocode.arcgis.com/arcgis/rest/services/World/GeocodeServer/",
+                "findAddressCandidates"
+            ),
+        }
+    }
+
+    /// The `delay` method returns the wait between requests the public service asks for.
+    pub fn delay(&self) -> time::Duration {
+        match self {
+            Self::Nominatim => time::Duration::from_secs(1),
+            _ => time::Duration::ZERO,
+        }
+    }
+
+    /// The `query` method returns the query parameters asking the service for the best match
+    /// for `address`, answered in JSON.
+    pub fn query(&self, address: &str) -> Vec<(&'static str, String)> {
+        let address = address.to_string();
+        match self {
+            Self::Census => vec![
+                ("address", address),
+                ("benchmark", "Public_AR_Current".to_string()),
+                ("format", "json".to_string()),
+            ],
+            Self::Nominatim => vec![
+                ("q", address),
+                ("format", "jsonv2".to_string()),
+                ("limit", "1".to_string()),
+            ],
+            Self::Arcgis => vec![
+                ("SingleLine", address),
+                ("f", "json".to_string()),
+                ("maxLocations", "1".to_string()),
+                ("outSR", "4326".to_string()),
+            ],
+        }
+    }
+
+    /// The `candidate` method reads the best match from the `body` of a response, or returns
+    /// [`None`] if there is none.  The score is left empty for a service that does not score.
+    pub fn candidate(&self, body: &serde_json::Value) -> Option<Candidate> {
+        match self {
+            Self::Census => {
+                let best = body.pointer("/result/addressMatches/0")?;
+                Some(Candidate::new(
+                    best.get("matchedAddress")?.as_str()?.to_string(),
+                    best.pointer("/coordinates/x")?.as_f64()?,
+                    best.pointer("/coordinates/y")?.as_f64()?,
+                    None,
+                ))
+            }
+            Self::Nominatim => {
+                // Nominatim sends its coordinates as text.
+                let best = body.get(0)?;
+                Some(Candidate::new(
+                    best.get("display_name")?.as_str()?.to_string(),
+                    best.get("lon")?.as_str()?.parse().ok()?,
+                    best.get("lat")?.as_str()?.parse().ok()?,
+                    None,
+                ))
+            }
+            Self::Arcgis => {
+                let best = body.pointer("/candidates/0")?;
+                Some(Candidate::new(
+                    best.get("address")?.as_str()?.to_string(),
+                    best.pointer("/location/x")?.as_f64()?,
+                    best.pointer("/location/y")?.as_f64()?,
+                    best.get("score").and_then(serde_json::Value::as_f64),
+                ))
+            }
+        }
+    }
+}
+
+/// The `Candidate` struct holds the best match a [`Service`] found for an address.
+///
+/// * The `address` field holds the address as the service matched it.
+/// * The `longitude` and `latitude` fields hold the place of the match, in degrees.
+/// * The `score` field holds the score of the match from 0 to 100, as described in the
+///   [`Geocoder`] docs, or [`None`] until it is scored.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters, derive_new::new)]
+pub struct Candidate {
+    address: String,
+    longitude: f64,
+    latitude: f64,
+    score: Option<f64>,
+}
+
+impl Candidate {
+    /// The `with_score` method sets the score of the match.
+    pub fn with_score(mut self, score: f64) -> Self {
+        self.score = Some(score);
+        self
+    }
+}
+
+/// The `GEOCODE_DIR` constant holds the name of the directory under [`crate::Home::data`] where
+/// the [`Geocoder`] writes its results.
+pub const GEOCODE_DIR: &str = "geocoded";
+
+/// The `MATCH_SCORE` constant holds the lowest score of a match that goes on the map, when the
+/// `[geocoding]` table does not say otherwise.
+pub const MATCH_SCORE: f64 = 80.0;
+
+/// The `ADDRESS_COLUMN` constant holds the name of the column to read addresses from, in any
+/// case, when the `[geocoding]` table names no columns.
+pub const ADDRESS_COLUMN: &str = "address";
+
+/// The `ABBREVIATIONS` constant holds the street types and directions that [`Geocoder::score`]
+/// shortens before comparing words, after the abbreviations of the Postal Service.
+const ABBREVIATIONS: [(&str, &str); 21] = [
+    ("avenue", "ave"),
+    ("boulevard", "blvd"),
+    ("circle", "cir"),
+    ("court", "ct"),
+    ("drive", "dr"),
+    ("highway", "hwy"),
+    ("lane", "ln"),
+    ("parkway", "pkwy"),
+    ("place", "pl"),
+    ("road", "rd"),
+    ("street", "st"),
+    ("terrace", "ter"),
+    ("trail", "trl"),
+    ("north", "n"),
+    ("south", "s"),
+    ("east", "e"),
+    ("west", "w"),
+    ("northeast", "ne"),
+    ("northwest", "nw"),
+    ("southeast", "se"),
+    ("southwest", "sw"),
+];
@@ -0,0 +1,171 @@
+use crate::{Arrive, Job};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{Context, Wkb, GEOMETRY, PROGRESS_STEP};
+use std::path;
+
+/// The `geopackage` module provides the [`GeoPackage`] struct, which reads the features of a
+/// GeoPackage file.
+///
+/// # Reading GeoPackages with `GeoPackage`
+///
+/// The county hands out its layers as GeoPackages more often than as anything else, since a
+/// desktop GIS saves to one by default.  A GeoPackage is a SQLite database with a table for each
+/// layer, and a few tables of its own, named `gpkg_` something, saying which tables hold
+/// features and which column of each holds the geometry.  We open it with the [`rusqlite`]
+/// crate, as the [`crate::Store`] does, and [`crate::Dataset::read`] hands a `.gpkg` file to
+/// [`GeoPackage::read`], which comes out as a table like the other formats:
+///
+/// * The first table of features listed in `gpkg_contents`, by name, becomes the table.  A file
+///   with more than one says so in the log, naming the table it read.
+/// * Each column becomes a column of the table, in the order of the table, read as text and
+///   typed as for a CSV file (see [`crate::Dataset`]).  A blob other than the geometry has no
+///   text, and reads as missing.
+/// * The geometry column becomes the [`crate::GEOMETRY`] column, as GeoJSON text.  Each
+///   geometry is a short header, starting with [`GEOPACKAGE_MAGIC`], followed by the geometry
+///   as WKB, which [`crate::Wkb::read`] takes from there.
+/// * Progress goes to the [`Job`] by rows, each [`crate::PROGRESS_STEP`] of the rows in the
+///   table, and the read stops at the next row once the job is cancelled.
+///
+/// We do not project coordinates.  A layer in a system other than longitude and latitude, by
+/// the `srs_id` of its geometry column, reads with a warning that its features will not line up
+/// with the map.  The browser build has no SQLite, so there [`GeoPackage::read`] fails with
+/// [`crate::Excuse::Unavailable`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct GeoPackage;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GeoPackage {
+    /// The `read` method reads the features of the GeoPackage at `path` as columns and rows of
+    /// text, reporting to `job`, as described in the [`GeoPackage`] docs.  It blocks until the
+    /// whole table is read, so call it through [`crate::Dataset::load`].
+    ///
+    /// Will [`crate::Excuse::UnsupportedFormat`] if the file holds no table of features,
+    /// [`crate::Blame::Sqlite`] if the file is not a GeoPackage or is damaged, and
+    /// [`crate::Blame::Io`] if the job is cancelled.
+    pub fn read(path: &path::Path, job: &Job) -> Arrive<(Vec<String>, Vec<Vec<String>>)> {
+        let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY;
+        let connection = rusqlite::Connection::open_with_flags(path, flags)
+            .with_context(|| format!("opening {}", path.display()))?;
+        let tables = connection
+            .prepare(
+                "SELECT table_name FROM gpkg_contents WHERE data_type = 'features'
+                 ORDER BY table_name",
+            )?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        let Some(table) = tables.first() else {
+            tracing::warn!("{} holds no table of features.", path.display());
+            return Err(crate::Excuse::UnsupportedFormat.into());
+        };
+        if tables.len() > 1 {
+            tracing::info!(
+                "Reading {table}, the first of {} tables of features in {}.",
+                tables.len(),
+                path.display()
+            );
+        }
+        let (geometry, srs) = connection.query_row(
+            "SELECT column_name, srs_id FROM gpkg_geometry_columns WHERE table_name = ?1",
+            [table],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        if srs != GEOPACKAGE_DEGREES {
+            tracing::warn!(
+                "{table} is in spatial reference {srs}, not longitude and latitude, \
+                 so its features will not line up with the map."
+            );
+        }
+        let quoted = format!("\"{}\"", table.replace('"', "\"\""));
+        let total = connection.query_row(&format!("SELECT COUNT(*) FROM {quoted}"), [], |row| {
+            row.get::<_, i64>(0)
+        })?;
+        let mut statement = connection.prepare(&format!("SELECT * FROM {quoted}"))?;
+        let names = statement
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        let shape = names.iter().position(|name| *name == geometry);
+        let mut rows = Vec::new();
+        let mut reported = 0.0;
+        let mut query = statement.query([])?;
+        while let Some(row) = query.next()? {
+            if job.is_cancelled() {
+                return Err(std::io::Error::other("the job was cancelled").into());
+            }
+            let mut values = Vec::with_capacity(names.len());
+            for column in 0..names.len() {
+                let value = match (row.get_ref(column)?, Some(column) == shape) {
+                    (rusqlite::types::ValueRef::Blob(bytes), true) => Self::geometry(bytes)
+                        .map(|geometry| geometry.to_string())
+                        .unwrap_or_default(),
+                    (rusqlite::types::ValueRef::Integer(number), _) => number.to_string(),
+                    (rusqlite::types::ValueRef::Real(number), _) => number.to_string(),
+                    (rusqlite::types::ValueRef::Text(text), _) => {
+                        String::from_utf8_lossy(text).to_string()
+                    }
+                    _ => String::new(),
+                };
+                values.push(value);
+            }
+            rows.push(values);
+            if total > 0 {
+                let fraction = rows.len() as f32 / total as f32;
+                if fraction - reported >= PROGRESS_STEP {
+                    reported = fraction;
+                    job.set_progress(fraction);
+                }
+            }
+        }
+        let columns = names
+            .into_iter()
+            .enumerate()
+            .map(|(column, name)| match Some(column) == shape {
+                true => GEOMETRY.to_string(),
+                false => name,
+            })
+            .collect();
+        Ok((columns, rows))
+    }
+
+    /// The `geometry` method reads a geometry of a GeoPackage as a GeoJSON geometry, skipping
+    /// the header in front of the WKB, or returns [`None`] if the bytes are not one we read, or
+    /// the header marks the geometry empty.
+    pub fn geometry(bytes: &[u8]) -> Option<serde_json::Value> {
+        if bytes.get(..2)? != GEOPACKAGE_MAGIC {
+            return None;
+        }
+        let flags = *bytes.get(3)?;
+        // The flags give the size of the envelope after the spatial reference, in sets of two
+        // floats, and mark an empty geometry.
+        let envelope = match (flags >> 1) & 0b111 {
+            0 => 0,
+            1 => 32,
+            2 | 3 => 48,
+            4 => 64,
+            _ => return None,
+        };
+        if flags & 0b1_0000 != 0 {
+            return None;
+        }
+        let mut at = 8 + envelope;
+        Wkb::read(bytes, &mut at)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl GeoPackage {
+    /// The `read` method fails with [`crate::Excuse::Unavailable`], since the browser has no
+    /// SQLite, as described in the [`GeoPackage`] docs.
+    pub fn read(_path: &path::Path, _job: &Job) -> Arrive<(Vec<String>, Vec<Vec<String>>)> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+}
+
+/// The `GEOPACKAGE_MAGIC` constant holds the two bytes that start each geometry of a
+/// [`GeoPackage`].
+pub const GEOPACKAGE_MAGIC: &[u8] = b"GP";
+
+/// The `GEOPACKAGE_DEGREES` constant holds the `srs_id` of longitude and latitude on WGS 84, as
+/// a [`GeoPackage`] numbers it, the one system we read without a warning.
+pub const GEOPACKAGE_DEGREES: i64 = 4326;
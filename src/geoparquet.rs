@@ -0,0 +1,503 @@
+use crate::{Arrive, Context, Dataset, Job, GEOMETRY, PROGRESS_STEP};
+use arrow::array::{Array, ArrayRef, AsArray, BinaryArray, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use std::collections::HashSet;
+use std::path;
+use std::sync::Arc;
+
+/// The `geoparquet` module provides the [`GeoParquet`] struct, which reads and writes tables in
+/// the Parquet format, with geometry as GeoParquet.
+///
+/// # Reading large tables with `GeoParquet`
+///
+/// A statewide extract from the BEA runs to millions of rows, and a CSV of it takes minutes to
+/// parse, most of it spent turning text into numbers and back.  The same table as Parquet is a
+/// fraction of the size, compressed column by column, and reads in seconds.  The state and the
+/// county now publish their larger feature sets as GeoParquet, which is Parquet with the geometry
+/// of each feature in a column of its own, encoded as WKB (well-known binary), and a `geo` entry
+/// in the metadata of the file naming that column.
+///
+/// [`Dataset::read`] hands a `.parquet` or `.geoparquet` file to [`GeoParquet::read`], which
+/// comes out as a table like the other formats:
+///
+/// * Each column of the file becomes a column of the table, in the order of the file, and keeps
+///   its type, as an [`arrow`] array decoded by the [`parquet`] crate (see [`Dataset`]).
+/// * The primary geometry column named in the `geo` metadata becomes the [`GEOMETRY`] column, as
+///   GeoJSON text, so a GeoParquet file draws, sums up and exports like a GeoJSON file.  Any other
+///   geometry column keeps its name, also as GeoJSON text.  A plain Parquet file, without the
+///   metadata, has no geometry, like a CSV.
+/// * Progress goes to the [`Job`] by rows, each [`PROGRESS_STEP`] of the rows in the file, and
+///   the read stops at the next batch of rows once the job is cancelled.
+///
+/// # Writing tables with `GeoParquet`
+///
+/// [`crate::Act::ExportSelectionParquet`] and [`crate::Act::ExportLayersParquet`] write the
+/// features of each open table with [`GeoParquet::save`], as [`Dataset::save_geojson`] does for
+/// GeoJSON.  Each column goes with its type, and the [`GEOMETRY`] column goes as WKB, with the
+/// `geo` metadata of version [`GEOPARQUET_VERSION`] naming it, so other GIS tools read it as
+/// GeoParquet.  A table read from text has only text columns, so a tool that wants numbers from
+/// one casts the columns it needs.
+///
+/// WKB is simple enough that we read and write it by hand.  Reading takes the two-dimensional
+/// types of the standard, from points to geometry collections, in either byte order, and the
+/// extended forms with a third or fourth coordinate, of which we keep the first two.
+///
+/// * The `geometry` field holds the name of the column of the file that becomes the
+///   [`GEOMETRY`] column, if any.
+/// * The `wkb` field holds the names of every column of the file encoded as WKB.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeoParquet {
+    geometry: Option<String>,
+    wkb: HashSet<String>,
+}
+
+impl GeoParquet {
+    /// The `from_metadata` method reads the geometry columns from the text of the `geo` entry in
+    /// the metadata of a file, as described in the [`GeoParquet`] docs.  Text that does not
+    /// parse leaves the file without geometry, with a warning.
+    pub fn from_metadata(text: &str) -> Self {
+        let Ok(geo) = serde_json::from_str::<serde_json::Value>(text) else {
+            tracing::warn!("The GeoParquet metadata does not parse, so reading without geometry.");
+            return Self::default();
+        };
+        let wkb = geo
+            .get("columns")
+            .and_then(serde_json::Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter(|(_, column)| {
+                column.get("encoding").and_then(serde_json::Value::as_str) == Some("WKB")
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<String>>();
+        let geometry = geo
+            .get("primary_column")
+            .and_then(serde_json::Value::as_str)
+            .filter(|name| wkb.contains(*name))
+            .map(str::to_string);
+        Self { geometry, wkb }
+    }
+
+    /// The `read` method reads the Parquet file at `path` into a table, reporting to `job`, as
+    /// described in the [`GeoParquet`] docs.  It blocks until the whole file is read, so call it
+    /// through [`Dataset::load`].
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be opened or the job is cancelled,
+    /// [`crate::Blame::Parquet`] if the file is not Parquet or is damaged, and
+    /// [`crate::Blame::Arrow`] if its batches of rows do not fit together.
+    pub fn read(path: &path::Path, job: &Job) -> Arrive<RecordBatch> {
+        let file =
+            std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        let builder = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let metadata = builder.metadata().file_metadata();
+        let total = metadata.num_rows().max(0) as usize;
+        let geo = metadata
+            .key_value_metadata()
+            .into_iter()
+            .flatten()
+            .find(|entry| entry.key == GEO_METADATA)
+            .and_then(|entry| entry.value.as_deref())
+            .map(Self::from_metadata)
+            .unwrap_or_default();
+        let reader = builder.build()?;
+        let schema = reader.schema();
+
+        let mut batches = Vec::new();
+        let mut read = 0;
+        let mut reported = 0.0;
+        for batch in reader {
+            if job.is_cancelled() {
+                return Err(std::io::Error::other("the job was cancelled").into());
+            }
+            let batch = batch?;
+            read += batch.num_rows();
+            batches.push(batch);
+            if total > 0 {
+                let fraction = read as f32 / total as f32;
+                if fraction - reported >= PROGRESS_STEP {
+                    reported = fraction;
+                    job.set_progress(fraction);
+                }
+            }
+        }
+        let table = arrow::compute::concat_batches(&schema, &batches)?;
+        geo.decode(table)
+    }
+
+    /// The `decode` method turns each WKB column of `table` into GeoJSON text, and renames the
+    /// primary one to [`GEOMETRY`], leaving the other columns as they are.
+    ///
+    /// Will [`crate::Blame::Arrow`] if the table cannot be rebuilt.
+    fn decode(&self, table: RecordBatch) -> Arrive<RecordBatch> {
+        if self.wkb.is_empty() {
+            return Ok(table);
+        }
+        let mut fields = Vec::with_capacity(table.num_columns());
+        let mut arrays = Vec::with_capacity(table.num_columns());
+        for (field, array) in table.schema().fields().iter().zip(table.columns()) {
+            let shapes: Option<Vec<Option<&[u8]>>> = match array.data_type() {
+                _ if !self.wkb.contains(field.name()) => None,
+                DataType::Binary => Some(array.as_binary::<i32>().iter().collect()),
+                DataType::LargeBinary => Some(array.as_binary::<i64>().iter().collect()),
+                _ => None,
+            };
+            let Some(shapes) = shapes else {
+                fields.push(field.as_ref().clone());
+                arrays.push(array.clone());
+                continue;
+            };
+            let text = shapes
+                .into_iter()
+                .map(|bytes| {
+                    let mut at = 0;
+                    bytes
+                        .and_then(|bytes| Wkb::read(bytes, &mut at))
+                        .map(|geometry| geometry.to_string())
+                })
+                .collect::<StringArray>();
+            let name = match Some(field.name().as_str()) == self.geometry.as_deref() {
+                true => GEOMETRY,
+                false => field.name().as_str(),
+            };
+            fields.push(Field::new(name, DataType::Utf8, true));
+            arrays.push(Arc::new(text) as ArrayRef);
+        }
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+    }
+
+    /// The `save` method writes the rows of `dataset` to `path` as GeoParquet, as described in
+    /// the [`GeoParquet`] docs.  Pick the rows first with [`Dataset::selection`].  Returns the
+    /// number of features written.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be created, [`crate::Blame::Arrow`] if the
+    /// geometry cannot be encoded, and [`crate::Blame::Parquet`] if the file cannot be written.
+    #[tracing::instrument(skip(dataset))]
+    pub fn save(dataset: &Dataset, path: &path::Path) -> Arrive<usize> {
+        let table = dataset.table();
+        let geometry = dataset.geometry();
+        let mut fields = Vec::with_capacity(table.num_columns());
+        let mut arrays = Vec::with_capacity(table.num_columns());
+        for (index, (field, array)) in table
+            .schema()
+            .fields()
+            .iter()
+            .zip(table.columns())
+            .enumerate()
+        {
+            match (Some(index) == geometry, dataset.shapes()) {
+                (true, Some(shapes)) => {
+                    let wkb = shapes
+                        .iter()
+                        .map(|text| {
+                            text.and_then(|text| serde_json::from_str(text).ok())
+                                .and_then(|geometry| Wkb::write(&geometry))
+                        })
+                        .collect::<BinaryArray>();
+                    fields.push(Field::new(GEOMETRY, DataType::Binary, true));
+                    arrays.push(Arc::new(wkb) as ArrayRef);
+                }
+                _ => {
+                    fields.push(field.as_ref().clone());
+                    arrays.push(array.clone());
+                }
+            }
+        }
+        let table = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+
+        let metadata = dataset.shapes().map(|_| {
+            let geo = serde_json::json!({
+                "version": GEOPARQUET_VERSION,
+                "primary_column": GEOMETRY,
+                "columns": { GEOMETRY: { "encoding": "WKB", "geometry_types": [] } },
+            });
+            vec![parquet::format::KeyValue::new(
+                GEO_METADATA.to_string(),
+                geo.to_string(),
+            )]
+        });
+        let properties = parquet::file::properties::WriterProperties::builder()
+            .set_compression(parquet::basic::Compression::SNAPPY)
+            .set_key_value_metadata(metadata)
+            .build();
+        let file =
+            std::fs::File::create(path).with_context(|| format!("writing {}", path.display()))?;
+        let mut writer =
+            parquet::arrow::ArrowWriter::try_new(file, table.schema(), Some(properties))?;
+        writer.write(&table)?;
+        writer.close()?;
+        let count = table.num_rows();
+        tracing::info!("Wrote {count} features to {}.", path.display());
+        Ok(count)
+    }
+}
+
+/// The `Wkb` struct reads and writes geometry as WKB, for [`GeoParquet`] and
+/// [`crate::GeoPackage`], turning it into and out of GeoJSON.
+pub struct Wkb;
+
+impl Wkb {
+    /// The `read` method reads the geometry starting at byte `at` of `bytes` as a GeoJSON
+    /// geometry, moving `at` past it, or returns [`None`] if the bytes are not WKB we read.
+    pub fn read(bytes: &[u8], at: &mut usize) -> Option<serde_json::Value> {
+        let little = *bytes.get(*at)? == 1;
+        *at += 1;
+        let mut code = Self::integer(bytes, at, little)?;
+        // The extended form flags a third and fourth coordinate, and an embedded SRID, in the
+        // high bits, and the standard form counts them in the thousands.
+        let mut dimensions = 2;
+        if code & 0x8000_0000 != 0 {
+            dimensions += 1;
+        }
+        if code & 0x4000_0000 != 0 {
+            dimensions += 1;
+        }
+        if code & 0x2000_0000 != 0 {
+            Self::integer(bytes, at, little)?;
+        }
+        code &= 0x0FFF_FFFF;
+        dimensions += match code / 1000 {
+            1 | 2 => 1,
+            3 => 2,
+            _ => 0,
+        };
+        let point = |at: &mut usize| -> Option<serde_json::Value> {
+            let mut coordinates = Vec::with_capacity(dimensions);
+            for _ in 0..dimensions {
+                coordinates.push(Self::float(bytes, at, little)?);
+            }
+            Some(serde_json::json!([coordinates[0], coordinates[1]]))
+        };
+        let line = |at: &mut usize| -> Option<serde_json::Value> {
+            let count = Self::integer(bytes, at, little)?;
+            (0..count)
+                .map(|_| point(at))
+                .collect::<Option<Vec<serde_json::Value>>>()
+                .map(serde_json::Value::Array)
+        };
+        let rings = |at: &mut usize| -> Option<serde_json::Value> {
+            let count = Self::integer(bytes, at, little)?;
+            (0..count)
+                .map(|_| line(at))
+                .collect::<Option<Vec<serde_json::Value>>>()
+                .map(serde_json::Value::Array)
+        };
+        // The parts of a multiple geometry are whole geometries of their own, byte order and all.
+        let parts = |at: &mut usize| -> Option<Vec<serde_json::Value>> {
+            let count = Self::integer(bytes, at, little)?;
+            (0..count).map(|_| Self::read(bytes, at)).collect()
+        };
+        let coordinates = |parts: Vec<serde_json::Value>| {
+            parts
+                .into_iter()
+                .map(|part| part.get("coordinates").cloned())
+                .collect::<Option<Vec<serde_json::Value>>>()
+                .map(serde_json::Value::Array)
+        };
+        let (kind, coordinates) = match code % 1000 {
+            1 => ("Point", point(at)?),
+            2 => ("LineString", line(at)?),
+            3 => ("Polygon", rings(at)?),
+            4 => ("MultiPoint", coordinates(parts(at)?)?),
+            5 => ("MultiLineString", coordinates(parts(at)?)?),
+            6 => ("MultiPolygon", coordinates(parts(at)?)?),
+            7 => {
+                let geometries = parts(at)?;
+                return Some(serde_json::json!({
+                    "type": "GeometryCollection",
+                    "geometries": geometries,
+                }));
+            }
+            _ => return None,
+        };
+        Some(serde_json::json!({ "type": kind, "coordinates": coordinates }))
+    }
+
+    /// The `integer` method reads a four-byte integer at `at` of `bytes`, in little-endian order
+    /// if `little` is `true`, moving `at` past it.
+    fn integer(bytes: &[u8], at: &mut usize, little: bool) -> Option<u32> {
+        let word = <[u8; 4]>::try_from(bytes.get(*at..*at + 4)?).ok()?;
+        *at += 4;
+        Some(match little {
+            true => u32::from_le_bytes(word),
+            false => u32::from_be_bytes(word),
+        })
+    }
+
+    /// The `float` method reads an eight-byte float at `at` of `bytes`, in little-endian order
+    /// if `little` is `true`, moving `at` past it.
+    fn float(bytes: &[u8], at: &mut usize, little: bool) -> Option<f64> {
+        let word = <[u8; 8]>::try_from(bytes.get(*at..*at + 8)?).ok()?;
+        *at += 8;
+        Some(match little {
+            true => f64::from_le_bytes(word),
+            false => f64::from_be_bytes(word),
+        })
+    }
+
+    /// The `write` method writes the GeoJSON `geometry` as two-dimensional WKB, in little-endian
+    /// order, or returns [`None`] if it is not a GeoJSON geometry.
+    fn write(geometry: &serde_json::Value) -> Option<Vec<u8>> {
+        let mut bytes = Vec::new();
+        Self::encode(geometry, &mut bytes)?;
+        Some(bytes)
+    }
+
+    /// The `encode` method writes the GeoJSON `geometry` onto `bytes`, for [`Wkb::write`].
+    fn encode(geometry: &serde_json::Value, bytes: &mut Vec<u8>) -> Option<()> {
+        let kind = geometry.get("type")?.as_str()?;
+        let head = |bytes: &mut Vec<u8>, code: u32| {
+            bytes.push(1);
+            bytes.extend_from_slice(&code.to_le_bytes());
+        };
+        let count = |bytes: &mut Vec<u8>, count: usize| {
+            bytes.extend_from_slice(&(count as u32).to_le_bytes());
+        };
+        let point = |bytes: &mut Vec<u8>, value: &serde_json::Value| -> Option<()> {
+            let pair = value.as_array()?;
+            bytes.extend_from_slice(&pair.first()?.as_f64()?.to_le_bytes());
+            bytes.extend_from_slice(&pair.get(1)?.as_f64()?.to_le_bytes());
+            Some(())
+        };
+        let line = |bytes: &mut Vec<u8>, value: &serde_json::Value| -> Option<()> {
+            let points = value.as_array()?;
+            count(bytes, points.len());
+            points.iter().try_for_each(|value| point(bytes, value))
+        };
+        let rings = |bytes: &mut Vec<u8>, value: &serde_json::Value| -> Option<()> {
+            let lines = value.as_array()?;
+            count(bytes, lines.len());
+            lines.iter().try_for_each(|value| line(bytes, value))
+        };
+        if kind == "GeometryCollection" {
+            let geometries = geometry.get("geometries")?.as_array()?;
+            head(bytes, 7);
+            count(bytes, geometries.len());
+            return geometries
+                .iter()
+                .try_for_each(|geometry| Self::encode(geometry, bytes));
+        }
+        let coordinates = geometry.get("coordinates")?;
+        match kind {
+            "Point" => {
+                head(bytes, 1);
+                point(bytes, coordinates)
+            }
+            "LineString" => {
+                head(bytes, 2);
+                line(bytes, coordinates)
+            }
+            "Polygon" => {
+                head(bytes, 3);
+                rings(bytes, coordinates)
+            }
+            "MultiPoint" | "MultiLineString" | "MultiPolygon" => {
+                let (code, part) = match kind {
+                    "MultiPoint" => (4, 1),
+                    "MultiLineString" => (5, 2),
+                    _ => (6, 3),
+                };
+                let members = coordinates.as_array()?;
+                head(bytes, code);
+                count(bytes, members.len());
+                members.iter().try_for_each(|member| {
+                    head(bytes, part);
+                    match part {
+                        1 => point(bytes, member),
+                        2 => line(bytes, member),
+                        _ => rings(bytes, member),
+                    }
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The `GEO_METADATA` constant holds the key of the entry in the metadata of a Parquet file that
+/// describes its geometry columns, under the GeoParquet standard.
+pub const GEO_METADATA: &str = "geo";
+
+/// The `GEOPARQUET_VERSION` constant holds the version of the GeoParquet standard that
+/// [`GeoParquet::save`] writes.
+pub const GEOPARQUET_VERSION: &str = "1.1.0";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `round_trip` function writes `geometry` as WKB and reads it back, checking that the
+    /// reading stops at the end of the bytes.
+    fn round_trip(geometry: serde_json::Value) -> Option<serde_json::Value> {
+        let bytes = Wkb::write(&geometry)?;
+        let mut at = 0;
+        let read = Wkb::read(&bytes, &mut at)?;
+        assert_eq!(at, bytes.len());
+        Some(read)
+    }
+
+    #[test]
+    fn wkb_round_trips() {
+        let geometries = [
+            serde_json::json!({ "type": "Point", "coordinates": [-123.3, 42.4] }),
+            serde_json::json!({
+                "type": "LineString",
+                "coordinates": [[0.0, 0.0], [1.0, 1.0], [2.0, 0.5]],
+            }),
+            serde_json::json!({
+                "type": "Polygon",
+                "coordinates": [
+                    [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 0.0]],
+                    [[1.0, 1.0], [2.0, 1.0], [1.0, 2.0], [1.0, 1.0]],
+                ],
+            }),
+            serde_json::json!({ "type": "MultiPoint", "coordinates": [[0.0, 1.0], [2.0, 3.0]] }),
+            serde_json::json!({
+                "type": "MultiLineString",
+                "coordinates": [[[0.0, 0.0], [1.0, 1.0]], [[2.0, 2.0], [3.0, 3.0]]],
+            }),
+            serde_json::json!({
+                "type": "MultiPolygon",
+                "coordinates": [[[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]],
+            }),
+            serde_json::json!({
+                "type": "GeometryCollection",
+                "geometries": [
+                    { "type": "Point", "coordinates": [5.0, 6.0] },
+                    { "type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]] },
+                ],
+            }),
+        ];
+        for geometry in geometries {
+            assert_eq!(round_trip(geometry.clone()), Some(geometry));
+        }
+    }
+
+    #[test]
+    fn wkb_reads_big_endian_and_extended() {
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&1.5f64.to_be_bytes());
+        bytes.extend_from_slice(&2.5f64.to_be_bytes());
+        let point = serde_json::json!({ "type": "Point", "coordinates": [1.5, 2.5] });
+        assert_eq!(Wkb::read(&bytes, &mut 0), Some(point.clone()));
+        // A point with a height and an SRID, as PostGIS writes it, drops the height.
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&0xA000_0001u32.to_le_bytes());
+        bytes.extend_from_slice(&4326u32.to_le_bytes());
+        for value in [1.5f64, 2.5, 100.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut at = 0;
+        assert_eq!(Wkb::read(&bytes, &mut at), Some(point));
+        assert_eq!(at, bytes.len());
+    }
+
+    #[test]
+    fn wkb_refuses_short_bytes() {
+        let bytes = Wkb::write(&serde_json::json!({ "type": "Point", "coordinates": [1.0, 2.0] }))
+            .expect("point writes");
+        assert_eq!(Wkb::read(&bytes[..bytes.len() - 1], &mut 0), None);
+        assert_eq!(Wkb::write(&serde_json::json!({ "type": "Circle" })), None);
+    }
+}
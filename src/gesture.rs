@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use web_time as time;
+use winit::{dpi, event};
+
+/// The `gesture` module provides the [`Gestures`] struct, which recognizes touch gestures from the
+/// raw touch events delivered by [`winit`], and the [`Gesture`] enum, which describes the
+/// gestures it recognizes.
+///
+/// # Recognizing touch gestures with `Gestures`
+///
+/// A touch screen does not send us a "pinch".  It sends a stream of [`event::Touch`] events, one
+/// per finger per movement, each with an id that stays the same from the moment the finger lands
+/// until it lifts.  The `Gestures` struct keeps track of the fingers currently on the screen in
+/// the `contacts` field, and turns their movement into gestures:
+///
+/// * With two fingers down, the change in distance between the fingers is a pinch, which zooms
+///   the map around the midpoint of the fingers, and the movement of the midpoint is a pan.
+/// * A single finger held still for at least [`LONG_PRESS`] and then lifted is a long press,
+///   which identifies the feature under the finger.  "Still" means it never strayed more than
+///   [`TOUCH_SLOP`] pixels from where it landed, times the scale of the large-UI mode (see
+///   [`crate::Palette`]), so a bigger hit target forgives a bigger wobble.
+///
+/// Each [`crate::Lens`] has its own `Gestures`, because each window receives its own touches.
+#[derive(Debug, Default, Clone)]
+pub struct Gestures {
+    contacts: HashMap<u64, Contact>,
+}
+
+impl Gestures {
+    /// The `touch` method updates the contacts with a touch event, and returns any gestures
+    /// completed by the event.  The `scale` widens the [`TOUCH_SLOP`] of a still finger.
+    pub fn touch(&mut self, touch: &event::Touch, scale: f64) -> Vec<Gesture> {
+        match touch.phase {
+            event::TouchPhase::Started => {
+                self.contacts.insert(touch.id, Contact::new(touch.location));
+                Vec::new()
+            }
+            event::TouchPhase::Moved => self.moved(touch, TOUCH_SLOP * scale),
+            event::TouchPhase::Ended => self.ended(touch),
+            event::TouchPhase::Cancelled => {
+                self.contacts.remove(&touch.id);
+                Vec::new()
+            }
+        }
+    }
+
+    /// The `moved` method updates the position of a finger, which stops holding still once it
+    /// strays more than `slop` pixels, and reports a pinch and a pan if exactly two fingers are
+    /// down.
+    fn moved(&mut self, touch: &event::Touch, slop: f64) -> Vec<Gesture> {
+        let before = self.pair();
+        if let Some(contact) = self.contacts.get_mut(&touch.id) {
+            contact.last = touch.location;
+            if distance(contact.start, contact.last) > slop {
+                contact.still = false;
+            }
+        }
+        let after = self.pair();
+        let mut gestures = Vec::new();
+        if let (Some((center, spread)), Some((new_center, new_spread))) = (before, after) {
+            if spread > 0.0 && new_spread > 0.0 && spread != new_spread {
+                // Spreading the fingers apart zooms in, which means a smaller resolution.
+                gestures.push(Gesture::Pinch {
+                    factor: spread / new_spread,
+                    anchor: new_center,
+                });
+            }
+            let (x, y) = (new_center.x - center.x, new_center.y - center.y);
+            if x != 0.0 || y != 0.0 {
+                gestures.push(Gesture::Pan {
+                    x,
+                    y,
+                    anchor: center,
+                });
+            }
+        }
+        gestures
+    }
+
+    /// The `ended` method removes a finger, and reports a long press if it was the only finger
+    /// and it held still long enough.
+    fn ended(&mut self, touch: &event::Touch) -> Vec<Gesture> {
+        let alone = self.contacts.len() == 1;
+        match self.contacts.remove(&touch.id) {
+            Some(contact) if alone && contact.still && contact.since.elapsed() >= LONG_PRESS => {
+                vec![Gesture::LongPress(contact.last)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The `pair` method returns the midpoint and distance between the fingers if exactly two
+    /// fingers are down.
+    fn pair(&self) -> Option<(dpi::PhysicalPosition<f64>, f64)> {
+        if self.contacts.len() != 2 {
+            return None;
+        }
+        let mut contacts = self.contacts.values();
+        let a = contacts.next()?.last;
+        let b = contacts.next()?.last;
+        let center = dpi::PhysicalPosition::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+        Some((center, distance(a, b)))
+    }
+}
+
+/// The `Contact` struct tracks a single finger on the screen.
+///
+/// * The `start` field holds the position where the finger landed.
+/// * The `last` field holds the most recent position of the finger.
+/// * The `since` field holds the time the finger landed.
+/// * The `still` field is `true` until the finger strays more than [`TOUCH_SLOP`] from `start`.
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    start: dpi::PhysicalPosition<f64>,
+    last: dpi::PhysicalPosition<f64>,
+    since: time::Instant,
+    still: bool,
+}
+
+impl Contact {
+    fn new(location: dpi::PhysicalPosition<f64>) -> Self {
+        Self {
+            start: location,
+            last: location,
+            since: time::Instant::now(),
+            still: true,
+        }
+    }
+}
+
+/// The `Gesture` enum describes a gesture recognized by [`Gestures`].
+///
+/// * The `Pinch` variant holds the factor by which to multiply the map resolution, and the
+///   midpoint of the fingers to zoom around.
+/// * The `Pan` variant holds the distance moved by the midpoint of two fingers, in pixels, and
+///   the midpoint before the move.
+/// * The `LongPress` variant holds the position of the finger.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// The `Pinch` variant indicates two fingers moved closer together or farther apart.
+    Pinch {
+        /// The factor to multiply the map resolution by.
+        factor: f64,
+        /// The position to zoom around.
+        anchor: dpi::PhysicalPosition<f64>,
+    },
+    /// The `Pan` variant indicates two fingers moved together.
+    Pan {
+        /// The horizontal distance moved.
+        x: f64,
+        /// The vertical distance moved.
+        y: f64,
+        /// The position the move started from.
+        anchor: dpi::PhysicalPosition<f64>,
+    },
+    /// The `LongPress` variant indicates one finger held still, then lifted.
+    LongPress(dpi::PhysicalPosition<f64>),
+}
+
+/// The `distance` function returns the distance in pixels between two positions.
+fn distance(a: dpi::PhysicalPosition<f64>, b: dpi::PhysicalPosition<f64>) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// The `LONG_PRESS` constant sets how long a finger must stay down to count as a long press.
+pub const LONG_PRESS: time::Duration = time::Duration::from_millis(600);
+
+/// The `TOUCH_SLOP` constant sets how far, in pixels, a finger may wander and still count as
+/// holding still.
+pub const TOUCH_SLOP: f64 = 10.0;
@@ -0,0 +1,1296 @@
+use crate::{Arrive, Damage, Effect, Excuse, Fxaa, Graphics, Offscreen};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use web_time as time;
+use winit::{dpi, window};
+
+/// The `gpu` module provides the [`Gpu`] struct, which owns the connection to the graphics card,
+/// and the [`Canvas`] struct, which holds the configured surface of one window.
+///
+/// # Getting pixels on the screen with `Gpu`
+///
+/// [`crate::Map::new`] asks for a [`wgpu::Device`], a [`wgpu::Queue`] and a configured
+/// [`wgpu::Surface`], and for a long time nothing in the crate made any of them, so no map ever
+/// opened.  Making them takes a few steps, and some of them are expensive:
+///
+/// 1. A [`wgpu::Instance`] picks the graphics backends, such as Vulkan, Metal or DirectX.  One
+///    per app.
+/// 2. A [`wgpu::Adapter`] stands for one graphics card.  Asking for it can take a moment, since
+///    the driver has to look around, so we ask once.
+/// 3. A [`wgpu::Device`] and its [`wgpu::Queue`] hold the resources we create on the card and
+///    the work we send it.  Every window shares them, so a texture loaded for one map is
+///    available to the next.
+/// 4. A [`wgpu::Surface`] is the part of a window we draw on.  Each window has its own, set up
+///    with a [`wgpu::SurfaceConfiguration`] for its size and pixel format.
+///
+/// The `Gpu` holds the first three, and [`Gpu::canvas`] makes the fourth for each window as it
+/// opens.  The adapter has to be able to draw on the window, and we cannot ask about a window
+/// before we have one, so the `Gpu` waits for the first [`Gpu::canvas`] call to choose an
+/// adapter and open the device.  A machine with no suitable card fails there with
+/// [`Excuse::NoAdapter`], and the window stays open without a map.
+///
+/// The [`crate::App`] keeps the `Gpu`, and gives each map window a [`Canvas`] and a
+/// [`crate::Map`] drawing on it in [`crate::App::create_window`].  The [`crate::Lens`] keeps the
+/// canvas, and resizes it along with the window.
+///
+/// ## Choosing a present mode
+///
+/// Every surface used to wait for the vertical blank, which never tears but holds each frame
+/// back until the monitor asks for it, and on some machines panning the map felt a step behind
+/// the mouse.  The `present_mode` key of the `[graphics]` table (see [`crate::Graphics`]) now
+/// picks a [`PresentMode`] for every window, trading tearing against latency.  Not every card
+/// offers every mode, so [`PresentMode::choose`] falls back to the nearest mode the surface does
+/// offer, and the [`Canvas`] remembers what its surface offers, so that a change to the setting
+/// reaches open windows on [`crate::App::reload_config`] through [`Canvas::adopt`].
+///
+/// ## Smoothing edges
+///
+/// The same table asks for anti-aliasing, with a multisample count in the `samples` key and
+/// the [`Fxaa`] pass in the `fxaa` key.  The canvas checks the count against the counts the card
+/// offers for the surface format, in [`SAMPLE_COUNTS`], and keeps it as
+/// [`Canvas::sample_count`], which [`crate::Map::new`] and [`crate::Lens::adopt`] hand to the
+/// [`galileo`] renderer to draw the map at.  When the card falls short, the map draws at one
+/// sample a pixel, and the canvas turns on the FXAA pass in place of the samples.  With the pass
+/// on, [`Canvas::draw`] has the map draw into the frame texture of the pass, which then draws
+/// onto the window.
+///
+/// ## Drawing only what changed
+///
+/// A stroke of ink or a toggle of the debug overlay used to draw the whole map again, though the
+/// map came out the same, and the map is by far the costliest part of a frame.  The canvas now
+/// keeps the last picture of the map, and [`Canvas::draw`] asks the map to draw again only when
+/// the [`Damage`] from the [`crate::Lens`] says the map changed.  Otherwise the kept picture goes
+/// back on the window as it was.  With the [`Fxaa`] pass on, the frame texture of the pass holds
+/// the picture already.  Without it, the map draws into a `retained` texture of the canvas, which
+/// is copied onto the window, if the surface lets us copy onto it.  A surface that does not
+/// keeps no picture, and the map draws every frame as before.  A resize, a fresh configuration,
+/// or a switch of the pass leaves the kept picture `stale`, and the next frame draws the map.
+///
+/// ## Running custom shaders
+///
+/// Past the symbology of the layers, the `[effects]` table of the config lists shaders of the
+/// user's own to run over the map, each an [`Effect`].  [`Canvas::shade`] builds them for the
+/// canvas, and [`Canvas::draw`] chains them like the [`Fxaa`] pass: the map draws into the frame
+/// texture of the first effect, each effect draws onto the frame texture of the next, and the
+/// last draws onto the FXAA pass, if on, or the window.  The frame texture of the first effect
+/// keeps the picture of the map, so the effects run again each frame without the map.
+///
+/// ## Keeping colors true
+///
+/// The map, the passes over it and the pictures we export all work in sRGB, the color space of
+/// the web and of most monitors, so a color picked in a style, a tile from a server and the same
+/// spot in a saved picture agree.  That holds as long as every texture in the chain marks itself
+/// as sRGB, so the card converts to linear light when a shader reads a color, and back when it
+/// writes one.  Shaders then blend in linear light, and the [`crate::Effect`] shaders of the
+/// user see linear colors too.  The surface takes an sRGB format where it offers one, and the
+/// frame textures of the [`Fxaa`] pass and the effects take the format of the surface.  A
+/// surface that offers only plain formats, as with some OpenGL drivers, shows the map too dark,
+/// and [`Gamut::choose`] says so in the log.  We cannot draw through an sRGB view of such a
+/// surface, because the [`galileo`] renderer configures the surface itself with the format it
+/// draws in.  Exported pictures come from an [`Offscreen`] texture in sRGB on any card, and an
+/// MP4 clip is tagged with the matrix, primaries and curve of sRGB, so players show the colors
+/// of the window.
+///
+/// A wide-gamut display can show greens and reds past the edge of sRGB.  The `gamut` key of the
+/// `[graphics]` table (see [`Gamut`]) asks for a surface in half floats, which the systems that
+/// support it read as extended sRGB in linear light, so sRGB colors come out as before and a
+/// shader can reach past them.  A surface that offers no such format falls back to sRGB, with a
+/// warning.  The gamut is chosen as the surface opens, so a change takes effect on the next
+/// start.
+///
+/// ## Catching mistakes
+///
+/// The card checks every command we send it, and by default [`wgpu`] answers a command it
+/// rejects by panicking, which takes every window down over one bad draw call.  A shader of the
+/// user's own, a surface format a driver does not quite support, or a bug in the [`galileo`]
+/// renderer should cost us a frame, not the app.  [`Canvas::draw`] and
+/// [`crate::Offscreen::render`] now run their passes inside error scopes with [`Fault::guard`],
+/// which turns anything the card rejects into [`crate::Blame::Gpu`] for
+/// [`crate::App::report_error`] to show the user.  A command sent outside any scope, such as a
+/// tile upload by the renderer, goes to a handler we install on the device in place of the panic,
+/// which logs the [`Fault`] and keeps it until the app collects it with [`Gpu::faults`].  Running
+/// out of memory on the card is still fatal, but now through [`crate::App::shutdown`], with a
+/// message for the user.
+///
+/// ## Choosing a card
+///
+/// A laptop with a graphics card of its own also has one built into the processor, and left to
+/// itself the driver does not always pick the one we want.  The `power` key of the `[graphics]`
+/// table asks for the faster card or the one easier on the battery (see [`Power`]), and the
+/// `adapter` key names a card outright, matching any part of the name, without regard to case.
+/// A name that matches no card able to draw falls back on the `power` preference, with a warning
+/// that lists the cards there are.  The card is chosen once, for the first window, so a change
+/// to either key takes effect on the next start.  [`crate::Act::ShowGpu`] reports the card in
+/// use and lists the others by name, ready to paste into the config.
+///
+/// ## Drawing without a card
+///
+/// Old kiosk hardware may have no card [`wgpu`] can use, and a remote desktop session may hide
+/// the card behind a driver that fails as soon as we ask for a device.  Rather than leave the
+/// window empty, [`Hardware::open`] tries once more with the software adapter of [`wgpu`], which
+/// draws on the processor, through WARP on Windows or a Vulkan or OpenGL software driver like
+/// llvmpipe elsewhere, where one is installed.  The map is slow to draw that way, but it draws,
+/// and [`Gpu::software`] tells the [`crate::App`] to say so in the title of each map window.
+/// The `software` key of the `[graphics]` table asks for the software adapter from the start,
+/// for machines where the card is present but misbehaves.
+///
+/// ## Opening the card in the browser
+///
+/// Asking for an adapter and a device returns a future.  On the desktop it finishes at once, so
+/// we wait for it in place, and the first window gets its map as it opens.  The browser answers
+/// only once we give back the thread, so there [`Gpu::prepare`] asks in the background, and the
+/// card arrives as a [`crate::UserEvent::Prepared`].  The [`crate::App`] hands it to
+/// [`Gpu::adopt`], and attaches the maps of the windows that opened in the meantime.  Browsers do
+/// not list their cards, so the `adapter` key has nothing to match there, and
+/// [`crate::Act::ShowGpu`] reports only the card in use.
+///
+/// * The `instance` field holds the [`wgpu::Instance`] for the app.
+/// * The `hardware` field holds the [`Hardware`] opened for the first window, if any yet.
+/// * The `waiting` field holds `true` while [`Gpu::prepare`] waits on the browser.
+#[derive(Debug)]
+pub struct Gpu {
+    instance: Arc<wgpu::Instance>,
+    hardware: Option<Hardware>,
+    #[cfg(target_arch = "wasm32")]
+    waiting: bool,
+}
+
+impl Gpu {
+    /// The `new` method creates an instance on the default backends for the platform.  No
+    /// adapter is chosen until the first window asks for a [`Canvas`].
+    pub fn new() -> Self {
+        Self {
+            instance: Arc::new(wgpu::Instance::new(wgpu::InstanceDescriptor::default())),
+            hardware: None,
+            #[cfg(target_arch = "wasm32")]
+            waiting: false,
+        }
+    }
+
+    /// The `canvas` method creates a surface for `window`, configured for its current size and
+    /// the `graphics` settings, and opens the adapter and device first if this is the first
+    /// window.
+    ///
+    /// Will [`crate::Blame::CreateSurface`] if the window cannot be drawn on,
+    /// [`Excuse::NoAdapter`] if no graphics card can draw on it, and
+    /// [`crate::Blame::RequestDevice`] if the card refuses us a device.  In the browser, will
+    /// [`Excuse::Unavailable`] until [`Gpu::adopt`] has the card.
+    #[tracing::instrument(skip_all)]
+    pub fn canvas(&mut self, window: Arc<window::Window>, graphics: &Graphics) -> Arrive<Canvas> {
+        let size = window.inner_size();
+        let surface = self.instance.create_surface(window)?;
+        let hardware = match &self.hardware {
+            Some(hardware) => hardware.clone(),
+            None => self.open(Some(&surface), graphics)?,
+        };
+        let capabilities = surface.get_capabilities(&hardware.adapter);
+        // An adapter that can draw on the surface offers at least one format of some kind.
+        let format = graphics
+            .gamut()
+            .choose(&capabilities.formats)
+            .ok_or(Excuse::NoAdapter)?;
+        // We copy the kept picture of the map onto the surface, where the surface allows it.
+        let copy = capabilities.usages.contains(wgpu::TextureUsages::COPY_DST);
+        let usage = match copy {
+            true => wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST,
+            false => wgpu::TextureUsages::RENDER_ATTACHMENT,
+        };
+        let config = wgpu::SurfaceConfiguration {
+            usage,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: graphics.present_mode().choose(&capabilities.present_modes),
+            desired_maximum_frame_latency: 2,
+            alpha_mode: capabilities
+                .alpha_modes
+                .first()
+                .copied()
+                .unwrap_or(wgpu::CompositeAlphaMode::Auto),
+            view_formats: Vec::new(),
+        };
+        surface.configure(&hardware.device, &config);
+        tracing::trace!(
+            "Surface configured: {format:?}, {}x{}, {:?}.",
+            config.width,
+            config.height,
+            config.present_mode
+        );
+        let flags = hardware.adapter.get_texture_format_features(format).flags;
+        let samples = SAMPLE_COUNTS
+            .into_iter()
+            .filter(|count| flags.sample_count_supported(*count))
+            .collect();
+        let retained = copy.then(|| Canvas::retain(&hardware.device, &config));
+        let mut canvas = Canvas {
+            surface: Arc::new(surface),
+            config,
+            modes: capabilities.present_modes,
+            samples,
+            sample_count: 1,
+            fxaa: None,
+            effects: Vec::new(),
+            animate: false,
+            opened: time::Instant::now(),
+            retained,
+            stale: true,
+            device: hardware.device,
+            queue: hardware.queue,
+        };
+        canvas.adopt(graphics);
+        Ok(canvas)
+    }
+
+    /// The `offscreen` method creates an [`Offscreen`] texture of `width` by `height` pixels on
+    /// the device shared by the windows.  Without a window open yet, we choose the adapter by
+    /// the `graphics` settings without one, and the windows that open later share it.
+    ///
+    /// Will [`Excuse::NoAdapter`] if there is no graphics card to draw with, and
+    /// [`Excuse::ExportSize`] if the card cannot hold a texture of the size.  In the browser,
+    /// will [`Excuse::Unavailable`] until [`Gpu::adopt`] has the card.
+    #[tracing::instrument(skip_all)]
+    pub fn offscreen(&mut self, width: u32, height: u32, graphics: &Graphics) -> Arrive<Offscreen> {
+        let hardware = match &self.hardware {
+            Some(hardware) => hardware.clone(),
+            None => self.open(None, graphics)?,
+        };
+        Offscreen::new(hardware.device, hardware.queue, width, height)
+    }
+
+    /// The `open` method opens the adapter and device with [`Hardware::open`], able to draw on
+    /// `surface`, if any, by the `graphics` settings, waiting for them in place, and keeps them
+    /// for the windows after.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open(
+        &mut self,
+        surface: Option<&wgpu::Surface<'static>>,
+        graphics: &Graphics,
+    ) -> Arrive<Hardware> {
+        let hardware = pollster::block_on(Hardware::open(&self.instance, surface, graphics))?;
+        self.hardware = Some(hardware.clone());
+        Ok(hardware)
+    }
+
+    /// The `open` method fails with [`Excuse::Unavailable`], since the browser would never
+    /// answer while we wait in place.  The card comes from [`Gpu::prepare`] instead, as
+    /// described in the [`Gpu`] docs.
+    #[cfg(target_arch = "wasm32")]
+    fn open(
+        &mut self,
+        _surface: Option<&wgpu::Surface<'static>>,
+        _graphics: &Graphics,
+    ) -> Arrive<Hardware> {
+        Err(Excuse::Unavailable.into())
+    }
+
+    /// The `prepare` method opens the adapter and device in the background, able to draw on
+    /// `window`, by the `graphics` settings, as described in the [`Gpu`] docs.  The card arrives
+    /// as a [`crate::UserEvent::Prepared`], and an error through `courier`.  Does nothing if the
+    /// card is open or on the way.
+    #[cfg(target_arch = "wasm32")]
+    pub fn prepare(
+        &mut self,
+        window: Arc<window::Window>,
+        graphics: &Graphics,
+        courier: crate::Courier,
+    ) {
+        if self.hardware.is_some() || self.waiting {
+            return;
+        }
+        self.waiting = true;
+        let instance = self.instance.clone();
+        let graphics = graphics.clone();
+        crate::spawn(async move {
+            let surface = match instance.create_surface(window) {
+                Ok(surface) => surface,
+                Err(e) => return courier.send(e.into()),
+            };
+            match Hardware::open(&instance, Some(&surface), &graphics).await {
+                Ok(hardware) => courier.notify(crate::UserEvent::Prepared(hardware)),
+                Err(blame) => courier.send(blame),
+            }
+        });
+    }
+
+    /// The `adopt` method keeps `hardware`, from [`Gpu::prepare`], for every window after.
+    pub fn adopt(&mut self, hardware: Hardware) {
+        self.hardware = Some(hardware);
+    }
+
+    /// The `ready` method returns `true` once the adapter and device are open.
+    pub fn ready(&self) -> bool {
+        self.hardware.is_some()
+    }
+
+    /// The `software` method returns `true` if the map is drawn in software, on the processor,
+    /// as described in the [`Gpu`] docs.
+    pub fn software(&self) -> bool {
+        self.adapter()
+            .is_some_and(|info| info.device_type == wgpu::DeviceType::Cpu)
+    }
+
+    /// The `adapter` method returns a description of the graphics card in use, or [`None`]
+    /// before the first window opens.
+    pub fn adapter(&self) -> Option<wgpu::AdapterInfo> {
+        self.hardware
+            .as_ref()
+            .map(|hardware| hardware.adapter.get_info())
+    }
+
+    /// The `faults` method takes the [`Fault`] types the device has reported outside any error
+    /// scope since the last call, oldest first, as described in the [`Gpu`] docs.
+    pub fn faults(&self) -> Vec<Fault> {
+        match &self.hardware {
+            Some(hardware) => match hardware.faults.lock() {
+                Ok(mut faults) => std::mem::take(&mut *faults),
+                Err(_) => Vec::new(),
+            },
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Reports the card in use, if any yet, followed by every card the app could use, one to a line,
+/// for [`crate::Act::ShowGpu`].
+impl std::fmt::Display for Gpu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.adapter() {
+            Some(info) => writeln!(f, "Drawing with {}.", Hardware::describe(&info))?,
+            None => writeln!(f, "No card chosen yet.")?,
+        }
+        writeln!(f, "Cards available:")?;
+        for adapter in Hardware::cards(&self.instance) {
+            writeln!(f, "  {}", Hardware::describe(&adapter.get_info()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Gpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `Hardware` struct holds the parts of the [`Gpu`] opened for the first window, shared by
+/// every window after.
+///
+/// * The `adapter` field holds the graphics card.
+/// * The `device` field holds the device opened on the card.
+/// * The `queue` field holds the queue of work for the device.
+/// * The `faults` field holds the [`Fault`] types reported outside any error scope, for
+///   [`Gpu::faults`] to collect.
+#[derive(Debug, Clone)]
+pub struct Hardware {
+    adapter: Arc<wgpu::Adapter>,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+    faults: Arc<Mutex<Vec<Fault>>>,
+}
+
+impl Hardware {
+    /// The `open` method chooses an adapter of `instance` able to draw on `surface`, if any, by
+    /// the `graphics` settings, and opens a device on it.  If there is no such adapter, or it
+    /// will not give us a device, we fall back on the software adapter, as described in the
+    /// [`Gpu`] docs.
+    ///
+    /// Will [`Excuse::NoAdapter`] if there is no software adapter either, and
+    /// [`crate::Blame::RequestDevice`] if the software adapter refuses us a device.
+    pub async fn open(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface<'static>>,
+        graphics: &Graphics,
+    ) -> Arrive<Self> {
+        if !*graphics.software() {
+            let named = graphics
+                .adapter()
+                .as_ref()
+                .and_then(|name| Self::named(instance, surface, name));
+            let adapter = match named {
+                Some(adapter) => Some(adapter),
+                None => Self::request(instance, surface, graphics, false).await,
+            };
+            match adapter {
+                Some(adapter) => match Self::connect(adapter).await {
+                    Ok(hardware) => return Ok(hardware),
+                    Err(e) => tracing::warn!("The graphics card failed ({e}), trying software."),
+                },
+                None => tracing::warn!("No graphics card can draw here, trying software."),
+            }
+        }
+        let adapter = Self::request(instance, surface, graphics, true)
+            .await
+            .ok_or(Excuse::NoAdapter)?;
+        let hardware = Self::connect(adapter).await?;
+        tracing::warn!("Drawing in software, so the map will be slow.");
+        Ok(hardware)
+    }
+
+    /// The `request` method asks `instance` for an adapter able to draw on `surface`, if any,
+    /// by the `power` preference of the `graphics` settings, or for the software adapter if
+    /// `software` is `true`.
+    async fn request(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface<'static>>,
+        graphics: &Graphics,
+        software: bool,
+    ) -> Option<wgpu::Adapter> {
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: graphics.power().preference(),
+                compatible_surface: surface,
+                force_fallback_adapter: software,
+            })
+            .await
+    }
+
+    /// The `connect` method opens a device on `adapter`.
+    ///
+    /// Will [`crate::Blame::RequestDevice`] if the adapter refuses us a device.
+    async fn connect(adapter: wgpu::Adapter) -> Arrive<Self> {
+        let info = adapter.get_info();
+        tracing::info!("Drawing with {}.", Self::describe(&info));
+        // OpenGL drivers, including the software ones, fall short of the default limits.
+        let limits = match info.backend {
+            wgpu::Backend::Gl => wgpu::Limits::downlevel_webgl2_defaults(),
+            _ => wgpu::Limits::default(),
+        };
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("tardy"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: limits.using_resolution(adapter.limits()),
+                    memory_hints: wgpu::MemoryHints::default(),
+                },
+                None,
+            )
+            .await?;
+        // The default handler panics, so we keep what the card rejects for the app to report.
+        let faults = Arc::new(Mutex::new(Vec::new()));
+        let kept = faults.clone();
+        device.on_uncaptured_error(Box::new(move |e| {
+            let fault = Fault::from(e);
+            tracing::warn!("The graphics card rejected a command: {fault}");
+            if let Ok(mut kept) = kept.lock() {
+                if kept.len() < FAULT_LIMIT {
+                    kept.push(fault);
+                }
+            }
+        }));
+        Ok(Self {
+            adapter: Arc::new(adapter),
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+            faults,
+        })
+    }
+
+    /// The `named` method returns the first adapter of `instance` with `name` in its name,
+    /// ignoring case, that can draw on `surface`, if any.  Without a match, we warn with the
+    /// names of the cards there are.
+    fn named(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface<'static>>,
+        name: &str,
+    ) -> Option<wgpu::Adapter> {
+        let adapters = Self::cards(instance);
+        let wanted = name.to_lowercase();
+        let names = adapters
+            .iter()
+            .map(|adapter| adapter.get_info().name)
+            .collect::<Vec<String>>();
+        let found = adapters.into_iter().find(|adapter| {
+            adapter.get_info().name.to_lowercase().contains(&wanted)
+                && surface.map_or(true, |surface| adapter.is_surface_supported(surface))
+        });
+        if found.is_none() {
+            tracing::warn!(
+                "No card named {name} can draw here, choosing by power.  Cards: {}",
+                names.join(", ")
+            );
+        }
+        found
+    }
+
+    /// The `cards` method lists every adapter of `instance`, on any backend.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cards(instance: &wgpu::Instance) -> Vec<wgpu::Adapter> {
+        instance.enumerate_adapters(wgpu::Backends::all())
+    }
+
+    /// The `cards` method returns no adapters, since browsers do not list their cards, as
+    /// described in the [`Gpu`] docs.
+    #[cfg(target_arch = "wasm32")]
+    fn cards(_instance: &wgpu::Instance) -> Vec<wgpu::Adapter> {
+        Vec::new()
+    }
+
+    /// The `describe` method sums up an adapter in a line, as in "NVIDIA GeForce RTX 3050
+    /// (discrete, Vulkan)".
+    fn describe(info: &wgpu::AdapterInfo) -> String {
+        let kind = match info.device_type {
+            wgpu::DeviceType::DiscreteGpu => "discrete",
+            wgpu::DeviceType::IntegratedGpu => "integrated",
+            wgpu::DeviceType::VirtualGpu => "virtual",
+            wgpu::DeviceType::Cpu => "software",
+            wgpu::DeviceType::Other => "other",
+        };
+        format!("{} ({kind}, {:?})", info.name, info.backend)
+    }
+}
+
+/// The `Power` enum describes which card to prefer in a machine with more than one, as described
+/// in the [`Gpu`] docs.
+///
+/// * The `HighPerformance` variant prefers the faster card, usually one of its own.  This is the
+///   default.
+/// * The `LowPower` variant prefers the card easier on the battery, usually the one built into
+///   the processor.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+)]
+pub enum Power {
+    /// The `HighPerformance` variant indicates a preference for the faster card.
+    #[default]
+    HighPerformance,
+    /// The `LowPower` variant indicates a preference for the thriftier card.
+    LowPower,
+}
+
+/// Reads the name of the power preference whatever its case, as described in [`crate::choice`].
+impl<'de> serde::Deserialize<'de> for Power {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::choice(deserializer)
+    }
+}
+
+impl Power {
+    /// The `preference` method returns the matching [`wgpu::PowerPreference`].
+    pub fn preference(&self) -> wgpu::PowerPreference {
+        match self {
+            Self::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            Self::LowPower => wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+/// The `Canvas` struct holds the surface of one window and what it takes to draw on it, as
+/// described in the [`Gpu`] docs.
+///
+/// * The `surface` field holds the [`wgpu::Surface`] of the window.
+/// * The `config` field holds the [`wgpu::SurfaceConfiguration`] last applied to the surface.
+/// * The `modes` field holds the present modes the surface offers.
+/// * The `samples` field holds the multisample counts the card offers for the surface format.
+/// * The `sample_count` field holds the multisample count the map draws at, from the `samples`
+///   key of the config if the card offers it, or else 1.
+/// * The `fxaa` field holds the [`Fxaa`] pass, if smoothing is on.
+/// * The `effects` field holds the custom shaders run over the map, in order.
+/// * The `animate` field is `true` if the effects move with time, and need frames to do it.
+/// * The `opened` field holds the time the canvas opened, for the `time` of the effects.
+/// * The `retained` field holds the last picture of the map, if the surface can be copied onto.
+/// * The `stale` field indicates the kept picture no longer fits the surface.
+/// * The `device` field holds the device shared by every window.
+/// * The `queue` field holds the queue shared by every window.
+#[derive(Debug, derive_getters::Getters)]
+pub struct Canvas {
+    surface: Arc<wgpu::Surface<'static>>,
+    config: wgpu::SurfaceConfiguration,
+    modes: Vec<wgpu::PresentMode>,
+    samples: Vec<u32>,
+    sample_count: u32,
+    fxaa: Option<Fxaa>,
+    effects: Vec<Effect>,
+    animate: bool,
+    opened: time::Instant,
+    retained: Option<wgpu::Texture>,
+    stale: bool,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+impl Canvas {
+    /// The `retain` method creates a texture to keep the picture of the map in, for a surface
+    /// configured by `config`.
+    fn retain(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("retained"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    /// The `resize` method configures the surface for a window of `size`.  A minimized window
+    /// reports a size of zero, which the surface cannot take, so we leave the surface alone
+    /// until the window comes back.
+    pub fn resize(&mut self, size: dpi::PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.config.width = size.width;
+        self.config.height = size.height;
+        self.surface.configure(&self.device, &self.config);
+        if let Some(fxaa) = &mut self.fxaa {
+            fxaa.resize(&self.config);
+        }
+        for effect in &mut self.effects {
+            effect.resize(&self.config);
+        }
+        if self.retained.is_some() {
+            self.retained = Some(Self::retain(&self.device, &self.config));
+        }
+        self.stale = true;
+    }
+
+    /// The `fits` method returns `true` if the surface is configured for a window of `size`.  A
+    /// window can change size without telling us in time, as when it moves to a monitor with a
+    /// different scale factor, and drawing on a surface of the old size stretches the frame.
+    pub fn fits(&self, size: dpi::PhysicalSize<u32>) -> bool {
+        (self.config.width, self.config.height) == (size.width.max(1), size.height.max(1))
+    }
+
+    /// The `reconfigure` method applies the current configuration to the surface again, after
+    /// [`wgpu::SurfaceError::Outdated`] or [`wgpu::SurfaceError::Lost`] says the surface has
+    /// fallen out of step with the window.
+    pub fn reconfigure(&mut self) {
+        self.surface.configure(&self.device, &self.config);
+        self.stale = true;
+    }
+
+    /// The `adopt` method brings the canvas in line with the `graphics` settings, switching to
+    /// the present mode nearest the one asked for, choosing the multisample count, and turning
+    /// the [`Fxaa`] pass on or off, as described in the [`Gpu`] docs.
+    pub fn adopt(&mut self, graphics: &Graphics) {
+        let present_mode = graphics.present_mode().choose(&self.modes);
+        if present_mode != self.config.present_mode {
+            tracing::trace!("Present mode changed to {present_mode:?}.");
+            self.config.present_mode = present_mode;
+            self.reconfigure();
+        }
+        let samples = (*graphics.samples()).max(1);
+        let fallback = !self.samples.contains(&samples);
+        if fallback {
+            tracing::warn!(
+                "Multisampling {samples}x not supported, offered {:?}, using FXAA.",
+                self.samples
+            );
+        }
+        self.sample_count = if fallback { 1 } else { samples };
+        match (*graphics.fxaa() || fallback, &self.fxaa) {
+            (true, None) => {
+                let fxaa = Fxaa::new(self.device.clone(), self.queue.clone(), &self.config);
+                self.fxaa = Some(fxaa);
+                self.stale = true;
+            }
+            (false, Some(_)) => {
+                self.fxaa = None;
+                self.stale = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// The `shade` method replaces the effects of the canvas with one built from each name and
+    /// shader text in `sources`, as described in the [`Gpu`] docs.  A shader that fails to build
+    /// is left out, with a warning from [`Effect::new`].  With `animate`, the canvas asks for a
+    /// steady stream of frames while it has effects.
+    pub fn shade(&mut self, sources: &[(String, String)], animate: bool) {
+        self.effects = sources
+            .iter()
+            .filter_map(|(name, source)| {
+                let (device, queue) = (self.device.clone(), self.queue.clone());
+                Effect::new(device, queue, &self.config, name, source).ok()
+            })
+            .collect();
+        self.animate = animate && !self.effects.is_empty();
+        self.stale = true;
+        tracing::trace!("{} effects on the canvas.", self.effects.len());
+    }
+
+    /// The `draw` method calls `paint` with the view to draw the map into, and finishes the
+    /// frame onto `target`, the window texture.  With `damage` short of [`Damage::Map`], and a
+    /// kept picture that is not stale, we skip `paint` and show the kept picture instead, as
+    /// described in the [`Gpu`] docs.  Without an [`Effect`], the [`Fxaa`] pass or a `retained`
+    /// texture, `paint` draws straight onto `target`.  Returns `true` if `paint` was called.
+    ///
+    /// Will [`crate::Blame::Gpu`] if the card rejects any of the passes, as caught by
+    /// [`Fault::guard`].
+    pub fn draw(
+        &mut self,
+        target: &wgpu::Texture,
+        damage: Damage,
+        paint: impl FnOnce(&wgpu::TextureView),
+    ) -> Arrive<bool> {
+        let device = self.device.clone();
+        Fault::guard(&device, || self.compose(target, damage, paint))
+    }
+
+    /// The `ring` method draws a focus ring `width` pixels wide around the edge of `target`, the
+    /// window texture, in `color`, as described in [`crate::Focus`].  The color comes from the
+    /// [`crate::Palette`] of the window, in sRGB with alpha.  We write the four
+    /// sides straight into the texture, so a surface we cannot copy onto, or in a format we do
+    /// not know how to write, goes without a ring.
+    ///
+    /// Will [`crate::Blame::Gpu`] if the card rejects the writes, as caught by [`Fault::guard`].
+    pub fn ring(&self, target: &wgpu::Texture, width: u32, color: [u8; 4]) -> Arrive<()> {
+        if !target.usage().contains(wgpu::TextureUsages::COPY_DST) {
+            return Ok(());
+        }
+        let Some(pixel) = Self::pixel(target.format(), color) else {
+            tracing::trace!("No focus ring on a surface in {:?}.", target.format());
+            return Ok(());
+        };
+        let size = target.size();
+        let width = width.min(size.width / 2).min(size.height / 2).max(1);
+        let sides = [
+            (0, 0, size.width, width),
+            (0, size.height.saturating_sub(width), size.width, width),
+            (0, width, width, size.height.saturating_sub(width * 2)),
+            (
+                size.width.saturating_sub(width),
+                width,
+                width,
+                size.height.saturating_sub(width * 2),
+            ),
+        ];
+        self.fill(target, &sides, &pixel)
+    }
+
+    /// The `trace` method draws `paths` over the map on `target`, the window texture, in
+    /// `color`, for the annotations of [`crate::Ink`].  Each point of a path holds its place in
+    /// the window and the width of the line there, in physical pixels, and a path of one point
+    /// draws a dot.  Like [`Canvas::ring`], we write the pixels straight into the texture, one
+    /// run of a row at a time, so the lines come out square and without smoothing, and a surface
+    /// we cannot copy onto goes without them.
+    ///
+    /// Will [`crate::Blame::Gpu`] if the card rejects the writes, as caught by [`Fault::guard`].
+    pub fn trace(
+        &self,
+        target: &wgpu::Texture,
+        paths: &[Vec<(f64, f64, f64)>],
+        color: [u8; 4],
+    ) -> Arrive<()> {
+        if paths.is_empty() || !target.usage().contains(wgpu::TextureUsages::COPY_DST) {
+            return Ok(());
+        }
+        let Some(pixel) = Self::pixel(target.format(), color) else {
+            tracing::trace!("No ink on a surface in {:?}.", target.format());
+            return Ok(());
+        };
+        let size = target.size();
+        let (width, height) = (f64::from(size.width), f64::from(size.height));
+        let mut rows = BTreeMap::<u32, Vec<(u32, u32)>>::new();
+        let mut stamp = |x: f64, y: f64, side: f64| {
+            let half = side.max(1.0) / 2.0;
+            let (left, right) = ((x - half).round().max(0.0), (x + half).round().min(width));
+            let (top, bottom) = ((y - half).round().max(0.0), (y + half).round().min(height));
+            if !(left < right && top < bottom) {
+                return;
+            }
+            for row in top as u32..bottom as u32 {
+                rows.entry(row)
+                    .or_default()
+                    .push((left as u32, right as u32));
+            }
+        };
+        for path in paths {
+            if let [(x, y, side)] = path.as_slice() {
+                stamp(*x, *y, *side);
+            }
+            for pair in path.windows(2) {
+                let ((x0, y0, side0), (x1, y1, side1)) = (pair[0], pair[1]);
+                let margin = side0.max(side1);
+                // A line of a parcel zoomed in close runs far off the window, so we only step
+                // along the part of it inside.
+                let Some((start, end)) = Self::clip((x0, y0), (x1, y1), width, height, margin)
+                else {
+                    continue;
+                };
+                let steps = ((x1 - x0).abs().max((y1 - y0).abs()) * (end - start))
+                    .ceil()
+                    .max(1.0);
+                for step in 0..=steps as u32 {
+                    let t = start + (end - start) * f64::from(step) / steps;
+                    stamp(
+                        x0 + (x1 - x0) * t,
+                        y0 + (y1 - y0) * t,
+                        side0 + (side1 - side0) * t,
+                    );
+                }
+            }
+        }
+        let mut runs = Vec::new();
+        for (row, mut spans) in rows {
+            spans.sort_unstable();
+            let mut spans = spans.into_iter();
+            let Some(mut run) = spans.next() else {
+                continue;
+            };
+            for (left, right) in spans {
+                match left <= run.1 {
+                    true => run.1 = run.1.max(right),
+                    false => {
+                        runs.push((run.0, row, run.1 - run.0, 1));
+                        run = (left, right);
+                    }
+                }
+            }
+            runs.push((run.0, row, run.1 - run.0, 1));
+        }
+        self.fill(target, &runs, &pixel)
+    }
+
+    /// The `paste` method copies `picture` onto `target`, the window texture, with its top left
+    /// corner at `x` and `y`, for a [`crate::Panel`].  Like [`Canvas::ring`], we write the pixels
+    /// straight into the texture, without blending, so the picture should be opaque, and the part
+    /// of it past the edge of the window is left off.  A surface we cannot copy onto, or in a
+    /// format we do not know how to write, goes without it.
+    ///
+    /// Will [`crate::Blame::Gpu`] if the card rejects the write, as caught by [`Fault::guard`].
+    pub fn paste(
+        &self,
+        target: &wgpu::Texture,
+        picture: &image::RgbaImage,
+        x: u32,
+        y: u32,
+    ) -> Arrive<()> {
+        if !target.usage().contains(wgpu::TextureUsages::COPY_DST) {
+            return Ok(());
+        }
+        let format = target.format();
+        let Some(probe) = Self::pixel(format, [0, 0, 0, 0]) else {
+            tracing::trace!("No panel on a surface in {format:?}.");
+            return Ok(());
+        };
+        let size = target.size();
+        let across = picture.width().min(size.width.saturating_sub(x));
+        let down = picture.height().min(size.height.saturating_sub(y));
+        if across == 0 || down == 0 {
+            return Ok(());
+        }
+        let data = (0..down)
+            .flat_map(|row| (0..across).map(move |column| (column, row)))
+            .flat_map(|(column, row)| {
+                Self::pixel(format, picture.get_pixel(column, row).0).unwrap_or_default()
+            })
+            .collect::<Vec<u8>>();
+        Fault::guard(&self.device, || {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: target,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(across * probe.len() as u32),
+                    rows_per_image: Some(down),
+                },
+                wgpu::Extent3d {
+                    width: across,
+                    height: down,
+                    depth_or_array_layers: 1,
+                },
+            );
+            // Writes wait for the next submission, and the frame presents before there is one.
+            self.queue.submit(std::iter::empty());
+        })
+    }
+
+    /// The `clip` method returns the part of the line from `from` to `to` that lies within
+    /// `margin` pixels of a window `width` by `height`, as the fractions of the way along the
+    /// line where the part starts and ends, or [`None`] if the line misses the window, or a
+    /// position is not a number.
+    fn clip(
+        from: (f64, f64),
+        to: (f64, f64),
+        width: f64,
+        height: f64,
+        margin: f64,
+    ) -> Option<(f64, f64)> {
+        let (mut start, mut end) = (0.0_f64, 1.0_f64);
+        let edges = [
+            (from.0 + margin, to.0 - from.0),
+            (width + margin - from.0, from.0 - to.0),
+            (from.1 + margin, to.1 - from.1),
+            (height + margin - from.1, from.1 - to.1),
+        ];
+        for (room, step) in edges {
+            if !(room.is_finite() && step.is_finite()) {
+                return None;
+            }
+            // The line runs alongside this edge, inside it or out.
+            if step == 0.0 {
+                if room < 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let t = -room / step;
+            match step > 0.0 {
+                true => start = start.max(t),
+                false => end = end.min(t),
+            }
+        }
+        (start <= end).then_some((start, end))
+    }
+
+    /// The `fill` method writes `pixel` over each box of `boxes` on `target`, given by the left
+    /// and top edges, the width and the height, in pixels.
+    ///
+    /// Will [`crate::Blame::Gpu`] if the card rejects the writes, as caught by [`Fault::guard`].
+    fn fill(
+        &self,
+        target: &wgpu::Texture,
+        boxes: &[(u32, u32, u32, u32)],
+        pixel: &[u8],
+    ) -> Arrive<()> {
+        Fault::guard(&self.device, || {
+            for &(x, y, across, down) in boxes {
+                if across == 0 || down == 0 {
+                    continue;
+                }
+                let data = pixel.repeat((across * down) as usize);
+                self.queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture: target,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d { x, y, z: 0 },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    &data,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(across * pixel.len() as u32),
+                        rows_per_image: Some(down),
+                    },
+                    wgpu::Extent3d {
+                        width: across,
+                        height: down,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+            // Writes wait for the next submission, and the frame presents before there is one.
+            self.queue.submit(std::iter::empty());
+        })
+    }
+
+    /// The `pixel` method returns the bytes of one pixel of `color`, in sRGB with alpha, in
+    /// `format`, or [`None`] for a format we do not write.  A surface in half floats holds
+    /// linear light, as described in the [`Gpu`] docs.
+    fn pixel(format: wgpu::TextureFormat, color: [u8; 4]) -> Option<Vec<u8>> {
+        let [red, green, blue, alpha] = color;
+        match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                Some(vec![red, green, blue, alpha])
+            }
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                Some(vec![blue, green, red, alpha])
+            }
+            wgpu::TextureFormat::Rgba16Float => {
+                let linear = |value: u8| {
+                    let value = f32::from(value) / 255.0;
+                    match value <= 0.04045 {
+                        true => value / 12.92,
+                        false => ((value + 0.055) / 1.055).powf(2.4),
+                    }
+                };
+                let channels = [
+                    linear(red),
+                    linear(green),
+                    linear(blue),
+                    f32::from(alpha) / 255.0,
+                ];
+                Some(
+                    channels
+                        .into_iter()
+                        .flat_map(|value| Self::half(value).to_le_bytes())
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// The `half` method converts `value`, between zero and one, to a half float, dropping the
+    /// bits of precision a half float does not hold.
+    fn half(value: f32) -> u16 {
+        let bits = value.to_bits();
+        let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+        if value <= 0.0 || exponent <= 0 {
+            return 0;
+        }
+        ((exponent as u16) << 10) | ((bits >> 13) & 0x3ff) as u16
+    }
+
+    /// The `compose` method does the work of [`Canvas::draw`], outside the error scopes.
+    fn compose(
+        &mut self,
+        target: &wgpu::Texture,
+        damage: Damage,
+        paint: impl FnOnce(&wgpu::TextureView),
+    ) -> bool {
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let fresh = damage == Damage::Map || self.stale;
+        self.stale = false;
+        if let Some(first) = self.effects.first() {
+            if fresh {
+                paint(first.view());
+            }
+            let elapsed = self.opened.elapsed();
+            for (index, effect) in self.effects.iter().enumerate() {
+                let next = match (self.effects.get(index + 1), &self.fxaa) {
+                    (Some(next), _) => next.view(),
+                    (None, Some(fxaa)) => fxaa.view(),
+                    (None, None) => &view,
+                };
+                effect.apply(next, elapsed);
+            }
+            if let Some(fxaa) = &self.fxaa {
+                fxaa.apply(&view);
+            }
+            return fresh;
+        }
+        match (&self.fxaa, &self.retained) {
+            (Some(fxaa), _) => {
+                if fresh {
+                    paint(fxaa.view());
+                }
+                fxaa.apply(&view);
+                fresh
+            }
+            (None, Some(retained)) => {
+                if fresh {
+                    paint(&retained.create_view(&wgpu::TextureViewDescriptor::default()));
+                }
+                let mut encoder =
+                    self.device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("retained"),
+                        });
+                encoder.copy_texture_to_texture(
+                    retained.as_image_copy(),
+                    target.as_image_copy(),
+                    retained.size(),
+                );
+                self.queue.submit(Some(encoder.finish()));
+                fresh
+            }
+            (None, None) => {
+                paint(&view);
+                true
+            }
+        }
+    }
+}
+
+/// The `Fault` struct describes a command the graphics card rejected, for
+/// [`crate::Blame::Gpu`], as described in the [`Gpu`] docs.
+///
+/// * The `filter` field holds the kind of fault: out of memory, failed validation, or an
+///   internal error of the driver.
+/// * The `description` field holds what [`wgpu`] said about it.
+#[derive(Debug, Clone, derive_new::new, derive_getters::Getters, derive_more::Error)]
+pub struct Fault {
+    filter: wgpu::ErrorFilter,
+    description: String,
+}
+
+impl Fault {
+    /// The `guard` method runs `work` inside error scopes on `device`, and returns what it
+    /// returns, unless the card rejected any of the commands it sent.  The scopes resolve at once
+    /// on native platforms, so we wait for them in place with [`crate::settle`].  In the browser
+    /// a scope the card has not answered yet is let go, and a fault in it goes unreported.
+    ///
+    /// Will [`crate::Blame::Gpu`] with the first fault, if any, with validation ahead of memory.
+    pub fn guard<T>(device: &wgpu::Device, work: impl FnOnce() -> T) -> Arrive<T> {
+        device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = work();
+        // Scopes pop in the reverse order they were pushed.
+        let invalid = crate::settle(device.pop_error_scope()).flatten();
+        let memory = crate::settle(device.pop_error_scope()).flatten();
+        match invalid.or(memory) {
+            Some(e) => Err(Self::from(e).into()),
+            None => Ok(result),
+        }
+    }
+}
+
+impl From<wgpu::Error> for Fault {
+    fn from(error: wgpu::Error) -> Self {
+        let filter = match &error {
+            wgpu::Error::OutOfMemory { .. } => wgpu::ErrorFilter::OutOfMemory,
+            wgpu::Error::Validation { .. } => wgpu::ErrorFilter::Validation,
+            wgpu::Error::Internal { .. } => wgpu::ErrorFilter::Internal,
+        };
+        Self::new(filter, error.to_string())
+    }
+}
+
+/// Prints the kind of fault and the description, as in `validation: In a RenderPass ...`.
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.filter {
+            wgpu::ErrorFilter::OutOfMemory => "out of memory",
+            wgpu::ErrorFilter::Validation => "validation",
+            wgpu::ErrorFilter::Internal => "internal",
+        };
+        write!(f, "{kind}: {}", self.description)
+    }
+}
+
+/// The `FAULT_LIMIT` constant caps the number of [`Fault`] types the device keeps between calls
+/// to [`Gpu::faults`], so a command rejected every frame does not fill memory.
+pub const FAULT_LIMIT: usize = 16;
+
+/// The `SOFTWARE_TITLE` constant holds the note added to the title of a map window drawn in
+/// software, as described in the [`Gpu`] docs.
+pub const SOFTWARE_TITLE: &str = " (software rendering, slow)";
+
+/// The `SAMPLE_COUNTS` constant holds the multisample counts we ask the card about.  No card
+/// goes past 16.
+pub const SAMPLE_COUNTS: [u32; 5] = [1, 2, 4, 8, 16];
+
+/// The `Gamut` enum describes the range of colors a window draws in, as described in the
+/// [`Gpu`] docs.
+///
+/// * The `Srgb` variant draws in sRGB, which every display can show, and matches exported
+///   pictures.  This is the default.
+/// * The `Wide` variant draws in extended sRGB with half floats, for wide-gamut displays.  Falls
+///   back to `Srgb`.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+)]
+pub enum Gamut {
+    /// The `Srgb` variant indicates the standard sRGB gamut.
+    #[default]
+    Srgb,
+    /// The `Wide` variant indicates extended sRGB for wide-gamut displays.
+    Wide,
+}
+
+/// Reads the name of the gamut whatever its case, as described in [`crate::choice`].
+impl<'de> serde::Deserialize<'de> for Gamut {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::choice(deserializer)
+    }
+}
+
+impl Gamut {
+    /// The `choose` method returns the surface format for the gamut among `offered`, the formats
+    /// of a surface, or [`None`] if the surface offers no format at all.  The wide gamut takes
+    /// [`WIDE_FORMAT`] if offered.  Otherwise an sRGB format comes first, then any format, with a
+    /// warning that the colors will be off.
+    pub fn choose(&self, offered: &[wgpu::TextureFormat]) -> Option<wgpu::TextureFormat> {
+        if *self == Self::Wide {
+            match offered.contains(&WIDE_FORMAT) {
+                true => return Some(WIDE_FORMAT),
+                false => tracing::warn!("No wide-gamut format offered, using sRGB."),
+            }
+        }
+        let srgb = offered.iter().copied().find(wgpu::TextureFormat::is_srgb);
+        srgb.or_else(|| {
+            let format = offered.first().copied();
+            if let Some(format) = format {
+                tracing::warn!("No sRGB format offered, using {format:?}, colors will be dark.");
+            }
+            format
+        })
+    }
+}
+
+/// The `WIDE_FORMAT` constant holds the surface format of [`Gamut::Wide`], half floats in linear
+/// light, which macOS and Windows read as extended sRGB.
+pub const WIDE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// The `PresentMode` enum describes when a finished frame reaches the screen, as described in
+/// the [`Gpu`] docs.
+///
+/// * The `Fifo` variant waits for the vertical blank, queueing frames drawn in between.  It never
+///   tears, every card offers it, and it adds up to a frame or two of latency.  This is the
+///   default.
+/// * The `Mailbox` variant also waits for the vertical blank, but a newer frame replaces one
+///   waiting, so the screen shows the latest frame without tearing.  Falls back to `Fifo`.
+/// * The `Immediate` variant shows each frame as soon as it is drawn, with the least latency and
+///   some tearing.  Falls back to `Mailbox`, then `Fifo`.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+)]
+pub enum PresentMode {
+    /// The `Fifo` variant indicates frames wait for the vertical blank, in order.
+    #[default]
+    Fifo,
+    /// The `Mailbox` variant indicates the latest frame waits for the vertical blank.
+    Mailbox,
+    /// The `Immediate` variant indicates frames show at once.
+    Immediate,
+}
+
+/// Reads the name of the present mode whatever its case, as described in [`crate::choice`].
+impl<'de> serde::Deserialize<'de> for PresentMode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::choice(deserializer)
+    }
+}
+
+impl PresentMode {
+    /// The `choose` method returns the first of `self` and its fallbacks found among `offered`,
+    /// the present modes of a surface.  Every surface offers [`wgpu::PresentMode::Fifo`], so we
+    /// settle on it when nothing better is offered, and say so in the log.
+    pub fn choose(&self, offered: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+        let preferences: &[wgpu::PresentMode] = match self {
+            Self::Fifo => &[wgpu::PresentMode::Fifo],
+            Self::Mailbox => &[wgpu::PresentMode::Mailbox],
+            Self::Immediate => &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox],
+        };
+        match preferences.iter().find(|mode| offered.contains(mode)) {
+            Some(mode) => *mode,
+            None => {
+                if *self != Self::Fifo {
+                    tracing::warn!("Present mode {self} not supported, using Fifo.");
+                }
+                wgpu::PresentMode::Fifo
+            }
+        }
+    }
+}
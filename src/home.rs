@@ -0,0 +1,194 @@
+use crate::{CONFIG_FILE, CONFIG_FORMATS, SECRETS_FILE};
+use std::path;
+
+/// The `home` module provides the [`Home`] struct, which knows where the app keeps its files on
+/// each platform.
+///
+/// # Finding our files with `Home`
+///
+/// The app used to read `config.toml` from the current working directory, which works fine when
+/// running from the repository with `cargo run`, and not at all when launched from a desktop
+/// shortcut, where the working directory is wherever the shortcut says.  Each platform has a
+/// proper place for config files and caches, and the [`directories`] crate knows where they are:
+///
+/// | Platform | Config                                               | Cache                                           |
+/// | -------- | ---------------------------------------------------- | ----------------------------------------------- |
+/// | Linux    | `$XDG_CONFIG_HOME/tardy` or `~/.config/tardy`        | `$XDG_CACHE_HOME/tardy` or `~/.cache/tardy`     |
+/// | macOS    | `~/Library/Application Support/gov.grantspassoregon.Tardy` | `~/Library/Caches/gov.grantspassoregon.Tardy` |
+/// | Windows  | `%APPDATA%\grantspassoregon\Tardy\config`            | `%LOCALAPPDATA%\grantspassoregon\Tardy\cache`   |
+///
+/// ## Config files
+///
+/// We read [`CONFIG_FILE`] from each of the following places that has one, listed by
+/// [`Home::layers`] from lowest to highest precedence, and [`crate::Layers`] merges them.  In
+/// place of `config.toml`, a directory may hold `config.yaml`, `config.yml` or `config.json`,
+/// which [`Home::find`] looks for in the order of [`CONFIG_FORMATS`]:
+///
+/// 1. The system config directory, for settings an administrator applies to every user:
+///    `/etc/tardy` on Linux, `/Library/Application Support/gov.grantspassoregon.Tardy` on
+///    macOS, and `%PROGRAMDATA%\grantspassoregon\Tardy` on Windows.
+/// 2. The user config directory from the table above.
+/// 3. The current working directory, as a project config, so that a checkout or a project
+///    folder can carry its own settings.
+///
+/// Changes made in the app, like new key bindings, go to the user config file from
+/// [`Home::config`], and never to the system or project file, which other people share.  If no
+/// file exists, the app runs on built-in defaults, and we write a new file to the user config
+/// directory.  A path passed with `--config` (see [`crate::Cli`])
+/// replaces all three layers, and lives in the `explicit` field.
+///
+/// The `project` field holds the [`directories::ProjectDirs`] for the app, which is [`None`] on
+/// the rare system with no home directory, in which case the working directory is all we have.
+#[derive(Debug, Clone)]
+pub struct Home {
+    explicit: Option<path::PathBuf>,
+    project: Option<directories::ProjectDirs>,
+}
+
+impl Home {
+    /// The `new` method looks up the platform directories for the app.
+    pub fn new() -> Self {
+        let project = directories::ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION);
+        if project.is_none() {
+            tracing::warn!("No home directory found, using the working directory.");
+        }
+        Self {
+            explicit: None,
+            project,
+        }
+    }
+
+    /// The `with_config` method sets the config file to `path`, skipping the search, or restores
+    /// the search if `path` is [`None`].
+    pub fn with_config(mut self, path: Option<path::PathBuf>) -> Self {
+        self.explicit = path;
+        self
+    }
+
+    /// The `layers` method lists the config files to merge, from lowest to highest precedence,
+    /// as described in the [`Home`] docs.  Some of them may not exist.
+    pub fn layers(&self) -> Vec<path::PathBuf> {
+        if let Some(explicit) = &self.explicit {
+            return vec![explicit.clone()];
+        }
+        let mut paths = Vec::new();
+        if let Some(system) = Self::system() {
+            paths.push(Self::find(&system));
+        }
+        if let Some(project) = &self.project {
+            paths.push(Self::find(project.config_dir()));
+        }
+        paths.push(Self::find(path::Path::new("")));
+        paths
+    }
+
+    /// The `find` method returns the config file in `dir`, trying each extension in
+    /// [`CONFIG_FORMATS`], or the path to [`CONFIG_FILE`] in `dir` if there is none.
+    pub fn find(dir: &path::Path) -> path::PathBuf {
+        CONFIG_FORMATS
+            .iter()
+            .map(|extension| dir.join(CONFIG_FILE).with_extension(extension))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| dir.join(CONFIG_FILE))
+    }
+
+    /// The `system` method returns the system config directory for the platform, if it has one.
+    pub fn system() -> Option<path::PathBuf> {
+        if cfg!(target_os = "windows") {
+            std::env::var_os("PROGRAMDATA").map(|data| {
+                path::PathBuf::from(data)
+                    .join(ORGANIZATION)
+                    .join(APPLICATION)
+            })
+        } else if cfg!(target_os = "macos") {
+            Some(
+                path::PathBuf::from("/Library/Application Support")
+                    .join(format!("{QUALIFIER}.{ORGANIZATION}.{APPLICATION}")),
+            )
+        } else if cfg!(unix) {
+            Some(path::PathBuf::from("/etc").join(APPLICATION.to_lowercase()))
+        } else {
+            None
+        }
+    }
+
+    /// The `config` method returns the path of the user's own config file.  This is the path set
+    /// with [`Home::with_config`] if any, or else the file in the user config directory, which is
+    /// where a new config file belongs even if it does not exist yet.  The system and project
+    /// files belong to the administrator and the checkout, so the app never writes to them.
+    pub fn config(&self) -> path::PathBuf {
+        if let Some(explicit) = &self.explicit {
+            return explicit.clone();
+        }
+        match &self.project {
+            Some(project) => Self::find(project.config_dir()),
+            None => Self::find(path::Path::new("")),
+        }
+    }
+
+    /// The `user` method returns the user config directory, or the working directory on a
+    /// system with no home directory.
+    pub fn user(&self) -> path::PathBuf {
+        match &self.project {
+            Some(project) => project.config_dir().to_path_buf(),
+            None => path::PathBuf::new(),
+        }
+    }
+
+    /// The `secrets` method returns the path of the [`crate::Secrets`] file, in the user config
+    /// directory so that it stays out of any checkout.  Without a home directory, it falls back
+    /// on the working directory.
+    pub fn secrets(&self) -> path::PathBuf {
+        match &self.project {
+            Some(project) => project.config_dir().join(SECRETS_FILE),
+            None => path::PathBuf::from(SECRETS_FILE),
+        }
+    }
+
+    /// The `cache` method returns the platform cache directory, creating it if needed.  Without
+    /// a home directory, or if the directory cannot be created, we fall back to a `.cache`
+    /// directory under the working directory.
+    pub fn cache(&self) -> path::PathBuf {
+        if let Some(project) = &self.project {
+            let cache = project.cache_dir().to_path_buf();
+            match std::fs::create_dir_all(&cache) {
+                Ok(()) => return cache,
+                Err(e) => tracing::warn!("Could not create {}: {e}", cache.display()),
+            }
+        }
+        path::PathBuf::from(".cache")
+    }
+
+    /// The `data` method returns the platform data directory, creating it if needed, for files
+    /// the app writes for itself, like the crash reports of [`crate::Crash`].  Without a home
+    /// directory, or if the directory cannot be created, we fall back to a `.data` directory
+    /// under the working directory.
+    pub fn data(&self) -> path::PathBuf {
+        if let Some(project) = &self.project {
+            let data = project.data_dir().to_path_buf();
+            match std::fs::create_dir_all(&data) {
+                Ok(()) => return data,
+                Err(e) => tracing::warn!("Could not create {}: {e}", data.display()),
+            }
+        }
+        path::PathBuf::from(".data")
+    }
+}
+
+impl Default for Home {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `QUALIFIER` constant holds the top-level domain of the organization, used by
+/// [`directories::ProjectDirs`] to name the app directories on macOS.
+pub const QUALIFIER: &str = "gov";
+
+/// The `ORGANIZATION` constant holds the name of the organization, used by
+/// [`directories::ProjectDirs`] to name the app directories on macOS and Windows.
+pub const ORGANIZATION: &str = "grantspassoregon";
+
+/// The `APPLICATION` constant holds the name of the app, used by [`directories::ProjectDirs`]
+/// to name the app directories on every platform.
+pub const APPLICATION: &str = "Tardy";
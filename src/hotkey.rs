@@ -0,0 +1,148 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::UserEvent;
+use crate::{Act, Arrive, Courier};
+#[cfg(not(target_arch = "wasm32"))]
+use global_hotkey::hotkey::HotKey;
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+/// The `hotkey` module provides the [`Hotkeys`] struct, which registers system-wide keyboard
+/// shortcuts with the operating system and translates them into [`Act`] variants.
+///
+/// # Reaching the app from anywhere with `Hotkeys`
+///
+/// The bindings in [`crate::Cmd`] only work while one of our windows has focus.  A few actions
+/// are useful from anywhere, like bringing the map back to the front while you are typing a
+/// report.  The operating system can deliver a key combination to us no matter which app has
+/// focus, if we ask nicely through the [`global_hotkey`] crate.
+///
+/// The user lists them in a `[global]` table in the config, using the same snake case act names
+/// as the key bindings:
+///
+/// ```toml
+/// [global]
+/// toggle_visible = "Ctrl+Alt+KeyB"
+/// save_snapshot = "Ctrl+Alt+KeyS"
+/// ```
+///
+/// Values use the syntax of [`HotKey`], where the key is the name of a physical key code like
+/// `KeyB` or `F5`.  The acts with an [`Act::default_global`] start out bound, as above, and an
+/// empty value unbinds one.
+///
+/// The operating system reports hotkey presses on a thread of its own, outside the [`winit`]
+/// event loop, so [`Hotkeys::load`] hands the [`global_hotkey`] crate a handler that sends each
+/// press on to the loop as a [`crate::UserEvent::Hotkey`], through a [`Courier`].  The loop
+/// sleeps until a press arrives, rather than waking to look for one.  Some platforms, notably
+/// Wayland, do not allow global hotkeys at all, in which case registration fails and we log a
+/// warning.  A web browser has no global hotkeys either, and the [`global_hotkey`] crate does not
+/// build for it, so in the browser [`Hotkeys::load`] registers nothing (see [`crate::run_web`]).
+pub struct Hotkeys {
+    #[cfg(not(target_arch = "wasm32"))]
+    manager: global_hotkey::GlobalHotKeyManager,
+    acts: HashMap<u32, Act>,
+    #[cfg(not(target_arch = "wasm32"))]
+    hotkeys: Vec<HotKey>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Hotkeys {
+    /// The `load` method reads the `[global]` table from `global` and registers each hotkey with
+    /// the operating system, then has the presses delivered to the event loop by `courier`.
+    /// Returns [`None`] if the table binds nothing or no hotkey registers successfully.
+    /// Hotkeys that fail to parse or register are skipped with a warning.
+    ///
+    /// Will [`crate::Blame::GlobalHotkey`] if the platform hotkey manager fails to start.
+    #[tracing::instrument(skip_all)]
+    pub fn load(global: &HashMap<String, String>, courier: Courier) -> Arrive<Option<Self>> {
+        if global.values().all(|value| value.trim().is_empty()) {
+            tracing::trace!("No global hotkeys configured.");
+            return Ok(None);
+        }
+        let manager = global_hotkey::GlobalHotKeyManager::new()?;
+        let mut acts = HashMap::new();
+        let mut hotkeys = Vec::new();
+        for act in Act::iter() {
+            let key = act.snake();
+            let Some(value) = global.get(&key).filter(|value| !value.trim().is_empty()) else {
+                continue;
+            };
+            let hotkey = match value.parse::<HotKey>() {
+                Ok(hotkey) => hotkey,
+                Err(e) => {
+                    tracing::warn!("Skipping global hotkey {key} = {value}: {e}");
+                    continue;
+                }
+            };
+            match manager.register(hotkey) {
+                Ok(()) => {
+                    tracing::info!("Global hotkey {value} registered for {}", act.title());
+                    acts.insert(hotkey.id(), act);
+                    hotkeys.push(hotkey);
+                }
+                Err(e) => tracing::warn!("Could not register global hotkey {value}: {e}"),
+            }
+        }
+        if acts.is_empty() {
+            return Ok(None);
+        }
+        // The handler runs on a thread of the platform, and must be shared across threads.
+        let courier = std::sync::Mutex::new(courier);
+        let handled = acts.clone();
+        global_hotkey::GlobalHotKeyEvent::set_event_handler(Some(
+            move |event: global_hotkey::GlobalHotKeyEvent| {
+                // We only act on presses, not releases.
+                if event.state() != global_hotkey::HotKeyState::Pressed {
+                    return;
+                }
+                let Some(act) = handled.get(&event.id()) else {
+                    return;
+                };
+                tracing::trace!("Global hotkey: {act}");
+                if let Ok(courier) = courier.lock() {
+                    courier.notify(UserEvent::Hotkey(act.clone()));
+                }
+            },
+        ));
+        Ok(Some(Self {
+            manager,
+            acts,
+            hotkeys,
+        }))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Hotkeys {
+    /// The `load` method registers nothing and returns [`None`], since the browser has no global
+    /// hotkeys.  The defaults of [`Act::default_global`] fill the `[global]` table everywhere, so
+    /// we only say so at the trace level.
+    pub fn load(global: &HashMap<String, String>, _courier: Courier) -> Arrive<Option<Self>> {
+        if !global.is_empty() {
+            tracing::trace!("Global hotkeys are not available in the web browser.");
+        }
+        Ok(None)
+    }
+}
+
+/// The [`global_hotkey::GlobalHotKeyManager`] does not implement [`std::fmt::Debug`], so we
+/// print the registered acts instead.
+impl std::fmt::Debug for Hotkeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hotkeys").field("acts", &self.acts).finish()
+    }
+}
+
+/// Unregisters the hotkeys with the operating system, so they do not outlive the app, and drops
+/// the handler, which holds a [`Courier`] to the event loop.
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for Hotkeys {
+    fn drop(&mut self) {
+        tracing::trace!("Unregistering {} global hotkeys.", self.hotkeys.len());
+        global_hotkey::GlobalHotKeyEvent::set_event_handler(
+            None::<fn(global_hotkey::GlobalHotKeyEvent)>,
+        );
+        if let Err(e) = self.manager.unregister_all(&self.hotkeys) {
+            tracing::warn!("Could not unregister global hotkeys: {e}");
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{Sketch, Viewport};
+use std::collections::HashMap;
+use web_time as time;
+use winit::{dpi, event};
+
+/// The `ink` module provides the [`Ink`] struct, which records pressure-sensitive stylus input
+/// as [`Scribble`] annotations.
+///
+/// # Drawing on the map with `Ink`
+///
+/// Staff in the field carry Surface-style tablets with a pen, and they want to circle things on
+/// the map.  The `Ink` struct is the annotation layer of a [`crate::Lens`].  While the `active`
+/// flag is on (see [`crate::Act::ToggleInk`]), pen contacts draw [`Scribble`] types instead of
+/// moving the map.  Each point in a scribble records the pressure of the pen, so that the line can
+/// be drawn thicker where the user pressed harder.  Finished scribbles collect in the
+/// `scribbles` field, and the scribble under the pen lives in the `drawing` field, keyed by touch
+/// id.
+///
+/// ## Telling a pen from a palm
+///
+/// When you write on a tablet, the side of your hand rests on the screen, and the screen reports
+/// it as a touch.  The [`winit`] touch events do not say whether a touch came from a pen or a
+/// finger, so we go by the pressure.  A pen reports how hard it presses, as an
+/// [`event::Force`], while most touch screens report no force for a finger.  An iPad pen reports
+/// [`event::Force::Calibrated`], and a Surface pen on Windows [`event::Force::Normalized`], so
+/// any force will do.  Once a pen has touched the screen, we ignore finger touches until
+/// [`PALM_REJECTION`] has passed since the pen last moved.  Palms land a moment before or after
+/// the pen, and this keeps them from panning the map out from under the drawing.
+///
+/// ## Pasted geometry
+///
+/// Geometry pasted from the clipboard with [`crate::Act::PasteGeometry`] joins the layer as a
+/// [`Sketch`], in the `sketches` field.  A scribble lies where the pen touched the window, but a
+/// sketch lies on the ground, in longitude and latitude, and stays put as the map moves.  The
+/// `marks` field records the [`Mark`] of each annotation in the order they arrived, so that
+/// [`Ink::undo`] takes off the latest, whichever kind it is.
+///
+/// The parcel found by a lookup (see [`crate::Parcels`]) is a sketch too, but a selection
+/// rather than a note, so it goes in the `selection` field through [`Ink::select`].  A new
+/// lookup replaces the selection, and [`Ink::undo`] takes it off like any other annotation.
+///
+/// ## Drawing the annotations
+///
+/// The [`crate::Lens`] draws the annotations over the map after each frame, with
+/// [`crate::Canvas::trace`], in the colors of its [`crate::Palette`].  [`Ink::trails`] gives
+/// the scribbles, in the window already, each dab as wide as the pen pressed, and
+/// [`Ink::outlines`] and [`Ink::selected`] place the sketches and the selection on the window
+/// as the map shows them, with a [`Viewport`], so they move with the map.  The debug overlay
+/// counts a draw for each annotation.
+#[derive(Debug, Default, Clone, derive_getters::Getters, derive_setters::Setters)]
+#[setters(prefix = "with_", borrow_self)]
+pub struct Ink {
+    active: bool,
+    #[setters(skip)]
+    drawing: HashMap<u64, Scribble>,
+    #[setters(skip)]
+    pen_seen: Option<time::Instant>,
+    #[setters(skip)]
+    scribbles: Vec<Scribble>,
+    #[setters(skip)]
+    sketches: Vec<Sketch>,
+    #[setters(skip)]
+    selection: Vec<Sketch>,
+    #[setters(skip)]
+    marks: Vec<Mark>,
+}
+
+impl Ink {
+    /// The `is_pen` method returns `true` if `touch` came from a stylus rather than a finger, by
+    /// checking for a force of any kind, as described in the [`Ink`] docs.
+    pub fn is_pen(touch: &event::Touch) -> bool {
+        touch.force.is_some()
+    }
+
+    /// The `touch` method offers a touch event to the ink layer, and returns `true` if the ink
+    /// layer consumed it.  Pen touches draw while the ink layer is active.  Finger touches are
+    /// consumed, and so ignored, while a pen is in use, for palm rejection.  Any other touch
+    /// returns `false`, leaving it free for the gesture recognizer.
+    #[tracing::instrument(skip_all)]
+    pub fn touch(&mut self, touch: &event::Touch) -> bool {
+        if !self.active {
+            return false;
+        }
+        if Self::is_pen(touch) {
+            self.pen_seen = Some(time::Instant::now());
+            self.draw(touch);
+            true
+        } else if self
+            .pen_seen
+            .is_some_and(|seen| seen.elapsed() < PALM_REJECTION)
+        {
+            tracing::trace!("Palm rejected: touch {}", touch.id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The `draw` method adds the pen position to the scribble for the touch, finishing the
+    /// scribble when the pen lifts.
+    fn draw(&mut self, touch: &event::Touch) {
+        let pressure = touch.force.map(|force| force.normalized()).unwrap_or(1.0);
+        let point = Dab::new(touch.location, pressure);
+        match touch.phase {
+            event::TouchPhase::Started => {
+                self.drawing.insert(touch.id, Scribble::new(vec![point]));
+            }
+            event::TouchPhase::Moved => {
+                if let Some(scribble) = self.drawing.get_mut(&touch.id) {
+                    scribble.push(point);
+                }
+            }
+            event::TouchPhase::Ended => {
+                if let Some(mut scribble) = self.drawing.remove(&touch.id) {
+                    scribble.push(point);
+                    tracing::trace!("Scribble finished with {} points.", scribble.len());
+                    self.scribbles.push(scribble);
+                    self.marks.push(Mark::Scribble);
+                }
+            }
+            event::TouchPhase::Cancelled => {
+                self.drawing.remove(&touch.id);
+            }
+        }
+    }
+
+    /// The `sketch` method adds `sketches` to the layer, as described in the [`Ink`] docs.
+    pub fn sketch(&mut self, sketches: Vec<Sketch>) {
+        self.marks.extend(sketches.iter().map(|_| Mark::Sketch));
+        self.sketches.extend(sketches);
+    }
+
+    /// The `trails` method returns the scribbles, finished and under the pen, as paths for
+    /// [`crate::Canvas::trace`].  A dab pressed all the way down is half again as wide as
+    /// `width`, in physical pixels, and a light touch half as wide.
+    pub fn trails(&self, width: f64) -> Vec<Vec<(f64, f64, f64)>> {
+        self.scribbles
+            .iter()
+            .chain(self.drawing.values())
+            .map(|scribble| {
+                scribble
+                    .iter()
+                    .map(|dab| {
+                        let side = width * (0.5 + dab.pressure.clamp(0.0, 1.0));
+                        (dab.position.x, dab.position.y, side)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The `outlines` method returns the sketches as paths for [`crate::Canvas::trace`], placed
+    /// on the window by `viewport`, with lines `width` physical pixels wide.  A point draws as a
+    /// dot [`INK_DOT`] times as wide, so it stands out from a line.
+    pub fn outlines(&self, viewport: &Viewport, width: f64) -> Vec<Vec<(f64, f64, f64)>> {
+        Self::place(&self.sketches, viewport, width)
+    }
+
+    /// The `selected` method returns the selection as paths for [`crate::Canvas::trace`], as
+    /// [`Ink::outlines`] does for the sketches.
+    pub fn selected(&self, viewport: &Viewport, width: f64) -> Vec<Vec<(f64, f64, f64)>> {
+        Self::place(&self.selection, viewport, width)
+    }
+
+    /// The `place` method returns `sketches` as paths, as described in [`Ink::outlines`].
+    fn place(sketches: &[Sketch], viewport: &Viewport, width: f64) -> Vec<Vec<(f64, f64, f64)>> {
+        sketches
+            .iter()
+            .flat_map(Sketch::paths)
+            .map(|path| {
+                let side = match path.len() {
+                    1 => width * INK_DOT,
+                    _ => width,
+                };
+                path.into_iter()
+                    .map(|(longitude, latitude)| {
+                        let (x, y) = viewport.place(longitude, latitude);
+                        (x, y, side)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The `select` method replaces the selection with `sketches`, as described in the [`Ink`]
+    /// docs.
+    pub fn select(&mut self, sketches: Vec<Sketch>) {
+        self.marks.retain(|mark| *mark != Mark::Selection);
+        self.marks.push(Mark::Selection);
+        self.selection = sketches;
+    }
+
+    /// The `undo` method removes the most recent finished scribble or sketch, or the selection,
+    /// returning its [`Mark`].
+    pub fn undo(&mut self) -> Option<Mark> {
+        let mark = self.marks.pop()?;
+        match mark {
+            Mark::Scribble => self.scribbles.pop().map(|_| mark),
+            Mark::Sketch => self.sketches.pop().map(|_| mark),
+            Mark::Selection => {
+                self.selection.clear();
+                Some(mark)
+            }
+        }
+    }
+
+    /// The `clear` method removes all scribbles and sketches, and the selection.
+    pub fn clear(&mut self) {
+        self.drawing.clear();
+        self.scribbles.clear();
+        self.sketches.clear();
+        self.selection.clear();
+        self.marks.clear();
+    }
+}
+
+/// The `Scribble` struct holds the points of a single pen stroke, from the moment the pen touches
+/// the screen until it lifts.  We use [`derive_more::Deref`] and [`derive_more::DerefMut`] to
+/// reach the methods of the underlying vector.
+#[derive(
+    Debug, Default, Clone, PartialEq, derive_new::new, derive_more::Deref, derive_more::DerefMut,
+)]
+pub struct Scribble(Vec<Dab>);
+
+/// The `Mark` enum names the kind of an annotation in the [`Ink`] layer.
+///
+/// * The `Scribble` variant holds a [`Scribble`] drawn with the pen.
+/// * The `Sketch` variant holds a [`Sketch`] pasted from the clipboard.
+/// * The `Selection` variant holds the selection from a lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// The `Scribble` variant indicates a pen stroke.
+    Scribble,
+    /// The `Sketch` variant indicates pasted geometry.
+    Sketch,
+    /// The `Selection` variant indicates the result of a lookup.
+    Selection,
+}
+
+/// The `Dab` struct is a single point in a [`Scribble`].
+///
+/// * The `position` field holds the position of the pen in the window.
+/// * The `pressure` field holds the pressure of the pen, from zero to one.  Pens that do not
+///   report pressure get a pressure of one.
+#[derive(Debug, Clone, Copy, PartialEq, derive_new::new, derive_getters::Getters)]
+pub struct Dab {
+    position: dpi::PhysicalPosition<f64>,
+    pressure: f64,
+}
+
+/// The `PALM_REJECTION` constant sets how long after the pen last moved we keep ignoring finger
+/// touches.
+pub const PALM_REJECTION: time::Duration = time::Duration::from_millis(500);
+
+/// The `INK_COLOR` constant holds the color of the annotations drawn over the map in the
+/// standard [`crate::Palette`], in sRGB with alpha.  Crimson reads against the greens and blues
+/// of a base map, and apart from the amber of the focus ring.
+pub const INK_COLOR: [u8; 4] = [220, 20, 60, 255];
+
+/// The `INK_WIDTH` constant holds the width of the lines of annotations in the standard
+/// [`crate::Palette`], in logical pixels.
+pub const INK_WIDTH: f64 = 2.0;
+
+/// The `INK_DOT` constant holds how many times wider than a line a pasted point draws.
+pub const INK_DOT: f64 = 4.0;
+
+/// The `SELECTION_COLOR` constant holds the color of the selection drawn over the map in the
+/// standard [`crate::Palette`], in sRGB with alpha, a blue apart from the crimson of the ink.
+pub const SELECTION_COLOR: [u8; 4] = [0, 120, 255, 255];
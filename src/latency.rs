@@ -0,0 +1,182 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::span;
+use tracing_subscriber::registry::LookupSpan;
+use web_time as time;
+
+/// The `latency` module provides the [`Latency`] struct, which times tile downloads and API
+/// requests, and the [`Fetch`] enum, which tells them apart.
+///
+/// # Telling slow servers from slow drawing with `Latency`
+///
+/// When the map fills in slowly, the frame rate in the [`crate::Overlay`] tells us whether
+/// drawing is to blame, but not whether the tile server or the BEA API is dragging its feet.
+/// The `Latency` struct is a [`tracing_subscriber::Layer`] that [`crate::trace_init`] installs
+/// next to the usual output.  It watches for spans named [`TILE_SPAN`], [`REQUEST_SPAN`] or
+/// [`DOWNLOAD_SPAN`], and when one closes, it keeps the time the span was open as a sample for
+/// the matching [`Fetch`].
+/// Timing a request is then a matter of naming its span:
+///
+/// ```ignore
+/// #[tracing::instrument(name = "request", skip_all)]
+/// async fn send(endpoint: &str, payload: &Payload) -> Arrive<()> { ... }
+/// ```
+///
+/// The overlay reads the median and 95th percentile of the last [`LATENCY_SAMPLES`] samples of
+/// each kind from [`Latency::percentile`].  A high median means the server is slow, and a
+/// median close to the frame time with a long tail means a few requests are stuck.
+///
+/// Spans only reach the layer if the log filter lets them through, so spans from other crates
+/// need a filter like `galileo=info`.  The tiles of the map load through the
+/// [`crate::Fetcher`], and the tiles the prefetcher of [`crate::Agents`] fetches, which both
+/// open a span named [`TILE_SPAN`] for each tile from the server.
+///
+/// There is one collector for the whole app, because there is one subscriber, and
+/// [`Latency::global`] returns a handle to it.  Handles are cheap to clone, and all of them share
+/// the same samples.
+///
+/// * The `samples` field holds the most recent durations of each kind of [`Fetch`].
+#[derive(Debug, Clone, Default)]
+pub struct Latency {
+    samples: Arc<Mutex<HashMap<Fetch, VecDeque<time::Duration>>>>,
+}
+
+impl Latency {
+    /// The `global` method returns a handle to the collector shared by the app.
+    pub fn global() -> Self {
+        static LATENCY: OnceLock<Latency> = OnceLock::new();
+        LATENCY.get_or_init(Latency::default).clone()
+    }
+
+    /// The `record` method keeps `duration` as a sample for `fetch`, dropping the oldest sample
+    /// once there are [`LATENCY_SAMPLES`] of them.
+    pub fn record(&self, fetch: Fetch, duration: time::Duration) {
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        let samples = samples.entry(fetch).or_default();
+        if samples.len() == LATENCY_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration);
+    }
+
+    /// The `percentile` method returns the duration below which `percent` of the samples for
+    /// `fetch` fall, like `50.0` for the median, or [`None`] if there are no samples yet.
+    pub fn percentile(&self, fetch: Fetch, percent: f64) -> Option<time::Duration> {
+        let samples = self.samples.lock().ok()?;
+        let mut sorted = samples.get(&fetch)?.iter().copied().collect::<Vec<_>>();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort();
+        let rank = (percent / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+        sorted.get(rank.min(sorted.len() - 1)).copied()
+    }
+
+    /// The `summary` method describes the median and 95th percentile for `fetch`, like
+    /// `tiles p50 120ms p95 480ms`, or says there are no samples.
+    pub fn summary(&self, fetch: Fetch) -> String {
+        match (self.percentile(fetch, 50.0), self.percentile(fetch, 95.0)) {
+            (Some(p50), Some(p95)) => format!(
+                "{fetch} p50 {}ms p95 {}ms",
+                p50.as_millis(),
+                p95.as_millis()
+            ),
+            _ => format!("{fetch} no samples"),
+        }
+    }
+}
+
+/// The `Opened` struct marks the time a span we are timing opened, in the extensions of the
+/// span.
+struct Opened(time::Instant);
+
+/// Times spans named [`TILE_SPAN`], [`REQUEST_SPAN`] and [`DOWNLOAD_SPAN`], as described in the
+/// [`Latency`] docs.
+impl<S> tracing_subscriber::Layer<S> for Latency
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        if Fetch::from_span(attrs.metadata().name()).is_none() {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Opened(time::Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(fetch) = Fetch::from_span(span.name()) else {
+            return;
+        };
+        if let Some(Opened(opened)) = span.extensions().get::<Opened>() {
+            self.record(fetch, opened.elapsed());
+        }
+    }
+}
+
+/// The `Fetch` enum names the kinds of download timed by [`Latency`].
+///
+/// * The `Tiles` variant covers map tiles, from spans named [`TILE_SPAN`].
+/// * The `Requests` variant covers API requests, from spans named [`REQUEST_SPAN`].
+/// * The `Downloads` variant covers large files fetched by [`crate::Download`], from spans named
+///   [`DOWNLOAD_SPAN`].
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+)]
+pub enum Fetch {
+    /// The `Tiles` variant indicates a map tile download.
+    #[display("tiles")]
+    Tiles,
+    /// The `Requests` variant indicates an API request.
+    #[display("requests")]
+    Requests,
+    /// The `Downloads` variant indicates a file download.
+    #[display("downloads")]
+    Downloads,
+}
+
+impl Fetch {
+    /// The `from_span` method returns the kind of fetch timed by a span called `name`, or
+    /// [`None`] if we do not time spans by that name.
+    pub fn from_span(name: &str) -> Option<Self> {
+        match name {
+            TILE_SPAN => Some(Self::Tiles),
+            REQUEST_SPAN => Some(Self::Requests),
+            DOWNLOAD_SPAN => Some(Self::Downloads),
+            _ => None,
+        }
+    }
+}
+
+/// The `DOWNLOAD_SPAN` constant holds the name of the spans [`Latency`] times as file downloads.
+pub const DOWNLOAD_SPAN: &str = "download";
+
+/// The `LATENCY_SAMPLES` constant holds the number of recent samples [`Latency`] keeps for each
+/// kind of [`Fetch`].
+pub const LATENCY_SAMPLES: usize = 256;
+
+/// The `REQUEST_SPAN` constant holds the name of the spans [`Latency`] times as API requests.
+pub const REQUEST_SPAN: &str = "request";
+
+/// The `TILE_SPAN` constant holds the name of the spans [`Latency`] times as tile downloads.
+pub const TILE_SPAN: &str = "tile";
@@ -1,5 +1,12 @@
+use crate::{
+    Access, Act, Arrive, Canvas, Fetcher, Figure, Flight, Focus, Gesture, Gestures, Glide,
+    Graphics, Ink, Map, Nav, Overlay, Palette, Panel, Place, Scenery, Sketch, Stamp, Stop,
+    FLIGHT_TIME, FOCUS_SEPARATOR, FRAME_INTERVAL, PANEL_PAD,
+};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use winit::window;
+use web_time as time;
+use winit::{dpi, event::WindowEvent, window};
 
 /// The `lens` module provides the [`Lens`] struct, which holds an application view and methods for
 /// interacting with the view.
@@ -25,19 +32,901 @@ use winit::window;
 /// Eventually I want to be able to share a window between the well-tested `egui` library and the
 /// relatively immature [galileo](https://docs.rs/galileo/latest/galileo/) library, but for now we
 /// are just stubbing this out for future use by wrapping it in an [`Arc`].
+///
+/// ## Update 0.1.2
+///
+/// Every window used to be an identical shell.  The `role` field now holds a [`Role`] describing
+/// what kind of content the window displays, so the [`crate::App`] can create windows with
+/// different purposes and route window events to the handler for that kind of content.
+///
+/// To keep the app quiet when nothing is happening, the `flight` field indicates the lens is
+/// showing something that moves, a map easing to a new view with a [`Flight`], and needs a steady
+/// stream of frames until it lands.  The `occluded` field indicates the window is hidden from
+/// view, in which case drawing is a waste of power.
+///
+/// The `map` field holds the [`Map`] shown by a lens with the [`Role::Map`] role, once a map has
+/// been attached.  The `cursor` field holds the last known position of the mouse in the window,
+/// which anchors zooming with the scroll wheel, and the `ui_zoom` field holds the scale factor
+/// applied to the user interface.  The `gestures` field recognizes touch gestures from the
+/// touches delivered to the window, and the `ink` field holds the stylus annotations drawn on it.
+/// The `overlay` field holds the frame statistics for the debug overlay (see [`Overlay`]).
+///
+/// ## Update 0.1.3
+///
+/// The `refresh` flag is wired up at last.  Lots of things change what a window shows: a tile
+/// arrives, a dataset finishes loading, the user scrolls, a style changes.  Each used to ask the
+/// window for a redraw on the spot, or set `refresh` and hope, and a burst of tile arrivals could
+/// ask for a dozen frames where one would do.  Now they all call [`Lens::invalidate`], which only
+/// sets the flag.  Once the event loop runs out of events, the [`crate::App`] calls
+/// [`Lens::flush`] on each lens, which turns a raised flag into a single
+/// [`window::Window::request_redraw`]:
+///
+/// * While a redraw is requested and not yet drawn, the `requested` field is `true`, and further
+///   invalidations ride along with the frame already coming.
+/// * The `drawn` field holds the time of the last frame, and a lens asks for at most one frame per
+///   [`FRAME_INTERVAL`].  An invalidation that arrives sooner waits for the interval to run out,
+///   and [`Lens::redraw_at`] tells the app when to wake up for it.
+/// * A lens that is not [`Lens::visible`] keeps its flag raised and asks for nothing until it can
+///   be seen.
+///
+/// Drawing the frame clears the flag, in [`Lens::drawn`].
+///
+/// ## Update 0.1.4
+///
+/// The `map` field finally gets a map.  A map needs a surface to draw on, and the `canvas` field
+/// holds it, a [`Canvas`] from [`crate::Gpu::canvas`], which the [`crate::App`] attaches along
+/// with the map when it opens a map window.  The lens resizes the canvas and the map with the
+/// window, and lets go of both in [`Lens::suspend`], since the surface may not outlive the
+/// suspension.
+///
+/// Drawing the map happens in [`Lens::render`], and the surface can fall out of step with the
+/// window between frames.  A resize that arrives late, a move to a monitor with another scale
+/// factor, or a driver reset leaves the surface the wrong size or gone, and [`wgpu`] tells us with
+/// [`wgpu::SurfaceError::Outdated`] or [`wgpu::SurfaceError::Lost`].  Rather than stretch a frame
+/// of the old size across the window, or give up on the window, the lens checks the size of the
+/// surface against the window before each frame, and configures the surface again and tries once
+/// more when the next texture will not come.
+///
+/// Not every change to a window changes the map.  A stroke of ink, the debug overlay or the scale
+/// of the interface lie over the map, and drawing the map again for them wastes the card on
+/// work that comes out the same.  The `damage` field says what changed since the last frame, as
+/// a [`Damage`]: [`Lens::invalidate`] marks the map damaged, and [`Lens::invalidate_chrome`]
+/// marks only what lies over it.  A redraw we did not ask for, from the system or a tile
+/// arriving, might have any cause, so it damages the map, and so does news from the map itself
+/// through [`Map::damaged`].  With the map undamaged, [`Canvas::draw`] shows the picture it kept
+/// from the last frame instead of asking [`galileo`] to draw it again, and the `painted` field
+/// records whether the map was drawn, for the debug overlay.
+///
+/// Each lens also has a policy for when to draw, a [`Pacing`] from [`Lens::policy`].  A reactive
+/// lens draws only when invalidated, and a continuous lens draws a frame every `interval`,
+/// whether or not anything asked.  Tables, charts and logs change only when their data does, so
+/// they stay reactive.  A map goes continuous while in flight, while its effects move with time
+/// or while it shows the debug overlay, or all the time if the `pacing` key of the `[graphics]`
+/// table asks for it, as for measuring the frame rate.  The `interval` comes from the `fps` key
+/// of the same table (see [`Graphics`]), and [`Lens::adopt`] picks up changes to both.  The
+/// [`crate::App`] paces the event loop to the continuous lenses, in [`crate::App::pace`], and
+/// otherwise lets it sleep.
+///
+/// The `access` field holds the [`Access`] describing the window to screen readers, which the
+/// [`crate::App`] attaches as it opens the window.  [`Lens::relay`] passes it every window
+/// event, and [`Lens::describe`] sends it the tree of the window.  The `focus` field holds the
+/// [`Focus`] of the window, which the keyboard moves through [`Lens::navigate`] and a screen
+/// reader through [`Lens::land`].  A move of focus changes the ring around the map or the title
+/// of the window, so it damages only the chrome.  The `palette` field holds the [`Palette`] the
+/// ring and the annotations of the `ink` take their colors and widths from, which the
+/// [`crate::App`] sets from the `[theme]` table.
+/// The open data files draw as layers of the map, from the [`Figure`] of each file, which
+/// [`Lens::show`] hands the map, so the kept picture of the map carries the data with it, and
+/// the effects run over both.  The icons of their points draw through the [`Stamp`] that
+/// [`Lens::adorn`] hands the map, at the [`Lens::symbol_scale`] of the window.
+/// The `synopsis` field holds the last line of text describing the view of the map, from
+/// [`crate::Act::DescribeView`], which the tree of the window carries (see [`crate::Synopsis`]).
+/// The `press` field holds the longitude and latitude of the last long press on the map, until
+/// the [`crate::App`] takes it with [`Lens::take_press`] for the active [`crate::Tool`].
+/// The `panels` field holds the [`Panel`] boxes of text the window shows over the map, one in
+/// each [`Place`], from [`Lens::post`], and the `sheets` field keeps the picture of each, with the
+/// scale and the palette it was drawn in, so a panel draws again only when one of them changes.
+/// Like the focus ring, panels lie over the map and damage only the chrome.
 #[derive(Debug, derive_getters::Getters, derive_setters::Setters)]
 #[setters(prefix = "with_", into, borrow_self)]
 pub struct Lens {
+    access: Option<Access>,
+    canvas: Option<Canvas>,
+    cursor: dpi::PhysicalPosition<f64>,
+    damage: Option<Damage>,
+    drawn: Option<time::Instant>,
+    flight: Option<Flight>,
+    focus: Focus,
+    gestures: Gestures,
+    ink: Ink,
+    interval: time::Duration,
+    map: Option<Map>,
+    occluded: bool,
+    overlay: Overlay,
+    pacing: Pacing,
+    painted: bool,
+    palette: Palette,
+    panels: BTreeMap<Place, Panel>,
+    press: Option<(f64, f64)>,
     refresh: bool,
+    requested: bool,
+    role: Role,
+    sheets: BTreeMap<Place, (f64, Palette, image::RgbaImage)>,
+    synopsis: Option<String>,
+    ui_zoom: f64,
     window: Arc<window::Window>,
 }
 
 impl Lens {
-    /// The `new` method creates an instance of `Lens` from an [`Arc<window::Window>`].
-    pub fn new(window: Arc<window::Window>) -> Self {
+    /// The `new` method creates an instance of `Lens` from an [`Arc<window::Window>`], displaying
+    /// the content type indicated by `role`.
+    pub fn new(window: Arc<window::Window>, role: Role) -> Self {
         Self {
+            access: None,
+            canvas: None,
+            cursor: dpi::PhysicalPosition::default(),
+            damage: None,
+            drawn: None,
+            flight: None,
+            focus: Focus::default(),
+            gestures: Gestures::default(),
+            ink: Ink::default(),
+            interval: FRAME_INTERVAL,
+            map: None,
+            occluded: false,
+            overlay: Overlay::default(),
+            pacing: Pacing::default(),
+            painted: false,
+            palette: Palette::default(),
+            panels: BTreeMap::new(),
+            press: None,
             refresh: false,
+            requested: false,
+            role,
+            sheets: BTreeMap::new(),
+            synopsis: None,
+            ui_zoom: 1.0,
             window,
         }
     }
+
+    /// The `scroll` method applies a [`Glide`] from the scroll wheel to the lens.
+    ///
+    /// A [`Glide::UiZoom`] scales the user interface of any lens, within the bounds of
+    /// [`UI_ZOOM_MIN`] and [`UI_ZOOM_MAX`].  A [`Glide::Zoom`] or [`Glide::Pan`] passes to
+    /// [`Map::scroll`] when the lens has a map, anchored at the cursor position, and otherwise
+    /// does nothing.
+    #[tracing::instrument(skip_all)]
+    pub fn scroll(&mut self, glide: &Glide) {
+        self.scroll_at(glide, self.cursor);
+    }
+
+    /// The `scroll_at` method applies a [`Glide`] like [`Lens::scroll`], anchored at the
+    /// position `anchor` in the window instead of the cursor.
+    #[tracing::instrument(skip_all)]
+    pub fn scroll_at(&mut self, glide: &Glide, anchor: dpi::PhysicalPosition<f64>) {
+        match glide {
+            Glide::UiZoom(factor) => {
+                self.ui_zoom = (self.ui_zoom * factor).clamp(UI_ZOOM_MIN, UI_ZOOM_MAX);
+                tracing::trace!("UI zoom: {}", self.ui_zoom);
+                self.invalidate_chrome();
+            }
+            glide => {
+                if let Some(map) = &self.map {
+                    map.scroll(glide, anchor);
+                    self.invalidate();
+                } else {
+                    tracing::trace!("{} lens cannot scroll {glide:?}", self.role);
+                }
+            }
+        }
+    }
+
+    /// The `nudge` method applies a [`Glide`] to the lens like [`Lens::scroll`], but anchored at
+    /// the center of the window instead of the cursor.  Used by keyboard and controller actions,
+    /// where the cursor could be anywhere.  A map eases into the new view over a [`Flight`],
+    /// finishing any flight already under way first, so quick presses add up.
+    #[tracing::instrument(skip_all)]
+    pub fn nudge(&mut self, glide: &Glide) {
+        let size = self.window.inner_size();
+        let center = dpi::PhysicalPosition::new(size.width as f64 / 2.0, size.height as f64 / 2.0);
+        if matches!(glide, Glide::UiZoom(_)) || self.map.is_none() {
+            self.scroll_at(glide, center);
+            return;
+        }
+        let now = time::Instant::now();
+        if let Some(mut flight) = self.flight.take() {
+            let rest = flight.step(now + FLIGHT_TIME);
+            self.scroll_at(&rest, *flight.anchor());
+        }
+        self.flight = Some(Flight::new(*glide, center, now));
+        self.invalidate();
+    }
+
+    /// The `fly` method makes the part of the [`Flight`] of the lens due by `now`, if any, and
+    /// lets go of the flight once it lands.  Called as each frame starts to draw.
+    fn fly(&mut self, now: time::Instant) {
+        let Some(mut flight) = self.flight.take() else {
+            return;
+        };
+        let glide = flight.step(now);
+        self.scroll_at(&glide, *flight.anchor());
+        if !flight.landed() {
+            self.flight = Some(flight);
+        }
+    }
+
+    /// The `gesture` method responds to a [`Gesture`] recognized from touch input.  Pinches and
+    /// two-finger pans move the map through [`Lens::scroll_at`], and a long press identifies
+    /// the feature under the finger using [`Lens::identify`].
+    #[tracing::instrument(skip_all)]
+    pub fn gesture(&mut self, gesture: &Gesture) {
+        tracing::trace!("Gesture: {gesture:?}");
+        match gesture {
+            Gesture::Pinch { factor, anchor } => self.scroll_at(&Glide::Zoom(*factor), *anchor),
+            Gesture::Pan { x, y, anchor } => self.scroll_at(&Glide::Pan(*x, *y), *anchor),
+            Gesture::LongPress(position) => self.identify(*position),
+        }
+    }
+
+    /// The `toggle_ink` method turns stylus annotation on or off for the lens.  While ink is on,
+    /// the pen draws instead of moving the map.
+    pub fn toggle_ink(&mut self) {
+        let active = !self.ink.active();
+        self.ink.with_active(active);
+        tracing::info!("Ink {}.", if active { "on" } else { "off" });
+    }
+
+    /// The `sketch` method adds `sketches` to the annotations of the lens, as described in
+    /// [`Ink`], and moves the map to show them, as described in [`Sketch::span`].
+    pub fn sketch(&mut self, sketches: Vec<Sketch>) {
+        let span = Sketch::span(&sketches);
+        tracing::info!("Pasted {} shapes.", sketches.len());
+        self.ink.sketch(sketches);
+        if let (Some(map), Some(span)) = (&self.map, span) {
+            let size = self.window.inner_size();
+            map.frame(&span, size.width, size.height);
+        }
+        self.invalidate();
+    }
+
+    /// The `select` method replaces the selection of the lens with `sketches`, as described in
+    /// [`Ink`], and moves the map to show them, as [`Lens::sketch`] does.
+    pub fn select(&mut self, sketches: Vec<Sketch>) {
+        let span = Sketch::span(&sketches);
+        self.ink.select(sketches);
+        if let (Some(map), Some(span)) = (&self.map, span) {
+            let size = self.window.inner_size();
+            map.frame(&span, size.width, size.height);
+        }
+        self.invalidate();
+    }
+
+    /// The `undo_ink` method removes the most recent annotation from the lens.
+    pub fn undo_ink(&mut self) {
+        if self.ink.undo().is_some() {
+            self.invalidate_chrome();
+        }
+    }
+
+    /// The `toggle_overlay` method shows or hides the debug overlay for the lens.
+    pub fn toggle_overlay(&mut self) {
+        self.overlay.toggle();
+        if !self.overlay.visible() {
+            self.post(Place::Overlay, None);
+        }
+        self.invalidate_chrome();
+    }
+
+    /// The `ui_scale` method returns the scale of the user interface, the `ui_zoom` from the
+    /// scroll wheel times the scale of the large-UI mode of the [`Palette`].  The lens draws
+    /// the focus ring and the lines of the [`Ink`] at this scale.
+    pub fn ui_scale(&self) -> f64 {
+        self.ui_zoom * self.palette.scale()
+    }
+
+    /// The `symbol_scale` method returns the scale to pick the icons of point symbols at, with
+    /// [`crate::Atlas::sprite`]: the scale factor of the window times the scale of the large-UI
+    /// mode of the [`Palette`].
+    pub fn symbol_scale(&self) -> f64 {
+        self.window.scale_factor() * self.palette.scale()
+    }
+
+    /// The `invalidate` method marks the lens as needing a redraw, to be requested by
+    /// [`Lens::flush`], with the map damaged.  Call it as often as you like, since the requests
+    /// coalesce into one frame.
+    pub fn invalidate(&mut self) {
+        self.mark(Damage::Map);
+    }
+
+    /// The `invalidate_chrome` method marks the lens as needing a redraw like
+    /// [`Lens::invalidate`], for a change to what lies over the map, leaving the map as it was.
+    pub fn invalidate_chrome(&mut self) {
+        self.mark(Damage::Chrome);
+    }
+
+    /// The `mark` method raises the refresh flag, and adds `damage` to the damage since the last
+    /// frame.
+    fn mark(&mut self, damage: Damage) {
+        self.refresh = true;
+        self.damage = self.damage.max(Some(damage));
+    }
+
+    /// The `redraw_at` method returns when the lens should next ask for a redraw, or [`None`] if
+    /// it has nothing to ask for: no invalidation, a redraw already on the way, or a window that
+    /// cannot be seen.
+    pub fn redraw_at(&self) -> Option<time::Instant> {
+        if !self.refresh || self.requested || !self.visible() {
+            return None;
+        }
+        Some(match self.drawn {
+            Some(drawn) => drawn + self.interval,
+            None => time::Instant::now(),
+        })
+    }
+
+    /// The `flush` method asks the window for a redraw if the lens is invalid and the time from
+    /// [`Lens::redraw_at`] has come by `now`.  Returns the time to try again if the lens has to
+    /// wait.
+    pub fn flush(&mut self, now: time::Instant) -> Option<time::Instant> {
+        let deadline = self.redraw_at()?;
+        if deadline > now {
+            return Some(deadline);
+        }
+        self.window.request_redraw();
+        self.requested = true;
+        None
+    }
+
+    /// The `drawn` method records that the lens drew a frame at `now`, clearing the invalidation
+    /// and the outstanding request.  A frame we did not ask for damages the map, as described in
+    /// the [`Lens`] docs.
+    pub fn drawn(&mut self, now: time::Instant) {
+        if !self.requested {
+            self.damage = Some(Damage::Map);
+        }
+        self.refresh = false;
+        self.requested = false;
+        self.drawn = Some(now);
+    }
+
+    /// The `render` method draws the map onto the canvas and presents the frame.  A lens
+    /// without a canvas and a map has nothing to draw.
+    ///
+    /// The surface is resized first if the window has changed size since it was configured.  An
+    /// outdated or lost surface is configured again and given one more try, and a surface that
+    /// times out skips the frame and asks for another.  Will [`crate::Blame::Surface`] if the
+    /// surface still will not give us a texture, or if the card is out of memory.
+    ///
+    /// The map draws again only if damaged, as described in the [`Lens`] docs.  The annotations
+    /// of the [`Ink`] and the focus ring draw over it every frame.
+    #[tracing::instrument(skip_all)]
+    pub fn render(&mut self) -> Arrive<()> {
+        self.painted = false;
+        self.fly(time::Instant::now());
+        let scale = self.ui_scale() * self.window.scale_factor();
+        let ring = match self.focus.ring() {
+            true => Some((self.palette.ring() * scale).round() as u32),
+            false => None,
+        };
+        let symbols = self.symbol_scale();
+        let (Some(canvas), Some(map)) = (&mut self.canvas, &mut self.map) else {
+            return Ok(());
+        };
+        map.rescale(symbols);
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        let damage = match map.damaged() {
+            true => Damage::Map,
+            false => self.damage.take().unwrap_or(Damage::Map),
+        };
+        if !canvas.fits(size) {
+            tracing::trace!("Surface out of step with the window, resizing.");
+            canvas.resize(size);
+            map.resize(size);
+        }
+        let texture = match canvas.surface().get_current_texture() {
+            Ok(texture) => texture,
+            Err(wgpu::SurfaceError::Outdated) | Err(wgpu::SurfaceError::Lost) => {
+                tracing::trace!("Surface outdated or lost, configuring again.");
+                canvas.reconfigure();
+                canvas.surface().get_current_texture()?
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                tracing::trace!("Surface timed out, skipping the frame.");
+                self.mark(damage);
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if texture.suboptimal {
+            // The frame would come out, but scaled, so we configure again and draw the next one.
+            tracing::trace!("Surface suboptimal, configuring again.");
+            drop(texture);
+            canvas.reconfigure();
+            self.mark(damage);
+            return Ok(());
+        }
+        let width = self.palette.line() * scale;
+        let painted = canvas.draw(&texture.texture, damage, |view| map.render(view));
+        let mut paths = self.ink.trails(width);
+        let mut selection = Vec::new();
+        if let Some(viewport) = map.viewport() {
+            paths.extend(self.ink.outlines(&viewport, width));
+            selection = self.ink.selected(&viewport, width);
+        }
+        let inked = canvas
+            .trace(&texture.texture, &paths, *self.palette.ink())
+            .and_then(|_| canvas.trace(&texture.texture, &selection, *self.palette.selection()));
+        let ringed = match ring {
+            Some(width) => canvas.ring(&texture.texture, width, *self.palette.focus()),
+            None => Ok(()),
+        };
+        let margin = (PANEL_PAD * scale).round() as u32;
+        let mut pasted = Ok(());
+        for (place, panel) in &self.panels {
+            let fresh = self
+                .sheets
+                .get(place)
+                .filter(|(drawn, palette, _)| *drawn == scale && *palette == self.palette);
+            if fresh.is_none() {
+                match panel.paint(&self.palette, scale) {
+                    Ok(sheet) => {
+                        self.sheets.insert(*place, (scale, self.palette, sheet));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Could not draw the {place:?} panel: {e}");
+                        continue;
+                    }
+                }
+            }
+            let Some((_, _, sheet)) = self.sheets.get(place) else {
+                continue;
+            };
+            let (x, y) = match place {
+                Place::Status => (margin, size.height.saturating_sub(sheet.height() + margin)),
+                Place::Notice => (margin, margin),
+                Place::Overlay => (size.width.saturating_sub(sheet.width() + margin), margin),
+            };
+            pasted = pasted.and(canvas.paste(&texture.texture, sheet, x, y));
+        }
+        texture.present();
+        self.painted = painted?;
+        inked.and(ringed).and(pasted)
+    }
+
+    /// The `post` method shows `panel` over the map in `place`, in place of the panel there, or
+    /// takes the panel there away with [`None`], as described in the [`Panel`] docs.
+    pub fn post(&mut self, place: Place, panel: Option<Panel>) {
+        if self.panels.get(&place) == panel.as_ref() {
+            return;
+        }
+        self.sheets.remove(&place);
+        match panel {
+            Some(panel) => self.panels.insert(place, panel),
+            None => self.panels.remove(&place),
+        };
+        self.invalidate_chrome();
+    }
+
+    /// The `adopt` method brings the canvas, if any, in line with the `graphics` settings, as in
+    /// [`Canvas::adopt`], hands the multisample count of the canvas to the map, and takes the
+    /// pacing and frame interval from them.
+    pub fn adopt(&mut self, graphics: &Graphics) {
+        self.pacing = *graphics.pacing();
+        self.interval = graphics.interval();
+        if let Some(canvas) = &mut self.canvas {
+            canvas.adopt(graphics);
+            if let Some(map) = &self.map {
+                map.set_samples(*canvas.sample_count());
+            }
+            self.invalidate();
+        }
+    }
+
+    /// The `show` method draws the layers of `figures` on the map, if any, as in [`Map::show`].
+    pub fn show(&mut self, figures: &[Figure]) {
+        if let Some(map) = &mut self.map {
+            map.show(figures);
+            self.invalidate();
+        }
+    }
+
+    /// The `reveal` method draws `scene` on the map, if any, as in [`Map::reveal`].
+    pub fn reveal(&mut self, scene: Option<&Scenery>) {
+        if let Some(map) = &mut self.map {
+            map.reveal(scene);
+            self.invalidate();
+        }
+    }
+
+    /// The `adorn` method hands the map, if any, the [`Stamp`] drawing the icons of point
+    /// symbols, as in [`Map::adorn`].
+    pub fn adorn(&mut self, stamp: Option<Arc<Stamp>>) {
+        if let Some(map) = &mut self.map {
+            map.adorn(stamp);
+            self.invalidate();
+        }
+    }
+
+    /// The `shade` method runs the shaders in `sources` over the map, if any, as in
+    /// [`Canvas::shade`].
+    pub fn shade(&mut self, sources: &[(String, String)], animate: bool) {
+        if let Some(canvas) = &mut self.canvas {
+            canvas.shade(sources, animate);
+            self.invalidate();
+        }
+    }
+
+    /// The `frame` method updates the debug overlay after the lens draws a frame.  We count one
+    /// draw call for the map, if it was drawn, and one for each annotation of ink drawn over it,
+    /// read the tiles on the way from [`Fetcher::queued`], and post the readout in
+    /// [`Place::Overlay`] when [`Overlay::report`] has a new one.
+    pub fn frame(&mut self) {
+        if !self.overlay.visible() {
+            return;
+        }
+        let now = time::Instant::now();
+        let draw_calls = usize::from(self.painted)
+            + self.ink.scribbles().len()
+            + self.ink.sketches().len()
+            + self.ink.drawing().len();
+        self.overlay.with_draw_calls(draw_calls);
+        self.overlay.with_tile_queue(Fetcher::queued());
+        self.overlay.record(now);
+        if let Some(panel) = self.overlay.report(now) {
+            self.post(Place::Overlay, Some(panel));
+        }
+    }
+
+    /// The `identify` method finds the place on the map under `position` in the window, and
+    /// holds it for [`Lens::take_press`], where the [`crate::App`] hands it to the active
+    /// [`crate::Tool`], or identifies what lies there.
+    #[tracing::instrument(skip_all)]
+    pub fn identify(&mut self, position: dpi::PhysicalPosition<f64>) {
+        match (self.role, &self.map) {
+            (Role::Map, Some(map)) => self.press = map.locate(position),
+            (role, _) => tracing::trace!("{role} lens has nothing to identify."),
+        }
+    }
+
+    /// The `take_press` method returns the longitude and latitude of the last press on the map,
+    /// if the app has not taken it yet.
+    pub fn take_press(&mut self) -> Option<(f64, f64)> {
+        self.press.take()
+    }
+
+    /// The `suspend` method releases resources tied to the display when the application is
+    /// suspended.  On mobile platforms and after some GPU resets, the native window surface is
+    /// destroyed while we are suspended, and drawing to it afterwards will fail.
+    ///
+    /// We let go of the canvas and the map drawing on it, and stop requesting redraws.  The
+    /// [`crate::App`] attaches a new map when the app resumes, opening on the configured view.
+    #[tracing::instrument(skip_all)]
+    pub fn suspend(&mut self) {
+        tracing::trace!("Suspending {} lens.", self.role);
+        self.map = None;
+        self.canvas = None;
+        self.flight = None;
+        self.refresh = false;
+        self.requested = false;
+    }
+
+    /// The `resume` method restores the lens after a call to [`Lens::suspend`].  We cannot trust
+    /// anything we drew before the suspension, so we invalidate the lens.
+    #[tracing::instrument(skip_all)]
+    pub fn resume(&mut self) {
+        tracing::trace!("Resuming {} lens.", self.role);
+        self.invalidate();
+    }
+
+    /// The `visible` method returns `true` unless the window is occluded or minimized.  Platforms
+    /// that cannot tell us whether a window is minimized count as visible.
+    pub fn visible(&self) -> bool {
+        !self.occluded && !self.window.is_minimized().unwrap_or(false)
+    }
+
+    /// The `policy` method returns the [`Pacing`] the lens draws by right now, as described in
+    /// the [`Lens`] docs.  The `pacing` field only asks, and only a map listens.  Effects on the
+    /// canvas that move with time keep the lens drawing too, as in [`Canvas::shade`], and so
+    /// does a map in flight, as in [`Lens::nudge`].
+    pub fn policy(&self) -> Pacing {
+        let map = self.role == Role::Map && self.pacing == Pacing::Continuous;
+        let effects = self.canvas.as_ref().is_some_and(|canvas| *canvas.animate());
+        let animate = self.flight.is_some();
+        match animate || *self.overlay.visible() || map || effects {
+            true => Pacing::Continuous,
+            false => Pacing::Reactive,
+        }
+    }
+
+    /// The `needs_frames` method returns `true` when the lens draws continuously by
+    /// [`Lens::policy`], and visible, meaning the [`crate::App`] should keep the event loop
+    /// spinning to draw new frames.
+    pub fn needs_frames(&self) -> bool {
+        self.policy() == Pacing::Continuous && self.visible()
+    }
+
+    /// The `relay` method passes `event` to the [`Access`] of the window, if any.  The adapter
+    /// underneath wants every event, including those the [`crate::App`] handles itself.
+    pub fn relay(&mut self, event: &WindowEvent) {
+        if let Some(access) = &mut self.access {
+            access.process(&self.window, event);
+        }
+    }
+
+    /// The `describe` method sends the tree of the window to the [`Access`], if any, with a
+    /// button for each of `acts` and an item for each of `layers`, as in [`Access::update`].
+    /// Focus on a button or item that has gone moves back to the content first.
+    pub fn describe(&mut self, acts: &[Act], layers: &[String]) {
+        self.focus.settle(acts, layers.len());
+        let title = self.title();
+        if let Some(access) = &mut self.access {
+            access.update(
+                &title,
+                self.role,
+                acts,
+                layers,
+                self.focus.stop().node(),
+                self.synopsis.as_deref(),
+            );
+        }
+    }
+
+    /// The `navigate` method moves the [`Focus`] of the window by `nav`, among a button for each
+    /// of `acts` and an item for each of `layers`, as in [`Focus::navigate`], and returns the
+    /// [`Act`] the key asks for, if any.
+    pub fn navigate(&mut self, nav: Nav, acts: &[Act], layers: &[String]) -> Option<Act> {
+        let before = self.focus.clone();
+        let act = self.focus.navigate(nav, acts, layers.len());
+        if self.focus != before {
+            self.refocus(layers);
+            self.describe(acts, layers);
+        }
+        act
+    }
+
+    /// The `land` method moves the [`Focus`] of the window to `stop`, as asked by a screen
+    /// reader, among a button for each of `acts` and an item for each of `layers`.
+    pub fn land(&mut self, stop: Stop, acts: &[Act], layers: &[String]) {
+        self.focus.land(stop);
+        self.refocus(layers);
+        self.describe(acts, layers);
+    }
+
+    /// The `point` method hides the [`Focus`] of the window after a click, as in
+    /// [`Focus::point`], among a button for each of `acts` and an item for each of `layers`.
+    pub fn point(&mut self, acts: &[Act], layers: &[String]) {
+        if self.focus.point() {
+            self.refocus(layers);
+            self.describe(acts, layers);
+        }
+    }
+
+    /// The `refocus` method shows where focus rests after it moves, as described in the
+    /// [`Focus`] docs: the name of a command or layer goes in the title, and the ring around the
+    /// map comes or goes with the next frame.
+    fn refocus(&mut self, layers: &[String]) {
+        let title = self.title();
+        let label = self.focus.stop().label(layers);
+        if *self.focus.visible() && !label.is_empty() {
+            tracing::trace!("Focus: {label}");
+            self.window
+                .set_title(&format!("{title}{FOCUS_SEPARATOR}{label}"));
+        } else {
+            self.window.set_title(&title);
+        }
+        self.invalidate_chrome();
+    }
+
+    /// The `title` method returns the title of the window, without the name of the command or
+    /// layer with focus.
+    pub fn title(&self) -> String {
+        let title = self.window.title();
+        match title.split_once(FOCUS_SEPARATOR) {
+            Some((title, _)) => title.to_string(),
+            None => title,
+        }
+    }
+
+    /// The `window_event` method receives the window events that [`crate::App`] does not handle
+    /// at the application level, and dispatches them according to the [`Role`] of the lens.
+    ///
+    /// Resizing a map or chart invalidates the drawing, so we resize the canvas and the map, if
+    /// any, and flag the lens for refresh.  Tables and logs are text, and the text layout takes
+    /// care of itself.
+    #[tracing::instrument(skip_all)]
+    pub fn window_event(&mut self, event: &WindowEvent) {
+        match (self.role, event) {
+            (_, WindowEvent::Touch(touch)) => {
+                // The pen gets first claim on the touch, so palms do not move the map.
+                if self.ink.touch(touch) {
+                    self.invalidate_chrome();
+                    return;
+                }
+                for gesture in self.gestures.touch(touch, self.palette.scale()) {
+                    self.gesture(&gesture);
+                }
+            }
+            (_, WindowEvent::CursorMoved { position, .. }) => {
+                self.cursor = *position;
+            }
+            (_, WindowEvent::Occluded(occluded)) => {
+                tracing::trace!("{} occluded: {occluded}", self.role);
+                self.occluded = *occluded;
+            }
+            (Role::Map, WindowEvent::Resized(size)) | (Role::Chart, WindowEvent::Resized(size)) => {
+                tracing::trace!("{} resized.", self.role);
+                if let Some(canvas) = &mut self.canvas {
+                    canvas.resize(*size);
+                }
+                if let Some(map) = &self.map {
+                    map.resize(*size);
+                }
+                self.invalidate();
+            }
+            (Role::Map, WindowEvent::ScaleFactorChanged { .. })
+            | (Role::Chart, WindowEvent::ScaleFactorChanged { .. }) => {
+                // A resize follows if the window changes size, and [`Lens::render`] checks the
+                // size again before drawing in case it does not.
+                tracing::trace!("{} scale factor changed.", self.role);
+                self.invalidate();
+            }
+            (role, event) => tracing::trace!("{role} ignoring event: {event:?}"),
+        }
+    }
+}
+
+/// The `Damage` enum describes what has changed in a [`Lens`] since its last frame, as described
+/// in the [`Lens`] docs.  The variants are ordered, so the greater damage covers the lesser.
+///
+/// * The `Chrome` variant means something over the map changed, like ink or the overlay.
+/// * The `Map` variant means the map changed, and everything over it must draw again too.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Damage {
+    /// The `Chrome` variant indicates a change over the map.
+    Chrome,
+    /// The `Map` variant indicates a change to the map.
+    Map,
+}
+
+/// The `Pacing` enum describes when a [`Lens`] draws, as described in the [`Lens`] docs.
+///
+/// * The `Reactive` variant draws only when something invalidates the lens.  This is the
+///   default.
+/// * The `Continuous` variant draws a frame every interval, at the `fps` of the `[graphics]`
+///   table.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+)]
+pub enum Pacing {
+    /// The `Reactive` variant indicates drawing on invalidation.
+    #[default]
+    Reactive,
+    /// The `Continuous` variant indicates drawing at a steady rate.
+    Continuous,
+}
+
+/// Reads the name of the pacing whatever its case, as described in [`crate::choice`].
+impl<'de> serde::Deserialize<'de> for Pacing {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::choice(deserializer)
+    }
+}
+
+/// The `Role` enum describes the type of content displayed by a [`Lens`].
+///
+/// # Giving windows a purpose with `Role`
+///
+/// The purpose of the `Role` enum is to tell windows apart.  A window showing a map needs a GPU
+/// surface and responds to the mouse by panning and zooming, while a window showing a table of
+/// economic data scrolls like a spreadsheet.  Rather than give each kind of window its own struct,
+/// we keep a single [`Lens`] type and match on the `role` field when the behavior differs.
+///
+/// * The `Map` variant displays a geographic map.  This is the default.
+/// * The `Table` variant displays tabular data.
+/// * The `Chart` variant displays a plot of data.
+/// * The `Log` variant displays application messages.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Role {
+    /// The `Map` variant indicates the window displays a geographic map.
+    #[default]
+    Map,
+    /// The `Table` variant indicates the window displays tabular data.
+    Table,
+    /// The `Chart` variant indicates the window displays a chart.
+    Chart,
+    /// The `Log` variant indicates the window displays application messages.
+    Log,
+}
+
+impl Role {
+    /// The `attributes` method returns the default [`window::WindowAttributes`] for a window
+    /// with this role.  Like the original default window, the window is transparent until we have
+    /// something to draw, and carries the title `Tardy`, followed by the role name.
+    pub fn attributes(&self) -> window::WindowAttributes {
+        window::Window::default_attributes()
+            .with_title(format!("Tardy - {self}"))
+            .with_transparent(true)
+    }
+}
+
+impl Lens {
+    /// The `prescription` method writes down what the lens was showing, so that we can grind a
+    /// new one later.  See [`Prescription`] for details.
+    pub fn prescription(&self) -> Prescription {
+        let title = self.window.title();
+        let position = self.window.outer_position().ok();
+        let size = self.window.inner_size();
+        Prescription::new(title, position, self.role, size)
+    }
+}
+
+/// The `Prescription` struct describes the contents of a [`Lens`] without holding on to the
+/// window itself.
+///
+/// # Remembering closed windows with `Prescription`
+///
+/// Dropping the [`Arc<window::Window>`] in a [`Lens`] is how we close a window, so we cannot
+/// keep the `Lens` around after the user closes it.  Instead, when a window closes we record the
+/// title, position, [`Role`] and size of the window in a `Prescription`, which is enough information to
+/// make a new `Lens` that looks just like the old one.  The name follows the eyeglasses theme: the
+/// prescription is what you hand to the optician when you need a replacement lens.
+///
+/// The [`crate::App`] keeps a short stack of prescriptions from recently closed windows, and pops
+/// the most recent one when the user asks to reopen a closed window, much like pressing
+/// \<Ctrl-Shift-T\> in a web browser.
+#[derive(Debug, Clone, PartialEq, Eq, derive_new::new, derive_getters::Getters)]
+pub struct Prescription {
+    title: String,
+    position: Option<dpi::PhysicalPosition<i32>>,
+    role: Role,
+    size: dpi::PhysicalSize<u32>,
+}
+
+impl Prescription {
+    /// The `attributes` method converts the prescription into [`window::WindowAttributes`] for
+    /// passing to [`crate::App::create_window`].  The window will be transparent like the default
+    /// windows created by the app.
+    pub fn attributes(&self) -> window::WindowAttributes {
+        let mut attr = window::Window::default_attributes()
+            .with_title(self.title.clone())
+            .with_transparent(true)
+            .with_inner_size(self.size);
+        if let Some(position) = self.position {
+            attr = attr.with_position(position);
+        }
+        attr
+    }
 }
+
+/// The `UI_ZOOM_MIN` constant sets the smallest scale factor for the user interface.
+pub const UI_ZOOM_MIN: f64 = 0.5;
+
+/// The `UI_ZOOM_MAX` constant sets the largest scale factor for the user interface.
+pub const UI_ZOOM_MAX: f64 = 3.0;
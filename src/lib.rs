@@ -1,17 +1,183 @@
+mod access;
 mod act;
+mod agents;
 mod app;
 mod arrive;
+mod bundle;
+mod catalog;
+mod cli;
 mod cmd;
+mod cog;
+mod courier;
+mod crash;
+mod dataset;
+mod diagnostic;
+mod download;
+mod editor;
+mod effect;
+mod export;
+mod fetcher;
+mod figure;
+mod finder;
+mod focus;
+mod fxaa;
+mod geocode;
+mod geopackage;
+mod geoparquet;
+mod gesture;
+mod gpu;
+mod home;
+mod hotkey;
+mod ink;
+mod latency;
 mod lens;
+mod lookup;
 mod map;
+mod migrate;
+mod network;
+mod offscreen;
+mod otlp;
+mod overlay;
+mod pad;
+mod panel;
+mod partial;
+mod permalink;
+mod pipeline;
+mod plugin;
+mod profile;
+mod rebind;
+mod recorder;
+mod runtime;
+mod scheme;
+mod scroll;
+mod secrets;
+mod settings;
+mod shapefile;
+mod sketch;
+mod snippet;
+mod sprite;
+mod stac;
+mod stamp;
+mod store;
+mod stroke;
+mod synopsis;
+mod tasks;
+mod telemetry;
+mod template;
+mod theme;
+mod usage;
 mod utils;
+mod verbosity;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod workbook;
+mod workspace;
 
 // Since this is a small application, we lift all user-facing data types and functions to the parent namespace
 // for ease of access.
-pub use act::Act;
-pub use app::{App, Frame, FRAMES, FRAME_POOL, MIN_SPAN};
-pub use arrive::{Arrive, Blame, Excuse};
-pub use cmd::Cmd;
-pub use lens::Lens;
-pub use map::Map;
-pub use utils::trace_init;
+pub use access::{
+    Access, ACT_NODES, COMMANDS_NODE, CONTENT_NODE, LAYERS_NODE, LAYER_NODES, ROOT_NODE,
+};
+pub use act::{Act, Group};
+pub use agents::{Agent, Agents, Restart, Status, PREFETCH_RADIUS, RESTART_DELAY, RESTART_LIMIT};
+pub use app::{
+    App, Frame, CLOSED_WINDOWS, CONFIG_FILE, CONFIG_FORMATS, FRAMES, FRAME_INTERVAL, FRAME_POOL,
+    MIN_SPAN,
+};
+pub use arrive::{Arrive, Blame, Context, Excuse, Severity, RETRY_DELAY, RETRY_LIMIT};
+pub use bundle::{Bundle, BUNDLE_ENTRY_LIMIT, BUNDLE_FILES, MANIFEST};
+pub use catalog::{Catalog, Feed, Listing, CATALOG_DIR, CATALOG_PAGES};
+pub use cli::Cli;
+pub use cmd::{Clash, Cmd, CHORD_TIMEOUT, DOUBLE_CLICK, DOUBLE_PRESS, HOLD_DELAY, MAX_COUNT};
+pub use cog::{Cog, Scenery, COG_CHUNKS, COG_HEADER, COG_HEADER_LIMIT, COG_TILE};
+pub use courier::{Courier, Slot, UserEvent};
+pub use crash::{Crash, Recent, RecentWriter, CRASH_DIR, CRASH_LINES, REPORTED};
+pub use dataset::{Dataset, Format, FEATURE_DIR, GEOMETRY, PROGRESS_STEP};
+pub use diagnostic::{Diagnostic, Kind, Problem};
+pub use download::{Download, DOWNLOAD_DIR, PARTIAL_EXTENSION, VALIDATOR_EXTENSION};
+pub use editor::{highlight, Editor, Span, Syntax};
+pub use effect::{Effect, EFFECT_PRELUDE};
+pub use export::{Export, EXPORT_DPI, EXPORT_QUALITY, EXPORT_ROUNDS, EXPORT_SETTLE, EXPORT_SIZE};
+pub use fetcher::Fetcher;
+pub use figure::{
+    Brush, Feature, Figure, Mark, Shape, Strokes, FIGURE_COLOR, FIGURE_DOT, FIGURE_FILL,
+};
+pub use finder::Finder;
+pub use focus::{Focus, Nav, Stop, FOCUS_COLOR, FOCUS_RING, FOCUS_SEPARATOR};
+pub use fxaa::{Fxaa, FXAA_SHADER};
+pub use geocode::{Candidate, Geocoder, Service, ADDRESS_COLUMN, GEOCODE_DIR, MATCH_SCORE};
+pub use geopackage::{GeoPackage, GEOPACKAGE_DEGREES, GEOPACKAGE_MAGIC};
+pub use geoparquet::{GeoParquet, Wkb, GEOPARQUET_VERSION, GEO_METADATA};
+pub use gesture::{Gesture, Gestures, LONG_PRESS, TOUCH_SLOP};
+pub use gpu::{
+    Canvas, Fault, Gamut, Gpu, Hardware, Power, PresentMode, FAULT_LIMIT, SAMPLE_COUNTS,
+    SOFTWARE_TITLE, WIDE_FORMAT,
+};
+pub use home::{Home, APPLICATION, ORGANIZATION, QUALIFIER};
+pub use hotkey::Hotkeys;
+pub use ink::{
+    Dab, Ink, Mark, Scribble, INK_COLOR, INK_DOT, INK_WIDTH, PALM_REJECTION, SELECTION_COLOR,
+};
+pub use latency::{Fetch, Latency, DOWNLOAD_SPAN, LATENCY_SAMPLES, REQUEST_SPAN, TILE_SPAN};
+pub use lens::{Damage, Lens, Pacing, Prescription, Role, UI_ZOOM_MAX, UI_ZOOM_MIN};
+pub use lookup::{Parcel, Parcels, Query, TAXLOT_LENGTH, TAXLOT_MATCHES};
+pub use map::{prune, Map, Tiles, Viewport, MAX_ZOOM};
+pub use migrate::{
+    config_version, migrate, upgrade, upgraded, Migration, CONFIG_VERSION, MIGRATIONS, VERSION,
+};
+pub use network::{
+    Exchange, Network, Overdue, NETWORK_ENTRIES, REDACTED, REDACTED_PARAMS, USER_AGENT,
+};
+pub use offscreen::{Offscreen, Snapshot, OFFSCREEN_FORMAT, SNAPSHOT_DIR, SNAPSHOT_SCALE};
+pub use otlp::Exporter;
+pub use overlay::{Overlay, HISTOGRAM_BUCKETS, OVERLAY_FRAMES, OVERLAY_INTERVAL};
+pub use pad::{Pad, DEADZONE, PAD_INTERVAL};
+pub use panel::{Panel, Place, PANEL_COLOR, PANEL_FONT, PANEL_LINES, PANEL_PAD, PANEL_TEXT};
+pub use partial::{Failure, PartialFailure};
+pub use permalink::{Permalink, PERMALINK_SCHEME};
+pub use pipeline::{Overflow, Pipe, Pushed, MAILBOX_CAPACITY, SHELF_CAPACITY};
+pub use plugin::{DataProvider, LayerFactory, Plugins, Tool};
+pub use profile::{Profile, PROFILES};
+pub use rebind::{Edit, Rebind, REBIND_ROWS};
+pub use recorder::{Clip, Recorder, Reel, FFMPEG, GIF_SPEED, RECORDING_BACKLOG, RECORDING_DIR};
+pub use runtime::{settle, sleep, spawn, timeout, unblock};
+pub use scheme::{Ramp, Scheme, Vision, CLASSES, DEFAULT_SCHEME, SCHEMES};
+pub use scroll::{
+    Flight, Glide, Scroll, ScrollMode, FLIGHT_TIME, LINE_HEIGHT, PAN_STEP, ZOOM_STEP,
+};
+pub use secrets::{Secrets, KEYRING_USER, SECRETS, SECRETS_FILE};
+pub use settings::{
+    Api, Basemap, Choropleth, Concurrency, Effects, Folders, Geocoding, Graphics, Groups, Imagery,
+    Keybindings, Layers, Listings, LogFormat, Logging, Lookup, Otlp, Recording, Schedule, Settings,
+    Sort, Startup, Summary, Symbols, Telemetry, Theme, Timeouts, Timing, Ui, ATTRIBUTION,
+    BULK_JOBS, CACHE_SIZE, CENTER, CONNECT_TIMEOUT, DOWNLOAD_DEADLINE, ENV_PREFIX, ENV_SHORTCUTS,
+    FPS, ICON_SIZE, INTERACTIVE_JOBS, JANITOR_INTERVAL, LOG_LEVEL, PREFETCHER_INTERVAL,
+    READ_TIMEOUT, RECORDING_FPS, RECORDING_SECONDS, REFRESHER_INTERVAL, REQUEST_DEADLINE, SAMPLES,
+    SERVICE_NAME, TILE_URL, WATCHER_INTERVAL, ZOOM,
+};
+pub use shapefile::{ShapeKind, Shapefile, FIELD_NAME_LIMIT, FIELD_WIDTH_LIMIT};
+pub use sketch::{Sketch, SKETCH_MARGIN};
+pub use snippet::Snippet;
+pub use sprite::{Atlas, Sprite, ATLAS_WIDTH, SPRITE_SCALES};
+pub use stac::{Stac, CLOUD_COVER, IMAGERY_DIR, STAC_CATALOG, STAC_PAGES};
+pub use stamp::{Stamp, STAMP_SHADER};
+pub use store::{
+    Store, ADDRESS_QUERY, BOOKMARKS_FILE, IMPORTED_EXTENSION, RECENT_FILE, RECENT_FILES,
+    STORE_FILE, STORE_MIGRATIONS, TAXLOT_QUERY,
+};
+pub use stroke::{Chord, Stroke, DOUBLE, HOLD, NAMED_KEYS, PHYSICAL};
+pub use synopsis::{Clipboard, Extent, Synopsis, EARTH_RADIUS, MERCATOR_LATITUDE};
+pub use tasks::{Class, Job, Tasks};
+pub use telemetry::{Payload, Reporter, TALLY_FILE};
+pub use template::{default_config, write_default_config};
+pub use theme::{
+    Contrast, Palette, CONTRAST_SHADER, HIGH_FOCUS_COLOR, HIGH_FOCUS_RING, HIGH_INK_COLOR,
+    HIGH_INK_WIDTH, HIGH_PANEL_COLOR, HIGH_PANEL_TEXT, HIGH_SELECTION_COLOR, LARGE_SCALE,
+};
+pub use usage::{Cache, Usage, RESPONSE_DIR, TILE_DIR};
+pub use utils::{choice, fold_choice, timestamp, trace_init};
+pub use verbosity::Verbosity;
+#[cfg(target_arch = "wasm32")]
+pub use web::run_web;
+pub use workbook::{Cell, Workbook, CELL_TEXT_LIMIT, NUMBER_DIGIT_LIMIT, SHEET_ROW_LIMIT};
+pub use workspace::{View, Workspace, WORKSPACE_DIR, WORKSPACE_EXTENSION};
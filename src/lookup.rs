@@ -0,0 +1,422 @@
+use crate::{Arrive, Catalog, Excuse, Feed, Listing, Lookup};
+
+/// The `lookup` module provides the [`Parcels`] struct, which looks up a parcel in the services
+/// of the city, the [`Query`] enum, which holds what to look it up by, and the [`Parcel`] struct,
+/// which holds what it found.
+///
+/// # Answering the counter with `Parcels`
+///
+/// Most of what the planning counter does starts with the same question: what is this lot?  A
+/// resident brings an address, a title company sends a map and tax lot number, or a planner
+/// points at a spot on the map, and the answer is the zoning, the size of the lot and the owner
+/// on record, with a link to the assessor.  Getting there took three web sites.  `Parcels` asks
+/// the parcel and address layers of the [`Catalog`] instead, named by the `[lookup]` table of
+/// the config (see [`Lookup`]), so that fixing the address of a service in the catalog fixes
+/// the lookup too:
+///
+/// * [`crate::Act::LookUpParcel`] reads an address or a map and tax lot number from the
+///   clipboard, since the counter copies them out of the permit system, and looks it up in the
+///   focused map.  [`Query::parse`] tells the two apart.
+/// * A press on the map with no [`crate::Tool`] chosen identifies the parcel under it, when the
+///   catalog has a parcel layer to ask.  Without one, the press only logs the place.
+/// * [`crate::Act::NextLookup`] asks again a lookup by address or by map and tax lot number that
+///   found a parcel before, saved in the [`crate::Store`], going round them in turn.
+///
+/// Each way runs as an interactive job of the [`crate::Tasks`] registry, and the parcel reaches
+/// the event loop as a [`crate::UserEvent::Found`]:
+///
+/// * A map and tax lot number asks the parcel layer for the parcel with that number in the
+///   `taxlot` field, in any case, and with or without separators, so `36-05-17-BC-01100` finds
+///   a parcel stored as `360517BC01100`, and the other way around.  The layer cannot drop the
+///   separators of its numbers, so we ask for up to [`TAXLOT_MATCHES`] parcels with the same
+///   letters and digits in order, and keep the first whose number matches once both lose
+///   everything but their letters and digits.
+/// * An address asks the address layer for the first point whose `address` field starts with the
+///   address, in any case, then asks the parcel layer for the parcel under the point.  A `%` or
+///   `_` in the address stands for itself, not for any text or any letter.
+/// * A point asks the parcel layer for the parcel under it.
+///
+/// The lines of [`Parcel::lines`] show in a [`crate::Panel`] over the map: the number of the
+/// parcel and the address asked for, the `fields` of the settings, or every field if it names
+/// none, and a link for each of the `links`, with the value of each field named in braces
+/// filled in, percent-encoded so that a value with a space or an `&` stays one value of the
+/// address.  The outline of the parcel becomes the selection of the
+/// window, a [`crate::Sketch`] drawn in the selection color of the [`crate::Palette`], which
+/// moves the map to it, gives way to the next lookup, and comes off with
+/// [`crate::Act::UndoInk`].  A lookup that finds nothing fails with
+/// [`Excuse::NoParcel`], and one the catalog has no layer for fails with [`Excuse::NoService`].
+/// The `taxlot` and `address` fields of the settings go into the where clauses sent to the
+/// layers, so a name that is not a plain field name, with letters, digits and underscores, fails
+/// with [`Excuse::FieldName`] before anything is sent.
+///
+/// * The `settings` field holds the [`Lookup`] settings from the config.
+/// * The `parcels` field holds the [`Listing`] of the parcel layer.
+/// * The `addresses` field holds the [`Listing`] of the address layer, if the catalog has one.
+#[derive(Debug, Clone, derive_getters::Getters)]
+pub struct Parcels {
+    settings: Lookup,
+    parcels: Listing,
+    addresses: Option<Listing>,
+}
+
+impl Parcels {
+    /// The `new` method finds the layers named by `settings` in `catalog`.  Only an ArcGIS
+    /// layer answers a query, so a layer read from a GeoJSON file does not count.
+    ///
+    /// Will [`Excuse::NoService`] if the catalog has no parcel layer of that name, and
+    /// [`Excuse::FieldName`] if the settings name a field that is not a plain field name.
+    pub fn new(settings: &Lookup, catalog: &Catalog) -> Arrive<Self> {
+        if let Some(field) = [settings.taxlot(), settings.address()]
+            .into_iter()
+            .find(|field| !Self::is_field(field))
+        {
+            tracing::warn!("The [lookup] table names the field {field:?}, which is not a name.");
+            return Err(Excuse::FieldName.into());
+        }
+        let find = |name: &str| {
+            catalog
+                .listings()
+                .iter()
+                .find(|listing| listing.name() == name && *listing.feed() == Feed::Arcgis)
+                .cloned()
+        };
+        let parcels = find(settings.parcels()).ok_or(Excuse::NoService)?;
+        Ok(Self {
+            settings: settings.clone(),
+            parcels,
+            addresses: find(settings.addresses()),
+        })
+    }
+
+    /// The `find` method looks up the parcel for `query`, as described in the [`Parcels`] docs.
+    ///
+    /// Will [`Excuse::NoParcel`] if there is none, [`Excuse::NoService`] if an address is asked
+    /// for without an address layer, and fails as [`Listing::fetch`] does.
+    #[tracing::instrument(skip(self))]
+    pub async fn find(&self, query: Query) -> Arrive<Parcel> {
+        match query {
+            Query::Point(longitude, latitude) => self.at(longitude, latitude, None).await,
+            Query::Taxlot(taxlot) => {
+                let wanted = Self::normalize(&taxlot);
+                if wanted.is_empty() {
+                    return Err(Excuse::NoParcel.into());
+                }
+                // Only letters and digits are left, so the pattern needs no quotes or escapes.
+                let pattern = wanted
+                    .chars()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+                    .join("%");
+                let clause = format!("UPPER({}) LIKE '{pattern}'", self.settings.taxlot());
+                let field = format!("/properties/{}", self.settings.taxlot());
+                let feature = self
+                    .features(&self.parcels, clause, None, TAXLOT_MATCHES)
+                    .await?
+                    .into_iter()
+                    .find(|feature| {
+                        let number = match feature.pointer(&field) {
+                            Some(serde_json::Value::String(number)) => number.clone(),
+                            Some(number) => number.to_string(),
+                            None => String::new(),
+                        };
+                        Self::normalize(&number) == wanted
+                    })
+                    .ok_or(Excuse::NoParcel)?;
+                Parcel::from_feature(&feature, None)
+            }
+            Query::Address(address) => {
+                let layer = self.addresses.as_ref().ok_or(Excuse::NoService)?;
+                let clause = format!(
+                    "UPPER({}) LIKE '{}%' ESCAPE '{LIKE_ESCAPE}'",
+                    self.settings.address(),
+                    Self::escape(&Self::quote(&address))
+                );
+                let point = self.first(layer, clause, None).await?;
+                let found = point
+                    .pointer(&format!("/properties/{}", self.settings.address()))
+                    .and_then(serde_json::Value::as_str)
+                    .map_or(address.clone(), str::to_string);
+                let coordinates = point
+                    .pointer("/geometry/coordinates")
+                    .and_then(serde_json::Value::as_array)
+                    .ok_or(Excuse::NoParcel)?;
+                let (Some(longitude), Some(latitude)) = (
+                    coordinates.first().and_then(serde_json::Value::as_f64),
+                    coordinates.get(1).and_then(serde_json::Value::as_f64),
+                ) else {
+                    return Err(Excuse::NoParcel.into());
+                };
+                self.at(longitude, latitude, Some(found)).await
+            }
+        }
+    }
+
+    /// The `at` method looks up the parcel under `longitude` and `latitude`, noting `address`
+    /// as the address asked for.
+    ///
+    /// Will [`Excuse::NoParcel`] if there is none, and fails as [`Listing::fetch`] does.
+    async fn at(&self, longitude: f64, latitude: f64, address: Option<String>) -> Arrive<Parcel> {
+        let point = format!("{longitude},{latitude}");
+        let feature = self
+            .first(&self.parcels, "1=1".to_string(), Some(point))
+            .await?;
+        Parcel::from_feature(&feature, address)
+    }
+
+    /// The `first` method asks `layer` for the first feature matching the `clause`, and under
+    /// the `point`, if any, in geographic coordinates, as GeoJSON.
+    ///
+    /// Will [`Excuse::NoParcel`] if there is none, and fails as [`Listing::fetch`] does.
+    async fn first(
+        &self,
+        layer: &Listing,
+        clause: String,
+        point: Option<String>,
+    ) -> Arrive<serde_json::Value> {
+        self.features(layer, clause, point, 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Excuse::NoParcel.into())
+    }
+
+    /// The `features` method asks `layer` for up to `count` features matching the `clause`, and
+    /// under the `point`, if any, in geographic coordinates, as GeoJSON.
+    ///
+    /// Fails as [`Listing::fetch`] does.
+    async fn features(
+        &self,
+        layer: &Listing,
+        clause: String,
+        point: Option<String>,
+        count: usize,
+    ) -> Arrive<Vec<serde_json::Value>> {
+        let mut query = vec![
+            ("where", clause),
+            ("outFields", "*".to_string()),
+            ("outSR", "4326".to_string()),
+            ("resultRecordCount", count.to_string()),
+            ("f", "geojson".to_string()),
+        ];
+        if let Some(point) = point {
+            query.extend([
+                ("geometry", point),
+                ("geometryType", "esriGeometryPoint".to_string()),
+                ("inSR", "4326".to_string()),
+                ("spatialRel", "esriSpatialRelIntersects".to_string()),
+            ]);
+        }
+        let body = layer.fetch(&query).await?;
+        Ok(body
+            .get("features")
+            .and_then(serde_json::Value::as_array)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// The `quote` method readies `text` for a string in a where clause, in upper case, with
+    /// each quote doubled.
+    fn quote(text: &str) -> String {
+        text.trim().to_uppercase().replace('\'', "''")
+    }
+
+    /// The `normalize` method returns the letters and digits of a map and tax lot number in
+    /// `text`, in upper case, leaving out the separators, as described in the [`Parcels`] docs.
+    fn normalize(text: &str) -> String {
+        text.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+
+    /// The `is_field` method returns `true` if `name` is a plain field name, fit to go in a
+    /// where clause: letters, digits and underscores, not starting with a digit, with dots
+    /// between the parts of a name qualified by its table.
+    fn is_field(name: &str) -> bool {
+        name.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    }
+
+    /// The `escape` method puts [`LIKE_ESCAPE`] before each wildcard of a `LIKE` pattern in
+    /// `text`, and before the escape itself, so that each matches only itself.
+    fn escape(text: &str) -> String {
+        text.chars().fold(String::new(), |mut escaped, c| {
+            if matches!(c, '%' | '_' | LIKE_ESCAPE) {
+                escaped.push(LIKE_ESCAPE);
+            }
+            escaped.push(c);
+            escaped
+        })
+    }
+}
+
+/// The `Query` enum holds what the user looks a parcel up by, as described in the [`Parcels`]
+/// docs.
+///
+/// * The `Address` variant holds a street address, or the start of one.
+/// * The `Taxlot` variant holds a map and tax lot number.
+/// * The `Point` variant holds the longitude and latitude of a press on the map, in degrees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// The `Address` variant indicates a lookup by address.
+    Address(String),
+    /// The `Taxlot` variant indicates a lookup by map and tax lot number.
+    Taxlot(String),
+    /// The `Point` variant indicates a lookup by place.
+    Point(f64, f64),
+}
+
+impl Query {
+    /// The `parse` method reads `text` as a map and tax lot number if it is one word holding a
+    /// digit and at least [`TAXLOT_LENGTH`] letters and digits, like `36-05-17-BC-01100`, and
+    /// as an address otherwise, since an address has a street name after the number.  A house
+    /// number alone, like `123`, is too short for a map and tax lot number, and reads as the
+    /// start of an address.  Returns [`None`] for empty text.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let taxlot = !text.contains(char::is_whitespace)
+            && text.contains(|c: char| c.is_ascii_digit())
+            && text.chars().filter(char::is_ascii_alphanumeric).count() >= TAXLOT_LENGTH;
+        match taxlot {
+            true => Some(Self::Taxlot(text.to_string())),
+            false => Some(Self::Address(text.to_string())),
+        }
+    }
+}
+
+/// The `Parcel` struct holds a parcel found by [`Parcels::find`].
+///
+/// * The `properties` field holds the fields of the parcel, by name.
+/// * The `geometry` field holds the outline of the parcel, as GeoJSON.
+/// * The `address` field holds the address the parcel was looked up by, if any.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Parcel {
+    properties: serde_json::Map<String, serde_json::Value>,
+    geometry: serde_json::Value,
+    address: Option<String>,
+}
+
+impl Parcel {
+    /// The `from_feature` method reads a parcel from a GeoJSON `feature`, noting `address` as
+    /// the address asked for.
+    ///
+    /// Will [`Excuse::NoParcel`] if the feature has no outline.
+    pub fn from_feature(feature: &serde_json::Value, address: Option<String>) -> Arrive<Self> {
+        let geometry = feature
+            .get("geometry")
+            .filter(|geometry| !geometry.is_null())
+            .cloned()
+            .ok_or(Excuse::NoParcel)?;
+        let properties = feature
+            .get("properties")
+            .and_then(serde_json::Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self {
+            properties,
+            geometry,
+            address,
+        })
+    }
+
+    /// The `value` method returns the field `name` of the parcel as text, or an empty string if
+    /// it has none.
+    pub fn value(&self, name: &str) -> String {
+        match self.properties.get(name) {
+            Some(serde_json::Value::String(text)) => text.clone(),
+            Some(serde_json::Value::Null) | None => String::new(),
+            Some(value) => value.to_string(),
+        }
+    }
+
+    /// The `lines` method describes the parcel for the user, one line each for the number of
+    /// the parcel, the fields and the links named by `settings`, as described in the
+    /// [`Parcels`] docs.
+    pub fn lines(&self, settings: &Lookup) -> Vec<String> {
+        let mut lines = vec![match &self.address {
+            Some(address) => format!("Parcel {} at {address}", self.value(settings.taxlot())),
+            None => format!("Parcel {}", self.value(settings.taxlot())),
+        }];
+        let fields = match settings.fields().is_empty() {
+            true => self.properties.keys().cloned().collect::<Vec<String>>(),
+            false => settings.fields().clone(),
+        };
+        lines.extend(
+            fields
+                .iter()
+                .map(|field| format!("  {field}: {}", self.value(field))),
+        );
+        lines.extend(settings.links().iter().map(|(name, template)| {
+            let link = self
+                .properties
+                .keys()
+                .fold(template.clone(), |link, field| {
+                    link.replace(&format!("{{{field}}}"), &Self::encode(&self.value(field)))
+                });
+            format!("  {name}: {link}")
+        }));
+        lines
+    }
+}
+
+impl Parcel {
+    /// The `encode` method percent-encodes `text` for a link, leaving only the characters an
+    /// address never reserves: letters, digits, `-`, `.`, `_` and `~`.
+    fn encode(text: &str) -> String {
+        text.bytes().fold(String::new(), |mut encoded, byte| {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    encoded.push(char::from(byte))
+                }
+                byte => encoded.push_str(&format!("%{byte:02X}")),
+            }
+            encoded
+        })
+    }
+}
+
+/// The `TAXLOT_LENGTH` constant holds the fewest letters and digits in a map and tax lot number,
+/// as read by [`Query::parse`].  The township, range and section alone take six.
+pub const TAXLOT_LENGTH: usize = 8;
+
+/// The `LIKE_ESCAPE` constant holds the character that marks a wildcard of a `LIKE` pattern as
+/// itself, in the where clauses [`Parcels`] sends.
+const LIKE_ESCAPE: char = '\\';
+
+/// The `TAXLOT_MATCHES` constant holds the most parcels a lookup by map and tax lot number asks
+/// for, to find the one whose number matches without its separators.
+pub const TAXLOT_MATCHES: usize = 10;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taxlots_match_without_separators() {
+        assert_eq!(Parcels::normalize("36-05-17-bc-01100"), "360517BC01100");
+        assert_eq!(Parcels::normalize(" 360517BC 01100 "), "360517BC01100");
+        assert_eq!(Parcels::normalize("--"), "");
+    }
+
+    #[test]
+    fn field_names_are_plain() {
+        assert!(Parcels::is_field("MAPTAXLOT"));
+        assert!(Parcels::is_field("parcels.Situs_Addr1"));
+        assert!(!Parcels::is_field("1ST"));
+        assert!(!Parcels::is_field("A) OR (1=1"));
+        assert!(!Parcels::is_field(""));
+        assert!(!Parcels::is_field("parcels."));
+    }
+
+    #[test]
+    fn link_values_are_encoded() {
+        assert_eq!(Parcel::encode("360517BC01100"), "360517BC01100");
+        assert_eq!(Parcel::encode("100 NW A & B"), "100%20NW%20A%20%26%20B");
+        assert_eq!(Parcel::encode("é"), "%C3%A9");
+    }
+}
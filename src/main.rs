@@ -1,14 +1,81 @@
-use bea_egui::{trace_init, App, Arrive};
+#[cfg(not(target_arch = "wasm32"))]
+use bea_egui::{
+    trace_init, write_default_config, App, Arrive, Blame, Bundle, Cli, Context, Courier, Crash,
+    Export, Exporter, Finder, Home, Layers, Plugins, Settings, UserEvent,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use clap::Parser;
+#[cfg(not(target_arch = "wasm32"))]
 use winit::event_loop;
 
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    bea_egui::run_web();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
 async fn main() -> Arrive<()> {
-    trace_init();
-    let event_loop = event_loop::EventLoop::<accesskit_winit::Event>::with_user_event().build()?;
+    let cli = Cli::parse();
+    // Logging starts before the app reads the config, so read the startup settings first.  Any
+    // problems reading it wait for the app, which reads it again and reports them.
+    let home = Home::new().with_config(cli.config().clone());
+    let (config, _) = Layers::new(cli.clone(), home.clone()).load();
+    let settings = Settings::load(&config);
+    let startup = settings.startup();
+    // The exporter flushes when dropped, so it lives until main returns.
+    let exporter = Exporter::start(settings.otlp());
+    let recent = trace_init(
+        startup.log_level().as_deref(),
+        *startup.log_format(),
+        settings.logging(),
+        exporter.as_ref().ok().and_then(Option::as_ref),
+    );
+    if let Err(e) = &exporter {
+        tracing::warn!("Exporting to the collector is off: {e}");
+    }
+    Crash::new(Crash::dir(&home), recent).install();
+    if let Some(path) = cli.write_default_config() {
+        let path = match path {
+            Some(path) => path.clone(),
+            None => home.config(),
+        };
+        return write_default_config(&path).inspect_err(|e| eprintln!("{}", e.report()));
+    }
+    // Headless mode with a picture to export draws it and exits, without an event loop, which a
+    // server without a desktop could not start.
+    if let (true, Some(export)) = (*startup.headless(), Export::from_cli(&cli)) {
+        export
+            .run(&settings, &home, &Plugins::default())
+            .await
+            .context("exporting the map")
+            .inspect_err(|e| eprintln!("{}", e.report()))?;
+        return Ok(());
+    }
+    let bundle = Bundle::new(home);
+    if let Some(path) = cli.export_bundle() {
+        bundle
+            .export(path)
+            .context("exporting the bundle")
+            .inspect_err(|e| eprintln!("{}", e.report()))?;
+        return Ok(());
+    }
+    if let Some(path) = cli.import_bundle() {
+        bundle
+            .import(path)
+            .context("importing the bundle")
+            .inspect_err(|e| eprintln!("{}", e.report()))?;
+        return Ok(());
+    }
+    let event_loop = event_loop::EventLoop::<UserEvent>::with_user_event()
+        .build()
+        .map_err(Blame::from)
+        .inspect_err(|e| eprintln!("{}", e.report()))?;
     let proxy = event_loop.create_proxy();
+    Finder::listen(Courier::new(proxy.clone()));
     event_loop.set_control_flow(event_loop::ControlFlow::Wait);
 
-    let mut app = App::new(proxy);
+    let mut app = App::new(proxy, cli);
     event_loop.run_app(&mut app)?;
 
     Ok(())
@@ -1,72 +1,615 @@
+use crate::{
+    Arrive, Basemap, Cache, Excuse, Extent, Fetcher, Figure, Glide, Home, Scenery, Stamp, Store,
+    OFFSCREEN_FORMAT, STORE_FILE,
+};
+use galileo::galileo_types::cartesian::{CartesianPoint2d, Point2d, Size};
 use galileo::galileo_types::geo::NewGeoPoint;
+use std::collections::{HashMap, HashSet};
+use std::path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use winit::dpi;
 
+/// The `Map` struct draws a [`galileo::Map`] in a window.
+///
+/// # Sharing the card between maps
+///
+/// Each map window keeps a renderer of its own, since a renderer draws on one surface and keeps
+/// textures the size of it.  Everything else we share where we can.  The [`crate::Gpu`] hands
+/// every window the same device and queue, so the card holds one set of resources for the app,
+/// and the tiles of the base map come from one [`Tiles`] layer for all the windows, made once
+/// by [`Map::tiles`].  A tile downloaded, decoded and packed for the card by the first window is
+/// there for the second, instead of each window fetching and holding its own copy, which made
+/// the memory of the app grow by a full tile cache with each window opened.  The layer sits
+/// behind a lock, and each window loads it for its own view before drawing.
+///
+/// * The `event_processor` field holds the [`galileo`] controls for the map.
+/// * The `renderer` field holds the renderer drawing on the surface of the window.
+/// * The `map` field holds the [`galileo::Map`], with its view and layers.
+/// * The `damaged` field holds the flag raised when the map asks to draw again, as described in
+///   the [`crate::Lens`] docs.
+/// * The `base` field holds the number of layers under the open data files: the base map, the
+///   scene, and the layers of any plugins, as described in [`Map::show`].
+/// * The `scene` field holds whether the map shows a scene, from [`Map::reveal`].
+/// * The `figures` field holds the open data files the map shows, for their icons.
+/// * The `stamp` field holds the pass drawing the icons over the map, from [`Map::adorn`].
+/// * The `scale` field holds the scale to pick the icons at, from [`Map::rescale`].
+/// * The `format` field holds the texture format the renderer draws the window in.
 pub struct Map {
     event_processor: galileo::control::EventProcessor,
     renderer: Arc<RwLock<galileo::render::WgpuRenderer>>,
     map: Arc<RwLock<galileo::Map>>,
+    damaged: Arc<AtomicBool>,
+    base: usize,
+    scene: bool,
+    figures: Vec<Figure>,
+    stamp: Option<Arc<Stamp>>,
+    scale: f64,
+    format: wgpu::TextureFormat,
 }
 
 impl Map {
+    /// The `tiles` method creates the [`Tiles`] layer of the base map described by `basemap`
+    /// from the `[map]` table of the config, for every map window to share.  Downloaded tiles are
+    /// kept in the [`Cache::Tiles`] directory of `home`, under the platform cache directory, so
+    /// that they survive a restart without cluttering the working directory.  Before we start
+    /// downloading, [`prune`] trims the tiles already there to the `cache_size` of the
+    /// [`Basemap`].  The [`Fetcher`] keeps the cache index in the [`Store`] of `home`, if it
+    /// opens, as the tiles are used.
+    pub fn tiles(basemap: &Basemap, home: &Home) -> Tiles {
+        let tiles = Cache::Tiles.dir(home);
+        let mut store = match Store::open(&home.data().join(STORE_FILE)) {
+            Ok(store) => Some(store),
+            Err(e) => {
+                tracing::warn!("Could not open the store for the tile cache index: {e}");
+                None
+            }
+        };
+        if *basemap.cache_size() > 0 {
+            if let Err(e) = prune(&tiles, basemap.cache_size() * 1024 * 1024, store.as_mut()) {
+                tracing::warn!("Could not trim the tile cache: {e}");
+            }
+        }
+        let layer = galileo::layer::RasterTileLayer::new(
+            galileo::TileSchema::web(MAX_ZOOM),
+            Fetcher::new(basemap, &tiles, store),
+            None,
+        );
+        tracing::info!("Base map: {}", basemap.attribution());
+        Arc::new(RwLock::new(layer))
+    }
+
+    /// The `new` method creates a map drawing to `surface`, opening on the view described by
+    /// `basemap` from the `[map]` table of the config, with the shared `tiles` layer from
+    /// [`Map::tiles`] as the base map.
+    ///
+    /// The renderer draws at `samples` a pixel, the count from [`crate::Canvas::sample_count`].
+    ///
+    /// Will [`crate::Excuse::NoResolution`] if the tile schema has no resolution for the zoom
+    /// level, which the caller should treat as one layer failing rather than the app.
     pub fn new(
+        basemap: &Basemap,
+        tiles: &Tiles,
         window: Arc<winit::window::Window>,
         device: Arc<wgpu::Device>,
         surface: Arc<wgpu::Surface<'static>>,
         queue: Arc<wgpu::Queue>,
         config: wgpu::SurfaceConfiguration,
-    ) -> Self {
-        let renderer = galileo::render::WgpuRenderer::new_with_device_and_surface(
+        samples: u32,
+    ) -> Arrive<Self> {
+        let format = config.format;
+        let mut renderer = galileo::render::WgpuRenderer::new_with_device_and_surface(
             device, surface, queue, config,
         );
+        renderer.set_sample_count(samples);
+        Self::assemble(basemap, tiles, renderer, Some(window), format)
+    }
+
+    /// The `offscreen` method creates a map with no window, for a headless [`crate::Export`],
+    /// with a renderer drawing into a texture of `width` by `height` pixels on `device`.  The map
+    /// opens on `extent`, if given, fitted to the size of the picture as described in
+    /// [`Map::frame`], and otherwise on the view described by `basemap`.
+    ///
+    /// Will [`crate::Excuse::NoResolution`] if the tile schema has no resolution for the zoom
+    /// level.
+    pub fn offscreen(
+        basemap: &Basemap,
+        tiles: &Tiles,
+        extent: Option<&Extent>,
+        width: u32,
+        height: u32,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+    ) -> Arrive<Self> {
+        let renderer = galileo::render::WgpuRenderer::new_with_device_and_texture_rt(
+            device,
+            queue,
+            Size::new(width, height),
+        );
+        let map = Self::assemble(basemap, tiles, renderer, None, OFFSCREEN_FORMAT)?;
+        match extent {
+            Some(extent) => map.frame(extent, width, height),
+            None => map.resize(dpi::PhysicalSize::new(width, height)),
+        }
+        Ok(map)
+    }
+
+    /// The `assemble` method puts a map together around `renderer`, drawing in `format`, on the
+    /// view described by `basemap` with the shared `tiles` layer, asking `window` to redraw when
+    /// the map changes, if there is one.
+    fn assemble(
+        basemap: &Basemap,
+        tiles: &Tiles,
+        renderer: galileo::render::WgpuRenderer,
+        window: Option<Arc<winit::window::Window>>,
+        format: wgpu::TextureFormat,
+    ) -> Arrive<Self> {
         let renderer = Arc::new(RwLock::new(renderer));
         let mut event_processor = galileo::control::EventProcessor::default();
         event_processor.add_handler(galileo::control::MapController::default());
 
-        let builder = galileo::MapBuilder::new();
-        builder
-            .center(galileo::galileo_types::geo::impls::GeoPoint2d::latlon(
-                42.4435, -123.3260,
-            ))
-            .resolution(galileo::TileSchema::web(18).lod_resolution(12).unwrap())
-            .with_raster_tiles(
-                |index| {
-                    format!(
-                        "https://tile.openstreetmap.org/{}/{}/{}.png",
-                        index.z, index.x, index.y
-                    )
-                },
-                galileo::TileSchema::web(18),
-            );
-
-        let view = galileo::MapView::new(
-            &galileo::galileo_types::geo::impls::GeoPoint2d::latlon(42.4435, -123.3260),
-            galileo::TileSchema::web(18).lod_resolution(13).unwrap(),
-        );
+        let [latitude, longitude] = *basemap.center();
+        let center = galileo::galileo_types::geo::impls::GeoPoint2d::latlon(latitude, longitude);
+        // The web schema has a resolution for every level up to the maximum, so the clamped
+        // level should always find one.
+        let zoom = (*basemap.zoom()).min(MAX_ZOOM);
+        let schema = galileo::TileSchema::web(MAX_ZOOM);
+        let resolution = schema
+            .lod_resolution(zoom)
+            .ok_or(crate::Blame::from(Excuse::NoResolution))?;
+        let view = galileo::MapView::new(&center, resolution);
+        let layer: Box<dyn galileo::layer::Layer> = Box::new(tiles.clone());
 
-        let tile_source = |index: &galileo::tile_scheme::TileIndex| {
-            format!(
-                "https://tile.openstreetmap.org/{}/{}/{}.png",
-                index.z, index.x, index.y
-            )
+        let damaged = Arc::new(AtomicBool::new(true));
+        let messenger = Courier {
+            window,
+            damaged: damaged.clone(),
         };
 
-        let layer = Box::new(galileo::MapBuilder::create_raster_tile_layer(
-            tile_source,
-            galileo::TileSchema::web(18),
-        ));
-
-        let messenger = galileo::winit::WinitMessenger::new(window);
-
         let map = Arc::new(RwLock::new(galileo::Map::new(
             view,
             vec![layer],
             Some(messenger),
         )));
 
-        Self {
+        Ok(Self {
             event_processor,
             renderer,
             map,
+            damaged,
+            base: 1,
+            scene: false,
+            figures: Vec::new(),
+            stamp: None,
+            scale: 1.0,
+            format,
+        })
+    }
+
+    /// The `damaged` method returns `true` if the map has asked to draw again since the last
+    /// call, as when a tile arrives, and lowers the flag.  See the [`crate::Lens`] docs.
+    pub fn damaged(&self) -> bool {
+        self.damaged.swap(false, Ordering::AcqRel)
+    }
+
+    /// The `scroll` method applies a [`Glide`] from the scroll wheel to the map view.
+    /// A [`Glide::Zoom`] zooms around the `cursor` position, and a [`Glide::Pan`] moves the map
+    /// by the given number of pixels.  The interface scale is not our concern here, so
+    /// [`Glide::UiZoom`] does nothing.
+    pub fn scroll(&self, glide: &Glide, cursor: dpi::PhysicalPosition<f64>) {
+        let mut map = match self.map.write() {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::warn!("Map lock poisoned: {e}");
+                return;
+            }
+        };
+        let view = map.view().clone();
+        let view = match glide {
+            Glide::Zoom(factor) => view.zoom(*factor, Point2d::new(cursor.x, cursor.y)),
+            Glide::Pan(x, y) => {
+                let from = Point2d::new(cursor.x, cursor.y);
+                let to = Point2d::new(cursor.x + x, cursor.y + y);
+                view.translate_by_pixels(from, to)
+            }
+            Glide::UiZoom(_) => return,
+        };
+        map.set_view(view);
+    }
+
+    /// The `render` method draws the map onto `view`, a view of the texture of the next frame,
+    /// loading any layers that have new data first, with the icons of the open data files over
+    /// it, as in [`Map::stamp`].
+    pub fn render(&self, view: &wgpu::TextureView) {
+        let (renderer, map) = match (self.renderer.read(), self.map.read()) {
+            (Ok(renderer), Ok(map)) => (renderer, map),
+            _ => {
+                tracing::warn!("Map lock poisoned, skipping the frame.");
+                return;
+            }
+        };
+        map.load_layers();
+        renderer.render_to_texture_view(&map, view);
+        self.stamp(map.view(), view, self.format);
+    }
+
+    /// The `snapshot` method draws the map with `renderer` onto `target`, a texture of `width`
+    /// by `height` pixels away from the window, as described in [`crate::Offscreen`], with the
+    /// icons of the open data files over it.  The view stretches to the size of the texture while
+    /// covering the same ground, and goes back as it was once the picture is drawn.
+    pub fn snapshot(
+        &self,
+        renderer: &galileo::render::WgpuRenderer,
+        target: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        let mut map = match self.map.write() {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::warn!("Map lock poisoned: {e}");
+                return;
+            }
+        };
+        let view = map.view().clone();
+        let across = view.size().width();
+        let resolution = match across > 0.0 {
+            true => view.resolution() * across / f64::from(width),
+            false => view.resolution(),
+        };
+        map.set_view(
+            view.with_resolution(resolution)
+                .with_size(Size::new(f64::from(width), f64::from(height))),
+        );
+        map.load_layers();
+        renderer.render_to_texture_view(&map, target);
+        self.stamp(map.view(), target, OFFSCREEN_FORMAT);
+        map.set_view(view);
+    }
+
+    /// The `stamp` method draws the icons of the points of the open data files onto `target`, in
+    /// `format`, where `view` places them, through the [`Stamp`] of the map, if any.
+    fn stamp(
+        &self,
+        view: &galileo::MapView,
+        target: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+    ) {
+        let (Some(stamp), Some(viewport)) = (&self.stamp, Viewport::from_view(view)) else {
+            return;
+        };
+        let size = view.size();
+        let size = dpi::PhysicalSize::new(size.width() as u32, size.height() as u32);
+        stamp.lay(target, format, size, &viewport, &self.figures, self.scale);
+    }
+
+    /// The `adorn` method hands the map the [`Stamp`] drawing the icons of point symbols, or
+    /// takes it away with [`None`], as when the icons change.
+    pub fn adorn(&mut self, stamp: Option<Arc<Stamp>>) {
+        self.stamp = stamp;
+    }
+
+    /// The `rescale` method sets the scale to pick the icons at, as from
+    /// [`crate::Lens::symbol_scale`].
+    pub fn rescale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    /// The `add_layers` method draws `layers` over the layers the map has, in order, as for the
+    /// layers of a [`crate::LayerFactory`].  The layers go under the open data files, and count
+    /// toward the `base` of the map.
+    pub fn add_layers(&mut self, layers: Vec<Box<dyn galileo::layer::Layer>>) {
+        match self.map.write() {
+            Ok(mut map) => {
+                let collection = map.layers_mut();
+                for layer in layers {
+                    collection.insert(self.base, layer);
+                    self.base += 1;
+                }
+            }
+            Err(e) => tracing::warn!("Map lock poisoned: {e}"),
+        }
+    }
+
+    /// The `reveal` method draws the layer of `scene` just over the base map, under the layers of
+    /// any plugins and the open data files, in place of the scene the map showed before, or
+    /// shows no scene.  Every map shares the layer of a scene, as for the base map.
+    pub fn reveal(&mut self, scene: Option<&Scenery>) {
+        match self.map.write() {
+            Ok(mut map) => {
+                let collection = map.layers_mut();
+                if self.scene {
+                    collection.remove(1);
+                    self.base -= 1;
+                }
+                if let Some(scene) = scene {
+                    collection.insert(1, Box::new(scene.clone()));
+                    self.base += 1;
+                }
+                self.scene = scene.is_some();
+            }
+            Err(e) => tracing::warn!("Map lock poisoned: {e}"),
+        }
+    }
+
+    /// The `show` method draws the layer of each of `figures` over the base map and the layers
+    /// of any plugins, in order, in place of the figures the map showed before.  Every map shares
+    /// the layer of a figure, as described in [`crate::Figure`], so showing a figure in a second
+    /// window costs no more than a handle to it.  The map keeps the figures for their icons, as
+    /// in [`Map::stamp`].
+    pub fn show(&mut self, figures: &[Figure]) {
+        match self.map.write() {
+            Ok(mut map) => {
+                let collection = map.layers_mut();
+                collection.truncate(self.base);
+                figures
+                    .iter()
+                    .for_each(|figure| collection.push(figure.layer()));
+            }
+            Err(e) => tracing::warn!("Map lock poisoned: {e}"),
         }
+        self.figures = figures.to_vec();
+    }
+
+    /// The `locate` method returns the longitude and latitude of the place on the map under
+    /// `position` in the window, or [`None`] before the view has a size.
+    pub fn locate(&self, position: dpi::PhysicalPosition<f64>) -> Option<(f64, f64)> {
+        let map = match self.map.read() {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::warn!("Map lock poisoned: {e}");
+                return None;
+            }
+        };
+        let point = map
+            .view()
+            .screen_to_map(Point2d::new(position.x, position.y))?;
+        Some(Extent::unproject(point.x(), point.y()))
     }
+
+    /// The `viewport` method returns the [`Viewport`] placing ground on the window as the map
+    /// shows it now, or [`None`] before the view has a size.
+    pub fn viewport(&self) -> Option<Viewport> {
+        let map = match self.map.read() {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::warn!("Map lock poisoned: {e}");
+                return None;
+            }
+        };
+        Viewport::from_view(map.view())
+    }
+
+    /// The `frame` method sets the view of the map to show all of `extent` in a picture of
+    /// `width` by `height` pixels, centered, at the resolution of whichever side fits more
+    /// tightly, so a picture of a different shape than the extent shows extra ground on two
+    /// sides rather than stretching.
+    pub fn frame(&self, extent: &Extent, width: u32, height: u32) {
+        let mut map = match self.map.write() {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::warn!("Map lock poisoned: {e}");
+                return;
+            }
+        };
+        let (x_min, y_min, x_max, y_max) = extent.to_mercator();
+        let resolution = f64::max(
+            (x_max - x_min) / f64::from(width.max(1)),
+            (y_max - y_min) / f64::from(height.max(1)),
+        );
+        // Halfway between the edges in latitude is south of halfway on the map, so we find the
+        // center in the projection rather than with `Extent::center`.
+        let center = Point2d::new((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+        let view = galileo::MapView::new_projected(&center, resolution)
+            .with_size(Size::new(f64::from(width), f64::from(height)));
+        map.set_view(view);
+    }
+
+    /// The `extent` method returns the ground the map shows, as described in
+    /// [`crate::Synopsis`], or [`None`] before the view has a size.
+    pub fn extent(&self) -> Option<Extent> {
+        let map = match self.map.read() {
+            Ok(map) => map,
+            Err(e) => {
+                tracing::warn!("Map lock poisoned: {e}");
+                return None;
+            }
+        };
+        let bbox = map.view().get_bbox()?;
+        Some(Extent::from_mercator(
+            bbox.x_min(),
+            bbox.y_min(),
+            bbox.x_max(),
+            bbox.y_max(),
+        ))
+    }
+
+    /// The `resize` method fits the renderer and the view of the map to a window of `size`.  A
+    /// minimized window reports a size of zero, which we ignore until it comes back.
+    pub fn resize(&self, size: dpi::PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        let (width, height) = (size.width, size.height);
+        match self.renderer.write() {
+            Ok(mut renderer) => renderer.resize(Size::new(width, height)),
+            Err(e) => tracing::warn!("Renderer lock poisoned: {e}"),
+        }
+        match self.map.write() {
+            Ok(mut map) => map.set_size(Size::new(f64::from(width), f64::from(height))),
+            Err(e) => tracing::warn!("Map lock poisoned: {e}"),
+        }
+    }
+
+    /// The `set_samples` method has the renderer draw at `samples` a pixel from the next frame,
+    /// after the count of the [`crate::Canvas`] changes.
+    pub fn set_samples(&self, samples: u32) {
+        match self.renderer.write() {
+            Ok(mut renderer) => renderer.set_sample_count(samples),
+            Err(e) => tracing::warn!("Renderer lock poisoned: {e}"),
+        }
+    }
+}
+
+/// The galileo types inside `Map` do not implement [`std::fmt::Debug`], so we print the name
+/// of the struct and leave it at that.
+impl std::fmt::Debug for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Map").finish_non_exhaustive()
+    }
+}
+
+/// The `Viewport` struct places longitude and latitude on the window of a [`Map`], for drawing
+/// over the map, as the annotations of [`crate::Ink`] do.  The view of [`galileo`] only answers
+/// the other way, from the window to the ground, but the base map is flat in web mercator, so
+/// the way from a pixel to the ground is the same step for every pixel.  We take the steps for
+/// a pixel across and a pixel down from the view, and [`Viewport::place`] undoes them.
+///
+/// * The `origin` field holds the corner of the window, in web mercator meters.
+/// * The `across` field holds the ground covered by a step of a pixel to the right.
+/// * The `down` field holds the ground covered by a step of a pixel down.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    origin: (f64, f64),
+    across: (f64, f64),
+    down: (f64, f64),
+}
+
+impl Viewport {
+    /// The `new` method creates a viewport from the `origin`, `across` and `down` of the
+    /// [`Viewport`] docs, or returns [`None`] if the steps do not span the plane, as for a view
+    /// with no size.
+    pub fn new(origin: (f64, f64), across: (f64, f64), down: (f64, f64)) -> Option<Self> {
+        let determinant = across.0 * down.1 - across.1 * down.0;
+        (determinant.is_finite() && determinant != 0.0).then_some(Self {
+            origin,
+            across,
+            down,
+        })
+    }
+
+    /// The `from_view` method returns the viewport of a map with `view`, or [`None`] before the
+    /// view has a size.
+    pub fn from_view(view: &galileo::MapView) -> Option<Self> {
+        let origin = view.screen_to_map(Point2d::new(0.0, 0.0))?;
+        let right = view.screen_to_map(Point2d::new(1.0, 0.0))?;
+        let down = view.screen_to_map(Point2d::new(0.0, 1.0))?;
+        Self::new(
+            (origin.x(), origin.y()),
+            (right.x() - origin.x(), right.y() - origin.y()),
+            (down.x() - origin.x(), down.y() - origin.y()),
+        )
+    }
+
+    /// The `place` method returns the position in the window, in physical pixels, of the ground
+    /// at `longitude` and `latitude`.
+    pub fn place(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        let (x, y) = Extent::project(longitude, latitude);
+        self.locate(x, y)
+    }
+
+    /// The `locate` method returns the position in the window, in physical pixels, of the ground
+    /// at `x` and `y` in web mercator meters, as the shapes of a [`Figure`] hold it.
+    pub fn locate(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = (x - self.origin.0, y - self.origin.1);
+        let determinant = self.across.0 * self.down.1 - self.across.1 * self.down.0;
+        (
+            (x * self.down.1 - y * self.down.0) / determinant,
+            (self.across.0 * y - self.across.1 * x) / determinant,
+        )
+    }
+}
+
+/// The `Tiles` type is the raster tile layer of the base map, shared by every map window behind
+/// a lock, as described in the [`Map`] docs.
+pub type Tiles = Arc<RwLock<galileo::layer::RasterTileLayer<Fetcher>>>;
+
+/// The `Courier` struct carries the requests of a [`galileo::Map`] to draw again.  Like the
+/// messenger [`galileo`] provides for [`winit`], it asks the window for a redraw, and it also
+/// raises the `damaged` flag of the [`Map`], so the [`crate::Lens`] knows the map has changed.
+///
+/// * The `window` field holds the window showing the map, or [`None`] for a map drawn offscreen.
+/// * The `damaged` field holds the flag shared with the [`Map`].
+struct Courier {
+    window: Option<Arc<winit::window::Window>>,
+    damaged: Arc<AtomicBool>,
+}
+
+impl galileo::messenger::Messenger for Courier {
+    fn request_redraw(&self) {
+        self.damaged.store(true, Ordering::Release);
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+}
+
+/// The `prune` function deletes the tiles under `dir` used longest ago until the rest fit in
+/// `limit` bytes.  A tile was last used when it was written, or when the index of `store` says
+/// it was, from [`Store::index`], whichever is later.  Entries of the index whose tiles are gone
+/// leave the index.  A missing `dir` has nothing to prune.
+///
+/// Will [`crate::Blame::Io`] if a file cannot be inspected or deleted, and
+/// [`crate::Blame::Sqlite`] if the index cannot be read or written.
+pub fn prune(dir: &path::Path, limit: u64, store: Option<&mut Store>) -> Arrive<()> {
+    let touched = match &store {
+        Some(store) => store.touched(Cache::Tiles)?,
+        None => HashMap::new(),
+    };
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(folder) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&folder) else {
+            continue;
+        };
+        for entry in entries {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                let path = entry.path();
+                let key = Fetcher::tile_key(dir, &path);
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |age| age.as_secs());
+                let used = touched.get(&key).copied().unwrap_or(0).max(modified);
+                files.push((used, metadata.len(), key, path));
+            }
+        }
+    }
+    let present = files
+        .iter()
+        .map(|(_, _, key, _)| key.as_str())
+        .collect::<HashSet<&str>>();
+    let mut gone = touched
+        .keys()
+        .filter(|key| !present.contains(key.as_str()))
+        .cloned()
+        .collect::<Vec<String>>();
+    let mut total = files.iter().map(|(_, len, _, _)| len).sum::<u64>();
+    files.sort_by_key(|(used, _, _, _)| *used);
+    let mut removed = 0;
+    for (_, len, key, path) in files {
+        if total <= limit {
+            break;
+        }
+        std::fs::remove_file(&path)?;
+        total -= len;
+        removed += 1;
+        gone.push(key);
+    }
+    if let Some(store) = store {
+        store.unindex(Cache::Tiles, &gone)?;
+    }
+    if removed > 0 {
+        tracing::info!("Removed {removed} tiles from the cache.");
+    }
+    Ok(())
 }
+
+/// The `MAX_ZOOM` constant holds the deepest zoom level of the base map.  Greater zoom levels
+/// in the config stop here.
+pub const MAX_ZOOM: u32 = 18;
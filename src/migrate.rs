@@ -0,0 +1,302 @@
+use crate::{Arrive, Context, Layers, PROFILES};
+use std::path;
+
+/// The `migrate` module provides the [`Migration`] struct and the [`migrate`] function, which
+/// upgrade config files written for older versions of the app.
+///
+/// # Keeping old configs working with `Migration`
+///
+/// The layout of the config changes as the app grows.  When a key moves, every config file in
+/// the wild still has it in the old place, and the user who set it years ago is in no position
+/// to know.  Rather than strand them, the config carries a `version` key, and on load we upgrade
+/// older files one step at a time until they reach [`CONFIG_VERSION`].  Files without a
+/// `version` key predate versioning, and count as version 1.
+///
+/// Each [`Migration`] in [`MIGRATIONS`] upgrades a file from one version to the next.  The
+/// steps so far:
+///
+/// * Version 1 to 2 moves `api_key` to `key` in the `[api]` table, and `tile_url` to `tile_url`
+///   in the `[map]` table, matching the sections of [`crate::Settings`].
+///
+/// Only the user's own file, from [`crate::Home::config`], is upgraded on disk.  The system and
+/// project files belong to the administrator and the checkout, so [`upgraded`] upgrades their
+/// text in memory as we read them, and leaves the files alone.
+///
+/// Before writing the upgraded file, [`migrate`] copies the original next to it, as in
+/// `config.toml.v1.bak`, so nothing the user wrote is lost.  The upgraded file does lose its
+/// comments, since the [`toml`] crate does not keep them, which is one more reason for the
+/// backup.  A file from a newer version of the app than this one is left alone, with a warning.
+/// So are YAML and JSON files, which come from deployment tooling that should write the current
+/// layout itself, so we warn that an old one needs upgrading by hand.
+///
+/// * The `version` field holds the version the migration upgrades from.
+/// * The `summary` field describes the change for the log.
+/// * The `apply` field holds the function that changes the table in place.
+#[derive(Debug, Clone, Copy, derive_getters::Getters)]
+pub struct Migration {
+    version: i64,
+    summary: &'static str,
+    apply: fn(&mut toml::Table),
+}
+
+impl Migration {
+    /// The `run` method applies the migration to `table`, and to each profile under
+    /// `[profile]`, since a profile can hold any key the top of the config can.
+    pub fn run(&self, table: &mut toml::Table) {
+        (self.apply)(table);
+        if let Some(toml::Value::Table(profiles)) = table.get_mut(PROFILES) {
+            for profile in profiles.values_mut() {
+                if let toml::Value::Table(profile) = profile {
+                    (self.apply)(profile);
+                }
+            }
+        }
+    }
+}
+
+/// The `config_version` function returns the config version of `table`, which is 1 if the
+/// `version` key is missing.
+pub fn config_version(table: &toml::Table) -> i64 {
+    table
+        .get(VERSION)
+        .and_then(|version| version.as_integer())
+        .unwrap_or(1)
+}
+
+/// The `upgrade` function applies each [`Migration`] that `table` has not had yet, sets the
+/// `version` key to [`CONFIG_VERSION`], and returns the migrations it ran.
+pub fn upgrade(table: &mut toml::Table) -> Vec<Migration> {
+    let from = config_version(table);
+    let steps = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version >= from)
+        .copied()
+        .collect::<Vec<Migration>>();
+    steps.iter().for_each(|migration| migration.run(table));
+    table.insert(VERSION.to_string(), toml::Value::Integer(CONFIG_VERSION));
+    steps
+}
+
+/// The `migrate` function upgrades the config file at `path` to [`CONFIG_VERSION`], after
+/// copying the original to a backup file.  Returns the path to the backup, or [`None`] if the
+/// file was already current.  A file that does not parse is left for [`crate::Layers`] to
+/// report.
+///
+/// Will [`crate::Blame::Io`] if reading, copying or writing the file fails, and
+/// [`crate::Blame::TomlSer`] if the upgraded table will not serialize.
+#[tracing::instrument]
+pub fn migrate(path: &path::Path) -> Arrive<Option<path::PathBuf>> {
+    match Layers::format(path) {
+        Some(config::FileFormat::Toml) => {}
+        Some(format) => {
+            by_hand(path, format);
+            return Ok(None);
+        }
+        None => return Ok(None),
+    }
+    let contents = std::fs::read_to_string(path)?;
+    let Ok(mut table) = contents.parse::<toml::Table>() else {
+        return Ok(None);
+    };
+    let from = config_version(&table);
+    if from > CONFIG_VERSION {
+        tracing::warn!(
+            "{} is from a newer version of the app (config version {from}), some settings may be ignored.",
+            path.display()
+        );
+        return Ok(None);
+    }
+    if from == CONFIG_VERSION {
+        return Ok(None);
+    }
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{from}.bak"));
+    let backup = path::PathBuf::from(backup);
+    std::fs::copy(path, &backup).with_context(|| format!("backing up {}", path.display()))?;
+    for migration in upgrade(&mut table) {
+        tracing::info!(
+            "Config version {}: {}.",
+            migration.version,
+            migration.summary
+        );
+    }
+    std::fs::write(path, toml::to_string_pretty(&table)?)
+        .with_context(|| format!("upgrading {}", path.display()))?;
+    tracing::info!(
+        "Upgraded {} to config version {CONFIG_VERSION}, the original is in {}.",
+        path.display(),
+        backup.display()
+    );
+    Ok(Some(backup))
+}
+
+/// The `upgraded` function returns `text`, read from the config file at `path`, upgraded to
+/// [`CONFIG_VERSION`] in memory, for a file the app must not write to.  The file itself stays
+/// as it is, with a note in the log for whoever owns it.  Text that is current, from a newer
+/// version, not TOML, or that does not parse comes back unchanged.
+pub fn upgraded(path: &path::Path, text: String) -> String {
+    match Layers::format(path) {
+        Some(config::FileFormat::Toml) => {}
+        Some(format) => {
+            by_hand(path, format);
+            return text;
+        }
+        None => return text,
+    }
+    let Ok(mut table) = text.parse::<toml::Table>() else {
+        return text;
+    };
+    let from = config_version(&table);
+    if from >= CONFIG_VERSION {
+        if from > CONFIG_VERSION {
+            tracing::warn!(
+                "{} is from a newer version of the app (config version {from}), some settings may be ignored.",
+                path.display()
+            );
+        }
+        return text;
+    }
+    upgrade(&mut table);
+    match toml::to_string_pretty(&table) {
+        Ok(upgraded) => {
+            tracing::info!(
+                "Read {} as config version {CONFIG_VERSION}, the file itself needs upgrading by its owner.",
+                path.display()
+            );
+            upgraded
+        }
+        Err(e) => {
+            tracing::warn!("Could not upgrade config at {}: {e}", path.display());
+            text
+        }
+    }
+}
+
+/// The `by_hand` function warns that the YAML or JSON config file at `path`, in `format`, needs
+/// each [`Migration`] it has not had applied by hand.
+fn by_hand(path: &path::Path, format: config::FileFormat) {
+    let from = config::Config::builder()
+        .add_source(config::File::from(path).format(format))
+        .build()
+        .ok()
+        .and_then(|config| config.get_int(VERSION).ok())
+        .unwrap_or(1);
+    MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version >= from)
+        .for_each(|migration| {
+            tracing::warn!(
+                "{} needs upgrading by hand: {}.",
+                path.display(),
+                migration.summary
+            )
+        });
+}
+
+/// The `services` function moves `api_key` and `tile_url` into the `[api]` and `[map]` tables.
+fn services(table: &mut toml::Table) {
+    for (old, section, new) in [("api_key", "api", "key"), ("tile_url", "map", "tile_url")] {
+        let Some(value) = table.remove(old) else {
+            continue;
+        };
+        let section = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(section) = section {
+            section.entry(new).or_insert(value);
+        }
+    }
+}
+
+/// The `MIGRATIONS` constant lists each [`Migration`] in order of the version it upgrades from.
+pub const MIGRATIONS: [Migration; 1] = [Migration {
+    version: 1,
+    summary: "moved api_key to [api] key and tile_url to [map] tile_url",
+    apply: services,
+}];
+
+/// The `CONFIG_VERSION` constant holds the version of the config layout this build of the app
+/// reads and writes.
+pub const CONFIG_VERSION: i64 = 2;
+
+/// The `VERSION` constant holds the key of the config version in the config.
+pub const VERSION: &str = "version";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `table` function parses `text` as a TOML table, for the tests below.
+    fn table(text: &str) -> toml::Table {
+        text.parse::<toml::Table>().expect("test TOML parses")
+    }
+
+    #[test]
+    fn missing_version_is_one() {
+        assert_eq!(config_version(&table("exit = \"Escape\"")), 1);
+        assert_eq!(config_version(&table("version = 2")), 2);
+    }
+
+    #[test]
+    fn upgrade_moves_keys() {
+        let mut old = table(
+            "api_key = \"secret\"\ntile_url = \"https://tiles\"\n[profile.kiosk]\napi_key = \"other\"\n",
+        );
+        let steps = upgrade(&mut old);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(config_version(&old), CONFIG_VERSION);
+        assert!(old.get("api_key").is_none());
+        assert_eq!(old["api"]["key"].as_str(), Some("secret"));
+        assert_eq!(old["map"]["tile_url"].as_str(), Some("https://tiles"));
+        assert_eq!(
+            old["profile"]["kiosk"]["api"]["key"].as_str(),
+            Some("other")
+        );
+    }
+
+    #[test]
+    fn upgrade_keeps_new_keys() {
+        let mut old = table("api_key = \"old\"\n[api]\nkey = \"new\"\n");
+        upgrade(&mut old);
+        assert_eq!(old["api"]["key"].as_str(), Some("new"));
+    }
+
+    #[test]
+    fn current_table_runs_nothing() {
+        let mut current = table("version = 2\n[api]\nkey = \"secret\"\n");
+        assert!(upgrade(&mut current).is_empty());
+    }
+
+    #[test]
+    fn migrate_backs_up_and_upgrades() {
+        let dir = std::env::temp_dir().join(format!("bea_egui-migrate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("config.toml");
+        let original = "api_key = \"secret\"\n";
+        std::fs::write(&path, original).expect("write config");
+        let backup = migrate(&path).expect("migrate").expect("backup written");
+        assert_eq!(std::fs::read_to_string(&backup).expect("backup"), original);
+        let upgraded = table(&std::fs::read_to_string(&path).expect("config"));
+        assert_eq!(config_version(&upgraded), CONFIG_VERSION);
+        assert_eq!(upgraded["api"]["key"].as_str(), Some("secret"));
+        // A second run finds the file current and leaves it alone.
+        assert!(migrate(&path).expect("migrate again").is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn upgraded_leaves_the_file() {
+        let dir = std::env::temp_dir().join(format!("bea_egui-upgraded-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("config.toml");
+        let original = "tile_url = \"https://tiles\"\n";
+        std::fs::write(&path, original).expect("write config");
+        let text = upgraded(&path, original.to_string());
+        assert_eq!(
+            table(&text)["map"]["tile_url"].as_str(),
+            Some("https://tiles")
+        );
+        assert_eq!(std::fs::read_to_string(&path).expect("config"), original);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,446 @@
+use crate::{Arrive, Blame, Fetch, Timeouts};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use web_time as time;
+
+/// The `network` module provides the [`Network`] struct, which keeps a log of the HTTP requests
+/// the app sends, and the [`Exchange`] struct, which describes one of them.
+///
+/// # Inspecting traffic with `Network`
+///
+/// When the map stays blank or a chart never fills in, the user calls us, and we ask whether the
+/// app is reaching the server at all.  The `Network` log answers without a packet sniffer.  Each
+/// request sent through [`Network::send`] becomes an [`Exchange`], noting the address, the
+/// status of the response, how long it took, how many bytes came back, and whether it came from
+/// a cache.  The last [`NETWORK_ENTRIES`] of them stay in memory, and
+/// [`crate::Act::ShowNetwork`] shows them as a table in a notice over the map.  To see the same
+/// requests in the latency figures of the debug overlay, call [`Network::send`] inside a span
+/// named for [`crate::Latency`].
+///
+/// Addresses to the BEA API carry the API key in the query, and the log goes into bug reports.
+/// [`Network::redact`] replaces the value of any query parameter named in [`REDACTED_PARAMS`],
+/// and any text given to [`Network::hide`], with `REDACTED` before the address goes in the log.
+/// The [`crate::App`] hides the API key from the config whenever it loads one.  Errors from
+/// [`reqwest`] carry the address too, and end up in retry messages and crash reports, so the
+/// conversion into [`Blame::Http`] redacts the address in the error the same way.
+///
+/// The tiles of the map travel through the tile layer of [`galileo`], which does its own
+/// fetching, and the [`crate::Fetcher`] it fetches through puts each tile from the server in
+/// the log as well.
+///
+/// ## Giving up on hung servers
+///
+/// A server that accepts the connection and then says nothing leaves a request waiting forever,
+/// and the user watching a spinner that never stops.  The `[network]` table of the config sets
+/// limits on each call, described in [`Timeouts`], and [`Network::configure`] applies them:
+///
+/// * The connect and read limits go on the [`reqwest::Client`] from [`Network::client`], which
+///   every request should be built from.  The client also names the app in the `User-Agent`
+///   header, as the usage policies of public services like the Nominatim geocoder and the
+///   OpenStreetMap tiles ask, so no request needs to set it.  In the browser, the page makes
+///   the connections and names itself, so only the deadline applies there.
+/// * The deadline for the whole call, from [`Network::deadline`], goes on by way of
+///   [`Network::within`].  [`Network::send`] holds the wait for the response to it, and a
+///   [`crate::Download`] holds each attempt to it, body and all.
+///
+/// A call that runs out of time fails as a [`Blame::Timeout`], holding an [`Overdue`] that names
+/// the address and the limit, rather than as one more [`Blame::Http`].  Timeouts are transient,
+/// so callers that retry will try again, and the user hears that the server was slow rather than
+/// that something broke.
+///
+/// There is one log for the whole app, and [`Network::global`] returns a handle to it.
+///
+/// * The `exchanges` field holds the most recent requests, oldest first.
+/// * The `hidden` field holds the text to redact wherever it appears, like the API key.
+/// * The `client` field holds the [`reqwest::Client`] with the connect and read limits.
+/// * The `timeouts` field holds the [`Timeouts`] from the config.
+#[derive(Debug, Clone)]
+pub struct Network {
+    exchanges: Arc<Mutex<VecDeque<Exchange>>>,
+    hidden: Arc<Mutex<Vec<String>>>,
+    client: Arc<Mutex<reqwest::Client>>,
+    timeouts: Arc<Mutex<Timeouts>>,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        let client = Self::builder().build().unwrap_or_default();
+        Self {
+            exchanges: Default::default(),
+            hidden: Default::default(),
+            client: Arc::new(Mutex::new(client)),
+            timeouts: Default::default(),
+        }
+    }
+}
+
+impl Network {
+    /// The `global` method returns a handle to the log shared by the app.
+    pub fn global() -> Self {
+        static NETWORK: OnceLock<Network> = OnceLock::new();
+        NETWORK.get_or_init(Network::default).clone()
+    }
+
+    /// The `configure` method applies `timeouts` to the calls made from now on, building a new
+    /// [`reqwest::Client`] with the connect and read limits.
+    ///
+    /// Will [`Blame::Http`] if the client cannot be built, in which case the old one stays.
+    pub fn configure(&self, timeouts: &Timeouts) -> Arrive<()> {
+        let client = Self::limit(Self::builder(), timeouts).build()?;
+        if let Ok(mut current) = self.client.lock() {
+            *current = client;
+        }
+        if let Ok(mut current) = self.timeouts.lock() {
+            *current = *timeouts;
+        }
+        tracing::trace!("Network limits: {timeouts:?}");
+        Ok(())
+    }
+
+    /// The `builder` method starts a [`reqwest::Client`] that names the app in the
+    /// `User-Agent` header of every request, as [`USER_AGENT`] does.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn builder() -> reqwest::ClientBuilder {
+        reqwest::Client::builder().user_agent(USER_AGENT)
+    }
+
+    /// The `builder` method starts a [`reqwest::Client`].  In the browser, the page names
+    /// itself in the `User-Agent` header, and a request may not change it.
+    #[cfg(target_arch = "wasm32")]
+    fn builder() -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+    }
+
+    /// The `limit` method puts the connect and read limits of `timeouts` on `builder`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn limit(mut builder: reqwest::ClientBuilder, timeouts: &Timeouts) -> reqwest::ClientBuilder {
+        if let Some(limit) = Timeouts::limit(*timeouts.connect()) {
+            builder = builder.connect_timeout(limit);
+        }
+        if let Some(limit) = Timeouts::limit(*timeouts.read()) {
+            builder = builder.read_timeout(limit);
+        }
+        builder
+    }
+
+    /// The `limit` method returns `builder` as it is, since the browser makes the connections
+    /// and gives us no say in how long they wait.  The deadline still holds each call.
+    #[cfg(target_arch = "wasm32")]
+    fn limit(builder: reqwest::ClientBuilder, _timeouts: &Timeouts) -> reqwest::ClientBuilder {
+        builder
+    }
+
+    /// The `client` method returns the [`reqwest::Client`] to build requests from, with the
+    /// limits from [`Network::configure`].  Clients share their connections, so the clone is
+    /// cheap.
+    pub fn client(&self) -> reqwest::Client {
+        self.client
+            .lock()
+            .map(|client| client.clone())
+            .unwrap_or_default()
+    }
+
+    /// The `deadline` method returns how long a call of kind `fetch` may take from start to
+    /// finish, or [`None`] for no limit.  Downloads get the `download` limit, and everything
+    /// else the `request` limit.
+    pub fn deadline(&self, fetch: Fetch) -> Option<time::Duration> {
+        let timeouts = self
+            .timeouts
+            .lock()
+            .map(|timeouts| *timeouts)
+            .unwrap_or_default();
+        match fetch {
+            Fetch::Downloads => Timeouts::limit(*timeouts.download()),
+            _ => Timeouts::limit(*timeouts.request()),
+        }
+    }
+
+    /// The `within` method runs `call`, a call of kind `fetch` to `url`, failing with
+    /// [`Blame::Timeout`] if it takes longer than [`Network::deadline`] allows.
+    pub async fn within<T>(
+        &self,
+        fetch: Fetch,
+        url: &str,
+        call: impl std::future::Future<Output = Arrive<T>>,
+    ) -> Arrive<T> {
+        let Some(limit) = self.deadline(fetch) else {
+            return call.await;
+        };
+        match crate::timeout(limit, call).await {
+            Some(result) => result,
+            None => Err(Overdue::new(self.redact(url), limit).into()),
+        }
+    }
+
+    /// The `blame` method converts `error`, from a call to `url`, into a [`Blame`], turning
+    /// timeouts from the client into a [`Blame::Timeout`] naming the limit that ran out.
+    pub fn blame(&self, error: reqwest::Error, url: &str) -> Blame {
+        if !error.is_timeout() {
+            return error.into();
+        }
+        let timeouts = self
+            .timeouts
+            .lock()
+            .map(|timeouts| *timeouts)
+            .unwrap_or_default();
+        let seconds = if error.is_connect() {
+            timeouts.connect()
+        } else {
+            timeouts.read()
+        };
+        let limit = Timeouts::limit(*seconds).unwrap_or_default();
+        Overdue::new(self.redact(url), limit).into()
+    }
+
+    /// The `hide` method adds `secret` to the text redacted from addresses.  Empty text is
+    /// ignored, since it would match everywhere.
+    pub fn hide(&self, secret: &str) {
+        if secret.is_empty() {
+            return;
+        }
+        if let Ok(mut hidden) = self.hidden.lock() {
+            if !hidden.iter().any(|known| known == secret) {
+                hidden.push(secret.to_string());
+            }
+        }
+    }
+
+    /// The `redact` method returns `url` with secrets replaced by `REDACTED`, as described in the
+    /// [`Network`] docs.
+    pub fn redact(&self, url: &str) -> String {
+        let mut url = match reqwest::Url::parse(url) {
+            Ok(mut parsed) => {
+                let pairs = parsed
+                    .query_pairs()
+                    .map(|(name, value)| {
+                        let secret = REDACTED_PARAMS
+                            .iter()
+                            .any(|param| param.eq_ignore_ascii_case(&name));
+                        let value = if secret {
+                            REDACTED.to_string()
+                        } else {
+                            value.to_string()
+                        };
+                        (name.to_string(), value)
+                    })
+                    .collect::<Vec<(String, String)>>();
+                if !pairs.is_empty() {
+                    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+                }
+                parsed.to_string()
+            }
+            Err(_) => url.to_string(),
+        };
+        if let Ok(hidden) = self.hidden.lock() {
+            for secret in hidden.iter() {
+                url = url.replace(secret.as_str(), REDACTED);
+            }
+        }
+        url
+    }
+
+    /// The `record` method adds `exchange` to the log, dropping the oldest entry once there are
+    /// [`NETWORK_ENTRIES`] of them.
+    pub fn record(&self, exchange: Exchange) {
+        tracing::trace!("{exchange}");
+        let Ok(mut exchanges) = self.exchanges.lock() else {
+            return;
+        };
+        if exchanges.len() == NETWORK_ENTRIES {
+            exchanges.pop_front();
+        }
+        exchanges.push_back(exchange);
+    }
+
+    /// The `exchanges` method returns a copy of the log, oldest first.
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges
+            .lock()
+            .map(|exchanges| exchanges.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The `send` method sends `request` and logs the exchange as a `fetch`, whether or not it
+    /// succeeds.  The body of the response is left for the caller, so the byte count comes from
+    /// the `Content-Length` header, and reads zero without one.  Build the request from
+    /// [`Network::client`], so the connect and read limits apply.
+    ///
+    /// Will [`Blame::Http`] if the request cannot be built or sent, and [`Blame::Timeout`] if
+    /// the response does not start within the limits.
+    pub async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        fetch: Fetch,
+    ) -> Arrive<reqwest::Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let url = self.redact(request.url().as_str());
+        let started = time::Instant::now();
+        let result = self
+            .within(fetch, &url, async {
+                client
+                    .execute(request)
+                    .await
+                    .map_err(|e| self.blame(e, &url))
+            })
+            .await;
+        let duration = started.elapsed();
+        let exchange = match &result {
+            Ok(response) => Exchange::new(
+                fetch,
+                url,
+                Some(response.status().as_u16()),
+                duration,
+                response.content_length().unwrap_or_default(),
+                Self::cached(response),
+            ),
+            Err(_) => Exchange::new(fetch, url, None, duration, 0, false),
+        };
+        self.record(exchange);
+        result
+    }
+
+    /// The `cached` method returns `true` if a cache between us and the server answered the
+    /// request, going by the `X-Cache` or `Age` headers that caching proxies add.
+    fn cached(response: &reqwest::Response) -> bool {
+        let headers = response.headers();
+        let hit = headers
+            .get("x-cache")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_uppercase().contains("HIT"));
+        hit || headers.contains_key(reqwest::header::AGE)
+    }
+}
+
+/// Prints the log as a table, one request per line, oldest first.
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let exchanges = self.exchanges();
+        if exchanges.is_empty() {
+            return write!(f, "No requests yet.");
+        }
+        write!(
+            f,
+            "{:<8} {:>6} {:>8} {:>10} {:<5} URL",
+            "KIND", "STATUS", "MS", "BYTES", "CACHE"
+        )?;
+        for exchange in exchanges {
+            write!(f, "\n{exchange}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Exchange` struct describes one request logged by [`Network`].
+///
+/// * The `fetch` field holds the kind of request, as a [`Fetch`].
+/// * The `url` field holds the address, with secrets redacted.
+/// * The `status` field holds the HTTP status of the response, or [`None`] if there was none.
+/// * The `duration` field holds the time from sending the request to the start of the response.
+/// * The `bytes` field holds the size of the body of the response, if the server said.
+/// * The `cached` field is `true` if a cache answered instead of the server.
+#[derive(Debug, Clone, PartialEq, Eq, derive_new::new, derive_getters::Getters)]
+pub struct Exchange {
+    fetch: Fetch,
+    url: String,
+    status: Option<u16>,
+    duration: time::Duration,
+    bytes: u64,
+    cached: bool,
+}
+
+/// Prints the exchange as a row of the table from [`Network`].
+impl std::fmt::Display for Exchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = self
+            .status
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "failed".to_string());
+        let cache = if self.cached { "hit" } else { "miss" };
+        write!(
+            f,
+            "{:<8} {:>6} {:>8} {:>10} {:<5} {}",
+            self.fetch.to_string(),
+            status,
+            self.duration.as_millis(),
+            self.bytes,
+            cache,
+            self.url
+        )
+    }
+}
+
+/// The `Overdue` struct describes a network call that ran out of time, for [`Blame::Timeout`].
+///
+/// * The `url` field holds the address called, with secrets redacted.
+/// * The `limit` field holds the time limit that ran out.
+#[derive(Debug, Clone, derive_new::new, derive_getters::Getters, derive_more::Error)]
+pub struct Overdue {
+    url: String,
+    limit: time::Duration,
+}
+
+/// Prints the address and the limit, as in `https://apps.bea.gov/api/data did not answer within
+/// 30s`.
+impl std::fmt::Display for Overdue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} did not answer within {}s",
+            self.url,
+            self.limit.as_secs()
+        )
+    }
+}
+
+/// The `NETWORK_ENTRIES` constant holds the number of recent requests [`Network`] keeps.
+pub const NETWORK_ENTRIES: usize = 200;
+
+/// The `REDACTED` constant holds the text that replaces secrets in the [`Network`] log.
+pub const REDACTED: &str = "REDACTED";
+
+/// The `REDACTED_PARAMS` constant lists the query parameters whose values [`Network::redact`]
+/// hides, compared without regard to case.  The BEA API takes its key as `UserID`.
+pub const REDACTED_PARAMS: [&str; 5] = ["userid", "key", "api_key", "apikey", "token"];
+
+/// The `USER_AGENT` constant holds the `User-Agent` header of every request from [`Network`],
+/// the name of the app from [`crate::APPLICATION`] and its version.
+pub const USER_AGENT: &str = concat!("Tardy/", env!("CARGO_PKG_VERSION"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_params_are_redacted() {
+        let network = Network::default();
+        let url = network.redact("https://apps.bea.gov/api/data?UserID=abc123&method=GetData");
+        assert!(!url.contains("abc123"));
+        assert!(url.contains(&format!("UserID={REDACTED}")));
+        assert!(url.contains("method=GetData"));
+        let plain = "https://tile.openstreetmap.org/3/4/2.png";
+        assert_eq!(network.redact(plain), plain);
+        assert_eq!(network.redact("not a url"), "not a url");
+    }
+
+    #[test]
+    fn hidden_text_is_redacted() {
+        let network = Network::default();
+        network.hide("");
+        network.hide("s3cret");
+        network.hide("s3cret");
+        assert_eq!(
+            network.hidden.lock().map(|hidden| hidden.len()).ok(),
+            Some(1)
+        );
+        let url = network.redact("https://tiles.example.com/s3cret/3/4/2.png");
+        assert_eq!(
+            url,
+            format!("https://tiles.example.com/{REDACTED}/3/4/2.png")
+        );
+        assert_eq!(
+            Network::default().redact("https://x.org/s3cret"),
+            "https://x.org/s3cret"
+        );
+    }
+}
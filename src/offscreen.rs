@@ -0,0 +1,253 @@
+use crate::{Arrive, Excuse, Fault, Map};
+use galileo::galileo_types::cartesian::Size;
+use std::path;
+use std::sync::Arc;
+
+/// The `offscreen` module provides the [`Offscreen`] struct, which draws the map into a texture
+/// instead of a window, and the [`Snapshot`] struct, which holds the pixels read back from it.
+///
+/// # Drawing without a window with `Offscreen`
+///
+/// Everything the map draws has gone to the surface of a window, at the size of the window, and
+/// the pixels stayed on the graphics card.  Exporting a picture of the map needs the opposite on
+/// both counts: a size of our choosing, often larger than the screen for print, and the pixels
+/// back in memory where we can encode them.  The `Offscreen` struct draws into a texture of its
+/// own, so it needs no window, and copies the texture into a buffer the processor can read.
+///
+/// The texture has a [`galileo`] renderer of its own, built for its size and format, since the
+/// renderer of a window keeps textures the size of the window.  [`Offscreen::render`] borrows
+/// the view of a [`Map`] through [`Map::snapshot`], stretched to the size of the texture but
+/// covering the same ground, so a picture twice the size of the window shows the same place in
+/// twice the detail.  The layers draw whatever tiles they have on hand, and ask for the tiles
+/// that the finer detail needs, so a second picture of the same view comes out sharper than the
+/// first.
+///
+/// The [`crate::Gpu`] opens an `Offscreen` with [`crate::Gpu::offscreen`], sharing the device
+/// of the windows, or opening one without a window if there are none, for headless use.  For
+/// now the callers are [`crate::Act::SaveSnapshot`], which saves a PNG with [`Snapshot::save`],
+/// the [`crate::Recorder`], which strings snapshots together into a clip, and the headless
+/// [`crate::Export`], which draws a [`Map`] of its own made with [`Map::offscreen`].
+///
+/// * The `renderer` field holds the renderer drawing into the texture.
+/// * The `texture` field holds the texture the map draws into.
+/// * The `buffer` field holds the buffer the texture is copied into for reading.
+/// * The `width` field holds the width of the texture in pixels.
+/// * The `height` field holds the height of the texture in pixels.
+/// * The `device` field holds the device the texture lives on.
+/// * The `queue` field holds the queue of the device.
+pub struct Offscreen {
+    renderer: galileo::render::WgpuRenderer,
+    texture: wgpu::Texture,
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+impl Offscreen {
+    /// The `new` method creates a texture of `width` by `height` pixels on `device`, with a
+    /// renderer and a buffer to read it back.
+    ///
+    /// Will [`Excuse::ExportSize`] if either side is zero, or larger than the device can hold.
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        width: u32,
+        height: u32,
+    ) -> Arrive<Self> {
+        let limit = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > limit || height > limit {
+            tracing::warn!("Cannot draw {width}x{height}, the limit is {limit} on a side.");
+            return Err(Excuse::ExportSize.into());
+        }
+        let renderer = galileo::render::WgpuRenderer::new_with_device_and_texture_rt(
+            device.clone(),
+            queue.clone(),
+            Size::new(width, height),
+        );
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen"),
+            size: u64::from(Self::padded_row(width)) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Ok(Self {
+            renderer,
+            texture,
+            buffer,
+            width,
+            height,
+            device,
+            queue,
+        })
+    }
+
+    /// The `device` method returns the device the texture lives on, for a [`Map`] to share.
+    pub fn device(&self) -> &Arc<wgpu::Device> {
+        &self.device
+    }
+
+    /// The `queue` method returns the queue of the device, for a [`Map`] to share.
+    pub fn queue(&self) -> &Arc<wgpu::Queue> {
+        &self.queue
+    }
+
+    /// The `size` method returns the width and height of the texture in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The `padded_row` method returns the bytes in a row of `width` pixels, padded to the
+    /// alignment [`wgpu`] requires for copying a texture into a buffer.
+    fn padded_row(width: u32) -> u32 {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        (width * 4).div_ceil(align) * align
+    }
+
+    /// The `render` method draws `map` into the texture and reads back the pixels, waiting for
+    /// the card to finish.
+    ///
+    /// Will [`Excuse::Readback`] if the card cannot hand back the pixels.
+    #[tracing::instrument(skip_all)]
+    pub fn render(&self, map: &Map) -> Arrive<Snapshot> {
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let padded = Self::padded_row(self.width);
+        Fault::guard(&self.device, || {
+            map.snapshot(&self.renderer, &view, self.width, self.height);
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("offscreen"),
+                });
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture: &self.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &self.buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded),
+                        rows_per_image: Some(self.height),
+                    },
+                },
+                self.texture.size(),
+            );
+            self.queue.submit(Some(encoder.finish()));
+        })?;
+
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        match receiver.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::warn!("Could not read back the picture: {e}");
+                return Err(Excuse::Readback.into());
+            }
+            Err(_) => return Err(Excuse::Readback.into()),
+        }
+        // Drop the padding from the end of each row as we copy out.
+        let row = self.width as usize * 4;
+        let pixels = slice
+            .get_mapped_range()
+            .chunks(padded as usize)
+            .flat_map(|padded| &padded[..row])
+            .copied()
+            .collect();
+        self.buffer.unmap();
+        Ok(Snapshot {
+            width: self.width,
+            height: self.height,
+            pixels,
+        })
+    }
+}
+
+/// The galileo renderer inside `Offscreen` does not implement [`std::fmt::Debug`], so we print
+/// the size and leave it at that.
+impl std::fmt::Debug for Offscreen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Offscreen")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `Snapshot` struct holds a picture of the map read back by [`Offscreen::render`].
+///
+/// * The `width` field holds the width of the picture in pixels.
+/// * The `height` field holds the height of the picture in pixels.
+/// * The `pixels` field holds the pixels, in rows from the top, four bytes of sRGB red, green,
+///   blue and alpha to a pixel.
+#[derive(Debug, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Snapshot {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Snapshot {
+    /// The `into_pixels` method hands over the pixels, for encoders that want to own them.
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    /// The `save` method writes the picture to `path`, in the format named by the extension,
+    /// as PNG for a `.png` file.
+    ///
+    /// Will [`crate::Blame::Image`] if the picture cannot be encoded or written.
+    pub fn save(&self, path: &path::Path) -> Arrive<()> {
+        image::save_buffer(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+        )?;
+        tracing::info!(
+            "Saved a {}x{} picture of the map to {}.",
+            self.width,
+            self.height,
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// The `OFFSCREEN_FORMAT` constant holds the pixel format of the texture of an [`Offscreen`],
+/// which is the format the [`galileo`] renderer builds its pipelines for when it draws into a
+/// texture.
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// The `SNAPSHOT_DIR` constant holds the name of the directory for pictures of the map, under
+/// the data directory from [`crate::Home::data`].
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// The `SNAPSHOT_SCALE` constant holds how many times larger than the window a picture from
+/// [`crate::Act::SaveSnapshot`] comes out, for a sharper print.
+pub const SNAPSHOT_SCALE: u32 = 2;
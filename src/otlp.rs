@@ -0,0 +1,123 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Context;
+use crate::{Arrive, Otlp};
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(not(target_arch = "wasm32"))]
+use opentelemetry::KeyValue;
+#[cfg(not(target_arch = "wasm32"))]
+use opentelemetry_otlp::WithExportConfig;
+
+/// The `otlp` module provides the [`Exporter`] struct, which sends our tracing spans and metrics
+/// to an OpenTelemetry collector.
+///
+/// # Watching the kiosk fleet with `Exporter`
+///
+/// The [`crate::Reporter`] tells us how often each error happens, a day late.  For the kiosks we
+/// also want to see what they are doing now: which requests are slow, how often the map redraws,
+/// and what led up to an error, across every kiosk at once.  The OpenTelemetry project defines a
+/// protocol for that, OTLP, and collectors that speak it can feed whatever dashboard the IT
+/// department already runs.
+///
+/// When the `[otlp]` table of the config names an `endpoint` (see [`Otlp`]), [`Exporter::start`]
+/// sets up two pipelines to the collector, sending OTLP over HTTP in the background:
+///
+/// * Spans, from every [`tracing`] span that gets past the log filter, through the layer from
+///   [`tracing_opentelemetry::layer`].  A span carries its fields and its parent, so the
+///   collector can show each request as a tree of the work it caused.
+/// * Metrics, through a [`tracing_opentelemetry::MetricsLayer`], which turns events with fields
+///   named like `monotonic_counter.tiles_drawn` or `histogram.request_ms` into metrics of the
+///   same name.
+///
+/// Both are tagged with the `service_name` from the config, so kiosks and desktops can report to
+/// the same collector and still be told apart.  The [`crate::trace_init`] function adds the
+/// layers to the subscriber, so the exporter has to start before logging does.  Dropping the
+/// exporter flushes whatever is still waiting to be sent, so `main` holds it until the app exits.
+///
+/// The pipelines send in the background on the [`tokio`] runtime, which the browser build does
+/// not have, so there [`Exporter::start`] fails with [`crate::Excuse::Unavailable`] if the config
+/// names an endpoint.
+///
+/// * The `tracer` field holds the provider of the tracer that exports spans.
+/// * The `meter` field holds the provider of the meters that export metrics.
+#[derive(Debug)]
+pub struct Exporter {
+    tracer: opentelemetry_sdk::trace::TracerProvider,
+    meter: opentelemetry_sdk::metrics::SdkMeterProvider,
+}
+
+impl Exporter {
+    /// The `start` method sets up the pipelines described in the [`Exporter`] docs, using the
+    /// `settings` from the `[otlp]` table.  Returns [`None`] if there is no endpoint, meaning
+    /// exporting is off.
+    ///
+    /// Will [`crate::Blame::Trace`] if the span pipeline cannot start, and
+    /// [`crate::Blame::Metrics`] if the metrics pipeline cannot.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start(settings: &Otlp) -> Arrive<Option<Self>> {
+        let Some(endpoint) = settings.endpoint() else {
+            return Ok(None);
+        };
+        let resource = opentelemetry_sdk::Resource::new(vec![
+            KeyValue::new("service.name", settings.service_name().clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]);
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .http()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(
+                opentelemetry_sdk::trace::Config::default().with_resource(resource.clone()),
+            )
+            .install_batch(opentelemetry_sdk::runtime::Tokio)
+            .with_context(|| format!("exporting spans to {endpoint}"))?;
+        let meter = opentelemetry_otlp::new_pipeline()
+            .metrics(opentelemetry_sdk::runtime::Tokio)
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .http()
+                    .with_endpoint(endpoint),
+            )
+            .with_resource(resource)
+            .build()
+            .with_context(|| format!("exporting metrics to {endpoint}"))?;
+        Ok(Some(Self { tracer, meter }))
+    }
+
+    /// The `start` method returns [`None`] if the `[otlp]` table of the `settings` has no
+    /// endpoint, and fails with [`crate::Excuse::Unavailable`] if it has one, since the browser
+    /// cannot run the pipelines, as described in the [`Exporter`] docs.
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(settings: &Otlp) -> Arrive<Option<Self>> {
+        match settings.endpoint() {
+            Some(_) => Err(crate::Excuse::Unavailable.into()),
+            None => Ok(None),
+        }
+    }
+
+    /// The `tracer` method returns the tracer for [`tracing_opentelemetry::layer`].
+    pub fn tracer(&self) -> opentelemetry_sdk::trace::Tracer {
+        self.tracer.tracer(env!("CARGO_PKG_NAME"))
+    }
+
+    /// The `meter` method returns the meter provider for
+    /// [`tracing_opentelemetry::MetricsLayer`].
+    pub fn meter(&self) -> &opentelemetry_sdk::metrics::SdkMeterProvider {
+        &self.meter
+    }
+}
+
+/// Flushes and shuts down both pipelines, so the last spans and metrics reach the collector.
+/// Failures go to the log, which outlives the exporter, since the subscriber is global.
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.tracer.shutdown() {
+            tracing::warn!("Could not flush spans: {e}");
+        }
+        if let Err(e) = self.meter.shutdown() {
+            tracing::warn!("Could not flush metrics: {e}");
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{Fetch, Latency, Panel};
+use std::collections::VecDeque;
+use strum::IntoEnumIterator;
+use web_time as time;
+
+/// The `overlay` module provides the [`Overlay`] struct, which keeps the frame statistics shown
+/// in the debug overlay.
+///
+/// # Watching the frame rate with `Overlay`
+///
+/// When the map stutters, the first question is whether we are slow to draw, slow to get tiles,
+/// or drawing more than we need to.  The `Overlay` answers all three at a glance.  Each
+/// [`crate::Lens`] keeps one, and calls [`Overlay::record`] every time it draws a frame.  The
+/// user turns the overlay on and off with [`crate::Act::ToggleDebugOverlay`], bound to `F12` by
+/// default, and while it is on we show:
+///
+/// * The frames per second, from [`Overlay::fps`], averaged over the last [`OVERLAY_FRAMES`]
+///   frames.
+/// * A histogram of frame times, from [`Overlay::histogram`].  The buckets end at the times in
+///   [`HISTOGRAM_BUCKETS`], so a frame landing in the last bucket missed a 30 Hz deadline.
+/// * The number of draw calls in the last frame.
+/// * The number of tiles on the way from the tile server, for every map, from
+///   [`crate::Fetcher::queued`].
+/// * The median and 95th percentile time taken by tile downloads and API requests, from
+///   [`Latency::global`], to tell a slow server from slow drawing.
+///
+/// The overlay sits on top of the map, in the top right corner, as a [`Panel`] in
+/// [`crate::Place::Overlay`], one line to each item above.  We write the readout again every
+/// [`OVERLAY_INTERVAL`], rather than every frame, so the numbers hold still long enough to read
+/// and the panel is not drawn again sixty times a second.  While the overlay is on, the lens asks
+/// for frames continuously (see [`crate::Lens::needs_frames`]), because a frame rate measured
+/// only when something moves says little about how fast we can draw.
+///
+/// * The `visible` field indicates the overlay is showing.
+/// * The `frames` field holds the time taken by each of the most recent frames.
+/// * The `last_frame` field holds the time the previous frame was drawn.
+/// * The `last_report` field holds the time we last wrote the readout.
+/// * The `draw_calls` field holds the number of draw calls in the last frame.
+/// * The `tile_queue` field holds the number of tiles waiting to download.
+#[derive(Debug, Default, Clone, derive_getters::Getters, derive_setters::Setters)]
+#[setters(prefix = "with_", borrow_self)]
+pub struct Overlay {
+    visible: bool,
+    #[setters(skip)]
+    frames: VecDeque<time::Duration>,
+    #[setters(skip)]
+    last_frame: Option<time::Instant>,
+    #[setters(skip)]
+    last_report: Option<time::Instant>,
+    draw_calls: usize,
+    tile_queue: usize,
+}
+
+impl Overlay {
+    /// The `toggle` method shows the overlay if it is hidden, and hides it if it is showing.
+    /// Hiding the overlay forgets the frame times, so that a long pause does not show up as one
+    /// slow frame when it comes back.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        if !self.visible {
+            self.frames.clear();
+            self.last_frame = None;
+            self.last_report = None;
+        }
+        tracing::info!("Debug overlay {}.", if self.visible { "on" } else { "off" });
+    }
+
+    /// The `record` method notes that a frame was drawn at `now`, keeping the time since the
+    /// previous frame.  We only keep the last [`OVERLAY_FRAMES`] frames.  Does nothing while the
+    /// overlay is hidden.
+    pub fn record(&mut self, now: time::Instant) {
+        if !self.visible {
+            return;
+        }
+        if let Some(last) = self.last_frame {
+            if self.frames.len() == OVERLAY_FRAMES {
+                self.frames.pop_front();
+            }
+            self.frames.push_back(now.saturating_duration_since(last));
+        }
+        self.last_frame = Some(now);
+    }
+
+    /// The `fps` method returns the mean frames per second over the frames we have kept, or
+    /// zero if we have not kept any.
+    pub fn fps(&self) -> f64 {
+        let total = self.frames.iter().sum::<time::Duration>().as_secs_f64();
+        if total > 0.0 {
+            self.frames.len() as f64 / total
+        } else {
+            0.0
+        }
+    }
+
+    /// The `histogram` method counts the frames we have kept by frame time.  Each count but the
+    /// last holds the frames that took no longer than the matching entry in
+    /// [`HISTOGRAM_BUCKETS`], and more than the one before it.  The last count holds the frames
+    /// slower than every bucket.
+    pub fn histogram(&self) -> [usize; HISTOGRAM_BUCKETS.len() + 1] {
+        let mut counts = [0; HISTOGRAM_BUCKETS.len() + 1];
+        for frame in &self.frames {
+            let bucket = HISTOGRAM_BUCKETS
+                .iter()
+                .position(|limit| frame <= limit)
+                .unwrap_or(HISTOGRAM_BUCKETS.len());
+            counts[bucket] += 1;
+        }
+        counts
+    }
+
+    /// The `report` method returns the readout as a [`Panel`] to draw over the map, at most once
+    /// every [`OVERLAY_INTERVAL`], and [`None`] in between, while the panel drawn last still
+    /// holds.  Returns [`None`] while the overlay is hidden.
+    pub fn report(&mut self, now: time::Instant) -> Option<Panel> {
+        if !self.visible
+            || self
+                .last_report
+                .is_some_and(|last| now.saturating_duration_since(last) < OVERLAY_INTERVAL)
+        {
+            return None;
+        }
+        self.last_report = Some(now);
+        let readout = self.to_string();
+        tracing::trace!("{readout}");
+        let lines = readout.split(" | ").map(str::to_owned).collect();
+        Some(Panel::new("Debug overlay", lines))
+    }
+}
+
+/// Prints the readout of the overlay on one line, as in
+/// `60.0 fps | ≤8ms: 0, ≤16ms: 58, ≤33ms: 2, >33ms: 0 | 3 draw calls | 12 tiles queued`,
+/// followed by the latency of each kind of [`Fetch`].
+impl std::fmt::Display for Overlay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1} fps |", self.fps())?;
+        let counts = self.histogram();
+        for (limit, count) in HISTOGRAM_BUCKETS.iter().zip(counts) {
+            write!(f, " ≤{}ms: {count},", limit.as_millis())?;
+        }
+        if let Some(limit) = HISTOGRAM_BUCKETS.last() {
+            write!(f, " >{}ms: {}", limit.as_millis(), counts[counts.len() - 1])?;
+        }
+        write!(
+            f,
+            " | {} draw calls | {} tiles queued",
+            self.draw_calls, self.tile_queue
+        )?;
+        let latency = Latency::global();
+        for fetch in Fetch::iter() {
+            write!(f, " | {}", latency.summary(fetch))?;
+        }
+        Ok(())
+    }
+}
+
+/// The `HISTOGRAM_BUCKETS` constant holds the upper limit of each bucket in the frame time
+/// histogram of the [`Overlay`], one frame at 120, 60 and 30 Hz.
+pub const HISTOGRAM_BUCKETS: [time::Duration; 3] = [
+    time::Duration::from_micros(8_333),
+    time::Duration::from_micros(16_667),
+    time::Duration::from_micros(33_333),
+];
+
+/// The `OVERLAY_FRAMES` constant holds the number of recent frames the [`Overlay`] keeps for its
+/// statistics, about two seconds at 60 Hz.
+pub const OVERLAY_FRAMES: usize = 120;
+
+/// The `OVERLAY_INTERVAL` constant holds the time between readouts of the [`Overlay`].
+pub const OVERLAY_INTERVAL: time::Duration = time::Duration::from_secs(1);
@@ -0,0 +1,144 @@
+use crate::{Act, Arrive};
+
+/// The `pad` module provides the [`Pad`] struct, which reads input from game controllers and
+/// translates it into [`Act`] variants.
+///
+/// # Steering the map with a joystick using `Pad`
+///
+/// Our public kiosk has no keyboard, and a mouse on a kiosk lasts about a week.  A sturdy game
+/// controller holds up better, and visitors already know what to do with one.  The `Pad` struct
+/// wraps a [`gilrs::Gilrs`] context, which talks to the controllers on each platform, and maps
+/// buttons to actions:
+///
+/// * The directional pad and the left stick pan the map with [`Act::PanUp`], [`Act::PanDown`],
+///   [`Act::PanLeft`] and [`Act::PanRight`].
+/// * The `South` button (A on an Xbox controller) zooms in with [`Act::ZoomIn`], and the `East`
+///   button (B) zooms out with [`Act::ZoomOut`].
+/// * The right bumper cycles to the next window with [`Act::NextWindow`].
+///
+/// Unlike the keyboard, [`winit`] does not deliver controller input through the event loop, so
+/// [`crate::App`] has to ask for it.  The `poll` method drains the pending controller events, and
+/// the app calls it every time the event loop wakes up.  While a controller is connected, the
+/// app wakes up every [`PAD_INTERVAL`] to check.
+///
+/// The left stick reports a position rather than a press.  We count a tilt past [`DEADZONE`] as
+/// a press in that direction, and ignore further movement until the stick returns to the center,
+/// so that holding the stick does not pan the map once per jitter.  The `tilt` field holds the
+/// direction of the current tilt on each axis.
+pub struct Pad {
+    gilrs: gilrs::Gilrs,
+    tilt: (i8, i8),
+}
+
+impl Pad {
+    /// The `new` method connects to the platform controller API.
+    ///
+    /// Will [`crate::Blame::Gilrs`] if the platform has no controller support or the API fails
+    /// to initialize.
+    pub fn new() -> Arrive<Self> {
+        let gilrs = gilrs::Gilrs::new()?;
+        for (id, gamepad) in gilrs.gamepads() {
+            tracing::info!("Gamepad {id} connected: {}", gamepad.name());
+        }
+        Ok(Self {
+            gilrs,
+            tilt: (0, 0),
+        })
+    }
+
+    /// The `connected` method returns `true` if at least one controller is connected.
+    pub fn connected(&self) -> bool {
+        self.gilrs.gamepads().next().is_some()
+    }
+
+    /// The `poll` method drains pending controller events and returns the [`Act`] variants they
+    /// map to, in the order they occurred.
+    #[tracing::instrument(skip_all)]
+    pub fn poll(&mut self) -> Vec<Act> {
+        let mut acts = Vec::new();
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            match event {
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    tracing::trace!("Gamepad {id} pressed {button:?}");
+                    if let Some(act) = Self::button(button) {
+                        acts.push(act);
+                    }
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    if let Some(act) = self.axis(axis, value) {
+                        acts.push(act);
+                    }
+                }
+                gilrs::EventType::Connected => tracing::info!("Gamepad {id} connected."),
+                gilrs::EventType::Disconnected => tracing::info!("Gamepad {id} disconnected."),
+                _ => (),
+            }
+        }
+        acts
+    }
+
+    /// The `button` method maps a controller button to an [`Act`].
+    fn button(button: gilrs::Button) -> Option<Act> {
+        match button {
+            gilrs::Button::DPadUp => Some(Act::PanUp),
+            gilrs::Button::DPadDown => Some(Act::PanDown),
+            gilrs::Button::DPadLeft => Some(Act::PanLeft),
+            gilrs::Button::DPadRight => Some(Act::PanRight),
+            gilrs::Button::South => Some(Act::ZoomIn),
+            gilrs::Button::East => Some(Act::ZoomOut),
+            gilrs::Button::RightTrigger => Some(Act::NextWindow),
+            _ => None,
+        }
+    }
+
+    /// The `axis` method converts movement of the left stick into a pan [`Act`] when the stick
+    /// tilts past [`DEADZONE`], as described in the [`Pad`] docs.
+    fn axis(&mut self, axis: gilrs::Axis, value: f32) -> Option<Act> {
+        let direction = if value > DEADZONE {
+            1
+        } else if value < -DEADZONE {
+            -1
+        } else {
+            0
+        };
+        let (current, positive, negative) = match axis {
+            gilrs::Axis::LeftStickX => (&mut self.tilt.0, Act::PanRight, Act::PanLeft),
+            // Pushing the stick forward reports a positive value.
+            gilrs::Axis::LeftStickY => (&mut self.tilt.1, Act::PanUp, Act::PanDown),
+            _ => return None,
+        };
+        if direction == *current {
+            return None;
+        }
+        *current = direction;
+        match direction {
+            1 => Some(positive),
+            -1 => Some(negative),
+            _ => None,
+        }
+    }
+}
+
+/// The [`gilrs::Gilrs`] context does not implement [`std::fmt::Debug`], so we list the
+/// connected controllers instead.
+impl std::fmt::Debug for Pad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = self
+            .gilrs
+            .gamepads()
+            .map(|(_, gamepad)| gamepad.name().to_string())
+            .collect::<Vec<String>>();
+        f.debug_struct("Pad")
+            .field("gamepads", &names)
+            .field("tilt", &self.tilt)
+            .finish()
+    }
+}
+
+/// The `DEADZONE` constant sets how far the stick must tilt, as a fraction of its full range,
+/// before [`Pad`] counts it as a press.
+pub const DEADZONE: f32 = 0.5;
+
+/// The `PAD_INTERVAL` constant sets how often the [`crate::App`] wakes up to check for
+/// controller input while a controller is connected.
+pub const PAD_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
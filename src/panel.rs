@@ -0,0 +1,231 @@
+use crate::{Arrive, Palette};
+use std::sync::{Arc, OnceLock};
+
+/// The `panel` module provides the [`Panel`] struct, a box of text the app draws over the map,
+/// and the [`Place`] enum, which says where it goes.
+///
+/// # Drawing text over the map with `Panel`
+///
+/// Much of what the app has to say is a few lines of text: the bindings that finish a key
+/// sequence, the count typed ahead of a command, the problems found in the config, the facts of a
+/// parcel from a lookup.  A `Panel` holds a title and the lines, and [`Panel::paint`] draws them
+/// into a picture, lines of monospaced text on a plain box, in the colors of the [`Palette`] and
+/// at the scale of the window.  We write the text as SVG and draw it with [`resvg`], as we draw
+/// the icons of the [`crate::Atlas`], with the fonts of the system, so the panel needs nothing
+/// from the card but a place to copy the picture.  The [`crate::Lens`] keeps the picture until
+/// the panel or the scale changes, and [`crate::Canvas::paste`] copies it over the window each
+/// frame, after the map and the ink, so a panel never lands in the picture of the map the
+/// canvas keeps, nor in a snapshot.
+///
+/// A window shows a panel in each [`Place`].  The [`crate::App`] keeps the one in
+/// [`Place::Status`] in step with the [`crate::Cmd`], showing the count and the bindings that
+/// finish the pending sequence in the focused window while a sequence is pending, and puts the
+/// reports the user asks for, and the warnings of startup, in [`Place::Notice`], over every
+/// map, until the next key press.  Every line of a notice goes to the log as well, so the record
+/// outlives the panel.  Each [`crate::Lens`] puts the readout of its [`crate::Overlay`] in
+/// [`Place::Overlay`] while the debug overlay is on.
+///
+/// * The `title` field holds the first line of the panel, drawn in bold.
+/// * The `lines` field holds the rest of the lines.
+#[derive(Debug, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Panel {
+    title: String,
+    lines: Vec<String>,
+}
+
+impl Panel {
+    /// The `new` method creates a panel headed by `title` over `lines`.
+    pub fn new(title: impl Into<String>, lines: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+        }
+    }
+
+    /// The `from_text` method creates a panel headed by `title` over the lines of `text`, as
+    /// printed by the [`std::fmt::Display`] of a report.
+    pub fn from_text(title: impl Into<String>, text: &str) -> Self {
+        Self::new(title, text.lines().map(str::to_owned).collect())
+    }
+
+    /// The `paint` method draws the panel at `scale` in the colors of `palette`, as described in
+    /// the [`Panel`] docs.  The box is opaque, so the picture can be copied over the window
+    /// without blending.
+    ///
+    /// Will [`crate::Blame::Svg`] if [`resvg`] cannot read the SVG of the panel.
+    pub fn paint(&self, palette: &Palette, scale: f64) -> Arrive<image::RgbaImage> {
+        let svg = self.svg(palette, scale);
+        let options = resvg::usvg::Options {
+            fontdb: Self::fonts(),
+            ..Default::default()
+        };
+        let tree = resvg::usvg::Tree::from_str(&svg, &options)?;
+        let size = tree.size().to_int_size();
+        let (width, height) = (size.width(), size.height());
+        let Some(mut pixmap) = resvg::tiny_skia::Pixmap::new(width, height) else {
+            return Ok(image::RgbaImage::new(0, 0));
+        };
+        resvg::render(
+            &tree,
+            resvg::tiny_skia::Transform::default(),
+            &mut pixmap.as_mut(),
+        );
+        let pixels = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        Ok(image::RgbaImage::from_raw(width, height, pixels).unwrap_or_default())
+    }
+
+    /// The `shown` method returns the lines the panel draws below its title.  A panel draws at
+    /// most [`PANEL_LINES`] lines, and sums up the rest in a last line, so a long report does
+    /// not cover the window.
+    pub fn shown(&self) -> Vec<String> {
+        let mut lines = self.lines.clone();
+        if lines.len() > PANEL_LINES {
+            let rest = lines.len() - PANEL_LINES + 1;
+            lines.truncate(PANEL_LINES - 1);
+            lines.push(format!("… and {rest} more lines in the log"));
+        }
+        lines
+    }
+
+    /// The `svg` method writes the panel as SVG, sized to the longest line.  Every character of
+    /// a monospaced font takes the same room, about three fifths of the size of the font, so we
+    /// size the box from the count of characters without measuring the text.
+    fn svg(&self, palette: &Palette, scale: f64) -> String {
+        let shown = self.shown();
+        let size = PANEL_FONT * scale;
+        let pad = (PANEL_PAD * scale).round();
+        let leading = (size * 1.4).round();
+        let widest = std::iter::once(&self.title)
+            .chain(&shown)
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or_default();
+        let width = (widest as f64 * size * 0.6 + pad * 2.0).ceil();
+        let height = (leading * (shown.len() + 1) as f64 + pad * 2.0).ceil();
+        let [red, green, blue, _] = *palette.panel();
+        let fill = format!("rgb({red},{green},{blue})");
+        let [red, green, blue, _] = *palette.text();
+        let ink = format!("rgb({red},{green},{blue})");
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}">"#
+        );
+        svg.push_str(&format!(
+            r#"<rect width="{width}" height="{height}" fill="{fill}"/>"#
+        ));
+        svg.push_str(&format!(
+            r#"<g font-family="monospace" font-size="{size}" fill="{ink}" xml:space="preserve">"#
+        ));
+        let lines =
+            std::iter::once((&self.title, "bold")).chain(shown.iter().map(|line| (line, "normal")));
+        for (row, (line, weight)) in lines.enumerate() {
+            let y = pad + leading * row as f64 + size;
+            svg.push_str(&format!(
+                r#"<text x="{pad}" y="{y}" font-weight="{weight}">{}</text>"#,
+                Self::escape(line)
+            ));
+        }
+        svg.push_str("</g></svg>");
+        svg
+    }
+
+    /// The `escape` method replaces the characters of `text` that mean something in XML with
+    /// their entities.
+    fn escape(text: &str) -> String {
+        text.chars()
+            .map(|c| match c {
+                '&' => "&amp;".to_string(),
+                '<' => "&lt;".to_string(),
+                '>' => "&gt;".to_string(),
+                c => c.to_string(),
+            })
+            .collect()
+    }
+
+    /// The `fonts` method returns the fonts of the system, read the first time a panel draws and
+    /// shared from then on.  The generic `monospace` family of the SVG names a font that may not
+    /// be installed, so we point it at the first monospaced font the system has.
+    fn fonts() -> Arc<resvg::usvg::fontdb::Database> {
+        static FONTS: OnceLock<Arc<resvg::usvg::fontdb::Database>> = OnceLock::new();
+        FONTS
+            .get_or_init(|| {
+                let mut fonts = resvg::usvg::fontdb::Database::new();
+                fonts.load_system_fonts();
+                let mono = fonts
+                    .faces()
+                    .find(|face| face.monospaced)
+                    .and_then(|face| face.families.first())
+                    .map(|(family, _)| family.clone());
+                match mono {
+                    Some(family) => fonts.set_monospace_family(family),
+                    None => tracing::warn!("No monospaced font found, panels draw no text."),
+                }
+                tracing::debug!("Read {} fonts for panels.", fonts.len());
+                Arc::new(fonts)
+            })
+            .clone()
+    }
+}
+
+/// The `Place` enum says where a [`Panel`] goes over the map, as described in the [`Panel`]
+/// docs.  A window shows one panel in each place.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Place {
+    /// The `Status` variant indicates the bottom left corner, for the state of the keyboard.
+    Status,
+    /// The `Notice` variant indicates the top left corner, for reports and warnings.
+    Notice,
+    /// The `Overlay` variant indicates the top right corner, for the readout of the
+    /// [`crate::Overlay`].
+    Overlay,
+}
+
+/// The `PANEL_FONT` constant holds the size of the text of a [`Panel`], in logical pixels,
+/// before the scale.
+pub const PANEL_FONT: f64 = 14.0;
+
+/// The `PANEL_PAD` constant holds the room between the edge of a [`Panel`] and its text, and
+/// between the panel and the edge of the window, in logical pixels, before the scale.
+pub const PANEL_PAD: f64 = 8.0;
+
+/// The `PANEL_LINES` constant holds the most lines a [`Panel`] shows below its title.
+pub const PANEL_LINES: usize = 24;
+
+/// The `PANEL_COLOR` constant holds the color of the box of a [`Panel`] in the standard look, a
+/// dark grey.
+pub const PANEL_COLOR: [u8; 4] = [32, 32, 36, 255];
+
+/// The `PANEL_TEXT` constant holds the color of the text of a [`Panel`] in the standard look, an
+/// off white.
+pub const PANEL_TEXT: [u8; 4] = [236, 236, 236, 255];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_panels_sum_up_the_rest() {
+        let lines = (0..40).map(|line| line.to_string()).collect();
+        let panel = Panel::new("Report", lines);
+        let shown = panel.shown();
+        assert_eq!(panel.lines().len(), 40);
+        assert_eq!(shown.len(), PANEL_LINES);
+        assert_eq!(
+            shown.last().map(String::as_str),
+            Some("… and 17 more lines in the log")
+        );
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let panel = Panel::new("a <b> & c", Vec::new());
+        let svg = panel.svg(&Palette::default(), 1.0);
+        assert!(svg.contains("a &lt;b&gt; &amp; c"));
+    }
+}
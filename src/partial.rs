@@ -0,0 +1,131 @@
+use crate::{Arrive, Blame, Severity};
+
+/// The `partial` module provides the [`PartialFailure`] struct, which collects the results of a
+/// batch operation, and the [`Failure`] struct, which records one item that went wrong.
+///
+/// # Reporting batch operations with `PartialFailure`
+///
+/// Loading ten layers or fetching fifty counties is one operation to the user, but fifty to us,
+/// and some of them will fail.  Returning the first error throws away the forty-nine that worked,
+/// and logging each error as it comes buries the user in warnings.  Instead, a batch collects
+/// each result into a `PartialFailure`, keeping what succeeded and, for each item that failed,
+/// a [`Failure`] naming the item and holding its [`Blame`].  The whole batch then reports once:
+///
+/// * [`PartialFailure::summary`] says how it went, as in `47 of 50 succeeded.`
+/// * [`PartialFailure::failures`] lists what went wrong, for a dialog to show on request.
+/// * [`PartialFailure::severity`] returns the worst [`Severity`] among the failures, so the
+///   caller can hand the batch to the same policy as a single error.
+///
+/// Collecting an iterator of labeled results builds one directly:
+///
+/// ```ignore
+/// let layers = paths
+///     .iter()
+///     .map(|path| (path.display().to_string(), Layer::load(path)))
+///     .collect::<PartialFailure<Layer>>();
+/// layers.log();
+/// ```
+///
+/// * The `successes` field holds the values of the items that succeeded, in order.
+/// * The `failures` field holds a [`Failure`] for each item that failed, in order.
+#[derive(Debug, derive_getters::Getters)]
+pub struct PartialFailure<T> {
+    successes: Vec<T>,
+    failures: Vec<Failure>,
+}
+
+impl<T> PartialFailure<T> {
+    /// The `push` method adds the `result` of the item named `item` to the batch.
+    pub fn push(&mut self, item: impl Into<String>, result: Arrive<T>) {
+        match result {
+            Ok(value) => self.successes.push(value),
+            Err(blame) => self.failures.push(Failure::new(item.into(), blame)),
+        }
+    }
+
+    /// The `total` method returns the number of items in the batch.
+    pub fn total(&self) -> usize {
+        self.successes.len() + self.failures.len()
+    }
+
+    /// The `is_complete` method returns `true` if no item failed, including when the batch is
+    /// empty.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// The `summary` method describes the outcome in a sentence for the user, as in
+    /// `47 of 50 succeeded.`
+    pub fn summary(&self) -> String {
+        format!("{} of {} succeeded.", self.successes.len(), self.total())
+    }
+
+    /// The `severity` method returns the worst [`Severity`] among the failures, or [`None`] if
+    /// nothing failed.
+    pub fn severity(&self) -> Option<Severity> {
+        self.failures
+            .iter()
+            .map(|failure| failure.blame.severity())
+            .max()
+    }
+
+    /// The `log` method reports the batch, standing in for the dialog the app does not have
+    /// yet.  A complete batch logs the summary at the info level.  Otherwise, the summary goes
+    /// out as a warning, followed by a line for each failure.
+    pub fn log(&self) {
+        if self.is_complete() {
+            tracing::info!("{}", self.summary());
+        } else {
+            tracing::warn!("{}", self.summary());
+            self.failures
+                .iter()
+                .for_each(|failure| tracing::warn!("  {failure}"));
+        }
+    }
+
+    /// The `into_arrive` method gives up on the batch if every item failed, returning the
+    /// [`Blame`] of the first failure, and otherwise returns the values that succeeded.  Use it
+    /// when a partial result is as good as a whole one, after calling [`PartialFailure::log`].
+    pub fn into_arrive(mut self) -> Arrive<Vec<T>> {
+        if self.successes.is_empty() && !self.failures.is_empty() {
+            return Err(self.failures.remove(0).blame);
+        }
+        Ok(self.successes)
+    }
+}
+
+impl<T> Default for PartialFailure<T> {
+    fn default() -> Self {
+        Self {
+            successes: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl<T, S: Into<String>> FromIterator<(S, Arrive<T>)> for PartialFailure<T> {
+    fn from_iter<I: IntoIterator<Item = (S, Arrive<T>)>>(iter: I) -> Self {
+        let mut batch = Self::default();
+        iter.into_iter()
+            .for_each(|(item, result)| batch.push(item, result));
+        batch
+    }
+}
+
+/// The `Failure` struct records one item of a [`PartialFailure`] that went wrong.
+///
+/// * The `item` field names the item, like the path of a layer or the name of a county.
+/// * The `blame` field holds the error.
+#[derive(Debug, derive_new::new, derive_getters::Getters)]
+pub struct Failure {
+    item: String,
+    blame: Blame,
+}
+
+/// Prints the item, then the user-facing [`Blame::report`], as in
+/// `parcels.geojson: A file could not be read or written. (BEA-E007)`.
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.item, self.blame.report())
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{
+    Arrive, Excuse, Extent, Query, APPLICATION, EARTH_RADIUS, MAX_ZOOM, MERCATOR_LATITUDE,
+};
+use std::path;
+
+/// The `permalink` module provides the [`Permalink`] struct, which writes the view of a map as a
+/// link that another copy of the app opens to the same view.
+///
+/// # Sharing a view with `Permalink`
+///
+/// "Look at the lot behind the fire station" starts a conversation about which fire station,
+/// which lot, and which layers are on.  A screenshot settles what the sender saw, but the person
+/// on the other end cannot pan or turn anything on.  [`crate::Act::CopyPermalink`] copies a link
+/// to the view of the focused map instead, to paste into an email or a chat, and
+/// [`crate::Act::OpenPermalink`] opens a link from the clipboard in the focused map, so the
+/// other person starts from exactly what the sender was looking at.
+///
+/// The link looks like this, with one `layer` or `catalog` parameter for each open table:
+///
+/// ```text
+/// tardy://view?lat=42.439100&lon=-123.327900&zoom=16.25&catalog=Parcels&taxlot=36-05-17-BC-01100
+/// ```
+///
+/// * `lat` and `lon` hold the center of the map, in degrees.
+/// * `zoom` holds the zoom level of the base map, with fractions, so the view keeps its scale on
+///   a window of another size.
+/// * `catalog` names a layer added from the [`crate::Catalog`], which the other copy adds again
+///   for the ground in view, since the file it was saved to lives in the data directory of the
+///   sender.  The layer keeps its name, not its address, so a fixed address in the catalog
+///   fixes old links too.
+/// * `layer` holds the path of any other open data file, which opens if the other copy finds a
+///   file there, as it does for files on a shared drive.  A path that is not there is left out,
+///   with a warning.
+/// * `address`, `taxlot` or `point` holds the last parcel lookup, by address, by map and tax lot
+///   number, or by longitude and latitude (see [`crate::Parcels`]), which the other copy looks
+///   up again, highlighting the parcel and moving the map to it.
+///
+/// The scheme is the name of the app, [`PERMALINK_SCHEME`], written in lowercase and read in any
+/// case.  A link passed to the app on the command line opens in the first window, like a data
+/// file.  The app does not register the scheme with the desktop itself, so a link clicked in
+/// another program opens only where the install has done so, as with an
+/// `x-scheme-handler/tardy` entry in the desktop file on Linux.
+///
+/// A link can come from anywhere, so [`Permalink::parse`] holds the view to what the base map
+/// can show: the latitude within [`MERCATOR_LATITUDE`] of the equator, the longitude within 180
+/// degrees of the prime meridian, and the zoom level from zero to [`MAX_ZOOM`].
+///
+/// * The `longitude` and `latitude` fields hold the center of the map, in degrees.
+/// * The `zoom` field holds the zoom level of the base map.
+/// * The `layers` field holds the paths of the open data files not from the catalog.
+/// * The `catalog` field holds the names of the layers added from the catalog.
+/// * The `query` field holds the last parcel lookup, if any.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Permalink {
+    longitude: f64,
+    latitude: f64,
+    zoom: f64,
+    layers: Vec<path::PathBuf>,
+    catalog: Vec<String>,
+    query: Option<Query>,
+}
+
+impl Permalink {
+    /// The `from_view` method describes the view of a map showing `extent` across `width`
+    /// pixels, with the data files at `layers` and the catalog layers named in `catalog` open,
+    /// and `query` the last lookup.
+    pub fn from_view(
+        extent: &Extent,
+        width: u32,
+        layers: Vec<path::PathBuf>,
+        catalog: Vec<String>,
+        query: Option<Query>,
+    ) -> Self {
+        let (x_min, y_min, x_max, y_max) = extent.to_mercator();
+        // As in `Map::frame`, the center lies halfway across the projection, not the degrees.
+        let (longitude, latitude) = Extent::unproject((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+        let resolution = (x_max - x_min) / f64::from(width.max(1));
+        let zoom = (Self::resolution(0.0) / resolution).log2();
+        Self {
+            longitude,
+            latitude,
+            zoom,
+            layers,
+            catalog,
+            query,
+        }
+    }
+
+    /// The `parse` method reads a link written by [`Permalink`], as described in its docs,
+    /// ignoring any parameter it does not know, so a link from a newer version still opens, and
+    /// holding the center and zoom level to what the base map can show.
+    ///
+    /// Will [`Excuse::NotPermalink`] if `text` is not a link of the [`PERMALINK_SCHEME`] with a
+    /// center and zoom level.
+    pub fn parse(text: &str) -> Arrive<Self> {
+        let url = reqwest::Url::parse(text.trim()).map_err(|_| Excuse::NotPermalink)?;
+        if !url.scheme().eq_ignore_ascii_case(PERMALINK_SCHEME) {
+            return Err(Excuse::NotPermalink.into());
+        }
+        let (mut longitude, mut latitude, mut zoom) = (None, None, None);
+        let mut layers = Vec::new();
+        let mut catalog = Vec::new();
+        let mut query = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "lon" => longitude = value.parse::<f64>().ok(),
+                "lat" => latitude = value.parse::<f64>().ok(),
+                "zoom" => zoom = value.parse::<f64>().ok(),
+                "layer" => layers.push(path::PathBuf::from(value.as_ref())),
+                "catalog" => catalog.push(value.to_string()),
+                "address" => query = Some(Query::Address(value.to_string())),
+                "taxlot" => query = Some(Query::Taxlot(value.to_string())),
+                "point" => {
+                    if let Some((longitude, latitude)) = value.split_once(',') {
+                        if let (Ok(longitude), Ok(latitude)) =
+                            (longitude.parse::<f64>(), latitude.parse::<f64>())
+                        {
+                            query = Some(Query::Point(longitude, latitude));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let (Some(longitude), Some(latitude), Some(zoom)) = (longitude, latitude, zoom) else {
+            return Err(Excuse::NotPermalink.into());
+        };
+        if !longitude.is_finite() || !latitude.is_finite() || !zoom.is_finite() {
+            return Err(Excuse::NotPermalink.into());
+        }
+        Ok(Self::new(longitude, latitude, zoom, layers, catalog, query))
+    }
+
+    /// The `new` method describes the view centered on `longitude` and `latitude` at `zoom`,
+    /// with the data files at `layers` and the catalog layers named in `catalog` open, and
+    /// `query` the last lookup.  As in [`Permalink::parse`], the center and zoom level are held
+    /// to what the base map can show.
+    pub fn new(
+        longitude: f64,
+        latitude: f64,
+        zoom: f64,
+        layers: Vec<path::PathBuf>,
+        catalog: Vec<String>,
+        query: Option<Query>,
+    ) -> Self {
+        Self {
+            longitude: longitude.clamp(-180.0, 180.0),
+            latitude: latitude.clamp(-MERCATOR_LATITUDE, MERCATOR_LATITUDE),
+            zoom: zoom.clamp(0.0, f64::from(MAX_ZOOM)),
+            layers,
+            catalog,
+            query,
+        }
+    }
+
+    /// The `is_permalink` method returns `true` if `text` starts with the [`PERMALINK_SCHEME`],
+    /// in any case, as a link does and a file name does not.
+    pub fn is_permalink(text: &str) -> bool {
+        text.trim()
+            .split_once(':')
+            .is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case(PERMALINK_SCHEME))
+    }
+
+    /// The `extent` method returns the ground the view shows on a map of `width` by `height`
+    /// pixels, for [`crate::Map::frame`].
+    pub fn extent(&self, width: u32, height: u32) -> Extent {
+        let (x, y, _, _) =
+            Extent::new(self.longitude, self.latitude, self.longitude, self.latitude).to_mercator();
+        let resolution = Self::resolution(self.zoom);
+        let half_width = resolution * f64::from(width.max(1)) / 2.0;
+        let half_height = resolution * f64::from(height.max(1)) / 2.0;
+        Extent::from_mercator(
+            x - half_width,
+            y - half_height,
+            x + half_width,
+            y + half_height,
+        )
+    }
+
+    /// The `resolution` method returns the meters on the ground to a pixel of the base map at
+    /// `zoom`, for tiles of 256 pixels.
+    fn resolution(zoom: f64) -> f64 {
+        2.0 * std::f64::consts::PI * EARTH_RADIUS / 256.0 / zoom.exp2()
+    }
+
+    /// The `encode` method escapes `text` for the query of a link, keeping letters, digits and
+    /// the few marks a link allows as they are.
+    fn encode(text: &str) -> String {
+        text.bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    char::from(byte).to_string()
+                }
+                _ => format!("%{byte:02X}"),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Permalink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}://view?lat={:.6}&lon={:.6}&zoom={:.2}",
+            PERMALINK_SCHEME.to_lowercase(),
+            self.latitude,
+            self.longitude,
+            self.zoom
+        )?;
+        for name in &self.catalog {
+            write!(f, "&catalog={}", Self::encode(name))?;
+        }
+        for path in &self.layers {
+            write!(f, "&layer={}", Self::encode(&path.display().to_string()))?;
+        }
+        match &self.query {
+            Some(Query::Address(address)) => write!(f, "&address={}", Self::encode(address)),
+            Some(Query::Taxlot(taxlot)) => write!(f, "&taxlot={}", Self::encode(taxlot)),
+            Some(Query::Point(longitude, latitude)) => {
+                write!(f, "&point={longitude:.6},{latitude:.6}")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// The `PERMALINK_SCHEME` constant holds the scheme of a link written by [`Permalink`], the name
+/// of the app, from [`APPLICATION`].
+pub const PERMALINK_SCHEME: &str = APPLICATION;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_round_trip() {
+        let link = Permalink {
+            longitude: -123.3279,
+            latitude: 42.4391,
+            zoom: 16.25,
+            layers: vec![path::PathBuf::from("/srv/gis/fire stations.geojson")],
+            catalog: vec!["Tax Lots".to_string()],
+            query: Some(Query::Taxlot("36-05-17-BC-01100".to_string())),
+        };
+        let text = link.to_string();
+        let scheme = PERMALINK_SCHEME.to_lowercase();
+        assert!(text.starts_with(&format!("{scheme}://view?lat=42.439100&")));
+        assert!(Permalink::is_permalink(&text));
+        assert_eq!(Permalink::parse(&text).expect("parses"), link);
+        let point = Permalink {
+            query: Some(Query::Point(-123.5, 42.25)),
+            ..link.clone()
+        };
+        assert_eq!(Permalink::parse(&point.to_string()).expect("parses"), point);
+        let address = Permalink {
+            query: Some(Query::Address("101 NW A St & 1st".to_string())),
+            ..link
+        };
+        assert_eq!(
+            Permalink::parse(&address.to_string()).expect("parses"),
+            address
+        );
+    }
+
+    #[test]
+    fn parse_skips_unknown_keys() {
+        let text = format!("{PERMALINK_SCHEME}://view?zoom=12&lon=-123.3&lat=42.4&theme=dark");
+        let link = Permalink::parse(&text).expect("parses");
+        assert_eq!(
+            (link.longitude, link.latitude, link.zoom),
+            (-123.3, 42.4, 12.0)
+        );
+        assert!(link.layers.is_empty() && link.query.is_none());
+    }
+
+    #[test]
+    fn parse_holds_the_view_to_the_map() {
+        let text = format!("{PERMALINK_SCHEME}://view?lat=95&lon=-200&zoom=1000");
+        let link = Permalink::parse(&text).expect("parses");
+        assert_eq!(
+            (link.longitude, link.latitude, link.zoom),
+            (-180.0, MERCATOR_LATITUDE, f64::from(MAX_ZOOM))
+        );
+        let text = format!("{PERMALINK_SCHEME}://view?lat=-95&lon=200&zoom=-1000");
+        let link = Permalink::parse(&text).expect("parses");
+        assert_eq!(
+            (link.longitude, link.latitude, link.zoom),
+            (180.0, -MERCATOR_LATITUDE, 0.0)
+        );
+    }
+
+    #[test]
+    fn parse_refuses_other_links() {
+        let code = |text: &str| Permalink::parse(text).unwrap_err().code();
+        let missing = format!("{PERMALINK_SCHEME}://view?lat=42.4&lon=-123.3");
+        assert_eq!(code(&missing), Excuse::NotPermalink.code());
+        let unreadable = format!("{PERMALINK_SCHEME}://view?lat=north&lon=-123.3&zoom=3");
+        assert_eq!(code(&unreadable), Excuse::NotPermalink.code());
+        assert_eq!(
+            code("https://example.com/view?lat=42.4&lon=-123.3&zoom=3"),
+            Excuse::NotPermalink.code()
+        );
+        assert_eq!(code("parcels.geojson"), Excuse::NotPermalink.code());
+        assert!(!Permalink::is_permalink("C:\\data\\parcels.geojson"));
+    }
+}
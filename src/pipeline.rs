@@ -0,0 +1,260 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// The `pipeline` module provides the [`Pipe`] struct, a bounded queue between the tasks that
+/// produce news and the event loop that draws it, and the [`Overflow`] enum, which says what a
+/// full pipe gives up.
+///
+/// # Keeping the queue short with `Pipe`
+///
+/// The [`winit`] event loop takes our events through an unbounded queue.  Every event waits its
+/// turn, however stale, so a burst of news from the background builds a backlog the event loop
+/// has to chew through before it gets back to the user.  Most of that news goes stale as soon as
+/// the next piece arrives: a job at 40% does not need to report 39% first, and a window asked to
+/// redraw twice only needs to redraw once.  A `Pipe` holds that kind of news instead, with two
+/// rules that keep the useless work out:
+///
+/// * Merge.  Each item may carry a key, and an item whose key is already waiting replaces the one
+///   waiting, in its place in the line, so the queue holds at most one item per key.  Progress
+///   merges by job and redraws merge by window.
+/// * Bound.  A pipe holds at most `capacity` items.  When a new key arrives at a full pipe, the
+///   [`Overflow`] policy decides whether the oldest item or the new one goes.
+///
+/// The [`crate::Courier`] pushes mergeable news into its pipe, and wakes the event loop with a
+/// single [`crate::UserEvent::Mail`] when the pipe goes from empty to not, so however fast the
+/// news comes, the event loop queue holds one wake-up at a time for each courier.  The
+/// [`crate::App`] drains the pipe when the wake-up arrives, and handles what is left.  News that
+/// must not be lost or reordered, like errors and finished jobs, skips the pipe.
+///
+/// Tables read in the background wait in a pipe of their own, keyed by file, as described in
+/// the [`crate::Courier`] docs.  Map tiles would benefit most, since rapid panning requests tiles
+/// for views the user has already left behind, but [`galileo`] queues and fetches the tiles
+/// itself, and gives us no hook into its queue.  What reaches us from it is a request to draw
+/// again as each tile lands, which only raises the damage flag of the [`crate::Map`] and asks
+/// the window for a redraw, and both hold one request however many tiles land.  The pipe is
+/// ready for the fetcher that replaces the one in [`galileo`].
+///
+/// * The `queue` field holds the items waiting, with their keys, shared among clones.
+/// * The `capacity` field holds the most items the pipe holds at once.
+/// * The `overflow` field holds the [`Overflow`] policy for a full pipe.
+#[derive(Debug, Clone)]
+pub struct Pipe<K, T> {
+    queue: Arc<Mutex<Queue<K, T>>>,
+    capacity: usize,
+    overflow: Overflow,
+}
+
+impl<K: std::hash::Hash + Eq + Clone, T> Pipe<K, T> {
+    /// The `new` method creates an empty pipe holding up to `capacity` items, and at least one,
+    /// giving way by `overflow` when full.
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(Queue::default())),
+            capacity: capacity.max(1),
+            overflow,
+        }
+    }
+
+    /// The `push` method adds `item` to the pipe under `key`, merging and dropping as described
+    /// in the [`Pipe`] docs, and returns what became of it.
+    pub fn push(&self, key: K, item: T) -> Pushed {
+        let Ok(mut queue) = self.queue.lock() else {
+            return Pushed::Dropped;
+        };
+        let was_empty = queue.order.is_empty();
+        if let Some(waiting) = queue.items.get_mut(&key) {
+            *waiting = item;
+            queue.merged += 1;
+            return Pushed::Merged;
+        }
+        if queue.order.len() >= self.capacity {
+            queue.dropped += 1;
+            match self.overflow {
+                Overflow::DropNewest => return Pushed::Dropped,
+                Overflow::DropOldest => {
+                    if let Some(oldest) = queue.order.pop_front() {
+                        queue.items.remove(&oldest);
+                    }
+                }
+            }
+        }
+        queue.order.push_back(key.clone());
+        queue.items.insert(key, item);
+        match was_empty {
+            true => Pushed::First,
+            false => Pushed::Queued,
+        }
+    }
+
+    /// The `drain` method takes every item waiting, oldest first, leaving the pipe empty.
+    pub fn drain(&self) -> Vec<T> {
+        let Ok(mut queue) = self.queue.lock() else {
+            return Vec::new();
+        };
+        let order = std::mem::take(&mut queue.order);
+        order
+            .into_iter()
+            .filter_map(|key| queue.items.remove(&key))
+            .collect()
+    }
+
+    /// The `len` method returns the number of items waiting.
+    pub fn len(&self) -> usize {
+        self.queue
+            .lock()
+            .map(|queue| queue.order.len())
+            .unwrap_or_default()
+    }
+
+    /// The `is_empty` method returns `true` if no items are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `merged` method returns how many items have replaced one waiting under the same key.
+    pub fn merged(&self) -> u64 {
+        self.queue
+            .lock()
+            .map(|queue| queue.merged)
+            .unwrap_or_default()
+    }
+
+    /// The `dropped` method returns how many items a full pipe has given up.
+    pub fn dropped(&self) -> u64 {
+        self.queue
+            .lock()
+            .map(|queue| queue.dropped)
+            .unwrap_or_default()
+    }
+}
+
+/// The `Queue` struct holds the contents of a [`Pipe`].
+///
+/// * The `order` field holds the keys waiting, oldest first.
+/// * The `items` field holds the item waiting under each key.
+/// * The `merged` field counts the items merged into one already waiting.
+/// * The `dropped` field counts the items given up by a full pipe.
+#[derive(Debug)]
+struct Queue<K, T> {
+    order: VecDeque<K>,
+    items: HashMap<K, T>,
+    merged: u64,
+    dropped: u64,
+}
+
+impl<K, T> Default for Queue<K, T> {
+    fn default() -> Self {
+        Self {
+            order: VecDeque::new(),
+            items: HashMap::new(),
+            merged: 0,
+            dropped: 0,
+        }
+    }
+}
+
+/// The `Overflow` enum says what a full [`Pipe`] gives up to make room.
+///
+/// * The `DropOldest` variant drops the item that has waited longest, for news where the latest
+///   matters most.
+/// * The `DropNewest` variant drops the item arriving, for news where the first matters most.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum Overflow {
+    /// The `DropOldest` variant indicates a full pipe drops its oldest item.
+    #[display("drop oldest")]
+    DropOldest,
+    /// The `DropNewest` variant indicates a full pipe drops the new item.
+    #[display("drop newest")]
+    DropNewest,
+}
+
+/// The `Pushed` enum says what became of an item pushed into a [`Pipe`].
+///
+/// * The `First` variant means the item went into an empty pipe, so whoever drains the pipe
+///   needs waking.
+/// * The `Queued` variant means the item joined others already waiting.
+/// * The `Merged` variant means the item replaced one waiting under the same key.
+/// * The `Dropped` variant means the pipe was full, and gave up the item.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Pushed {
+    /// The `First` variant indicates the item went into an empty pipe.
+    First,
+    /// The `Queued` variant indicates the item joined the line.
+    Queued,
+    /// The `Merged` variant indicates the item replaced one with the same key.
+    Merged,
+    /// The `Dropped` variant indicates the item was dropped.
+    Dropped,
+}
+
+/// The `MAILBOX_CAPACITY` constant holds the most items the pipe of a [`crate::Courier`] holds.
+/// Each job and window takes one place at most, so only a flood of jobs fills it.
+pub const MAILBOX_CAPACITY: usize = 256;
+
+/// The `SHELF_CAPACITY` constant holds the most tables and offers the shelf of a
+/// [`crate::Courier`] holds.  Each file takes one place at most, and a table can run to
+/// millions of rows, so the shelf is short.
+pub const SHELF_CAPACITY: usize = 32;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_reports_first_and_queued() {
+        let pipe = Pipe::new(4, Overflow::DropOldest);
+        assert_eq!(pipe.push(1, "a"), Pushed::First);
+        assert_eq!(pipe.push(2, "b"), Pushed::Queued);
+        assert_eq!(pipe.drain(), vec!["a", "b"]);
+        assert_eq!(pipe.push(3, "c"), Pushed::First);
+    }
+
+    #[test]
+    fn same_key_merges_in_place() {
+        let pipe = Pipe::new(4, Overflow::DropOldest);
+        pipe.push(1, "a");
+        pipe.push(2, "b");
+        assert_eq!(pipe.push(1, "c"), Pushed::Merged);
+        assert_eq!(pipe.len(), 2);
+        assert_eq!(pipe.merged(), 1);
+        assert_eq!(pipe.drain(), vec!["c", "b"]);
+    }
+
+    #[test]
+    fn full_pipe_drops_oldest() {
+        let pipe = Pipe::new(2, Overflow::DropOldest);
+        pipe.push(1, "a");
+        pipe.push(2, "b");
+        assert_eq!(pipe.push(3, "c"), Pushed::Queued);
+        assert_eq!(pipe.dropped(), 1);
+        assert_eq!(pipe.drain(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn full_pipe_drops_newest() {
+        let pipe = Pipe::new(2, Overflow::DropNewest);
+        pipe.push(1, "a");
+        pipe.push(2, "b");
+        assert_eq!(pipe.push(3, "c"), Pushed::Dropped);
+        assert_eq!(pipe.dropped(), 1);
+        assert_eq!(pipe.drain(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn full_pipe_still_merges() {
+        let pipe = Pipe::new(2, Overflow::DropNewest);
+        pipe.push(1, "a");
+        pipe.push(2, "b");
+        assert_eq!(pipe.push(2, "c"), Pushed::Merged);
+        assert_eq!(pipe.dropped(), 0);
+        assert_eq!(pipe.drain(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn capacity_is_at_least_one() {
+        let pipe = Pipe::new(0, Overflow::DropNewest);
+        assert_eq!(pipe.push(1, "a"), Pushed::First);
+        assert_eq!(pipe.push(2, "b"), Pushed::Dropped);
+        assert!(!pipe.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{Arrive, Basemap, Context, Dataset, Job};
+use std::path;
+use std::sync::Arc;
+
+/// The `plugin` module provides the [`DataProvider`], [`LayerFactory`] and [`Tool`] traits,
+/// which other crates implement to add to the app, and the [`Plugins`] struct, which holds what
+/// they register.
+///
+/// # Extending the app with `Plugins`
+///
+/// The county wants parcels from its own permit system on the map, and the fire district wants
+/// a tool that reports the nearest hydrant.  Neither belongs in the app for everyone, and
+/// neither office wants to keep a fork of it.  Instead, a crate of their own depends on this
+/// one, implements the traits for what it adds, and builds its own `main` around the
+/// [`crate::App`], handing it the [`Plugins`] with [`crate::App::with_plugins`]:
+///
+/// ```ignore
+/// let plugins = Plugins::default()
+///     .with_provider(PermitProvider::new(url))
+///     .with_tool(NearestHydrant::default());
+/// let mut app = App::new(proxy, cli).with_plugins(plugins);
+/// event_loop.run_app(&mut app)?;
+/// ```
+///
+/// There are three ways in:
+///
+/// * A [`DataProvider`] reads files the app does not recognize, or recognizes and reads
+///   differently.  When the user opens a file, the first provider that accepts the path reads
+///   it, in the order registered, and the built-in readers of [`Dataset`] take the rest.  The
+///   provider runs on a worker thread, reporting to the [`Job`] as [`Dataset::read`] does, and
+///   hands back a [`Dataset`] made with [`Dataset::new`] from an [`arrow`] table, or with
+///   [`Dataset::from_rows`] from text, so the table reaches the rest of the app like any other.
+/// * A [`LayerFactory`] makes a [`galileo`] layer for each map window, drawn over the base map in
+///   the order registered.  A factory that fails leaves its layer out of that window, with a
+///   warning, rather than failing the window.  The headless [`crate::Export`] draws the layers
+///   too.
+/// * A [`Tool`] answers a press on the map, with the longitude and latitude pressed and the
+///   tables open in the app.  [`crate::Act::NextTool`] cycles through the tools registered, and
+///   back to identifying, which is what a press does with no tool chosen.  What a tool has to
+///   say shows in a [`crate::Panel`] over the map, headed by the name of the tool.
+///
+/// The traits ask for [`Send`] and [`Sync`] where the app shares the plugin with a worker
+/// thread, which a provider and a factory are, and not where it does not.  Each has a `name`,
+/// which the log uses to say who did what.
+///
+/// Loading plugins at runtime from a shared library would need a stable interface across
+/// compilers, which Rust does not promise, so for now a plugin is a crate compiled into the
+/// app.  A loader that opens libraries and registers what they offer could sit on top of the
+/// same traits later.
+///
+/// * The `providers` field holds each [`DataProvider`], in the order registered.
+/// * The `factories` field holds each [`LayerFactory`], in the order registered.
+/// * The `tools` field holds each [`Tool`], in the order registered.
+/// * The `active` field holds the index of the tool a press goes to, or [`None`] to identify.
+#[derive(Default)]
+pub struct Plugins {
+    providers: Vec<Arc<dyn DataProvider>>,
+    factories: Vec<Arc<dyn LayerFactory>>,
+    tools: Vec<Box<dyn Tool>>,
+    active: Option<usize>,
+}
+
+impl Plugins {
+    /// The `with_provider` method registers `provider` to read data files, after any registered
+    /// before it.
+    pub fn with_provider(mut self, provider: impl DataProvider + 'static) -> Self {
+        tracing::trace!("Registered data provider {}.", provider.name());
+        self.providers.push(Arc::new(provider));
+        self
+    }
+
+    /// The `with_factory` method registers `factory` to make a layer for each map window, drawn
+    /// over the layers registered before it.
+    pub fn with_factory(mut self, factory: impl LayerFactory + 'static) -> Self {
+        tracing::trace!("Registered layer factory {}.", factory.name());
+        self.factories.push(Arc::new(factory));
+        self
+    }
+
+    /// The `with_tool` method registers `tool` to answer presses on the map, next in the cycle
+    /// of [`crate::Act::NextTool`].
+    pub fn with_tool(mut self, tool: impl Tool + 'static) -> Self {
+        tracing::trace!("Registered tool {}.", tool.name());
+        self.tools.push(Box::new(tool));
+        self
+    }
+
+    /// The `provider` method returns the first registered [`DataProvider`] that accepts the file
+    /// at `path`, or [`None`] if the built-in readers should have it.
+    pub fn provider(&self, path: &path::Path) -> Option<Arc<dyn DataProvider>> {
+        self.providers
+            .iter()
+            .find(|provider| provider.accepts(path))
+            .cloned()
+    }
+
+    /// The `load` method reads the file at `path` with `provider` on a worker thread, reporting
+    /// to `job`, as [`Dataset::load`] does with the built-in readers.
+    ///
+    /// Will [`crate::Blame::Join`] if the worker thread panics, and otherwise fails as the
+    /// provider does.
+    pub async fn load(
+        provider: Arc<dyn DataProvider>,
+        path: path::PathBuf,
+        job: Job,
+    ) -> Arrive<Dataset> {
+        let name = format!("{} with {}", path.display(), provider.name());
+        crate::unblock(move || provider.read(&path, &job))
+            .await
+            .with_context(|| format!("reading {name}"))?
+    }
+
+    /// The `layers` method asks each [`LayerFactory`] for a layer, with the `basemap` from the
+    /// `[map]` table of the config, leaving out any that fail, with a warning.
+    pub fn layers(&self, basemap: &Basemap) -> Vec<Box<dyn galileo::layer::Layer>> {
+        self.factories
+            .iter()
+            .filter_map(|factory| match factory.layer(basemap) {
+                Ok(layer) => Some(layer),
+                Err(e) => {
+                    tracing::warn!("Layer {} left out: {e}", factory.name());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The `next_tool` method chooses the tool after the active one, or no tool after the last,
+    /// and returns the name of the choice, or [`None`] for identifying.
+    pub fn next_tool(&mut self) -> Option<&str> {
+        self.active = match self.active {
+            None if !self.tools.is_empty() => Some(0),
+            Some(index) if index + 1 < self.tools.len() => Some(index + 1),
+            _ => None,
+        };
+        self.active.map(|index| self.tools[index].name())
+    }
+
+    /// The `tool` method returns the active tool, or [`None`] for identifying.
+    pub fn tool(&mut self) -> Option<&mut Box<dyn Tool>> {
+        self.tools.get_mut(self.active?)
+    }
+}
+
+/// The trait objects inside `Plugins` do not implement [`std::fmt::Debug`], so we print the
+/// name of each plugin instead.
+impl std::fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugins")
+            .field(
+                "providers",
+                &self.providers.iter().map(|p| p.name()).collect::<Vec<_>>(),
+            )
+            .field(
+                "factories",
+                &self.factories.iter().map(|f| f.name()).collect::<Vec<_>>(),
+            )
+            .field(
+                "tools",
+                &self.tools.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            )
+            .field("active", &self.active)
+            .finish()
+    }
+}
+
+/// The `DataProvider` trait reads data files into a [`Dataset`], for a plugin adding a source
+/// of data, as described in the [`Plugins`] docs.
+pub trait DataProvider: Send + Sync {
+    /// The `name` method returns the name of the provider, for the log.
+    fn name(&self) -> &str;
+
+    /// The `accepts` method returns `true` if the provider reads the file at `path`, usually
+    /// going by the extension.
+    fn accepts(&self, path: &path::Path) -> bool;
+
+    /// The `read` method reads the file at `path` into a [`Dataset`], reporting progress to
+    /// `job` and stopping once [`Job::is_cancelled`].  It runs on a worker thread, so it may
+    /// block for as long as it needs.
+    fn read(&self, path: &path::Path, job: &Job) -> Arrive<Dataset>;
+}
+
+/// The `LayerFactory` trait makes a [`galileo`] layer for each map, for a plugin adding to what
+/// the map draws, as described in the [`Plugins`] docs.
+pub trait LayerFactory: Send + Sync {
+    /// The `name` method returns the name of the layer, for the log.
+    fn name(&self) -> &str;
+
+    /// The `layer` method makes a layer for a new map, which opens on the view of `basemap`.
+    fn layer(&self, basemap: &Basemap) -> Arrive<Box<dyn galileo::layer::Layer>>;
+}
+
+/// The `Tool` trait answers presses on the map, for a plugin adding a way to work with it, as
+/// described in the [`Plugins`] docs.
+pub trait Tool: Send {
+    /// The `name` method returns the name of the tool, for the log.
+    fn name(&self) -> &str;
+
+    /// The `press` method answers a press on the map at `longitude` and `latitude`, in degrees,
+    /// with the `datasets` open in the app, and returns what it has to tell the user, if
+    /// anything.
+    fn press(
+        &mut self,
+        longitude: f64,
+        latitude: f64,
+        datasets: &[Dataset],
+    ) -> Arrive<Option<String>>;
+}
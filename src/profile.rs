@@ -0,0 +1,61 @@
+/// The `profile` module provides the [`Profile`] struct, which applies a named set of overrides
+/// from the config.
+///
+/// # Switching setups with `Profile`
+///
+/// The same config file serves the kiosk in the lobby and the analysts upstairs, who want
+/// different key bindings, different scroll behavior, and different actions switched off.  Rather
+/// than keep a config file per machine, the config holds named profiles, each a table under
+/// `[profile]` that can set any key the config recognizes:
+///
+/// ```toml
+/// [profile.kiosk]
+/// next_window = "Tab"
+///
+/// [profile.kiosk.groups]
+/// disabled = ["editing", "destructive"]
+///
+/// [profile.analysis.scroll]
+/// mode = "Pan"
+/// ```
+///
+/// The `--profile` argument of [`crate::Cli`] picks a profile by name.  [`crate::Layers`] adds
+/// the profile as a [`config::Source`] after the config files, so the profile wins over the rest
+/// of the files, and the environment and command line still win over the profile.  Tables merge
+/// key by key, so a profile that sets `scroll.mode` keeps the rest of the `[scroll]` table.
+///
+/// * The `name` field holds the name of the profile.
+/// * The `table` field holds the keys set by the profile.
+#[derive(Debug, Clone, derive_getters::Getters)]
+pub struct Profile {
+    name: String,
+    table: config::Map<String, config::Value>,
+}
+
+impl Profile {
+    /// The `load` method reads the profile called `name` from `config`.
+    ///
+    /// Will [`crate::Blame::Config`] if the config has no such profile, or the profile is not a
+    /// table.
+    pub fn load(config: &config::Config, name: &str) -> crate::Arrive<Self> {
+        let table = config.get_table(&format!("{PROFILES}.{name}"))?;
+        tracing::info!("Using profile {name}.");
+        Ok(Self {
+            name: name.to_string(),
+            table,
+        })
+    }
+}
+
+impl config::Source for Profile {
+    fn clone_into_box(&self) -> Box<dyn config::Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<config::Map<String, config::Value>, config::ConfigError> {
+        Ok(self.table.clone())
+    }
+}
+
+/// The `PROFILES` constant holds the name of the table holding the profiles in the config.
+pub const PROFILES: &str = "profile";
@@ -0,0 +1,194 @@
+use crate::{Act, Chord, Cmd, Panel, Stroke};
+use strum::IntoEnumIterator;
+use winit::{event, keyboard};
+
+/// The `rebind` module provides the [`Rebind`] struct, the keybinding editor, and the [`Edit`]
+/// enum it answers each key press with.
+///
+/// # Changing key bindings with `Rebind`
+///
+/// Most users never open the config file, and should not have to in order to move a binding
+/// they keep hitting by accident.  [`crate::Act::EditBindings`] opens the editor, a
+/// [`Panel`] over the map that lists each [`Act`] with the keys bound to it, drawn by
+/// [`Rebind::panel`].  While the editor is open, the [`crate::App`] sends every key press to
+/// [`Rebind::key`] instead of the [`Cmd`], so the keys the user presses land in the editor rather
+/// than running the acts they are bound to:
+///
+/// * `ArrowUp` and `ArrowDown` move the cursor, and `PageUp` and `PageDown` move it a screen at a
+///   time.
+/// * `Enter` starts taking the new keys for the act under the cursor.  Each key pressed from then
+///   on adds a [`Stroke`] to the new binding, so a sequence like `g b` takes two presses.
+///   `Backspace` takes back the last stroke.
+/// * `Enter` again binds the strokes to the act, as [`Edit::Bind`], which the app hands to
+///   [`crate::App::bind`] to write back to the config file.
+/// * `Escape` drops the strokes taken so far, or closes the editor, as [`Edit::Close`].
+///
+/// Since `Enter`, `Escape` and `Backspace` drive the editor, they cannot be bound here on their
+/// own, though they can with a modifier, as in `Ctrl+Enter`.  A binding of one of them alone
+/// still goes in the config file.
+///
+/// * The `acts` field holds the acts listed, every [`Act`] but [`Act::Be`].
+/// * The `cursor` field holds the index of the act under the cursor.
+/// * The `strokes` field holds the new keys taken so far, while the editor is taking keys.
+#[derive(Debug, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Rebind {
+    acts: Vec<Act>,
+    cursor: usize,
+    strokes: Option<Vec<Stroke>>,
+}
+
+impl Rebind {
+    /// The `new` method opens the editor with the cursor on the first act.
+    pub fn new() -> Self {
+        let acts = Act::iter().filter(|act| *act != Act::Be).collect();
+        Self {
+            acts,
+            cursor: 0,
+            strokes: None,
+        }
+    }
+
+    /// The `act` method returns the act under the cursor.
+    pub fn act(&self) -> &Act {
+        &self.acts[self.cursor]
+    }
+
+    /// The `key` method answers the key press in `event`, made while holding `modifiers`, as
+    /// described in the [`Rebind`] docs.
+    pub fn key(&mut self, event: &event::KeyEvent, modifiers: keyboard::ModifiersState) -> Edit {
+        let plain = modifiers.is_empty();
+        let named = match &event.logical_key {
+            keyboard::Key::Named(named) if plain => Some(*named),
+            _ => None,
+        };
+        match (&mut self.strokes, named) {
+            (None, Some(keyboard::NamedKey::Escape)) => Edit::Close,
+            (None, Some(keyboard::NamedKey::Enter)) => {
+                self.strokes = Some(Vec::new());
+                Edit::Stay
+            }
+            (None, Some(keyboard::NamedKey::ArrowUp)) => self.step(-1),
+            (None, Some(keyboard::NamedKey::ArrowDown)) => self.step(1),
+            (None, Some(keyboard::NamedKey::PageUp)) => self.step(-(REBIND_ROWS as isize)),
+            (None, Some(keyboard::NamedKey::PageDown)) => self.step(REBIND_ROWS as isize),
+            (None, _) => Edit::Stay,
+            (Some(_), Some(keyboard::NamedKey::Escape)) => {
+                self.strokes = None;
+                Edit::Stay
+            }
+            (Some(strokes), Some(keyboard::NamedKey::Backspace)) => {
+                strokes.pop();
+                Edit::Stay
+            }
+            (Some(strokes), Some(keyboard::NamedKey::Enter)) => {
+                if strokes.is_empty() {
+                    return Edit::Stay;
+                }
+                let chord = Chord::new(std::mem::take(strokes));
+                self.strokes = None;
+                Edit::Bind(self.act().clone(), chord)
+            }
+            (Some(strokes), _) => {
+                if let Some(stroke) = Stroke::from_key(&event.logical_key, modifiers) {
+                    strokes.push(stroke);
+                }
+                Edit::Stay
+            }
+        }
+    }
+
+    /// The `step` method moves the cursor by `rows`, stopping at either end of the list.
+    fn step(&mut self, rows: isize) -> Edit {
+        let last = self.acts.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(rows).min(last);
+        Edit::Stay
+    }
+
+    /// The `panel` method draws the editor as a [`Panel`], with the bindings from `cmd`.  The
+    /// panel lists [`REBIND_ROWS`] acts around the cursor, with a marker on the act under it, and
+    /// ends with a line on the keys that drive the editor.
+    pub fn panel(&self, cmd: &Cmd) -> Panel {
+        let title = match &self.strokes {
+            Some(strokes) => {
+                let keys = strokes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                format!("New keys for {}: {keys}_", self.act().title())
+            }
+            None => "Key bindings".to_string(),
+        };
+        let first = self
+            .cursor
+            .saturating_sub(REBIND_ROWS / 2)
+            .min(self.acts.len().saturating_sub(REBIND_ROWS));
+        let width = self
+            .acts
+            .iter()
+            .map(|act| act.title().chars().count())
+            .max()
+            .unwrap_or_default();
+        let mut lines = self
+            .acts
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(REBIND_ROWS)
+            .map(|(row, act)| {
+                let marker = if row == self.cursor { '>' } else { ' ' };
+                let keys = cmd.keys_for(act).join(", ");
+                format!("{marker} {:width$}  {keys}", act.title())
+            })
+            .collect::<Vec<String>>();
+        lines.push(String::new());
+        lines.push(match self.strokes {
+            Some(_) => "Press the new keys, Enter to bind, Escape to cancel".to_string(),
+            None => "Up and Down to choose, Enter to rebind, Escape to close".to_string(),
+        });
+        Panel::new(title, lines)
+    }
+}
+
+impl Default for Rebind {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `Edit` enum holds the answer of [`Rebind::key`] to a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// The `Stay` variant means the editor stays open, and may have changed.
+    Stay,
+    /// The `Bind` variant means the user chose new keys for an act.
+    Bind(Act, Chord),
+    /// The `Close` variant means the user is done with the editor.
+    Close,
+}
+
+/// The `REBIND_ROWS` constant holds the number of acts the [`Rebind`] panel lists at a time.
+pub const REBIND_ROWS: usize = 16;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_stops_at_the_ends() {
+        let mut rebind = Rebind::new();
+        rebind.step(-1);
+        assert_eq!(*rebind.cursor(), 0);
+        rebind.step(isize::MAX);
+        assert_eq!(*rebind.cursor(), rebind.acts().len() - 1);
+    }
+
+    #[test]
+    fn panel_fits_the_window() {
+        let mut rebind = Rebind::new();
+        rebind.step(REBIND_ROWS as isize * 2);
+        let panel = rebind.panel(&Cmd::default());
+        assert_eq!(panel.lines().len(), REBIND_ROWS + 2);
+        assert!(panel.lines().iter().any(|line| line.starts_with('>')));
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{Arrive, Blame, Excuse, Map, Offscreen, Recording, Snapshot};
+use std::path;
+use std::sync::mpsc;
+use web_time as time;
+use winit::window;
+
+/// The `recorder` module provides the [`Recorder`] struct, which captures the frames of a map
+/// window while the user records, and the [`Reel`] struct, which encodes the frames into a clip
+/// as they come.
+///
+/// # Recording the map with `Recorder`
+///
+/// A still picture of the map, from [`crate::Act::SaveSnapshot`], shows where things are.  A
+/// clip shows how they move, which is what a presentation wants from an animation over time or
+/// a flight from one place to another.  [`crate::Act::ToggleRecording`] starts a `Recorder` for
+/// the focused map window, and stops it again.
+///
+/// We do not copy frames off the surface of the window, which is made for the screen and not
+/// for reading back.  Instead, each time the window draws, the recorder draws the same view
+/// again into an [`Offscreen`] texture the size the window had when the recording started, and
+/// reads back the pixels as a [`Snapshot`].  The window draws only when something changes, so a
+/// map that sits still for five seconds makes one frame, shown for five seconds, and a busy map
+/// makes at most `fps` frames a second, from the `[recording]` table (see [`Recording`]).  The
+/// recording stops by itself once it has run for `seconds` seconds.
+///
+/// A frame of a large window is several megabytes, and a minute of them would run to gigabytes,
+/// so the frames do not wait for the end in memory.  Each goes straight to a [`Reel`], which
+/// encodes it on a thread of its own, as a GIF through the [`image`] crate, or as an MP4 through
+/// [`FFMPEG`], if it is installed.  The reel takes at most [`RECORDING_BACKLOG`] frames ahead of
+/// the encoder, and a frame that finds the backlog full is skipped, so the frame before it
+/// shows a little longer.  Stopping the recorder hands over the reel, which [`crate::App`]
+/// finishes on a background job with [`Reel::finish`].
+///
+/// * The `window` field holds the id of the window recorded.
+/// * The `offscreen` field holds the texture the frames are drawn into.
+/// * The `reel` field holds the encoder the frames go to.
+/// * The `interval` field holds the least time between frames.
+/// * The `length` field holds the longest the recording runs.
+/// * The `started` field holds the time of the first frame, if any.
+/// * The `last` field holds the time of the latest frame, if any.
+#[derive(Debug)]
+pub struct Recorder {
+    window: window::WindowId,
+    offscreen: Offscreen,
+    reel: Reel,
+    interval: time::Duration,
+    length: time::Duration,
+    started: Option<time::Instant>,
+    last: Option<time::Instant>,
+}
+
+impl Recorder {
+    /// The `new` method creates a recorder for the window with id `window`, drawing frames into
+    /// `offscreen`, with the limits and format from `recording`, and encoding the clip to
+    /// `path`.
+    ///
+    /// Will fail as [`Reel::start`] does.
+    pub fn new(
+        window: window::WindowId,
+        offscreen: Offscreen,
+        recording: &Recording,
+        path: &path::Path,
+    ) -> Arrive<Self> {
+        let fps = (*recording.fps()).max(1);
+        let (width, height) = offscreen.size();
+        let reel = Reel::start(*recording.format(), fps, width, height, path)?;
+        Ok(Self {
+            window,
+            offscreen,
+            reel,
+            interval: time::Duration::from_secs(1) / fps,
+            length: time::Duration::from_secs((*recording.seconds()).max(1)),
+            started: None,
+            last: None,
+        })
+    }
+
+    /// The `window` method returns the id of the window recorded.
+    pub fn window(&self) -> window::WindowId {
+        self.window
+    }
+
+    /// The `capture` method draws `map` as a frame of the recording at `now`, unless the last
+    /// frame was less than an interval ago.  Returns `true` once the recording has run its
+    /// length, or the encoder has stopped, and the recording should stop.
+    ///
+    /// Will fail as [`Offscreen::render`] does.
+    pub fn capture(&mut self, map: &Map, now: time::Instant) -> Arrive<bool> {
+        let started = *self.started.get_or_insert(now);
+        if now >= started + self.length {
+            return Ok(true);
+        }
+        if self.last.is_some_and(|last| now < last + self.interval) {
+            return Ok(false);
+        }
+        let snapshot = self.offscreen.render(map)?;
+        self.last = Some(now);
+        Ok(!self.reel.push(snapshot, now))
+    }
+
+    /// The `finish` method stops the recording and hands back the [`Reel`], to finish with
+    /// [`Reel::finish`].
+    pub fn finish(self) -> Reel {
+        self.reel
+    }
+}
+
+/// The `Cue` enum holds the messages from a [`Reel`] to its encoder thread.
+///
+/// * The `Frame` variant holds a picture and the time it was taken.
+/// * The `End` variant holds the time the recording stopped.
+#[derive(Debug)]
+enum Cue {
+    /// The `Frame` variant indicates a picture to encode.
+    Frame(Snapshot, time::Instant),
+    /// The `End` variant indicates the recording stopped.
+    End(time::Instant),
+}
+
+/// The `Reel` struct encodes the frames of a recording into a clip as they come, on a thread of
+/// its own, as described in the [`Recorder`] docs.
+///
+/// The encoder cannot write a frame until it knows how long the frame shows, which is until the
+/// next one comes, so it holds one frame back, and writes it when the next frame or the end of
+/// the recording arrives.
+///
+/// * The `format` field holds the [`Clip`] format encoded.
+/// * The `path` field holds the file the clip goes to.
+/// * The `cues` field holds the sending end of the queue to the encoder thread.
+/// * The `worker` field holds the encoder thread, which returns the number of frames written.
+/// * The `frames` field counts the frames taken by the encoder.
+/// * The `skipped` field counts the frames skipped because the backlog was full.
+#[derive(Debug)]
+pub struct Reel {
+    format: Clip,
+    path: path::PathBuf,
+    cues: mpsc::SyncSender<Cue>,
+    worker: std::thread::JoinHandle<Arrive<usize>>,
+    frames: usize,
+    skipped: usize,
+}
+
+impl Reel {
+    /// The `start` method starts encoding a clip of `width` by `height` frames at `fps` frames a
+    /// second, in `format`, to the file at `path`.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be created, [`crate::Blame::Image`] if the
+    /// GIF cannot be started, and [`Excuse::Encoder`] if [`FFMPEG`] is missing.  A web page has
+    /// no thread to encode on, so in the browser it fails with [`Excuse::Unavailable`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start(
+        format: Clip,
+        fps: u32,
+        width: u32,
+        height: u32,
+        path: &path::Path,
+    ) -> Arrive<Self> {
+        let mut encoder = Encoder::new(format, fps, width, height, path)?;
+        let (cues, received) = mpsc::sync_channel(RECORDING_BACKLOG);
+        let worker = std::thread::Builder::new()
+            .name("recording".to_string())
+            .spawn(move || {
+                let mut held: Option<(Snapshot, time::Instant)> = None;
+                let mut count = 0;
+                for cue in received {
+                    let (next, at) = match cue {
+                        Cue::Frame(snapshot, at) => (Some((snapshot, at)), at),
+                        Cue::End(at) => (None, at),
+                    };
+                    if let Some((snapshot, taken)) = held.take() {
+                        encoder.write(&snapshot, at.saturating_duration_since(taken))?;
+                        count += 1;
+                    }
+                    held = next;
+                }
+                encoder.finish()?;
+                Ok(count)
+            })?;
+        Ok(Self {
+            format,
+            path: path.to_path_buf(),
+            cues,
+            worker,
+            frames: 0,
+            skipped: 0,
+        })
+    }
+
+    /// The `start` method fails with [`Excuse::Unavailable`], since the browser has no thread to
+    /// encode a clip on.
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(
+        _format: Clip,
+        _fps: u32,
+        _width: u32,
+        _height: u32,
+        _path: &path::Path,
+    ) -> Arrive<Self> {
+        Err(Excuse::Unavailable.into())
+    }
+
+    /// The `format` method returns the [`Clip`] format encoded.
+    pub fn format(&self) -> Clip {
+        self.format
+    }
+
+    /// The `push` method hands `snapshot`, taken at `at`, to the encoder, or skips it if the
+    /// encoder is [`RECORDING_BACKLOG`] frames behind.  Returns `false` if the encoder has
+    /// stopped, after an error it reports from [`Reel::finish`].
+    pub fn push(&mut self, snapshot: Snapshot, at: time::Instant) -> bool {
+        match self.cues.try_send(Cue::Frame(snapshot, at)) {
+            Ok(()) => {
+                self.frames += 1;
+                true
+            }
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.skipped += 1;
+                tracing::trace!("The encoder is behind, skipped a frame.");
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    /// The `finish` method ends the recording at `at`, showing the last frame until then, and
+    /// waits for the encoder to write the rest of the clip.  It blocks, so call it off the event
+    /// loop.
+    ///
+    /// Will [`Excuse::ExportSize`] if the recording has no frames, [`crate::Blame::Image`] if the
+    /// GIF cannot be encoded, and [`Excuse::Encoder`] if [`FFMPEG`] fails.
+    pub fn finish(self, at: time::Instant) -> Arrive<()> {
+        // An encoder that stopped early has hung up, and its error is waiting in the worker.
+        let _ = self.cues.send(Cue::End(at));
+        drop(self.cues);
+        let count = self
+            .worker
+            .join()
+            .map_err(|_| Blame::from(Excuse::Encoder))??;
+        if count == 0 {
+            std::fs::remove_file(&self.path).ok();
+            return Err(Excuse::ExportSize.into());
+        }
+        match self.skipped {
+            0 => tracing::info!("Saved {count} frames to {}.", self.path.display()),
+            skipped => tracing::info!(
+                "Saved {count} frames to {}, skipping {skipped} while the encoder caught up.",
+                self.path.display()
+            ),
+        }
+        Ok(())
+    }
+
+    /// The `frames` method returns the number of frames handed to the encoder so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+/// The `Encoder` enum writes the frames of a [`Reel`] in one [`Clip`] format.
+///
+/// * The `Gif` variant holds the GIF encoder writing the file.
+/// * The `Mp4` variant holds the [`FFMPEG`] process, the pipe to it, and the frame rate.
+#[cfg(not(target_arch = "wasm32"))]
+enum Encoder {
+    /// The `Gif` variant indicates an animated GIF under way.
+    Gif(image::codecs::gif::GifEncoder<std::io::BufWriter<std::fs::File>>),
+    /// The `Mp4` variant indicates an MP4 video under way.
+    Mp4(std::process::Child, std::process::ChildStdin, u32),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Encoder {
+    /// The `new` method starts encoding `width` by `height` frames in `format` to `path`.  A GIF
+    /// loops forever.
+    fn new(format: Clip, fps: u32, width: u32, height: u32, path: &path::Path) -> Arrive<Self> {
+        match format {
+            Clip::Gif => {
+                let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+                let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(file, GIF_SPEED);
+                encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+                Ok(Self::Gif(encoder))
+            }
+            Clip::Mp4 => {
+                let (child, stdin) = Self::ffmpeg(fps, width, height, path)?;
+                Ok(Self::Mp4(child, stdin, fps))
+            }
+        }
+    }
+
+    /// The `ffmpeg` method starts [`FFMPEG`] encoding raw frames from its input into an H.264
+    /// video at `path`.
+    fn ffmpeg(
+        fps: u32,
+        width: u32,
+        height: u32,
+        path: &path::Path,
+    ) -> Arrive<(std::process::Child, std::process::ChildStdin)> {
+        let size = format!("{width}x{height}");
+        let fps = fps.to_string();
+        let mut child = std::process::Command::new(FFMPEG)
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgba",
+            ])
+            .args(["-s", &size, "-r", &fps, "-i", "-"])
+            // H.264 wants even sides, so we pad odd ones by a pixel.  Left to itself, ffmpeg
+            // converts with the matrix of old television and tags nothing, and players that
+            // assume the modern one shift the colors, so we convert with BT.709 and say so.
+            .args([
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2,scale=out_color_matrix=bt709:out_range=tv",
+                "-pix_fmt",
+                "yuv420p",
+            ])
+            .args([
+                "-colorspace",
+                "bt709",
+                "-color_primaries",
+                "bt709",
+                "-color_trc",
+                "iec61966-2-1",
+                "-color_range",
+                "tv",
+            ])
+            .arg(path)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Blame::from(Excuse::Encoder),
+                _ => Blame::from(e),
+            })?;
+        let stdin = child.stdin.take().ok_or(Excuse::Encoder)?;
+        Ok((child, stdin))
+    }
+
+    /// The `write` method encodes `snapshot`, showing for `delay`.  Video runs at a steady rate,
+    /// so a frame that shows for longer goes in more than once.
+    fn write(&mut self, snapshot: &Snapshot, delay: time::Duration) -> Arrive<()> {
+        match self {
+            Self::Gif(encoder) => {
+                let (width, height) = (*snapshot.width(), *snapshot.height());
+                let buffer = image::RgbaImage::from_raw(width, height, snapshot.pixels().clone())
+                    .ok_or(Excuse::Readback)?;
+                let delay = image::Delay::from_saturating_duration(delay);
+                encoder.encode_frame(image::Frame::from_parts(buffer, 0, 0, delay))?;
+            }
+            Self::Mp4(_, stdin, fps) => {
+                use std::io::Write;
+                let repeats = (delay.as_secs_f64() * f64::from(*fps)).round().max(1.0) as usize;
+                for _ in 0..repeats {
+                    stdin.write_all(snapshot.pixels())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The `finish` method closes the clip.  For a GIF that means flushing the file, and for a
+    /// video closing the pipe and waiting for [`FFMPEG`] to finish.
+    fn finish(self) -> Arrive<()> {
+        match self {
+            Self::Gif(encoder) => {
+                drop(encoder);
+                Ok(())
+            }
+            Self::Mp4(mut child, stdin, _) => {
+                drop(stdin);
+                match child.wait()?.success() {
+                    true => Ok(()),
+                    false => Err(Excuse::Encoder.into()),
+                }
+            }
+        }
+    }
+}
+
+/// The `Clip` enum describes the format of a recording.
+///
+/// * The `Gif` variant makes an animated GIF, which plays anywhere a picture does, including
+///   slides and email, at the cost of a large file and 256 colors a frame.  This is the default.
+/// * The `Mp4` variant makes an H.264 video, smaller and in full color, through [`FFMPEG`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Clip {
+    /// The `Gif` variant indicates an animated GIF.
+    #[default]
+    Gif,
+    /// The `Mp4` variant indicates an MP4 video.
+    Mp4,
+}
+
+impl Clip {
+    /// The `extension` method returns the file extension for clips of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Mp4 => "mp4",
+        }
+    }
+}
+
+/// The `FFMPEG` constant holds the name of the program that encodes MP4 clips, found on the
+/// `PATH`.
+pub const FFMPEG: &str = "ffmpeg";
+
+/// The `GIF_SPEED` constant holds the speed of the GIF encoder, from 1 for the best colors to
+/// 30 for the fastest.  The default of the encoder is fine for maps.
+pub const GIF_SPEED: i32 = 10;
+
+/// The `RECORDING_BACKLOG` constant holds the most frames a [`Reel`] takes ahead of its
+/// encoder.  A frame of a large window is several megabytes, so a short backlog keeps a slow
+/// encoder from eating the memory a long recording would.
+pub const RECORDING_BACKLOG: usize = 8;
+
+/// The `RECORDING_DIR` constant holds the name of the directory for recordings, under the data
+/// directory from [`crate::Home::data`].
+pub const RECORDING_DIR: &str = "recordings";
@@ -0,0 +1,94 @@
+/// The `runtime` module hosts the functions that run async work, so that the rest of the app
+/// does not care whether it runs on the desktop or in a web browser.
+///
+/// On the desktop, background work runs on the [`tokio`] runtime started by `main`, with a pool
+/// of threads for work that blocks.  A web page has neither: there is one thread, and async work
+/// runs on the event loop of the browser, through [`wasm_bindgen_futures`].  Rather than scatter
+/// the difference through every module that starts a task or waits on a timer, those modules
+/// call the functions here, which pick the right way for the target at compile time.  See
+/// [`crate::run_web`] for what else changes in the browser.
+use crate::Arrive;
+use std::future::Future;
+
+/// The `spawn` function runs `task` in the background, without waiting for it.  On the desktop
+/// it runs on the [`tokio`] runtime, and in the browser on the event loop of the page.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<F>(task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(task);
+}
+
+/// The `spawn` function runs `task` in the background, without waiting for it.  On the desktop
+/// it runs on the [`tokio`] runtime, and in the browser on the event loop of the page.
+#[cfg(target_arch = "wasm32")]
+pub fn spawn<F>(task: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+/// The `unblock` function runs `work`, which blocks, where it will not hold up the event loop,
+/// and waits for the result.  On the desktop that is a worker thread of the [`tokio`] runtime.
+///
+/// Will [`crate::Blame::Join`] if the worker thread panics.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn unblock<F, T>(work: F) -> Arrive<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Ok(tokio::task::spawn_blocking(work).await?)
+}
+
+/// The `unblock` function runs `work`, which blocks, where it will not hold up the event loop,
+/// and waits for the result.  A web page has no other thread to run it on, so in the browser
+/// it runs in place, and the page waits for it.
+#[cfg(target_arch = "wasm32")]
+pub async fn unblock<F, T>(work: F) -> Arrive<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Ok(work())
+}
+
+/// The `sleep` function waits for `duration` without blocking, on the timer of the [`tokio`]
+/// runtime on the desktop, and a timer of the page in the browser.
+pub async fn sleep(duration: std::time::Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+}
+
+/// The `timeout` function waits for `task` for up to `limit`, and returns its output, or
+/// [`None`] if the time runs out first, in which case `task` is dropped.
+pub async fn timeout<T>(limit: std::time::Duration, task: impl Future<Output = T>) -> Option<T> {
+    tokio::select! {
+        output = task => Some(output),
+        () = sleep(limit) => None,
+    }
+}
+
+/// The `settle` function waits in place for `future`, which has to finish without help from the
+/// event loop, as the error scopes of the graphics card do on the desktop, and returns its
+/// output.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn settle<F: Future>(future: F) -> Option<F::Output> {
+    Some(pollster::block_on(future))
+}
+
+/// The `settle` function checks `future` once, and returns its output if it is done, or
+/// [`None`] if it is not.  In the browser, nothing finishes while we hold the thread, so waiting
+/// in place would wait forever.
+#[cfg(target_arch = "wasm32")]
+pub fn settle<F: Future>(future: F) -> Option<F::Output> {
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    match std::pin::pin!(future).poll(&mut context) {
+        std::task::Poll::Ready(output) => Some(output),
+        std::task::Poll::Pending => None,
+    }
+}
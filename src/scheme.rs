@@ -0,0 +1,399 @@
+/// The `scheme` module provides the [`Scheme`] struct, which holds a ramp of colors for shading
+/// a choropleth, and the [`Vision`] enum, which simulates how a ramp looks to a user with color
+/// blindness.
+///
+/// # Shading data for every eye with `Scheme`
+///
+/// About one man in twelve, and one woman in two hundred, tells red from green poorly.  A ramp
+/// from red through yellow to green, the first one most people reach for, shows them a muddy
+/// band where the middle classes should be, and a map of property values shaded with it tells
+/// them nothing.  Cynthia Brewer worked out ramps for maps that stay readable, in print, on a
+/// projector, and to the color blind, and the [`SCHEMES`] ship a set of them in the style of
+/// ColorBrewer:
+///
+/// * A [`Ramp::Sequential`] scheme runs from light to dark, for data that runs from low to high,
+///   like the value of a parcel.  Every sequential scheme here is safe for color blindness.
+/// * A [`Ramp::Diverging`] scheme runs from one dark hue through a light middle to another, for
+///   data that runs either way from a middle value, like the change in population since the last
+///   census.  Most are safe, and the `safe` field marks the two that are not, `RdYlGn` and
+///   `Spectral`, which we keep because users ask for them by name.
+///
+/// Each scheme holds the colors of its largest number of classes, nine for a sequential scheme
+/// and eleven for a diverging one, and [`Scheme::colors`] picks evenly spaced colors from them
+/// for fewer classes.  ColorBrewer tunes each number of classes by hand, so ours come out close
+/// to the published ramps rather than the same.
+///
+/// The `[choropleth]` table of the config (see [`crate::Choropleth`]) picks the scheme, the
+/// number of classes and the column to shade by, and classifies the rows of each data file into
+/// the classes by quantile, and each file draws over the map in the colors of its classes (see
+/// [`crate::Figure`]).  The default, [`DEFAULT_SCHEME`], is sequential and safe, and the
+/// app warns if the config picks a scheme that is not.
+///
+/// ## Checking a scheme with `Vision`
+///
+/// A user with typical color vision cannot see the trouble with a ramp, so
+/// [`crate::Act::CycleVision`] shows it to them.  Each press moves on through
+/// [`Vision::Deuteranopia`], missing the green cones, and [`Vision::Protanopia`], missing the red
+/// ones, and back to normal vision.  While a vision is on, its shader from [`Vision::shader`]
+/// runs over the whole map as an [`crate::Effect`], so the base map and every layer look as they
+/// would to that user, and the log shows the colors of the choropleth scheme as they would see
+/// them, with [`Scheme::swatch`].  The style editor will show the same preview beside each
+/// scheme once it lands.  We simulate with the matrices of Machado, Oliveira and Fernandes
+/// (2009) at full severity, applied in linear light.
+///
+/// * The `name` field holds the name of the scheme, as ColorBrewer names it.
+/// * The `ramp` field holds the [`Ramp`] of the scheme.
+/// * The `safe` field is `true` if users with color blindness can tell the classes apart.
+/// * The `colors` field holds the colors of the largest number of classes, in sRGB.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Scheme {
+    name: &'static str,
+    ramp: Ramp,
+    safe: bool,
+    colors: &'static [[u8; 3]],
+}
+
+impl Scheme {
+    /// The `find` method returns the scheme in [`SCHEMES`] called `name`, ignoring case, or
+    /// [`None`] if there is none.
+    pub fn find(name: &str) -> Option<&'static Self> {
+        SCHEMES
+            .iter()
+            .find(|scheme| scheme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The `colors` method returns `classes` colors from the scheme, evenly spaced from the
+    /// first to the last, as described in the [`Scheme`] docs.  Asking for more classes than the
+    /// scheme holds returns every color it has.
+    pub fn colors(&self, classes: usize) -> Vec<[u8; 3]> {
+        let last = self.colors.len() - 1;
+        match classes {
+            0 => Vec::new(),
+            1 => vec![self.colors[last / 2]],
+            classes if classes > last => self.colors.to_vec(),
+            classes => (0..classes)
+                .map(|class| self.colors[(class * last + (classes - 1) / 2) / (classes - 1)])
+                .collect(),
+        }
+    }
+
+    /// The `swatch` method writes `classes` colors of the scheme as hex codes, as seen with
+    /// `vision`, or with normal vision if [`None`].
+    pub fn swatch(&self, classes: usize, vision: Option<Vision>) -> String {
+        self.colors(classes)
+            .into_iter()
+            .map(|color| match vision {
+                Some(vision) => vision.simulate(color),
+                None => color,
+            })
+            .map(|[red, green, blue]| format!("#{red:02x}{green:02x}{blue:02x}"))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+/// The `Ramp` enum names the shapes of [`Scheme`], as described in the [`Scheme`] docs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, strum_macros::EnumIter, derive_more::Display)]
+pub enum Ramp {
+    /// The `Sequential` variant runs from light to dark.
+    Sequential,
+    /// The `Diverging` variant runs from dark through light to dark in another hue.
+    Diverging,
+}
+
+/// The `Vision` enum names the kinds of color blindness we can simulate, as described in the
+/// [`Scheme`] docs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, strum_macros::EnumIter, derive_more::Display)]
+pub enum Vision {
+    /// The `Deuteranopia` variant sees without green cones.
+    Deuteranopia,
+    /// The `Protanopia` variant sees without red cones.
+    Protanopia,
+}
+
+impl Vision {
+    /// The `next` method returns the vision after `vision` for [`crate::Act::CycleVision`],
+    /// where [`None`] is normal vision.
+    pub fn next(vision: Option<Self>) -> Option<Self> {
+        match vision {
+            None => Some(Self::Deuteranopia),
+            Some(Self::Deuteranopia) => Some(Self::Protanopia),
+            Some(Self::Protanopia) => None,
+        }
+    }
+
+    /// The `matrix` method returns the rows of the matrix that takes a color in linear light to
+    /// the color seen with the vision.
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Self::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+        }
+    }
+
+    /// The `simulate` method returns `color`, in sRGB, as seen with the vision.
+    pub fn simulate(&self, color: [u8; 3]) -> [u8; 3] {
+        let linear = color.map(|channel| {
+            let value = f32::from(channel) / 255.0;
+            match value <= 0.04045 {
+                true => value / 12.92,
+                false => ((value + 0.055) / 1.055).powf(2.4),
+            }
+        });
+        self.matrix().map(|row| {
+            let value =
+                (row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]).clamp(0.0, 1.0);
+            let value = match value <= 0.003_130_8 {
+                true => value * 12.92,
+                false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+            };
+            (value * 255.0).round() as u8
+        })
+    }
+
+    /// The `shader` method returns the fragment shader that runs the vision over the map, in the
+    /// form of an [`crate::Effect`].  The frame reads in linear light, where the matrix applies.
+    pub fn shader(&self) -> String {
+        let [red, green, blue] = self
+            .matrix()
+            .map(|[r, g, b]| format!("vec3<f32>({r:?}, {g:?}, {b:?})"));
+        format!(
+            r#"
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {{
+    let color = textureSample(frame, frame_sampler, in.uv);
+    let seen = vec3<f32>(
+        dot({red}, color.rgb),
+        dot({green}, color.rgb),
+        dot({blue}, color.rgb),
+    );
+    return vec4<f32>(clamp(seen, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
+}}
+"#
+        )
+    }
+}
+
+/// The `DEFAULT_SCHEME` constant holds the name of the scheme a choropleth takes unless the
+/// config picks another, a sequential ramp safe for color blindness that prints well in grey.
+pub const DEFAULT_SCHEME: &str = "YlGnBu";
+
+/// The `CLASSES` constant holds the number of classes a choropleth takes unless the config picks
+/// another.  Five is as many as most readers can tell apart and match to the legend.
+pub const CLASSES: usize = 5;
+
+/// The `SCHEMES` constant holds the schemes we ship, as described in the [`Scheme`] docs.
+pub const SCHEMES: &[Scheme] = &[
+    Scheme {
+        name: "Blues",
+        ramp: Ramp::Sequential,
+        safe: true,
+        colors: &[
+            [247, 251, 255],
+            [222, 235, 247],
+            [198, 219, 239],
+            [158, 202, 225],
+            [107, 174, 214],
+            [66, 146, 198],
+            [33, 113, 181],
+            [8, 81, 156],
+            [8, 48, 107],
+        ],
+    },
+    Scheme {
+        name: "Greens",
+        ramp: Ramp::Sequential,
+        safe: true,
+        colors: &[
+            [247, 252, 245],
+            [229, 245, 224],
+            [199, 233, 192],
+            [161, 217, 155],
+            [116, 196, 118],
+            [65, 171, 93],
+            [35, 139, 69],
+            [0, 109, 44],
+            [0, 68, 27],
+        ],
+    },
+    Scheme {
+        name: "Oranges",
+        ramp: Ramp::Sequential,
+        safe: true,
+        colors: &[
+            [255, 245, 235],
+            [254, 230, 206],
+            [253, 208, 162],
+            [253, 174, 107],
+            [253, 141, 60],
+            [241, 105, 19],
+            [217, 72, 1],
+            [166, 54, 3],
+            [127, 39, 4],
+        ],
+    },
+    Scheme {
+        name: "Purples",
+        ramp: Ramp::Sequential,
+        safe: true,
+        colors: &[
+            [252, 251, 253],
+            [239, 237, 245],
+            [218, 218, 235],
+            [188, 189, 220],
+            [158, 154, 200],
+            [128, 125, 186],
+            [106, 81, 163],
+            [84, 39, 143],
+            [63, 0, 125],
+        ],
+    },
+    Scheme {
+        name: "YlGnBu",
+        ramp: Ramp::Sequential,
+        safe: true,
+        colors: &[
+            [255, 255, 217],
+            [237, 248, 177],
+            [199, 233, 180],
+            [127, 205, 187],
+            [65, 182, 196],
+            [29, 145, 192],
+            [34, 94, 168],
+            [37, 52, 148],
+            [8, 29, 88],
+        ],
+    },
+    Scheme {
+        name: "YlOrRd",
+        ramp: Ramp::Sequential,
+        safe: true,
+        colors: &[
+            [255, 255, 204],
+            [255, 237, 160],
+            [254, 217, 118],
+            [254, 178, 76],
+            [253, 141, 60],
+            [252, 78, 42],
+            [227, 26, 28],
+            [189, 0, 38],
+            [128, 0, 38],
+        ],
+    },
+    Scheme {
+        name: "BrBG",
+        ramp: Ramp::Diverging,
+        safe: true,
+        colors: &[
+            [84, 48, 5],
+            [140, 81, 10],
+            [191, 129, 45],
+            [223, 194, 125],
+            [246, 232, 195],
+            [245, 245, 245],
+            [199, 234, 229],
+            [128, 205, 193],
+            [53, 151, 143],
+            [1, 102, 94],
+            [0, 60, 48],
+        ],
+    },
+    Scheme {
+        name: "PiYG",
+        ramp: Ramp::Diverging,
+        safe: true,
+        colors: &[
+            [142, 1, 82],
+            [197, 27, 125],
+            [222, 119, 174],
+            [241, 182, 218],
+            [253, 224, 239],
+            [247, 247, 247],
+            [230, 245, 208],
+            [184, 225, 134],
+            [127, 188, 65],
+            [77, 146, 33],
+            [39, 100, 25],
+        ],
+    },
+    Scheme {
+        name: "PuOr",
+        ramp: Ramp::Diverging,
+        safe: true,
+        colors: &[
+            [127, 59, 8],
+            [179, 88, 6],
+            [224, 130, 20],
+            [253, 184, 99],
+            [254, 224, 182],
+            [247, 247, 247],
+            [216, 218, 235],
+            [178, 171, 210],
+            [128, 115, 172],
+            [84, 39, 136],
+            [45, 0, 75],
+        ],
+    },
+    Scheme {
+        name: "RdBu",
+        ramp: Ramp::Diverging,
+        safe: true,
+        colors: &[
+            [103, 0, 31],
+            [178, 24, 43],
+            [214, 96, 77],
+            [244, 165, 130],
+            [253, 219, 199],
+            [247, 247, 247],
+            [209, 229, 240],
+            [146, 197, 222],
+            [67, 147, 195],
+            [33, 102, 172],
+            [5, 48, 97],
+        ],
+    },
+    Scheme {
+        name: "RdYlGn",
+        ramp: Ramp::Diverging,
+        safe: false,
+        colors: &[
+            [165, 0, 38],
+            [215, 48, 39],
+            [244, 109, 67],
+            [253, 174, 97],
+            [254, 224, 139],
+            [255, 255, 191],
+            [217, 239, 139],
+            [166, 217, 106],
+            [102, 189, 99],
+            [26, 152, 80],
+            [0, 104, 55],
+        ],
+    },
+    Scheme {
+        name: "Spectral",
+        ramp: Ramp::Diverging,
+        safe: false,
+        colors: &[
+            [158, 1, 66],
+            [213, 62, 79],
+            [244, 109, 67],
+            [253, 174, 97],
+            [254, 224, 139],
+            [255, 255, 191],
+            [230, 245, 152],
+            [171, 221, 164],
+            [102, 194, 165],
+            [50, 136, 189],
+            [94, 79, 162],
+        ],
+    },
+];
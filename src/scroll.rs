@@ -0,0 +1,212 @@
+use web_time as time;
+use winit::{dpi, event, keyboard};
+
+/// The `scroll` module provides the [`Scroll`] struct, which holds user preferences for the
+/// mouse wheel, and the [`Glide`] enum, which describes what a turn of the wheel should do.
+///
+/// # Configuring the scroll wheel with `Scroll`
+///
+/// People have strong feelings about scroll wheels.  Some expect the wheel to zoom the map, like
+/// most web maps, while others expect it to pan, like a document.  Trackpads report scrolling in
+/// pixels, mice report it in lines, and half the world has "natural" scrolling turned on.  Rather
+/// than pick a winner, we read the preferences from the `[scroll]` table in `config.toml`:
+///
+/// ```toml
+/// [scroll]
+/// mode = "Zoom"
+/// invert = false
+/// sensitivity = 1.0
+/// ctrl = "UiZoom"
+/// shift = "Pan"
+/// ```
+///
+/// * The `mode` field sets the [`ScrollMode`] of the plain scroll wheel.
+/// * The `invert` field reverses the direction of scrolling.
+/// * The `sensitivity` field multiplies the distance of each scroll.
+/// * The `ctrl`, `shift` and `alt` fields set the [`ScrollMode`] used while holding the
+///   corresponding modifier key.  When a field is missing, the modifier does not change the mode.
+///
+/// Missing fields take the values from [`Scroll::default`], shown above.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    derive_getters::Getters,
+    derive_setters::Setters,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[setters(prefix = "with_", borrow_self)]
+#[serde(default)]
+pub struct Scroll {
+    mode: ScrollMode,
+    invert: bool,
+    sensitivity: f64,
+    ctrl: Option<ScrollMode>,
+    shift: Option<ScrollMode>,
+    alt: Option<ScrollMode>,
+}
+
+impl Default for Scroll {
+    fn default() -> Self {
+        Self {
+            mode: ScrollMode::Zoom,
+            invert: false,
+            sensitivity: 1.0,
+            ctrl: Some(ScrollMode::UiZoom),
+            shift: Some(ScrollMode::Pan),
+            alt: None,
+        }
+    }
+}
+
+impl Scroll {
+    /// The `glide` method converts a [`event::MouseScrollDelta`] into a [`Glide`], given the
+    /// modifier keys held down at the time.
+    ///
+    /// We convert line deltas from a mouse wheel into pixels using [`LINE_HEIGHT`], so that mice
+    /// and trackpads travel a similar distance, then apply the `invert` and `sensitivity`
+    /// settings.  The [`ScrollMode`] comes from the first held modifier with a configured mode,
+    /// checking `Ctrl`, then `Shift`, then `Alt`, and otherwise from the `mode` field.
+    pub fn glide(
+        &self,
+        delta: &event::MouseScrollDelta,
+        modifiers: keyboard::ModifiersState,
+    ) -> Glide {
+        let (x, y) = match delta {
+            event::MouseScrollDelta::LineDelta(x, y) => {
+                (*x as f64 * LINE_HEIGHT, *y as f64 * LINE_HEIGHT)
+            }
+            event::MouseScrollDelta::PixelDelta(position) => (position.x, position.y),
+        };
+        let sign = if self.invert { -1.0 } else { 1.0 };
+        let (x, y) = (x * sign * self.sensitivity, y * sign * self.sensitivity);
+        match self.mode(modifiers) {
+            ScrollMode::Zoom => Glide::Zoom(ZOOM_STEP.powf(-y / LINE_HEIGHT)),
+            ScrollMode::Pan => Glide::Pan(x, y),
+            ScrollMode::UiZoom => Glide::UiZoom(ZOOM_STEP.powf(y / LINE_HEIGHT)),
+        }
+    }
+
+    /// The `mode` method returns the [`ScrollMode`] in effect for the given modifier keys.
+    pub fn mode(&self, modifiers: keyboard::ModifiersState) -> ScrollMode {
+        let held = [
+            (modifiers.control_key(), self.ctrl),
+            (modifiers.shift_key(), self.shift),
+            (modifiers.alt_key(), self.alt),
+        ];
+        held.iter()
+            .find_map(|(pressed, mode)| if *pressed { *mode } else { None })
+            .unwrap_or(self.mode)
+    }
+}
+
+/// The `ScrollMode` enum describes what the scroll wheel does.
+///
+/// * The `Zoom` variant zooms the map in and out.
+/// * The `Pan` variant moves the map up, down and sideways.
+/// * The `UiZoom` variant scales the user interface, leaving the map alone.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum ScrollMode {
+    /// The `Zoom` variant indicates the scroll wheel zooms the map.
+    #[default]
+    Zoom,
+    /// The `Pan` variant indicates the scroll wheel pans the map.
+    Pan,
+    /// The `UiZoom` variant indicates the scroll wheel scales the user interface.
+    UiZoom,
+}
+
+/// The `Glide` enum describes the effect of a single scroll event, as computed by
+/// [`Scroll::glide`].
+///
+/// * The `Zoom` variant holds the factor by which to multiply the map resolution.  Factors less
+///   than one zoom in.
+/// * The `Pan` variant holds the horizontal and vertical distance to move the map, in pixels.
+/// * The `UiZoom` variant holds the factor by which to multiply the scale of the user interface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Glide {
+    /// The `Zoom` variant holds a factor for the map resolution.
+    Zoom(f64),
+    /// The `Pan` variant holds a distance in pixels.
+    Pan(f64, f64),
+    /// The `UiZoom` variant holds a factor for the interface scale.
+    UiZoom(f64),
+}
+
+/// The `Flight` struct spreads a [`Glide`] over [`FLIGHT_TIME`], so that a map moved from the
+/// keyboard or a controller eases into its new view instead of jumping there.  The lens asks for
+/// frames while a flight is under way, and each frame takes the next part of the glide with
+/// [`Flight::step`], until the flight lands.
+///
+/// * The `glide` field holds the whole move.
+/// * The `anchor` field holds the position in the window the move is anchored at.
+/// * The `start` field holds the time the flight took off.
+/// * The `done` field holds the share of the move made so far, from zero to one.
+#[derive(Debug, Clone, Copy, PartialEq, derive_getters::Getters)]
+pub struct Flight {
+    glide: Glide,
+    anchor: dpi::PhysicalPosition<f64>,
+    start: time::Instant,
+    done: f64,
+}
+
+impl Flight {
+    /// The `new` method starts a flight making `glide`, anchored at `anchor`, at the time `start`.
+    pub fn new(glide: Glide, anchor: dpi::PhysicalPosition<f64>, start: time::Instant) -> Self {
+        Self {
+            glide,
+            anchor,
+            start,
+            done: 0.0,
+        }
+    }
+
+    /// The `step` method returns the part of the glide to make between the last step and `now`.
+    /// The flight slows as it lands, so most of the move happens in the first few frames, and the
+    /// parts multiply (for a zoom) or add (for a pan) to the whole glide by the time it lands.
+    pub fn step(&mut self, now: time::Instant) -> Glide {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f64();
+        let t = (elapsed / FLIGHT_TIME.as_secs_f64()).min(1.0);
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let part = eased - self.done;
+        self.done = eased;
+        match self.glide {
+            Glide::Zoom(factor) => Glide::Zoom(factor.powf(part)),
+            Glide::Pan(x, y) => Glide::Pan(x * part, y * part),
+            Glide::UiZoom(factor) => Glide::UiZoom(factor.powf(part)),
+        }
+    }
+
+    /// The `landed` method returns `true` once the flight has made the whole glide.
+    pub fn landed(&self) -> bool {
+        self.done >= 1.0
+    }
+}
+
+/// The `FLIGHT_TIME` constant sets how long a [`Flight`] takes to make its glide.
+pub const FLIGHT_TIME: time::Duration = time::Duration::from_millis(150);
+
+/// The `LINE_HEIGHT` constant sets the number of pixels scrolled per line reported by a mouse
+/// wheel.
+pub const LINE_HEIGHT: f64 = 20.0;
+
+/// The `PAN_STEP` constant sets the number of pixels moved by each pan action, like
+/// [`crate::Act::PanLeft`].
+pub const PAN_STEP: f64 = 100.0;
+
+/// The `ZOOM_STEP` constant sets the zoom factor applied per line of scrolling.
+pub const ZOOM_STEP: f64 = 1.25;
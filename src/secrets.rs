@@ -0,0 +1,111 @@
+use crate::{Api, Arrive, Context};
+use std::path;
+
+/// The `secrets` module provides the [`Secrets`] struct, which holds credentials kept apart from
+/// the config.
+///
+/// # Keeping keys out of the config with `Secrets`
+///
+/// The config is meant to be shared.  The project config sits in a checkout and gets committed,
+/// the system config gets copied between machines, and a profile gets pasted into a chat so a
+/// colleague can use it.  An API key in any of them ends up somewhere it should not.  Instead,
+/// credentials live in [`SECRETS_FILE`], which [`crate::Home::secrets`] places in the user
+/// config directory, away from any checkout:
+///
+/// ```toml
+/// [api]
+/// key = "..."
+/// ```
+///
+/// If there is no key in the file, we ask the system keyring through the [`keyring`] crate,
+/// under the service name [`crate::APPLICATION`] and the user [`KEYRING_USER`].  Failing both,
+/// a key in the config still works, but [`crate::Diagnostic::check`] points out each one found
+/// in a file.  Environment variables like `BEA_EGUI_API_KEY` are fine, since they never touch
+/// a file.  The browser has no system keyring, so there the key comes from the file or the
+/// config alone.
+///
+/// The secrets file is read on startup and nothing in the app writes to it.  Write-back of the
+/// config, as in [`crate::Cmd::save`], only ever touches the config file.
+///
+/// * The `api` field holds the [`Api`] credentials from the `[api]` table of the file.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Secrets {
+    api: Api,
+}
+
+impl Secrets {
+    /// The `load` method reads the secrets from the file at `path`, falling back on the system
+    /// keyring for anything the file does not hold.  A missing file is fine, and yields whatever
+    /// the keyring has.
+    ///
+    /// Will [`crate::Blame::Io`] if the file exists but cannot be read, and
+    /// [`crate::Blame::TomlDe`] if it is not valid TOML.
+    #[tracing::instrument]
+    pub fn load(path: &path::Path) -> Arrive<Self> {
+        let mut secrets = if path.is_file() {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            let secrets = toml::from_str::<Self>(&contents)
+                .with_context(|| format!("parsing {}", path.display()))?;
+            tracing::trace!("Secrets read from {}.", path.display());
+            secrets
+        } else {
+            Self::default()
+        };
+        if secrets.api.key().is_none() {
+            secrets.api = Api::new(Self::keyring());
+        }
+        Ok(secrets)
+    }
+
+    /// The `keyring` method returns the API key from the system keyring, if there is one.  A
+    /// platform without a keyring, or an empty entry, yields [`None`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn keyring() -> Option<String> {
+        let entry = match keyring::Entry::new(crate::APPLICATION, KEYRING_USER) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::trace!("No keyring available: {e}");
+                return None;
+            }
+        };
+        match entry.get_password() {
+            Ok(key) => {
+                tracing::trace!("API key read from the keyring.");
+                Some(key)
+            }
+            Err(keyring::Error::NoEntry) => None,
+            Err(e) => {
+                tracing::warn!("Could not read the keyring: {e}");
+                None
+            }
+        }
+    }
+
+    /// The `keyring` method returns [`None`], since the browser has no system keyring.
+    #[cfg(target_arch = "wasm32")]
+    pub fn keyring() -> Option<String> {
+        None
+    }
+
+    /// The `is_secret` method returns `true` if the config key at `key`, a dotted path like
+    /// `api.key`, holds a secret, including inside a [`crate::Profile`].
+    pub fn is_secret(key: &str) -> bool {
+        SECRETS
+            .iter()
+            .any(|secret| key == *secret || key.ends_with(&format!(".{secret}")))
+    }
+}
+
+/// The `KEYRING_USER` constant holds the user name of the API key entry in the system keyring.
+pub const KEYRING_USER: &str = "api_key";
+
+/// The `SECRETS` constant lists the config keys that hold secrets, and belong in
+/// [`SECRETS_FILE`] rather than the config.
+pub const SECRETS: [&str; 1] = ["api.key"];
+
+/// The `SECRETS_FILE` constant holds the name of the secrets file.
+pub const SECRETS_FILE: &str = "secrets.toml";
@@ -0,0 +1,1708 @@
+use crate::{
+    default_config, Act, Arrive, Cli, Clip, Contrast, Diagnostic, Gamut, Group, Home, Listing,
+    Pacing, Power, PresentMode, Problem, Profile, Scheme, Scroll, Secrets, Service, Snippet,
+    CHORD_TIMEOUT, CLASSES, CLOUD_COVER, DEFAULT_SCHEME, DOUBLE_PRESS, HOLD_DELAY, MATCH_SCORE,
+    PROFILES, STAC_CATALOG,
+};
+use arrow::array::Array;
+use std::collections::{BTreeMap, HashMap};
+use std::path;
+use strum::IntoEnumIterator;
+use web_time as time;
+
+/// The `settings` module provides the [`Layers`] struct, which merges the config from every
+/// place it can come from, and the [`Settings`] struct, which reads the merged config into
+/// typed sections.
+///
+/// # Merging the config with `Layers`
+///
+/// Settings come from a lot of places now: defaults compiled into the app, files for the system,
+/// the user and the project, a profile, the environment and the command line.  Each has a
+/// reason to exist, and the only sane way to combine them is a fixed order where later layers
+/// win.  From lowest to highest precedence:
+///
+/// 1. Built-in defaults, read from the same text as [`default_config`], so the defaults and the
+///    default config file cannot disagree.
+/// 2. The system config file.
+/// 3. The user config file.
+/// 4. The project config file, in the working directory.
+/// 5. The [`Profile`] chosen with `--profile`, read from the merged files above.
+/// 6. Environment variables starting with [`ENV_PREFIX`].
+/// 7. Arguments from the command line, from [`Cli::overrides`].
+///
+/// The API key from the [`Secrets`] file comes in between the profile and the environment, as
+/// described in [`Settings::with_secrets`].
+///
+/// [`Home::layers`] says where the three files live.  Tables merge key by key, so a user file
+/// that sets `scroll.mode` keeps the rest of the `[scroll]` table from the layers below.
+///
+/// Config files may be TOML, YAML or JSON, told apart by extension with [`Layers::format`], so
+/// that deployment tooling can write whichever it speaks.  The user config file is upgraded to
+/// the current layout with [`crate::migrate`] before we read it, and the system and project
+/// files are upgraded in memory with [`crate::upgraded`].  A file
+/// that fails to parse is left out, with a [`Diagnostic`] saying why, rather than taking the
+/// other layers down with it.
+///
+/// * The `cli` field holds the command line arguments.
+/// * The `home` field holds the [`Home`] that locates the files.
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Layers {
+    cli: Cli,
+    home: Home,
+}
+
+impl Layers {
+    /// The `load` method merges the layers into a single [`config::Config`], and returns it
+    /// along with a [`Diagnostic`] for each layer that could not be used.  Always produces a
+    /// config, though in a bad enough case it holds only the built-in defaults.
+    #[tracing::instrument(skip_all)]
+    pub fn load(&self) -> (config::Config, Vec<Diagnostic>) {
+        let mut diagnostics = Vec::new();
+        let defaults = config::File::from_str(&default_config(), config::FileFormat::Toml);
+        let mut files = config::Config::builder().add_source(defaults.clone());
+        let user = self.home.config();
+        for path in self.home.layers() {
+            if !path.is_file() {
+                continue;
+            }
+            if path == user {
+                if let Err(e) = crate::migrate(&path) {
+                    tracing::warn!("Could not upgrade config at {}: {e}", path.display());
+                }
+            }
+            let Some(format) = Self::format(&path) else {
+                diagnostics.push(Diagnostic::new(
+                    String::new(),
+                    Problem::Unreadable("the file must be TOML, YAML or JSON".to_string()),
+                    Some(path.display().to_string()),
+                ));
+                continue;
+            };
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) => {
+                    tracing::warn!("Could not read config from {}.", path.display());
+                    diagnostics.push(Diagnostic::new(
+                        String::new(),
+                        Problem::Unreadable(e.to_string()),
+                        Some(path.display().to_string()),
+                    ));
+                    continue;
+                }
+            };
+            // Files that belong to someone else are upgraded in memory, and left as they are.
+            let text = match path == user {
+                true => text,
+                false => crate::upgraded(&path, text),
+            };
+            // Read each file alone first, so a broken file only costs its own layer.
+            let file = config::File::from_str(&text, format);
+            match config::Config::builder().add_source(file.clone()).build() {
+                Ok(_) => {
+                    tracing::trace!("Config layer: {}", path.display());
+                    files = files.add_source(file);
+                }
+                Err(e) => {
+                    tracing::warn!("Could not read config from {}.", path.display());
+                    let problem = match format {
+                        config::FileFormat::Toml => Snippet::from_file(&path)
+                            .map(Problem::Syntax)
+                            .unwrap_or_else(|| Problem::Unreadable(e.to_string())),
+                        _ => Problem::Unreadable(e.to_string()),
+                    };
+                    diagnostics.push(Diagnostic::new(
+                        String::new(),
+                        problem,
+                        Some(path.display().to_string()),
+                    ));
+                }
+            }
+        }
+        let base = match files.build() {
+            Ok(base) => base,
+            Err(e) => {
+                tracing::warn!("Could not merge config files: {e}");
+                config::Config::builder()
+                    .add_source(defaults)
+                    .build()
+                    .unwrap_or_default()
+            }
+        };
+
+        let mut builder = config::Config::builder().add_source(base.clone());
+        if let Some(name) = self.cli.profile() {
+            match Profile::load(&base, name) {
+                Ok(profile) => builder = builder.add_source(profile),
+                Err(e) => {
+                    tracing::warn!("Could not apply profile {name}: {e}");
+                    diagnostics.push(Diagnostic::new(
+                        format!("{PROFILES}.{name}"),
+                        Problem::Unknown,
+                        Some("--profile".to_string()),
+                    ));
+                }
+            }
+        }
+        let config = match self
+            .overlay(builder)
+            .and_then(|builder| Ok(builder.build()?))
+        {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("Could not apply environment and arguments: {e}");
+                diagnostics.push(Diagnostic::new(
+                    String::new(),
+                    Problem::Unreadable(e.to_string()),
+                    Some("environment or command line".to_string()),
+                ));
+                base
+            }
+        };
+        (config, diagnostics)
+    }
+
+    /// The `format` method returns the [`config::FileFormat`] of the config file at `path`, from
+    /// its extension, or [`None`] if the extension is not one of [`crate::CONFIG_FORMATS`].
+    pub fn format(path: &path::Path) -> Option<config::FileFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "toml" => Some(config::FileFormat::Toml),
+            "yaml" | "yml" => Some(config::FileFormat::Yaml),
+            "json" => Some(config::FileFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// The `overlay` method adds the environment and command line layers to `builder`.
+    ///
+    /// Environment variable names start with [`ENV_PREFIX`] and an underscore.  The rest of the
+    /// name, in lower case, is the config key, so `BEA_EGUI_HEADLESS` sets `headless` and
+    /// `BEA_EGUI_NEW_WINDOW` sets the binding for [`crate::Act::NewWindow`].  A double underscore
+    /// reaches into a table, so `BEA_EGUI_SCROLL__MODE=Pan` sets `mode` in the `[scroll]` table.
+    /// The values a deployment sets most often also have the short names in [`ENV_SHORTCUTS`],
+    /// so `BEA_EGUI_API_KEY` sets `key` in the `[api]` table and `BEA_EGUI_TILE_URL` sets
+    /// `tile_url` in the `[map]` table, unless the long name is set too.  Values that look like
+    /// numbers or booleans are read as such.  Container deployments like the kiosk can then ship
+    /// one config file in the image and adjust it per machine, keeping values like
+    /// `BEA_EGUI_API_KEY` out of the image entirely.
+    ///
+    /// Will [`crate::Blame::Config`] if [`Cli::overrides`] fails.
+    pub fn overlay(
+        &self,
+        builder: config::ConfigBuilder<config::builder::DefaultState>,
+    ) -> Arrive<config::ConfigBuilder<config::builder::DefaultState>> {
+        let builder = builder.add_source(
+            config::Environment::with_prefix(ENV_PREFIX)
+                .prefix_separator("_")
+                .separator("__")
+                .try_parsing(true)
+                .source(Some(Self::environment(std::env::vars()))),
+        );
+        self.cli.overrides(builder)
+    }
+
+    /// The `environment` method returns the variables of `vars` for the environment layer, with
+    /// the short names of [`ENV_SHORTCUTS`] spelled out in full, as described in
+    /// [`Layers::overlay`].
+    pub fn environment(
+        vars: impl Iterator<Item = (String, String)>,
+    ) -> config::Map<String, String> {
+        let mut environment = config::Map::new();
+        let mut shortcuts = Vec::new();
+        for (name, value) in vars {
+            let short = name
+                .strip_prefix(ENV_PREFIX)
+                .and_then(|rest| rest.strip_prefix('_'))
+                .and_then(|rest| ENV_SHORTCUTS.iter().find(|(short, _)| *short == rest));
+            match short {
+                Some((_, long)) => shortcuts.push((format!("{ENV_PREFIX}_{long}"), value)),
+                None => {
+                    environment.insert(name, value);
+                }
+            }
+        }
+        for (name, value) in shortcuts {
+            environment.entry(name).or_insert(value);
+        }
+        environment
+    }
+
+    /// The `pinned` method returns the config set by the environment and command line layers
+    /// alone, which no other source may override, as [`Settings::with_secrets`] needs to know.
+    pub fn pinned(&self) -> config::Config {
+        self.overlay(config::Config::builder())
+            .and_then(|builder| Ok(builder.build()?))
+            .unwrap_or_default()
+    }
+}
+
+/// The `ATTRIBUTION` constant holds the default credit for the tile server of the base map.
+pub const ATTRIBUTION: &str = "© OpenStreetMap contributors";
+
+/// The `CACHE_SIZE` constant holds the default limit on downloaded tiles, in megabytes.
+pub const CACHE_SIZE: u64 = 512;
+
+/// The `CENTER` constant holds the default latitude and longitude of the base map, in Grants
+/// Pass.
+pub const CENTER: [f64; 2] = [42.4435, -123.3260];
+
+/// The `ENV_PREFIX` constant holds the prefix of environment variables that override config
+/// values.  See [`Layers::overlay`].
+pub const ENV_PREFIX: &str = "BEA_EGUI";
+
+/// The `ENV_SHORTCUTS` constant holds the short names of environment variables, after the
+/// [`ENV_PREFIX`], each with the long name it stands for.  See [`Layers::overlay`].
+pub const ENV_SHORTCUTS: [(&str, &str); 2] =
+    [("API_KEY", "API__KEY"), ("TILE_URL", "MAP__TILE_URL")];
+
+/// The `LOG_LEVEL` constant holds the log filter used when neither the config nor `RUST_LOG`
+/// gives one.
+pub const LOG_LEVEL: &str = "bea_egui=info";
+
+/// The `Settings` struct holds the merged config, read into types the rest of the app can use
+/// without asking the [`config`] crate for strings.
+///
+/// # Reading the config with `Settings`
+///
+/// Every part of the app used to reach into the [`config::Config`] by key, as in
+/// `config.get::<Scroll>("scroll")`, and decide for itself what to do when the key was missing
+/// or the wrong type.  A typo in a key name compiled just fine.  The `Settings` struct collects
+/// the values into sections, each a struct deserialized with [`serde`], so the keys live in one
+/// place and the compiler checks the rest.
+///
+/// The `api`, `catalog`, `choropleth`, `geocoding`, `imagery`, `logging`, `lookup`, `map`,
+/// `network`, `otlp`, `sort`, `summary`, `tasks`, `telemetry` and `theme` sections are tables of
+/// the same name in the config.  The other sections are a Rust view of the config, and do not
+/// add tables to the file.  Each reads its own keys from the top of the merged config and
+/// ignores the others, so the file keeps the layout described in [`crate::Diagnostic`].  We
+/// deserialize the tables one at a time in [`Settings::load`], so a bad value costs the user
+/// one table rather than all of them.
+///
+/// * The `agents` field holds the [`Schedule`] of the background [`crate::Agents`].
+/// * The `api` field holds the [`Api`] credentials for data services.
+/// * The `catalog` field holds the [`Listings`] added to the data catalog.
+/// * The `choropleth` field holds the [`Choropleth`] settings for shading data files.
+/// * The `data` field holds the [`Folders`] watched for new data files.
+/// * The `effects` field holds the [`Effects`] run over the map.
+/// * The `geocoding` field holds the [`Geocoding`] settings for finding addresses.
+/// * The `graphics` field holds the [`Graphics`] settings for drawing windows.
+/// * The `imagery` field holds the [`Imagery`] settings for searching for imagery.
+/// * The `keybindings` field holds the [`Keybindings`] for keys, global hotkeys and timing.
+/// * The `logging` field holds the [`Logging`] filters for individual modules.
+/// * The `lookup` field holds the [`Lookup`] settings for looking up parcels.
+/// * The `map` field holds the [`Basemap`] settings for the base map.
+/// * The `network` field holds the [`Timeouts`] for network calls.
+/// * The `otlp` field holds the [`Otlp`] settings for exporting traces and metrics.
+/// * The `startup` field holds the [`Startup`] settings for launching the app.
+/// * The `tasks` field holds the [`Concurrency`] limits for background jobs.
+/// * The `telemetry` field holds the [`Telemetry`] settings for reporting errors home.
+/// * The `theme` field holds the [`Theme`] settings for the look of the app.
+/// * The `recording` field holds the [`Recording`] settings for recording the map.
+/// * The `sort` field holds the [`Sort`] order of the rows of exported tables.
+/// * The `summary` field holds the [`Summary`] settings for describing the view of the map.
+/// * The `symbols` field holds the [`Symbols`] for the icons of point layers.
+/// * The `ui` field holds the [`Ui`] settings for scrolling and action groups.
+#[derive(Debug, Clone, Default, PartialEq, derive_getters::Getters)]
+pub struct Settings {
+    agents: Schedule,
+    api: Api,
+    catalog: Listings,
+    choropleth: Choropleth,
+    data: Folders,
+    effects: Effects,
+    geocoding: Geocoding,
+    graphics: Graphics,
+    imagery: Imagery,
+    keybindings: Keybindings,
+    logging: Logging,
+    lookup: Lookup,
+    map: Basemap,
+    network: Timeouts,
+    otlp: Otlp,
+    recording: Recording,
+    sort: Sort,
+    startup: Startup,
+    summary: Summary,
+    symbols: Symbols,
+    tasks: Concurrency,
+    telemetry: Telemetry,
+    theme: Theme,
+    ui: Ui,
+}
+
+impl Settings {
+    /// The `load` method reads each section of the `Settings` from `config`.  A section that
+    /// fails to deserialize takes its defaults, with a warning.  The [`crate::Diagnostic`]
+    /// report has already told the user which value was at fault.
+    #[tracing::instrument(skip_all)]
+    pub fn load(config: &config::Config) -> Self {
+        Self {
+            agents: Self::table(config, "agents"),
+            api: Self::table(config, "api"),
+            catalog: Self::table(config, "catalog"),
+            choropleth: Self::table(config, "choropleth"),
+            data: Self::table(config, "data"),
+            effects: Self::table(config, "effects"),
+            geocoding: Self::table(config, "geocoding"),
+            graphics: Self::table(config, "graphics"),
+            imagery: Self::table(config, "imagery"),
+            keybindings: Self::section(config, "keybindings"),
+            logging: Self::table(config, "logging"),
+            lookup: Self::table(config, "lookup"),
+            map: Self::table(config, "map"),
+            network: Self::table(config, "network"),
+            otlp: Self::table(config, "otlp"),
+            recording: Self::table(config, "recording"),
+            sort: Self::table(config, "sort"),
+            startup: Self::section(config, "startup"),
+            summary: Self::table(config, "summary"),
+            symbols: Self::table(config, "symbols"),
+            tasks: Self::table(config, "tasks"),
+            telemetry: Self::table(config, "telemetry"),
+            theme: Self::table(config, "theme"),
+            ui: Ui::new(Self::table(config, "scroll"), Self::table(config, "groups")),
+        }
+    }
+
+    /// The `with_secrets` method puts the credentials from `secrets` in place of those from the
+    /// config files, keeping any the secrets do not have.  The environment and the command line
+    /// still win, so a key set in the `pinned` config of [`Layers::pinned`] stays.
+    pub fn with_secrets(mut self, secrets: &Secrets, pinned: &config::Config) -> Self {
+        if pinned.get_string("api.key").is_ok() {
+            return self;
+        }
+        if let Some(key) = secrets.api().key() {
+            self.api = Api::new(Some(key.clone()));
+        }
+        self
+    }
+
+    /// The `section` method deserializes a single section called `name` from `config`, or
+    /// returns the default for the section if it cannot.
+    fn section<T: serde::de::DeserializeOwned + Default>(config: &config::Config, name: &str) -> T {
+        match config.clone().try_deserialize::<T>() {
+            Ok(section) => section,
+            Err(e) => {
+                tracing::warn!("Using default {name} settings: {e}");
+                T::default()
+            }
+        }
+    }
+
+    /// The `table` method deserializes the section from the table called `name` in `config`, or
+    /// returns the default for the section if the table is missing or malformed.
+    fn table<T: serde::de::DeserializeOwned + Default>(config: &config::Config, name: &str) -> T {
+        match config.get::<T>(name) {
+            Ok(section) => section,
+            Err(config::ConfigError::NotFound(_)) => T::default(),
+            Err(e) => {
+                tracing::warn!("Using default {name} settings: {e}");
+                T::default()
+            }
+        }
+    }
+}
+
+/// The `Api` struct holds credentials for data services, from the `[api]` table.  The same
+/// table in the [`crate::Secrets`] file takes precedence over the config files, and the
+/// environment and command line over both, see [`Settings::with_secrets`].
+///
+/// * The `key` field holds the key sent to data services.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    derive_getters::Getters,
+    derive_new::new,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Api {
+    key: Option<String>,
+}
+
+/// The `Basemap` struct holds settings for the base map, from the `[map]` table, as used by
+/// [`crate::Map::new`] and [`crate::Map::tiles`].
+///
+/// ```toml
+/// [map]
+/// center = [42.4435, -123.3260]
+/// zoom = 13
+/// tile_url = "https://tile.openstreetmap.org/{z}/{x}/{y}.png"
+/// attribution = "© OpenStreetMap contributors"
+/// cache_size = 512
+/// ```
+///
+/// * The `center` field holds the latitude and longitude the map opens on, in that order.
+/// * The `zoom` field holds the zoom level the map opens at, from 0 for the whole world to 18.
+/// * The `tile_url` field holds the address of the tile server, where `{z}`, `{x}` and `{y}`
+///   stand for the zoom level and tile coordinates.
+/// * The `attribution` field holds the credit the tile server asks for.
+/// * The `cache_size` field holds the most disk space downloaded tiles may take, in megabytes.
+///   Zero turns the limit off.
+///
+/// Missing fields take the values of [`CENTER`], [`ZOOM`], [`TILE_URL`], [`ATTRIBUTION`] and
+/// [`CACHE_SIZE`], which show Grants Pass on OpenStreetMap.
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Basemap {
+    center: [f64; 2],
+    zoom: u32,
+    tile_url: String,
+    attribution: String,
+    cache_size: u64,
+}
+
+impl Basemap {
+    /// The `tile` method fills the zoom level `z` and tile coordinates `x` and `y` into the
+    /// `tile_url` template, producing the address of a single tile.
+    pub fn tile(&self, z: u32, x: i32, y: i32) -> String {
+        self.tile_url
+            .replace("{z}", &z.to_string())
+            .replace("{x}", &x.to_string())
+            .replace("{y}", &y.to_string())
+    }
+}
+
+impl Default for Basemap {
+    fn default() -> Self {
+        Self {
+            center: CENTER,
+            zoom: ZOOM,
+            tile_url: TILE_URL.to_string(),
+            attribution: ATTRIBUTION.to_string(),
+            cache_size: CACHE_SIZE,
+        }
+    }
+}
+
+/// The `Keybindings` struct holds the key bindings read by [`crate::Cmd::load`] and
+/// [`crate::Hotkeys::load`].
+///
+/// Key bindings sit at the top of the config, keyed by the snake case name of each [`Act`], so
+/// we cannot derive the deserializer.  The implementation below keeps values whose key names an
+/// act, and skips everything else.  A value that is not text keeps its printed form, which
+/// [`crate::Cmd::load`] then reports as a [`crate::Clash::Invalid`] binding, and the same goes
+/// for the values of the `[global]` table.  The `[global]` and `[timing]` tables read on their
+/// own, so a mistake in one costs the user that table, with a warning, and leaves the bindings
+/// alone.
+///
+/// * The `acts` field holds the binding for each act, as written in the config.
+/// * The `global` field holds the `[global]` table of system-wide hotkeys, keyed by act name.
+/// * The `timing` field holds the [`Timing`] from the `[timing]` table.
+#[derive(Debug, Clone, Default, PartialEq, derive_getters::Getters)]
+pub struct Keybindings {
+    acts: HashMap<Act, String>,
+    global: HashMap<String, String>,
+    timing: Timing,
+}
+
+impl<'de> serde::Deserialize<'de> for Keybindings {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(KeybindingsVisitor)
+    }
+}
+
+impl Keybindings {
+    /// The `text` method returns a binding from `value` as written, or the printed form of a
+    /// value that is not text.
+    fn text(value: config::Value) -> String {
+        value
+            .clone()
+            .into_string()
+            .unwrap_or_else(|_| value.to_string())
+    }
+
+    /// The `global` method reads the `[global]` table from `value`, or no global hotkeys if
+    /// `value` is not a table.
+    fn global(value: config::Value) -> HashMap<String, String> {
+        match value.into_table() {
+            Ok(table) => table
+                .into_iter()
+                .map(|(name, value)| (name, Self::text(value)))
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Using no global hotkeys: {e}");
+                HashMap::new()
+            }
+        }
+    }
+
+    /// The `timing` method reads the `[timing]` table from `value`, or the default [`Timing`] if
+    /// it cannot.
+    fn timing(value: config::Value) -> Timing {
+        value.try_deserialize().unwrap_or_else(|e| {
+            tracing::warn!("Using default timing settings: {e}");
+            Timing::default()
+        })
+    }
+}
+
+/// The `KeybindingsVisitor` struct walks the top of the config for the [`serde::Deserialize`]
+/// implementation of [`Keybindings`].
+struct KeybindingsVisitor;
+
+impl<'de> serde::de::Visitor<'de> for KeybindingsVisitor {
+    type Value = Keybindings;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a table of key bindings")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Keybindings, A::Error> {
+        let mut keybindings = Keybindings::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "global" => keybindings.global = Keybindings::global(map.next_value()?),
+                "timing" => keybindings.timing = Keybindings::timing(map.next_value()?),
+                key => match Act::iter().find(|act| act.snake() == key) {
+                    Some(act) => {
+                        let value = map.next_value::<config::Value>()?;
+                        keybindings.acts.insert(act, Keybindings::text(value));
+                    }
+                    None => {
+                        map.next_value::<serde::de::IgnoredAny>()?;
+                    }
+                },
+            }
+        }
+        Ok(keybindings)
+    }
+}
+
+/// The `Concurrency` struct holds the `[tasks]` table of the config, which limits how many jobs
+/// of each [`crate::Class`] run at once, as described in [`crate::Tasks`].
+///
+/// ```toml
+/// [tasks]
+/// interactive = 8
+/// bulk = 2
+/// ```
+///
+/// * The `interactive` field holds the most interactive jobs to run at once.
+/// * The `bulk` field holds the most bulk jobs to run at once.
+///
+/// Missing fields take the values of [`INTERACTIVE_JOBS`] and [`BULK_JOBS`].  A limit of zero
+/// counts as one, since otherwise the jobs would never run.
+#[derive(
+    Debug, Clone, Copy, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Concurrency {
+    interactive: usize,
+    bulk: usize,
+}
+
+impl Default for Concurrency {
+    fn default() -> Self {
+        Self {
+            interactive: INTERACTIVE_JOBS,
+            bulk: BULK_JOBS,
+        }
+    }
+}
+
+/// The `BULK_JOBS` constant holds the default limit on bulk jobs running at once.  Bulk work
+/// like prefetching is polite to servers when it keeps to a couple of connections.
+pub const BULK_JOBS: usize = 2;
+
+/// The `INTERACTIVE_JOBS` constant holds the default limit on interactive jobs running at once,
+/// enough to fetch the tiles of a screen in parallel.
+pub const INTERACTIVE_JOBS: usize = 8;
+
+/// The `Timeouts` struct holds the `[network]` table of the config, which limits how long a
+/// network call may take, in seconds, as described in [`crate::Network`].
+///
+/// ```toml
+/// [network]
+/// connect = 10
+/// read = 30
+/// request = 60
+/// download = 3600
+/// ```
+///
+/// * The `connect` field holds how long to wait for a server to accept the connection.
+/// * The `read` field holds how long to wait for the next piece of a response.
+/// * The `request` field holds how long a request may take from start to finish.
+/// * The `download` field holds how long one attempt at a [`crate::Download`] may take, since
+///   a large file takes longer than an ordinary request.
+///
+/// Missing fields take the values of [`CONNECT_TIMEOUT`], [`READ_TIMEOUT`], [`REQUEST_DEADLINE`]
+/// and [`DOWNLOAD_DEADLINE`].  A limit of zero means no limit.
+#[derive(
+    Debug, Clone, Copy, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Timeouts {
+    connect: u64,
+    read: u64,
+    request: u64,
+    download: u64,
+}
+
+impl Timeouts {
+    /// The `limit` method turns `seconds` from the config into a [`time::Duration`], or [`None`]
+    /// for zero, meaning no limit.
+    pub fn limit(seconds: u64) -> Option<time::Duration> {
+        (seconds > 0).then(|| time::Duration::from_secs(seconds))
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: CONNECT_TIMEOUT.as_secs(),
+            read: READ_TIMEOUT.as_secs(),
+            request: REQUEST_DEADLINE.as_secs(),
+            download: DOWNLOAD_DEADLINE.as_secs(),
+        }
+    }
+}
+
+/// The `CONNECT_TIMEOUT` constant holds the default time to wait for a server to accept a
+/// connection.
+pub const CONNECT_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+/// The `DOWNLOAD_DEADLINE` constant holds the default time one attempt at a download may take.
+pub const DOWNLOAD_DEADLINE: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// The `READ_TIMEOUT` constant holds the default time to wait for the next piece of a response.
+pub const READ_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// The `REQUEST_DEADLINE` constant holds the default time a request may take from start to
+/// finish.
+pub const REQUEST_DEADLINE: time::Duration = time::Duration::from_secs(60);
+
+/// The `Schedule` struct holds the `[agents]` table of the config, which sets how often each
+/// [`crate::Agent`] goes to work, in seconds, as described in [`crate::Agents`].
+///
+/// ```toml
+/// [agents]
+/// janitor = 600
+/// refresher = 30
+/// prefetcher = 0
+/// watcher = 5
+/// ```
+///
+/// * The `janitor` field holds the time between trims of the tile cache.
+/// * The `refresher` field holds the time between checks for changes to open data files.
+/// * The `prefetcher` field holds the time between fetches of the tiles around the opening view.
+/// * The `watcher` field holds the time between scans of the data folders in [`Folders`].
+///
+/// Missing fields take the values of [`JANITOR_INTERVAL`], [`REFRESHER_INTERVAL`] and
+/// [`WATCHER_INTERVAL`].  An interval of zero turns the agent off, and the prefetcher starts out
+/// off: bulk downloads from a tile server someone else pays for need their permission, and the
+/// usage policy of the OpenStreetMap tile servers forbids them.  A deployment with its own tile
+/// server, or one that allows it, turns the prefetcher on with an interval like
+/// [`PREFETCHER_INTERVAL`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Schedule {
+    janitor: u64,
+    refresher: u64,
+    prefetcher: u64,
+    watcher: u64,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            janitor: JANITOR_INTERVAL.as_secs(),
+            refresher: REFRESHER_INTERVAL.as_secs(),
+            prefetcher: 0,
+            watcher: WATCHER_INTERVAL.as_secs(),
+        }
+    }
+}
+
+/// The `JANITOR_INTERVAL` constant holds the default time between trims of the tile cache.
+pub const JANITOR_INTERVAL: time::Duration = time::Duration::from_secs(10 * 60);
+
+/// The `PREFETCHER_INTERVAL` constant holds the suggested time between fetches of the tiles
+/// around the opening view, for a config that turns the prefetcher on.  Tiles already in the
+/// cache are skipped, so later rounds only replace what the janitor trimmed.
+pub const PREFETCHER_INTERVAL: time::Duration = time::Duration::from_secs(24 * 60 * 60);
+
+/// The `REFRESHER_INTERVAL` constant holds the default time between checks for changes to open
+/// data files.
+pub const REFRESHER_INTERVAL: time::Duration = time::Duration::from_secs(30);
+
+/// The `WATCHER_INTERVAL` constant holds the default time between scans of the data folders.
+/// A scan only reads the listing of each folder, so it can run often.
+pub const WATCHER_INTERVAL: time::Duration = time::Duration::from_secs(5);
+
+/// The `Folders` struct holds the `[data]` table of the config, which lists the folders the
+/// [`crate::Agent::Watcher`] keeps an eye on, as described in [`crate::Agents`].
+///
+/// ```toml
+/// [data]
+/// dirs = ["/home/planner/exports", "/srv/gis/parcels"]
+/// ```
+///
+/// * The `dirs` field holds the folders to watch for new and changed data files.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Folders {
+    dirs: Vec<path::PathBuf>,
+}
+
+/// The `Effects` struct holds the `[effects]` table of the config, which lists the custom
+/// shaders run over the map, as described in [`crate::Effect`].
+///
+/// ```toml
+/// [effects]
+/// files = ["shaders/hillshade.wgsl", "shaders/flow.wgsl"]
+/// animate = true
+/// ```
+///
+/// * The `files` field holds the paths of the shader files, in the order they run.  A relative
+///   path starts from the directory of the config file.
+/// * The `animate` field is `true` to keep drawing the map while the shaders move with `time`.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Effects {
+    files: Vec<path::PathBuf>,
+    animate: bool,
+}
+
+impl Effects {
+    /// The `sources` method reads each shader file, with relative paths starting from the
+    /// directory of `config`, the config file, and returns the name and text of each, named for
+    /// the file.  A file that cannot be read is left out, with a warning.
+    pub fn sources(&self, config: &path::Path) -> Vec<(String, String)> {
+        let dir = config.parent().unwrap_or(path::Path::new(""));
+        self.files
+            .iter()
+            .filter_map(|file| {
+                let path = dir.join(file);
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => {
+                        let name = path.file_stem().unwrap_or(path.as_os_str());
+                        Some((name.to_string_lossy().into_owned(), text))
+                    }
+                    Err(e) => {
+                        tracing::warn!("Could not read the shader {}: {e}", path.display());
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// The `Graphics` struct holds the `[graphics]` table of the config, which sets how windows
+/// draw, as described in [`crate::Gpu`].
+///
+/// ```toml
+/// [graphics]
+/// present_mode = "Mailbox"
+/// samples = 4
+/// fxaa = false
+/// power = "LowPower"
+/// adapter = "Intel"
+/// software = false
+/// pacing = "Continuous"
+/// fps = 30
+/// gamut = "Wide"
+/// ```
+///
+/// * The `present_mode` field holds the [`PresentMode`] for every window.
+/// * The `samples` field holds the multisample count wanted for the map, 1 for none.  A card
+///   that cannot multisample at the count gets the [`crate::Fxaa`] pass instead.
+/// * The `fxaa` field is `true` to smooth every frame with the [`crate::Fxaa`] pass.
+/// * The `power` field holds the [`Power`] preference for choosing a graphics card.
+/// * The `adapter` field holds part of the name of the graphics card to use, if any.
+/// * The `software` field is `true` to draw in software, on the processor, from the start.
+/// * The `pacing` field holds the [`Pacing`] asked for map windows, as in [`crate::Lens`].
+/// * The `fps` field holds the frame rate of windows drawing continuously.
+/// * The `gamut` field holds the [`Gamut`] of colors to draw in.
+///
+/// A missing `samples` takes the value of [`SAMPLES`], and a missing `fps` the value of [`FPS`].
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Graphics {
+    present_mode: PresentMode,
+    samples: u32,
+    fxaa: bool,
+    power: Power,
+    adapter: Option<String>,
+    software: bool,
+    pacing: Pacing,
+    fps: u32,
+    gamut: Gamut,
+}
+
+impl Default for Graphics {
+    fn default() -> Self {
+        Self {
+            present_mode: PresentMode::default(),
+            samples: SAMPLES,
+            fxaa: false,
+            power: Power::default(),
+            adapter: None,
+            software: false,
+            pacing: Pacing::default(),
+            fps: FPS,
+            gamut: Gamut::default(),
+        }
+    }
+}
+
+impl Graphics {
+    /// The `interval` method returns the time between frames at the `fps` of the table.  A rate
+    /// of zero counts as one frame a second.
+    pub fn interval(&self) -> time::Duration {
+        time::Duration::from_secs(1) / self.fps.max(1)
+    }
+}
+
+/// The `FPS` constant holds the default frame rate of windows drawing continuously, to match
+/// the common monitor.
+pub const FPS: u32 = 60;
+
+/// The `SAMPLES` constant holds the default multisample count for the map.  Every card that
+/// runs [`wgpu`] can multisample at 4 for the common surface formats.
+pub const SAMPLES: u32 = 4;
+
+/// The `Recording` struct holds the `[recording]` table of the config, which shapes the clips
+/// made by the [`crate::Recorder`].
+///
+/// ```toml
+/// [recording]
+/// format = "Mp4"
+/// fps = 30
+/// seconds = 20
+/// ```
+///
+/// * The `format` field holds the [`Clip`] format of the recording.
+/// * The `fps` field holds the most frames to capture per second.
+/// * The `seconds` field holds the longest recording, after which it stops by itself.
+///
+/// Missing fields take the values of [`Clip::default`], [`RECORDING_FPS`] and
+/// [`RECORDING_SECONDS`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Recording {
+    format: Clip,
+    fps: u32,
+    seconds: u64,
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Self {
+            format: Clip::default(),
+            fps: RECORDING_FPS,
+            seconds: RECORDING_SECONDS,
+        }
+    }
+}
+
+/// The `RECORDING_FPS` constant holds the default most frames per second for a recording.
+/// Fifteen keeps a pan smooth enough for a slide, and a GIF small enough to email.
+pub const RECORDING_FPS: u32 = 15;
+
+/// The `RECORDING_SECONDS` constant holds the default longest recording.  The frames stream to
+/// disk, so the limit keeps a forgotten recording from filling it, and a minute covers most
+/// demonstrations.
+pub const RECORDING_SECONDS: u64 = 60;
+
+/// The `Symbols` struct holds the `[symbols]` table of the config, which sets the icons of point
+/// layers, as described in [`crate::Atlas`].
+///
+/// ```toml
+/// [symbols]
+/// dir = "icons"
+/// size = 24
+/// column = "kind"
+/// fallback = "pin"
+///
+/// [symbols.icons]
+/// school = "school"
+/// hydrant = "fire-hydrant"
+/// "bus stop" = "bus"
+/// ```
+///
+/// * The `dir` field holds the folder of SVG and PNG icons, if any.  A relative path starts from
+///   the directory of the config file.
+/// * The `size` field holds the size of an icon in pixels, at a scale factor of one.
+/// * The `column` field holds the name of the column of a point layer that picks the icon.
+/// * The `icons` field holds the name of the icon for each value of the column.
+/// * The `fallback` field holds the name of the icon for values not in `icons`, if any.
+///
+/// A missing `size` takes the value of [`ICON_SIZE`].
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Symbols {
+    dir: Option<path::PathBuf>,
+    size: u32,
+    column: Option<String>,
+    icons: BTreeMap<String, String>,
+    fallback: Option<String>,
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            size: ICON_SIZE,
+            column: None,
+            icons: BTreeMap::new(),
+            fallback: None,
+        }
+    }
+}
+
+impl Symbols {
+    /// The `folder` method returns the folder of icons, with a relative `dir` starting from the
+    /// directory of `config`, the config file, or [`None`] without a `dir`.
+    pub fn folder(&self, config: &path::Path) -> Option<path::PathBuf> {
+        let dir = self.dir.as_ref()?;
+        Some(config.parent().unwrap_or(path::Path::new("")).join(dir))
+    }
+
+    /// The `icon` method returns the name of the icon for a point whose column holds `value`,
+    /// or the `fallback`, if any.
+    pub fn icon(&self, value: &str) -> Option<&str> {
+        self.icons
+            .get(value)
+            .or(self.fallback.as_ref())
+            .map(String::as_str)
+    }
+
+    /// The `classify` method returns the name of the icon for each row of `dataset`, from the
+    /// value of its `column`.  Without a `column`, or with a dataset that lacks it, every row
+    /// gets the `fallback`.
+    pub fn classify(&self, dataset: &crate::Dataset) -> Vec<Option<String>> {
+        let values = self
+            .column
+            .as_ref()
+            .and_then(|column| dataset.column(column))
+            .map(|column| dataset.texts(column));
+        (0..dataset.len())
+            .map(|row| {
+                let value = values.as_ref().map(|values| match values.is_valid(row) {
+                    true => values.value(row),
+                    false => "",
+                });
+                let icon = match value {
+                    Some(value) => self.icon(value),
+                    None => self.fallback.as_deref(),
+                };
+                icon.map(str::to_owned)
+            })
+            .collect()
+    }
+}
+
+/// The `ICON_SIZE` constant holds the default size of an icon in pixels, at a scale factor of
+/// one, about the height of a line of text.
+pub const ICON_SIZE: u32 = 24;
+
+/// The `Choropleth` struct holds the `[choropleth]` table of the config, which shades the rows
+/// of data files by the value of a column, as described in [`crate::Scheme`].
+///
+/// ```toml
+/// [choropleth]
+/// column = "value"
+/// scheme = "Blues"
+/// classes = 7
+/// ```
+///
+/// * The `column` field holds the name of the column to shade by, if any.
+/// * The `scheme` field holds the name of the [`Scheme`], from [`crate::SCHEMES`].
+/// * The `classes` field holds the number of classes to sort the rows into.
+///
+/// A missing `scheme` takes the value of [`DEFAULT_SCHEME`], and a missing `classes` the value of
+/// [`CLASSES`].
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Choropleth {
+    column: Option<String>,
+    scheme: String,
+    classes: usize,
+}
+
+impl Default for Choropleth {
+    fn default() -> Self {
+        Self {
+            column: None,
+            scheme: DEFAULT_SCHEME.to_string(),
+            classes: CLASSES,
+        }
+    }
+}
+
+impl Choropleth {
+    /// The `ramp` method returns the [`Scheme`] named by `scheme`, or the scheme named by
+    /// [`DEFAULT_SCHEME`] if there is no such scheme.
+    pub fn ramp(&self) -> &'static Scheme {
+        Scheme::find(&self.scheme)
+            .or_else(|| Scheme::find(DEFAULT_SCHEME))
+            .unwrap_or(&crate::SCHEMES[0])
+    }
+
+    /// The `classify` method returns the color of the class of each row of `dataset`, sorting
+    /// the rows into `classes` of about the same size by the value of `column`.  A row without a
+    /// number in the column, or a dataset without the column, gets no color.
+    pub fn classify(&self, dataset: &crate::Dataset) -> Vec<Option<[u8; 3]>> {
+        let values = self
+            .column
+            .as_ref()
+            .and_then(|column| dataset.column(column))
+            .map(|column| dataset.numbers(column).iter().collect::<Vec<Option<f64>>>())
+            .unwrap_or_else(|| vec![None; dataset.len()]);
+        let mut sorted = values.iter().flatten().copied().collect::<Vec<f64>>();
+        sorted.sort_by(f64::total_cmp);
+        let colors = self.ramp().colors(self.classes);
+        if sorted.is_empty() || colors.is_empty() {
+            return vec![None; values.len()];
+        }
+        let breaks = (1..colors.len())
+            .map(|class| sorted[class * sorted.len() / colors.len()])
+            .collect::<Vec<f64>>();
+        values
+            .into_iter()
+            .map(|value| {
+                value.map(|value| colors[breaks.iter().filter(|bound| value >= **bound).count()])
+            })
+            .collect()
+    }
+}
+
+/// The `Summary` struct holds the `[summary]` table of the config, which shapes the line of text
+/// describing the view of the map, as described in [`crate::Synopsis`].
+///
+/// ```toml
+/// [summary]
+/// place = "Josephine County"
+/// column = "value"
+/// ```
+///
+/// * The `place` field holds the name of the place the map covers, if any, in place of the
+///   latitude and longitude of the view.
+/// * The `column` field holds the name of the column to report the median of, if any.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Summary {
+    place: Option<String>,
+    column: Option<String>,
+}
+
+/// The `Sort` struct holds the `[sort]` table of the config, which orders the rows of the tables
+/// the app exports, with [`crate::Dataset::sort`].
+///
+/// ```toml
+/// [sort]
+/// column = "value"
+/// descending = true
+/// ```
+///
+/// * The `column` field holds the name of the column to order the rows by, if any.  A table
+///   without the column keeps the order of its file.
+/// * The `descending` field holds `true` to put the largest value first, and `false` for the
+///   smallest.  Missing values go last either way.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Sort {
+    column: Option<String>,
+    descending: bool,
+}
+
+/// The `Geocoding` struct holds the `[geocoding]` table of the config, which sets up the
+/// [`crate::Geocoder`].
+///
+/// ```toml
+/// [geocoding]
+/// service = "Arcgis"
+/// url = "https://gis.example.gov/locator/GeocodeServer/findAddressCandidates"
+/// columns = ["street", "city", "state", "zip"]
+/// delay = 0
+/// score = 85
+/// ```
+///
+/// * The `service` field holds the [`Service`] to ask, the Census Bureau by default.
+/// * The `url` field holds the address of the service, if not the public one of
+///   [`Service::url`].
+/// * The `columns` field holds the names of the columns that make up an address, in order, or
+///   none to read a column called `address`.
+/// * The `delay` field holds the wait between requests in milliseconds, if not the one of
+///   [`Service::delay`].
+/// * The `score` field holds the lowest score of a match that goes on the map, from 0 to 100,
+///   [`MATCH_SCORE`] by default.
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Geocoding {
+    service: Service,
+    url: Option<String>,
+    columns: Vec<String>,
+    delay: Option<u64>,
+    score: f64,
+}
+
+impl Default for Geocoding {
+    fn default() -> Self {
+        Self {
+            service: Service::default(),
+            url: None,
+            columns: Vec::new(),
+            delay: None,
+            score: MATCH_SCORE,
+        }
+    }
+}
+
+/// The `Listings` struct holds the `[catalog]` table of the config, which adds layers to the
+/// [`crate::Catalog`], each a table under `layers` keyed by its name, as described in
+/// [`Listing`].
+///
+/// ```toml
+/// [catalog]
+/// builtin = true
+///
+/// [catalog.layers."Storm drains"]
+/// group = "City of Grants Pass"
+/// url = "https://gis.example.gov/arcgis/rest/services/Storm/MapServer/2"
+/// ```
+///
+/// * The `builtin` field is `true` to list the built-in layer, as by default.
+/// * The `layers` field maps the names of layers to their [`Listing`], which replaces a
+///   built-in layer of the same name.  The layers of the city and the county belong here.
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Listings {
+    builtin: bool,
+    layers: BTreeMap<String, Listing>,
+}
+
+impl Default for Listings {
+    fn default() -> Self {
+        Self {
+            builtin: true,
+            layers: BTreeMap::new(),
+        }
+    }
+}
+
+/// The `Lookup` struct holds the `[lookup]` table of the config, which sets up the lookup of
+/// parcels with [`crate::Parcels`].
+///
+/// ```toml
+/// [lookup]
+/// parcels = "Parcels"
+/// addresses = "Addresses"
+/// taxlot = "MAPTAXLOT"
+/// address = "FULLADDR"
+/// fields = ["ZONE", "ACRES", "OWNER"]
+///
+/// [lookup.links]
+/// Assessor = "https://assessor.example.gov/property?maptaxlot={MAPTAXLOT}"
+/// ```
+///
+/// * The `parcels` field holds the name of the parcel layer in the [`crate::Catalog`].
+/// * The `addresses` field holds the name of the address layer in the catalog.
+/// * The `taxlot` field holds the name of the field holding the map and tax lot number of a
+///   parcel.
+/// * The `address` field holds the name of the field holding the full address of a point.
+/// * The `fields` field holds the names of the fields of a parcel to show, in order, or none to
+///   show every field.
+/// * The `links` field maps the names of links to their addresses, with the name of a field in
+///   braces standing for its value.
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Lookup {
+    parcels: String,
+    addresses: String,
+    taxlot: String,
+    address: String,
+    fields: Vec<String>,
+    links: BTreeMap<String, String>,
+}
+
+impl Default for Lookup {
+    fn default() -> Self {
+        Self {
+            parcels: "Parcels".to_string(),
+            addresses: "Addresses".to_string(),
+            taxlot: "MAPTAXLOT".to_string(),
+            address: "FULLADDR".to_string(),
+            fields: Vec::new(),
+            links: BTreeMap::new(),
+        }
+    }
+}
+
+/// The `Imagery` struct holds the `[imagery]` table of the config, which sets up the search for
+/// imagery with [`crate::Stac`].
+///
+/// ```toml
+/// [imagery]
+/// catalog = "https://planetarycomputer.microsoft.com/api/stac/v1"
+/// collection = "naip"
+/// days = 1095
+/// cloud_cover = 10
+/// limit = 20
+/// assets = ["image"]
+/// ```
+///
+/// * The `catalog` field holds the address of the STAC catalog, [`STAC_CATALOG`] by default.
+/// * The `collection` field holds the collection to search, Sentinel-2 by default.
+/// * The `days` field holds how far back to search, in days.
+/// * The `cloud_cover` field holds the most cloud of a scene to keep, in percent,
+///   [`CLOUD_COVER`] by default.
+/// * The `limit` field holds the most scenes to keep.
+/// * The `assets` field holds the names of the assets to link for each scene, in order.
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Imagery {
+    catalog: String,
+    collection: String,
+    days: u64,
+    cloud_cover: f64,
+    limit: usize,
+    assets: Vec<String>,
+}
+
+impl Default for Imagery {
+    fn default() -> Self {
+        Self {
+            catalog: STAC_CATALOG.to_string(),
+            collection: "sentinel-2-l2a".to_string(),
+            days: 90,
+            cloud_cover: CLOUD_COVER,
+            limit: 50,
+            assets: vec!["visual".to_string(), "image".to_string()],
+        }
+    }
+}
+
+/// The `Theme` struct holds the `[theme]` table of the config, which sets the look of the app,
+/// as described in [`crate::Contrast`].
+///
+/// ```toml
+/// [theme]
+/// contrast = "High"
+/// large = true
+/// ```
+///
+/// * The `contrast` field holds the [`Contrast`] of the app, following the system by default.
+/// * The `large` field is `true` to start the app in the large-UI mode, as described in
+///   [`crate::Palette`].
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Theme {
+    contrast: Contrast,
+    large: bool,
+}
+
+/// The `Timing` struct holds the `[timing]` table of the config, in milliseconds, as described
+/// in [`crate::Cmd`].
+///
+/// * The `double_press` field holds the longest gap between the presses of a double-press.
+/// * The `hold` field holds how long a key must stay down to count as held.
+/// * The `chord` field holds how long to wait for the next stroke of a sequence.
+///
+/// Missing fields take the values of [`DOUBLE_PRESS`], [`HOLD_DELAY`] and [`CHORD_TIMEOUT`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Timing {
+    double_press: u64,
+    hold: u64,
+    chord: u64,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            double_press: DOUBLE_PRESS.as_millis() as u64,
+            hold: HOLD_DELAY.as_millis() as u64,
+            chord: CHORD_TIMEOUT.as_millis() as u64,
+        }
+    }
+}
+
+/// The `Startup` struct holds the settings for launching the app, which the arguments of
+/// [`Cli`] override.
+///
+/// * The `open` field holds data files to open on startup.
+/// * The `workspace` field holds the path of the [`crate::Workspace`] to restore on startup.
+/// * The `log_level` field holds the log filter, as in `RUST_LOG`.
+/// * The `log_format` field holds the [`LogFormat`] of the log output.
+/// * The `headless` field is `true` to run without windows.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Startup {
+    open: Vec<path::PathBuf>,
+    workspace: Option<path::PathBuf>,
+    log_level: Option<String>,
+    log_format: LogFormat,
+    headless: bool,
+}
+
+/// The `Logging` struct holds the `[logging]` table of the config, which sets the log level of
+/// individual modules.
+///
+/// ```toml
+/// [logging.filters]
+/// galileo = "warn"
+/// "bea_egui::map" = "error"
+/// ```
+///
+/// The `log_level` key sets one filter for everything, and gets changed often while chasing a
+/// bug.  The filters here are for the modules a deployment never wants to hear from, like tile
+/// fetching on a kiosk with a flaky connection, so they stay put whatever the level.
+/// [`Logging::merge`] puts them in front of the level, or `RUST_LOG`, so that a filter for the
+/// same module in the level wins, and a developer can still turn a silenced module back up.
+///
+/// * The `filters` field maps module paths to levels, like `warn` or `off`.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Logging {
+    filters: BTreeMap<String, String>,
+}
+
+impl Logging {
+    /// The `merge` method returns the filters as directives for a
+    /// [`tracing_subscriber::EnvFilter`], followed by the directives in `base`, as described in
+    /// the [`Logging`] docs.
+    pub fn merge(&self, base: &str) -> String {
+        self.filters
+            .iter()
+            .map(|(module, level)| format!("{module}={level}"))
+            .chain((!base.is_empty()).then(|| base.to_string()))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+/// The `LogFormat` enum describes how [`crate::trace_init`] writes the log.
+///
+/// * The `Text` variant writes a line of text for each event, for people to read.  This is the
+///   default.
+/// * The `Json` variant writes a JSON object for each event, one per line, for log shippers like
+///   Filebeat to read into Elasticsearch without a custom parser.  The fields of the event, the
+///   spans it happened in, and the error codes from [`crate::Blame::code`] in the messages all
+///   arrive as fields a search can filter on.
+///
+/// The config takes either name whatever its case, as in `log_format = "json"`, the way
+/// `--log-format` spells it (see [`crate::choice`]).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+    clap::ValueEnum,
+)]
+pub enum LogFormat {
+    /// The `Text` variant writes plain text.
+    #[default]
+    Text,
+    /// The `Json` variant writes JSON lines.
+    Json,
+}
+
+/// Reads the name of the format whatever its case, as described in [`crate::choice`].
+impl<'de> serde::Deserialize<'de> for LogFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::choice(deserializer)
+    }
+}
+
+/// The `Telemetry` struct holds the `[telemetry]` table of the config, which turns on the
+/// [`crate::Reporter`].  Telemetry is off by default.
+///
+/// ```toml
+/// [telemetry]
+/// enabled = true
+/// endpoint = "https://telemetry.example.gov/bea_egui"
+/// ```
+///
+/// * The `enabled` field is `true` to send crash reports and error counts.
+/// * The `endpoint` field holds the address to send them to.  Without one, nothing is sent.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Telemetry {
+    enabled: bool,
+    endpoint: Option<String>,
+}
+
+/// The `Otlp` struct holds the `[otlp]` table of the config, which turns on the
+/// [`crate::Exporter`].  Exporting is off unless there is an endpoint.
+///
+/// ```toml
+/// [otlp]
+/// endpoint = "http://collector.example.gov:4318"
+/// service_name = "bea_egui-library-kiosk"
+/// ```
+///
+/// * The `endpoint` field holds the address of the OpenTelemetry collector, which takes OTLP
+///   over HTTP, usually on port 4318.  Without one, nothing is exported.
+/// * The `service_name` field holds the name the collector files our spans and metrics under,
+///   [`SERVICE_NAME`] by default.  Give each kind of deployment its own name to tell them apart.
+#[derive(
+    Debug, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Otlp {
+    endpoint: Option<String>,
+    service_name: String,
+}
+
+impl Default for Otlp {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            service_name: SERVICE_NAME.to_string(),
+        }
+    }
+}
+
+/// The `SERVICE_NAME` constant holds the default service name for the [`Otlp`] settings.
+pub const SERVICE_NAME: &str = "bea_egui";
+
+/// The `Ui` struct holds settings for how the app responds to the user.  Each table reads on its
+/// own in [`Settings::load`], so a mistake in the `[scroll]` table cannot switch the disabled
+/// groups back on.
+///
+/// * The `scroll` field holds the [`Scroll`] preferences from the `[scroll]` table.
+/// * The `groups` field holds the [`Groups`] from the `[groups]` table.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    derive_getters::Getters,
+    derive_new::new,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Ui {
+    scroll: Scroll,
+    groups: Groups,
+}
+
+/// The `Groups` struct holds the `[groups]` table of the config.  A name in `disabled` that is
+/// not a [`Group`] is skipped with a warning, so a typo in one name leaves the other groups
+/// switched off.
+///
+/// * The `disabled` field holds the [`Group`] types switched off for this deployment.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Groups {
+    #[serde(deserialize_with = "Groups::disabled")]
+    disabled: Vec<Group>,
+}
+
+impl Groups {
+    /// The `disabled` method reads the names in the `disabled` list, skipping any that name no
+    /// [`Group`].
+    fn disabled<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Group>, D::Error> {
+        let names = <Vec<String> as serde::Deserialize>::deserialize(deserializer)?;
+        let groups = names
+            .into_iter()
+            .filter_map(|name| {
+                let text = serde::de::value::StringDeserializer::<serde::de::value::Error>::new;
+                match <Group as serde::Deserialize>::deserialize(text(name.clone())) {
+                    Ok(group) => Some(group),
+                    Err(e) => {
+                        tracing::warn!("Skipping the group {name}: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Ok(groups)
+    }
+}
+
+/// The `TILE_URL` constant holds the default tile server of the base map, OpenStreetMap.
+pub const TILE_URL: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+
+/// The `ZOOM` constant holds the default zoom level of the base map, which fits the city.
+pub const ZOOM: u32 = 13;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<(String, String)>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn shortcuts_reach_their_tables() {
+        let environment = Layers::environment(vars(&[
+            ("BEA_EGUI_API_KEY", "short"),
+            ("BEA_EGUI_TILE_URL", "https://tiles/{z}/{x}/{y}.png"),
+            ("BEA_EGUI_SCROLL__MODE", "Pan"),
+            ("HOME", "/root"),
+        ]));
+        assert_eq!(
+            environment.get("BEA_EGUI_API__KEY").map(String::as_str),
+            Some("short")
+        );
+        assert!(environment.contains_key("BEA_EGUI_MAP__TILE_URL"));
+        assert!(!environment.contains_key("BEA_EGUI_API_KEY"));
+        assert!(environment.contains_key("BEA_EGUI_SCROLL__MODE"));
+        assert!(environment.contains_key("HOME"));
+    }
+
+    #[test]
+    fn long_names_beat_shortcuts() {
+        let environment = Layers::environment(vars(&[
+            ("BEA_EGUI_API__KEY", "long"),
+            ("BEA_EGUI_API_KEY", "short"),
+        ]));
+        assert_eq!(
+            environment.get("BEA_EGUI_API__KEY").map(String::as_str),
+            Some("long")
+        );
+    }
+
+    #[test]
+    fn pinned_keys_beat_secrets() {
+        let secrets = toml::from_str::<Secrets>("[api]\nkey = \"secret\"").expect("secrets");
+        let settings = Settings::default().with_secrets(&secrets, &config::Config::default());
+        assert_eq!(settings.api().key().as_deref(), Some("secret"));
+        let pinned = config::Config::builder()
+            .set_override("api.key", "pinned")
+            .and_then(|builder| builder.build())
+            .expect("pinned");
+        let settings = Settings::load(&pinned).with_secrets(&secrets, &pinned);
+        assert_eq!(settings.api().key().as_deref(), Some("pinned"));
+    }
+
+    fn config(text: &str) -> config::Config {
+        config::Config::builder()
+            .add_source(config::File::from_str(text, config::FileFormat::Toml))
+            .build()
+            .expect("config")
+    }
+
+    #[test]
+    fn bad_scroll_keeps_the_groups() {
+        let settings = Settings::load(&config(
+            "[scroll]\nmode = 7\n[groups]\ndisabled = [\"editing\", \"destructiv\", \"debug\"]",
+        ));
+        assert_eq!(
+            settings.ui().groups().disabled(),
+            &vec![Group::Editing, Group::Debug]
+        );
+    }
+
+    #[test]
+    fn bad_timing_keeps_the_bindings() {
+        let settings = Settings::load(&config(
+            "exit = \"Escape\"\n[timing]\nhold = \"long\"\n[global]\ntoggle_visible = 3",
+        ));
+        let keybindings = settings.keybindings();
+        assert_eq!(
+            keybindings.acts().get(&Act::Exit).map(String::as_str),
+            Some("Escape")
+        );
+        assert_eq!(keybindings.timing(), &Timing::default());
+        assert_eq!(
+            keybindings
+                .global()
+                .get("toggle_visible")
+                .map(String::as_str),
+            Some("3")
+        );
+    }
+
+    #[test]
+    fn later_layers_win_key_by_key() {
+        let environment = Layers::environment(vars(&[
+            ("BEA_EGUI_SCROLL__INVERT", "true"),
+            ("BEA_EGUI_TILE_URL", "https://env/{z}/{x}/{y}.png"),
+        ]));
+        let config = config::Config::builder()
+            .add_source(config::File::from_str(
+                "[scroll]\nmode = \"Pan\"\nsensitivity = 2.0\n[map]\nzoom = 12",
+                config::FileFormat::Toml,
+            ))
+            .add_source(config::File::from_str(
+                "[scroll]\nsensitivity = 3.0",
+                config::FileFormat::Toml,
+            ))
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true)
+                    .source(Some(environment)),
+            )
+            .build()
+            .expect("config");
+        let settings = Settings::load(&config);
+        let scroll = settings.ui().scroll();
+        assert_eq!(*scroll.mode(), crate::ScrollMode::Pan);
+        assert_eq!(*scroll.sensitivity(), 3.0);
+        assert!(*scroll.invert());
+        assert_eq!(*settings.map().zoom(), 12);
+        assert_eq!(settings.map().tile_url(), "https://env/{z}/{x}/{y}.png");
+    }
+
+    #[test]
+    fn choices_take_any_case() {
+        for name in ["json", "Json", "JSON", "j_son"] {
+            let settings = Settings::load(&config(&format!("log_format = \"{name}\"")));
+            assert_eq!(*settings.startup().log_format(), LogFormat::Json);
+        }
+        let settings = Settings::load(&config("log_format = \"xml\""));
+        assert_eq!(*settings.startup().log_format(), LogFormat::Text);
+    }
+}
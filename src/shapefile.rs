@@ -0,0 +1,986 @@
+use crate::{Arrive, Context, Dataset, Job, GEOMETRY, PROGRESS_STEP};
+use arrow::array::Array;
+use std::io::Write;
+use std::path;
+use web_time as time;
+
+/// The `shapefile` module provides the [`Shapefile`] struct, which reads and writes the set of
+/// files that make up an ESRI shapefile, and the [`ShapeKind`] enum, which names the kinds of
+/// shape it holds.
+///
+/// # Writing shapefiles with `Shapefile`
+///
+/// GeoJSON opens in any GIS tool written this century, but several of our partner agencies still
+/// take data only as shapefiles, and someone has been running each export through a desktop GIS
+/// to convert it.  [`crate::Act::ExportSelectionShapefile`] and
+/// [`crate::Act::ExportLayersShapefile`] write the same features as their GeoJSON counterparts
+/// (see [`Dataset::save_geojson`]), as a shapefile instead.
+///
+/// A shapefile is really five files with the same name:
+///
+/// * The `.shp` file holds the shapes.
+/// * The `.shx` file holds where each shape starts in the `.shp` file.
+/// * The `.dbf` file holds the attributes of each shape, as a dBASE table.
+/// * The `.prj` file names the coordinate system, here longitude and latitude on WGS 84, which is
+///   what GeoJSON holds.
+/// * The `.cpg` file names the encoding of the text in the `.dbf` file, here UTF-8.
+///
+/// The format is old and simple, so we write it by hand, as [`crate::Export`] writes a PDF.  Its
+/// age shows in three limits, which we meet as follows:
+///
+/// * A shapefile holds one kind of shape.  The first feature with a geometry picks the
+///   [`ShapeKind`], with a [`ShapeKind::Polygon`] taking a polygon or multipolygon, and so on.  A
+///   feature of another kind keeps its row of attributes, with a null shape, and we warn how many
+///   lost their shape.
+/// * A field name holds at most [`FIELD_NAME_LIMIT`] characters, of letters, digits and
+///   underscores.  We shorten longer column names and replace other characters with underscores,
+///   numbering any that then clash, and warn for each name we change, so the user can tell the
+///   partner which field is which.
+/// * A text field holds at most [`FIELD_WIDTH_LIMIT`] bytes, and we cut longer values short,
+///   with a warning for each column cut.
+///
+/// A column whose values all read as plain decimal numbers becomes a number field, so it sorts
+/// and sums as a number in the GIS, and any other column a text field.  The [`crate::GEOMETRY`]
+/// column goes to the `.shp` file rather than the table.  The rings of a polygon run clockwise
+/// around the outside and counterclockwise around the holes, the reverse of GeoJSON, so we turn
+/// any ring that runs the wrong way.
+///
+/// # Reading shapefiles with `Shapefile::read`
+///
+/// The county and the state still publish much of their data as shapefiles, so a `.shp` file
+/// opens like any other data file (see [`crate::Dataset`]).  [`Shapefile::read`] reads the
+/// `.shp` file and the `.dbf` file beside it, taking a row for each record of the table and a
+/// column for each field, with the shape of the record in the [`GEOMETRY`] column, as GeoJSON
+/// text:
+///
+/// * A point becomes a `Point`, a set of points a `MultiPoint`, and a line a `LineString`, or a
+///   `MultiLineString` if it has more than one part.  The heights and measures of the `Z` and
+///   `M` kinds of shape are left behind, since the map is flat.
+/// * The rings of a polygon go to the polygon of the last clockwise ring before them, so a
+///   shape of several outsides becomes a `MultiPolygon`, and each ring turns to run the way
+///   GeoJSON runs them.
+/// * A null shape, or a kind of shape we cannot draw, like a multipatch, leaves the geometry
+///   missing, and we warn how many shapes we could not read.
+/// * A number field reads as the number, without the padding, and a logical field as `true` or
+///   `false`.  Text reads as UTF-8, as the `.cpg` file we write says, or else as Latin-1, which
+///   most older shapefiles hold.
+/// * A record marked as deleted in the `.dbf` file is left out, with its shape.
+///
+/// We do not project coordinates, so a shapefile whose `.prj` file names a projected system
+/// opens with a warning that its features will not line up with the map, as a GeoPackage does.
+///
+/// * The `kind` field holds the kind of shape in the file, or [`None`] if no feature has one.
+/// * The `shapes` field holds the parts of the shape of each row, or [`None`] for a null shape.
+/// * The `fields` field holds the attribute fields, in the order of the columns.
+/// * The `records` field holds the attribute values of each row, in the order of the `fields`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shapefile {
+    kind: Option<ShapeKind>,
+    shapes: Vec<Option<Vec<Vec<(f64, f64)>>>>,
+    fields: Vec<Field>,
+    records: Vec<Vec<String>>,
+}
+
+impl Shapefile {
+    /// The `from_dataset` method gathers the rows of `dataset`, as described in the
+    /// [`Shapefile`] docs.  Pick the rows first with [`Dataset::selection`].
+    pub fn from_dataset(dataset: &Dataset) -> Self {
+        let geometry = dataset.geometry();
+        let columns = dataset
+            .columns()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != geometry)
+            .collect::<Vec<(usize, String)>>();
+        let count = dataset.len();
+
+        let mut kind = None;
+        let mut lost = 0;
+        let mut shapes = Vec::with_capacity(count);
+        for row in 0..count {
+            let shape = dataset
+                .shapes()
+                .filter(|shapes| shapes.is_valid(row))
+                .and_then(|shapes| {
+                    serde_json::from_str::<serde_json::Value>(shapes.value(row)).ok()
+                })
+                .and_then(|value| ShapeKind::read(&value));
+            let shape = match shape {
+                Some((found, parts)) if *kind.get_or_insert(found) == found => Some(parts),
+                Some(_) => {
+                    lost += 1;
+                    None
+                }
+                None => None,
+            };
+            shapes.push(shape);
+        }
+        if let (Some(kind), true) = (kind, lost > 0) {
+            tracing::warn!("{lost} features are not {kind} shapes, and go without a shape.");
+        }
+
+        let mut fields = Vec::with_capacity(columns.len());
+        let mut records = vec![Vec::with_capacity(columns.len()); count];
+        for (index, name) in &columns {
+            let texts = dataset.texts(*index);
+            let values = texts
+                .iter()
+                .map(Option::unwrap_or_default)
+                .collect::<Vec<&str>>();
+            let short = Field::name(name, &fields);
+            if short != *name {
+                tracing::warn!("Column {name} is {short} in the shapefile.");
+            }
+            let field = Field::fit(short, &values);
+            let mut cut = 0;
+            for (record, value) in records.iter_mut().zip(&values) {
+                let (text, shortened) = field.format(value);
+                cut += usize::from(shortened);
+                record.push(text);
+            }
+            if cut > 0 {
+                tracing::warn!(
+                    "{cut} values of {name} are cut to {FIELD_WIDTH_LIMIT} bytes in the shapefile."
+                );
+            }
+            fields.push(field);
+        }
+        Self {
+            kind,
+            shapes,
+            fields,
+            records,
+        }
+    }
+
+    /// The `save` method writes the shapefile to `path`, which should end in `.shp`, and the
+    /// other files beside it, as described in the [`Shapefile`] docs.  Returns the number of
+    /// features written.
+    ///
+    /// Will [`crate::Blame::Io`] if a file cannot be written.
+    #[tracing::instrument(skip(self))]
+    pub fn save(&self, path: &path::Path) -> Arrive<usize> {
+        let (shp, shx) = self.shapes();
+        let files = [
+            ("shp", shp),
+            ("shx", shx),
+            ("dbf", self.table()),
+            ("prj", WGS84.as_bytes().to_vec()),
+            ("cpg", b"UTF-8".to_vec()),
+        ];
+        for (extension, bytes) in files {
+            let path = path.with_extension(extension);
+            std::fs::File::create(&path)
+                .and_then(|mut file| file.write_all(&bytes))
+                .with_context(|| format!("writing {}", path.display()))?;
+        }
+        let count = self.records.len();
+        tracing::info!("Wrote {count} features to {}.", path.display());
+        Ok(count)
+    }
+
+    /// The `read` method reads the shapefile at `path`, with the `.dbf` file beside it, as
+    /// columns and rows of text, reporting to `job`, as described in the [`Shapefile`] docs.  It
+    /// blocks until the whole file is read, so call it through [`Dataset::load`].
+    ///
+    /// Will [`crate::Blame::Io`] if the `.shp` or `.dbf` file cannot be read or is not part of a
+    /// shapefile, or if the job is cancelled.
+    #[tracing::instrument(skip(job))]
+    pub fn read(path: &path::Path, job: &Job) -> Arrive<(Vec<String>, Vec<Vec<String>>)> {
+        let shp = Self::open(path, "shp")?;
+        let dbf = Self::open(path, "dbf")?;
+        if shp.get(..4) != Some(&9994i32.to_be_bytes()[..]) || shp.len() < HEADER {
+            return Err(Self::invalid(path, "shp"));
+        }
+        let (mut columns, records) =
+            Self::records(&dbf).ok_or_else(|| Self::invalid(path, "dbf"))?;
+        columns.push(GEOMETRY.to_string());
+        if let Ok(prj) = std::fs::read_to_string(Self::sibling(path, "prj")) {
+            if prj.trim_start().starts_with("PROJCS") {
+                tracing::warn!(
+                    "{} is in a projected system, not longitude and latitude, \
+                     so its features will not line up with the map.",
+                    path.display()
+                );
+            }
+        }
+        let contents = Self::contents(&shp);
+        if contents.len() != records.len() {
+            tracing::warn!(
+                "{} holds {} shapes for {} records.",
+                path.display(),
+                contents.len(),
+                records.len()
+            );
+        }
+        let total = records.len();
+        let mut rows = Vec::new();
+        let mut unread = 0;
+        let mut reported = 0.0;
+        for (index, record) in records.into_iter().enumerate() {
+            if job.is_cancelled() {
+                return Err(std::io::Error::other("the job was cancelled").into());
+            }
+            if let Some(mut values) = record {
+                let shape = contents.get(index).copied().and_then(ShapeKind::decode);
+                let geometry = match shape {
+                    Some(serde_json::Value::Null) => String::new(),
+                    Some(shape) => shape.to_string(),
+                    None => {
+                        unread += 1;
+                        String::new()
+                    }
+                };
+                values.push(geometry);
+                rows.push(values);
+            }
+            let fraction = (index + 1) as f32 / total as f32;
+            if fraction - reported >= PROGRESS_STEP {
+                reported = fraction;
+                job.set_progress(fraction);
+            }
+        }
+        if unread > 0 {
+            tracing::warn!("Could not read {unread} shapes of {}.", path.display());
+        }
+        Ok((columns, rows))
+    }
+
+    /// The `open` method reads the file of the shapefile at `path` with the `extension`.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be read.
+    fn open(path: &path::Path, extension: &str) -> Arrive<Vec<u8>> {
+        let path = Self::sibling(path, extension);
+        let bytes = std::fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        Ok(bytes)
+    }
+
+    /// The `sibling` method returns the path of the file of the shapefile at `path` with the
+    /// `extension`, in capitals if the extension of `path` is, as older tools write them.
+    fn sibling(path: &path::Path, extension: &str) -> path::PathBuf {
+        let upper = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.chars().all(|c| c.is_ascii_uppercase()));
+        match upper {
+            true => path.with_extension(extension.to_ascii_uppercase()),
+            false => path.with_extension(extension),
+        }
+    }
+
+    /// The `invalid` method describes the file of the shapefile at `path` with the `extension`
+    /// as not what it should be.
+    fn invalid(path: &path::Path, extension: &str) -> crate::Blame {
+        let path = Self::sibling(path, extension);
+        let message = format!("{} is not part of a shapefile", path.display());
+        std::io::Error::new(std::io::ErrorKind::InvalidData, message).into()
+    }
+
+    /// The `contents` method splits the records of the `.shp` file in `shp` into their contents,
+    /// in order, stopping at the first record that runs past the end of the file.
+    fn contents(shp: &[u8]) -> Vec<&[u8]> {
+        let mut contents = Vec::new();
+        let mut at = HEADER;
+        while let Some(head) = shp.get(at..at + 8) {
+            // Lengths count 16-bit words, and leave out the header of the record.
+            let words = i32::from_be_bytes([head[4], head[5], head[6], head[7]]);
+            let Ok(words) = usize::try_from(words) else {
+                break;
+            };
+            let start = at + 8;
+            let Some(content) = shp.get(start..start + words * 2) else {
+                break;
+            };
+            contents.push(content);
+            at = start + words * 2;
+        }
+        contents
+    }
+
+    /// The `records` method reads the `.dbf` file in `dbf` into the names of its fields and the
+    /// values of each record, as text, with [`None`] for a deleted record.  Returns [`None`] if
+    /// the header does not read.
+    fn records(dbf: &[u8]) -> Option<(Vec<String>, Vec<Option<Vec<String>>>)> {
+        let count = u32::from_le_bytes(dbf.get(4..8)?.try_into().ok()?) as usize;
+        let length = usize::from(u16::from_le_bytes(dbf.get(8..10)?.try_into().ok()?));
+        let width = usize::from(u16::from_le_bytes(dbf.get(10..12)?.try_into().ok()?));
+        let mut fields = Vec::new();
+        let mut at = 32;
+        // Each field takes 32 bytes of the header, which ends with a carriage return.
+        while at + 32 <= length && *dbf.get(at)? != 0x0D {
+            let descriptor = dbf.get(at..at + 32)?;
+            let name = descriptor[..11].split(|byte| *byte == 0).next()?;
+            fields.push((
+                Field::text(name),
+                descriptor[11],
+                usize::from(descriptor[16]),
+            ));
+            at += 32;
+        }
+        let mut records = Vec::new();
+        for index in 0..count {
+            let start = length + index * width;
+            let Some(record) = dbf.get(start..start + width) else {
+                tracing::warn!("The table ends after {index} of {count} records.");
+                break;
+            };
+            // An asterisk marks a deleted record, and a space one that is not.
+            if record.first() == Some(&b'*') {
+                records.push(None);
+                continue;
+            }
+            let mut offset = 1;
+            let values = fields
+                .iter()
+                .map(|(_, kind, size)| {
+                    let raw = record.get(offset..offset + size).unwrap_or_default();
+                    offset += size;
+                    Field::read(*kind, raw)
+                })
+                .collect();
+            records.push(Some(values));
+        }
+        let names = fields.into_iter().map(|(name, _, _)| name).collect();
+        Some((names, records))
+    }
+
+    /// The `shapes` method encodes the `.shp` file and its `.shx` index.
+    fn shapes(&self) -> (Vec<u8>, Vec<u8>) {
+        let code = self.kind.map_or(0, ShapeKind::code);
+        let mut shp = Vec::new();
+        let mut shx = Vec::new();
+        for (number, shape) in self.shapes.iter().enumerate() {
+            let mut content = Vec::new();
+            match (self.kind, shape) {
+                (Some(kind), Some(parts)) => kind.encode(parts, &mut content),
+                _ => content.extend_from_slice(&0i32.to_le_bytes()),
+            }
+            // Offsets and lengths count 16-bit words, after the header of 100 bytes.
+            let offset = (HEADER + shp.len()) / 2;
+            shx.extend_from_slice(&(offset as i32).to_be_bytes());
+            shx.extend_from_slice(&((content.len() / 2) as i32).to_be_bytes());
+            shp.extend_from_slice(&(number as i32 + 1).to_be_bytes());
+            shp.extend_from_slice(&((content.len() / 2) as i32).to_be_bytes());
+            shp.extend_from_slice(&content);
+        }
+        let points = self.shapes.iter().flatten().flatten().flatten();
+        let bounds = Self::bounds(points).unwrap_or_default();
+        let mut head = Self::header(code, HEADER + shp.len(), bounds);
+        head.extend_from_slice(&shp);
+        let mut index = Self::header(code, HEADER + shx.len(), bounds);
+        index.extend_from_slice(&shx);
+        (head, index)
+    }
+
+    /// The `header` method encodes the header of 100 bytes that starts both the `.shp` and the
+    /// `.shx` file, for a file of `length` bytes holding shapes of type `code` within `bounds`.
+    fn header(code: i32, length: usize, bounds: (f64, f64, f64, f64)) -> Vec<u8> {
+        let mut head = Vec::with_capacity(HEADER);
+        head.extend_from_slice(&9994i32.to_be_bytes());
+        head.extend_from_slice(&[0; 20]);
+        head.extend_from_slice(&((length / 2) as i32).to_be_bytes());
+        head.extend_from_slice(&1000i32.to_le_bytes());
+        head.extend_from_slice(&code.to_le_bytes());
+        let (west, south, east, north) = bounds;
+        for value in [west, south, east, north, 0.0, 0.0, 0.0, 0.0] {
+            head.extend_from_slice(&value.to_le_bytes());
+        }
+        head
+    }
+
+    /// The `bounds` method returns the west, south, east and north edges around `points`, or
+    /// [`None`] if there are none.
+    fn bounds<'a>(points: impl Iterator<Item = &'a (f64, f64)>) -> Option<(f64, f64, f64, f64)> {
+        points.fold(None, |bounds, &(x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((west, south, east, north)) => {
+                Some((west.min(x), south.min(y), east.max(x), north.max(y)))
+            }
+        })
+    }
+
+    /// The `table` method encodes the `.dbf` file, as a dBASE III table.
+    fn table(&self) -> Vec<u8> {
+        let width = 1 + self
+            .fields
+            .iter()
+            .map(|field| usize::from(field.width))
+            .sum::<usize>();
+        let length = 32 + 32 * self.fields.len() + 1;
+        let (year, month, day) = Self::today();
+        let mut table = vec![0x03, year, month, day];
+        table.extend_from_slice(&(self.records.len() as u32).to_le_bytes());
+        table.extend_from_slice(&(length as u16).to_le_bytes());
+        table.extend_from_slice(&(width as u16).to_le_bytes());
+        table.extend_from_slice(&[0; 20]);
+        for field in &self.fields {
+            let mut name = [0u8; 11];
+            name[..field.name.len()].copy_from_slice(field.name.as_bytes());
+            table.extend_from_slice(&name);
+            table.push(if field.number { b'N' } else { b'C' });
+            table.extend_from_slice(&[0; 4]);
+            table.push(field.width);
+            table.push(field.decimals);
+            table.extend_from_slice(&[0; 14]);
+        }
+        table.push(0x0D);
+        for record in &self.records {
+            // A space marks a record that is not deleted.
+            table.push(b' ');
+            record
+                .iter()
+                .for_each(|value| table.extend_from_slice(value.as_bytes()));
+        }
+        table.push(0x1A);
+        table
+    }
+
+    /// The `today` method returns the date for the header of the `.dbf` file, as years since
+    /// 1900, month and day.
+    fn today() -> (u8, u8, u8) {
+        let days = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs() / 86_400)
+            .unwrap_or_default() as i64;
+        // Turns days since 1970 into a civil date, after Howard Hinnant.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        ((year - 1900) as u8, month as u8, day as u8)
+    }
+}
+
+/// The `Field` struct describes a field of the `.dbf` file of a [`Shapefile`].
+///
+/// * The `name` field holds the name of the field, as written.
+/// * The `number` field holds `true` for a number field, and `false` for a text field.
+/// * The `width` field holds the width of the field in bytes.
+/// * The `decimals` field holds the number of digits after the decimal point, for a number field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    name: String,
+    number: bool,
+    width: u8,
+    decimals: u8,
+}
+
+impl Field {
+    /// The `name` method fits the column `name` to the rules for a field name, as described in
+    /// the [`Shapefile`] docs, numbering it if it clashes with one of the `fields` before it.
+    fn name(name: &str, fields: &[Field]) -> String {
+        let clean = name
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c,
+                false => '_',
+            })
+            .take(FIELD_NAME_LIMIT)
+            .collect::<String>();
+        let clean = if clean.is_empty() {
+            "FIELD".to_string()
+        } else {
+            clean
+        };
+        let taken = |name: &str| {
+            fields
+                .iter()
+                .any(|field| field.name.eq_ignore_ascii_case(name))
+        };
+        let mut fitted = clean.clone();
+        let mut count = 1;
+        while taken(&fitted) {
+            let suffix = format!("_{count}");
+            let keep = FIELD_NAME_LIMIT
+                .saturating_sub(suffix.len())
+                .min(clean.len());
+            fitted = format!("{}{suffix}", &clean[..keep]);
+            count += 1;
+        }
+        fitted
+    }
+
+    /// The `fit` method picks the type and width of the field `name` to hold `values`, as
+    /// described in the [`Shapefile`] docs.
+    fn fit(name: String, values: &[&str]) -> Self {
+        let filled = values
+            .iter()
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<&&str>>();
+        let number = !filled.is_empty() && filled.iter().all(|value| Self::decimal(value));
+        if number {
+            let decimals = filled
+                .iter()
+                .map(|value| {
+                    value
+                        .split_once('.')
+                        .map_or(0, |(_, fraction)| fraction.len())
+                })
+                .max()
+                .unwrap_or_default();
+            let whole = filled
+                .iter()
+                .map(|value| value.split('.').next().unwrap_or_default().len())
+                .max()
+                .unwrap_or_default();
+            let width = whole + if decimals > 0 { decimals + 1 } else { 0 };
+            if width <= NUMBER_WIDTH_LIMIT {
+                return Self {
+                    name,
+                    number,
+                    width: width as u8,
+                    decimals: decimals as u8,
+                };
+            }
+        }
+        let width = values
+            .iter()
+            .map(|value| value.len())
+            .max()
+            .unwrap_or_default()
+            .clamp(1, FIELD_WIDTH_LIMIT);
+        Self {
+            name,
+            number: false,
+            width: width as u8,
+            decimals: 0,
+        }
+    }
+
+    /// The `decimal` method returns `true` if `value` is a plain decimal number, with an optional
+    /// minus sign and fraction, and no exponent.
+    fn decimal(value: &str) -> bool {
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+        !whole.is_empty()
+            && !fraction.is_empty()
+            && whole.chars().all(|c| c.is_ascii_digit())
+            && fraction.chars().all(|c| c.is_ascii_digit())
+    }
+
+    /// The `read` method reads the `raw` bytes of a value of a field of type `kind` from a
+    /// `.dbf` file, as described in the [`Shapefile`] docs.  A value that does not read is
+    /// missing, and comes back empty.
+    fn read(kind: u8, raw: &[u8]) -> String {
+        let text = Self::text(raw);
+        match kind {
+            b'N' | b'F' => text
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .map(|number| number.to_string())
+                .unwrap_or_default(),
+            b'L' => match text.trim() {
+                "T" | "t" | "Y" | "y" => "true".to_string(),
+                "F" | "f" | "N" | "n" => "false".to_string(),
+                _ => String::new(),
+            },
+            _ => text.trim_end().to_string(),
+        }
+    }
+
+    /// The `text` method reads `raw` bytes from a `.dbf` file as UTF-8, or else as Latin-1,
+    /// one character for each byte.
+    fn text(raw: &[u8]) -> String {
+        match std::str::from_utf8(raw) {
+            Ok(text) => text.to_string(),
+            Err(_) => raw.iter().copied().map(char::from).collect(),
+        }
+    }
+
+    /// The `format` method pads `value` to the width of the field, right-aligned with its
+    /// decimals filled out for a number field, and left-aligned for a text field.  Returns the
+    /// padded value, and `true` if the value was too long and cut short.
+    fn format(&self, value: &str) -> (String, bool) {
+        let width = usize::from(self.width);
+        if self.number {
+            if value.is_empty() {
+                return (" ".repeat(width), false);
+            }
+            let decimals = usize::from(self.decimals);
+            let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+            let text = match decimals {
+                0 => whole.to_string(),
+                _ => format!("{whole}.{fraction:0<decimals$}"),
+            };
+            return (format!("{text:>width$}"), false);
+        }
+        let mut end = value.len().min(width);
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        let text = &value[..end];
+        // Pad by bytes, not characters, since the width of the field counts bytes.
+        let pad = " ".repeat(width - text.len());
+        (format!("{text}{pad}"), end < value.len())
+    }
+}
+
+/// The `ShapeKind` enum names the kinds of shape a [`Shapefile`] holds, each standing for the
+/// GeoJSON geometries it takes.
+///
+/// * The `Point` variant holds a single point, from a GeoJSON `Point`.
+/// * The `Line` variant holds lines of one or more parts, from a `LineString` or a
+///   `MultiLineString`.
+/// * The `Polygon` variant holds polygons of one or more rings, from a `Polygon` or a
+///   `MultiPolygon`.
+/// * The `MultiPoint` variant holds a set of points, from a `MultiPoint`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum ShapeKind {
+    /// The `Point` variant indicates single points.
+    #[display("point")]
+    Point,
+    /// The `Line` variant indicates lines.
+    #[display("line")]
+    Line,
+    /// The `Polygon` variant indicates polygons.
+    #[display("polygon")]
+    Polygon,
+    /// The `MultiPoint` variant indicates sets of points.
+    #[display("multipoint")]
+    MultiPoint,
+}
+
+impl ShapeKind {
+    /// The `code` method returns the number that stands for the kind in a shapefile.
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Point => 1,
+            Self::Line => 3,
+            Self::Polygon => 5,
+            Self::MultiPoint => 8,
+        }
+    }
+
+    /// The `read` method reads a GeoJSON `geometry` into its kind and its parts, each a list of
+    /// longitude and latitude, with the rings of a polygon turned as described in the
+    /// [`Shapefile`] docs.  Returns [`None`] for a geometry a shapefile cannot hold, such as a
+    /// `GeometryCollection`, or one that does not parse.
+    pub fn read(geometry: &serde_json::Value) -> Option<(Self, Vec<Vec<(f64, f64)>>)> {
+        let coordinates = geometry.get("coordinates")?;
+        let point = |value: &serde_json::Value| {
+            let pair = value.as_array()?;
+            Some((pair.first()?.as_f64()?, pair.get(1)?.as_f64()?))
+        };
+        let line = |value: &serde_json::Value| {
+            value
+                .as_array()?
+                .iter()
+                .map(point)
+                .collect::<Option<Vec<(f64, f64)>>>()
+        };
+        let many = |value: &serde_json::Value| {
+            value
+                .as_array()?
+                .iter()
+                .map(line)
+                .collect::<Option<Vec<Vec<(f64, f64)>>>>()
+        };
+        let shape = match geometry.get("type")?.as_str()? {
+            "Point" => (Self::Point, vec![vec![point(coordinates)?]]),
+            "MultiPoint" => (Self::MultiPoint, vec![line(coordinates)?]),
+            "LineString" => (Self::Line, vec![line(coordinates)?]),
+            "MultiLineString" => (Self::Line, many(coordinates)?),
+            "Polygon" => (Self::Polygon, Self::turn(many(coordinates)?)),
+            "MultiPolygon" => {
+                let mut rings = Vec::new();
+                for polygon in coordinates.as_array()? {
+                    rings.extend(Self::turn(many(polygon)?));
+                }
+                (Self::Polygon, rings)
+            }
+            _ => return None,
+        };
+        Some(shape)
+    }
+
+    /// The `from_code` method returns the kind that the number `code` stands for in a shapefile,
+    /// taking the `Z` and `M` kinds as the flat kind, or [`None`] for a null shape or a kind we
+    /// cannot draw.
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 | 11 | 21 => Some(Self::Point),
+            3 | 13 | 23 => Some(Self::Line),
+            5 | 15 | 25 => Some(Self::Polygon),
+            8 | 18 | 28 => Some(Self::MultiPoint),
+            _ => None,
+        }
+    }
+
+    /// The `turn` method turns the outer ring of a polygon clockwise and the holes
+    /// counterclockwise, going by the sign of the area of each ring.
+    fn turn(mut rings: Vec<Vec<(f64, f64)>>) -> Vec<Vec<(f64, f64)>> {
+        for (index, ring) in rings.iter_mut().enumerate() {
+            let clockwise = Self::area(ring) < 0.0;
+            if clockwise != (index == 0) {
+                ring.reverse();
+            }
+        }
+        rings
+    }
+
+    /// The `area` method returns twice the signed area of `ring`, which is positive when the
+    /// ring runs counterclockwise.
+    fn area(ring: &[(f64, f64)]) -> f64 {
+        ring.iter()
+            .zip(ring.iter().cycle().skip(1))
+            .map(|((x0, y0), (x1, y1))| x0 * y1 - x1 * y0)
+            .sum()
+    }
+
+    /// The `decode` method reads the `content` of a record of a `.shp` file into a GeoJSON
+    /// geometry, as described in the [`Shapefile`] docs.  Returns [`serde_json::Value::Null`]
+    /// for a null shape, and [`None`] for a kind we cannot draw or a record that does not read.
+    pub fn decode(content: &[u8]) -> Option<serde_json::Value> {
+        let int = |at: usize| {
+            let bytes = content.get(at..at + 4)?.try_into().ok()?;
+            Some(i32::from_le_bytes(bytes))
+        };
+        let float = |at: usize| {
+            let bytes = content.get(at..at + 8)?.try_into().ok()?;
+            Some(f64::from_le_bytes(bytes))
+        };
+        let code = int(0)?;
+        if code == 0 {
+            return Some(serde_json::Value::Null);
+        }
+        let kind = Self::from_code(code)?;
+        if kind == Self::Point {
+            let point = (float(4)?, float(12)?);
+            return Some(serde_json::json!({ "type": "Point", "coordinates": point }));
+        }
+        // The bounds take the 32 bytes after the kind, and a set of points has no parts.
+        let (parts, count, start) = match kind {
+            Self::MultiPoint => (0, int(36)?, 40),
+            _ => (int(36)?, int(40)?, 44),
+        };
+        let parts = usize::try_from(parts).ok()?;
+        let count = usize::try_from(count).ok()?;
+        let first = start + parts * 4;
+        let points = (0..count)
+            .map(|index| Some((float(first + index * 16)?, float(first + index * 16 + 8)?)))
+            .collect::<Option<Vec<(f64, f64)>>>()?;
+        if kind == Self::MultiPoint {
+            return Some(serde_json::json!({ "type": "MultiPoint", "coordinates": points }));
+        }
+        let mut starts = (0..parts)
+            .map(|part| usize::try_from(int(start + part * 4)?).ok())
+            .collect::<Option<Vec<usize>>>()?;
+        starts.push(count);
+        let rings = starts
+            .windows(2)
+            .map(|pair| points.get(pair[0]..pair[1]).map(<[(f64, f64)]>::to_vec))
+            .collect::<Option<Vec<Vec<(f64, f64)>>>>()?;
+        let geometry = match (kind, rings.len()) {
+            (Self::Line, 1) => serde_json::json!({ "type": "LineString", "coordinates": rings[0] }),
+            (Self::Line, _) => {
+                serde_json::json!({ "type": "MultiLineString", "coordinates": rings })
+            }
+            _ => {
+                let mut polygons = Vec::<Vec<Vec<(f64, f64)>>>::new();
+                for mut ring in rings {
+                    let outside = Self::area(&ring) < 0.0;
+                    // Shapefiles run their rings the other way around from GeoJSON.
+                    ring.reverse();
+                    match polygons.last_mut() {
+                        Some(polygon) if !outside => polygon.push(ring),
+                        _ => polygons.push(vec![ring]),
+                    }
+                }
+                match polygons.len() {
+                    1 => serde_json::json!({ "type": "Polygon", "coordinates": polygons[0] }),
+                    _ => serde_json::json!({ "type": "MultiPolygon", "coordinates": polygons }),
+                }
+            }
+        };
+        Some(geometry)
+    }
+
+    /// The `encode` method writes the content of a record holding `parts` into `content`.
+    fn encode(self, parts: &[Vec<(f64, f64)>], content: &mut Vec<u8>) {
+        content.extend_from_slice(&self.code().to_le_bytes());
+        let points = parts.iter().flatten();
+        if self == Self::Point {
+            let (x, y) = points.clone().next().copied().unwrap_or_default();
+            content.extend_from_slice(&x.to_le_bytes());
+            content.extend_from_slice(&y.to_le_bytes());
+            return;
+        }
+        let (west, south, east, north) = Shapefile::bounds(points.clone()).unwrap_or_default();
+        for value in [west, south, east, north] {
+            content.extend_from_slice(&value.to_le_bytes());
+        }
+        if self != Self::MultiPoint {
+            content.extend_from_slice(&(parts.len() as i32).to_le_bytes());
+        }
+        content.extend_from_slice(&(points.clone().count() as i32).to_le_bytes());
+        if self != Self::MultiPoint {
+            let mut start = 0;
+            for part in parts {
+                content.extend_from_slice(&(start as i32).to_le_bytes());
+                start += part.len();
+            }
+        }
+        for (x, y) in points {
+            content.extend_from_slice(&x.to_le_bytes());
+            content.extend_from_slice(&y.to_le_bytes());
+        }
+    }
+}
+
+/// The `HEADER` constant holds the length in bytes of the header of the `.shp` and `.shx` files.
+const HEADER: usize = 100;
+
+/// The `NUMBER_WIDTH_LIMIT` constant holds the widest number field we write, since readers of
+/// dBASE files disagree past it.  A column of longer numbers goes as text.
+const NUMBER_WIDTH_LIMIT: usize = 19;
+
+/// The `WGS84` constant holds the `.prj` file of a [`Shapefile`], naming longitude and latitude
+/// on the WGS 84 datum.
+const WGS84: &str = "GEOGCS[\"GCS_WGS_1984\",DATUM[\"D_WGS_1984\",SPHEROID[\"WGS_1984\",\
+                     6378137.0,298.257223563]],PRIMEM[\"Greenwich\",0.0],\
+                     UNIT[\"Degree\",0.0174532925199433]]";
+
+/// The `FIELD_NAME_LIMIT` constant holds the most characters in the name of a field of a
+/// [`Shapefile`].
+pub const FIELD_NAME_LIMIT: usize = 10;
+
+/// The `FIELD_WIDTH_LIMIT` constant holds the most bytes in a text field of a [`Shapefile`].
+pub const FIELD_WIDTH_LIMIT: usize = 254;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `word` function reads the big-endian integer at byte `at` of `bytes`.
+    fn word(bytes: &[u8], at: usize) -> i32 {
+        i32::from_be_bytes(bytes[at..at + 4].try_into().expect("four bytes"))
+    }
+
+    #[test]
+    fn rings_turn_for_shapefiles() {
+        // GeoJSON runs the outside counterclockwise and the hole clockwise.
+        let geometry = serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]],
+                [[1.0, 1.0], [1.0, 2.0], [2.0, 2.0], [2.0, 1.0], [1.0, 1.0]],
+            ],
+        });
+        let (kind, rings) = ShapeKind::read(&geometry).expect("polygon reads");
+        assert_eq!(kind, ShapeKind::Polygon);
+        assert!(ShapeKind::area(&rings[0]) < 0.0);
+        assert!(ShapeKind::area(&rings[1]) > 0.0);
+    }
+
+    #[test]
+    fn headers_count_words() {
+        let shapefile = Shapefile {
+            kind: Some(ShapeKind::Point),
+            shapes: vec![Some(vec![vec![(1.0, 2.0)]]), None],
+            fields: Vec::new(),
+            records: vec![Vec::new(); 2],
+        };
+        let (shp, shx) = shapefile.shapes();
+        // A point record is a header of 8 bytes and 20 of content, and a null one 8 and 4.
+        assert_eq!(shp.len(), HEADER + 28 + 12);
+        assert_eq!(word(&shp, 24) as usize * 2, shp.len());
+        assert_eq!(shx.len(), HEADER + 16);
+        assert_eq!(word(&shx, 24) as usize * 2, shx.len());
+        assert_eq!((word(&shx, 100), word(&shx, 104)), (50, 10));
+        assert_eq!((word(&shx, 108), word(&shx, 112)), (64, 2));
+    }
+
+    #[test]
+    fn shapefiles_read_back() {
+        let geometry = serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]],
+                [[1.0, 1.0], [1.0, 2.0], [2.0, 2.0], [2.0, 1.0], [1.0, 1.0]],
+            ],
+        });
+        let (kind, rings) = ShapeKind::read(&geometry).expect("polygon reads");
+        let fields = vec![
+            Field::fit("NAME".to_string(), &["Café", "Lot"]),
+            Field::fit("AREA".to_string(), &["12.5", ""]),
+        ];
+        let records = [["Café", "12.5"], ["Lot", ""]]
+            .iter()
+            .map(|values| {
+                let pairs = fields.iter().zip(values.iter());
+                pairs.map(|(field, value)| field.format(value).0).collect()
+            })
+            .collect();
+        let shapefile = Shapefile {
+            kind: Some(kind),
+            shapes: vec![Some(rings), None],
+            fields,
+            records,
+        };
+        let dir = std::env::temp_dir().join("bea_egui_shapefile_test");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("lots.shp");
+        shapefile.save(&path).expect("saved");
+        let (columns, rows) = Shapefile::read(&path, &Job::detached("test")).expect("read");
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(columns, vec!["NAME", "AREA", GEOMETRY]);
+        assert_eq!(rows[0][..2], ["Café", "12.5"]);
+        let read = serde_json::from_str::<serde_json::Value>(&rows[0][2]).expect("geojson");
+        assert_eq!(read, geometry);
+        assert_eq!(rows[1], vec!["Lot", "", ""]);
+    }
+
+    #[test]
+    fn outsides_start_polygons() {
+        let square = |x: f64| vec![(x, 0.0), (x, 1.0), (x + 1.0, 1.0), (x + 1.0, 0.0), (x, 0.0)];
+        let mut content = Vec::new();
+        ShapeKind::Polygon.encode(&[square(0.0), square(2.0)], &mut content);
+        let geometry = ShapeKind::decode(&content).expect("decodes");
+        assert_eq!(geometry["type"], "MultiPolygon");
+        assert_eq!(geometry["coordinates"].as_array().map(Vec::len), Some(2));
+        assert_eq!(
+            ShapeKind::decode(&0i32.to_le_bytes()),
+            Some(serde_json::Value::Null)
+        );
+        assert_eq!(ShapeKind::decode(&31i32.to_le_bytes()), None);
+    }
+
+    #[test]
+    fn names_fit_and_number() {
+        let long = Field::name("assessed value", &[]);
+        assert_eq!(long, "assessed_v");
+        let fields = [Field::fit(long, &[])];
+        assert_eq!(Field::name("Assessed Valuation", &fields), "Assessed_1");
+        assert_eq!(Field::name("", &[]), "FIELD");
+    }
+
+    #[test]
+    fn text_cuts_at_characters() {
+        let field = Field::fit("NAME".to_string(), &["abc"]);
+        assert_eq!(field.format("ab"), ("ab ".to_string(), false));
+        // The accent takes two bytes, and the cut may not split it.
+        assert_eq!(field.format("abé"), ("ab ".to_string(), true));
+        let long = "x".repeat(FIELD_WIDTH_LIMIT + 10);
+        let field = Field::fit("NOTE".to_string(), &[&long]);
+        assert_eq!(usize::from(field.width), FIELD_WIDTH_LIMIT);
+        let (text, cut) = field.format(&long);
+        assert_eq!((text.len(), cut), (FIELD_WIDTH_LIMIT, true));
+    }
+
+    #[test]
+    fn numbers_line_up() {
+        let field = Field::fit("AREA".to_string(), &["12.5", "3", ""]);
+        assert!(field.number);
+        assert_eq!((field.width, field.decimals), (4, 1));
+        assert_eq!(field.format("3").0, " 3.0");
+        assert_eq!(field.format("").0, "    ");
+        assert!(!Field::fit("ID".to_string(), &["1e5"]).number);
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{Arrive, Excuse, Extent, Synopsis};
+
+/// The `sketch` module provides the [`Sketch`] struct, which holds a geometry pasted onto the
+/// map as an annotation.
+///
+/// # Pasting geometry with `Sketch`
+///
+/// Engineers send geometry around in the body of an email: a parcel boundary copied out of a
+/// database as WKT, or a route as GeoJSON from a web map.  Until now the only way to see one on
+/// the map was to save it to a file and open it as a table.  With
+/// [`crate::Act::PasteGeometry`], the user copies the text and pastes it straight onto the
+/// focused map, where it joins the annotation layer of the window (see [`crate::Ink`]) and the
+/// map moves to show it.  [`crate::Act::UndoInk`] takes it off again, like any other annotation.
+///
+/// [`Sketch::parse`] accepts either kind of text:
+///
+/// * WKT, as in `POINT (-123.326 42.4435)` or `POLYGON ((...))`, including the `MULTI` types and
+///   `GEOMETRYCOLLECTION`, and the `SRID=4326;` prefix of extended WKT that PostGIS adds.  A
+///   third or fourth number on each position, as in `POINT Z`, comes along as GeoJSON allows.
+/// * GeoJSON, as a bare geometry, a `Feature`, or a `FeatureCollection`, which pastes each
+///   feature as its own sketch.
+///
+/// Both hold positions as longitude then latitude.  Text in another coordinate system, like the
+/// state plane feet that come out of the county survey, lands far off the globe, and rather than
+/// draw it in the ocean we refuse it with [`Excuse::Projected`].  Text that is neither WKT nor
+/// GeoJSON fails with [`Excuse::NotGeometry`].
+///
+/// * The `geometry` field holds the geometry as a GeoJSON value, whatever the text it came from.
+/// * The `extent` field holds the ground the geometry covers.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Sketch {
+    geometry: serde_json::Value,
+    extent: Extent,
+}
+
+impl Sketch {
+    /// The `new` method creates a sketch of the GeoJSON `geometry`, working out its extent.
+    ///
+    /// Will [`Excuse::NotGeometry`] if the geometry has no positions, and [`Excuse::Projected`]
+    /// if a position lies off the globe.
+    pub fn new(geometry: serde_json::Value) -> Arrive<Self> {
+        let mut points = Vec::new();
+        Self::points(&geometry, &mut points);
+        let Some((first, rest)) = points.split_first() else {
+            return Err(Excuse::NotGeometry.into());
+        };
+        let mut bounds = (first.0, first.1, first.0, first.1);
+        for (longitude, latitude) in rest {
+            bounds.0 = bounds.0.min(*longitude);
+            bounds.1 = bounds.1.min(*latitude);
+            bounds.2 = bounds.2.max(*longitude);
+            bounds.3 = bounds.3.max(*latitude);
+        }
+        let (west, south, east, north) = bounds;
+        if west < -180.0 || east > 180.0 || south < -90.0 || north > 90.0 {
+            return Err(Excuse::Projected.into());
+        }
+        Ok(Self {
+            geometry,
+            extent: Extent::new(west, south, east, north),
+        })
+    }
+
+    /// The `parse` method reads the WKT or GeoJSON in `text` into one sketch for each geometry,
+    /// as described in the [`Sketch`] docs.
+    ///
+    /// Will [`Excuse::NotGeometry`] if the text is neither, and [`Excuse::Projected`] if a
+    /// position lies off the globe.
+    pub fn parse(text: &str) -> Arrive<Vec<Self>> {
+        let text = text.trim();
+        let geometries = match text.starts_with('{') {
+            true => Self::from_geojson(text)?,
+            false => vec![Wkt::new(text).geometry()?],
+        };
+        geometries.into_iter().map(Self::new).collect()
+    }
+
+    /// The `span` method returns the extent covering every sketch in `sketches`, grown by
+    /// [`SKETCH_MARGIN`] on each side so that a point or a straight line still has ground around
+    /// it, or [`None`] if there are no sketches.
+    pub fn span(sketches: &[Self]) -> Option<Extent> {
+        let (first, rest) = sketches.split_first()?;
+        let extent = rest.iter().fold(*first.extent(), |span, sketch| {
+            let extent = sketch.extent();
+            Extent::new(
+                span.west().min(*extent.west()),
+                span.south().min(*extent.south()),
+                span.east().max(*extent.east()),
+                span.north().max(*extent.north()),
+            )
+        });
+        Some(Extent::new(
+            (extent.west() - SKETCH_MARGIN).max(-180.0),
+            (extent.south() - SKETCH_MARGIN).max(-90.0),
+            (extent.east() + SKETCH_MARGIN).min(180.0),
+            (extent.north() + SKETCH_MARGIN).min(90.0),
+        ))
+    }
+
+    /// The `paths` method returns the lines that draw the sketch, in longitude and latitude: a
+    /// path of one position for each point, and a path for each line and each ring of a
+    /// polygon, including the members of a geometry collection.
+    pub fn paths(&self) -> Vec<Vec<(f64, f64)>> {
+        let mut paths = Vec::new();
+        Self::trace(&self.geometry, &mut paths);
+        paths
+    }
+
+    /// The `trace` method collects the paths of the GeoJSON `geometry` into `paths`, as
+    /// described in [`Sketch::paths`].
+    fn trace(geometry: &serde_json::Value, paths: &mut Vec<Vec<(f64, f64)>>) {
+        let empty = serde_json::Value::Null;
+        let coordinates = geometry.get("coordinates").unwrap_or(&empty);
+        match geometry.get("type").and_then(serde_json::Value::as_str) {
+            Some("Point" | "LineString") => paths.push(Self::path(coordinates)),
+            Some("MultiPoint" | "MultiLineString" | "Polygon") => {
+                paths.extend(Self::members(coordinates).iter().map(Self::path));
+            }
+            Some("MultiPolygon") => Self::members(coordinates).iter().for_each(|polygon| {
+                paths.extend(Self::members(polygon).iter().map(Self::path));
+            }),
+            Some("GeometryCollection") => {
+                Self::members(geometry.get("geometries").unwrap_or(&empty))
+                    .iter()
+                    .for_each(|member| Self::trace(member, paths));
+            }
+            _ => {}
+        }
+        paths.retain(|path| !path.is_empty());
+    }
+
+    /// The `members` method returns the members of the GeoJSON array `value`, or none if it is
+    /// not an array.
+    fn members(value: &serde_json::Value) -> &[serde_json::Value] {
+        value.as_array().map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The `path` method returns the positions of the GeoJSON `coordinates` of a point or a line,
+    /// in order.
+    fn path(coordinates: &serde_json::Value) -> Vec<(f64, f64)> {
+        let mut points = Vec::new();
+        Synopsis::points(coordinates, &mut points);
+        points
+    }
+
+    /// The `from_geojson` method reads the geometries out of GeoJSON `text`, as described in the
+    /// [`Sketch`] docs.  A feature without a geometry is skipped.
+    ///
+    /// Will [`Excuse::NotGeometry`] if the text is not GeoJSON, or holds no geometry.
+    fn from_geojson(text: &str) -> Arrive<Vec<serde_json::Value>> {
+        let value =
+            serde_json::from_str::<serde_json::Value>(text).map_err(|_| Excuse::NotGeometry)?;
+        let geometries = match value.get("type").and_then(serde_json::Value::as_str) {
+            Some("FeatureCollection") => value
+                .get("features")
+                .and_then(serde_json::Value::as_array)
+                .map(|features| {
+                    features
+                        .iter()
+                        .filter_map(|feature| feature.get("geometry"))
+                        .filter(|geometry| !geometry.is_null())
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Some("Feature") => value
+                .get("geometry")
+                .filter(|geometry| !geometry.is_null())
+                .cloned()
+                .into_iter()
+                .collect(),
+            Some(_) => vec![value],
+            None => Vec::new(),
+        };
+        if geometries.is_empty() {
+            return Err(Excuse::NotGeometry.into());
+        }
+        Ok(geometries)
+    }
+
+    /// The `points` method collects the longitude and latitude of every position in the GeoJSON
+    /// `geometry`, including the members of a geometry collection, into `points`.
+    fn points(geometry: &serde_json::Value, points: &mut Vec<(f64, f64)>) {
+        if let Some(coordinates) = geometry.get("coordinates") {
+            Synopsis::points(coordinates, points);
+        }
+        if let Some(members) = geometry
+            .get("geometries")
+            .and_then(serde_json::Value::as_array)
+        {
+            members
+                .iter()
+                .for_each(|member| Self::points(member, points));
+        }
+    }
+}
+
+/// The `Wkt` struct reads well-known text into GeoJSON, as described in the [`Sketch`] docs.  It
+/// walks the text once, a word, number or parenthesis at a time.
+///
+/// * The `text` field holds the rest of the text still to read.
+struct Wkt<'a> {
+    text: &'a str,
+}
+
+impl<'a> Wkt<'a> {
+    /// The `new` method starts reading `text`, skipping an `SRID=...;` prefix.
+    fn new(text: &'a str) -> Self {
+        let text = match text.split_once(';') {
+            Some((srid, rest)) if srid.trim().to_uppercase().starts_with("SRID=") => rest,
+            _ => text,
+        };
+        Self { text }
+    }
+
+    /// The `geometry` method reads one tagged geometry, and checks that nothing but space
+    /// follows it.
+    ///
+    /// Will [`Excuse::NotGeometry`] if the text is not WKT.
+    fn geometry(&mut self) -> Arrive<serde_json::Value> {
+        let geometry = self.tagged()?;
+        match self.text.trim().is_empty() {
+            true => Ok(geometry),
+            false => Err(Excuse::NotGeometry.into()),
+        }
+    }
+
+    /// The `tagged` method reads a geometry type, any `Z`, `M` or `ZM` after it, and its body.
+    fn tagged(&mut self) -> Arrive<serde_json::Value> {
+        let tag = self.word().to_uppercase();
+        let word = self.peek_word().to_uppercase();
+        if matches!(word.as_str(), "Z" | "M" | "ZM") {
+            self.word();
+        }
+        let kind = match tag.as_str() {
+            "POINT" => "Point",
+            "LINESTRING" => "LineString",
+            "POLYGON" => "Polygon",
+            "MULTIPOINT" => "MultiPoint",
+            "MULTILINESTRING" => "MultiLineString",
+            "MULTIPOLYGON" => "MultiPolygon",
+            "GEOMETRYCOLLECTION" => "GeometryCollection",
+            _ => return Err(Excuse::NotGeometry.into()),
+        };
+        if self.peek_word().eq_ignore_ascii_case("EMPTY") {
+            self.word();
+            return Ok(match kind {
+                "GeometryCollection" => serde_json::json!({ "type": kind, "geometries": [] }),
+                _ => serde_json::json!({ "type": kind, "coordinates": [] }),
+            });
+        }
+        if kind == "GeometryCollection" {
+            let geometries = self.list(Self::tagged)?;
+            return Ok(serde_json::json!({ "type": kind, "geometries": geometries }));
+        }
+        let coordinates = match kind {
+            "Point" => {
+                self.expect('(')?;
+                let position = self.position()?;
+                self.expect(')')?;
+                position
+            }
+            "LineString" => self.positions()?,
+            "Polygon" => serde_json::Value::Array(self.list(Self::positions)?),
+            // Points of a multipoint may come with or without their own parentheses.
+            "MultiPoint" => serde_json::Value::Array(self.list(|wkt| match wkt.peek('(') {
+                true => {
+                    wkt.expect('(')?;
+                    let position = wkt.position()?;
+                    wkt.expect(')')?;
+                    Ok(position)
+                }
+                false => wkt.position(),
+            })?),
+            "MultiLineString" => serde_json::Value::Array(self.list(Self::positions)?),
+            _ => serde_json::Value::Array(
+                self.list(|wkt| Ok(serde_json::Value::Array(wkt.list(Self::positions)?)))?,
+            ),
+        };
+        Ok(serde_json::json!({ "type": kind, "coordinates": coordinates }))
+    }
+
+    /// The `positions` method reads a parenthesized list of positions.
+    fn positions(&mut self) -> Arrive<serde_json::Value> {
+        Ok(serde_json::Value::Array(self.list(Self::position)?))
+    }
+
+    /// The `list` method reads a parenthesized, comma-separated list of items, each read by
+    /// `item`.
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Arrive<T>) -> Arrive<Vec<T>> {
+        self.expect('(')?;
+        let mut items = vec![item(self)?];
+        while self.peek(',') {
+            self.expect(',')?;
+            items.push(item(self)?);
+        }
+        self.expect(')')?;
+        Ok(items)
+    }
+
+    /// The `position` method reads a position of two to four numbers, separated by space.
+    fn position(&mut self) -> Arrive<serde_json::Value> {
+        let mut numbers = Vec::new();
+        while let Ok(number) = self.peek_word().parse::<f64>() {
+            self.word();
+            numbers.push(number);
+        }
+        match numbers.len() {
+            2..=4 => Ok(serde_json::json!(numbers)),
+            _ => Err(Excuse::NotGeometry.into()),
+        }
+    }
+
+    /// The `peek` method returns `true` if the next character, past any space, is `mark`.
+    fn peek(&mut self, mark: char) -> bool {
+        self.text = self.text.trim_start();
+        self.text.starts_with(mark)
+    }
+
+    /// The `expect` method reads `mark`, past any space.
+    ///
+    /// Will [`Excuse::NotGeometry`] if the next character is anything else.
+    fn expect(&mut self, mark: char) -> Arrive<()> {
+        match self.peek(mark) {
+            true => {
+                self.text = &self.text[mark.len_utf8()..];
+                Ok(())
+            }
+            false => Err(Excuse::NotGeometry.into()),
+        }
+    }
+
+    /// The `peek_word` method returns the next word or number, past any space, without reading
+    /// it.
+    fn peek_word(&mut self) -> &'a str {
+        self.text = self.text.trim_start();
+        let end = self
+            .text
+            .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ','))
+            .unwrap_or(self.text.len());
+        &self.text[..end]
+    }
+
+    /// The `word` method reads the next word or number, past any space.
+    fn word(&mut self) -> &'a str {
+        let word = self.peek_word();
+        self.text = &self.text[word.len()..];
+        word
+    }
+}
+
+/// The `SKETCH_MARGIN` constant holds the ground, in degrees, that [`Sketch::span`] leaves
+/// around pasted geometry when the map moves to show it, about a hundred meters.
+pub const SKETCH_MARGIN: f64 = 0.001;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wkt_reads_points_and_polygons() {
+        let sketches = Sketch::parse("SRID=4326;POINT Z (-123.326 42.4435 10)").expect("parses");
+        assert_eq!(sketches.len(), 1);
+        assert_eq!(sketches[0].paths(), vec![vec![(-123.326, 42.4435)]]);
+        let sketches =
+            Sketch::parse("polygon ((0 0, 1 0, 1 1, 0 0), (0.2 0.1, 0.8 0.1, 0.8 0.7, 0.2 0.1))")
+                .expect("parses");
+        assert_eq!(sketches[0].geometry()["type"], "Polygon");
+        assert_eq!(sketches[0].paths().len(), 2);
+        assert_eq!(*sketches[0].extent(), Extent::new(0.0, 0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn wkt_reads_collections() {
+        let text = "GEOMETRYCOLLECTION (POINT (1 2), MULTILINESTRING ((0 0, 1 1), (2 2, 3 3)))";
+        let sketches = Sketch::parse(text).expect("parses");
+        assert_eq!(sketches[0].paths().len(), 3);
+    }
+
+    #[test]
+    fn geojson_splits_features() {
+        let text = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]}},
+            {"type": "Feature", "geometry": null},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, 0], [3, 4]]}}
+        ]}"#;
+        let sketches = Sketch::parse(text).expect("parses");
+        assert_eq!(sketches.len(), 2);
+        assert_eq!(*sketches[1].extent(), Extent::new(0.0, 0.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn refuses_other_text() {
+        let code = |text: &str| Sketch::parse(text).map(|_| ()).unwrap_err().code();
+        assert_eq!(code("POINT (1234567 890123)"), Excuse::Projected.code());
+        assert_eq!(
+            code("the lot behind the fire station"),
+            Excuse::NotGeometry.code()
+        );
+        assert_eq!(
+            code(r#"{"type": "Feature", "geometry": null}"#),
+            Excuse::NotGeometry.code()
+        );
+        assert_eq!(code("POINT (1 2"), Excuse::NotGeometry.code());
+    }
+}
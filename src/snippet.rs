@@ -0,0 +1,195 @@
+use std::ops::Range;
+use std::path;
+
+/// The `snippet` module provides the [`Snippet`] struct, which shows the user where a file went
+/// wrong.
+///
+/// # Pointing at mistakes with `Snippet`
+///
+/// A parse error like `invalid string, expected "` tells the user what the parser wanted, but not
+/// where, and a user who has never read a grammar needs both.  A `Snippet` holds the line of the
+/// file with the mistake, the span of the mistake within it, and a hint on how to fix it where we
+/// can guess one, and prints them in the manner of `rustc` and the `miette` crate:
+///
+/// ```text
+/// error: invalid string
+///  --> config.toml:3:8
+///   |
+/// 3 | mode = line
+///   |        ^^^^
+///   = help: text values need quotes, as in mode = "line"
+/// ```
+///
+/// Each parser reports the position of an error its own way, so each has a constructor:
+///
+/// * [`Snippet::from_toml`] takes the byte span from a [`toml::de::Error`], for the config and
+///   the other settings files.  [`Snippet::from_file`] reads and parses a TOML file in one go.
+/// * [`Snippet::from_csv`] takes the line from a [`csv::Error`], for data files.
+/// * [`Snippet::at`] takes a line and column, as reported by JSON parsers, for GeoJSON files.
+/// * [`Snippet::from_span`] takes a byte range, for anything else.
+///
+/// A [`crate::Diagnostic`] holding a [`crate::Problem::Syntax`] prints its snippet in the startup
+/// report, and the [`crate::Editor`] returns one from [`crate::Editor::validate`].
+///
+/// * The `origin` field holds the name of the file, if we know it.
+/// * The `message` field holds the error from the parser.
+/// * The `line` field holds the number of the line with the mistake, counting from one.
+/// * The `column` field holds the column where the mistake starts, counting characters from one.
+/// * The `source` field holds the text of the line.
+/// * The `width` field holds the number of characters to underline, at least one.
+/// * The `hint` field holds a suggestion for fixing the mistake, if we have one.
+#[derive(Debug, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Snippet {
+    origin: Option<String>,
+    message: String,
+    line: usize,
+    column: usize,
+    source: String,
+    width: usize,
+    hint: Option<String>,
+}
+
+impl Snippet {
+    /// The `at` method points at `width` characters of `text`, starting from `column` on
+    /// `line`, both counting from one.  Positions past the end of the text point at its end.
+    /// We guess a hint from `message` with [`Snippet::guess_hint`].
+    pub fn at(text: &str, line: usize, column: usize, width: usize, message: &str) -> Self {
+        let lines = text.lines().collect::<Vec<&str>>();
+        let line = line.clamp(1, lines.len().max(1));
+        let source = lines.get(line - 1).copied().unwrap_or_default().to_string();
+        let length = source.chars().count();
+        let column = column.clamp(1, length + 1);
+        let width = width.clamp(1, (length + 1 - column).max(1));
+        Self {
+            origin: None,
+            message: message.to_string(),
+            line,
+            column,
+            source,
+            width,
+            hint: Self::guess_hint(message),
+        }
+    }
+
+    /// The `from_span` method points at the bytes in `span` of `text`.  A span that runs past
+    /// the end of its first line is cut off there.
+    pub fn from_span(text: &str, span: Range<usize>, message: &str) -> Self {
+        let start = Self::floor(text, span.start);
+        let end = Self::floor(text, span.end.max(start));
+        let before = &text[..start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|at| at + 1).unwrap_or(0);
+        let column = text[line_start..start].chars().count() + 1;
+        let width = text[start..end].chars().count();
+        Self::at(text, line, column, width, message)
+    }
+
+    /// The `from_toml` method points at the mistake reported by `error` in the TOML `text`.
+    /// Returns [`None`] if the error does not say where it happened.
+    pub fn from_toml(text: &str, error: &toml::de::Error) -> Option<Self> {
+        let span = error.span()?;
+        Some(Self::from_span(text, span, error.message()))
+    }
+
+    /// The `from_csv` method points at the line of the record reported by `error` in the CSV
+    /// `text`.  Returns [`None`] if the error does not say where it happened.
+    pub fn from_csv(text: &str, error: &csv::Error) -> Option<Self> {
+        let position = error.position()?;
+        let line = position.line() as usize;
+        let length = text
+            .lines()
+            .nth(line.saturating_sub(1))
+            .map(|source| source.chars().count())
+            .unwrap_or_default();
+        let message = match error.kind() {
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => format!("found {len} fields, but the header has {expected_len}"),
+            csv::ErrorKind::Utf8 { .. } => "the record is not valid UTF-8".to_string(),
+            _ => error.to_string(),
+        };
+        Some(Self::at(text, line, 1, length, &message))
+    }
+
+    /// The `from_file` method reads the TOML file at `path` and parses it, returning a snippet
+    /// naming the file if it does not parse.  Returns [`None`] if the file parses, or cannot be
+    /// read at all, which is a problem for someone other than a snippet.
+    pub fn from_file(path: &path::Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        let error = text.parse::<toml::Table>().err()?;
+        Some(Self::from_toml(&text, &error)?.with_origin(path.display().to_string()))
+    }
+
+    /// The `with_origin` method sets the name of the file the snippet comes from.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// The `with_hint` method replaces the hint with `hint`.
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// The `guess_hint` method suggests a fix for the mistake described by `message`, for the
+    /// mistakes we see most often.  Returns [`None`] when we have nothing useful to add.
+    pub fn guess_hint(message: &str) -> Option<String> {
+        let message = message.to_lowercase();
+        let hint = if message.contains("invalid string") || message.contains("unquoted") {
+            "text values need quotes, as in mode = \"line\""
+        } else if message.contains("duplicate key") {
+            "each key may appear only once in a table, so remove or rename one of them"
+        } else if message.contains("invalid table header") || message.contains("unclosed table") {
+            "table headers sit alone on their line, in brackets, as in [scroll]"
+        } else if message.contains("expected `=`") || message.contains("expected an equals") {
+            "each setting needs a key, an equals sign and a value, as in zoom = 13"
+        } else if message.contains("invalid key") {
+            "keys may hold letters, numbers, dashes and underscores, or be quoted"
+        } else if message.contains("header has") {
+            "each row needs as many fields as the header, so check for a stray comma"
+        } else if message.contains("utf-8") {
+            "save the file with UTF-8 encoding"
+        } else if message.contains("trailing comma") {
+            "remove the comma after the last value"
+        } else {
+            return None;
+        };
+        Some(hint.to_string())
+    }
+
+    /// The `floor` method moves the byte index `at` back to the nearest character boundary in
+    /// `text`, so we never slice through a character.
+    fn floor(text: &str, at: usize) -> usize {
+        let mut at = at.min(text.len());
+        while !text.is_char_boundary(at) {
+            at -= 1;
+        }
+        at
+    }
+}
+
+/// Prints the snippet over several lines, as shown in the docs for [`Snippet`].
+impl std::fmt::Display for Snippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        writeln!(f, "error: {}", self.message)?;
+        match &self.origin {
+            Some(origin) => writeln!(f, "{gutter}--> {origin}:{}:{}", self.line, self.column)?,
+            None => writeln!(f, "{gutter}--> line {}, column {}", self.line, self.column)?,
+        }
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{number} | {}", self.source)?;
+        write!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(self.column - 1),
+            "^".repeat(self.width)
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n{gutter} = help: {hint}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use crate::Arrive;
+use std::collections::BTreeMap;
+use std::path;
+
+/// The `sprite` module provides the [`Atlas`] struct, which packs the icons of point symbols into
+/// one texture, and the [`Sprite`] struct, which locates one icon in it.
+///
+/// # Drawing icons with `Atlas`
+///
+/// A point layer of schools, hydrants and bus stops wants a little picture for each kind of
+/// point, and a county has thousands of hydrants.  Drawing each from its own texture means a
+/// texture switch for every point, and rasterizing an SVG icon for each one is slower still.  A
+/// sprite atlas does the work once: we rasterize every icon up front, pack the results side by
+/// side into one large picture, and remember where each landed.  Drawing a point then comes down
+/// to copying a rectangle out of the one texture, which the card does for thousands of points
+/// in a single draw.
+///
+/// [`Atlas::build`] reads the icons from the `dir` of the `[symbols]` table (see
+/// [`crate::Symbols`]), SVG files and PNG files alike, named for the file.  An SVG icon stays
+/// sharp at any size, so we rasterize it with [`resvg`] once for each of the [`SPRITE_SCALES`],
+/// at the `size` of the table times the scale, to suit monitors of any density.  A PNG icon is
+/// resized to the same sizes, which blurs it some when it grows.  An icon that cannot be read is
+/// left out, with a warning.
+///
+/// The icons go in rows, tallest first, across an atlas [`ATLAS_WIDTH`] pixels wide, and the
+/// atlas grows as tall as it needs, up to [`ATLAS_WIDTH`] again.  [`Atlas::sprite`] finds an
+/// icon at the scale nearest the scale factor of a window, and [`Atlas::upload`] puts the whole
+/// atlas on the card.  The [`crate::Symbols`] decide which icon a point gets, from the value of
+/// a column of its [`crate::Dataset`], in [`crate::Symbols::classify`].  The [`crate::App`]
+/// builds the atlas as the config loads, every map draws the icons of its points through a
+/// [`crate::Stamp`], and [`crate::Act::ShowSymbols`] reports what the atlas holds, and how many
+/// points of each open file get each icon.
+///
+/// * The `image` field holds the pixels of the atlas.
+/// * The `sprites` field holds the place of each icon, by name and scale.
+#[derive(Debug, Clone, derive_getters::Getters)]
+pub struct Atlas {
+    image: image::RgbaImage,
+    sprites: BTreeMap<(String, u32), Sprite>,
+}
+
+impl Atlas {
+    /// The `build` method rasterizes every SVG and PNG icon in `dir` at each of the
+    /// [`SPRITE_SCALES`] of `size` pixels, and packs them into an atlas, as described in the
+    /// [`Atlas`] docs.
+    ///
+    /// Will [`crate::Blame::Io`] if `dir` cannot be read.
+    #[tracing::instrument(skip_all)]
+    pub fn build(dir: &path::Path, size: u32) -> Arrive<Self> {
+        let mut icons = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(name) = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            for scale in SPRITE_SCALES {
+                let side = size.max(1) * scale;
+                match Self::rasterize(&path, side) {
+                    Ok(Some(icon)) => icons.push(((name.clone(), scale), icon)),
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::warn!("Could not read the icon {}: {e}", path.display());
+                        break;
+                    }
+                }
+            }
+        }
+        // Tallest first keeps the rows even, which wastes less of the atlas.
+        icons.sort_by_key(|(_, icon)| std::cmp::Reverse(icon.height()));
+        let mut sprites = BTreeMap::new();
+        let (mut x, mut y, mut row) = (0, 0, 0);
+        for (key, icon) in &icons {
+            if x + icon.width() > ATLAS_WIDTH {
+                (x, y, row) = (0, y + row, 0);
+            }
+            if y + icon.height() > ATLAS_WIDTH || icon.width() > ATLAS_WIDTH {
+                tracing::warn!(
+                    "The atlas is full, leaving out the icon {} at {}x.",
+                    key.0,
+                    key.1
+                );
+                continue;
+            }
+            let sprite = Sprite {
+                x,
+                y,
+                width: icon.width(),
+                height: icon.height(),
+            };
+            sprites.insert(key.clone(), sprite);
+            x += icon.width();
+            row = row.max(icon.height());
+        }
+        let mut image = image::RgbaImage::new(ATLAS_WIDTH, (y + row).max(1));
+        for (key, icon) in &icons {
+            if let Some(sprite) = sprites.get(key) {
+                image::imageops::replace(&mut image, icon, sprite.x.into(), sprite.y.into());
+            }
+        }
+        tracing::info!(
+            "Packed {} icons into a {}x{} atlas.",
+            sprites.len(),
+            image.width(),
+            image.height()
+        );
+        Ok(Self { image, sprites })
+    }
+
+    /// The `rasterize` method draws the icon at `path` into a square of `side` pixels, keeping
+    /// its proportions, or returns [`None`] if the file is not an SVG or PNG file.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be read, [`crate::Blame::Image`] if a PNG
+    /// file cannot be decoded, and [`crate::Blame::Svg`] if an SVG file cannot be parsed.
+    fn rasterize(path: &path::Path, side: u32) -> Arrive<Option<image::RgbaImage>> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("svg") => {
+                let data = std::fs::read(path)?;
+                let tree = resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default())?;
+                let size = tree.size();
+                let fit = side as f32 / size.width().max(size.height());
+                let width = ((size.width() * fit).round() as u32).max(1);
+                let height = ((size.height() * fit).round() as u32).max(1);
+                let Some(mut pixmap) = resvg::tiny_skia::Pixmap::new(width, height) else {
+                    return Ok(None);
+                };
+                let transform = resvg::tiny_skia::Transform::from_scale(fit, fit);
+                resvg::render(&tree, transform, &mut pixmap.as_mut());
+                // The pixmap holds premultiplied alpha, and the atlas holds straight alpha.
+                let pixels = pixmap
+                    .pixels()
+                    .iter()
+                    .flat_map(|pixel| {
+                        let color = pixel.demultiply();
+                        [color.red(), color.green(), color.blue(), color.alpha()]
+                    })
+                    .collect();
+                Ok(image::RgbaImage::from_raw(width, height, pixels))
+            }
+            Some("png") => {
+                let icon = image::open(path)?.into_rgba8();
+                let fit = side as f32 / icon.width().max(icon.height()).max(1) as f32;
+                let width = ((icon.width() as f32 * fit).round() as u32).max(1);
+                let height = ((icon.height() as f32 * fit).round() as u32).max(1);
+                let filter = image::imageops::FilterType::Lanczos3;
+                Ok(Some(image::imageops::resize(&icon, width, height, filter)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The `names` method returns the names of the icons in the atlas, in order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.sprites.keys().map(|(name, _)| name.as_str()).collect();
+        names.dedup();
+        names
+    }
+
+    /// The `sprite` method returns the scale and place of the icon `name` best suited to a
+    /// window with `scale_factor`: the smallest scale that is not blurry, or the largest there
+    /// is.  Returns [`None`] if there is no icon of the name.
+    pub fn sprite(&self, name: &str, scale_factor: f64) -> Option<(u32, Sprite)> {
+        let mut scales = self
+            .sprites
+            .range((name.to_owned(), 0)..=(name.to_owned(), u32::MAX))
+            .map(|((_, scale), sprite)| (*scale, *sprite));
+        let mut last = None;
+        for (scale, sprite) in &mut scales {
+            last = Some((scale, sprite));
+            if f64::from(scale) >= scale_factor {
+                break;
+            }
+        }
+        last
+    }
+
+    /// The `icon` method cuts the icon `name` for a window with `scale_factor` out of the atlas,
+    /// as in [`Atlas::sprite`], for a renderer that wants a picture of its own.
+    pub fn icon(&self, name: &str, scale_factor: f64) -> Option<image::RgbaImage> {
+        let (_, sprite) = self.sprite(name, scale_factor)?;
+        let view =
+            image::imageops::crop_imm(&self.image, sprite.x, sprite.y, sprite.width, sprite.height);
+        Some(view.to_image())
+    }
+
+    /// The `upload` method copies the atlas into a new texture on `device`, in sRGB like the
+    /// icons it was drawn from.
+    pub fn upload(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+        let size = wgpu::Extent3d {
+            width: self.image.width(),
+            height: self.image.height(),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("sprite atlas"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            &self.image,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(self.image.width() * 4),
+                rows_per_image: Some(self.image.height()),
+            },
+            size,
+        );
+        texture
+    }
+}
+
+/// The `Sprite` struct holds the place of one icon in an [`Atlas`], in pixels from the top left.
+///
+/// * The `x` field holds the left edge of the icon.
+/// * The `y` field holds the top edge of the icon.
+/// * The `width` field holds the width of the icon.
+/// * The `height` field holds the height of the icon.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, derive_getters::Getters)]
+pub struct Sprite {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// The `ATLAS_WIDTH` constant holds the width of an [`Atlas`] in pixels, which is also the most
+/// it grows in height.  Every card that runs [`wgpu`] holds a texture this size.
+pub const ATLAS_WIDTH: u32 = 2048;
+
+/// The `SPRITE_SCALES` constant holds the scales we rasterize each icon at, for monitors of
+/// ordinary, high and very high density.
+pub const SPRITE_SCALES: [u32; 3] = [1, 2, 3];
@@ -0,0 +1,290 @@
+use crate::{Arrive, Context, Dataset, Extent, Fetch, Format, Imagery, Job, Network, GEOMETRY};
+use std::path;
+use web_time as time;
+
+/// The `stac` module provides the [`Stac`] struct, which searches a SpatioTemporal Asset
+/// Catalog for imagery over the map.
+///
+/// # Finding imagery with `Stac`
+///
+/// The planners want to see what a site looked like last summer, and the aerial photos the
+/// county buys are three years old.  NAIP flies the state every other year, and Sentinel-2
+/// passes over every few days, and both are free, but finding a scene meant a trip to a web
+/// portal, drawing the area again, and downloading a gigabyte to look at one field.  Both
+/// programs publish their scenes through a STAC catalog, a JSON API that answers a search by
+/// place, date and cloud cover with the scenes that match, each a GeoJSON feature with links
+/// to its files.  The files are cloud-optimized GeoTIFFs, or COGs, laid out so that a reader can
+/// fetch the part of the picture in view with HTTP range requests, rather than the whole file.
+///
+/// [`crate::Act::SearchImagery`] asks the catalog named in the `[imagery]` table of the config
+/// (see [`Imagery`]) for the scenes over the focused map, as an interactive job of the
+/// [`crate::Tasks`] registry:
+///
+/// 1. The search goes to the `/search` endpoint of the catalog, for the `collection` of the
+///    settings, within the ground in view, taken since `days` ago, and with no more than
+///    `cloud_cover` percent of cloud, through the `query` extension that Earth Search and the
+///    Planetary Computer both speak.  A catalog without the extension ignores the cloud cover,
+///    so we check it again on our side.
+/// 2. The catalog answers a page at a time, with a `next` link to the rest, and we follow the
+///    links until we have `limit` scenes, run out, or have read [`STAC_PAGES`] pages, reporting
+///    progress after each page.  The cap matters when the cloud check on our side turns away
+///    most of what a catalog without the extension sends, which could otherwise page through
+///    every scene of the collection.  A page that fails in a way worth retrying waits and tries
+///    again, as for a [`crate::Download`].
+/// 3. The scenes go to a GeoJSON file under [`IMAGERY_DIR`] in the data directory, newest
+///    first, with the columns `id`, `collection`, `datetime`, `cloud_cover`, `asset` and `href`,
+///    and the footprint of each scene as its geometry.  The same table opens as a layer, and the
+///    file reopens as one later.
+///
+/// The `href` column holds the address of the COG of each scene, to draw on the map, open in a
+/// GIS or download.
+/// Each collection names its files differently, so the `assets` key lists the names to look for,
+/// in order, like `visual` for the true-colour picture of Sentinel-2 or `image` for NAIP.
+/// Failing those we take the first asset of the scene that calls itself cloud-optimized, and
+/// failing that we leave the columns empty.
+///
+/// The layer that opens shows the footprints of the scenes.  [`crate::Act::ShowScene`] draws the
+/// picture of the newest scene under the middle of the focused map on every map, and of the next
+/// older one with each press after, reading the tiles of its COG in view with range requests,
+/// as described in [`crate::Cog`].
+///
+/// * The `settings` field holds the [`Imagery`] settings from the config.
+#[derive(Debug, Clone, derive_getters::Getters, derive_new::new)]
+pub struct Stac {
+    settings: Imagery,
+}
+
+impl Stac {
+    /// The `search` method finds the scenes over `extent`, reporting to `job`, and writes them
+    /// to a GeoJSON file in `dir`, as described in the [`Stac`] docs.  Returns the scenes as a
+    /// table, to open as a layer.
+    ///
+    /// Will [`crate::Blame::Http`] if the catalog cannot be reached or answers with an error,
+    /// [`crate::Blame::Timeout`] if it stops answering, once retries are spent, and
+    /// [`crate::Blame::Io`] if the file cannot be written.
+    #[tracing::instrument(skip_all)]
+    pub async fn search(&self, extent: Extent, dir: path::PathBuf, job: Job) -> Arrive<Dataset> {
+        let limit = (*self.settings.limit()).max(1);
+        let url = format!("{}/search", self.settings.catalog().trim_end_matches('/'));
+        let mut page = Some(Page::Post(url, self.body(&extent)));
+        let mut scenes = Vec::new();
+        let mut pages = 0;
+        while let Some(request) = page.take() {
+            pages += 1;
+            let body = self.fetch(&request).await?;
+            let features = body
+                .get("features")
+                .and_then(serde_json::Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            scenes.extend(features.iter().filter_map(|feature| self.scene(feature)));
+            job.set_progress((scenes.len() as f32 / limit as f32).min(1.0));
+            if scenes.len() < limit && !features.is_empty() {
+                if pages < STAC_PAGES {
+                    page = Page::next(&body);
+                } else if Page::next(&body).is_some() {
+                    tracing::warn!("Stopped after {pages} pages of scenes, with more to read.");
+                }
+            }
+        }
+        scenes.truncate(limit);
+        // RFC 3339 times in UTC sort as text.
+        scenes.sort_by(|a, b| b[2].cmp(&a[2]));
+
+        let stamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        let name = self.settings.collection().replace(['/', '\\'], "-");
+        let path = dir.join(format!("{name}-{stamp}.geojson"));
+        let columns = [
+            "id",
+            "collection",
+            "datetime",
+            "cloud_cover",
+            "asset",
+            "href",
+            GEOMETRY,
+        ]
+        .map(String::from)
+        .to_vec();
+        let count = scenes.len();
+        let table = Dataset::from_rows(path.clone(), Format::GeoJson, columns, scenes)?;
+        table.save_geojson(&path)?;
+        tracing::info!(
+            "Found {count} scenes of {} over the map, listed in {}.",
+            self.settings.collection(),
+            path.display()
+        );
+        Ok(table)
+    }
+
+    /// The `body` method returns the search for scenes over `extent`, as described in the
+    /// [`Stac`] docs.
+    pub fn body(&self, extent: &Extent) -> serde_json::Value {
+        let since = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default()
+            .saturating_sub(self.settings.days() * 86_400);
+        serde_json::json!({
+            "collections": [self.settings.collection()],
+            "bbox": [extent.west(), extent.south(), extent.east(), extent.north()],
+            "datetime": format!("{}/..", crate::timestamp(since)),
+            "limit": (*self.settings.limit()).clamp(1, PAGE_SIZE),
+            "query": { "eo:cloud_cover": { "lte": self.settings.cloud_cover() } },
+        })
+    }
+
+    /// The `fetch` method sends `page` to the catalog, retrying while the failure is worth
+    /// retrying, and returns the body of the answer.
+    ///
+    /// Will [`crate::Blame::Http`] if the catalog cannot be reached or answers with an error,
+    /// and [`crate::Blame::Timeout`] if it stops answering, once retries are spent.
+    async fn fetch(&self, page: &Page) -> Arrive<serde_json::Value> {
+        let mut attempt = 0;
+        loop {
+            let blame = match self.ask(page).await {
+                Ok(body) => return Ok(body),
+                Err(blame) => blame,
+            };
+            attempt += 1;
+            match blame.retry_after(attempt) {
+                Some(delay) => {
+                    tracing::info!("Imagery search interrupted, retrying in {delay:?}: {blame}");
+                    crate::sleep(delay).await;
+                }
+                None => return Err(blame),
+            }
+        }
+    }
+
+    /// The `ask` method sends one page of the search to the catalog.  The span is named for
+    /// [`crate::REQUEST_SPAN`], so [`crate::Latency`] times it, and the request goes through
+    /// [`Network::send`], so it shows up in the network log.
+    ///
+    /// Will [`crate::Blame::Http`] if the request fails or the catalog answers with an error.
+    #[tracing::instrument(name = "request", skip_all)]
+    async fn ask(&self, page: &Page) -> Arrive<serde_json::Value> {
+        let client = Network::global().client();
+        let request = match page {
+            Page::Get(url) => client.get(url),
+            Page::Post(url, body) => client.post(url).json(body),
+        };
+        let body = Network::global()
+            .send(request, Fetch::Requests)
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?;
+        Ok(body)
+    }
+
+    /// The `scene` method reads a row of the table from one `feature` of an answer, as described
+    /// in the [`Stac`] docs, or returns [`None`] if it has no footprint or too much cloud.
+    fn scene(&self, feature: &serde_json::Value) -> Option<Vec<String>> {
+        let geometry = feature.get("geometry").filter(|value| !value.is_null())?;
+        let properties = feature.get("properties");
+        let text = |value: Option<&serde_json::Value>| {
+            value
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let cloud = properties
+            .and_then(|properties| properties.get("eo:cloud_cover"))
+            .and_then(serde_json::Value::as_f64);
+        if cloud.is_some_and(|cloud| cloud > *self.settings.cloud_cover()) {
+            return None;
+        }
+        let (asset, href) = self.asset(feature).unwrap_or_default();
+        Some(vec![
+            text(feature.get("id")),
+            text(feature.get("collection")),
+            text(properties.and_then(|properties| properties.get("datetime"))),
+            cloud.map(|cloud| cloud.to_string()).unwrap_or_default(),
+            asset,
+            href,
+            geometry.to_string(),
+        ])
+    }
+
+    /// The `asset` method returns the name and address of the COG to show for `feature`, as
+    /// described in the [`Stac`] docs, or [`None`] if it has none.
+    fn asset(&self, feature: &serde_json::Value) -> Option<(String, String)> {
+        let assets = feature.get("assets")?.as_object()?;
+        let href = |asset: &serde_json::Value| {
+            asset
+                .get("href")
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+        };
+        let named = self.settings.assets().iter().find_map(|name| {
+            let asset = assets.get(name)?;
+            Some((name.clone(), href(asset)?))
+        });
+        named.or_else(|| {
+            assets.iter().find_map(|(name, asset)| {
+                asset
+                    .get("type")
+                    .and_then(serde_json::Value::as_str)
+                    .filter(|kind| kind.contains("cloud-optimized"))?;
+                Some((name.clone(), href(asset)?))
+            })
+        })
+    }
+}
+
+/// The `Page` enum holds the request for one page of a search.
+///
+/// * The `Get` variant holds the address of a page to fetch.
+/// * The `Post` variant holds the address of a page to post to, and the search to post.
+#[derive(Debug, Clone, PartialEq)]
+enum Page {
+    /// The `Get` variant indicates a page fetched with a GET request.
+    Get(String),
+    /// The `Post` variant indicates a page fetched with a POST request.
+    Post(String, serde_json::Value),
+}
+
+impl Page {
+    /// The `next` method reads the `next` link of the `body` of an answer, or returns [`None`]
+    /// on the last page.  A catalog that pages a POST search sends the search to post again with
+    /// the link, with the token for the next page in it.
+    fn next(body: &serde_json::Value) -> Option<Self> {
+        let link = body
+            .get("links")?
+            .as_array()?
+            .iter()
+            .find(|link| link.get("rel").and_then(serde_json::Value::as_str) == Some("next"))?;
+        let href = link.get("href")?.as_str()?.to_string();
+        let method = link.get("method").and_then(serde_json::Value::as_str);
+        match (method, link.get("body")) {
+            (Some(method), Some(body)) if method.eq_ignore_ascii_case("POST") => {
+                Some(Self::Post(href, body.clone()))
+            }
+            _ => Some(Self::Get(href)),
+        }
+    }
+}
+
+/// The `IMAGERY_DIR` constant holds the name of the directory under [`crate::Home::data`] where
+/// a [`Stac`] search writes the scenes it finds.
+pub const IMAGERY_DIR: &str = "imagery";
+
+/// The `STAC_CATALOG` constant holds the address of the catalog to search when the `[imagery]`
+/// table does not name one, the Earth Search catalog of Element 84, which needs no key.
+pub const STAC_CATALOG: &str = "https://earth-search.aws.element84.com/v1";
+
+/// The `CLOUD_COVER` constant holds the most cloud, in percent, of a scene a search keeps, when
+/// the `[imagery]` table does not say otherwise.
+pub const CLOUD_COVER: f64 = 20.0;
+
+/// The `STAC_PAGES` constant holds the most pages of scenes a [`Stac`] search reads before it
+/// stops, with however many scenes it has.
+pub const STAC_PAGES: usize = 20;
+
+/// The `PAGE_SIZE` constant holds the most scenes to ask for in one page, which public catalogs
+/// cap at about this many.
+const PAGE_SIZE: usize = 100;
@@ -0,0 +1,336 @@
+use crate::{Atlas, Figure, Viewport};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use winit::dpi;
+
+/// The `stamp` module provides the [`Stamp`] struct, a pass that draws the icons of point
+/// symbols over the map, out of the one texture of an [`Atlas`].
+///
+/// # Drawing icons with `Stamp`
+///
+/// The [`crate::Symbols`] give a point of an open data file the name of an icon, and the
+/// [`Atlas`] holds the icon, packed with the rest into one picture.  A `Stamp` puts the picture
+/// on the card once, with [`Atlas::upload`], and each time a map draws, [`Stamp::lay`] draws a
+/// square for every point in view, each reading its icon out of the atlas, in a single draw.  A
+/// point with an icon goes without the dot of the [`crate::Brush`], so the icon stands alone.
+///
+/// The squares keep the size the icon has in the atlas, picked for the scale of the window by
+/// [`Atlas::sprite`], and sit centered on the point, snapped to whole pixels so the icon comes
+/// out as sharp as it went in.  The [`crate::Viewport`] of the map places each point on the
+/// window, and a point more than an icon away from the window is left out before it reaches the
+/// card.  Every map shares the one stamp of the [`crate::App`], so the atlas is on the card
+/// once, whatever the number of windows.  A render pipeline fits one texture format, and a map
+/// window draws in the format of its surface while a snapshot draws in
+/// [`crate::OFFSCREEN_FORMAT`], so the stamp builds a pipeline for each format as it first
+/// meets it.
+///
+/// * The `atlas` field holds the atlas, for the places of the icons.
+/// * The `module` field holds the shader of the pass, from [`STAMP_SHADER`].
+/// * The `layout` field holds the layout of the pipelines.
+/// * The `screen` field holds the uniform buffer of the sizes of the target and the atlas.
+/// * The `bind_group` field binds the atlas, its sampler and `screen` for the pass.
+/// * The `pipelines` field holds the render pipeline for each format drawn into so far.
+/// * The `device` field holds the device shared by every window.
+/// * The `queue` field holds the queue shared by every window.
+#[derive(Debug)]
+pub struct Stamp {
+    atlas: Atlas,
+    module: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    screen: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipelines: Mutex<HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>>,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+impl Stamp {
+    /// The `new` method puts `atlas` on the card and prepares the pass drawing its icons.
+    pub fn new(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>, atlas: &Atlas) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("stamp"),
+            source: wgpu::ShaderSource::Wgsl(STAMP_SHADER.into()),
+        });
+        let group = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("stamp"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("stamp"),
+            bind_group_layouts: &[&group],
+            push_constant_ranges: &[],
+        });
+        let texture = atlas.upload(&device, &queue);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // The icons keep their size in the atlas, so we read whole pixels without blending.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("stamp"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let screen = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("stamp screen"),
+            size: SCREEN_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("stamp"),
+            layout: &group,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: screen.as_entire_binding(),
+                },
+            ],
+        });
+        Self {
+            atlas: atlas.clone(),
+            module,
+            layout,
+            screen,
+            bind_group,
+            pipelines: Mutex::new(HashMap::new()),
+            device,
+            queue,
+        }
+    }
+
+    /// The `pipeline` method builds the render pipeline of the pass for a target in `format`,
+    /// blending the icons over what the target holds.
+    fn pipeline(&self, format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+        self.device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("stamp"),
+                layout: Some(&self.layout),
+                vertex: wgpu::VertexState {
+                    module: &self.module,
+                    entry_point: "vs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: INSTANCE_SIZE,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &INSTANCE_ATTRIBUTES,
+                    }],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.module,
+                    entry_point: "fs_main",
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            })
+    }
+
+    /// The `lay` method draws the icons of the points of `figures` onto `target`, a texture of
+    /// `size` in `format`, placed by `viewport`, at the icon scale nearest `scale`, as described
+    /// in the [`Stamp`] docs.  What the target holds stays under the icons.
+    pub fn lay(
+        &self,
+        target: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        size: dpi::PhysicalSize<u32>,
+        viewport: &Viewport,
+        figures: &[Figure],
+        scale: f64,
+    ) {
+        let (width, height) = (size.width, size.height);
+        let mut instances = Vec::<f32>::new();
+        for feature in figures.iter().flat_map(|figure| figure.features().iter()) {
+            let Some((_, sprite)) = feature
+                .icon()
+                .as_deref()
+                .and_then(|name| self.atlas.sprite(name, scale))
+            else {
+                continue;
+            };
+            let margin = f64::from(*sprite.width().max(sprite.height()));
+            for (x, y) in feature.shape().points() {
+                let (x, y) = viewport.locate(*x, *y);
+                let across = -margin..=f64::from(width) + margin;
+                let down = -margin..=f64::from(height) + margin;
+                if !(across.contains(&x) && down.contains(&y)) {
+                    continue;
+                }
+                instances.extend([
+                    x as f32,
+                    y as f32,
+                    *sprite.x() as f32,
+                    *sprite.y() as f32,
+                    *sprite.width() as f32,
+                    *sprite.height() as f32,
+                ]);
+            }
+        }
+        if instances.is_empty() {
+            return;
+        }
+        let mut pipelines = match self.pipelines.lock() {
+            Ok(pipelines) => pipelines,
+            Err(e) => {
+                tracing::warn!("Stamp lock poisoned: {e}");
+                return;
+            }
+        };
+        let pipeline = pipelines
+            .entry(format)
+            .or_insert_with(|| self.pipeline(format));
+        let screen = [
+            width as f32,
+            height as f32,
+            self.atlas.image().width() as f32,
+            self.atlas.image().height() as f32,
+        ];
+        let bytes: Vec<u8> = screen
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        self.queue.write_buffer(&self.screen, 0, &bytes);
+        let bytes: Vec<u8> = instances
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("stamp instances"),
+            size: bytes.len() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&buffer, 0, &bytes);
+        let count = (instances.len() as u64 * 4 / INSTANCE_SIZE) as u32;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("stamp"),
+            });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("stamp"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.set_vertex_buffer(0, buffer.slice(..));
+            // Two triangles for each icon, see the shader.
+            pass.draw(0..6, 0..count);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
+
+/// The `SCREEN_SIZE` constant holds the size in bytes of the `Screen` struct of
+/// [`STAMP_SHADER`], four floats.
+const SCREEN_SIZE: u64 = 16;
+
+/// The `INSTANCE_SIZE` constant holds the size in bytes of the `Instance` struct of
+/// [`STAMP_SHADER`], six floats.
+const INSTANCE_SIZE: u64 = 24;
+
+/// The `INSTANCE_ATTRIBUTES` constant holds the layout of the `Instance` struct of
+/// [`STAMP_SHADER`], the center of the icon and its rectangle in the atlas.
+const INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
+
+/// The `STAMP_SHADER` constant holds the WGSL of the [`Stamp`] pass.  Each icon is an instance
+/// holding the center of the icon on the target and its rectangle in the atlas, in pixels, and
+/// the vertex shader spreads the six corners of two triangles over the rectangle.
+pub const STAMP_SHADER: &str = r#"
+struct Screen {
+    target: vec2<f32>,
+    atlas: vec2<f32>,
+};
+
+struct Instance {
+    @location(0) center: vec2<f32>,
+    @location(1) rect: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0) var atlas: texture_2d<f32>;
+@group(0) @binding(1) var atlas_sampler: sampler;
+@group(0) @binding(2) var<uniform> screen: Screen;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, icon: Instance) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[index];
+    let origin = floor(icon.center - icon.rect.zw * 0.5 + 0.5);
+    let pixel = origin + corner * icon.rect.zw;
+    var out: VertexOutput;
+    let clip = pixel / screen.target * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+    out.position = vec4<f32>(clip, 0.0, 1.0);
+    out.uv = (icon.rect.xy + corner * icon.rect.zw) / screen.atlas;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(atlas, atlas_sampler, in.uv);
+}
+"#;
@@ -0,0 +1,632 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Context;
+use crate::{Arrive, Cache, Extent, Query};
+#[cfg(not(target_arch = "wasm32"))]
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
+use std::path;
+
+/// The `store` module provides the [`Store`] struct, which keeps the small things the app
+/// remembers between runs in one SQLite database.
+///
+/// # Remembering things with `Store`
+///
+/// Every feature that wants to remember something has been tempted to drop its own little file
+/// in the data directory, as [`crate::Reporter`] does with [`crate::TALLY_FILE`].  That works for
+/// one file, but bookmarks, saved queries, recent files and cache indexes would make four more,
+/// each with its own format, its own half-written state after a crash, and its own plan for the
+/// day the format changes.  Instead, they share [`STORE_FILE`] in the data directory, a SQLite
+/// database opened through the [`rusqlite`] crate, which writes each change whole or not at all.
+///
+/// The database holds four tables:
+///
+/// * `bookmarks` holds views of the map, saved with [`crate::Act::SaveBookmark`] under the
+///   place described by [`crate::Synopsis`], and visited in turn with
+///   [`crate::Act::NextBookmark`].  Each bookmark has a number of its own, so a second bookmark
+///   at the same place sits beside the first rather than taking its place.
+/// * `queries` holds the parcel lookups by address or by map and tax lot number that found a
+///   parcel, saved with [`Store::save_query`], and asked again in turn with
+///   [`crate::Act::NextLookup`].  Asking the same thing twice keeps one query.
+/// * `recent_files` holds the data files opened most recently, up to [`RECENT_FILES`] of them,
+///   as listed by [`crate::Act::ShowRecentFiles`].
+/// * `cache_index` holds the size of each entry of a [`Cache`] and when it was last used, so
+///   that [`crate::prune`] can trim the cache by the entries used longest ago.  A file on disk
+///   only knows when it was written, and a tile read from the cache every day is as old on disk
+///   as one never read again.  Clearing a cache clears its index with it.
+///
+/// Bookmarks and recent files kept as JSON in the data directory, in [`BOOKMARKS_FILE`] and
+/// [`RECENT_FILE`], move into the database with [`Store::import`] when the app opens the store.
+/// Each file read is renamed with [`IMPORTED_EXTENSION`] on the end, so it is not read twice,
+/// and is still there if the user wants it back.
+///
+/// ## Migrations
+///
+/// The schema grows by appending to [`STORE_MIGRATIONS`], never by editing an entry.  SQLite
+/// keeps a `user_version` number in the file, starting at zero, and [`Store::migrate`] runs each
+/// migration past that number in a transaction, bumping the number as it goes.  An old file
+/// catches up on open, and a file from a newer build, with a number past the end of the list, is
+/// left alone.  The same idea as [`crate::migrate`] for the config, but in a database.
+///
+/// The browser build has no SQLite, so there [`Store::open`] fails with
+/// [`crate::Excuse::Unavailable`] and the app carries on without remembering anything.
+///
+/// * The `connection` field holds the [`rusqlite::Connection`] to the database.
+/// * The `path` field holds the path to the database file.
+pub struct Store {
+    #[cfg(not(target_arch = "wasm32"))]
+    connection: rusqlite::Connection,
+    path: path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Store {
+    /// The `open` method opens the database at `path`, creating it and its directory if need
+    /// be, and brings the schema up to date with [`Store::migrate`].
+    ///
+    /// Will [`crate::Blame::Io`] if the directory cannot be created, and
+    /// [`crate::Blame::Sqlite`] if the file cannot be opened or migrated.
+    #[tracing::instrument]
+    pub fn open(path: &path::Path) -> Arrive<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+        let connection = rusqlite::Connection::open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        let mut store = Self {
+            connection,
+            path: path.to_path_buf(),
+        };
+        let version = store.migrate()?;
+        tracing::trace!("Store at version {version} in {}.", path.display());
+        Ok(store)
+    }
+
+    /// The `version` method returns the number of migrations the database has been through.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the database cannot be read.
+    pub fn version(&self) -> Arrive<usize> {
+        let version = self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get::<_, usize>(0))?;
+        Ok(version)
+    }
+
+    /// The `migrate` method runs each of [`STORE_MIGRATIONS`] the database has not been through,
+    /// each in its own transaction, and returns the version reached.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if a migration fails, leaving the database at the version
+    /// before it.
+    #[tracing::instrument(skip(self))]
+    pub fn migrate(&mut self) -> Arrive<usize> {
+        let mut version = self.version()?;
+        if version > STORE_MIGRATIONS.len() {
+            tracing::warn!("The store is at version {version}, from a newer build.");
+        }
+        while let Some(migration) = STORE_MIGRATIONS.get(version) {
+            let transaction = self.connection.transaction()?;
+            transaction
+                .execute_batch(migration)
+                .with_context(|| format!("migrating the store to version {}", version + 1))?;
+            // The pragma takes no parameters, so the number goes into the text.
+            transaction.execute_batch(&format!("PRAGMA user_version = {}", version + 1))?;
+            transaction.commit()?;
+            version += 1;
+            tracing::info!("Migrated the store to version {version}.");
+        }
+        Ok(version)
+    }
+
+    /// The `save_bookmark` method saves `extent` under `name`, beside any bookmark of the same
+    /// name, as the last to be visited.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the bookmark cannot be written.
+    pub fn save_bookmark(&self, name: &str, extent: &Extent) -> Arrive<()> {
+        self.connection.execute(
+            "INSERT INTO bookmarks (name, west, south, east, north, visited)
+             VALUES (?1, ?2, ?3, ?4, ?5, (SELECT IFNULL(MAX(visited), 0) + 1 FROM bookmarks))",
+            rusqlite::params![
+                name,
+                extent.west(),
+                extent.south(),
+                extent.east(),
+                extent.north()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The `visit` method returns the bookmark visited longest ago, and moves it to the back of
+    /// the line, so that calling it again and again goes round every bookmark in turn.  Returns
+    /// [`None`] if there are no bookmarks.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the bookmarks cannot be read or written.
+    pub fn visit(&self) -> Arrive<Option<(String, Extent)>> {
+        let next = self
+            .connection
+            .query_row(
+                "SELECT id, name, west, south, east, north FROM bookmarks
+                 ORDER BY visited, id LIMIT 1",
+                [],
+                |row| {
+                    let extent = Extent::new(row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?);
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, extent))
+                },
+            )
+            .optional()?;
+        let Some((id, name, extent)) = next else {
+            return Ok(None);
+        };
+        self.connection.execute(
+            "UPDATE bookmarks SET visited = (SELECT MAX(visited) + 1 FROM bookmarks)
+             WHERE id = ?1",
+            [id],
+        )?;
+        Ok(Some((name, extent)))
+    }
+
+    /// The `save_query` method saves `query` as the last to be asked again, replacing the same
+    /// query saved before.  A lookup by a press on the map is not worth asking again, and is
+    /// not saved.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the query cannot be written.
+    pub fn save_query(&self, query: &Query) -> Arrive<()> {
+        let (kind, text) = match query {
+            Query::Address(address) => (ADDRESS_QUERY, address),
+            Query::Taxlot(taxlot) => (TAXLOT_QUERY, taxlot),
+            Query::Point(..) => return Ok(()),
+        };
+        self.connection.execute(
+            "INSERT OR REPLACE INTO queries (kind, text, visited)
+             VALUES (?1, ?2, (SELECT IFNULL(MAX(visited), 0) + 1 FROM queries))",
+            [kind, text.trim()],
+        )?;
+        Ok(())
+    }
+
+    /// The `next_query` method returns the saved query asked longest ago, and moves it to the
+    /// back of the line, as [`Store::visit`] does for bookmarks.  Returns [`None`] if there are
+    /// no saved queries.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the queries cannot be read or written.
+    pub fn next_query(&self) -> Arrive<Option<Query>> {
+        let next = self
+            .connection
+            .query_row(
+                "SELECT kind, text FROM queries ORDER BY visited LIMIT 1",
+                [],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        let Some((kind, text)) = next else {
+            return Ok(None);
+        };
+        self.connection.execute(
+            "UPDATE queries SET visited = (SELECT MAX(visited) + 1 FROM queries)
+             WHERE kind = ?1 AND text = ?2",
+            [&kind, &text],
+        )?;
+        match kind.as_str() {
+            TAXLOT_QUERY => Ok(Some(Query::Taxlot(text))),
+            _ => Ok(Some(Query::Address(text))),
+        }
+    }
+
+    /// The `index` method records that the entry `key` of `cache`, holding `bytes` bytes, was
+    /// used just now.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the index cannot be written.
+    pub fn index(&self, cache: Cache, key: &str, bytes: u64) -> Arrive<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO cache_index (cache, key, bytes, touched)
+             VALUES (?1, ?2, ?3, strftime('%s', 'now'))",
+            rusqlite::params![cache.to_string(), key, bytes],
+        )?;
+        Ok(())
+    }
+
+    /// The `touched` method returns when each entry of `cache` in the index was last used, in
+    /// seconds since the Unix epoch, by key.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the index cannot be read.
+    pub fn touched(&self, cache: Cache) -> Arrive<HashMap<String, u64>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT key, touched FROM cache_index WHERE cache = ?1")?;
+        let touched = statement
+            .query_map([cache.to_string()], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<String, u64>, rusqlite::Error>>()?;
+        Ok(touched)
+    }
+
+    /// The `unindex` method drops the entries `keys` of `cache` from the index, once they are
+    /// gone from the cache.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the index cannot be written.
+    pub fn unindex(&mut self, cache: Cache, keys: &[String]) -> Arrive<()> {
+        let transaction = self.connection.transaction()?;
+        for key in keys {
+            transaction.execute(
+                "DELETE FROM cache_index WHERE cache = ?1 AND key = ?2",
+                [cache.to_string().as_str(), key],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The `forget` method drops the whole index of `cache`, when the cache is cleared.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the index cannot be written.
+    pub fn forget(&self, cache: Cache) -> Arrive<()> {
+        self.connection.execute(
+            "DELETE FROM cache_index WHERE cache = ?1",
+            [cache.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// The `copy` method writes a whole copy of the database to a new file at `path`, with
+    /// SQLite's `VACUUM INTO`, which reads the database in a single transaction, so the copy
+    /// never catches a change half made.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the copy cannot be written, as when `path` exists.
+    pub fn copy(&self, path: &path::Path) -> Arrive<()> {
+        self.connection
+            .execute("VACUUM INTO ?1", [path.to_string_lossy()])
+            .with_context(|| format!("copying the store to {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The `remember` method puts the file at `path` at the top of the recent files, and forgets
+    /// the oldest past [`RECENT_FILES`].
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the recent files cannot be written.
+    pub fn remember(&self, path: &path::Path) -> Arrive<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO recent_files (path, opened)
+             VALUES (?1, (SELECT IFNULL(MAX(opened), 0) + 1 FROM recent_files))",
+            [path.to_string_lossy()],
+        )?;
+        self.connection.execute(
+            "DELETE FROM recent_files WHERE path NOT IN
+             (SELECT path FROM recent_files ORDER BY opened DESC LIMIT ?1)",
+            [RECENT_FILES],
+        )?;
+        Ok(())
+    }
+
+    /// The `recent` method returns the recent files, the most recently opened first.
+    ///
+    /// Will [`crate::Blame::Sqlite`] if the recent files cannot be read.
+    pub fn recent(&self) -> Arrive<Vec<path::PathBuf>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT path FROM recent_files ORDER BY opened DESC")?;
+        let recent = statement
+            .query_map([], |row| row.get::<_, String>(0).map(path::PathBuf::from))?
+            .collect::<Result<Vec<path::PathBuf>, rusqlite::Error>>()?;
+        Ok(recent)
+    }
+
+    /// The `import` method moves the bookmarks and recent files kept as JSON under `dir` into
+    /// the database, as described in the [`Store`] docs, and returns the number of each moved.
+    /// [`BOOKMARKS_FILE`] holds a list of bookmarks, each with a `name` and the `west`, `south`,
+    /// `east` and `north` edges of its view, visited in the order listed.  [`RECENT_FILE`] holds
+    /// a list of paths, the most recently opened first.  A missing file has nothing to move.
+    ///
+    /// Will [`crate::Blame::Io`] if a file cannot be read or renamed, [`crate::Blame::Json`] if
+    /// one is malformed, and [`crate::Blame::Sqlite`] if the database cannot be written, which
+    /// leaves the file where it was.
+    #[tracing::instrument(skip(self))]
+    pub fn import(&mut self, dir: &path::Path) -> Arrive<(usize, usize)> {
+        let marks = Self::imported::<Vec<Bookmark>>(&dir.join(BOOKMARKS_FILE))?;
+        let recent = Self::imported::<Vec<path::PathBuf>>(&dir.join(RECENT_FILE))?;
+        let counts = (
+            marks.as_ref().map_or(0, Vec::len),
+            recent.as_ref().map_or(0, Vec::len),
+        );
+        let transaction = self.connection.transaction()?;
+        for mark in marks.iter().flatten() {
+            transaction.execute(
+                "INSERT INTO bookmarks (name, west, south, east, north, visited)
+                 VALUES (?1, ?2, ?3, ?4, ?5, (SELECT IFNULL(MAX(visited), 0) + 1 FROM bookmarks))",
+                rusqlite::params![mark.name, mark.west, mark.south, mark.east, mark.north],
+            )?;
+        }
+        // The oldest goes in first, so the newest ends up on top.
+        for path in recent.iter().flatten().rev() {
+            transaction.execute(
+                "INSERT OR REPLACE INTO recent_files (path, opened)
+                 VALUES (?1, (SELECT IFNULL(MAX(opened), 0) + 1 FROM recent_files))",
+                [path.to_string_lossy()],
+            )?;
+        }
+        transaction.execute(
+            "DELETE FROM recent_files WHERE path NOT IN
+             (SELECT path FROM recent_files ORDER BY opened DESC LIMIT ?1)",
+            [RECENT_FILES],
+        )?;
+        transaction.commit()?;
+        for (file, read) in [
+            (BOOKMARKS_FILE, marks.is_some()),
+            (RECENT_FILE, recent.is_some()),
+        ] {
+            if read {
+                let from = dir.join(file);
+                let to = dir.join(format!("{file}.{IMPORTED_EXTENSION}"));
+                std::fs::rename(&from, &to)
+                    .with_context(|| format!("renaming {}", from.display()))?;
+            }
+        }
+        if counts != (0, 0) {
+            tracing::info!(
+                "Moved {} bookmarks and {} recent files into the store.",
+                counts.0,
+                counts.1
+            );
+        }
+        Ok(counts)
+    }
+
+    /// The `imported` method reads the JSON file at `path` for [`Store::import`], or returns
+    /// [`None`] if there is no such file.
+    fn imported<T: serde::de::DeserializeOwned>(path: &path::Path) -> Arrive<Option<T>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        let value =
+            serde_json::from_str(&text).with_context(|| format!("reading {}", path.display()))?;
+        Ok(Some(value))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Store {
+    /// The `open` method fails with [`crate::Excuse::Unavailable`], since the browser has no
+    /// SQLite, as described in the [`Store`] docs.
+    pub fn open(_path: &path::Path) -> Arrive<Self> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `save_bookmark` method fails with [`crate::Excuse::Unavailable`], as
+    /// [`Store::open`] does.
+    pub fn save_bookmark(&self, _name: &str, _extent: &Extent) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `visit` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn visit(&self) -> Arrive<Option<(String, Extent)>> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `save_query` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`]
+    /// does.
+    pub fn save_query(&self, _query: &Query) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `next_query` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`]
+    /// does.
+    pub fn next_query(&self) -> Arrive<Option<Query>> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `index` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn index(&self, _cache: Cache, _key: &str, _bytes: u64) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `touched` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn touched(&self, _cache: Cache) -> Arrive<HashMap<String, u64>> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `unindex` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn unindex(&mut self, _cache: Cache, _keys: &[String]) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `forget` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn forget(&self, _cache: Cache) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `copy` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn copy(&self, _path: &path::Path) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `remember` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`]
+    /// does.
+    pub fn remember(&self, _path: &path::Path) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `recent` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn recent(&self) -> Arrive<Vec<path::PathBuf>> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `import` method fails with [`crate::Excuse::Unavailable`], as [`Store::open`] does.
+    pub fn import(&mut self, _dir: &path::Path) -> Arrive<(usize, usize)> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+}
+
+impl Store {
+    /// The `path` method returns the path to the database file.
+    pub fn path(&self) -> &path::Path {
+        &self.path
+    }
+}
+
+impl std::fmt::Debug for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Store")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `STORE_FILE` constant holds the name of the SQLite database under [`crate::Home::data`]
+/// where the [`Store`] keeps what the app remembers between runs.
+pub const STORE_FILE: &str = "store.sqlite3";
+
+/// The `RECENT_FILES` constant holds the number of recently opened files the [`Store`] keeps.
+pub const RECENT_FILES: usize = 20;
+
+/// The `BOOKMARKS_FILE` constant holds the name of the JSON file of bookmarks under
+/// [`crate::Home::data`] that [`Store::import`] moves into the [`Store`].
+pub const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// The `RECENT_FILE` constant holds the name of the JSON file of recent files under
+/// [`crate::Home::data`] that [`Store::import`] moves into the [`Store`].
+pub const RECENT_FILE: &str = "recent.json";
+
+/// The `ADDRESS_QUERY` constant holds the kind of a saved [`Query::Address`] in the `queries`
+/// table of the [`Store`].
+pub const ADDRESS_QUERY: &str = "address";
+
+/// The `TAXLOT_QUERY` constant holds the kind of a saved [`Query::Taxlot`] in the `queries`
+/// table of the [`Store`].
+pub const TAXLOT_QUERY: &str = "taxlot";
+
+/// The `IMPORTED_EXTENSION` constant holds the extension [`Store::import`] adds to a JSON file
+/// once it has moved what the file holds into the [`Store`].
+pub const IMPORTED_EXTENSION: &str = "imported";
+
+/// The `Bookmark` struct holds one bookmark of [`BOOKMARKS_FILE`], as [`Store::import`] reads
+/// it.
+///
+/// * The `name` field holds the name of the bookmark.
+/// * The `west`, `south`, `east` and `north` fields hold the edges of its view, in degrees.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(serde::Deserialize)]
+struct Bookmark {
+    name: String,
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+}
+
+/// The `STORE_MIGRATIONS` constant holds the SQL that brings the [`Store`] from each version to
+/// the next, as described in the [`Store`] docs.  Only ever append to it.
+pub const STORE_MIGRATIONS: &[&str] = &[
+    // Version 1: bookmarks, saved queries, recent files and the cache index.
+    "CREATE TABLE bookmarks (
+        name TEXT PRIMARY KEY NOT NULL,
+        west REAL NOT NULL,
+        south REAL NOT NULL,
+        east REAL NOT NULL,
+        north REAL NOT NULL,
+        visited INTEGER NOT NULL
+    );
+    CREATE TABLE queries (
+        name TEXT PRIMARY KEY NOT NULL,
+        text TEXT NOT NULL,
+        saved INTEGER NOT NULL
+    );
+    CREATE TABLE recent_files (
+        path TEXT PRIMARY KEY NOT NULL,
+        opened INTEGER NOT NULL
+    );
+    CREATE TABLE cache_index (
+        cache TEXT NOT NULL,
+        key TEXT NOT NULL,
+        bytes INTEGER NOT NULL,
+        touched INTEGER NOT NULL,
+        PRIMARY KEY (cache, key)
+    );",
+    // Version 2: bookmarks by number rather than by name, so two at the same place both keep.
+    // Nothing saved queries or filled the cache index, so those tables go.
+    "CREATE TABLE numbered_bookmarks (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        west REAL NOT NULL,
+        south REAL NOT NULL,
+        east REAL NOT NULL,
+        north REAL NOT NULL,
+        visited INTEGER NOT NULL
+    );
+    INSERT INTO numbered_bookmarks (name, west, south, east, north, visited)
+        SELECT name, west, south, east, north, visited FROM bookmarks ORDER BY visited;
+    DROP TABLE bookmarks;
+    ALTER TABLE numbered_bookmarks RENAME TO bookmarks;
+    DROP TABLE queries;
+    DROP TABLE cache_index;",
+    // Version 3: saved parcel lookups, asked again in turn, and the cache index, by last use.
+    "CREATE TABLE queries (
+        kind TEXT NOT NULL,
+        text TEXT NOT NULL,
+        visited INTEGER NOT NULL,
+        PRIMARY KEY (kind, text)
+    );
+    CREATE TABLE cache_index (
+        cache TEXT NOT NULL,
+        key TEXT NOT NULL,
+        bytes INTEGER NOT NULL,
+        touched INTEGER NOT NULL,
+        PRIMARY KEY (cache, key)
+    );",
+];
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn store(name: &str) -> Store {
+        let path = std::env::temp_dir().join(format!("bea_egui_store_{name}.sqlite3"));
+        std::fs::remove_file(&path).ok();
+        Store::open(&path).expect("store")
+    }
+
+    #[test]
+    fn saved_queries_take_turns() {
+        let store = store("queries");
+        assert_eq!(store.version().expect("version"), STORE_MIGRATIONS.len());
+        assert_eq!(store.next_query().expect("none"), None);
+        let taxlot = Query::Taxlot("360517BC01100".to_string());
+        let address = Query::Address("101 NW A St".to_string());
+        store.save_query(&taxlot).expect("taxlot");
+        store.save_query(&address).expect("address");
+        store
+            .save_query(&Query::Point(-123.3, 42.4))
+            .expect("point");
+        store.save_query(&taxlot).expect("again");
+        assert_eq!(store.next_query().expect("first"), Some(address.clone()));
+        assert_eq!(store.next_query().expect("second"), Some(taxlot));
+        assert_eq!(store.next_query().expect("third"), Some(address));
+        std::fs::remove_file(store.path()).ok();
+    }
+
+    #[test]
+    fn pruning_keeps_tiles_in_use() {
+        let mut store = store("index");
+        let dir = std::env::temp_dir().join("bea_egui_store_tiles");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("3/4")).expect("tiles");
+        let written = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1000);
+        for name in ["old", "new", "used"] {
+            let path = dir.join("3/4").join(name);
+            std::fs::write(&path, [0u8; 100]).expect("tile");
+            let file = std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .expect("open");
+            file.set_modified(written).expect("modified");
+        }
+        store.index(Cache::Tiles, "3/4/used", 100).expect("index");
+        store.index(Cache::Tiles, "3/4/gone", 100).expect("index");
+        crate::prune(&dir, 150, Some(&mut store)).expect("prune");
+        assert!(dir.join("3/4/used").is_file());
+        let touched = store.touched(Cache::Tiles).expect("touched");
+        assert_eq!(touched.keys().collect::<Vec<&String>>(), vec!["3/4/used"]);
+        store.forget(Cache::Tiles).expect("forget");
+        assert!(store.touched(Cache::Tiles).expect("empty").is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::remove_file(store.path()).ok();
+    }
+}
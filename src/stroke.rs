@@ -0,0 +1,384 @@
+use crate::{Blame, Excuse};
+use winit::{event, keyboard};
+
+/// The `stroke` module provides the [`Stroke`] struct, which describes a single key press along
+/// with any modifier keys held down at the time.
+///
+/// # Parsing key bindings with `Stroke`
+///
+/// Originally, the values in `config.toml` were single characters or named keys, matched directly
+/// against the logical key of the incoming [`winit::event::KeyEvent`].  Single character bindings
+/// clash with any text field, where typing the letter `n` into a search box would open a new
+/// window.  The usual fix is to require a modifier, so that `Ctrl+N` opens a window
+/// and `n` is just a letter.
+///
+/// The `Stroke` struct holds the [`keyboard::ModifiersState`] and the name of the key.  We parse
+/// strokes from strings of the form `Ctrl+Shift+N`, where the modifiers and the key are separated
+/// by a `+`.  The recognized modifier names are:
+///
+/// * `Ctrl` or `Control`
+/// * `Shift`
+/// * `Alt` or `Option`
+/// * `Super`, `Cmd`, `Meta` or `Logo`
+///
+/// Modifier names are not case sensitive.  The key is either a single character, or the name of a
+/// [`keyboard::NamedKey`] variant like `Escape` or `F1`, or the name of a mouse button as
+/// described in [`Stroke::from_mouse`].  Single characters are stored in lower
+/// case, because holding `Shift` changes the logical key from `n` to `N`, and we want `Ctrl+Shift+N`
+/// and `Ctrl+Shift+n` to mean the same thing.  To bind the `+` key itself, write `Ctrl++`.
+///
+/// ## Physical keys
+///
+/// Logical keys follow the keyboard layout, so on an AZERTY keyboard the key that types `q` on a
+/// US keyboard types `a`.  Usually that is what we want, because `Ctrl+N` should mean "new" no
+/// matter where the `N` is.  For bindings that are about position, like a cluster of keys for
+/// panning, prefix the key with `physical:` and name a [`keyboard::KeyCode`] variant, as in
+/// `pan_left = "physical:KeyA"`.  The binding then follows the key in that position on a US
+/// keyboard, whatever the layout types there.
+///
+/// ## Double-press and hold
+///
+/// A key name with the [`DOUBLE`] prefix, as in the default `exit = "DoubleEscape"`, binds
+/// pressing the key twice in quick succession, the same way `DoubleMouseLeft` binds a
+/// double-click, so a stray press of `Escape` does not close the app.  A key name with the
+/// [`HOLD`] prefix, as in `toggle_ink = "HoldSpace"`, binds holding the key down.  See
+/// [`crate::Cmd`] for the timing.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, derive_new::new, derive_getters::Getters)]
+pub struct Stroke {
+    modifiers: keyboard::ModifiersState,
+    key: String,
+}
+
+impl Stroke {
+    /// The `from_key` method creates a `Stroke` from a logical [`keyboard::Key`] and the
+    /// modifiers held while pressing it.  Returns [`None`] for keys that we cannot bind, such as
+    /// dead keys.  Modifier keys pressed on their own also return [`None`], otherwise pressing
+    /// `Shift` in the middle of a [`Chord`] would break the sequence.
+    pub fn from_key(key: &keyboard::Key, modifiers: keyboard::ModifiersState) -> Option<Self> {
+        match key.as_ref() {
+            keyboard::Key::Named(
+                keyboard::NamedKey::Shift
+                | keyboard::NamedKey::Control
+                | keyboard::NamedKey::Alt
+                | keyboard::NamedKey::AltGraph
+                | keyboard::NamedKey::Super
+                | keyboard::NamedKey::Meta
+                | keyboard::NamedKey::Hyper,
+            ) => None,
+            keyboard::Key::Named(k) => Some(Self::new(modifiers, format!("{k:?}"))),
+            keyboard::Key::Character(k) => Some(Self::new(modifiers, Self::normalize(k))),
+            other => {
+                tracing::trace!("Unbindable key: {other:?}");
+                None
+            }
+        }
+    }
+
+    /// The `from_physical` method creates a `Stroke` from a [`keyboard::PhysicalKey`] and the
+    /// modifiers held while pressing it, with the key named `physical:` followed by the name of
+    /// the [`keyboard::KeyCode`].  Returns [`None`] for keys without a known code.
+    pub fn from_physical(
+        key: &keyboard::PhysicalKey,
+        modifiers: keyboard::ModifiersState,
+    ) -> Option<Self> {
+        match key {
+            keyboard::PhysicalKey::Code(code) => {
+                Some(Self::new(modifiers, format!("{PHYSICAL}{code:?}")))
+            }
+            keyboard::PhysicalKey::Unidentified(code) => {
+                tracing::trace!("Unidentified physical key: {code:?}");
+                None
+            }
+        }
+    }
+
+    /// The `from_mouse` method creates a `Stroke` from a [`event::MouseButton`] and the
+    /// modifiers held while pressing it.  Mouse buttons take the names `MouseLeft`, `MouseRight`,
+    /// `MouseMiddle`, `MouseBack` and `MouseForward`, and other buttons take the name `Mouse`
+    /// followed by the button number, as in `Mouse8`.  When `double` is `true`, the name gains a
+    /// `Double` prefix, as in `DoubleMouseLeft`.
+    pub fn from_mouse(
+        button: &event::MouseButton,
+        modifiers: keyboard::ModifiersState,
+        double: bool,
+    ) -> Self {
+        let name = match button {
+            event::MouseButton::Left => "MouseLeft".to_string(),
+            event::MouseButton::Right => "MouseRight".to_string(),
+            event::MouseButton::Middle => "MouseMiddle".to_string(),
+            event::MouseButton::Back => "MouseBack".to_string(),
+            event::MouseButton::Forward => "MouseForward".to_string(),
+            event::MouseButton::Other(n) => format!("Mouse{n}"),
+        };
+        let stroke = Self::new(modifiers, name);
+        if double {
+            stroke.prefixed(DOUBLE)
+        } else {
+            stroke
+        }
+    }
+
+    /// The `prefixed` method returns a copy of the stroke with `prefix` in front of the key name,
+    /// for reading the stroke as a double-press with [`DOUBLE`] or a hold with [`HOLD`].
+    pub fn prefixed(&self, prefix: &str) -> Self {
+        Self::new(self.modifiers, format!("{prefix}{}", self.key))
+    }
+
+    /// The `is_known` method returns `true` if the key is a single character, a mouse button name
+    /// from [`Stroke::from_mouse`], a function key, or one of the [`NAMED_KEYS`].  The list of
+    /// named keys in [`winit`] is very long, so we only check against the ones people bind in
+    /// practice, and [`crate::Cmd::load`] treats an unknown name as a warning rather than an
+    /// error.  The [`DOUBLE`] and [`HOLD`] prefixes do not change the answer.
+    pub fn is_known(&self) -> bool {
+        let key = self.key.as_str();
+        let key = key
+            .strip_prefix(DOUBLE)
+            .or_else(|| key.strip_prefix(HOLD))
+            .unwrap_or(key);
+        key.chars().count() == 1
+            || NAMED_KEYS.contains(&key)
+            || key
+                .strip_prefix(PHYSICAL)
+                .is_some_and(|code| !code.is_empty())
+            || key.starts_with("Mouse")
+            || key
+                .strip_prefix('F')
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    /// The `without_shift` method returns a copy of the stroke with the `Shift` modifier
+    /// removed.  Characters like `?` and `+` require `Shift` on many keyboards, and users will
+    /// write `?` rather than `Shift+?` in the config, so [`crate::Cmd::act`] tries this as a
+    /// fallback for character keys.
+    pub fn without_shift(&self) -> Self {
+        let mut modifiers = self.modifiers;
+        modifiers.remove(keyboard::ModifiersState::SHIFT);
+        Self::new(modifiers, self.key.clone())
+    }
+
+    /// The `normalize` method converts single-character keys to lower case and leaves named keys
+    /// alone.
+    fn normalize(key: &str) -> String {
+        if key.chars().count() == 1 {
+            key.to_lowercase()
+        } else {
+            key.to_string()
+        }
+    }
+
+    /// The `modifier` method converts the name of a modifier key to the corresponding
+    /// [`keyboard::ModifiersState`] flag.
+    fn modifier(name: &str) -> Option<keyboard::ModifiersState> {
+        match name.to_lowercase().as_str() {
+            "ctrl" | "control" => Some(keyboard::ModifiersState::CONTROL),
+            "shift" => Some(keyboard::ModifiersState::SHIFT),
+            "alt" | "option" => Some(keyboard::ModifiersState::ALT),
+            "super" | "cmd" | "meta" | "logo" => Some(keyboard::ModifiersState::SUPER),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a `Stroke` from the `Ctrl+Shift+N` syntax described in the [`Stroke`] docs.
+///
+/// Will [`Excuse::EmptyBinding`] if the string is empty, and [`Excuse::UnknownModifier`] if any
+/// segment before the key is not a recognized modifier name.
+impl std::str::FromStr for Stroke {
+    type Err = Blame;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // A trailing "++" means the key is the plus sign.
+        let (prefix, key) = if s == "+" {
+            ("", "+")
+        } else if let Some(prefix) = s.strip_suffix("++") {
+            (prefix, "+")
+        } else if let Some((prefix, key)) = s.rsplit_once('+') {
+            (prefix, key)
+        } else {
+            ("", s)
+        };
+        if key.is_empty() {
+            return Err(Excuse::EmptyBinding.into());
+        }
+        let mut modifiers = keyboard::ModifiersState::empty();
+        for name in prefix.split('+').filter(|name| !name.is_empty()) {
+            match Self::modifier(name.trim()) {
+                Some(flag) => modifiers.insert(flag),
+                None => {
+                    tracing::warn!("Unknown modifier {name} in binding {s}");
+                    return Err(Excuse::UnknownModifier.into());
+                }
+            }
+        }
+        Ok(Self::new(modifiers, Self::normalize(key)))
+    }
+}
+
+/// Writes the stroke back out in the same `Ctrl+Shift+n` syntax we parse.
+impl std::fmt::Display for Stroke {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.control_key() {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift_key() {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt_key() {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.super_key() {
+            write!(f, "Super+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// The `Chord` struct is a sequence of [`Stroke`] types that together trigger a single action.
+///
+/// # Leader-style sequences with `Chord`
+///
+/// Modifier keys only go so far before the user needs three hands.  Following the example of
+/// editors like vim, a binding can also be a sequence of strokes separated by spaces, as in
+/// `go_to_bookmark = "g b"`.  The user presses `g`, releases it, and then presses `b`.  Most
+/// bindings are a sequence of one, and a `Chord` of one [`Stroke`] behaves exactly like the
+/// stroke.
+///
+/// The `Chord` struct wraps a [`Vec<Stroke>`], and we use [`derive_more::Deref`] and
+/// [`derive_more::DerefMut`] to reach the methods of the underlying vector.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_new::new,
+    derive_more::Deref,
+    derive_more::DerefMut,
+)]
+pub struct Chord(Vec<Stroke>);
+
+/// Parses a `Chord` from a space-separated list of strokes, using [`Stroke::from_str`] on each
+/// member of the list.
+///
+/// Will [`Excuse::EmptyBinding`] if the string contains no strokes.
+impl std::str::FromStr for Chord {
+    type Err = Blame;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strokes = s
+            .split_whitespace()
+            .map(|stroke| stroke.parse::<Stroke>())
+            .collect::<Result<Vec<Stroke>, Blame>>()?;
+        if strokes.is_empty() {
+            Err(Excuse::EmptyBinding.into())
+        } else {
+            Ok(Self::new(strokes))
+        }
+    }
+}
+
+/// Writes the chord back out as a space-separated list of strokes.
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let strokes = self
+            .iter()
+            .map(|stroke| stroke.to_string())
+            .collect::<Vec<String>>();
+        write!(f, "{}", strokes.join(" "))
+    }
+}
+
+/// The `NAMED_KEYS` constant lists the names of commonly bound [`keyboard::NamedKey`] variants,
+/// as they appear in the config.  Used by [`Stroke::is_known`] to catch typos.
+pub const NAMED_KEYS: [&str; 22] = [
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "ArrowUp",
+    "Backspace",
+    "CapsLock",
+    "ContextMenu",
+    "Delete",
+    "End",
+    "Enter",
+    "Escape",
+    "Help",
+    "Home",
+    "Insert",
+    "NumLock",
+    "PageDown",
+    "PageUp",
+    "Pause",
+    "PrintScreen",
+    "ScrollLock",
+    "Space",
+    "Tab",
+];
+
+/// The `PHYSICAL` constant holds the prefix marking a binding to a physical key position rather
+/// than a logical key.  See the [`Stroke`] docs.
+pub const PHYSICAL: &str = "physical:";
+
+/// The `DOUBLE` constant holds the prefix marking a binding to a double-press or double-click.
+/// See the [`Stroke`] docs.
+pub const DOUBLE: &str = "Double";
+
+/// The `HOLD` constant holds the prefix marking a binding to holding a key down.  See the
+/// [`Stroke`] docs.
+pub const HOLD: &str = "Hold";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strokes_read_back() {
+        let stroke = "ctrl+Shift+N".parse::<Stroke>().expect("stroke");
+        let modifiers = keyboard::ModifiersState::CONTROL | keyboard::ModifiersState::SHIFT;
+        assert_eq!(stroke, Stroke::new(modifiers, "n".to_string()));
+        assert_eq!(stroke.to_string(), "Ctrl+Shift+n");
+        assert_eq!(stroke.to_string().parse::<Stroke>().expect("again"), stroke);
+        let plus = "Ctrl++".parse::<Stroke>().expect("plus");
+        assert_eq!(plus.key(), "+");
+        assert_eq!(plus.modifiers(), &keyboard::ModifiersState::CONTROL);
+        assert_eq!("+".parse::<Stroke>().expect("bare plus").key(), "+");
+        assert!("".parse::<Stroke>().is_err());
+        assert!("Hyper+n".parse::<Stroke>().is_err());
+    }
+
+    #[test]
+    fn chords_read_back() {
+        let chord = " g  Ctrl+b ".parse::<Chord>().expect("chord");
+        assert_eq!(chord.len(), 2);
+        assert_eq!(chord.to_string(), "g Ctrl+b");
+        assert!("   ".parse::<Chord>().is_err());
+        assert!("g Hyper+b".parse::<Chord>().is_err());
+    }
+
+    #[test]
+    fn prefixes_keep_keys_known() {
+        let escape = Stroke::new(keyboard::ModifiersState::empty(), "Escape".to_string());
+        let double = escape.prefixed(DOUBLE);
+        assert_eq!(double.key(), "DoubleEscape");
+        assert!(double.is_known());
+        assert!(escape.prefixed(HOLD).is_known());
+        for key in ["n", "F12", "MouseLeft", "Mouse8", "physical:KeyA", "PageUp"] {
+            let stroke = Stroke::new(keyboard::ModifiersState::empty(), key.to_string());
+            assert!(stroke.is_known(), "{key}");
+        }
+        for key in ["Escpe", "Fx", "physical:", "DoubleEscpe"] {
+            let stroke = Stroke::new(keyboard::ModifiersState::empty(), key.to_string());
+            assert!(!stroke.is_known(), "{key}");
+        }
+    }
+
+    #[test]
+    fn shift_comes_off() {
+        let stroke = "Ctrl+Shift+?".parse::<Stroke>().expect("stroke");
+        let plain = stroke.without_shift();
+        assert_eq!(plain.modifiers(), &keyboard::ModifiersState::CONTROL);
+        assert_eq!(plain.key(), "?");
+    }
+}
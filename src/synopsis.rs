@@ -0,0 +1,353 @@
+use crate::{Arrive, Dataset, Summary};
+use arrow::array::Array;
+
+/// The `synopsis` module provides the [`Synopsis`] struct, which sums up what the map in a window
+/// shows in a sentence, and the [`Extent`] struct, which holds the ground the map covers.
+///
+/// # Putting the map into words with `Synopsis`
+///
+/// A screen reader can name the buttons of a window and the layers in it, as described in
+/// [`crate::Access`], but the map itself is a picture, and it has nothing to say about one.  A
+/// sighted user glances at the map and knows where it is and what is on it.  The
+/// [`crate::Act::DescribeView`] act gives everyone else the same glance, in a line of text:
+///
+/// ```text
+/// Merlin, 4.2 km across; 3 layers visible; 1,204 features in view; median value in view: 54,200
+/// ```
+///
+/// * The place comes from the `place` key of the `[summary]` table (see [`Summary`]), or failing
+///   that, the latitude and longitude of the center of the view, followed by the width of the
+///   view on the ground.
+/// * A layer is visible if any feature of its data file lies in the view.  We take the shape of
+///   each feature from [`Dataset::outlines`], read once from its [`crate::GEOMETRY`] column, so a
+///   CSV file without one has nothing in view.  A feature counts if any of its points lie in the
+///   view, which is close enough for a count, if not for a survey.
+/// * The median comes from the column named by the `column` key of the `[summary]` table, among
+///   the features in view with a number there.  Dollar signs, commas and percent signs are
+///   ignored, so a column of prices still counts.
+///
+/// A lookup selects one parcel, which its own panel describes, so the line sums up the view
+/// alone.
+///
+/// The [`crate::App`] shows the line in a [`crate::Panel`] over the map, hands it to the
+/// [`crate::Access`] of the window as the description of the map, which a screen reader
+/// announces as it changes, and copies it to the [`Clipboard`], ready to paste into an email or
+/// a ticket.
+///
+/// * The `place` field holds the name of the place in view.
+/// * The `layers` field holds the number of layers with features in view.
+/// * The `features` field holds the number of features in view, across the layers.
+/// * The `median` field holds the name of the column and its median in view, if any.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Synopsis {
+    place: String,
+    layers: usize,
+    features: usize,
+    median: Option<(String, f64)>,
+}
+
+impl Synopsis {
+    /// The `survey` method sums up the view of `extent` over the `datasets` open in the app,
+    /// with the place and column from `summary`, as described in the [`Synopsis`] docs.
+    pub fn survey(extent: &Extent, datasets: &[Dataset], summary: &Summary) -> Self {
+        let (latitude, longitude) = extent.center();
+        let place = match summary.place() {
+            Some(place) => place.clone(),
+            None => format!(
+                "Near {:.3}°{}, {:.3}°{}",
+                latitude.abs(),
+                if latitude < 0.0 { "S" } else { "N" },
+                longitude.abs(),
+                if longitude < 0.0 { "W" } else { "E" },
+            ),
+        };
+        let place = format!("{place}, {:.1} km across", extent.across());
+
+        let mut layers = 0;
+        let mut features = 0;
+        let mut values = Vec::new();
+        let bounds = extent.to_mercator();
+        for dataset in datasets {
+            if dataset.geometry().is_none() {
+                continue;
+            }
+            let numbers = summary
+                .column()
+                .as_ref()
+                .and_then(|column| dataset.column(column))
+                .map(|column| dataset.numbers(column));
+            let mut seen = 0;
+            for (row, shape) in dataset.outlines().iter().enumerate() {
+                if !shape.as_ref().is_some_and(|shape| shape.touches(bounds)) {
+                    continue;
+                }
+                seen += 1;
+                if let Some(numbers) = numbers.as_ref().filter(|numbers| numbers.is_valid(row)) {
+                    values.push(numbers.value(row));
+                }
+            }
+            if seen > 0 {
+                layers += 1;
+                features += seen;
+            }
+        }
+        let median = summary.column().clone().zip(Self::median(values));
+        Self {
+            place,
+            layers,
+            features,
+            median,
+        }
+    }
+
+    /// The `points` method collects the longitude and latitude of every position in the GeoJSON
+    /// `coordinates`, however deeply nested, into `points`.
+    pub fn points(coordinates: &serde_json::Value, points: &mut Vec<(f64, f64)>) {
+        let Some(members) = coordinates.as_array() else {
+            return;
+        };
+        match (
+            members.first().and_then(|x| x.as_f64()),
+            members.get(1).and_then(|y| y.as_f64()),
+        ) {
+            (Some(longitude), Some(latitude)) => points.push((longitude, latitude)),
+            _ => members
+                .iter()
+                .for_each(|member| Self::points(member, points)),
+        }
+    }
+
+    /// The `number` method reads `text` as a number, ignoring dollar signs, commas, percent
+    /// signs and spaces, or returns [`None`] if it is not one.
+    pub fn number(text: &str) -> Option<f64> {
+        let digits = text
+            .chars()
+            .filter(|c| !matches!(c, '$' | ',' | '%' | ' '))
+            .collect::<String>();
+        digits.parse::<f64>().ok().filter(|value| value.is_finite())
+    }
+
+    /// The `median` method returns the middle of `values`, or the mean of the two in the middle
+    /// of an even count, or [`None`] if there are none.
+    fn median(mut values: Vec<f64>) -> Option<f64> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let middle = values.len() / 2;
+        match values.len() % 2 {
+            0 => Some((values[middle - 1] + values[middle]) / 2.0),
+            _ => Some(values[middle]),
+        }
+    }
+
+    /// The `figure` method writes `value` for reading aloud, with commas between the thousands
+    /// and no more than two decimal places.
+    fn figure(value: f64) -> String {
+        let text = format!("{:.2}", value.abs());
+        let text = text.trim_end_matches('0').trim_end_matches('.');
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let mut grouped = String::new();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        let sign = if value < 0.0 { "-" } else { "" };
+        match fraction.is_empty() {
+            true => format!("{sign}{grouped}"),
+            false => format!("{sign}{grouped}.{fraction}"),
+        }
+    }
+}
+
+/// Writes the line described in the [`Synopsis`] docs.
+impl std::fmt::Display for Synopsis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.place)?;
+        match self.layers {
+            1 => write!(f, "; 1 layer visible")?,
+            layers => write!(f, "; {layers} layers visible")?,
+        }
+        match self.features {
+            1 => write!(f, "; 1 feature in view")?,
+            features => write!(f, "; {} features in view", Self::figure(features as f64))?,
+        }
+        if let Some((column, median)) = &self.median {
+            write!(f, "; median {column} in view: {}", Self::figure(*median))?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Extent` struct holds the ground a map shows, in degrees of longitude and latitude.
+///
+/// * The `west` and `east` fields hold the longitude of the left and right edges.
+/// * The `south` and `north` fields hold the latitude of the bottom and top edges.
+#[derive(Debug, Copy, Clone, PartialEq, derive_getters::Getters, derive_new::new)]
+pub struct Extent {
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+}
+
+impl Extent {
+    /// The `from_mercator` method returns the extent of a box in web mercator meters, the
+    /// projection of the base map, from the corners `(x_min, y_min)` and `(x_max, y_max)`.
+    pub fn from_mercator(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Self {
+        let (west, south) = Self::unproject(x_min, y_min);
+        let (east, north) = Self::unproject(x_max, y_max);
+        Self::new(west, south, east, north)
+    }
+
+    /// The `unproject` method returns the longitude and latitude of the point at `x` and `y` in
+    /// web mercator meters.
+    pub fn unproject(x: f64, y: f64) -> (f64, f64) {
+        let longitude = (x / EARTH_RADIUS).to_degrees();
+        let latitude =
+            (2.0 * (y / EARTH_RADIUS).exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees();
+        (longitude, latitude)
+    }
+
+    /// The `project` method returns the point at `longitude` and `latitude` in web mercator
+    /// meters, the other way from [`Extent::unproject`].  The poles lie at infinity, so we
+    /// stop at [`MERCATOR_LATITUDE`].
+    pub fn project(longitude: f64, latitude: f64) -> (f64, f64) {
+        let radians = latitude
+            .clamp(-MERCATOR_LATITUDE, MERCATOR_LATITUDE)
+            .to_radians();
+        let x = longitude.to_radians() * EARTH_RADIUS;
+        let y = (std::f64::consts::FRAC_PI_4 + radians / 2.0).tan().ln() * EARTH_RADIUS;
+        (x, y)
+    }
+
+    /// The `to_mercator` method returns the corners of the extent in web mercator meters, as
+    /// `(x_min, y_min, x_max, y_max)`, the other way from [`Extent::from_mercator`].
+    pub fn to_mercator(&self) -> (f64, f64, f64, f64) {
+        let (x_min, y_min) = Self::project(self.west, self.south);
+        let (x_max, y_max) = Self::project(self.east, self.north);
+        (x_min, y_min, x_max, y_max)
+    }
+
+    /// The `parse` method reads an extent from `text` in the form `WEST,SOUTH,EAST,NORTH`, in
+    /// degrees, as the `--extent` argument of [`crate::Cli`] takes it.  The message of the error
+    /// goes to [`clap`], which shows it to the user.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let sides = text
+            .split(',')
+            .map(|side| side.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| format!("{e} in {text}"))?;
+        let [west, south, east, north] = sides[..] else {
+            return Err(format!("expected WEST,SOUTH,EAST,NORTH, found {text}"));
+        };
+        if west >= east || south >= north {
+            return Err(format!(
+                "the west and south edges must come before the east and north in {text}"
+            ));
+        }
+        if south <= -MERCATOR_LATITUDE || north >= MERCATOR_LATITUDE {
+            return Err(format!(
+                "the base map stops at {MERCATOR_LATITUDE} degrees of latitude"
+            ));
+        }
+        Ok(Self::new(west, south, east, north))
+    }
+
+    /// The `contains` method returns `true` if the point at `longitude` and `latitude` lies in
+    /// the extent.
+    pub fn contains(&self, longitude: f64, latitude: f64) -> bool {
+        let across = (self.west..=self.east).contains(&longitude);
+        across && (self.south..=self.north).contains(&latitude)
+    }
+
+    /// The `center` method returns the latitude and longitude of the middle of the extent.
+    pub fn center(&self) -> (f64, f64) {
+        (
+            (self.south + self.north) / 2.0,
+            (self.west + self.east) / 2.0,
+        )
+    }
+
+    /// The `across` method returns the width of the extent on the ground in kilometers, along
+    /// the latitude of its center.
+    pub fn across(&self) -> f64 {
+        let (latitude, _) = self.center();
+        let radians = (self.east - self.west).to_radians();
+        radians * EARTH_RADIUS * latitude.to_radians().cos() / 1000.0
+    }
+}
+
+/// The `Clipboard` struct holds the system clipboard, through the [`arboard`] crate.  On Linux,
+/// the text we copy lasts only as long as the clipboard that copied it, so the [`crate::App`]
+/// keeps one for good rather than opening one for each copy.  The [`arboard`] crate does not
+/// build for the web browser, so there copying fails with [`crate::Excuse::Unavailable`] (see
+/// [`crate::run_web`]).
+///
+/// * The `inner` field holds the [`arboard::Clipboard`].
+pub struct Clipboard {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: arboard::Clipboard,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clipboard {
+    /// The `new` method opens the system clipboard.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the platform has no clipboard we can reach.
+    pub fn new() -> Arrive<Self> {
+        Ok(Self {
+            inner: arboard::Clipboard::new()?,
+        })
+    }
+
+    /// The `copy` method puts `text` on the clipboard.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the platform refuses it.
+    pub fn copy(&mut self, text: &str) -> Arrive<()> {
+        self.inner.set_text(text)?;
+        Ok(())
+    }
+
+    /// The `paste` method returns the text on the clipboard.
+    ///
+    /// Will [`crate::Blame::Clipboard`] if the clipboard is empty or holds no text.
+    pub fn paste(&mut self) -> Arrive<String> {
+        Ok(self.inner.get_text()?)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Clipboard {
+    /// The `new` method fails with [`crate::Excuse::Unavailable`], since the browser has no
+    /// clipboard we can reach this way.
+    pub fn new() -> Arrive<Self> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `copy` method fails with [`crate::Excuse::Unavailable`], as [`Clipboard::new`] does.
+    pub fn copy(&mut self, _text: &str) -> Arrive<()> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+
+    /// The `paste` method fails with [`crate::Excuse::Unavailable`], as [`Clipboard::new`] does.
+    pub fn paste(&mut self) -> Arrive<String> {
+        Err(crate::Excuse::Unavailable.into())
+    }
+}
+
+impl std::fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Clipboard").finish_non_exhaustive()
+    }
+}
+
+/// The `EARTH_RADIUS` constant holds the radius of the sphere of the web mercator projection, in
+/// meters.
+pub const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// The `MERCATOR_LATITUDE` constant holds the latitude, north and south, where the web mercator
+/// projection of the base map stops, in degrees.
+pub const MERCATOR_LATITUDE: f64 = 85.051_128_78;
@@ -0,0 +1,406 @@
+use crate::{Arrive, Concurrency, Context, Courier, UserEvent};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+/// The `tasks` module provides the [`Tasks`] struct, which keeps track of the jobs running in
+/// the background, and the [`Job`] struct, which describes one of them.
+///
+/// # Keeping track of background work with `Tasks`
+///
+/// A [`Courier`] gets the error out of a background task, but once the task is spawned, nobody
+/// knows it is there.  The user cannot see that a download is half done, cannot stop an import
+/// they started by mistake, and when the app closes, the tasks die wherever they happen to be,
+/// half way through writing a file.  That was fine for the first background task, but every
+/// async feature to come adds more.
+///
+/// The `Tasks` registry spawns each job through the [`Courier`], and keeps a [`Job`] for it until
+/// it finishes:
+///
+/// * Each job has an id, counting up from one, and a description for the user, like
+///   `Downloading county income`.
+/// * The job receives a clone of its own [`Job`], and reports how far along it is with
+///   [`Job::set_progress`].  Jobs that cannot tell leave the progress unknown.  Each report
+///   reaches the event loop as a [`crate::UserEvent::Progress`], and a job that finishes without
+///   error sends a [`crate::UserEvent::Finished`], so the app can show the results.
+/// * Each job has a [`CancellationToken`].  [`Tasks::cancel`] stops one job, and
+///   [`Tasks::cancel_all`] stops them all, which the [`crate::App`] does on the way out.  A job
+///   that stops at the next `await` needs do nothing, since we race it against its token.  A job
+///   with cleanup to do can watch [`Job::is_cancelled`] and stop at a safe point.
+///
+/// [`Tasks::jobs`] lists the jobs still running, and [`crate::Act::ShowTasks`] shows them in a
+/// [`crate::Panel`] over the map.
+///
+/// ## Putting the user first
+///
+/// Not all jobs are equal.  When the user pans the map, the tiles coming into view matter more
+/// than the tiles we are prefetching in case they pan further, and an identify query they are
+/// waiting on matters more than a batch download they started an hour ago.  Each job belongs to
+/// a [`Class`]:
+///
+/// * [`Class::Interactive`] jobs are ones the user is waiting on.
+/// * [`Class::Bulk`] jobs can wait.
+///
+/// Each class has a pool of its own, a [`Semaphore`] with as many permits as the `[tasks]` table
+/// of the config allows (see [`Concurrency`]), so a flood of bulk jobs cannot take the
+/// connections an interactive job needs.  On top of that, a bulk job does not start while any
+/// interactive job is waiting for a permit.  Bulk jobs already running carry on, since stopping
+/// a download half way wastes what it fetched, but no new bulk work begins until the user has
+/// what they asked for.
+///
+/// * The `courier` field holds the [`Courier`] that spawns the jobs and delivers their errors.
+/// * The `jobs` field holds the jobs still running, by id.
+/// * The `next` field holds the id of the next job.
+/// * The `interactive` field holds the pool of permits for interactive jobs.
+/// * The `bulk` field holds the pool of permits for bulk jobs.
+/// * The `waiting` field counts the interactive jobs waiting for a permit, for bulk jobs to watch.
+#[derive(Debug, Clone)]
+pub struct Tasks {
+    courier: Courier,
+    jobs: Arc<Mutex<BTreeMap<u64, Job>>>,
+    next: Arc<AtomicU64>,
+    interactive: Arc<Mutex<Arc<Semaphore>>>,
+    bulk: Arc<Mutex<Arc<Semaphore>>>,
+    waiting: Arc<watch::Sender<usize>>,
+}
+
+impl Tasks {
+    /// The `new` method creates an empty registry, spawning jobs through `courier`, with the
+    /// default [`Concurrency`] limits.
+    pub fn new(courier: Courier) -> Self {
+        let limits = Concurrency::default();
+        Self {
+            courier,
+            jobs: Arc::new(Mutex::new(BTreeMap::new())),
+            next: Arc::new(AtomicU64::new(1)),
+            interactive: Arc::new(Mutex::new(Self::pool(*limits.interactive()))),
+            bulk: Arc::new(Mutex::new(Self::pool(*limits.bulk()))),
+            waiting: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    /// The `configure` method applies new `limits` to jobs started from now on.  Jobs already
+    /// running keep their permits from the old pools.
+    pub fn configure(&self, limits: &Concurrency) {
+        if let Ok(mut pool) = self.interactive.lock() {
+            *pool = Self::pool(*limits.interactive());
+        }
+        if let Ok(mut pool) = self.bulk.lock() {
+            *pool = Self::pool(*limits.bulk());
+        }
+        tracing::trace!(
+            "Running up to {} interactive and {} bulk jobs.",
+            limits.interactive(),
+            limits.bulk()
+        );
+    }
+
+    /// The `pool` method creates a pool of `limit` permits, and at least one.
+    fn pool(limit: usize) -> Arc<Semaphore> {
+        Arc::new(Semaphore::new(limit.max(1)))
+    }
+
+    /// The `permits` method returns the pool for jobs of `class`.
+    fn permits(&self, class: Class) -> Arc<Semaphore> {
+        let pool = match class {
+            Class::Interactive => &self.interactive,
+            Class::Bulk => &self.bulk,
+        };
+        match pool.lock() {
+            Ok(pool) => pool.clone(),
+            Err(_) => Self::pool(1),
+        }
+    }
+
+    /// The `spawn` method registers a job of `class` called `description`, and runs the future
+    /// returned by `task` in the background, once a permit is free, until it finishes or the job
+    /// is cancelled.  The `task` closure receives a clone of the [`Job`], for reporting progress
+    /// and checking for cancellation.  Returns the [`Job`], so the caller can cancel it.
+    ///
+    /// An error from the job reaches the event loop through [`Courier::send`], with the
+    /// description as context.  A cancelled job is not an error.
+    pub fn spawn<F, T>(&self, description: impl Into<String>, class: Class, task: F) -> Job
+    where
+        F: FnOnce(Job) -> T,
+        T: std::future::Future<Output = Arrive<()>> + Send + 'static,
+    {
+        let job = self.register(description.into(), class);
+        let future = task(job.clone());
+        let tasks = self.clone();
+        let finished = job.clone();
+        self.courier.spawn(async move {
+            let result = tasks
+                .execute(finished.clone(), future)
+                .await
+                .unwrap_or(Ok(()));
+            if result.is_ok() && !finished.is_cancelled() {
+                tasks.courier.notify(UserEvent::Finished(finished.clone()));
+            }
+            result.with_context(|| finished.description.to_lowercase())
+        });
+        job
+    }
+
+    /// The `run` method registers a job of `class` called `description` and runs the future
+    /// returned by `task` in place, like [`Tasks::spawn`], but waits for it and hands back the
+    /// result rather than sending errors to the event loop.  Returns [`None`] if the job was
+    /// cancelled.  For a caller that has to know how the job went, like [`crate::Agents`].
+    pub async fn run<F, T, R>(
+        &self,
+        description: impl Into<String>,
+        class: Class,
+        task: F,
+    ) -> Option<Arrive<R>>
+    where
+        F: FnOnce(Job) -> T,
+        T: std::future::Future<Output = Arrive<R>>,
+    {
+        let job = self.register(description.into(), class);
+        let future = task(job.clone());
+        self.execute(job, future).await
+    }
+
+    /// The `register` method adds a new job of `class` called `description` to the registry.
+    fn register(&self, description: String, class: Class) -> Job {
+        let id = self.next.fetch_add(1, Ordering::Relaxed);
+        let job = Job::new(id, description, class, self.courier.clone());
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(id, job.clone());
+        }
+        tracing::trace!("Queueing {job}");
+        job
+    }
+
+    /// The `execute` method waits for a permit for `job`, then runs `future` until it finishes
+    /// or the job is cancelled, and removes the job from the registry.  Returns [`None`] if the
+    /// job was cancelled.
+    async fn execute<T, R>(&self, job: Job, future: T) -> Option<Arrive<R>>
+    where
+        T: std::future::Future<Output = Arrive<R>>,
+    {
+        let class = job.class;
+        let permits = self.permits(class);
+        let waiting = self.waiting.clone();
+        if class == Class::Interactive {
+            waiting.send_modify(|count| *count += 1);
+        }
+        let run = async {
+            if class == Class::Bulk {
+                // Only fails if the sender is gone, and then nobody is waiting.
+                let _ = waiting.subscribe().wait_for(|count| *count == 0).await;
+            }
+            // The pool is never closed, so a permit always comes eventually.
+            let _permit = permits.acquire_owned().await.ok();
+            if class == Class::Interactive {
+                waiting.send_modify(|count| *count = count.saturating_sub(1));
+            }
+            job.start();
+            future.await
+        };
+        let result = tokio::select! {
+            _ = job.token.cancelled() => {
+                if class == Class::Interactive && !job.is_running() {
+                    waiting.send_modify(|count| *count = count.saturating_sub(1));
+                }
+                tracing::info!("Cancelled {}.", job.description);
+                None
+            }
+            result = run => Some(result),
+        };
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.remove(&job.id);
+        }
+        result
+    }
+
+    /// The `jobs` method returns the jobs still running, in the order they started.
+    pub fn jobs(&self) -> Vec<Job> {
+        self.jobs
+            .lock()
+            .map(|jobs| jobs.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The `cancel` method cancels the job with the id `id`.  Returns `false` if there is no such
+    /// job, perhaps because it already finished.
+    pub fn cancel(&self, id: u64) -> bool {
+        let job = self
+            .jobs
+            .lock()
+            .ok()
+            .and_then(|jobs| jobs.get(&id).cloned());
+        match job {
+            Some(job) => {
+                job.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The `cancel_all` method cancels every job still running.
+    #[tracing::instrument(skip_all)]
+    pub fn cancel_all(&self) {
+        let jobs = self.jobs();
+        if !jobs.is_empty() {
+            tracing::info!("Cancelling {} background tasks.", jobs.len());
+        }
+        jobs.iter().for_each(Job::cancel);
+    }
+}
+
+/// Prints the jobs still running, one per line, or says there are none.
+impl std::fmt::Display for Tasks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let jobs = self.jobs();
+        if jobs.is_empty() {
+            return write!(f, "No background tasks.");
+        }
+        let lines = jobs.iter().map(Job::to_string).collect::<Vec<String>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// The `Job` struct describes a job run by [`Tasks`].  Clones share the progress and the
+/// cancellation token, so the job and the registry see the same state.
+///
+/// * The `id` field holds the number of the job, unique for the life of the app.
+/// * The `description` field tells the user what the job is doing.
+/// * The `class` field holds the [`Class`] of the job.
+/// * The `progress` field holds how far along the job is, from zero to one, if it has said.
+/// * The `running` field is `true` once the job has a permit and has started.
+/// * The `token` field holds the [`CancellationToken`] that stops the job.
+/// * The `courier` field holds the [`Courier`] that carries news of the job to the event loop, or
+///   [`None`] for a job run without one, from [`Job::detached`].
+#[derive(Debug, Clone, derive_getters::Getters)]
+pub struct Job {
+    id: u64,
+    description: String,
+    class: Class,
+    #[getter(skip)]
+    progress: Arc<Mutex<Option<f32>>>,
+    #[getter(skip)]
+    running: Arc<AtomicBool>,
+    token: CancellationToken,
+    #[getter(skip)]
+    courier: Option<Courier>,
+}
+
+impl Job {
+    /// The `new` method creates a queued job with no progress and a fresh token.
+    fn new(id: u64, description: String, class: Class, courier: Courier) -> Self {
+        Self {
+            id,
+            description,
+            class,
+            progress: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(false)),
+            token: CancellationToken::new(),
+            courier: Some(courier),
+        }
+    }
+
+    /// The `detached` method creates a running job called `description` outside any registry,
+    /// for work done where there is no event loop to hear of it, as by the headless
+    /// [`crate::Export`].  Its progress goes nowhere, and nobody cancels it.
+    pub fn detached(description: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            description: description.into(),
+            class: Class::Interactive,
+            progress: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(true)),
+            token: CancellationToken::new(),
+            courier: None,
+        }
+    }
+
+    /// The `start` method marks the job as running.
+    fn start(&self) {
+        self.running.store(true, Ordering::Relaxed);
+    }
+
+    /// The `is_running` method returns `true` once the job has started, and `false` while it
+    /// waits for a permit.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// The `progress` method returns how far along the job is, from zero to one, or [`None`] if
+    /// the job has not said.
+    pub fn progress(&self) -> Option<f32> {
+        self.progress.lock().ok().and_then(|progress| *progress)
+    }
+
+    /// The `set_progress` method records how far along the job is, as a `fraction` from zero to
+    /// one, and tells the event loop with a [`UserEvent::Progress`].  Values outside the range are
+    /// clamped to it.
+    pub fn set_progress(&self, fraction: f32) {
+        if let Ok(mut progress) = self.progress.lock() {
+            *progress = Some(fraction.clamp(0.0, 1.0));
+        }
+        if let Some(courier) = &self.courier {
+            courier.notify(UserEvent::Progress(self.clone()));
+        }
+    }
+
+    /// The `cancel` method asks the job to stop.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// The `is_cancelled` method returns `true` once someone has asked the job to stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// Prints the id, description and progress of the job, as in `#3 Downloading tiles (45%)`,
+/// marking bulk jobs and jobs still in the queue.
+impl std::fmt::Display for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} {}", self.id, self.description)?;
+        if self.class == Class::Bulk {
+            write!(f, " [bulk]")?;
+        }
+        if !self.is_running() {
+            write!(f, " [queued]")?;
+        }
+        if let Some(progress) = self.progress() {
+            write!(f, " ({:.0}%)", progress * 100.0)?;
+        }
+        if self.is_cancelled() {
+            write!(f, " [cancelling]")?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Class` enum sorts the jobs run by [`Tasks`] by how urgently the user needs them, as
+/// described in the [`Tasks`] docs.
+///
+/// * The `Interactive` variant marks a job the user is waiting on, like fetching the visible
+///   tiles or an identify query.
+/// * The `Bulk` variant marks a job that can wait, like prefetching or a batch download.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+)]
+pub enum Class {
+    /// The `Interactive` variant indicates the user is waiting on the job.
+    #[default]
+    Interactive,
+    /// The `Bulk` variant indicates the job can wait.
+    Bulk,
+}
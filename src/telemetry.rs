@@ -0,0 +1,233 @@
+use crate::{Arrive, Blame, Context, Fetch, Home, Network, Telemetry};
+use std::collections::BTreeMap;
+use std::path;
+
+/// The `telemetry` module provides the [`Reporter`] struct, which sends crash reports and error
+/// counts home, if the deployment asks it to.
+///
+/// # Hearing from kiosks with `Reporter`
+///
+/// Some of our deployments are kiosks that nobody logs into.  When one crashes, the crash report
+/// from [`crate::Crash`] sits on its disk until someone happens by with a keyboard, and an error
+/// that recurs a hundred times a day never reaches us at all.  The `Reporter` sends both to an
+/// endpoint we host ourselves, but only when the `[telemetry]` table of the config turns it on:
+///
+/// ```toml
+/// [telemetry]
+/// enabled = true
+/// endpoint = "https://telemetry.example.gov/bea_egui"
+/// ```
+///
+/// Telemetry is off unless `enabled` is `true` and there is an `endpoint`, so nothing leaves a
+/// machine that has not been configured to send it.  While it is on, we keep:
+///
+/// * A count of each error code from [`Blame::code`], as handled by
+///   [`crate::App::report_error`].  The counts go to [`TALLY_FILE`] in the data directory when
+///   the app exits, since a kiosk rarely exits politely enough to upload on the way out.
+/// * The crash reports mentioned on startup by [`crate::Crash::report`].
+///
+/// On the next start, [`Reporter::upload`] posts a [`Payload`] as JSON.  The crash reports
+/// themselves never leave the machine, since their panic messages and log lines hold file
+/// paths, addresses and request URLs, and no pattern can be trusted to catch all of them.  All
+/// we send of each is its backtrace, cut down by [`Reporter::backtrace`] to the names of the
+/// functions, which come from our code and our dependencies rather than from the user.  The
+/// error counts are only codes and numbers, and nothing identifies the machine.  The upload
+/// runs in the background, and a failed upload only goes to the log, since telemetry should
+/// never trouble the user.  Counts that fail to upload stay in the file for next time.
+///
+/// * The `settings` field holds the [`Telemetry`] settings from the config.
+/// * The `tally` field holds the count of each error code seen during this run.
+/// * The `path` field holds the path to [`TALLY_FILE`].
+#[derive(Debug, Clone, Default, derive_getters::Getters)]
+pub struct Reporter {
+    settings: Telemetry,
+    tally: BTreeMap<String, u64>,
+    path: path::PathBuf,
+}
+
+impl Reporter {
+    /// The `new` method creates a reporter using `settings`, keeping its counts under the data
+    /// directory of `home`.
+    pub fn new(settings: Telemetry, home: &Home) -> Self {
+        Self {
+            settings,
+            tally: BTreeMap::new(),
+            path: home.data().join(TALLY_FILE),
+        }
+    }
+
+    /// The `is_enabled` method returns `true` if the config turns telemetry on and names an
+    /// endpoint to send it to.
+    pub fn is_enabled(&self) -> bool {
+        *self.settings.enabled() && self.settings.endpoint().is_some()
+    }
+
+    /// The `count` method adds one to the count for the code of `blame`, if telemetry is on.
+    pub fn count(&mut self, blame: &Blame) {
+        if self.is_enabled() {
+            *self.tally.entry(blame.code().to_string()).or_default() += 1;
+        }
+    }
+
+    /// The `save` method adds the counts from this run to those in [`TALLY_FILE`], and clears
+    /// them.  Does nothing if telemetry is off or nothing happened.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be written, and [`crate::Blame::TomlSer`] if
+    /// the counts will not serialize.
+    #[tracing::instrument(skip_all)]
+    pub fn save(&mut self) -> Arrive<()> {
+        if !self.is_enabled() || self.tally.is_empty() {
+            return Ok(());
+        }
+        let mut tally = Self::read(&self.path);
+        for (code, count) in std::mem::take(&mut self.tally) {
+            *tally.entry(code).or_default() += count;
+        }
+        std::fs::write(&self.path, toml::to_string(&tally)?)
+            .with_context(|| format!("saving {}", self.path.display()))?;
+        tracing::trace!("Error counts saved to {}.", self.path.display());
+        Ok(())
+    }
+
+    /// The `upload` method sends the saved error counts and the crash reports at `crashes` to the
+    /// endpoint in the background, as described in the [`Reporter`] docs.  Does nothing if
+    /// telemetry is off, or there is nothing to send.
+    #[tracing::instrument(skip_all)]
+    pub fn upload(&self, crashes: &[path::PathBuf]) {
+        let Some(endpoint) = self
+            .settings
+            .endpoint()
+            .clone()
+            .filter(|_| self.is_enabled())
+        else {
+            return;
+        };
+        let errors = Self::read(&self.path);
+        let crashes = crashes
+            .iter()
+            .filter_map(|crash| std::fs::read_to_string(crash).ok())
+            .map(|crash| Self::backtrace(&crash))
+            .filter(|frames| !frames.is_empty())
+            .collect::<Vec<Vec<String>>>();
+        if errors.is_empty() && crashes.is_empty() {
+            return;
+        }
+        let payload = Payload::new(
+            env!("CARGO_PKG_VERSION").to_string(),
+            std::env::consts::OS.to_string(),
+            errors,
+            crashes,
+        );
+        let path = self.path.clone();
+        // Not through a crate::Courier, since a failed upload is our problem, not the user's.
+        crate::spawn(async move {
+            match Self::send(&endpoint, &payload).await {
+                Ok(()) => {
+                    let endpoint = Network::global().redact(&endpoint);
+                    tracing::info!("Telemetry sent to {endpoint}.");
+                    if path.is_file() {
+                        if let Err(e) = std::fs::remove_file(&path) {
+                            tracing::trace!("Could not clear {}: {e}", path.display());
+                        }
+                    }
+                }
+                Err(e) => tracing::debug!("Could not send telemetry: {e}"),
+            }
+        });
+    }
+
+    /// The `send` method posts `payload` to `endpoint` as JSON.
+    ///
+    /// Will [`crate::Blame::Http`] if the request fails or the endpoint answers with an error
+    /// status.  The span is named for [`crate::REQUEST_SPAN`], so [`crate::Latency`] times it,
+    /// and the request goes through [`Network::send`], so it shows up in the network log.
+    #[tracing::instrument(name = "request", skip_all)]
+    async fn send(endpoint: &str, payload: &Payload) -> Arrive<()> {
+        let request = Network::global().client().post(endpoint).json(payload);
+        Network::global()
+            .send(request, Fetch::Requests)
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// The `read` method returns the counts saved in the file at `path`, or none if the file is
+    /// missing or unreadable.
+    fn read(path: &path::Path) -> BTreeMap<String, u64> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// The `backtrace` method returns the frames of the backtrace in the crash report `text`,
+    /// as written by [`crate::Crash::write`], each cut down to the name of its function.  The
+    /// `at` lines with the source path of each frame go, along with everything outside the
+    /// backtrace, like the panic message and the log.
+    pub fn backtrace(text: &str) -> Vec<String> {
+        text.lines()
+            .skip_while(|line| line.trim() != "Backtrace:")
+            .skip(1)
+            .take_while(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                let (number, function) = line.trim().split_once(": ")?;
+                number
+                    .chars()
+                    .all(|c| c.is_ascii_digit())
+                    .then(|| function.trim().to_string())
+            })
+            .collect()
+    }
+}
+
+/// The `Payload` struct holds what [`Reporter::upload`] sends to the endpoint.
+///
+/// * The `app_version` field holds the version of the app.
+/// * The `os` field holds the name of the operating system, like `linux`.
+/// * The `errors` field holds the count of each error code since the last upload.
+/// * The `crashes` field holds the functions in the backtrace of each new crash report, from
+///   [`Reporter::backtrace`].
+#[derive(Debug, Clone, PartialEq, derive_new::new, serde::Serialize)]
+pub struct Payload {
+    app_version: String,
+    os: String,
+    errors: BTreeMap<String, u64>,
+    crashes: Vec<Vec<String>>,
+}
+
+/// The `TALLY_FILE` constant holds the name of the file in the data directory where
+/// [`Reporter`] keeps error counts between runs.
+pub const TALLY_FILE: &str = "telemetry.toml";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backtrace_keeps_only_functions() {
+        let report = "bea_egui 0.1.0\n\
+            Panicked on thread 'main' at src/app.rs:10:5:\n\
+            cannot open /home/someone/parcels.csv\n\
+            \n\
+            Backtrace:\n   \
+            0: std::panicking::begin_panic\n             \
+            at /home/someone/.rustup/library/std/src/panicking.rs:1:1\n   \
+            1: bea_egui::app::App::open_file\n             \
+            at ./src/app.rs:10:5\n\
+            \n\
+            Recent log:\n\
+            GET https://example.gov/parcels?address=123+Main+St\n";
+        assert_eq!(
+            Reporter::backtrace(report),
+            vec![
+                "std::panicking::begin_panic".to_string(),
+                "bea_egui::app::App::open_file".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn backtrace_without_one_is_empty() {
+        assert!(Reporter::backtrace("no backtrace here\nat /home/someone").is_empty());
+    }
+}
@@ -0,0 +1,629 @@
+use crate::{
+    Act, Arrive, Basemap, Choropleth, Concurrency, Context, Contrast, Effects, Excuse, Geocoding,
+    Graphics, Group, Imagery, Layers, LogFormat, Lookup, Recording, Schedule, Scroll, ScrollMode,
+    Sort, Symbols, Timeouts, CHORD_TIMEOUT, CONFIG_VERSION, DOUBLE_PRESS, ENV_PREFIX, HOLD_DELAY,
+    LOG_LEVEL, PREFETCHER_INTERVAL, SECRETS_FILE, SERVICE_NAME, VERSION,
+};
+use std::fmt::Write;
+use std::path;
+use strum::IntoEnumIterator;
+
+/// The `template` module writes the default config file, listing every key the app recognizes.
+///
+/// # Discovering settings with `default_config`
+///
+/// The settings of the app are scattered across the docs for [`crate::Cmd`], [`Scroll`],
+/// [`crate::Hotkeys`] and friends, which is a fine place for them if you read Rust docs for fun.
+/// Everyone else finds settings by opening the config file and looking around.  The
+/// [`default_config`] function writes a config file containing every key recognized by
+/// [`crate::Diagnostic::check`], each with a comment explaining it.  Keys with a default value
+/// appear with that value, and keys without one appear commented out, ready to fill in.
+///
+/// The values come from the same places the app reads its defaults, such as
+/// [`Act::default_binding`], [`Act::default_global`] and [`Scroll::default`], so the file cannot
+/// drift out of date.
+///
+/// The file is written on first run, when [`crate::App::load_config`] finds no config file, and
+/// on demand with the `--write-default-config` argument of [`crate::Cli`].
+pub fn default_config() -> String {
+    let mut text = String::new();
+    // Writing to a String cannot fail.
+    let _ = write_config(&mut text);
+    text
+}
+
+/// The `write_config` function writes the body of [`default_config`] into `text`.
+fn write_config(text: &mut String) -> std::fmt::Result {
+    writeln!(text, "# Tardy configuration")?;
+    writeln!(text)?;
+    writeln!(text, "# Layout of this file, upgraded automatically.")?;
+    writeln!(text, "{VERSION} = {CONFIG_VERSION}")?;
+    writeln!(text)?;
+    writeln!(
+        text,
+        "# Any value here can also be set with an environment variable named {ENV_PREFIX}_ and the"
+    )?;
+    writeln!(
+        text,
+        "# key in capitals, using a double underscore for tables, e.g. {ENV_PREFIX}_SCROLL__MODE."
+    )?;
+    writeln!(text)?;
+    writeln!(text, "# Key bindings")?;
+    writeln!(text, "#")?;
+    writeln!(
+        text,
+        "# Modifiers are Ctrl, Shift, Alt and Super, joined to the key with +, as in Ctrl+Shift+T."
+    )?;
+    writeln!(
+        text,
+        "# Separate strokes with spaces for a sequence, as in \"g b\".  Prefix a key with Double"
+    )?;
+    writeln!(
+        text,
+        "# or Hold for a double-press or a hold, or with physical: for a key position."
+    )?;
+    writeln!(text)?;
+    for act in Act::iter().filter(|act| act != &Act::Be) {
+        writeln!(text, "# {}", act.title())?;
+        match act.default_binding() {
+            Some(binding) => writeln!(text, "{} = \"{binding}\"", act.snake())?,
+            None => writeln!(text, "# {} = \"\"", act.snake())?,
+        }
+    }
+    writeln!(text)?;
+    writeln!(text, "# Startup")?;
+    writeln!(text)?;
+    writeln!(text, "# Data files to open on startup.")?;
+    writeln!(text, "# open = []")?;
+    writeln!(
+        text,
+        "# Workspace to restore on startup, as saved by the save_workspace binding."
+    )?;
+    writeln!(text, "# workspace = \"\"")?;
+    writeln!(text, "# Log filter, as in RUST_LOG.")?;
+    writeln!(text, "# log_level = \"{LOG_LEVEL}\"")?;
+    writeln!(
+        text,
+        "# Log format, Text for people or Json for log shippers."
+    )?;
+    writeln!(text, "log_format = \"{}\"", LogFormat::default())?;
+    writeln!(text, "# Run without windows.")?;
+    writeln!(text, "headless = false")?;
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# Log levels for individual modules, behind the log filter above."
+    )?;
+    writeln!(text, "[logging.filters]")?;
+    writeln!(text, "# galileo = \"warn\"")?;
+    writeln!(text)?;
+
+    let scroll = Scroll::default();
+    let modes = ScrollMode::iter()
+        .map(|mode| mode.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    writeln!(text, "# The scroll wheel.  Modes are {modes}.")?;
+    writeln!(text, "[scroll]")?;
+    writeln!(text, "# Mode of the plain scroll wheel.")?;
+    writeln!(text, "mode = \"{}\"", scroll.mode())?;
+    writeln!(text, "# Reverse the direction of scrolling.")?;
+    writeln!(text, "invert = {}", scroll.invert())?;
+    writeln!(text, "# Multiply the distance of each scroll.")?;
+    writeln!(text, "sensitivity = {:?}", scroll.sensitivity())?;
+    writeln!(text, "# Modes used while holding a modifier key.")?;
+    for (name, mode) in [
+        ("ctrl", scroll.ctrl()),
+        ("shift", scroll.shift()),
+        ("alt", scroll.alt()),
+    ] {
+        match mode {
+            Some(mode) => writeln!(text, "{name} = \"{mode}\"")?,
+            None => writeln!(text, "# {name} = \"\"")?,
+        }
+    }
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# System-wide hotkeys, using key codes like KeyB.  An empty value unbinds one."
+    )?;
+    writeln!(text, "[global]")?;
+    for act in Act::iter() {
+        if let Some(hotkey) = act.default_global() {
+            writeln!(text, "{} = \"{hotkey}\"", act.snake())?;
+        }
+    }
+    writeln!(text)?;
+
+    let groups = Group::iter()
+        .map(|group| format!("\"{}\"", group.to_string().to_lowercase()))
+        .collect::<Vec<String>>()
+        .join(", ");
+    writeln!(text, "# Groups of actions to switch off, from {groups}.")?;
+    writeln!(text, "[groups]")?;
+    writeln!(text, "disabled = []")?;
+    writeln!(text)?;
+
+    writeln!(text, "# Timing of key bindings, in milliseconds.")?;
+    writeln!(text, "[timing]")?;
+    writeln!(text, "# Longest gap between the presses of a double-press.")?;
+    writeln!(text, "double_press = {}", DOUBLE_PRESS.as_millis())?;
+    writeln!(text, "# How long a key must stay down to count as held.")?;
+    writeln!(text, "hold = {}", HOLD_DELAY.as_millis())?;
+    writeln!(
+        text,
+        "# How long to wait for the next stroke of a sequence."
+    )?;
+    writeln!(text, "chord = {}", CHORD_TIMEOUT.as_millis())?;
+    writeln!(text)?;
+
+    writeln!(text, "# Data services.")?;
+    writeln!(text, "[api]")?;
+    writeln!(
+        text,
+        "# API key for data services.  Better kept in {SECRETS_FILE} in the user"
+    )?;
+    writeln!(
+        text,
+        "# config directory, or the system keyring, than here."
+    )?;
+    writeln!(text, "# key = \"\"")?;
+    writeln!(text)?;
+
+    let map = Basemap::default();
+    writeln!(text, "# The base map.")?;
+    writeln!(text, "[map]")?;
+    writeln!(text, "# Latitude and longitude the map opens on.")?;
+    writeln!(text, "center = {:?}", map.center())?;
+    writeln!(
+        text,
+        "# Zoom level the map opens at, from 0 for the whole world to 18."
+    )?;
+    writeln!(text, "zoom = {}", map.zoom())?;
+    writeln!(
+        text,
+        "# Tile server, where {{z}}, {{x}} and {{y}} stand for the zoom level and tile."
+    )?;
+    writeln!(text, "tile_url = \"{}\"", map.tile_url())?;
+    writeln!(text, "# Credit for the tile server.")?;
+    writeln!(text, "attribution = \"{}\"", map.attribution())?;
+    writeln!(
+        text,
+        "# Most disk space for downloaded tiles, in megabytes, or 0 for no limit."
+    )?;
+    writeln!(text, "cache_size = {}", map.cache_size())?;
+    writeln!(text)?;
+
+    let network = Timeouts::default();
+    writeln!(
+        text,
+        "# Limits on network calls, in seconds.  Zero means no limit."
+    )?;
+    writeln!(text, "[network]")?;
+    writeln!(
+        text,
+        "# How long to wait for a server to accept the connection."
+    )?;
+    writeln!(text, "connect = {}", network.connect())?;
+    writeln!(text, "# How long to wait for the next piece of a response.")?;
+    writeln!(text, "read = {}", network.read())?;
+    writeln!(text, "# How long a request may take from start to finish.")?;
+    writeln!(text, "request = {}", network.request())?;
+    writeln!(
+        text,
+        "# How long one attempt at downloading a large file may take."
+    )?;
+    writeln!(text, "download = {}", network.download())?;
+    writeln!(text)?;
+
+    let agents = Schedule::default();
+    writeln!(
+        text,
+        "# Background agents, in seconds between rounds.  Zero turns an agent off."
+    )?;
+    writeln!(text, "[agents]")?;
+    writeln!(text, "# Trim the tile cache to the cache_size of the map.")?;
+    writeln!(text, "janitor = {}", agents.janitor())?;
+    writeln!(text, "# Reload open data files that changed on disk.")?;
+    writeln!(text, "refresher = {}", agents.refresher())?;
+    writeln!(
+        text,
+        "# Fetch the tiles around the opening view, so they are there offline."
+    )?;
+    writeln!(
+        text,
+        "# Off unless your tile server allows bulk downloads; {} is once a day.",
+        PREFETCHER_INTERVAL.as_secs()
+    )?;
+    writeln!(text, "prefetcher = {}", agents.prefetcher())?;
+    writeln!(
+        text,
+        "# Look for new and changed files in the data folders."
+    )?;
+    writeln!(text, "watcher = {}", agents.watcher())?;
+    writeln!(text)?;
+
+    let choropleth = Choropleth::default();
+    writeln!(
+        text,
+        "# Shading the rows of data files by a column, with colors safe for color blindness."
+    )?;
+    writeln!(text, "[choropleth]")?;
+    writeln!(text, "# column = \"value\"")?;
+    writeln!(
+        text,
+        "# Sequential: Blues, Greens, Oranges, Purples, YlGnBu, YlOrRd.  Diverging: BrBG, PiYG,"
+    )?;
+    writeln!(
+        text,
+        "# PuOr, RdBu, and RdYlGn and Spectral, which the color blind cannot read."
+    )?;
+    writeln!(text, "scheme = \"{}\"", choropleth.scheme())?;
+    writeln!(text, "classes = {}", choropleth.classes())?;
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# Folders where other tools export data, watched for new CSV and GeoJSON files."
+    )?;
+    writeln!(text, "[data]")?;
+    writeln!(text, "# dirs = [\"/srv/gis/exports\"]")?;
+    writeln!(text)?;
+
+    let geocoding = Geocoding::default();
+    writeln!(
+        text,
+        "# Finding the addresses in a CSV file, for the geocode_addresses action."
+    )?;
+    writeln!(text, "[geocoding]")?;
+    writeln!(text, "# Census, Nominatim or Arcgis.")?;
+    writeln!(text, "service = \"{}\"", geocoding.service())?;
+    writeln!(
+        text,
+        "# The address of the service, such as the findAddressCandidates of a county locator."
+    )?;
+    writeln!(
+        text,
+        "# url = \"https://gis.example.gov/locator/GeocodeServer/findAddressCandidates\""
+    )?;
+    writeln!(
+        text,
+        "# The columns that make up an address, in order, if there is no address column."
+    )?;
+    writeln!(
+        text,
+        "# columns = [\"street\", \"city\", \"state\", \"zip\"]"
+    )?;
+    writeln!(
+        text,
+        "# Milliseconds between requests, if not what the service asks for."
+    )?;
+    writeln!(text, "# delay = 1000")?;
+    writeln!(
+        text,
+        "# The lowest score from 0 to 100 of a match that goes on the map."
+    )?;
+    writeln!(text, "score = {}", geocoding.score())?;
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# Layers for the data catalog, added to or replacing the built-in ones by name."
+    )?;
+    writeln!(text, "[catalog]")?;
+    writeln!(text, "# List the built-in FEMA flood hazard layer.")?;
+    writeln!(text, "builtin = true")?;
+    writeln!(
+        text,
+        "# The parcel lookup reads the layer named in the [lookup] table below."
+    )?;
+    writeln!(text, "# [catalog.layers.Parcels]")?;
+    writeln!(text, "# group = \"City of Grants Pass\"")?;
+    writeln!(
+        text,
+        "# Arcgis for a layer of a map or feature service, or Geojson for a file."
+    )?;
+    writeln!(text, "# feed = \"Arcgis\"")?;
+    writeln!(
+        text,
+        "# url = \"https://gis.example.gov/arcgis/rest/services/Parcels/MapServer/0\""
+    )?;
+    writeln!(
+        text,
+        "# about = \"tax lots with their map and tax lot numbers\""
+    )?;
+    writeln!(text)?;
+
+    let lookup = Lookup::default();
+    writeln!(
+        text,
+        "# Looking up parcels, for the look_up_parcel action and presses on the map."
+    )?;
+    writeln!(text, "[lookup]")?;
+    writeln!(
+        text,
+        "# The names of the parcel and address layers in the data catalog."
+    )?;
+    writeln!(text, "parcels = \"{}\"", lookup.parcels())?;
+    writeln!(text, "addresses = \"{}\"", lookup.addresses())?;
+    writeln!(
+        text,
+        "# The fields holding the map and tax lot number, and the full address."
+    )?;
+    writeln!(text, "taxlot = \"{}\"", lookup.taxlot())?;
+    writeln!(text, "address = \"{}\"", lookup.address())?;
+    writeln!(
+        text,
+        "# The fields of a parcel to show, in order, if not all of them."
+    )?;
+    writeln!(text, "# fields = [\"ZONE\", \"ACRES\", \"OWNER\"]")?;
+    writeln!(
+        text,
+        "# Links to show, with a field name in braces standing for its value."
+    )?;
+    writeln!(text, "# [lookup.links]")?;
+    writeln!(
+        text,
+        "# Assessor = \"https://assessor.example.gov/property?maptaxlot={{MAPTAXLOT}}\""
+    )?;
+    writeln!(text)?;
+
+    let imagery = Imagery::default();
+    writeln!(
+        text,
+        "# Searching a STAC catalog for imagery, for the search_imagery action."
+    )?;
+    writeln!(text, "[imagery]")?;
+    writeln!(text, "catalog = \"{}\"", imagery.catalog())?;
+    writeln!(
+        text,
+        "# The collection to search, such as naip on the Planetary Computer."
+    )?;
+    writeln!(text, "collection = \"{}\"", imagery.collection())?;
+    writeln!(text, "# How far back to search, in days.")?;
+    writeln!(text, "days = {}", imagery.days())?;
+    writeln!(text, "# The most cloud of a scene to keep, in percent.")?;
+    writeln!(text, "cloud_cover = {}", imagery.cloud_cover())?;
+    writeln!(text, "# The most scenes to keep.")?;
+    writeln!(text, "limit = {}", imagery.limit())?;
+    writeln!(
+        text,
+        "# The names of the assets to show for each scene, in order."
+    )?;
+    writeln!(
+        text,
+        "assets = [{}]",
+        imagery
+            .assets()
+            .iter()
+            .map(|asset| format!("\"{asset}\""))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )?;
+    writeln!(text)?;
+
+    let effects = Effects::default();
+    writeln!(
+        text,
+        "# Custom WGSL shaders run over the map in order, relative to this file."
+    )?;
+    writeln!(text, "[effects]")?;
+    writeln!(text, "# files = [\"shaders/hillshade.wgsl\"]")?;
+    writeln!(
+        text,
+        "# Keep drawing while the shaders move with time, for animations like flowing water."
+    )?;
+    writeln!(text, "animate = {}", effects.animate())?;
+    writeln!(text)?;
+
+    let graphics = Graphics::default();
+    writeln!(text, "# Drawing windows.")?;
+    writeln!(text, "[graphics]")?;
+    writeln!(
+        text,
+        "# When frames reach the screen: Fifo waits for the monitor and never tears, Mailbox"
+    )?;
+    writeln!(
+        text,
+        "# shows the latest frame without tearing, and Immediate has the least lag but tears."
+    )?;
+    writeln!(text, "present_mode = \"{}\"", graphics.present_mode())?;
+    writeln!(
+        text,
+        "# Samples per pixel to smooth the edges of lines, 1 for none.  Cards that cannot manage"
+    )?;
+    writeln!(text, "# the count smooth each frame with FXAA instead.")?;
+    writeln!(text, "samples = {}", graphics.samples())?;
+    writeln!(
+        text,
+        "# Smooth each frame with FXAA, which is softer but works on any card."
+    )?;
+    writeln!(text, "fxaa = {}", graphics.fxaa())?;
+    writeln!(
+        text,
+        "# Which graphics card to prefer, HighPerformance or LowPower, read on the next start."
+    )?;
+    writeln!(text, "power = \"{}\"", graphics.power())?;
+    writeln!(
+        text,
+        "# Part of the name of the card to use instead, as listed by the show_gpu action."
+    )?;
+    writeln!(text, "# adapter = \"\"")?;
+    writeln!(
+        text,
+        "# Draw on the processor instead of a graphics card.  Slow, but works on any machine."
+    )?;
+    writeln!(text, "software = {}", graphics.software())?;
+    writeln!(
+        text,
+        "# When map windows draw: Reactive when something changes, Continuous all the time."
+    )?;
+    writeln!(text, "pacing = \"{}\"", graphics.pacing())?;
+    writeln!(
+        text,
+        "# Frames per second while a window draws continuously."
+    )?;
+    writeln!(text, "fps = {}", graphics.fps())?;
+    writeln!(
+        text,
+        "# Colors to draw in: Srgb, or Wide for wide-gamut displays, read on the next start."
+    )?;
+    writeln!(text, "gamut = \"{}\"", graphics.gamut())?;
+    writeln!(text)?;
+
+    let recording = Recording::default();
+    writeln!(text, "# Recording the map as a clip.")?;
+    writeln!(text, "[recording]")?;
+    writeln!(
+        text,
+        "# Gif plays anywhere a picture does.  Mp4 is smaller and needs ffmpeg installed."
+    )?;
+    writeln!(text, "format = \"{}\"", recording.format())?;
+    writeln!(text, "# Most frames to capture per second.")?;
+    writeln!(text, "fps = {}", recording.fps())?;
+    writeln!(
+        text,
+        "# Longest recording in seconds, after which it stops by itself."
+    )?;
+    writeln!(text, "seconds = {}", recording.seconds())?;
+    writeln!(text)?;
+
+    let symbols = Symbols::default();
+    writeln!(
+        text,
+        "# Icons for point layers, from a folder of SVG and PNG files."
+    )?;
+    writeln!(text, "[symbols]")?;
+    writeln!(text, "# dir = \"icons\"")?;
+    writeln!(
+        text,
+        "# Size of an icon in pixels, before the scale of the screen."
+    )?;
+    writeln!(text, "size = {}", symbols.size())?;
+    writeln!(
+        text,
+        "# The column of a point layer that picks the icon, and the icon for other values."
+    )?;
+    writeln!(text, "# column = \"kind\"")?;
+    writeln!(text, "# fallback = \"pin\"")?;
+    writeln!(text)?;
+    writeln!(
+        text,
+        "# The icon for each value of the column, named for its file."
+    )?;
+    writeln!(text, "[symbols.icons]")?;
+    writeln!(text, "# school = \"school\"")?;
+    writeln!(text)?;
+
+    writeln!(text, "# The order of the rows of exported tables.")?;
+    writeln!(text, "[sort]")?;
+    writeln!(
+        text,
+        "# The column to order the rows by.  Tables without it keep the order of their file."
+    )?;
+    writeln!(text, "# column = \"value\"")?;
+    writeln!(text, "# Put the largest value first.")?;
+    writeln!(text, "descending = {}", Sort::default().descending())?;
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# The line describing the view of the map, for screen readers and the clipboard."
+    )?;
+    writeln!(text, "[summary]")?;
+    writeln!(
+        text,
+        "# The name of the place the map covers, in place of the latitude and longitude."
+    )?;
+    writeln!(text, "# place = \"Josephine County\"")?;
+    writeln!(
+        text,
+        "# The column of the data files to report the median of."
+    )?;
+    writeln!(text, "# column = \"value\"")?;
+    writeln!(text)?;
+
+    let tasks = Concurrency::default();
+    writeln!(text, "# Background jobs.")?;
+    writeln!(text, "[tasks]")?;
+    writeln!(
+        text,
+        "# Most jobs the user is waiting on, like fetching visible tiles, to run at once."
+    )?;
+    writeln!(text, "interactive = {}", tasks.interactive())?;
+    writeln!(
+        text,
+        "# Most bulk jobs, like prefetching, to run at once.  They wait for interactive jobs."
+    )?;
+    writeln!(text, "bulk = {}", tasks.bulk())?;
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# Reporting crashes and error counts home, off unless enabled."
+    )?;
+    writeln!(text, "[telemetry]")?;
+    writeln!(
+        text,
+        "# Send crash backtraces and a count of each error code on startup."
+    )?;
+    writeln!(text, "enabled = false")?;
+    writeln!(text, "# Address to send them to.")?;
+    writeln!(text, "# endpoint = \"\"")?;
+    writeln!(text)?;
+
+    writeln!(text, "# The look of the app.")?;
+    writeln!(text, "[theme]")?;
+    writeln!(
+        text,
+        "# High contrast: System follows the operating system, or choose Standard or High."
+    )?;
+    writeln!(text, "contrast = \"{}\"", Contrast::default())?;
+    writeln!(
+        text,
+        "# Start with bigger lines, touch targets and symbols, for showing the map across a desk."
+    )?;
+    writeln!(text, "large = false")?;
+    writeln!(text)?;
+
+    writeln!(
+        text,
+        "# Exporting traces and metrics to an OpenTelemetry collector, off without an endpoint."
+    )?;
+    writeln!(text, "[otlp]")?;
+    writeln!(text, "# Address of the collector, taking OTLP over HTTP.")?;
+    writeln!(text, "# endpoint = \"http://localhost:4318\"")?;
+    writeln!(text, "# Name to file our traces and metrics under.")?;
+    writeln!(text, "service_name = \"{SERVICE_NAME}\"")?;
+    Ok(())
+}
+
+/// The `write_default_config` function writes [`default_config`] to `path`, creating any missing
+/// parent directories.
+///
+/// Will [`Excuse::ConfigExists`] rather than overwrite an existing file,
+/// [`Excuse::ConfigFormat`] if `path` names a YAML or JSON file, and [`crate::Blame::Io`] if
+/// writing fails.
+#[tracing::instrument]
+pub fn write_default_config(path: &path::Path) -> Arrive<()> {
+    if path.exists() {
+        tracing::warn!("Not overwriting {}.", path.display());
+        return Err(Excuse::ConfigExists.into());
+    }
+    if Layers::format(path).is_some_and(|format| format != config::FileFormat::Toml) {
+        tracing::warn!(
+            "The default config is TOML, not writing {}.",
+            path.display()
+        );
+        return Err(Excuse::ConfigFormat.into());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, default_config())
+        .with_context(|| format!("writing {}", path.display()))?;
+    tracing::info!("Default config written to {}.", path.display());
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use crate::{
+    FOCUS_COLOR, FOCUS_RING, INK_COLOR, INK_WIDTH, PANEL_COLOR, PANEL_TEXT, SELECTION_COLOR,
+};
+
+/// The `theme` module provides the [`Contrast`] enum, which chooses between the standard look of
+/// the app and a high-contrast one, and the [`Palette`] struct, which holds the colors of the
+/// choice.
+///
+/// # Seeing the map clearly with `Contrast`
+///
+/// Some of our users have low vision, and some work outdoors on tablets in full sun.  A base map
+/// of soft greens and greys washes out for both, and an amber focus ring three pixels wide is
+/// easy to lose against it.  The `contrast` key of the `[theme]` table (see [`crate::Theme`])
+/// asks for a high-contrast look, with three choices:
+///
+/// * `System`, the default, follows the contrast setting of the operating system, where we can
+///   find it, with [`Contrast::detect`].
+/// * `Standard` keeps the standard look whatever the system says.
+/// * `High` turns high contrast on whatever the system says.
+///
+/// [`Contrast::palette`] turns the choice into a [`Palette`].  The high-contrast palette draws
+/// the focus ring of [`crate::Focus`] wider and in yellow, the color high-contrast themes use for
+/// focus on every platform, and runs the [`CONTRAST_SHADER`] over the map ahead of the effects of
+/// the user, as an [`crate::Effect`] of its own, stretching light and dark apart.  What the app
+/// draws over the map takes its colors from the palette too: the annotations of
+/// [`crate::Ink`], in magenta and wider, the parcel selected by a lookup, in cyan, and the
+/// [`crate::Panel`] boxes of text, in white on black.
+///
+/// ## Update 0.1.4
+///
+/// The counter staff turn a monitor around to show a visitor the map, and lines and icons sized
+/// for someone at arm's length are lost on someone across the counter.  The large-UI mode makes
+/// everything bigger at once, by [`LARGE_SCALE`]: the focus ring, the lines of annotations and
+/// the selection, through [`crate::Lens::ui_scale`], the distance a finger may stray and still
+/// count as a tap, through [`crate::Gestures::touch`], the icons of point symbols, through
+/// [`crate::Lens::symbol_scale`], and the text of the [`crate::Panel`] boxes over the map.  The
+/// `large` key of the `[theme]` table starts the app in the mode, and
+/// [`crate::Act::ToggleLargeUi`] turns it on and off in one click, until the config reloads.
+/// The window titles and the log keep the size the system gives them.
+///
+/// No platform API tells [`winit`] about contrast, so [`Contrast::detect`] asks each system in
+/// its own way: the `HighContrast` flags in the registry on Windows, the `increaseContrast`
+/// accessibility default on macOS, and the `high-contrast` key of GNOME or a high-contrast
+/// `GTK_THEME` elsewhere.  A system we cannot ask reads as standard.  We ask when the config
+/// loads, so a change to the system setting reaches the app on [`crate::Act::ReloadConfig`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+    serde::Serialize,
+)]
+pub enum Contrast {
+    /// The `System` variant indicates the contrast setting of the operating system.
+    #[default]
+    System,
+    /// The `Standard` variant indicates the standard look.
+    Standard,
+    /// The `High` variant indicates the high-contrast look.
+    High,
+}
+
+/// Reads the name of the contrast setting whatever its case, as described in [`crate::choice`].
+impl<'de> serde::Deserialize<'de> for Contrast {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::choice(deserializer)
+    }
+}
+
+impl Contrast {
+    /// The `palette` method returns the [`Palette`] for the contrast, asking the system with
+    /// [`Contrast::detect`] if the choice is `System`.
+    pub fn palette(&self) -> Palette {
+        let high = match self {
+            Self::System => Self::detect(),
+            Self::Standard => false,
+            Self::High => true,
+        };
+        match high {
+            true => Palette::high(),
+            false => Palette::standard(),
+        }
+    }
+
+    /// The `detect` method returns `true` if the operating system asks for high contrast, as
+    /// described in the [`Contrast`] docs.
+    pub fn detect() -> bool {
+        let high = Self::ask();
+        tracing::trace!("System high contrast: {high}.");
+        high
+    }
+
+    /// The `ask` method reads the high contrast flag from the registry.  Bit one of the `Flags`
+    /// value is set while high contrast is on.
+    #[cfg(target_os = "windows")]
+    fn ask() -> bool {
+        let output = Self::run(
+            "reg",
+            &[
+                "query",
+                r"HKCU\Control Panel\Accessibility\HighContrast",
+                "/v",
+                "Flags",
+            ],
+        );
+        output
+            .split_whitespace()
+            .last()
+            .and_then(|flags| u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok())
+            .is_some_and(|flags| flags & 1 == 1)
+    }
+
+    /// The `ask` method reads the increased contrast setting from the accessibility defaults.
+    #[cfg(target_os = "macos")]
+    fn ask() -> bool {
+        let output = Self::run(
+            "defaults",
+            &["read", "com.apple.universalaccess", "increaseContrast"],
+        );
+        output.trim() == "1"
+    }
+
+    /// The `ask` method reads the high contrast key of GNOME, or failing that, looks for a
+    /// high-contrast theme in `GTK_THEME`.
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    fn ask() -> bool {
+        let output = Self::run(
+            "gsettings",
+            &["get", "org.gnome.desktop.a11y.interface", "high-contrast"],
+        );
+        output.trim() == "true"
+            || std::env::var("GTK_THEME")
+                .is_ok_and(|theme| theme.to_lowercase().contains("highcontrast"))
+    }
+
+    /// The `run` method returns what `program` prints with `args`, or nothing if it does not run.
+    fn run(program: &str, args: &[&str]) -> String {
+        match std::process::Command::new(program).args(args).output() {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+            Ok(_) => String::new(),
+            Err(e) => {
+                tracing::trace!("Could not run {program}: {e}");
+                String::new()
+            }
+        }
+    }
+}
+
+/// The `Palette` struct holds the colors and sizes of what the app draws over the map, as
+/// chosen by [`Contrast::palette`].
+///
+/// * The `high` field indicates the high-contrast look, which runs the [`CONTRAST_SHADER`].
+/// * The `focus` field holds the color of the focus ring, in sRGB with alpha.
+/// * The `ring` field holds the width of the focus ring, in logical pixels, before the scale.
+/// * The `ink` field holds the color of the annotations of [`crate::Ink`].
+/// * The `selection` field holds the color of the selection, from [`crate::Ink::select`].
+/// * The `line` field holds the width of the lines of both, in logical pixels, before the scale.
+/// * The `panel` field holds the color of the box of a [`crate::Panel`].
+/// * The `text` field holds the color of the text of a [`crate::Panel`].
+/// * The `large` field indicates the large-UI mode, which scales by [`LARGE_SCALE`].
+#[derive(Debug, Copy, Clone, PartialEq, derive_getters::Getters)]
+pub struct Palette {
+    high: bool,
+    focus: [u8; 4],
+    ring: f64,
+    ink: [u8; 4],
+    selection: [u8; 4],
+    line: f64,
+    panel: [u8; 4],
+    text: [u8; 4],
+    large: bool,
+}
+
+impl Palette {
+    /// The `standard` method returns the palette of the standard look, with the focus ring in
+    /// [`FOCUS_COLOR`] and [`FOCUS_RING`] pixels wide, and lines in [`INK_COLOR`] and
+    /// [`SELECTION_COLOR`], [`INK_WIDTH`] pixels wide, and panels in [`PANEL_TEXT`] on
+    /// [`PANEL_COLOR`].
+    pub fn standard() -> Self {
+        Self {
+            high: false,
+            focus: FOCUS_COLOR,
+            ring: FOCUS_RING,
+            ink: INK_COLOR,
+            selection: SELECTION_COLOR,
+            line: INK_WIDTH,
+            panel: PANEL_COLOR,
+            text: PANEL_TEXT,
+            large: false,
+        }
+    }
+
+    /// The `high` method returns the palette of the high-contrast look, with the focus ring in
+    /// [`HIGH_FOCUS_COLOR`] and [`HIGH_FOCUS_RING`] pixels wide, and lines in
+    /// [`HIGH_INK_COLOR`] and [`HIGH_SELECTION_COLOR`], [`HIGH_INK_WIDTH`] pixels wide, and
+    /// panels in [`HIGH_PANEL_TEXT`] on [`HIGH_PANEL_COLOR`].
+    pub fn high() -> Self {
+        Self {
+            high: true,
+            focus: HIGH_FOCUS_COLOR,
+            ring: HIGH_FOCUS_RING,
+            ink: HIGH_INK_COLOR,
+            selection: HIGH_SELECTION_COLOR,
+            line: HIGH_INK_WIDTH,
+            panel: HIGH_PANEL_COLOR,
+            text: HIGH_PANEL_TEXT,
+            large: false,
+        }
+    }
+
+    /// The `sized` method returns the palette in the large-UI mode if `large`, or at the usual
+    /// size otherwise.
+    pub fn sized(mut self, large: bool) -> Self {
+        self.large = large;
+        self
+    }
+
+    /// The `scale` method returns how much bigger the palette draws the strokes over the map, the
+    /// hit targets and the symbols: [`LARGE_SCALE`] in the large-UI mode, and one otherwise.
+    pub fn scale(&self) -> f64 {
+        match self.large {
+            true => LARGE_SCALE,
+            false => 1.0,
+        }
+    }
+
+    /// The `shaders` method returns the name and text of the shaders the palette runs over the
+    /// map, ahead of the effects of the user, in the form of [`crate::Effects::sources`].
+    pub fn shaders(&self) -> Vec<(String, String)> {
+        match self.high {
+            true => vec![("high contrast".to_string(), CONTRAST_SHADER.to_string())],
+            false => Vec::new(),
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// The `HIGH_FOCUS_COLOR` constant holds the color of the focus ring in high contrast, a pure
+/// yellow.
+pub const HIGH_FOCUS_COLOR: [u8; 4] = [255, 255, 0, 255];
+
+/// The `HIGH_FOCUS_RING` constant holds the width of the focus ring in high contrast, in logical
+/// pixels.
+pub const HIGH_FOCUS_RING: f64 = 6.0;
+
+/// The `HIGH_INK_COLOR` constant holds the color of annotations in high contrast, a pure
+/// magenta, which no base map uses.
+pub const HIGH_INK_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// The `HIGH_SELECTION_COLOR` constant holds the color of the selection in high contrast, a pure
+/// cyan.
+pub const HIGH_SELECTION_COLOR: [u8; 4] = [0, 255, 255, 255];
+
+/// The `HIGH_INK_WIDTH` constant holds the width of the lines of annotations and the selection
+/// in high contrast, in logical pixels.
+pub const HIGH_INK_WIDTH: f64 = 4.0;
+
+/// The `HIGH_PANEL_COLOR` constant holds the color of the box of a [`crate::Panel`] in high
+/// contrast, a pure black.
+pub const HIGH_PANEL_COLOR: [u8; 4] = [0, 0, 0, 255];
+
+/// The `HIGH_PANEL_TEXT` constant holds the color of the text of a [`crate::Panel`] in high
+/// contrast, a pure white.
+pub const HIGH_PANEL_TEXT: [u8; 4] = [255, 255, 255, 255];
+
+/// The `LARGE_SCALE` constant holds how much bigger the large-UI mode draws everything.  Half
+/// again as big reads across a counter, and still leaves room for the map on a laptop screen.
+pub const LARGE_SCALE: f64 = 1.5;
+
+/// The `CONTRAST_SHADER` constant holds the fragment shader the high-contrast [`Palette`] runs
+/// over the map, in the form of an [`crate::Effect`].  The frame reads in linear light, so we
+/// stretch each color away from middle grey on the sRGB curve, where the eye judges contrast,
+/// and go back to linear light to write it.
+pub const CONTRAST_SHADER: &str = r#"
+const STRETCH: f32 = 1.6;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(frame, frame_sampler, in.uv);
+    let curved = pow(color.rgb, vec3<f32>(1.0 / 2.2));
+    let stretched = clamp((curved - 0.5) * STRETCH + 0.5, vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(pow(stretched, vec3<f32>(2.2)), color.a);
+}
+"#;
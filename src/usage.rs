@@ -0,0 +1,152 @@
+use crate::{Arrive, Context, Home};
+use std::path;
+use strum::IntoEnumIterator;
+
+/// The `usage` module provides the [`Usage`] struct, which measures how much memory and disk the
+/// app is using, and the [`Cache`] enum, which names the caches the user can clear.
+///
+/// # Keeping an eye on resources with `Usage`
+///
+/// The tile cache grows as the user wanders the map, and on a laptop with a small disk, the
+/// first sign of trouble is a full drive.  [`Usage::measure`] adds up the size of each [`Cache`]
+/// under [`Home::cache`], along with the memory held by the app, and shows them in one
+/// [`crate::Panel`] over the map when the user asks with [`crate::Act::ShowUsage`].  The user can
+/// then empty a cache with [`crate::Act::ClearTileCache`] or [`crate::Act::ClearResponseCache`].
+///
+/// Neither [`galileo`] nor the standard library will tell us how much memory a layer holds, so
+/// we report the resident memory of the whole process instead, which is mostly layers.  Only
+/// Linux tells us even that much without a new dependency, so on other platforms the memory
+/// reads as unknown.
+///
+/// * The `memory` field holds the resident memory of the process in bytes, if we can tell.
+/// * The `caches` field holds the size of each [`Cache`] in bytes.
+#[derive(Debug, Clone, PartialEq, Eq, derive_getters::Getters)]
+pub struct Usage {
+    memory: Option<u64>,
+    caches: Vec<(Cache, u64)>,
+}
+
+impl Usage {
+    /// The `measure` method reads the memory of the process and the size of each [`Cache`] under
+    /// the cache directory of `home`.  A cache we cannot read counts as empty.
+    #[tracing::instrument(skip_all)]
+    pub fn measure(home: &Home) -> Self {
+        let caches = Cache::iter()
+            .map(|cache| (cache, cache.size(home)))
+            .collect::<Vec<(Cache, u64)>>();
+        Self {
+            memory: Self::memory(),
+            caches,
+        }
+    }
+
+    /// The `memory` method returns the resident memory of the process in bytes, read from the
+    /// `VmRSS` line of `/proc/self/status`, or [`None`] on platforms without one.
+    fn memory() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+        Some(kilobytes * 1024)
+    }
+
+    /// The `megabytes` method formats a size in `bytes` as megabytes, to one decimal place.
+    pub fn megabytes(bytes: u64) -> String {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// Prints the report over several lines, one for the memory and one for each cache.
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.memory {
+            Some(memory) => write!(f, "Memory: {}", Self::megabytes(memory))?,
+            None => write!(f, "Memory: unknown")?,
+        }
+        for (cache, size) in &self.caches {
+            write!(f, "\n{} cache: {}", cache, Self::megabytes(*size))?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Cache` enum names the caches the app keeps under [`Home::cache`].
+///
+/// * The `Tiles` variant holds the map tiles downloaded by [`crate::Map`], under [`TILE_DIR`].
+/// * The `Responses` variant holds responses from the BEA API, under [`RESPONSE_DIR`].  Nothing
+///   writes there yet, but the directory is set aside so the API client has a place to put them.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    derive_more::Display,
+)]
+pub enum Cache {
+    /// The `Tiles` variant indicates the map tile cache.
+    Tiles,
+    /// The `Responses` variant indicates the BEA response cache.
+    Responses,
+}
+
+impl Cache {
+    /// The `dir` method returns the directory of the cache under the cache directory of `home`.
+    pub fn dir(&self, home: &Home) -> path::PathBuf {
+        let name = match self {
+            Self::Tiles => TILE_DIR,
+            Self::Responses => RESPONSE_DIR,
+        };
+        home.cache().join(name)
+    }
+
+    /// The `size` method returns the total size in bytes of the files in the cache.  Files we
+    /// cannot inspect, and a missing directory, count as empty.
+    pub fn size(&self, home: &Home) -> u64 {
+        let mut total = 0;
+        let mut dirs = vec![self.dir(home)];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                } else {
+                    total += metadata.len();
+                }
+            }
+        }
+        total
+    }
+
+    /// The `clear` method deletes everything in the cache, and returns the number of bytes
+    /// freed.  A missing directory has nothing to clear.
+    ///
+    /// Will [`crate::Blame::Io`] if the directory cannot be removed.
+    #[tracing::instrument(skip_all)]
+    pub fn clear(&self, home: &Home) -> Arrive<u64> {
+        let dir = self.dir(home);
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let size = self.size(home);
+        std::fs::remove_dir_all(&dir).with_context(|| format!("clearing {}", dir.display()))?;
+        tracing::info!("Cleared {} from the {self} cache.", Usage::megabytes(size));
+        Ok(size)
+    }
+}
+
+/// The `TILE_DIR` constant holds the name of the directory under [`Home::cache`] where
+/// [`crate::Map`] keeps downloaded tiles.
+pub const TILE_DIR: &str = "tiles";
+
+/// The `RESPONSE_DIR` constant holds the name of the directory under [`Home::cache`] set aside
+/// for responses from the BEA API.
+pub const RESPONSE_DIR: &str = "responses";
@@ -1,19 +1,133 @@
 /// The `utils` module hosts global functions that do not belong to any particular data type.
+use crate::{Exporter, Latency, LogFormat, Logging, Recent, Verbosity, LOG_LEVEL};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// The `trace_init` function initializing logging using the [`tracing`] and [`tracing_subscriber`]
 /// crates.
 /// Pass the desired log level into the environment when running the app from cargo.
 /// E.g. `$RUST_LOG="trace" cargo run` for debugging.
-pub fn trace_init() {
+///
+/// The `level` parameter holds the `--log-level` argument from [`crate::Cli`], if any, which
+/// takes precedence over the environment.  A level that fails to parse falls back to the
+/// environment as if it were not there.  The `logging` parameter holds the per-module filters
+/// from the `[logging.filters]` table, which go in front of the level, as described in
+/// [`Logging`].  The filter can change later through [`Verbosity::global`].
+///
+/// The `format` parameter picks the [`LogFormat`] of the usual output, from the `log_format` key
+/// of the config or the `--log-format` argument.  Besides the usual output, each log line also
+/// goes to the returned [`Recent`] as plain text, without colors, so that a [`crate::Crash`]
+/// report can show what led up to a panic whatever the format.  The [`Latency`] collector from
+/// [`Latency::global`] also watches the spans, timing downloads for the [`crate::Overlay`].
+///
+/// The `exporter` parameter holds the [`Exporter`] from the `[otlp]` table of the config, if
+/// any, which sends spans and metrics to an OpenTelemetry collector as well.
+pub fn trace_init(
+    level: Option<&str>,
+    format: LogFormat,
+    logging: &Logging,
+    exporter: Option<&Exporter>,
+) -> Recent {
+    let parses = |directives: &String| tracing_subscriber::EnvFilter::try_new(directives).is_ok();
+    let base = level
+        .map(str::to_string)
+        .filter(parses)
+        .or_else(|| {
+            std::env::var(tracing_subscriber::EnvFilter::DEFAULT_ENV)
+                .ok()
+                .filter(parses)
+        })
+        .unwrap_or_else(|| LOG_LEVEL.to_string());
+    // A filter from the config that does not parse costs us the filters, not the level.
+    let filter = tracing_subscriber::EnvFilter::try_new(logging.merge(&base))
+        .or_else(|_| tracing_subscriber::EnvFilter::try_new(&base))
+        .unwrap_or_else(|_| LOG_LEVEL.into());
+    let filter = Verbosity::global().connect(filter);
+    let recent = Recent::default();
+    // Only one of the two is Some, and a layer that is None does nothing.
+    let (text, json) = match format {
+        LogFormat::Text => (Some(tracing_subscriber::fmt::layer()), None),
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true),
+            ),
+        ),
+    };
+    let spans =
+        exporter.map(|exporter| tracing_opentelemetry::layer().with_tracer(exporter.tracer()));
+    let metrics =
+        exporter.map(|exporter| tracing_opentelemetry::MetricsLayer::new(exporter.meter().clone()));
     if tracing_subscriber::registry()
+        .with(filter)
+        .with(text)
+        .with(json)
+        .with(Latency::global())
+        .with(spans)
+        .with(metrics)
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "bea_egui=info".into()),
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(recent.clone()),
         )
-        .with(tracing_subscriber::fmt::layer())
         .try_init()
         .is_ok()
     {};
     tracing::info!("Loading bea_egui ...");
+    recent
+}
+
+/// The `timestamp` function writes `seconds` since 1970 as an RFC 3339 time in UTC, as a STAC
+/// catalog expects and a spreadsheet reads, without pulling in a calendar crate.
+pub fn timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let rest = seconds % 86_400;
+    // Turns days since 1970 into a civil date, after Howard Hinnant.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
+
+/// The `choice` function reads a name from the config into the variant of `T` it names, for an
+/// enum of the config with a fixed set of names, like [`LogFormat`].  People type a name the
+/// way they last saw it, in these docs, on the command line or in another program, so we take
+/// it whatever its case and with or without underscores, as in `LowPower`, `lowpower` or
+/// `low_power`, and compare it to the [`std::fmt::Display`] of each variant with
+/// [`fold_choice`], as [`crate::Diagnostic`] does.  Call it from the [`serde::Deserialize`] of
+/// the enum.
+pub fn choice<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: strum::IntoEnumIterator + std::fmt::Display,
+{
+    let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+    T::iter()
+        .find(|variant| fold_choice(&variant.to_string()) == fold_choice(&name))
+        .ok_or_else(|| {
+            let names = T::iter()
+                .map(|variant| variant.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            serde::de::Error::custom(format!("unknown value {name}, expected one of {names}"))
+        })
+}
+
+/// The `fold_choice` function returns `name` in lowercase without underscores, so the names the
+/// config takes from a fixed set compare the same however they are written, as in [`choice`].
+pub fn fold_choice(name: &str) -> String {
+    name.replace('_', "").to_lowercase()
 }
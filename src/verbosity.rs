@@ -0,0 +1,124 @@
+use crate::{Arrive, Context};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// The `verbosity` module provides the [`Verbosity`] struct, which changes the log filter while
+/// the app runs.
+///
+/// # Turning up the logs with `Verbosity`
+///
+/// When the map misbehaves, we want trace logs from `bea_egui::map`, and only from there, since
+/// trace logs from everything bury the interesting lines.  Setting `RUST_LOG` means closing the
+/// app, and with it whatever state got the map into trouble.  Instead, [`crate::trace_init`]
+/// wraps the [`EnvFilter`] in a [`reload::Layer`], and hands the other end to the `Verbosity`
+/// collector, so we can swap the filter without a restart:
+///
+/// * [`Verbosity::set`] replaces the filter with new directives, in the syntax of `RUST_LOG`, as
+///   in `bea_egui=info,bea_egui::map=trace`.  The [`crate::App`] calls it when a reload of the
+///   config finds a new `log_level`, so editing the config is enough.
+/// * [`Verbosity::boost`] turns one target up to trace on top of the current filter, for
+///   [`crate::Act::TraceLogs`].
+/// * [`Verbosity::reset`] goes back to the filter we started with, or the last one from
+///   [`Verbosity::set`], for [`crate::Act::ResetLogs`].
+///
+/// There is one filter for the whole app, because there is one subscriber, and
+/// [`Verbosity::global`] returns a handle to it.  Until [`crate::trace_init`] connects it,
+/// changing the filter does nothing.
+///
+/// * The `handle` field holds the handle to the reloadable filter, once logging has started.
+/// * The `base` field holds the directives that [`Verbosity::reset`] goes back to.
+#[derive(Debug, Clone, Default)]
+pub struct Verbosity {
+    handle: Arc<OnceLock<reload::Handle<EnvFilter, Registry>>>,
+    base: Arc<Mutex<String>>,
+}
+
+impl Verbosity {
+    /// The `global` method returns a handle to the filter shared by the app.
+    pub fn global() -> Self {
+        static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+        VERBOSITY.get_or_init(Verbosity::default).clone()
+    }
+
+    /// The `connect` method wraps `filter` in a [`reload::Layer`] for the subscriber, and keeps
+    /// the handle to it.  Only the first connection takes, since there is only one subscriber.
+    pub fn connect(&self, filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+        if let Ok(mut base) = self.base.lock() {
+            *base = filter.to_string();
+        }
+        let (layer, handle) = reload::Layer::new(filter);
+        if self.handle.set(handle).is_err() {
+            tracing::trace!("The log filter is already connected.");
+        }
+        layer
+    }
+
+    /// The `current` method returns the directives of the filter in use, or [`None`] if logging
+    /// has not started.
+    pub fn current(&self) -> Option<String> {
+        self.handle
+            .get()?
+            .with_current(|filter| filter.to_string())
+            .ok()
+    }
+
+    /// The `set` method replaces the filter with `directives`, and remembers them for
+    /// [`Verbosity::reset`].
+    ///
+    /// Will [`crate::Blame::Filter`] if the directives do not parse, and [`crate::Blame::Reload`]
+    /// if the subscriber has gone away.
+    #[tracing::instrument(skip_all)]
+    pub fn set(&self, directives: &str) -> Arrive<()> {
+        self.apply(directives)?;
+        if let Ok(mut base) = self.base.lock() {
+            *base = directives.to_string();
+        }
+        tracing::info!("Log filter: {directives}");
+        Ok(())
+    }
+
+    /// The `boost` method turns `target`, like `bea_egui::map`, up to trace, keeping the rest
+    /// of the current filter.  The change lasts until [`Verbosity::reset`], or the next
+    /// [`Verbosity::set`].
+    ///
+    /// Will [`crate::Blame::Filter`] if `target` does not parse, and [`crate::Blame::Reload`] if
+    /// the subscriber has gone away.
+    #[tracing::instrument(skip_all)]
+    pub fn boost(&self, target: &str) -> Arrive<()> {
+        let directives = match self.current() {
+            Some(current) if !current.is_empty() => format!("{current},{target}=trace"),
+            _ => format!("{target}=trace"),
+        };
+        self.apply(&directives)?;
+        tracing::info!("Tracing {target} until the logs are reset.");
+        Ok(())
+    }
+
+    /// The `reset` method goes back to the filter from [`Verbosity::set`], or the one logging
+    /// started with.
+    ///
+    /// Will [`crate::Blame::Filter`] if the saved directives no longer parse, and
+    /// [`crate::Blame::Reload`] if the subscriber has gone away.
+    #[tracing::instrument(skip_all)]
+    pub fn reset(&self) -> Arrive<()> {
+        let base = self
+            .base
+            .lock()
+            .map(|base| base.clone())
+            .unwrap_or_default();
+        self.apply(&base)?;
+        tracing::info!("Log filter reset to {base}.");
+        Ok(())
+    }
+
+    /// The `apply` method swaps in a filter built from `directives`.  Does nothing before
+    /// logging starts.
+    fn apply(&self, directives: &str) -> Arrive<()> {
+        let filter = EnvFilter::try_new(directives)
+            .with_context(|| format!("reading the log filter {directives}"))?;
+        if let Some(handle) = self.handle.get() {
+            handle.reload(filter).context("changing the log filter")?;
+        }
+        Ok(())
+    }
+}
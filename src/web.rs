@@ -0,0 +1,52 @@
+use crate::{App, Blame, Cli, UserEvent};
+use winit::event_loop;
+use winit::platform::web::EventLoopExtWebSys;
+
+/// The `run_web` function starts the app in a web browser, for the viewer embedded in the city
+/// website.  The `main` of the binary calls it when built for `wasm32`, as by `trunk build`, in
+/// place of the startup of the desktop app.
+///
+/// # Running the app in the browser with `run_web`
+///
+/// The public should not have to install anything to look at the map, so the same crate builds
+/// for `wasm32-unknown-unknown`.  [`winit`] draws each window on a canvas of the page, and
+/// [`wgpu`] draws on it with WebGPU where the browser has it, and WebGL otherwise.  The desktop
+/// runs on the [`tokio`] runtime, which a page cannot host, so the app runs its async work
+/// through the functions of the `runtime` module instead, [`crate::spawn`], [`crate::unblock`],
+/// [`crate::sleep`] and [`crate::timeout`], which use the event loop of the page in the browser.
+/// Clocks come from [`web_time`], since the clock of the standard library panics in a page.
+///
+/// The page has no command line and no config directory, so the app starts with the default
+/// [`Cli`] and the default settings.  Some features need what only the desktop has, and stand
+/// down in the browser:
+///
+/// * The system clipboard of [`crate::Clipboard`] and the global hotkeys of [`crate::Hotkeys`]
+///   have no web backend, so copying fails with [`crate::Excuse::Unavailable`], and there are no
+///   global hotkeys.
+/// * A page has no file system, so a [`crate::Download`] fails with
+///   [`crate::Excuse::Unavailable`], and the tile cache, the agents that tend it, snapshots and
+///   recordings fail when they try to write.
+/// * The browser answers a request for the graphics card only once we give back the thread, so
+///   [`crate::Gpu::prepare`] opens it in the background, and each window gets its map when it
+///   answers.  Browsers do not list their cards, so the `adapter` key of the `[graphics]` table
+///   does nothing there.
+/// * The system keyring of [`crate::Secrets`] and the OTLP [`crate::Exporter`] stand down, and
+///   the network limits of [`crate::Timeouts`] other than the deadline are up to the browser.
+///
+/// Logging goes to the console of the browser, through [`tracing_wasm`], and a panic writes its
+/// message there too, rather than vanishing.
+pub fn run_web() {
+    console_error_panic_hook::set_once();
+    tracing_wasm::set_as_global_default();
+    let event_loop = match event_loop::EventLoop::<UserEvent>::with_user_event().build() {
+        Ok(event_loop) => event_loop,
+        Err(e) => {
+            tracing::error!("{}", Blame::from(e).report());
+            return;
+        }
+    };
+    let proxy = event_loop.create_proxy();
+    event_loop.set_control_flow(event_loop::ControlFlow::Wait);
+    let app = App::new(proxy, Cli::default());
+    event_loop.spawn_app(app);
+}
@@ -0,0 +1,454 @@
+use crate::{Arrive, Context, Dataset, Extent, APPLICATION};
+use std::io::Write;
+use std::path;
+use web_time as time;
+
+/// The `workbook` module provides the [`Workbook`] struct, which writes a table to an Excel
+/// workbook or to CSV, with a record of where the rows came from, and the [`Cell`] enum, which
+/// holds one value of the workbook.
+///
+/// # Handing tables over with `Workbook`
+///
+/// The maps get attention, but most of what leaves the office is a table: the attributes of the
+/// parcels in a study area, the rows of a BEA series, the output of a join.  The people who ask
+/// for them work in Excel, and until now the way to get a table there was to export GeoJSON and
+/// convert it, losing track along the way of which file the rows came from and which of them
+/// were kept.  A table that shows up in a staff report a month later with no record of its
+/// source is a table nobody can check.
+///
+/// [`crate::Act::ExportSelectionXlsx`] and [`crate::Act::ExportLayersXlsx`] write each open
+/// table as an Excel workbook, and [`crate::Act::ExportSelectionCsv`] and
+/// [`crate::Act::ExportLayersCsv`] as CSV, the rows picked the same way as their GeoJSON
+/// counterparts (see [`Dataset::save_geojson`]): the rows in view of the focused map, or every
+/// row.  Every open table is a [`Dataset`], whether read from a layer, fetched from the BEA or
+/// joined by a [`crate::DataProvider`], so each exports the same way.
+///
+/// A workbook holds two sheets:
+///
+/// * The `Data` sheet holds the rows, with a header row of the column names, frozen so it stays
+///   in view as the user scrolls.  The [`crate::GEOMETRY`] column stays out, since the outline
+///   of a parcel runs past what a cell holds and means nothing in a spreadsheet.  Export GeoJSON
+///   to keep the shapes.
+/// * The `Metadata` sheet holds the record of the export, one field to a row: the source file and
+///   its format, the rows kept out of the rows in the source, the filter, the sort, the columns,
+///   the time of the export in UTC and the version of the app.
+///
+/// A CSV file has no second sheet, so [`Workbook::save_csv`] writes the rows with
+/// [`Dataset::save_csv`], geometry included, and the record from [`Workbook::record`] beside
+/// them, in a file of the same name ending in `.metadata.csv`, without gathering the cells of a
+/// workbook it has no use for.
+///
+/// The rows go in the order of the `[sort]` table of the config (see [`crate::Sort`]), sorted
+/// with [`Dataset::sort`] before they get here, and the record names the column and the
+/// direction, or says the rows keep the order of the source.
+///
+/// The XLSX format is a zip file of XML parts, and we need little of it, so we write it by hand
+/// over the [`zip`] crate, as [`crate::Shapefile`] writes a shapefile, with each text as an
+/// inline string rather than in a table of shared strings.  A column whose values all read as
+/// plain decimal numbers, or that holds numbers to begin with, goes as numbers, so it sorts and
+/// sums in Excel.  A value like `01234` keeps the column as text, so a ZIP code keeps its leading
+/// zero, as does a number of more than [`NUMBER_DIGIT_LIMIT`] digits, like a parcel number, which
+/// Excel would round.  Excel also holds at most [`SHEET_ROW_LIMIT`] rows to a sheet and
+/// [`CELL_TEXT_LIMIT`] characters to a cell, so we cut anything longer short, with a warning.
+///
+/// * The `columns` field holds the names of the columns of the `Data` sheet.
+/// * The `rows` field holds the cells of each row of the `Data` sheet.
+/// * The `metadata` field holds the fields of the `Metadata` sheet, by name, in order.
+#[derive(Debug, Clone, PartialEq, derive_getters::Getters)]
+pub struct Workbook {
+    columns: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+    metadata: Vec<(String, String)>,
+}
+
+impl Workbook {
+    /// The `new` method gathers the rows of `chosen`, picked from `source` with
+    /// [`Dataset::selection`] for the `extent`, if any, and ordered by `sort`, if any, as the
+    /// name of a column and `true` if descending, and records how, as described in the
+    /// [`Workbook`] docs.
+    pub fn new(
+        source: &Dataset,
+        chosen: &Dataset,
+        extent: Option<&Extent>,
+        sort: Option<(&str, bool)>,
+    ) -> Self {
+        let geometry = chosen.geometry();
+        let kept = chosen
+            .columns()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != geometry)
+            .collect::<Vec<(usize, String)>>();
+        let cells = kept
+            .iter()
+            .map(|(index, _)| Self::cells(chosen, *index))
+            .collect::<Vec<Vec<Cell>>>();
+        let rows = (0..chosen.len())
+            .map(|row| cells.iter().map(|column| column[row].clone()).collect())
+            .collect();
+        let columns = kept
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<String>>();
+        let metadata = Self::record(source, chosen, extent, sort);
+        Self {
+            columns,
+            rows,
+            metadata,
+        }
+    }
+
+    /// The `save` method writes the workbook to `path`, which should end in `.xlsx`, as
+    /// described in the [`Workbook`] docs.  Returns the number of rows written.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be written, and [`crate::Blame::Zip`] if
+    /// the workbook cannot be packed.
+    #[tracing::instrument(skip(self))]
+    pub fn save(&self, path: &path::Path) -> Arrive<usize> {
+        let mut data = vec![self
+            .columns
+            .iter()
+            .map(|name| Cell::Text(name.clone()))
+            .collect::<Vec<Cell>>()];
+        let count = self.rows.len().min(SHEET_ROW_LIMIT - 1);
+        if count < self.rows.len() {
+            tracing::warn!(
+                "Cut {} rows past the {SHEET_ROW_LIMIT} rows a sheet holds.",
+                self.rows.len() - count
+            );
+        }
+        data.extend(self.rows.iter().take(count).cloned());
+        let mut metadata = vec![vec![
+            Cell::Text("Field".to_string()),
+            Cell::Text("Value".to_string()),
+        ]];
+        metadata.extend(
+            self.metadata
+                .iter()
+                .map(|(name, value)| vec![Cell::Text(name.clone()), Cell::Text(value.clone())]),
+        );
+        let parts = [
+            ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+            ("_rels/.rels", ROOT_RELS.to_string()),
+            ("xl/workbook.xml", WORKBOOK.to_string()),
+            ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.to_string()),
+            ("xl/worksheets/sheet1.xml", Self::sheet(&data)),
+            ("xl/worksheets/sheet2.xml", Self::sheet(&metadata)),
+        ];
+        let file =
+            std::fs::File::create(path).with_context(|| format!("writing {}", path.display()))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, xml) in parts {
+            zip.start_file(name, options)?;
+            zip.write_all(xml.as_bytes())?;
+        }
+        zip.finish()?;
+        tracing::info!("Wrote {count} rows to {}.", path.display());
+        Ok(count)
+    }
+
+    /// The `save_csv` method writes the rows of `chosen` to `path` with [`Dataset::save_csv`],
+    /// and the `record` of them from [`Workbook::record`] beside it, as described in the
+    /// [`Workbook`] docs.  Returns the number of rows written.
+    ///
+    /// Will [`crate::Blame::Io`] if a file cannot be written, and [`crate::Blame::Csv`] if a
+    /// row cannot be encoded.
+    #[tracing::instrument(skip(chosen, record))]
+    pub fn save_csv(
+        chosen: &Dataset,
+        record: &[(String, String)],
+        path: &path::Path,
+    ) -> Arrive<usize> {
+        let count = chosen.save_csv(path)?;
+        let record = path.with_extension("metadata.csv");
+        let file = std::fs::File::create(&record)
+            .with_context(|| format!("writing {}", record.display()))?;
+        let mut writer = csv::Writer::from_writer(std::io::BufWriter::new(file));
+        writer.write_record(["Field", "Value"])?;
+        for (name, value) in record {
+            writer.write_record([name, value])?;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// The `record` method describes where the rows of `chosen` came from, picked from `source`
+    /// for the `extent`, if any, and ordered by `sort`, if any, for the `Metadata` sheet or the
+    /// record beside a CSV file.
+    pub fn record(
+        source: &Dataset,
+        chosen: &Dataset,
+        extent: Option<&Extent>,
+        sort: Option<(&str, bool)>,
+    ) -> Vec<(String, String)> {
+        let filter = match extent {
+            Some(extent) => format!(
+                "Features in view of the map: west {:.6}, south {:.6}, east {:.6}, north {:.6}",
+                extent.west(),
+                extent.south(),
+                extent.east(),
+                extent.north()
+            ),
+            None => "None, every row".to_string(),
+        };
+        let sort = match sort {
+            Some((column, true)) => format!("By {column}, largest first"),
+            Some((column, false)) => format!("By {column}, smallest first"),
+            None => "None, in the order of the source".to_string(),
+        };
+        let seconds = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        [
+            ("Source", source.path().display().to_string()),
+            ("Format", source.format().to_string()),
+            ("Rows", chosen.len().to_string()),
+            ("Rows in source", source.len().to_string()),
+            ("Filter", filter),
+            ("Sort", sort),
+            ("Columns", chosen.columns().join(", ")),
+            ("Exported", crate::timestamp(seconds)),
+            (
+                "Application",
+                format!("{APPLICATION} {}", env!("CARGO_PKG_VERSION")),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+    }
+
+    /// The `cells` method returns the value of each row in the column at `column` of `dataset`
+    /// as a cell, numbers if the whole column reads as numbers, as described in the [`Workbook`]
+    /// docs, and text otherwise.
+    fn cells(dataset: &Dataset, column: usize) -> Vec<Cell> {
+        let texts = dataset.texts(column);
+        let numeric = texts
+            .iter()
+            .flatten()
+            .filter(|text| !text.is_empty())
+            .all(Cell::reads_as_number);
+        let mut cut = false;
+        let cells = texts
+            .iter()
+            .map(
+                |text| match (text.filter(|text| !text.is_empty()), numeric) {
+                    (None, _) => Cell::Empty,
+                    (Some(text), true) => Cell::Number(text.to_string()),
+                    (Some(text), false) => {
+                        if text.chars().count() > CELL_TEXT_LIMIT {
+                            cut = true;
+                            Cell::Text(text.chars().take(CELL_TEXT_LIMIT).collect())
+                        } else {
+                            Cell::Text(text.to_string())
+                        }
+                    }
+                },
+            )
+            .collect();
+        if cut {
+            let name = dataset.columns().swap_remove(column);
+            tracing::warn!(
+                "Cut values of {name} to the {CELL_TEXT_LIMIT} characters a cell holds."
+            );
+        }
+        cells
+    }
+
+    /// The `sheet` method encodes `rows` as a worksheet, with the first row frozen.
+    fn sheet(rows: &[Vec<Cell>]) -> String {
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+            r#"<sheetViews><sheetView workbookViewId="0">"#,
+            r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#,
+            r#"</sheetView></sheetViews><sheetData>"#,
+        ));
+        for (index, row) in rows.iter().enumerate() {
+            let number = index + 1;
+            xml.push_str(&format!(r#"<row r="{number}">"#));
+            for (column, cell) in row.iter().enumerate() {
+                let reference = format!("{}{number}", Cell::column_name(column));
+                xml.push_str(&cell.xml(&reference));
+            }
+            xml.push_str("</row>");
+        }
+        xml.push_str("</sheetData></worksheet>");
+        xml
+    }
+}
+
+/// The `Cell` enum holds one value of a [`Workbook`].
+///
+/// * The `Number` variant holds a number, as the text it was read from, so it keeps its digits.
+/// * The `Text` variant holds text.
+/// * The `Empty` variant holds a missing value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    /// The `Number` variant indicates a cell holding a number.
+    Number(String),
+    /// The `Text` variant indicates a cell holding text.
+    Text(String),
+    /// The `Empty` variant indicates a cell holding nothing.
+    Empty,
+}
+
+impl Cell {
+    /// The `reads_as_number` method returns `true` if `text` is a plain decimal number that
+    /// Excel holds without change, as described in the [`Workbook`] docs.
+    pub fn reads_as_number(text: &str) -> bool {
+        let digits = text.chars().filter(char::is_ascii_digit).count();
+        match serde_json::from_str::<serde_json::Number>(text) {
+            Ok(number) => number.to_string() == text && digits <= NUMBER_DIGIT_LIMIT,
+            Err(_) => false,
+        }
+    }
+
+    /// The `column_name` method returns the letters Excel names the column at `index` by,
+    /// counting from zero, so `A` for 0, `Z` for 25 and `AA` for 26.
+    pub fn column_name(index: usize) -> String {
+        let mut name = Vec::new();
+        let mut rest = index + 1;
+        while rest > 0 {
+            let letter = (rest - 1) % 26;
+            name.push(b'A' + letter as u8);
+            rest = (rest - 1) / 26;
+        }
+        name.reverse();
+        String::from_utf8_lossy(&name).to_string()
+    }
+
+    /// The `xml` method encodes the cell at `reference`, like `B2`, for a worksheet.
+    fn xml(&self, reference: &str) -> String {
+        match self {
+            Self::Number(number) => format!(r#"<c r="{reference}"><v>{number}</v></c>"#),
+            Self::Text(text) => format!(
+                r#"<c r="{reference}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                Self::escape(text)
+            ),
+            Self::Empty => String::new(),
+        }
+    }
+
+    /// The `escape` method readies `text` for XML, escaping markup and dropping the control
+    /// characters XML does not allow.
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\t' | '\n' | '\r' => escaped.push(c),
+                c if c.is_control() => {}
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+/// The `SHEET_ROW_LIMIT` constant holds the most rows Excel holds in a sheet of a [`Workbook`],
+/// header included.
+pub const SHEET_ROW_LIMIT: usize = 1_048_576;
+
+/// The `CELL_TEXT_LIMIT` constant holds the most characters Excel holds in a cell of a
+/// [`Workbook`].
+pub const CELL_TEXT_LIMIT: usize = 32_767;
+
+/// The `NUMBER_DIGIT_LIMIT` constant holds the most digits of a number Excel holds without
+/// rounding, so a longer one stays text in a [`Workbook`].
+pub const NUMBER_DIGIT_LIMIT: usize = 15;
+
+/// The `CONTENT_TYPES` constant holds the part of a [`Workbook`] naming the type of each other
+/// part.
+const CONTENT_TYPES: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+    r#"<Default Extension="rels" "#,
+    r#"ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+    r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+    r#"<Override PartName="/xl/workbook.xml" "#,
+    r#"ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    r#"<Override PartName="/xl/worksheets/sheet1.xml" "#,
+    r#"ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+    r#"<Override PartName="/xl/worksheets/sheet2.xml" "#,
+    r#"ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+    r#"</Types>"#,
+);
+
+/// The `ROOT_RELS` constant holds the part of a [`Workbook`] pointing to the workbook part.
+const ROOT_RELS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" "#,
+    r#"Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" "#,
+    r#"Target="xl/workbook.xml"/>"#,
+    r#"</Relationships>"#,
+);
+
+/// The `WORKBOOK` constant holds the part of a [`Workbook`] naming its sheets.
+const WORKBOOK: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+    r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+    r#"<sheets><sheet name="Data" sheetId="1" r:id="rId1"/>"#,
+    r#"<sheet name="Metadata" sheetId="2" r:id="rId2"/></sheets>"#,
+    r#"</workbook>"#,
+);
+
+/// The `WORKBOOK_RELS` constant holds the part of a [`Workbook`] pointing to its sheets.
+const WORKBOOK_RELS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" "#,
+    r#"Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" "#,
+    r#"Target="worksheets/sheet1.xml"/>"#,
+    r#"<Relationship Id="rId2" "#,
+    r#"Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" "#,
+    r#"Target="worksheets/sheet2.xml"/>"#,
+    r#"</Relationships>"#,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_read_plainly() {
+        for text in ["0", "12", "-3", "12.5", "123456789012345"] {
+            assert!(Cell::reads_as_number(text), "{text} is a number");
+        }
+        // Leading zeros, exponents, signs and long digits change when Excel reads them.
+        for text in [
+            "",
+            "007",
+            "1e5",
+            "+4",
+            "1.50",
+            "1234567890123456",
+            "97526-1234",
+            "NaN",
+        ] {
+            assert!(!Cell::reads_as_number(text), "{text} is text");
+        }
+    }
+
+    #[test]
+    fn columns_name_like_excel() {
+        let names = [
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (27, "AB"),
+            (701, "ZZ"),
+            (702, "AAA"),
+        ];
+        for (index, name) in names {
+            assert_eq!(Cell::column_name(index), name);
+        }
+    }
+}
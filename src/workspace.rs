@@ -0,0 +1,181 @@
+use crate::{Arrive, Context, Extent, Permalink};
+use std::path;
+
+/// The `workspace` module provides the [`Workspace`] struct, which saves the open data files and
+/// the view of a map to a file, to restore on startup.
+///
+/// # Picking up where we left off with `Workspace`
+///
+/// The same few layers go together for each kind of work: the parcels, zoning and hydrants for a
+/// fire flow review, the parcels and flood zones for a floodplain permit.  Opening them one at a
+/// time each morning, and finding the part of town again, is busywork.  A workspace file holds
+/// the files and the view, and `--workspace <file>` on the command line, or a `workspace` value
+/// in the config, restores them on startup (see [`crate::App::open_startup`]).  In headless
+/// mode, the [`crate::Export`] draws the files of the workspace, from its view, so a scheduled
+/// job can print a map set up by hand in the app.
+///
+/// [`crate::Act::SaveWorkspace`] writes the workspace of the focused window under the
+/// [`WORKSPACE_DIR`] directory of the data directory.  The file is TOML, short enough to write
+/// or trim by hand:
+///
+/// ```toml
+/// open = ["parcels.gpkg", "/srv/gis/hydrants.geojson"]
+///
+/// [view]
+/// longitude = -123.3279
+/// latitude = 42.4391
+/// zoom = 16.25
+/// ```
+///
+/// A relative path in `open` is relative to the folder of the workspace file, so a workspace
+/// kept next to its data moves with it.  The `[view]` table may be left off, and the map then
+/// opens on the view from the `[map]` table of the config.  The view is held to what the base map
+/// can show, as for a [`Permalink`], which the app opens the workspace through.
+///
+/// * The `open` field holds the paths of the data files to open.
+/// * The `view` field holds the center and zoom level of the map, if saved.
+#[derive(
+    Debug, Clone, Default, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+#[serde(default)]
+pub struct Workspace {
+    open: Vec<path::PathBuf>,
+    view: Option<View>,
+}
+
+impl Workspace {
+    /// The `from_view` method describes the workspace of a map showing `extent` across `width`
+    /// pixels, with the data files at `open`.
+    pub fn from_view(extent: &Extent, width: u32, open: Vec<path::PathBuf>) -> Self {
+        let permalink = Permalink::from_view(extent, width, Vec::new(), Vec::new(), None);
+        let view = View {
+            longitude: *permalink.longitude(),
+            latitude: *permalink.latitude(),
+            zoom: *permalink.zoom(),
+        };
+        Self {
+            open,
+            view: Some(view),
+        }
+    }
+
+    /// The `read` method reads the workspace in the file at `path`, resolving the relative paths
+    /// of its data files against the folder of the file.
+    ///
+    /// Will [`crate::Blame::Io`] if the file cannot be read, and [`crate::Blame::TomlDe`] if it
+    /// is not a workspace.
+    #[tracing::instrument]
+    pub fn read(path: &path::Path) -> Arrive<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut workspace = toml::from_str::<Self>(&contents)
+            .with_context(|| format!("parsing {}", path.display()))?;
+        if let Some(dir) = path.parent() {
+            workspace.open = workspace
+                .open
+                .into_iter()
+                .map(|file| dir.join(file))
+                .collect();
+        }
+        tracing::info!(
+            "Read {} with {} data files.",
+            path.display(),
+            workspace.open.len()
+        );
+        Ok(workspace)
+    }
+
+    /// The `is_workspace` method returns `true` if the file at `path` has the
+    /// [`WORKSPACE_EXTENSION`], which no data file the app reads has, so a workspace handed over
+    /// by the desktop can be told from a data file without reading it.
+    pub fn is_workspace(path: &path::Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(WORKSPACE_EXTENSION))
+    }
+
+    /// The `save` method writes the workspace to the file at `path`.
+    ///
+    /// Will [`crate::Blame::TomlSer`] if the workspace cannot be written as TOML, and
+    /// [`crate::Blame::Io`] if the file cannot be written.
+    pub fn save(&self, path: &path::Path) -> Arrive<()> {
+        let text = toml::to_string_pretty(self)?;
+        std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))?;
+        tracing::info!("Saved the workspace to {}.", path.display());
+        Ok(())
+    }
+
+    /// The `permalink` method returns the view of the workspace as a [`Permalink`] with its data
+    /// files as layers, for [`crate::App::open_permalink`] to open, or [`None`] if the workspace
+    /// has no view, or a view that is not a number.
+    pub fn permalink(&self) -> Option<Permalink> {
+        let view = self.view.filter(|view| {
+            view.longitude.is_finite() && view.latitude.is_finite() && view.zoom.is_finite()
+        })?;
+        Some(Permalink::new(
+            view.longitude,
+            view.latitude,
+            view.zoom,
+            self.open.clone(),
+            Vec::new(),
+            None,
+        ))
+    }
+}
+
+/// The `View` struct holds the `[view]` table of a [`Workspace`].
+///
+/// * The `longitude` and `latitude` fields hold the center of the map, in degrees.
+/// * The `zoom` field holds the zoom level of the base map.
+#[derive(
+    Debug, Copy, Clone, PartialEq, derive_getters::Getters, serde::Serialize, serde::Deserialize,
+)]
+pub struct View {
+    longitude: f64,
+    latitude: f64,
+    zoom: f64,
+}
+
+/// The `WORKSPACE_DIR` constant holds the name of the directory under the data directory where
+/// [`crate::Act::SaveWorkspace`] writes workspaces.
+pub const WORKSPACE_DIR: &str = "workspaces";
+
+/// The `WORKSPACE_EXTENSION` constant holds the extension of a workspace file.
+pub const WORKSPACE_EXTENSION: &str = "toml";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_files_follow_the_workspace() {
+        let dir = std::env::temp_dir().join("bea_egui_workspace_test");
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("review.toml");
+        let text =
+            "open = [\"parcels.gpkg\"]\n[view]\nlongitude = -123.3\nlatitude = 42.4\nzoom = 99.0";
+        std::fs::write(&path, text).expect("workspace");
+        let workspace = Workspace::read(&path).expect("read");
+        assert_eq!(workspace.open(), &vec![dir.join("parcels.gpkg")]);
+        let permalink = workspace.permalink().expect("view");
+        assert_eq!(*permalink.zoom(), f64::from(crate::MAX_ZOOM));
+        assert_eq!(permalink.layers(), workspace.open());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn saved_workspaces_read_back() {
+        let extent = Extent::new(-123.34, 42.43, -123.32, 42.45);
+        let workspace = Workspace::from_view(&extent, 800, vec!["/srv/parcels.gpkg".into()]);
+        let text = toml::to_string_pretty(&workspace).expect("toml");
+        let read = toml::from_str::<Workspace>(&text).expect("workspace");
+        assert_eq!(read, workspace);
+        assert!(Workspace::default().permalink().is_none());
+    }
+
+    #[test]
+    fn workspaces_are_told_by_extension() {
+        assert!(Workspace::is_workspace(path::Path::new("/srv/review.TOML")));
+        assert!(!Workspace::is_workspace(path::Path::new("parcels.gpkg")));
+        assert!(!Workspace::is_workspace(path::Path::new("toml")));
+    }
+}